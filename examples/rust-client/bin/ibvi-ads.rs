@@ -0,0 +1,331 @@
+//! # ibvi-ads
+//!
+//! Command line client of the Core API for scripted agency workflows,
+//! built on `ibvi-ads-client` (so paging and retries work the same way):
+//!
+//! ```bash
+//! export IBVI_API_URL=https://core.internal IBVI_API_TOKEN=...
+//! ibvi-ads campaigns list --platform meta --status enabled --output csv > meta.csv
+//! ibvi-ads campaigns list --from 2026-10-01 --to 2026-10-14 --output json
+//! ibvi-ads campaigns pause meta:120210000000
+//! ibvi-ads summary --currency BRL
+//! ```
+//!
+//! Results go to stdout as an aligned table (the default), JSON or CSV.
+//! Errors go to stderr with exit status 1, so scripts stop on the first
+//! failed command.
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use ibvi_ads_client::{Campaign, CampaignMetrics, CampaignStatus, Client, Platform};
+
+#[derive(Parser)]
+#[command(name = "ibvi-ads", version, about = "Campaign operations on the IBVI Ads Core API")]
+struct Cli {
+    /// Base URL of the API
+    #[arg(long, env = "IBVI_API_URL", default_value = "http://localhost:8080")]
+    url: String,
+    /// API key, sent as a bearer token
+    #[arg(long, env = "IBVI_API_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List, pause and enable campaigns
+    #[command(subcommand)]
+    Campaigns(CampaignsCommand),
+    /// Budget, spend, conversions and ROAS totals per currency
+    Summary {
+        /// Only the totals in this currency, e.g. BRL
+        #[arg(long)]
+        currency: Option<String>,
+        #[arg(long, value_enum, default_value_t = Output::Table)]
+        output: Output,
+    },
+}
+
+#[derive(Subcommand)]
+enum CampaignsCommand {
+    /// Campaigns of every platform, highest daily budget first
+    List {
+        /// Keep campaigns of this platform; repeat for several
+        #[arg(long)]
+        platform: Vec<Platform>,
+        /// Keep campaigns with this status (enabled, paused, removed);
+        /// repeat for several
+        #[arg(long)]
+        status: Vec<CampaignStatus>,
+        /// Keep campaigns carrying this label; repeat for several
+        #[arg(long)]
+        label: Vec<String>,
+        /// First day of the metrics; month-to-date without `--from`/`--to`
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last day of the metrics, inclusive
+        #[arg(long)]
+        to: Option<NaiveDate>,
+        #[arg(long, value_enum, default_value_t = Output::Table)]
+        output: Output,
+    },
+    /// Pause a campaign, given as `<platform>:<id>`
+    Pause { campaign: CampaignRef },
+    /// Enable a paused campaign, given as `<platform>:<id>`
+    Enable { campaign: CampaignRef },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Campaign of one platform, e.g. `meta:120210000000`
+#[derive(Clone)]
+struct CampaignRef {
+    platform: Platform,
+    id: String,
+}
+
+impl FromStr for CampaignRef {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some((platform, id)) if !id.is_empty() => Ok(CampaignRef {
+                platform: platform.parse()?,
+                id: id.to_string(),
+            }),
+            _ => Err(format!("expected <platform>:<id>, got {:?}", value)),
+        }
+    }
+}
+
+/// Rows with a header, printed as a table or CSV
+struct Rows {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Rows {
+    fn table(&self) -> String {
+        let mut widths: Vec<usize> = self.header.iter().map(|h| h.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: Vec<&str>| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        let mut lines = vec![line(self.header.clone())];
+        lines.extend(self.rows.iter().map(|row| line(row.iter().map(String::as_str).collect())));
+        lines.join("\n")
+    }
+
+    fn csv(&self) -> String {
+        let line = |cells: Vec<&str>| {
+            let fields: Vec<String> = cells.into_iter().map(csv_field).collect();
+            fields.join(",")
+        };
+        let mut lines = vec![line(self.header.clone())];
+        lines.extend(self.rows.iter().map(|row| line(row.iter().map(String::as_str).collect())));
+        lines.join("\n")
+    }
+}
+
+/// CSV field, quoted when it holds a comma, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Failure of a command
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error(transparent)]
+    Client(#[from] ibvi_ads_client::Error),
+    #[error("could not write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+fn print<T: Serialize>(
+    output: Output,
+    value: &T,
+    rows: impl FnOnce() -> Rows,
+) -> Result<(), Error> {
+    match output {
+        Output::Table => println!("{}", rows().table()),
+        Output::Csv => println!("{}", rows().csv()),
+        Output::Json => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+    Ok(())
+}
+
+fn campaign_rows(campaigns: &[Campaign]) -> Rows {
+    Rows {
+        header: vec![
+            "PLATFORM", "ID", "NAME", "STATUS", "DAILY_BUDGET", "CURRENCY", "IMPRESSIONS",
+            "CLICKS", "CONVERSIONS", "COST", "ROAS",
+        ],
+        rows: campaigns
+            .iter()
+            .map(|c| {
+                vec![
+                    c.platform.as_str().to_string(),
+                    c.id.clone(),
+                    c.name.clone(),
+                    c.status.as_str().to_string(),
+                    format!("{:.2}", c.daily_budget),
+                    c.currency.clone(),
+                    c.metrics.impressions.to_string(),
+                    c.metrics.clicks.to_string(),
+                    c.metrics.conversions.to_string(),
+                    format!("{:.2}", c.metrics.cost),
+                    format!("{:.2}", c.metrics.roas),
+                ]
+            })
+            .collect(),
+    }
+}
+
+/// Totals per currency, with the daily budget of enabled campaigns
+#[derive(Serialize)]
+struct CurrencyTotals {
+    daily_budget: f64,
+    #[serde(flatten)]
+    metrics: CampaignMetrics,
+}
+
+fn summary_rows(totals: &BTreeMap<String, CurrencyTotals>) -> Rows {
+    Rows {
+        header: vec![
+            "CURRENCY", "DAILY_BUDGET", "IMPRESSIONS", "CLICKS", "CONVERSIONS", "COST", "CPA",
+            "ROAS",
+        ],
+        rows: totals
+            .iter()
+            .map(|(currency, t)| {
+                vec![
+                    currency.clone(),
+                    format!("{:.2}", t.daily_budget),
+                    t.metrics.impressions.to_string(),
+                    t.metrics.clicks.to_string(),
+                    t.metrics.conversions.to_string(),
+                    format!("{:.2}", t.metrics.cost),
+                    format!("{:.2}", t.metrics.cpa),
+                    format!("{:.2}", t.metrics.roas),
+                ]
+            })
+            .collect(),
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+    let mut builder = Client::builder(&cli.url);
+    if let Some(token) = cli.token {
+        builder = builder.token(token);
+    }
+    let client = builder.build()?;
+
+    match cli.command {
+        Command::Campaigns(CampaignsCommand::List {
+            platform,
+            status,
+            label,
+            from,
+            to,
+            output,
+        }) => {
+            let mut request = client.campaigns();
+            for platform in platform {
+                request = request.platform(platform);
+            }
+            for status in status {
+                request = request.status(status);
+            }
+            for label in label {
+                request = request.label(label);
+            }
+            request = match (from, to) {
+                (Some(from), Some(to)) => request.date_range(from..=to),
+                (Some(from), None) => request.date_range(from..),
+                (None, Some(to)) => request.date_range(..=to),
+                (None, None) => request,
+            };
+
+            let campaigns = request.list().await?;
+            print(output, &campaigns, || campaign_rows(&campaigns))?;
+        }
+        Command::Campaigns(CampaignsCommand::Pause { campaign }) => {
+            client.pause(campaign.platform, &campaign.id).await?;
+            eprintln!("paused {}:{}", campaign.platform.as_str(), campaign.id);
+        }
+        Command::Campaigns(CampaignsCommand::Enable { campaign }) => {
+            client.enable(campaign.platform, &campaign.id).await?;
+            eprintln!("enabled {}:{}", campaign.platform.as_str(), campaign.id);
+        }
+        Command::Summary { currency, output } => {
+            let mut summary = client.summary().await?;
+            let totals: BTreeMap<String, CurrencyTotals> = std::mem::take(&mut summary.metrics)
+                .into_iter()
+                .filter(|(code, _)| currency.iter().all(|c| c.eq_ignore_ascii_case(code)))
+                .map(|(code, metrics)| {
+                    let daily_budget =
+                        summary.total_daily_budget.get(&code).map_or(0.0, |m| m.amount);
+                    (code, CurrencyTotals { daily_budget, metrics })
+                })
+                .collect();
+            print(output, &totals, || summary_rows(&totals))?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_campaign_refs_and_csv_output() {
+        let campaign: CampaignRef = "Meta:1202:7".parse().unwrap();
+        assert_eq!((campaign.platform, campaign.id.as_str()), (Platform::Meta, "1202:7"));
+        assert!("1202".parse::<CampaignRef>().is_err());
+        assert!("tiktok:1".parse::<CampaignRef>().is_err());
+
+        let rows = Rows {
+            header: vec!["ID", "NAME"],
+            rows: vec![vec!["1".to_string(), "Black Friday, \"Sale\"".to_string()]],
+        };
+        assert_eq!(rows.csv(), "ID,NAME\n1,\"Black Friday, \"\"Sale\"\"\"");
+        assert_eq!(rows.table(), "ID  NAME\n1   Black Friday, \"Sale\"");
+    }
+}
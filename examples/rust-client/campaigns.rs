@@ -0,0 +1,178 @@
+//! `GET /campaigns` request builder
+
+use chrono::NaiveDate;
+use std::ops::{Bound, RangeBounds};
+
+use super::client::{Client, NEXT_CURSOR_HEADER};
+use super::error::Error;
+use super::types::{Campaign, CampaignStatus, Platform};
+
+/// Filters of a campaign list; each setter adds to the filter, so
+/// `.platform(Google).platform(Meta)` keeps campaigns of either
+#[derive(Debug, Clone)]
+pub struct CampaignsRequest<'a> {
+    client: &'a Client,
+    platforms: Vec<Platform>,
+    statuses: Vec<CampaignStatus>,
+    labels: Vec<String>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    include_removed: bool,
+    filter: Option<String>,
+}
+
+impl<'a> CampaignsRequest<'a> {
+    pub(crate) fn new(client: &'a Client) -> Self {
+        Self {
+            client,
+            platforms: Vec::new(),
+            statuses: Vec::new(),
+            labels: Vec::new(),
+            start_date: None,
+            end_date: None,
+            include_removed: false,
+            filter: None,
+        }
+    }
+
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platforms.push(platform);
+        self
+    }
+
+    /// Listing `Removed` also lists removed campaigns, which are left out
+    /// otherwise
+    pub fn status(mut self, status: CampaignStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Keep campaigns carrying the label, on any platform, ignoring case
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+
+    /// Metrics as the totals of the days in `range`, e.g. `start..=end`,
+    /// instead of month-to-date
+    ///
+    /// An open start defaults to the first day of the end's month, an open
+    /// end to today.
+    pub fn date_range(mut self, range: impl RangeBounds<NaiveDate>) -> Self {
+        self.start_date = match range.start_bound() {
+            Bound::Included(start) => Some(*start),
+            Bound::Excluded(start) => start.succ_opt(),
+            Bound::Unbounded => None,
+        };
+        self.end_date = match range.end_bound() {
+            Bound::Included(end) => Some(*end),
+            Bound::Excluded(end) => end.pred_opt(),
+            Bound::Unbounded => None,
+        };
+        self
+    }
+
+    pub fn include_removed(mut self) -> Self {
+        self.include_removed = true;
+        self
+    }
+
+    /// Keep campaigns matching a filter expression, e.g.
+    /// `platform = google AND metrics.cpa < 30`; replaces an earlier one
+    pub fn filter(mut self, expression: impl Into<String>) -> Self {
+        self.filter = Some(expression.into());
+        self
+    }
+
+    /// Query parameters of the filters, without paging
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let join = |items: Vec<&str>| items.join(",");
+
+        let mut query = Vec::new();
+        if !self.platforms.is_empty() {
+            query.push(("platform", join(self.platforms.iter().map(|p| p.as_str()).collect())));
+        }
+        if !self.statuses.is_empty() {
+            query.push(("status", join(self.statuses.iter().map(|s| s.as_str()).collect())));
+        }
+        if !self.labels.is_empty() {
+            query.push(("label", join(self.labels.iter().map(String::as_str).collect())));
+        }
+        if let Some(start) = self.start_date {
+            query.push(("start_date", start.to_string()));
+        }
+        if let Some(end) = self.end_date {
+            query.push(("end_date", end.to_string()));
+        }
+        if self.include_removed {
+            query.push(("include_removed", "true".to_string()));
+        }
+        if let Some(filter) = &self.filter {
+            query.push(("filter", filter.clone()));
+        }
+        query
+    }
+
+    /// Every matching campaign, read page by page
+    pub async fn list(self) -> Result<Vec<Campaign>, Error> {
+        let filters = self.query();
+        let mut campaigns = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut query = filters.clone();
+            query.push(("limit", self.client.page_size().to_string()));
+            if let Some(cursor) = &cursor {
+                query.push(("cursor", cursor.clone()));
+            }
+
+            let response = self.client.get("campaigns", &query).await?;
+            let next = response
+                .headers()
+                .get(NEXT_CURSOR_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            campaigns.extend(response.json::<Vec<Campaign>>().await?);
+
+            // A cursor repeating the previous one would loop on the same page
+            match next {
+                Some(next) if !next.is_empty() && cursor.as_deref() != Some(next.as_str()) => {
+                    cursor = Some(next);
+                }
+                _ => return Ok(campaigns),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_become_query_parameters() {
+        let client = Client::new("http://core.internal/api").unwrap();
+        let day = |d: &str| d.parse::<NaiveDate>().unwrap();
+
+        let request = client
+            .campaigns()
+            .platform(Platform::Google)
+            .platform(Platform::Meta)
+            .status(CampaignStatus::Enabled)
+            .date_range(day("2026-10-01")..day("2026-10-15"));
+        let query = request.query();
+        let query: Vec<(&str, &str)> = query.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        assert_eq!(
+            query,
+            [
+                ("platform", "google,meta"),
+                ("status", "ENABLED"),
+                ("start_date", "2026-10-01"),
+                ("end_date", "2026-10-14"),
+            ]
+        );
+
+        let open = client.campaigns().date_range(..=day("2026-10-14")).query();
+        assert_eq!(open, [("end_date", "2026-10-14".to_string())]);
+    }
+}
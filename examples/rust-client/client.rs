@@ -0,0 +1,395 @@
+//! HTTP client and its builder
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Method, Url};
+use serde::Serialize;
+use std::time::Duration;
+
+use super::campaigns::CampaignsRequest;
+use super::error::Error;
+use super::retry::{self, RetryPolicy};
+use super::types::{
+    BulkOperation, BulkReport, BulkRequest, CampaignStatus, CampaignSummary, Platform,
+};
+
+/// Campaigns requested per page of a list
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// Response header carrying the cursor of a list's next page
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Header making retried writes safe, see the API's `idempotency` module
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Timeout of each attempt; a list aggregates every platform on each page
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Client of the Core API; cheap to clone, share one per service
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    /// Always ends with `/`, so relative paths are joined under it
+    base_url: Url,
+    retry: RetryPolicy,
+    page_size: usize,
+}
+
+impl Client {
+    /// Client of the API at `base_url` with the default settings
+    pub fn new(base_url: &str) -> Result<Self, Error> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: &str) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.to_string(),
+            token: None,
+            actor: None,
+            tenant: None,
+            timeout: DEFAULT_TIMEOUT,
+            retry: RetryPolicy::default(),
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// `GET /campaigns`, filtered through the returned builder
+    pub fn campaigns(&self) -> CampaignsRequest<'_> {
+        CampaignsRequest::new(self)
+    }
+
+    pub(crate) fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// `GET /campaigns/summary`: totals per currency, overall and per platform
+    pub async fn summary(&self) -> Result<CampaignSummary, Error> {
+        Ok(self.get("campaigns/summary", &[]).await?.json().await?)
+    }
+
+    /// Apply `operations` through `POST /campaigns/bulk`
+    ///
+    /// The report lists the outcome of each operation; the request only
+    /// fails as a whole when the API rejects the batch.
+    pub async fn bulk(&self, operations: Vec<BulkOperation>) -> Result<BulkReport, Error> {
+        let body = BulkRequest { operations };
+        let response = self.send(Method::POST, "campaigns/bulk", &[], Some(&body)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Set the status of one campaign, failing when the change was rejected
+    pub async fn set_status(
+        &self,
+        platform: Platform,
+        campaign_id: &str,
+        status: CampaignStatus,
+    ) -> Result<(), Error> {
+        let operation = BulkOperation::SetStatus {
+            platform,
+            campaign_id: campaign_id.to_string(),
+            status,
+        };
+        let report = self.bulk(vec![operation]).await?;
+        match report.results.into_iter().find(|r| !r.success) {
+            Some(failed) => Err(Error::Rejected {
+                code: failed.error_code,
+                message: failed.error.unwrap_or_default(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn pause(&self, platform: Platform, campaign_id: &str) -> Result<(), Error> {
+        self.set_status(platform, campaign_id, CampaignStatus::Paused).await
+    }
+
+    pub async fn enable(&self, platform: Platform, campaign_id: &str) -> Result<(), Error> {
+        self.set_status(platform, campaign_id, CampaignStatus::Enabled).await
+    }
+
+    /// GET `path` under the base URL, retrying transient failures
+    pub(crate) async fn get(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<reqwest::Response, Error> {
+        self.send::<()>(Method::GET, path, query, None).await
+    }
+
+    /// Send a request to `path` under the base URL, retrying transient
+    /// failures
+    ///
+    /// Writes carry an `Idempotency-Key` kept across retries, so a retried
+    /// write whose first attempt did reach the API is replayed instead of
+    /// applied twice.
+    async fn send<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&B>,
+    ) -> Result<reqwest::Response, Error> {
+        let url = self.base_url.join(path).map_err(|_| Error::InvalidUrl(path.to_string()))?;
+        let idempotency_key = (method != Method::GET).then(|| uuid::Uuid::new_v4().to_string());
+
+        let mut attempt = 0;
+        loop {
+            let mut request = self.http.request(method.clone(), url.clone()).query(query);
+            if let Some(key) = &idempotency_key {
+                request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+            }
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let retrying = attempt < self.retry.max_retries;
+            let retry_after = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if retrying && retry::is_retryable(response.status()) => {
+                    tracing::debug!(%url, status = %response.status(), attempt, "retrying");
+                    retry::retry_after(response.headers())
+                }
+                Ok(response) => return Err(Error::from_response(response).await),
+                Err(e) if retrying && retry::is_transient(&e) => {
+                    tracing::debug!(%url, error = %e, attempt, "retrying");
+                    None
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            tokio::time::sleep(self.retry.delay(attempt, retry_after)).await;
+            attempt += 1;
+        }
+    }
+}
+
+pub struct ClientBuilder {
+    base_url: String,
+    token: Option<String>,
+    actor: Option<String>,
+    tenant: Option<String>,
+    timeout: Duration,
+    retry: RetryPolicy,
+    page_size: usize,
+}
+
+impl ClientBuilder {
+    /// API key sent as `Authorization: Bearer`, for calls through the
+    /// authenticating proxy
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// `X-Actor` of every request, for services calling the API directly
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// `X-Tenant-Id` of every request, for services calling the API directly
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Timeout of each attempt, retries excluded
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Items requested per page of a list, up to the API's 500
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, 500);
+        self
+    }
+
+    pub fn build(self) -> Result<Client, Error> {
+        let mut base_url = Url::parse(&self.base_url)
+            .ok()
+            .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+            .ok_or_else(|| Error::InvalidUrl(self.base_url.clone()))?;
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        let mut headers = HeaderMap::new();
+        let values = [
+            (AUTHORIZATION, self.token.map(|token| format!("Bearer {}", token))),
+            (HeaderName::from_static("x-actor"), self.actor),
+            (HeaderName::from_static("x-tenant-id"), self.tenant),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value {
+                let mut value = HeaderValue::from_str(&value)
+                    .map_err(|_| Error::InvalidHeader(name.as_str().to_string()))?;
+                value.set_sensitive(name == AUTHORIZATION);
+                headers.insert(name, value);
+            }
+        }
+
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(self.timeout)
+            .user_agent(concat!("ibvi-ads-client/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Client {
+            http,
+            base_url,
+            retry: self.retry,
+            page_size: self.page_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Query, State};
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Requests the stub API received
+    #[derive(Default)]
+    struct Stub {
+        /// `cursor` of each list request
+        cursors: Mutex<Vec<Option<String>>>,
+        /// `Idempotency-Key` of each write
+        idempotency_keys: Mutex<Vec<String>>,
+    }
+
+    /// Serve `router` locally; the client retries without waiting
+    async fn serve(router: Router) -> Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let retry = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        Client::builder(&url).retry(retry).build().unwrap()
+    }
+
+    fn campaign(id: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "platform": "google",
+            "name": format!("Campaign {}", id),
+            "status": "ENABLED",
+            "daily_budget": 100.0,
+            "currency": "BRL",
+            "metrics": {
+                "impressions": 0, "clicks": 0, "conversions": 0,
+                "cost": 0.0, "ctr": 0.0, "cpa": 0.0
+            }
+        })
+    }
+
+    fn record_cursor(stub: &Stub, query: &HashMap<String, String>) -> Option<String> {
+        let cursor = query.get("cursor").cloned();
+        stub.cursors.lock().unwrap().push(cursor.clone());
+        cursor
+    }
+
+    /// Two pages, the first pointing at `page-2`
+    async fn two_pages(
+        State(stub): State<Arc<Stub>>,
+        Query(query): Query<HashMap<String, String>>,
+    ) -> Response {
+        match record_cursor(&stub, &query) {
+            None => ([(NEXT_CURSOR_HEADER, "page-2")], Json(vec![campaign("1")])).into_response(),
+            Some(_) => Json(vec![campaign("2")]).into_response(),
+        }
+    }
+
+    /// Every page points at the same cursor
+    async fn repeated_cursor(
+        State(stub): State<Arc<Stub>>,
+        Query(query): Query<HashMap<String, String>>,
+    ) -> Response {
+        record_cursor(&stub, &query);
+        ([(NEXT_CURSOR_HEADER, "again")], Json(vec![campaign("1")])).into_response()
+    }
+
+    /// Rate limits the first write, applies the next one
+    async fn rate_limited_once(
+        State(stub): State<Arc<Stub>>,
+        headers: axum::http::HeaderMap,
+    ) -> Response {
+        let key = headers
+            .get(IDEMPOTENCY_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let mut keys = stub.idempotency_keys.lock().unwrap();
+        keys.push(key.to_string());
+        if keys.len() == 1 {
+            return (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0")]).into_response();
+        }
+
+        let operation = json!({
+            "type": "set_status", "platform": "google", "campaign_id": "1", "status": "PAUSED"
+        });
+        Json(json!({
+            "batch_id": "batch-1",
+            "succeeded": 1,
+            "failed": 0,
+            "results": [{"index": 0, "operation": operation, "success": true}]
+        }))
+        .into_response()
+    }
+
+    #[tokio::test]
+    async fn test_list_follows_the_next_cursor_to_the_last_page() {
+        let stub = Arc::new(Stub::default());
+        let router = Router::new().route("/campaigns", get(two_pages)).with_state(stub.clone());
+        let client = serve(router).await;
+
+        let campaigns = client.campaigns().list().await.unwrap();
+        let ids: Vec<&str> = campaigns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2"]);
+        assert_eq!(*stub.cursors.lock().unwrap(), [None, Some("page-2".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_list_stops_when_the_cursor_repeats() {
+        let stub = Arc::new(Stub::default());
+        let router = Router::new()
+            .route("/campaigns", get(repeated_cursor))
+            .with_state(stub.clone());
+        let client = serve(router).await;
+
+        let campaigns = client.campaigns().list().await.unwrap();
+        assert_eq!(campaigns.len(), 2);
+        assert_eq!(*stub.cursors.lock().unwrap(), [None, Some("again".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_write_is_retried_with_the_same_idempotency_key() {
+        let stub = Arc::new(Stub::default());
+        let router = Router::new()
+            .route("/campaigns/bulk", post(rate_limited_once))
+            .with_state(stub.clone());
+        let client = serve(router).await;
+
+        client.pause(Platform::Google, "1").await.unwrap();
+
+        let keys = stub.idempotency_keys.lock().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(!keys[0].is_empty());
+        assert_eq!(keys[0], keys[1]);
+    }
+}
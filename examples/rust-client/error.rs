@@ -0,0 +1,76 @@
+//! Errors of the client
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// `application/problem+json` body of an API error
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    /// Stable machine-readable code, e.g. `validation_failed`
+    pub code: String,
+    pub detail: String,
+    /// Seconds to wait before retrying, for rate limits
+    #[serde(default)]
+    pub retry_after: Option<u64>,
+    /// What to do about a platform error
+    #[serde(default)]
+    pub remediation: Option<String>,
+    /// Platform's own error code, for support tickets
+    #[serde(default)]
+    pub upstream_code: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid base URL {0:?}")]
+    InvalidUrl(String),
+    #[error("invalid value for header {0}")]
+    InvalidHeader(String),
+    /// Connection, timeout or decoding failure, after retries
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// Error response, with its problem details when the API sent any
+    #[error("API returned {status}: {}", problem_detail(.problem))]
+    Api {
+        status: StatusCode,
+        /// Boxed, so results of the client stay small
+        problem: Option<Box<Problem>>,
+    },
+    /// Operation of an accepted bulk request that the API could not apply,
+    /// e.g. during a change freeze
+    #[error("operation rejected: {message}")]
+    Rejected {
+        /// API error code, e.g. `change_frozen`
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Error of a non-success response
+    pub(crate) async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status();
+        let problem = match response.bytes().await {
+            Ok(body) => serde_json::from_slice::<Problem>(&body).ok().map(Box::new),
+            Err(_) => None,
+        };
+        Error::Api { status, problem }
+    }
+
+    /// Machine-readable code of an API error, e.g. `rate_limited`
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Error::Api { problem: Some(problem), .. } => Some(&problem.code),
+            Error::Rejected { code, .. } => code.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+fn problem_detail(problem: &Option<Box<Problem>>) -> &str {
+    problem.as_ref().map_or("no problem details", |p| p.detail.as_str())
+}
@@ -0,0 +1,53 @@
+//! # IBVI Ads Client
+//!
+//! Typed async client for the Core API, so internal Rust services stop
+//! hand-rolling `reqwest` calls and re-implementing its paging and error
+//! bodies:
+//!
+//! ```no_run
+//! use chrono::NaiveDate;
+//! use ibvi_ads_client::{CampaignStatus, Client, Platform};
+//!
+//! # async fn run() -> Result<(), ibvi_ads_client::Error> {
+//! let client = Client::builder("https://core.internal").token("...").build()?;
+//!
+//! let start = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+//! let end = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+//! let campaigns = client
+//!     .campaigns()
+//!     .platform(Platform::Google)
+//!     .status(CampaignStatus::Enabled)
+//!     .date_range(start..=end)
+//!     .list()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! - lists are read page by page (`?limit=` and the `X-Next-Cursor`
+//!   header) until the last page, so callers always get complete lists;
+//! - reads failing with a timeout, a connection error, `429` or a `502`,
+//!   `503` or `504` are retried with exponential backoff, waiting at least
+//!   the `Retry-After` the API asked for (see `RetryPolicy`);
+//! - other errors surface as `Error::Api` with the `application/problem+json`
+//!   body the API sent.
+//!
+//! The types mirror the API's JSON rather than depending on the server
+//! crate, so clients do not compile axum and the connectors; fields they
+//! do not know are ignored, which keeps older clients working as the API
+//! grows.
+
+mod campaigns;
+mod client;
+mod error;
+mod retry;
+mod types;
+
+pub use campaigns::CampaignsRequest;
+pub use client::{Client, ClientBuilder, DEFAULT_PAGE_SIZE, NEXT_CURSOR_HEADER};
+pub use error::{Error, Problem};
+pub use retry::RetryPolicy;
+pub use types::{
+    BulkItemResult, BulkOperation, BulkReport, Campaign, CampaignMetrics, CampaignStatus,
+    CampaignSummary, Money, Platform,
+};
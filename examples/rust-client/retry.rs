@@ -0,0 +1,80 @@
+//! Retries of failed reads
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// How failed reads are retried
+///
+/// The `n`th retry waits `base_delay * 2^n`, capped at `max_delay`, or the
+/// response's `Retry-After` when it asks for longer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (from 0)
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        let backoff = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        retry_after.map_or(backoff, |after| after.max(backoff))
+    }
+}
+
+/// Whether a response status is worth retrying: rate limits and gateway
+/// errors, which the next attempt may not hit
+pub(crate) fn is_retryable(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Whether a request failed before getting a response in a way worth retrying
+pub(crate) fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect()
+}
+
+/// `Retry-After` of a response, in seconds; HTTP dates are ignored
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers.get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap_and_honors_retry_after() {
+        let policy = RetryPolicy::default();
+        let delays: Vec<u128> = (0..6).map(|n| policy.delay(n, None).as_millis()).collect();
+        assert_eq!(delays, [200, 400, 800, 1600, 3200, 5000]);
+
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(2))), Duration::from_secs(2));
+        assert_eq!(policy.delay(4, Some(Duration::from_secs(1))), Duration::from_millis(3200));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2026 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+}
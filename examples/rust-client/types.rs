@@ -0,0 +1,198 @@
+//! Request and response bodies of the API
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Google,
+    Meta,
+    /// Only listed by servers built with the `linkedin` feature
+    LinkedIn,
+    /// Only listed by servers built with the `microsoft` feature
+    Microsoft,
+}
+
+impl Platform {
+    /// Value of the `platform` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Google => "google",
+            Platform::Meta => "meta",
+            Platform::LinkedIn => "linkedin",
+            Platform::Microsoft => "microsoft",
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    /// Case-insensitive, e.g. `Meta`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "google" => Ok(Platform::Google),
+            "meta" => Ok(Platform::Meta),
+            "linkedin" => Ok(Platform::LinkedIn),
+            "microsoft" => Ok(Platform::Microsoft),
+            _ => Err(format!("unknown platform {:?}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CampaignStatus {
+    Enabled,
+    Paused,
+    Removed,
+    /// Platform value the API does not map, e.g. `PENDING`
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl CampaignStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CampaignStatus::Enabled => "ENABLED",
+            CampaignStatus::Paused => "PAUSED",
+            CampaignStatus::Removed => "REMOVED",
+            CampaignStatus::Other(value) => value,
+        }
+    }
+}
+
+impl FromStr for CampaignStatus {
+    type Err = String;
+
+    /// Case-insensitive, e.g. `enabled`; only the statuses the API maps
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "ENABLED" => Ok(CampaignStatus::Enabled),
+            "PAUSED" => Ok(CampaignStatus::Paused),
+            "REMOVED" => Ok(CampaignStatus::Removed),
+            _ => Err(format!("unknown status {:?}", value)),
+        }
+    }
+}
+
+/// Month-to-date totals, or totals of the requested date range
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct CampaignMetrics {
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub cost: f64,
+    /// Click-through rate in percent
+    pub ctr: f64,
+    pub cpa: f64,
+    #[serde(default)]
+    pub conversion_value: f64,
+    #[serde(default)]
+    pub roas: f64,
+    /// People reached; `None` where the platform does not report it or on
+    /// totals spanning platforms
+    #[serde(default)]
+    pub reach: Option<u64>,
+    /// Impressions per person reached
+    #[serde(default)]
+    pub frequency: Option<f64>,
+    #[serde(default)]
+    pub video_views: Option<u64>,
+    /// Video views per impression in percent
+    #[serde(default)]
+    pub video_view_rate: Option<f64>,
+    #[serde(default)]
+    pub average_watch_time_seconds: Option<f64>,
+}
+
+/// Campaign of any platform, in the API's unified shape
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Campaign {
+    pub id: String,
+    pub platform: Platform,
+    pub name: String,
+    pub status: CampaignStatus,
+    /// e.g. `SALES`; `None` for platforms that do not report one
+    #[serde(default)]
+    pub objective: Option<String>,
+    /// e.g. `SEARCH`; `None` for platforms that do not report one
+    #[serde(default)]
+    pub campaign_type: Option<String>,
+    pub daily_budget: f64,
+    pub currency: String,
+    pub metrics: CampaignMetrics,
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Fields parsed from the name by the naming conventions
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+/// Amount in one currency
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Money {
+    pub amount: f64,
+    pub currency: String,
+}
+
+/// Response of `GET /campaigns/summary`; every total is keyed by the
+/// currency it is in, amounts are never converted
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignSummary {
+    /// Daily budget of enabled campaigns
+    pub total_daily_budget: BTreeMap<String, Money>,
+    pub metrics: BTreeMap<String, CampaignMetrics>,
+    /// Totals per platform, then per currency
+    pub by_platform: BTreeMap<String, BTreeMap<String, CampaignMetrics>>,
+    /// Display label of each metric in the request's locale
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Single change of a `POST /campaigns/bulk` request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkOperation {
+    SetStatus {
+        platform: Platform,
+        campaign_id: String,
+        status: CampaignStatus,
+    },
+    SetBudget {
+        platform: Platform,
+        campaign_id: String,
+        daily_budget: f64,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct BulkRequest {
+    pub operations: Vec<BulkOperation>,
+}
+
+/// Outcome of one bulk operation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkItemResult {
+    /// Position of the operation in the request
+    pub index: usize,
+    pub operation: BulkOperation,
+    pub success: bool,
+    /// API error code when the operation failed
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Response of `POST /campaigns/bulk`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkReport {
+    /// ID to roll the batch back with `POST /bulk/{batch_id}/rollback`
+    pub batch_id: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
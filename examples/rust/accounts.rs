@@ -0,0 +1,424 @@
+//! # Account Hierarchy Example
+//!
+//! Agencies run their ad accounts under a Google manager account (MCC) or
+//! a Meta Business Manager. The gateway lists both hierarchies and this
+//! module normalizes them into one tree of `AccountNode`s:
+//!
+//! - Google: every customer client of the login manager account, managers
+//!   included, each under its direct manager;
+//! - Meta: the ad accounts the token can reach, each under the business
+//!   that owns it. Ad accounts without a business are roots of their own.
+//!
+//! `GET /accounts` returns the tree and `GET /accounts/summary` the same
+//! tree with every node carrying the totals of the campaigns below it.
+//! `GET /campaigns?account_id=` keeps the campaigns of an account and of
+//! every account under it, so a manager ID lists its whole hierarchy and
+//! a leaf ID a single ad account.
+//!
+//! Hierarchies change rarely; they are fetched at most once per
+//! `ACCOUNTS_TTL`.
+
+use axum::{extract::State, response::Json};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use super::archive;
+use super::call_budget;
+use super::campaign_aggregator::{
+    aggregate_campaigns, combine_by_currency, AppState, Campaign, CampaignMetrics, CampaignStatus,
+    Platform,
+};
+use super::connectors::{ConnectorError, ConnectorRegistry};
+use super::error::ApiError;
+use super::ids::AccountId;
+use super::money::{self, Currency, MoneyByCurrency};
+
+/// How long a fetched hierarchy is served before it is fetched again
+pub const ACCOUNTS_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AccountKind {
+    /// Google manager account (MCC)
+    Manager,
+    /// Meta Business Manager
+    Business,
+    /// Account owning campaigns
+    AdAccount,
+}
+
+/// Account in a platform hierarchy
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AccountNode {
+    pub platform: Platform,
+    pub id: AccountId,
+    pub name: String,
+    pub kind: AccountKind,
+    /// Manager or business directly above; `None` for roots
+    pub parent_id: Option<AccountId>,
+    /// `None` for businesses, which hold no money
+    pub currency: Option<Currency>,
+}
+
+/// Customer client of the login manager account, as returned by the
+/// Google gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleCustomerClient {
+    pub id: AccountId,
+    pub descriptive_name: String,
+    pub manager: bool,
+    /// Direct manager; `None` for the login manager account itself
+    pub parent_id: Option<AccountId>,
+    pub currency_code: Option<String>,
+}
+
+/// Ad account as returned by the Meta gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaAdAccount {
+    /// `act_` ID
+    pub id: AccountId,
+    pub name: String,
+    pub currency: Option<String>,
+    /// Owning Business Manager
+    pub business: Option<MetaBusiness>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaBusiness {
+    pub id: AccountId,
+    pub name: String,
+}
+
+pub fn normalize_google(clients: Vec<GoogleCustomerClient>) -> Vec<AccountNode> {
+    clients
+        .into_iter()
+        .map(|client| AccountNode {
+            platform: Platform::Google,
+            id: client.id,
+            name: client.descriptive_name,
+            kind: if client.manager { AccountKind::Manager } else { AccountKind::AdAccount },
+            parent_id: client.parent_id,
+            currency: client.currency_code.as_deref().map(Currency::new),
+        })
+        .collect()
+}
+
+/// Ad accounts and the businesses owning them, each business once
+pub fn normalize_meta(accounts: Vec<MetaAdAccount>) -> Vec<AccountNode> {
+    let mut businesses = BTreeMap::new();
+    let mut nodes = Vec::new();
+    for account in accounts {
+        if let Some(business) = &account.business {
+            businesses.entry(business.id.clone()).or_insert_with(|| AccountNode {
+                platform: Platform::Meta,
+                id: business.id.clone(),
+                name: business.name.clone(),
+                kind: AccountKind::Business,
+                parent_id: None,
+                currency: None,
+            });
+        }
+        nodes.push(AccountNode {
+            platform: Platform::Meta,
+            id: account.id,
+            name: account.name,
+            kind: AccountKind::AdAccount,
+            parent_id: account.business.map(|b| b.id),
+            currency: account.currency.as_deref().map(Currency::new),
+        });
+    }
+    businesses.into_values().chain(nodes).collect()
+}
+
+/// Totals of the campaigns of an account and of every account below it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountRollup {
+    pub campaigns: usize,
+    /// Daily budget of enabled campaigns
+    pub total_daily_budget: MoneyByCurrency,
+    pub metrics: BTreeMap<Currency, CampaignMetrics>,
+}
+
+/// Node of the tree returned by `GET /accounts`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountTree {
+    #[serde(flatten)]
+    pub account: AccountNode,
+    /// Present in `GET /accounts/summary`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<AccountRollup>,
+    pub children: Vec<AccountTree>,
+}
+
+/// Accounts of an account's subtree, the account included
+#[derive(Debug, Default)]
+pub struct AccountScope {
+    accounts: HashSet<(Platform, AccountId)>,
+}
+
+impl AccountScope {
+    pub fn contains(&self, campaign: &Campaign) -> bool {
+        campaign
+            .account_id
+            .as_ref()
+            .is_some_and(|id| self.accounts.contains(&(campaign.platform.clone(), id.clone())))
+    }
+}
+
+/// Accounts of every platform, linked to their parents
+#[derive(Debug, Default)]
+pub struct AccountHierarchy {
+    nodes: Vec<AccountNode>,
+    /// Children of each node, by node index
+    children: HashMap<usize, Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+impl AccountHierarchy {
+    /// Hierarchy of `nodes`; a node whose parent is not listed becomes a root
+    pub fn new(nodes: Vec<AccountNode>) -> Self {
+        let mut index = HashMap::new();
+        let mut unique = Vec::new();
+        for node in nodes {
+            let key = (node.platform.clone(), node.id.clone());
+            if let Entry::Vacant(entry) = index.entry(key) {
+                entry.insert(unique.len());
+                unique.push(node);
+            }
+        }
+        let nodes = unique;
+
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut roots = Vec::new();
+        for (position, node) in nodes.iter().enumerate() {
+            let parent = node
+                .parent_id
+                .as_ref()
+                .and_then(|p| index.get(&(node.platform.clone(), p.clone())));
+            match parent {
+                Some(parent) => children.entry(*parent).or_default().push(position),
+                None => roots.push(position),
+            }
+        }
+        Self { nodes, children, roots }
+    }
+
+    fn children_of(&self, index: usize) -> &[usize] {
+        self.children.get(&index).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Accounts below `id`, on whichever platform lists it; `None` when no
+    /// platform does
+    pub fn scope(&self, id: &AccountId) -> Option<AccountScope> {
+        let mut pending: Vec<usize> =
+            self.nodes.iter().enumerate().filter(|(_, n)| &n.id == id).map(|(i, _)| i).collect();
+        if pending.is_empty() {
+            return None;
+        }
+        let mut scope = AccountScope::default();
+        while let Some(index) = pending.pop() {
+            let node = &self.nodes[index];
+            // Guards against a manager cycle in the gateway's response
+            if scope.accounts.insert((node.platform.clone(), node.id.clone())) {
+                pending.extend_from_slice(self.children_of(index));
+            }
+        }
+        Some(scope)
+    }
+
+    /// Every root with its accounts below, roots in platform then name order
+    pub fn tree(&self) -> Vec<AccountTree> {
+        let mut visited = HashSet::new();
+        let mut roots: Vec<AccountTree> =
+            self.roots.iter().map(|index| self.subtree(*index, &mut visited)).collect();
+        roots.sort_by(|a, b| {
+            (a.account.platform.as_str(), &a.account.name)
+                .cmp(&(b.account.platform.as_str(), &b.account.name))
+        });
+        roots
+    }
+
+    fn subtree(&self, index: usize, visited: &mut HashSet<usize>) -> AccountTree {
+        visited.insert(index);
+        let mut children = Vec::new();
+        for &child in self.children_of(index) {
+            if !visited.contains(&child) {
+                children.push(self.subtree(child, visited));
+            }
+        }
+        children.sort_by(|a, b| a.account.name.cmp(&b.account.name));
+        AccountTree { account: self.nodes[index].clone(), summary: None, children }
+    }
+}
+
+/// Fill in the `summary` of every node of `tree` from `campaigns`
+///
+/// Campaigns of accounts missing from the tree are not counted anywhere.
+pub fn roll_up(tree: &mut [AccountTree], campaigns: &[Campaign]) {
+    let mut by_account: HashMap<(Platform, AccountId), Vec<&Campaign>> = HashMap::new();
+    for campaign in campaigns {
+        if let Some(account_id) = &campaign.account_id {
+            by_account
+                .entry((campaign.platform.clone(), account_id.clone()))
+                .or_default()
+                .push(campaign);
+        }
+    }
+    for node in tree {
+        roll_up_node(node, &by_account);
+    }
+}
+
+fn roll_up_node<'a>(
+    node: &mut AccountTree,
+    by_account: &HashMap<(Platform, AccountId), Vec<&'a Campaign>>,
+) -> Vec<&'a Campaign> {
+    let key = (node.account.platform.clone(), node.account.id.clone());
+    let mut campaigns = by_account.get(&key).cloned().unwrap_or_default();
+    for child in &mut node.children {
+        campaigns.extend(roll_up_node(child, by_account));
+    }
+    node.summary = Some(AccountRollup {
+        campaigns: campaigns.len(),
+        total_daily_budget: money::sum_by_currency(
+            campaigns
+                .iter()
+                .filter(|c| matches!(c.status, CampaignStatus::Enabled))
+                .map(|c| (c.currency(), c.daily_budget)),
+        ),
+        metrics: combine_by_currency(campaigns.iter().copied()),
+    });
+    campaigns
+}
+
+/// Account hierarchies of every platform, fetched at most once per
+/// `ACCOUNTS_TTL`
+#[derive(Default)]
+pub struct AccountDirectory {
+    current: RwLock<Option<(Instant, Arc<AccountHierarchy>)>>,
+}
+
+impl AccountDirectory {
+    pub async fn hierarchy(
+        &self,
+        registry: &ConnectorRegistry,
+    ) -> Result<Arc<AccountHierarchy>, ApiError> {
+        if let Some((fetched_at, hierarchy)) = &*self.current.read().unwrap() {
+            if fetched_at.elapsed() < ACCOUNTS_TTL {
+                return Ok(hierarchy.clone());
+            }
+        }
+
+        call_budget::charge(registry.all().len())?;
+        let fetches = registry.all().iter().map(|connector| async move {
+            (connector.platform(), connector.fetch_accounts().await)
+        });
+        let mut nodes = Vec::new();
+        let mut complete = true;
+        for (platform, result) in futures::future::join_all(fetches).await {
+            match result {
+                Ok(accounts) => nodes.extend(accounts),
+                Err(ConnectorError::NotSupported(_)) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        platform = platform.as_str(),
+                        error = %e,
+                        "account fetch failed"
+                    );
+                    complete = false;
+                }
+            }
+        }
+
+        let hierarchy = Arc::new(AccountHierarchy::new(nodes));
+        // A partial hierarchy is served but fetched again on the next request
+        if complete {
+            *self.current.write().unwrap() = Some((Instant::now(), hierarchy.clone()));
+        }
+        Ok(hierarchy)
+    }
+}
+
+/// GET /accounts - Manager and Business Manager hierarchies of every platform
+pub async fn get_accounts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AccountTree>>, ApiError> {
+    let hierarchy = state.accounts.hierarchy(&state.connectors).await?;
+    Ok(Json(hierarchy.tree()))
+}
+
+/// GET /accounts/summary - Account tree with the campaign totals of every node
+pub async fn get_account_summary(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AccountTree>>, ApiError> {
+    let hierarchy = state.accounts.hierarchy(&state.connectors).await?;
+    let mut campaigns = aggregate_campaigns(&state).await;
+    archive::exclude_removed(&mut campaigns);
+
+    let mut tree = hierarchy.tree();
+    roll_up(&mut tree, &campaigns);
+    Ok(Json(tree))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(id: &str, manager: bool, parent_id: Option<&str>) -> GoogleCustomerClient {
+        GoogleCustomerClient {
+            id: id.into(),
+            descriptive_name: format!("Account {}", id),
+            manager,
+            parent_id: parent_id.map(AccountId::from),
+            currency_code: Some("BRL".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_manager_scope_and_rollup_cover_sub_accounts() {
+        let mut nodes = normalize_google(vec![
+            client("mcc", true, None),
+            client("sub-mcc", true, Some("mcc")),
+            client("a1", false, Some("sub-mcc")),
+            client("a2", false, Some("mcc")),
+        ]);
+        nodes.extend(normalize_meta(vec![MetaAdAccount {
+            id: "act_1".into(),
+            name: "Vila Nova".to_string(),
+            currency: Some("BRL".to_string()),
+            business: Some(MetaBusiness { id: "bm".into(), name: "IBVI".to_string() }),
+        }]));
+        let hierarchy = AccountHierarchy::new(nodes);
+
+        let campaign = |id: &str, platform: Platform, account: &str| {
+            let mut campaign = Campaign::sample(id, platform);
+            campaign.account_id = Some(account.into());
+            campaign.metrics.cost = 100.0;
+            campaign
+        };
+        let campaigns = vec![
+            campaign("g1", Platform::Google, "a1"),
+            campaign("g2", Platform::Google, "a2"),
+            campaign("m1", Platform::Meta, "act_1"),
+        ];
+
+        let sub_mcc = hierarchy.scope(&"sub-mcc".into()).unwrap();
+        let kept: Vec<&str> =
+            campaigns.iter().filter(|c| sub_mcc.contains(c)).map(|c| c.id.as_str()).collect();
+        assert_eq!(kept, ["g1"]);
+        assert!(hierarchy.scope(&"unknown".into()).is_none());
+
+        let mut tree = hierarchy.tree();
+        roll_up(&mut tree, &campaigns);
+        let names: Vec<&str> = tree.iter().map(|n| n.account.name.as_str()).collect();
+        assert_eq!(names, ["Account mcc", "IBVI"]);
+        let mcc = &tree[0];
+        assert_eq!(mcc.summary.as_ref().unwrap().campaigns, 2);
+        assert_eq!(mcc.children[1].account.id, "sub-mcc");
+        assert_eq!(mcc.children[1].summary.as_ref().unwrap().campaigns, 1);
+        assert_eq!(tree[1].children[0].account.kind, AccountKind::AdAccount);
+    }
+}
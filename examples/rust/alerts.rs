@@ -0,0 +1,757 @@
+//! # Budget Alerts Example
+//!
+//! User-defined alert rules evaluated by the sync worker after every sync,
+//! with pluggable notifiers (Slack webhook, SMTP email, generic webhook)
+//! and an in-memory alert history.
+//!
+//! A flapping metric fires the same rule on every sync. Repeats of a rule
+//! for the same campaign within the rule's dedup window are folded into
+//! the first alert instead of being delivered again; once the window
+//! closes, one summary notification carries the repeat count.
+//!
+//! Rules with `digest` delivery queue their alerts instead and send them
+//! bundled into one message once a day at `send_at` (UTC).
+//!
+//! Email delivery pulls in an SMTP client and is compiled only with the
+//! `email` cargo feature (on by default); without it email rules fail to
+//! deliver like they do when no SMTP relay is configured.
+
+use async_trait::async_trait;
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+#[cfg(feature = "email")]
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{AppState, Campaign};
+#[cfg(feature = "email")]
+use super::config::{ConfigError, SmtpConfig};
+use super::error::ApiError;
+use super::ids::CampaignId;
+use super::store::MetricsStore;
+
+/// Dedup window of rules created without one
+pub const DEFAULT_DEDUP_WINDOW_HOURS: u32 = 6;
+
+fn default_dedup_window_hours() -> u32 {
+    DEFAULT_DEDUP_WINDOW_HOURS
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Condition that triggers an alert
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Today's spend exceeds `percentage` percent of the daily budget
+    SpendOverBudget { percentage: f64 },
+    /// Daily CPA stayed above `max_cpa` for `days` consecutive days
+    CpaAbove { max_cpa: f64, days: usize },
+}
+
+/// Where a triggered alert is delivered
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    Slack { webhook_url: String },
+    Email { to: String },
+    Webhook { url: String },
+}
+
+/// When a rule's alerts are delivered
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// One notification per alert, as soon as it fires
+    #[default]
+    Immediate,
+    /// Alerts bundled into one message per day, sent at `send_at` UTC
+    Digest { send_at: NaiveTime },
+}
+
+/// Registered alert rule
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    /// Restrict the rule to one campaign; `None` applies it to all campaigns
+    pub campaign_id: Option<CampaignId>,
+    pub condition: AlertCondition,
+    pub notifier: NotifierConfig,
+    /// Repeats within this many hours are grouped; 0 delivers every alert
+    #[serde(default = "default_dedup_window_hours")]
+    pub dedup_window_hours: u32,
+    #[serde(default)]
+    pub delivery: DeliveryMode,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AlertRule {
+    pub fn dedup_window(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.dedup_window_hours.into())
+    }
+}
+
+/// Request body for `POST /alerts/rules`
+#[derive(Debug, Deserialize)]
+pub struct CreateAlertRule {
+    pub name: String,
+    pub campaign_id: Option<CampaignId>,
+    pub condition: AlertCondition,
+    pub notifier: NotifierConfig,
+    #[serde(default = "default_dedup_window_hours")]
+    pub dedup_window_hours: u32,
+    #[serde(default)]
+    pub delivery: DeliveryMode,
+}
+
+/// How urgently an alert needs attention
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    /// Raised by user-defined rules
+    #[default]
+    Warning,
+    /// Needs action before money is lost, e.g. a tenant nearing its spend cap
+    Critical,
+}
+
+/// Alert raised by a rule for a campaign
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertEvent {
+    pub rule_id: String,
+    /// Empty for alerts about a whole tenant
+    pub campaign_id: String,
+    pub campaign_name: String,
+    pub message: String,
+    #[serde(default)]
+    pub severity: AlertSeverity,
+    pub triggered_at: DateTime<Utc>,
+    /// Whether the notifier accepted the alert
+    pub delivered: bool,
+    /// Times the rule fired for the campaign within the dedup window
+    #[serde(default = "one")]
+    pub occurrences: u32,
+    /// Latest repeat folded into this alert
+    #[serde(default)]
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    /// Whether the repeat count was sent after the window closed
+    #[serde(default)]
+    pub summary_sent: bool,
+}
+
+/// Rules and alert history
+#[derive(Default)]
+pub struct AlertStore {
+    rules: RwLock<Vec<AlertRule>>,
+    history: RwLock<Vec<AlertEvent>>,
+    /// Alerts of digest rules waiting for their next digest
+    digest_queue: RwLock<Vec<AlertEvent>>,
+    /// Day each digest rule last sent its digest
+    last_digest: RwLock<HashMap<String, NaiveDate>>,
+}
+
+impl AlertStore {
+    pub fn add_rule(&self, rule: AlertRule) {
+        self.rules.write().unwrap().push(rule);
+    }
+
+    pub fn rules(&self) -> Vec<AlertRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    pub fn record(&self, event: AlertEvent) {
+        self.history.write().unwrap().push(event);
+    }
+
+    /// Hold an alert of a digest rule until its next digest
+    pub fn queue_digest(&self, event: AlertEvent) {
+        self.digest_queue.write().unwrap().push(event);
+    }
+
+    /// Digests of every rule whose `send_at` passed today
+    ///
+    /// Repeats for the same campaign become one line with a count. Rules
+    /// with nothing queued send no digest.
+    pub fn take_due_digests(
+        &self,
+        rules: &[AlertRule],
+        now: DateTime<Utc>,
+    ) -> Vec<(AlertRule, AlertEvent)> {
+        let today = now.date_naive();
+        let mut queue = self.digest_queue.write().unwrap();
+        let mut last_digest = self.last_digest.write().unwrap();
+        let mut due = Vec::new();
+
+        for rule in rules {
+            let DeliveryMode::Digest { send_at } = rule.delivery else {
+                continue;
+            };
+            if now.time() < send_at || last_digest.get(&rule.id) == Some(&today) {
+                continue;
+            }
+
+            let (events, rest): (Vec<_>, Vec<_>) =
+                queue.drain(..).partition(|e| e.rule_id == rule.id);
+            *queue = rest;
+            if events.is_empty() {
+                continue;
+            }
+            last_digest.insert(rule.id.clone(), today);
+
+            // One line per campaign, in the order campaigns first fired
+            let mut lines: Vec<(&AlertEvent, u32)> = Vec::new();
+            for event in &events {
+                match lines.iter_mut().find(|(e, _)| e.campaign_id == event.campaign_id) {
+                    Some((latest, count)) => {
+                        *latest = event;
+                        *count += 1;
+                    }
+                    None => lines.push((event, 1)),
+                }
+            }
+
+            let mut message = format!("{}: {} alerts since the last digest", rule.name, events.len());
+            for (event, count) in &lines {
+                message.push_str(&format!("\n- {}", event.message));
+                if *count > 1 {
+                    message.push_str(&format!(" ({} times)", count));
+                }
+            }
+
+            let campaign_ids: Vec<&str> = lines.iter().map(|(e, _)| e.campaign_id.as_str()).collect();
+            due.push((
+                rule.clone(),
+                AlertEvent {
+                    rule_id: rule.id.clone(),
+                    campaign_id: campaign_ids.join(","),
+                    campaign_name: format!("{} digest", rule.name),
+                    message,
+                    severity: AlertSeverity::Warning,
+                    triggered_at: now,
+                    delivered: false,
+                    occurrences: events.len() as u32,
+                    last_triggered_at: events.last().map(|e| e.triggered_at),
+                    // The digest already carries the counts
+                    summary_sent: true,
+                },
+            ));
+        }
+
+        due
+    }
+
+    /// Fold `event` into an alert of the same rule and campaign raised
+    /// less than `window` before it
+    ///
+    /// Returns `false` when no such alert exists and `event` must be
+    /// delivered.
+    pub fn absorb(&self, event: &AlertEvent, window: chrono::Duration) -> bool {
+        let mut history = self.history.write().unwrap();
+        let open = history.iter_mut().rev().find(|e| {
+            e.rule_id == event.rule_id
+                && e.campaign_id == event.campaign_id
+                && event.triggered_at < e.triggered_at + window
+        });
+
+        match open {
+            Some(open) => {
+                open.occurrences += 1;
+                open.last_triggered_at = Some(event.triggered_at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Summaries of grouped alerts whose dedup window closed by `now`
+    ///
+    /// Each group is summarized once; alerts that never repeated need none.
+    pub fn take_due_summaries(
+        &self,
+        rules: &[AlertRule],
+        now: DateTime<Utc>,
+    ) -> Vec<(AlertRule, AlertEvent)> {
+        let mut history = self.history.write().unwrap();
+        let mut due = Vec::new();
+
+        for event in history.iter_mut().filter(|e| e.occurrences > 1 && !e.summary_sent) {
+            let Some(rule) = rules.iter().find(|r| r.id == event.rule_id) else {
+                // Rule deleted; nobody to notify
+                event.summary_sent = true;
+                continue;
+            };
+            if now < event.triggered_at + rule.dedup_window() {
+                continue;
+            }
+
+            event.summary_sent = true;
+            let last = event.last_triggered_at.unwrap_or(event.triggered_at);
+            due.push((
+                rule.clone(),
+                AlertEvent {
+                    message: format!(
+                        "{} (fired {} times between {} and {})",
+                        event.message,
+                        event.occurrences,
+                        event.triggered_at.format("%Y-%m-%d %H:%M UTC"),
+                        last.format("%Y-%m-%d %H:%M UTC")
+                    ),
+                    triggered_at: now,
+                    delivered: false,
+                    ..event.clone()
+                },
+            ));
+        }
+
+        due
+    }
+
+    /// Alert history, most recent first
+    pub fn history(&self) -> Vec<AlertEvent> {
+        let mut history = self.history.read().unwrap().clone();
+        history.reverse();
+        history
+    }
+}
+
+/// Evaluate a rule against one campaign, returning the alert message if it fires
+pub fn evaluate_rule(
+    rule: &AlertRule,
+    campaign: &Campaign,
+    metrics: &MetricsStore,
+    today: NaiveDate,
+) -> Option<String> {
+    if let Some(campaign_id) = &rule.campaign_id {
+        if campaign_id != &campaign.id {
+            return None;
+        }
+    }
+
+    match &rule.condition {
+        AlertCondition::SpendOverBudget { percentage } => {
+            let spend = metrics
+                .recent(&campaign.global_id(), 1)
+                .into_iter()
+                .find(|day| day.date == today)?
+                .spend;
+            let limit = campaign.daily_budget * percentage / 100.0;
+
+            (campaign.daily_budget > 0.0 && spend > limit).then(|| {
+                format!(
+                    "{} spent {:.2} {} today, above {}% of its {:.2} daily budget",
+                    campaign.name, spend, campaign.currency, percentage, campaign.daily_budget
+                )
+            })
+        }
+        AlertCondition::CpaAbove { max_cpa, days } => {
+            let recent = metrics.recent(&campaign.global_id(), *days);
+            if *days == 0 || recent.len() < *days {
+                return None;
+            }
+
+            // A day without conversions but with spend counts as an infinite CPA
+            let breached = recent.iter().all(|day| match day.cpa() {
+                Some(cpa) => cpa > *max_cpa,
+                None => day.spend > 0.0,
+            });
+
+            breached.then(|| {
+                format!(
+                    "{} CPA stayed above {:.2} {} for {} consecutive days",
+                    campaign.name, max_cpa, campaign.currency, days
+                )
+            })
+        }
+    }
+}
+
+/// Evaluate every rule against every campaign
+pub fn evaluate_rules(
+    rules: &[AlertRule],
+    campaigns: &[Campaign],
+    metrics: &MetricsStore,
+    today: NaiveDate,
+) -> Vec<(AlertRule, AlertEvent)> {
+    let mut fired = Vec::new();
+
+    for rule in rules {
+        for campaign in campaigns {
+            if let Some(message) = evaluate_rule(rule, campaign, metrics, today) {
+                fired.push((
+                    rule.clone(),
+                    AlertEvent {
+                        rule_id: rule.id.clone(),
+                        campaign_id: campaign.id.to_string(),
+                        campaign_name: campaign.name.clone(),
+                        message,
+                        severity: AlertSeverity::Warning,
+                        triggered_at: Utc::now(),
+                        delivered: false,
+                        occurrences: 1,
+                        last_triggered_at: None,
+                        summary_sent: false,
+                    },
+                ));
+            }
+        }
+    }
+
+    fired
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("http notification failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("email notification failed: {0}")]
+    Email(String),
+    #[error("email notifier is not configured")]
+    EmailNotConfigured,
+}
+
+/// Delivery channel for alert events
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, event: &AlertEvent) -> Result<(), NotifyError>;
+}
+
+/// Posts alerts to a Slack incoming webhook
+pub struct SlackNotifier {
+    pub http_client: reqwest::Client,
+    pub webhook_url: String,
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn notify(&self, event: &AlertEvent) -> Result<(), NotifyError> {
+        self.http_client
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "text": format!(":rotating_light: {}", event.message) }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Posts the raw alert event as JSON to an arbitrary URL
+pub struct WebhookNotifier {
+    pub http_client: reqwest::Client,
+    pub url: String,
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, event: &AlertEvent) -> Result<(), NotifyError> {
+        self.http_client
+            .post(&self.url)
+            .json(event)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// SMTP connection used for email alerts
+#[cfg(feature = "email")]
+#[derive(Clone)]
+pub struct SmtpSettings {
+    pub transport: AsyncSmtpTransport<Tokio1Executor>,
+    pub from: Mailbox,
+}
+
+#[cfg(feature = "email")]
+impl SmtpSettings {
+    pub fn from_config(config: &SmtpConfig) -> Result<Self, ConfigError> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+            .map_err(|e| ConfigError::invalid("smtp.host", e.to_string()))?
+            .credentials(Credentials::new(config.username.clone(), config.password.clone()))
+            .build();
+
+        let from = config
+            .from
+            .parse()
+            .map_err(|e: lettre::address::AddressError| ConfigError::invalid("smtp.from", e.to_string()))?;
+
+        Ok(Self { transport, from })
+    }
+}
+
+/// Sends alerts by email over SMTP
+#[cfg(feature = "email")]
+pub struct EmailNotifier {
+    pub smtp: SmtpSettings,
+    pub to: String,
+}
+
+#[cfg(feature = "email")]
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, event: &AlertEvent) -> Result<(), NotifyError> {
+        let to: Mailbox = self
+            .to
+            .parse()
+            .map_err(|e: lettre::address::AddressError| NotifyError::Email(e.to_string()))?;
+
+        let email = Message::builder()
+            .from(self.smtp.from.clone())
+            .to(to)
+            .subject(match event.severity {
+                AlertSeverity::Warning => format!("[IBVI Ads] Alert for {}", event.campaign_name),
+                AlertSeverity::Critical => {
+                    format!("[IBVI Ads] CRITICAL alert for {}", event.campaign_name)
+                }
+            })
+            .body(event.message.clone())
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+
+        self.smtp
+            .transport
+            .send(email)
+            .await
+            .map_err(|e| NotifyError::Email(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Builds the notifier for a rule and delivers its alerts
+pub struct AlertDispatcher {
+    pub http_client: reqwest::Client,
+    /// SMTP settings; email rules fail to deliver when absent
+    #[cfg(feature = "email")]
+    pub smtp: Option<SmtpSettings>,
+}
+
+impl AlertDispatcher {
+    pub fn notifier_for(&self, config: &NotifierConfig) -> Result<Box<dyn Notifier>, NotifyError> {
+        Ok(match config {
+            NotifierConfig::Slack { webhook_url } => Box::new(SlackNotifier {
+                http_client: self.http_client.clone(),
+                webhook_url: webhook_url.clone(),
+            }),
+            NotifierConfig::Webhook { url } => Box::new(WebhookNotifier {
+                http_client: self.http_client.clone(),
+                url: url.clone(),
+            }),
+            #[cfg(feature = "email")]
+            NotifierConfig::Email { to } => Box::new(EmailNotifier {
+                smtp: self.smtp.clone().ok_or(NotifyError::EmailNotConfigured)?,
+                to: to.clone(),
+            }),
+            #[cfg(not(feature = "email"))]
+            NotifierConfig::Email { .. } => return Err(NotifyError::EmailNotConfigured),
+        })
+    }
+
+    /// Deliver an alert of `rule`, recording whether delivery succeeded
+    pub async fn dispatch(&self, rule: &AlertRule, event: AlertEvent) -> AlertEvent {
+        self.deliver(&rule.notifier, event).await
+    }
+
+    /// Deliver an alert through `notifier`, recording whether delivery succeeded
+    pub async fn deliver(&self, notifier: &NotifierConfig, mut event: AlertEvent) -> AlertEvent {
+        let result = match self.notifier_for(notifier) {
+            Ok(notifier) => notifier.notify(&event).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = &result {
+            tracing::warn!(rule_id = %event.rule_id, error = %e, "alert delivery failed");
+        }
+
+        event.delivered = result.is_ok();
+        event
+    }
+}
+
+/// POST /alerts/rules - Register an alert rule
+pub async fn create_alert_rule(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateAlertRule>,
+) -> Result<Json<AlertRule>, ApiError> {
+    let valid = match &body.condition {
+        AlertCondition::SpendOverBudget { percentage } => *percentage > 0.0,
+        AlertCondition::CpaAbove { max_cpa, days } => *max_cpa > 0.0 && *days > 0,
+    };
+    if !valid {
+        return Err(ApiError::Validation(
+            "percentage, max_cpa and days must be greater than zero".to_string(),
+        ));
+    }
+
+    let rule = AlertRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: body.name,
+        campaign_id: body.campaign_id,
+        condition: body.condition,
+        notifier: body.notifier,
+        dedup_window_hours: body.dedup_window_hours,
+        delivery: body.delivery,
+        created_at: Utc::now(),
+    };
+
+    state.alerts.add_rule(rule.clone());
+
+    Ok(Json(rule))
+}
+
+/// GET /alerts/rules - List alert rules
+pub async fn list_alert_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AlertRule>>, ApiError> {
+    Ok(Json(state.alerts.rules()))
+}
+
+/// GET /alerts/history - Triggered alerts, most recent first
+pub async fn get_alert_history(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AlertEvent>>, ApiError> {
+    Ok(Json(state.alerts.history()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn rule(condition: AlertCondition) -> AlertRule {
+        AlertRule {
+            id: "r1".to_string(),
+            name: "test".to_string(),
+            campaign_id: None,
+            condition,
+            notifier: NotifierConfig::Webhook {
+                url: "http://localhost/hook".to_string(),
+            },
+            dedup_window_hours: 6,
+            delivery: DeliveryMode::Immediate,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_spend_over_budget_fires_on_todays_spend() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+
+        campaign.metrics.cost = 50.0;
+        store.record(day(1), &[campaign.clone()]);
+        campaign.metrics.cost = 145.0;
+        store.record(day(2), &[campaign.clone()]);
+
+        let at_90 = rule(AlertCondition::SpendOverBudget { percentage: 90.0 });
+        assert!(evaluate_rule(&at_90, &campaign, &store, day(2)).is_some());
+
+        let at_100 = rule(AlertCondition::SpendOverBudget { percentage: 100.0 });
+        assert!(evaluate_rule(&at_100, &campaign, &store, day(2)).is_none());
+    }
+
+    #[test]
+    fn test_cpa_above_requires_consecutive_days() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        let cpa_rule = rule(AlertCondition::CpaAbove { max_cpa: 40.0, days: 3 });
+
+        // Daily CPA of 50 on each of three days
+        for d in 1..=3 {
+            campaign.metrics.cost = d as f64 * 100.0;
+            campaign.metrics.conversions = d * 2;
+            store.record(day(d), &[campaign.clone()]);
+            let fired = evaluate_rule(&cpa_rule, &campaign, &store, day(d));
+            assert_eq!(fired.is_some(), d == 3);
+        }
+    }
+
+    #[test]
+    fn test_repeats_within_window_collapse_into_one_summary() {
+        let store = AlertStore::default();
+        let rule = rule(AlertCondition::SpendOverBudget { percentage: 90.0 });
+        let start = Utc::now();
+        let event = |minutes: i64| AlertEvent {
+            rule_id: "r1".to_string(),
+            campaign_id: "g1".to_string(),
+            campaign_name: "Campaign g1".to_string(),
+            message: "Campaign g1 spent too much".to_string(),
+            severity: AlertSeverity::Warning,
+            triggered_at: start + chrono::Duration::minutes(minutes),
+            delivered: true,
+            occurrences: 1,
+            last_triggered_at: None,
+            summary_sent: false,
+        };
+
+        assert!(!store.absorb(&event(0), rule.dedup_window()));
+        store.record(event(0));
+        for minutes in (15..=120).step_by(15) {
+            assert!(store.absorb(&event(minutes), rule.dedup_window()));
+        }
+        assert_eq!(store.history().len(), 1);
+        assert_eq!(store.history()[0].occurrences, 9);
+
+        // Nothing to summarize while the window is open
+        let rules = vec![rule.clone()];
+        assert!(store.take_due_summaries(&rules, start + chrono::Duration::hours(1)).is_empty());
+
+        let due = store.take_due_summaries(&rules, start + chrono::Duration::hours(6));
+        assert_eq!(due.len(), 1);
+        assert!(due[0].1.message.contains("fired 9 times"));
+        assert!(store.take_due_summaries(&rules, start + chrono::Duration::hours(7)).is_empty());
+
+        // After the window a new alert is delivered again
+        assert!(!store.absorb(&event(6 * 60), rule.dedup_window()));
+    }
+
+    #[test]
+    fn test_digest_bundles_queued_alerts_once_a_day() {
+        let store = AlertStore::default();
+        let mut digest_rule = rule(AlertCondition::SpendOverBudget { percentage: 90.0 });
+        digest_rule.delivery = DeliveryMode::Digest {
+            send_at: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        };
+        let rules = vec![digest_rule];
+        let at = |d: u32, h: u32| day(d).and_hms_opt(h, 0, 0).unwrap().and_utc();
+        let event = |campaign: &str| AlertEvent {
+            rule_id: "r1".to_string(),
+            campaign_id: campaign.to_string(),
+            campaign_name: format!("Campaign {}", campaign),
+            message: format!("Campaign {} spent too much", campaign),
+            severity: AlertSeverity::Warning,
+            triggered_at: at(1, 2),
+            delivered: false,
+            occurrences: 1,
+            last_triggered_at: None,
+            summary_sent: false,
+        };
+
+        store.queue_digest(event("g1"));
+        store.queue_digest(event("g2"));
+        store.queue_digest(event("g1"));
+
+        assert!(store.take_due_digests(&rules, at(1, 7)).is_empty());
+
+        let due = store.take_due_digests(&rules, at(1, 8));
+        assert_eq!(due.len(), 1);
+        let digest = &due[0].1;
+        assert_eq!(digest.occurrences, 3);
+        assert_eq!(digest.campaign_id, "g1,g2");
+        assert!(digest.message.contains("Campaign g1 spent too much (2 times)"));
+
+        // Sent at most once per day
+        store.queue_digest(event("g3"));
+        assert!(store.take_due_digests(&rules, at(1, 20)).is_empty());
+        assert_eq!(store.take_due_digests(&rules, at(2, 8)).len(), 1);
+    }
+}
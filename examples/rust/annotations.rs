@@ -0,0 +1,273 @@
+//! # Campaign Annotations Example
+//!
+//! Analysts explain metric shifts with dated notes on a campaign
+//! ("creative refreshed", "promo started"), kept by this service rather
+//! than on the platforms:
+//!
+//! - `POST /campaigns/{platform}/{id}/annotations` adds a note, dated
+//!   today unless the body gives a `date`;
+//! - `GET /campaigns/{platform}/{id}/annotations` lists them by date, and
+//!   `DELETE /campaigns/{platform}/{id}/annotations/{annotation_id}` drops
+//!   one;
+//! - `GET /campaigns/{platform}/{id}/history` returns the annotations
+//!   dated within its days, so charts can overlay them on the series.
+//!
+//! Notes can be added to any campaign its platform lists or the metrics
+//! store recorded, removed ones included. They are written next to the
+//! metrics snapshot (`server.snapshot_path`, with an `.annotations.json`
+//! extension) on every change and loaded on startup.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::timezones;
+
+/// Longest note, in characters
+pub const MAX_NOTE_CHARS: usize = 500;
+
+/// Dated note on a campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Annotation {
+    pub id: String,
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    /// Day the note explains
+    pub date: NaiveDate,
+    pub note: String,
+    /// `X-Actor` of the request adding it
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /campaigns/{platform}/{id}/annotations`
+#[derive(Debug, Deserialize)]
+pub struct AnnotationRequest {
+    /// Today (UTC) when absent
+    pub date: Option<NaiveDate>,
+    pub note: String,
+}
+
+impl AnnotationRequest {
+    /// The trimmed note, if not empty nor longer than `MAX_NOTE_CHARS`
+    fn validate(&self) -> Result<&str, ApiError> {
+        let note = self.note.trim();
+        if note.is_empty() {
+            return Err(ApiError::Validation("note must not be empty".to_string()));
+        }
+        if note.chars().count() > MAX_NOTE_CHARS {
+            return Err(ApiError::Validation(format!(
+                "note must be at most {} characters",
+                MAX_NOTE_CHARS
+            )));
+        }
+        Ok(note)
+    }
+}
+
+/// Annotations of every campaign
+#[derive(Default)]
+pub struct AnnotationStore {
+    annotations: RwLock<HashMap<GlobalCampaignId, Vec<Annotation>>>,
+}
+
+impl AnnotationStore {
+    pub fn add(&self, annotation: Annotation) {
+        let key =
+            GlobalCampaignId::new(annotation.platform.clone(), annotation.campaign_id.clone());
+        let mut annotations = self.annotations.write().unwrap();
+        let notes = annotations.entry(key).or_default();
+        notes.push(annotation);
+        notes.sort_by_key(|a| (a.date, a.created_at));
+    }
+
+    /// Annotations of a campaign, oldest date first
+    pub fn list(&self, campaign: &GlobalCampaignId) -> Vec<Annotation> {
+        self.annotations.read().unwrap().get(campaign).cloned().unwrap_or_default()
+    }
+
+    /// Annotations of a campaign dated from `start` to `end` inclusive,
+    /// oldest date first
+    pub fn between(
+        &self,
+        campaign: &GlobalCampaignId,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Vec<Annotation> {
+        let mut found = self.list(campaign);
+        found.retain(|a| a.date >= start && a.date <= end);
+        found
+    }
+
+    pub fn remove(&self, campaign: &GlobalCampaignId, annotation_id: &str) -> Option<Annotation> {
+        let mut annotations = self.annotations.write().unwrap();
+        let notes = annotations.get_mut(campaign)?;
+        let position = notes.iter().position(|a| a.id == annotation_id)?;
+        let removed = notes.remove(position);
+        if notes.is_empty() {
+            annotations.remove(campaign);
+        }
+        Some(removed)
+    }
+
+    /// Write every annotation to `path` atomically (write to temp file, then rename)
+    pub fn persist(&self, path: &FsPath) -> std::io::Result<()> {
+        let all: Vec<Annotation> =
+            self.annotations.read().unwrap().values().flatten().cloned().collect();
+        let json = serde_json::to_vec_pretty(&all)?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Add annotations previously written by `persist`
+    pub fn load(&self, path: &FsPath) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let loaded: Vec<Annotation> = serde_json::from_slice(&bytes)?;
+
+        for annotation in loaded {
+            self.add(annotation);
+        }
+        Ok(())
+    }
+}
+
+/// File the annotations are written to, next to the metrics snapshot
+pub fn annotations_path(snapshot_path: &FsPath) -> PathBuf {
+    snapshot_path.with_extension("annotations.json")
+}
+
+fn persist(state: &AppState) {
+    let Some(path) = state.config.server.snapshot_path.as_deref().map(annotations_path) else {
+        return;
+    };
+    if let Err(e) = state.annotations.persist(&path) {
+        tracing::error!(path = %path.display(), error = %e, "annotations not persisted");
+    }
+}
+
+/// Whether the campaign was recorded by the store or is listed by its platform
+async fn ensure_campaign(
+    state: &AppState,
+    platform: &Platform,
+    id: &CampaignId,
+) -> Result<(), ApiError> {
+    let campaign = GlobalCampaignId::new(platform.clone(), id.clone());
+    if !state.metrics_store.series(&campaign).is_empty() {
+        return Ok(());
+    }
+
+    let connector = state
+        .connectors
+        .get(platform)
+        .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+    if connector.fetch_campaigns().await?.iter().any(|c| &c.id == id) {
+        Ok(())
+    } else {
+        Err(ApiError::NotFound(format!("campaign {}", id)))
+    }
+}
+
+/// GET /campaigns/{platform}/{id}/annotations - Notes on a campaign
+pub async fn list_annotations(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<Vec<Annotation>>, ApiError> {
+    Ok(Json(state.annotations.list(&GlobalCampaignId::new(platform, id))))
+}
+
+/// POST /campaigns/{platform}/{id}/annotations - Add a dated note
+pub async fn add_annotation(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<Json<Annotation>, ApiError> {
+    let note = request.validate()?;
+    ensure_campaign(&state, &platform, &id).await?;
+
+    let now = Utc::now();
+    let annotation = Annotation {
+        id: uuid::Uuid::new_v4().to_string(),
+        platform,
+        campaign_id: id,
+        date: request.date.unwrap_or_else(timezones::today),
+        note: note.to_string(),
+        author: actor.id,
+        created_at: now,
+    };
+    state.annotations.add(annotation.clone());
+    persist(&state);
+
+    Ok(Json(annotation))
+}
+
+/// DELETE /campaigns/{platform}/{id}/annotations/{annotation_id} - Drop a note
+pub async fn delete_annotation(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id, annotation_id)): Path<(Platform, CampaignId, String)>,
+) -> Result<Json<Annotation>, ApiError> {
+    let campaign = GlobalCampaignId::new(platform, id);
+    let removed = state
+        .annotations
+        .remove(&campaign, &annotation_id)
+        .ok_or_else(|| ApiError::NotFound(format!("annotation {}", annotation_id)))?;
+    persist(&state);
+
+    Ok(Json(removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(id: &str, platform: Platform, day: u32, note: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            platform,
+            campaign_id: "123".into(),
+            date: NaiveDate::from_ymd_opt(2026, 10, day).unwrap(),
+            note: note.to_string(),
+            author: "ana".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_annotations_are_dated_and_survive_a_restart() {
+        let store = AnnotationStore::default();
+        store.add(annotation("b", Platform::Google, 12, "promo started"));
+        store.add(annotation("a", Platform::Google, 3, "creative refreshed"));
+        store.add(annotation("c", Platform::Meta, 5, "same ID, other platform"));
+
+        let google = GlobalCampaignId::new(Platform::Google, CampaignId::from("123"));
+        let notes: Vec<String> = store.list(&google).into_iter().map(|a| a.id).collect();
+        assert_eq!(notes, ["a", "b"]);
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        assert_eq!(store.between(&google, day(4), day(12)).len(), 2);
+
+        let path = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::new_v4()));
+        store.persist(&path).unwrap();
+        let loaded = AnnotationStore::default();
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.list(&google), store.list(&google));
+
+        assert_eq!(loaded.remove(&google, "a").unwrap().note, "creative refreshed");
+        assert!(loaded.remove(&google, "a").is_none());
+
+        let blank = AnnotationRequest { date: None, note: "  ".to_string() };
+        assert!(blank.validate().is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! # Anomaly Detection Example
+//!
+//! Flags sudden changes in a campaign's daily metrics by comparing the
+//! latest complete day against a trailing baseline with a z-score:
+//!
+//! - CTR drops (z-score below `-Z_THRESHOLD`)
+//! - Cost spikes (z-score above `Z_THRESHOLD`)
+//! - Zero-impression days for campaigns that normally deliver
+//!
+//! The current reporting day is still delivering and is left out; shortly
+//! after midnight it would otherwise read as a day without impressions.
+
+use axum::{extract::State, response::Json};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+use super::finite::safe_div;
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::store::DailyMetrics;
+use super::timezones;
+
+/// Number of trailing days used as the baseline
+pub const BASELINE_DAYS: usize = 14;
+
+/// Minimum baseline length before anything is flagged
+pub const MIN_BASELINE_DAYS: usize = 7;
+
+/// Absolute z-score above which a value counts as anomalous
+pub const Z_THRESHOLD: f64 = 3.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AnomalyKind {
+    CtrDrop,
+    CostSpike,
+    ZeroImpressions,
+}
+
+/// Anomalous day detected for a campaign
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Anomaly {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub date: NaiveDate,
+    pub kind: AnomalyKind,
+    /// Observed value on `date`
+    pub value: f64,
+    /// Mean of the baseline window
+    pub baseline: f64,
+    /// `None` for zero-impression days, which are flagged without a score
+    pub z_score: Option<f64>,
+}
+
+/// Mean and population standard deviation
+fn mean_std(values: &[f64]) -> Option<(f64, f64)> {
+    let n = values.len() as f64;
+    let mean = safe_div(values.iter().sum::<f64>(), n)?;
+    let variance = safe_div(values.iter().map(|v| (v - mean).powi(2)).sum::<f64>(), n)?;
+    Some((mean, variance.sqrt()))
+}
+
+/// z-score of `value` against `baseline`, `None` when the baseline is
+/// empty or flat
+fn z_score(value: f64, baseline: &[f64]) -> Option<(f64, f64)> {
+    let (mean, std) = mean_std(baseline)?;
+    safe_div(value - mean, std).map(|z| (z, mean))
+}
+
+/// Detect anomalies on the latest day before `today` of a campaign's daily
+/// series
+pub fn detect_anomalies(
+    campaign: &GlobalCampaignId,
+    series: &[DailyMetrics],
+    today: NaiveDate,
+) -> Vec<Anomaly> {
+    let complete = series.partition_point(|d| d.date < today);
+    let Some((latest, history)) = series[..complete].split_last() else {
+        return Vec::new();
+    };
+
+    let start = history.len().saturating_sub(BASELINE_DAYS);
+    let baseline = &history[start..];
+    if baseline.len() < MIN_BASELINE_DAYS {
+        return Vec::new();
+    }
+
+    let mut anomalies = Vec::new();
+
+    let impressions: Vec<f64> = baseline.iter().map(|d| d.impressions as f64).collect();
+    let (mean_impressions, _) = mean_std(&impressions).unwrap_or_default();
+
+    if latest.impressions == 0 && mean_impressions > 0.0 {
+        anomalies.push(Anomaly {
+            platform: campaign.platform.clone(),
+            campaign_id: campaign.id.clone(),
+            date: latest.date,
+            kind: AnomalyKind::ZeroImpressions,
+            value: 0.0,
+            baseline: mean_impressions,
+            z_score: None,
+        });
+        // CTR and cost are meaningless on a day without delivery
+        return anomalies;
+    }
+
+    let ctr: Vec<f64> = baseline.iter().map(DailyMetrics::ctr).collect();
+    if let Some((z, mean)) = z_score(latest.ctr(), &ctr) {
+        if z < -Z_THRESHOLD {
+            anomalies.push(Anomaly {
+                platform: campaign.platform.clone(),
+                campaign_id: campaign.id.clone(),
+                date: latest.date,
+                kind: AnomalyKind::CtrDrop,
+                value: latest.ctr(),
+                baseline: mean,
+                z_score: Some(z),
+            });
+        }
+    }
+
+    let spend: Vec<f64> = baseline.iter().map(|d| d.spend).collect();
+    if let Some((z, mean)) = z_score(latest.spend, &spend) {
+        if z > Z_THRESHOLD {
+            anomalies.push(Anomaly {
+                platform: campaign.platform.clone(),
+                campaign_id: campaign.id.clone(),
+                date: latest.date,
+                kind: AnomalyKind::CostSpike,
+                value: latest.spend,
+                baseline: mean,
+                z_score: Some(z),
+            });
+        }
+    }
+
+    anomalies
+}
+
+/// GET /campaigns/anomalies - Anomalies on the latest complete synced day
+pub async fn get_anomalies(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Anomaly>>, ApiError> {
+    let today = timezones::today();
+    let anomalies = state
+        .metrics_store
+        .campaign_ids()
+        .iter()
+        .flat_map(|campaign| {
+            detect_anomalies(campaign, &state.metrics_store.daily_series(campaign), today)
+        })
+        .collect();
+
+    Ok(Json(anomalies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn g1() -> GlobalCampaignId {
+        GlobalCampaignId::new(Platform::Google, "g1")
+    }
+
+    fn series(values: &[(u64, u64, f64)]) -> Vec<DailyMetrics> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, (impressions, clicks, spend))| DailyMetrics {
+                date: NaiveDate::from_ymd_opt(2025, 11, i as u32 + 1).unwrap(),
+                impressions: *impressions,
+                clicks: *clicks,
+                conversions: 0,
+                spend: *spend,
+                conversion_value: 0.0,
+            })
+            .collect()
+    }
+
+    /// Day after the last of `days`, as the sync would see it
+    fn day_after(days: &[(u64, u64, f64)]) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, days.len() as u32 + 1).unwrap()
+    }
+
+    fn steady_days() -> Vec<(u64, u64, f64)> {
+        vec![
+            (1000, 50, 100.0),
+            (1000, 52, 98.0),
+            (1000, 48, 102.0),
+            (1000, 51, 101.0),
+            (1000, 49, 99.0),
+            (1000, 50, 100.0),
+            (1000, 50, 100.0),
+        ]
+    }
+
+    #[test]
+    fn test_detects_cost_spike_and_ctr_drop() {
+        let mut days = steady_days();
+        days.push((1000, 10, 400.0));
+
+        let kinds: Vec<AnomalyKind> = detect_anomalies(&g1(), &series(&days), day_after(&days))
+            .into_iter()
+            .map(|a| a.kind)
+            .collect();
+
+        assert_eq!(kinds, vec![AnomalyKind::CtrDrop, AnomalyKind::CostSpike]);
+    }
+
+    #[test]
+    fn test_detects_zero_impression_day() {
+        let mut days = steady_days();
+        days.push((0, 0, 0.0));
+
+        let anomalies = detect_anomalies(&g1(), &series(&days), day_after(&days));
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::ZeroImpressions);
+    }
+
+    #[test]
+    fn test_short_history_is_ignored() {
+        let days = vec![(1000, 50, 100.0), (1000, 5, 900.0)];
+        assert!(detect_anomalies(&g1(), &series(&days), day_after(&days)).is_empty());
+    }
+
+    #[test]
+    fn test_current_partial_day_is_not_compared() {
+        let mut days = steady_days();
+        days.push((1000, 10, 400.0));
+        // Shortly after midnight: today has barely delivered
+        days.push((0, 0, 0.0));
+        let series = series(&days);
+        let today = series.last().unwrap().date;
+
+        let anomalies = detect_anomalies(&g1(), &series, today);
+        let kinds: Vec<AnomalyKind> = anomalies.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, vec![AnomalyKind::CtrDrop, AnomalyKind::CostSpike]);
+        assert!(anomalies.iter().all(|a| a.date < today));
+    }
+}
@@ -0,0 +1,302 @@
+//! # API Usage Analytics Example
+//!
+//! Agencies are billed by how much of the API they use, and a runaway
+//! integration shows up as one tenant's traffic jumping. Every request is
+//! counted against its `X-Tenant-Id`, set by the authenticating proxy from
+//! the caller's API key:
+//!
+//! - requests served, whatever their status;
+//! - bytes served, before compression; streamed bodies are counted as they
+//!   are sent;
+//! - upstream calls, as charged to the request's call budget.
+//!
+//! Requests without a tenant are counted under `-`, probes and warm-up
+//! replays not at all. Counters are kept per day and summed by
+//! `GET /admin/usage?period=month` (or `period=day`). They are flushed next
+//! to the metrics snapshot (`server.snapshot_path`, with a `.usage.json`
+//! extension) on shutdown and loaded again on startup.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Query, Request, State},
+    middleware::Next,
+    response::{Json, Response},
+};
+use chrono::{NaiveDate, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use super::call_budget;
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+use super::warmup::WARMUP_HEADER;
+
+/// Tenant of requests without `X-Tenant-Id`
+pub const NO_TENANT: &str = "-";
+
+/// Days of counters kept, enough to compare a month with the year before
+pub const RETENTION_DAYS: i64 = 400;
+
+/// Paths never counted: the orchestrator's probes
+const UNCOUNTED_PREFIXES: &[&str] = &["/healthz", "/readyz"];
+
+/// Usage of one tenant over a day or a month
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct UsageCounters {
+    pub requests: u64,
+    pub bytes_served: u64,
+    pub upstream_calls: u64,
+}
+
+impl UsageCounters {
+    fn add(&mut self, other: &UsageCounters) {
+        self.requests += other.requests;
+        self.bytes_served += other.bytes_served;
+        self.upstream_calls += other.upstream_calls;
+    }
+}
+
+/// Counters of each tenant by day
+pub type DailyUsage = BTreeMap<NaiveDate, HashMap<String, UsageCounters>>;
+
+/// Per-tenant counters of the API's own traffic
+#[derive(Default)]
+pub struct ApiUsage {
+    days: RwLock<DailyUsage>,
+}
+
+impl ApiUsage {
+    /// Add `counters` to `tenant`'s usage on `date`
+    pub fn record(&self, tenant: &str, date: NaiveDate, counters: UsageCounters) {
+        let mut days = self.days.write().unwrap();
+        if !days.contains_key(&date) {
+            let cutoff = date - chrono::Duration::days(RETENTION_DAYS);
+            days.retain(|day, _| *day > cutoff);
+        }
+        days.entry(date)
+            .or_default()
+            .entry(tenant.to_string())
+            .or_default()
+            .add(&counters);
+    }
+
+    /// Counters by tenant and period, latest period first, busiest first
+    pub fn report(&self, query: &UsageQuery) -> Vec<TenantUsage> {
+        let mut periods: BTreeMap<(String, String), UsageCounters> = BTreeMap::new();
+        for (date, tenants) in self.days.read().unwrap().iter() {
+            for (tenant, counters) in tenants {
+                if query.tenant.iter().all(|t| t == tenant) {
+                    let period = query.period.label(*date);
+                    periods.entry((period, tenant.clone())).or_default().add(counters);
+                }
+            }
+        }
+
+        let mut rows: Vec<TenantUsage> = periods
+            .into_iter()
+            .map(|((period, tenant), counters)| TenantUsage {
+                tenant,
+                period,
+                counters,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.period
+                .cmp(&a.period)
+                .then_with(|| b.counters.requests.cmp(&a.counters.requests))
+                .then_with(|| a.tenant.cmp(&b.tenant))
+        });
+        rows
+    }
+
+    /// Write the counters to `path` atomically (write to temp file, then rename)
+    pub fn persist(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec(&*self.days.read().unwrap())?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Add counters previously written by `persist`
+    pub fn load(&self, path: &Path) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let loaded: DailyUsage = serde_json::from_slice(&bytes)?;
+
+        let mut days = self.days.write().unwrap();
+        for (date, tenants) in loaded {
+            let day = days.entry(date).or_default();
+            for (tenant, counters) in tenants {
+                day.entry(tenant).or_default().add(&counters);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// File the counters are flushed to, next to the metrics snapshot
+pub fn usage_path(snapshot_path: &Path) -> PathBuf {
+    snapshot_path.with_extension("usage.json")
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsagePeriod {
+    Day,
+    #[default]
+    Month,
+}
+
+impl UsagePeriod {
+    /// `2026-10` for a month, `2026-10-15` for a day
+    fn label(self, date: NaiveDate) -> String {
+        match self {
+            UsagePeriod::Day => date.format("%Y-%m-%d").to_string(),
+            UsagePeriod::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Query parameters accepted by `GET /admin/usage`
+#[derive(Debug, Deserialize, Default)]
+pub struct UsageQuery {
+    #[serde(default)]
+    pub period: UsagePeriod,
+    pub tenant: Option<String>,
+}
+
+/// Usage of one tenant over one period
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TenantUsage {
+    pub tenant: String,
+    pub period: String,
+    #[serde(flatten)]
+    pub counters: UsageCounters,
+}
+
+/// Middleware counting requests, bytes and upstream calls per tenant
+///
+/// Sits inside the call budget middleware, which scopes the budget the
+/// upstream calls are read from, and outside the layers rewriting bodies.
+pub async fn record_api_usage(
+    State(usage): State<Arc<ApiUsage>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path();
+    if UNCOUNTED_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+        || req.headers().contains_key(WARMUP_HEADER)
+    {
+        return next.run(req).await;
+    }
+    let tenant = req
+        .headers()
+        .get("x-tenant-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(NO_TENANT)
+        .to_string();
+
+    let response = next.run(req).await;
+    let today = Utc::now().date_naive();
+    let upstream_calls = call_budget::current().map_or(0, |budget| budget.used()) as u64;
+
+    let (parts, body) = response.into_parts();
+    let size = body.size_hint().exact();
+    let counters = UsageCounters {
+        requests: 1,
+        bytes_served: size.unwrap_or(0),
+        upstream_calls,
+    };
+    usage.record(&tenant, today, counters);
+    if size.is_some() {
+        return Response::from_parts(parts, body);
+    }
+
+    // Streamed: count each chunk as it is sent
+    let body = body.into_data_stream().inspect_ok(move |chunk| {
+        let sent = UsageCounters {
+            bytes_served: chunk.len() as u64,
+            ..UsageCounters::default()
+        };
+        usage.record(&tenant, Utc::now().date_naive(), sent);
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// GET /admin/usage - Requests, bytes and upstream calls per tenant
+pub async fn get_api_usage(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<Vec<TenantUsage>>, ApiError> {
+    Ok(Json(state.api_usage.report(&query)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn day(d: &str) -> NaiveDate {
+        d.parse().unwrap()
+    }
+
+    #[test]
+    fn test_days_are_summed_by_month() {
+        let usage = ApiUsage::default();
+        let counters = |requests, upstream_calls| UsageCounters {
+            requests,
+            bytes_served: 100 * requests,
+            upstream_calls,
+        };
+        usage.record("acme", day("2026-09-30"), counters(1, 2));
+        usage.record("acme", day("2026-10-01"), counters(2, 0));
+        usage.record("acme", day("2026-10-15"), counters(3, 4));
+        usage.record("globex", day("2026-10-15"), counters(9, 9));
+
+        let rows = usage.report(&UsageQuery::default());
+        let rows: Vec<(&str, &str, u64)> = rows
+            .iter()
+            .map(|r| (r.period.as_str(), r.tenant.as_str(), r.counters.requests))
+            .collect();
+        assert_eq!(
+            rows,
+            [("2026-10", "globex", 9), ("2026-10", "acme", 5), ("2026-09", "acme", 1)]
+        );
+
+        let query = UsageQuery {
+            period: UsagePeriod::Day,
+            tenant: Some("acme".to_string()),
+        };
+        let rows = usage.report(&query);
+        assert_eq!(rows[0].period, "2026-10-15");
+        assert_eq!(rows[0].counters, counters(3, 4));
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_requests_and_bytes_are_counted_per_tenant() {
+        let usage = Arc::new(ApiUsage::default());
+        let router = Router::new()
+            .route("/campaigns", get(|| async { "0123456789" }))
+            .route("/healthz", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(usage.clone(), record_api_usage));
+
+        for (path, tenant) in [("/campaigns", "acme"), ("/campaigns", ""), ("/healthz", "acme")] {
+            let request = Request::get(path).header("x-tenant-id", tenant);
+            router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        }
+
+        let rows = usage.report(&UsageQuery::default());
+        let tenants: Vec<(&str, u64, u64)> = rows
+            .iter()
+            .map(|r| (r.tenant.as_str(), r.counters.requests, r.counters.bytes_served))
+            .collect();
+        assert_eq!(tenants, [("-", 1, 10), ("acme", 1, 10)]);
+    }
+}
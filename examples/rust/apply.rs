@@ -0,0 +1,661 @@
+//! # Declarative Apply Example
+//!
+//! Turns the diff between a desired account spec and live state into a
+//! plan of platform mutations and applies it through the connectors.
+//! Steps run one at a time in plan order; when one fails, every step that
+//! already succeeded is undone in reverse order, so a partially applied
+//! spec does not leave the account half-migrated.
+//!
+//! Campaigns missing from the spec are paused, never deleted. Currencies
+//! cannot be changed on any platform, so plans that would need it are
+//! rejected up front, as are plans touching a campaign under an active
+//! change freeze or raising spend over the tenant's spend cap. Status
+//! and budget changes, including those made by the undo, are recorded in
+//! the audit log.
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::audit::{AuditChange, AuditEntry, AuditLog};
+use super::call_budget;
+use super::campaign_aggregator::{aggregate_campaigns, AppState, CampaignStatus, Platform};
+use super::connectors::ConnectorRegistry;
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{AdGroupId, CampaignId, GlobalCampaignId};
+use super::spec::{
+    diff_specs, live_spec, parse_spec, AccountSpec, AdGroupSpec, CampaignSpec, FieldChange,
+    SpecChange, SpecQuery,
+};
+use super::spend_caps::SpendChange;
+
+/// Single platform mutation of a plan
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Mutation {
+    CreateCampaign {
+        campaign: CampaignSpec,
+    },
+    CreateAdGroup {
+        platform: Platform,
+        campaign_id: CampaignId,
+        ad_group: AdGroupSpec,
+    },
+    SetStatus {
+        platform: Platform,
+        campaign_id: CampaignId,
+        status: CampaignStatus,
+    },
+    SetBudget {
+        platform: Platform,
+        campaign_id: CampaignId,
+        daily_budget: f64,
+    },
+    Rename {
+        platform: Platform,
+        campaign_id: CampaignId,
+        name: String,
+    },
+    UpdateAdGroup {
+        platform: Platform,
+        campaign_id: CampaignId,
+        ad_group: AdGroupSpec,
+    },
+}
+
+impl Mutation {
+    pub fn platform(&self) -> &Platform {
+        match self {
+            Mutation::CreateCampaign { campaign } => &campaign.platform,
+            Mutation::CreateAdGroup { platform, .. }
+            | Mutation::SetStatus { platform, .. }
+            | Mutation::SetBudget { platform, .. }
+            | Mutation::Rename { platform, .. }
+            | Mutation::UpdateAdGroup { platform, .. } => platform,
+        }
+    }
+
+    /// Campaign changed by the mutation; `None` for campaigns not created yet
+    pub fn campaign_id(&self) -> Option<&CampaignId> {
+        match self {
+            Mutation::CreateCampaign { .. } => None,
+            Mutation::CreateAdGroup { campaign_id, .. }
+            | Mutation::UpdateAdGroup { campaign_id, .. }
+            | Mutation::SetStatus { campaign_id, .. }
+            | Mutation::SetBudget { campaign_id, .. }
+            | Mutation::Rename { campaign_id, .. } => Some(campaign_id),
+        }
+    }
+
+    /// Short description for audit records, e.g. `set_budget google/123`
+    pub fn target(&self) -> String {
+        let kind = match self {
+            Mutation::CreateCampaign { .. } => "create_campaign",
+            Mutation::CreateAdGroup { .. } => "create_ad_group",
+            Mutation::SetStatus { .. } => "set_status",
+            Mutation::SetBudget { .. } => "set_budget",
+            Mutation::Rename { .. } => "rename",
+            Mutation::UpdateAdGroup { .. } => "update_ad_group",
+        };
+        let id = match self {
+            Mutation::CreateCampaign { campaign } => campaign.name.as_str(),
+            Mutation::UpdateAdGroup { ad_group, .. } => ad_group.id.as_deref().unwrap_or_default(),
+            _ => self.campaign_id().map(CampaignId::as_str).unwrap_or_default(),
+        };
+        format!("{} {}/{}", kind, self.platform().as_str(), id)
+    }
+
+    /// Audit record of a status or budget change; `restore` is the mutation
+    /// bringing back the previous value, when known
+    pub fn audit_change(&self, restore: Option<&Mutation>) -> Option<AuditChange> {
+        match self {
+            Mutation::SetStatus { status, .. } => Some(AuditChange::Status {
+                before: match restore {
+                    Some(Mutation::SetStatus { status, .. }) => Some(status.clone()),
+                    _ => None,
+                },
+                after: status.clone(),
+            }),
+            Mutation::SetBudget { daily_budget, .. } => Some(AuditChange::DailyBudget {
+                before: match restore {
+                    Some(Mutation::SetBudget { daily_budget, .. }) => Some(*daily_budget),
+                    _ => None,
+                },
+                after: *daily_budget,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Undo of a create, once the platform has assigned the new ID
+    fn undo_create(&self, created_id: String) -> Option<Mutation> {
+        match self {
+            Mutation::CreateCampaign { campaign } => Some(Mutation::SetStatus {
+                platform: campaign.platform.clone(),
+                campaign_id: created_id.into(),
+                status: CampaignStatus::Removed,
+            }),
+            Mutation::CreateAdGroup {
+                platform,
+                campaign_id,
+                ad_group,
+            } => Some(Mutation::UpdateAdGroup {
+                platform: platform.clone(),
+                campaign_id: campaign_id.clone(),
+                ad_group: AdGroupSpec {
+                    id: Some(created_id.into()),
+                    status: CampaignStatus::Removed,
+                    ..ad_group.clone()
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Mutation together with the mutation that reverts it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlanStep {
+    pub mutation: Mutation,
+    /// Known up front for updates; creates are undone by removing what
+    /// they created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo: Option<Mutation>,
+}
+
+/// `POST /accounts/spec/plan` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Plan {
+    /// Diff the plan was built from
+    pub changes: Vec<SpecChange>,
+    pub steps: Vec<PlanStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyOutcome {
+    Applied,
+    RolledBack,
+    /// Some undo steps failed too; the account needs manual attention
+    RollbackFailed,
+}
+
+/// Step that failed during apply or rollback
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StepFailure {
+    pub mutation: Mutation,
+    pub error_code: String,
+    pub error: String,
+}
+
+/// `POST /accounts/spec/apply` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApplyReport {
+    pub outcome: ApplyOutcome,
+    pub plan: Plan,
+    /// Steps applied before the failure, in order
+    pub applied: Vec<Mutation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<StepFailure>,
+    /// Undo steps that failed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rollback_failures: Vec<StepFailure>,
+}
+
+fn field_value<T: serde::de::DeserializeOwned>(value: &Value, field: &str) -> Result<T, ApiError> {
+    serde_json::from_value(value.clone())
+        .map_err(|e| ApiError::Validation(format!("invalid value for {}: {}", field, e)))
+}
+
+fn campaign_step(
+    platform: &Platform,
+    campaign_id: &CampaignId,
+    change: &FieldChange,
+) -> Result<PlanStep, ApiError> {
+    let step = |from: Mutation, to: Mutation| PlanStep {
+        mutation: to,
+        undo: Some(from),
+    };
+    let platform = platform.clone();
+    let campaign_id = campaign_id.clone();
+
+    match change.field.as_str() {
+        "status" => Ok(step(
+            Mutation::SetStatus {
+                platform: platform.clone(),
+                campaign_id: campaign_id.clone(),
+                status: field_value(&change.from, "status")?,
+            },
+            Mutation::SetStatus {
+                platform,
+                campaign_id,
+                status: field_value(&change.to, "status")?,
+            },
+        )),
+        "daily_budget" => Ok(step(
+            Mutation::SetBudget {
+                platform: platform.clone(),
+                campaign_id: campaign_id.clone(),
+                daily_budget: field_value(&change.from, "daily_budget")?,
+            },
+            Mutation::SetBudget {
+                platform,
+                campaign_id,
+                daily_budget: field_value(&change.to, "daily_budget")?,
+            },
+        )),
+        "name" => Ok(step(
+            Mutation::Rename {
+                platform: platform.clone(),
+                campaign_id: campaign_id.clone(),
+                name: field_value(&change.from, "name")?,
+            },
+            Mutation::Rename {
+                platform,
+                campaign_id,
+                name: field_value(&change.to, "name")?,
+            },
+        )),
+        "currency" => Err(ApiError::Validation(format!(
+            "currency of campaign {} cannot be changed; create a new campaign instead",
+            campaign_id
+        ))),
+        other => Err(ApiError::Validation(format!("field {} cannot be applied", other))),
+    }
+}
+
+fn live_ad_group<'a>(live: &'a AccountSpec, id: &AdGroupId) -> Option<&'a AdGroupSpec> {
+    live.campaigns
+        .iter()
+        .flat_map(|c| &c.ad_groups)
+        .find(|g| g.id.as_ref() == Some(id))
+}
+
+/// Plan of mutations turning `live` into `desired`
+pub fn build_plan(live: &AccountSpec, desired: &AccountSpec) -> Result<Plan, ApiError> {
+    let changes = diff_specs(live, desired);
+    let mut steps = Vec::new();
+
+    for change in &changes {
+        match change {
+            SpecChange::CreateCampaign { campaign } => steps.push(PlanStep {
+                mutation: Mutation::CreateCampaign {
+                    campaign: campaign.clone(),
+                },
+                undo: None,
+            }),
+            SpecChange::UpdateCampaign {
+                platform,
+                id,
+                changes,
+                ..
+            } => {
+                for field in changes {
+                    steps.push(campaign_step(platform, id, field)?);
+                }
+            }
+            SpecChange::RemoveCampaign { platform, id, .. } => {
+                let current = live
+                    .campaigns
+                    .iter()
+                    .find(|c| &c.platform == platform && c.id.as_ref() == Some(id))
+                    .map(|c| c.status.clone())
+                    .unwrap_or(CampaignStatus::Enabled);
+                if current == CampaignStatus::Paused {
+                    continue;
+                }
+
+                steps.push(PlanStep {
+                    mutation: Mutation::SetStatus {
+                        platform: platform.clone(),
+                        campaign_id: id.clone(),
+                        status: CampaignStatus::Paused,
+                    },
+                    undo: Some(Mutation::SetStatus {
+                        platform: platform.clone(),
+                        campaign_id: id.clone(),
+                        status: current,
+                    }),
+                });
+            }
+            SpecChange::CreateAdGroup {
+                platform,
+                campaign_id,
+                ad_group,
+            } => steps.push(PlanStep {
+                mutation: Mutation::CreateAdGroup {
+                    platform: platform.clone(),
+                    campaign_id: campaign_id.clone(),
+                    ad_group: ad_group.clone(),
+                },
+                undo: None,
+            }),
+            SpecChange::UpdateAdGroup {
+                platform,
+                campaign_id,
+                id,
+                ..
+            } => {
+                let current = live_ad_group(live, id)
+                    .ok_or_else(|| ApiError::NotFound(format!("ad group {}", id)))?;
+                let wanted = desired
+                    .campaigns
+                    .iter()
+                    .flat_map(|c| &c.ad_groups)
+                    .find(|g| match &g.id {
+                        Some(wanted_id) => wanted_id == id,
+                        None => g.name == current.name,
+                    })
+                    .ok_or_else(|| ApiError::NotFound(format!("ad group {}", id)))?;
+
+                steps.push(PlanStep {
+                    mutation: Mutation::UpdateAdGroup {
+                        platform: platform.clone(),
+                        campaign_id: campaign_id.clone(),
+                        ad_group: AdGroupSpec {
+                            id: Some(id.clone()),
+                            ..wanted.clone()
+                        },
+                    },
+                    undo: Some(Mutation::UpdateAdGroup {
+                        platform: platform.clone(),
+                        campaign_id: campaign_id.clone(),
+                        ad_group: current.clone(),
+                    }),
+                });
+            }
+        }
+    }
+
+    Ok(Plan { changes, steps })
+}
+
+/// Run one mutation, returning the ID of whatever it created
+pub async fn execute_mutation(
+    registry: &ConnectorRegistry,
+    mutation: &Mutation,
+) -> Result<Option<String>, ApiError> {
+    let connector = registry
+        .get(mutation.platform())
+        .ok_or_else(|| ApiError::NotSupported(mutation.platform().as_str().to_string()))?;
+
+    match mutation {
+        Mutation::CreateCampaign { campaign } => {
+            return Ok(Some(connector.create_campaign(campaign).await?.into()));
+        }
+        Mutation::CreateAdGroup {
+            campaign_id,
+            ad_group,
+            ..
+        } => return Ok(Some(connector.create_ad_group(campaign_id, ad_group).await?.into())),
+        Mutation::SetStatus {
+            campaign_id,
+            status,
+            ..
+        } => connector.mutate_status(campaign_id, status.clone()).await?,
+        Mutation::SetBudget {
+            campaign_id,
+            daily_budget,
+            ..
+        } => connector.mutate_budget(campaign_id, *daily_budget).await?,
+        Mutation::Rename {
+            campaign_id, name, ..
+        } => connector.rename_campaign(campaign_id, name).await?,
+        Mutation::UpdateAdGroup { ad_group, .. } => connector.update_ad_group(ad_group).await?,
+    }
+
+    Ok(None)
+}
+
+/// Run one mutation, auditing it when it changes a status or budget
+async fn execute_audited(
+    registry: &ConnectorRegistry,
+    mutation: &Mutation,
+    restore: Option<&Mutation>,
+    actor: &Actor,
+    audit: &AuditLog,
+) -> Result<Option<String>, ApiError> {
+    let result = execute_mutation(registry, mutation).await;
+    if let (Some(change), Some(campaign_id)) =
+        (mutation.audit_change(restore), mutation.campaign_id())
+    {
+        audit.record(AuditEntry::new(actor, mutation.platform(), campaign_id, change, &result));
+    }
+    result
+}
+
+fn failure(mutation: &Mutation, error: &ApiError) -> StepFailure {
+    StepFailure {
+        mutation: mutation.clone(),
+        error_code: error.code().to_string(),
+        error: error.to_string(),
+    }
+}
+
+/// Apply every step in order, undoing the applied ones if a step fails
+pub async fn apply_plan(
+    registry: &ConnectorRegistry,
+    plan: Plan,
+    actor: &Actor,
+    audit: &AuditLog,
+) -> ApplyReport {
+    let mut applied = Vec::new();
+    // Undo mutations, each with the step it undoes
+    let mut undo_stack = Vec::new();
+    let mut failed = None;
+
+    for step in &plan.steps {
+        match execute_audited(registry, &step.mutation, step.undo.as_ref(), actor, audit).await {
+            Ok(created) => {
+                applied.push(step.mutation.clone());
+                let undo = match created {
+                    Some(id) => step.mutation.undo_create(id),
+                    None => step.undo.clone(),
+                };
+                undo_stack.extend(undo.map(|undo| (undo, step.mutation.clone())));
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "spec apply step failed, rolling back");
+                failed = Some(failure(&step.mutation, &e));
+                break;
+            }
+        }
+    }
+
+    let Some(failed) = failed else {
+        return ApplyReport {
+            outcome: ApplyOutcome::Applied,
+            plan,
+            applied,
+            failure: None,
+            rollback_failures: Vec::new(),
+        };
+    };
+
+    let mut rollback_failures = Vec::new();
+    for (undo, step) in undo_stack.iter().rev() {
+        if let Err(e) = execute_audited(registry, undo, Some(step), actor, audit).await {
+            tracing::error!(error = %e, "spec rollback step failed");
+            rollback_failures.push(failure(undo, &e));
+        }
+    }
+
+    ApplyReport {
+        outcome: if rollback_failures.is_empty() {
+            ApplyOutcome::RolledBack
+        } else {
+            ApplyOutcome::RollbackFailed
+        },
+        plan,
+        applied,
+        failure: Some(failed),
+        rollback_failures,
+    }
+}
+
+/// POST /accounts/spec/plan - Mutations needed to reach the posted spec
+pub async fn plan_spec(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpecQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Plan>, ApiError> {
+    let desired = parse_spec(&headers, &body)?;
+    let live = live_spec(&state, &query).await?;
+
+    Ok(Json(build_plan(&live, &desired)?))
+}
+
+/// POST /accounts/spec/apply - Apply the posted spec, all or nothing
+///
+/// Every step is checked against change freezes and the spend cap before
+/// anything runs, so a frozen campaign or a step over the cap blocks the
+/// whole apply.
+pub async fn apply_spec(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpecQuery>,
+    actor: Actor,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ApplyReport>, ApiError> {
+    let desired = parse_spec(&headers, &body)?;
+    let live = live_spec(&state, &query).await?;
+    let plan = build_plan(&live, &desired)?;
+
+    let campaigns = aggregate_campaigns(&state).await;
+    let labels: HashMap<GlobalCampaignId, &[String]> = campaigns
+        .iter()
+        .map(|c| (c.global_id(), c.labels.as_slice()))
+        .collect();
+    let now = Utc::now();
+    let mut spend_cap = state.spend_caps.guard(&actor, &campaigns, now);
+    for step in &plan.steps {
+        let mutation = &step.mutation;
+        let target = mutation.target();
+        let id = mutation
+            .campaign_id()
+            .map(|id| GlobalCampaignId::new(mutation.platform().clone(), id.clone()));
+        let campaign_labels =
+            id.as_ref().and_then(|id| labels.get(id)).copied().unwrap_or_default();
+        state.freezes.check(&actor, campaign_labels, &target, now)?;
+
+        match (mutation, &id) {
+            (Mutation::CreateCampaign { campaign }, _) => spend_cap.check_new(
+                desired.account_id.as_ref(),
+                &campaign.status,
+                campaign.daily_budget,
+                &target,
+            )?,
+            (Mutation::SetStatus { status, .. }, Some(id)) => {
+                spend_cap.check(id, SpendChange::Status(status.clone()), &target)?
+            }
+            (Mutation::SetBudget { daily_budget, .. }, Some(id)) => {
+                spend_cap.check(id, SpendChange::DailyBudget(*daily_budget), &target)?
+            }
+            _ => {}
+        }
+    }
+
+    // Worst case every step is undone as well
+    call_budget::charge(plan.steps.len() * 2)?;
+
+    Ok(Json(apply_plan(&state.connectors, plan, &actor, &state.audit).await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Campaign;
+    use super::super::connectors::MockConnector;
+    use super::super::spec::SPEC_API_VERSION;
+
+    fn account(daily_budget: f64, ad_group_status: CampaignStatus) -> AccountSpec {
+        AccountSpec {
+            api_version: SPEC_API_VERSION.to_string(),
+            account_id: None,
+            campaigns: vec![CampaignSpec {
+                platform: Platform::Google,
+                id: Some("g1".into()),
+                name: "Brand".to_string(),
+                status: CampaignStatus::Enabled,
+                daily_budget,
+                currency: "BRL".to_string(),
+                ad_groups: vec![AdGroupSpec {
+                    id: Some("ag1".into()),
+                    name: "Brand terms".to_string(),
+                    status: ad_group_status,
+                    audiences: Vec::new(),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_plan_records_undo_for_updates() {
+        let live = account(100.0, CampaignStatus::Enabled);
+        let desired = account(150.0, CampaignStatus::Enabled);
+
+        let plan = build_plan(&live, &desired).unwrap();
+
+        assert_eq!(plan.steps.len(), 1);
+        assert!(matches!(
+            plan.steps[0].undo,
+            Some(Mutation::SetBudget { daily_budget, .. }) if daily_budget == 100.0
+        ));
+    }
+
+    #[test]
+    fn test_currency_changes_are_rejected() {
+        let live = account(100.0, CampaignStatus::Enabled);
+        let mut desired = live.clone();
+        desired.campaigns[0].currency = "USD".to_string();
+
+        assert!(build_plan(&live, &desired).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_partial_failure_rolls_back_applied_steps() {
+        let mut registry = ConnectorRegistry::default();
+        registry.register(Arc::new(MockConnector {
+            platform: Platform::Google,
+            campaigns: vec![Campaign::sample("g1", Platform::Google)],
+            fail: false,
+        }));
+
+        // The budget change succeeds; the mock cannot update ad groups
+        let live = account(100.0, CampaignStatus::Enabled);
+        let desired = account(150.0, CampaignStatus::Paused);
+        let plan = build_plan(&live, &desired).unwrap();
+
+        let audit = AuditLog::default();
+        let report = apply_plan(&registry, plan, &Actor::automation("test", None), &audit).await;
+
+        assert_eq!(report.outcome, ApplyOutcome::RolledBack);
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.failure.unwrap().error_code, "not_supported");
+        assert!(report.rollback_failures.is_empty());
+
+        // The budget change and its undo, most recent first
+        let budgets: Vec<_> = audit
+            .query(&Default::default())
+            .into_iter()
+            .map(|e| e.change)
+            .collect();
+        assert_eq!(
+            budgets,
+            vec![
+                AuditChange::DailyBudget { before: Some(150.0), after: 100.0 },
+                AuditChange::DailyBudget { before: Some(100.0), after: 150.0 },
+            ]
+        );
+    }
+}
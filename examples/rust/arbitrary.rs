@@ -0,0 +1,451 @@
+//! # Property Tests
+//!
+//! `proptest` strategies generating unified campaigns and metrics, and the
+//! properties normalization must keep whatever the platforms send:
+//!
+//! - campaigns survive a serde round trip, unknown enum values included;
+//! - recomputed rates follow their definitions (CTR is clicks per
+//!   impression, in percent) and stay finite, without impressions,
+//!   conversions or spend too;
+//! - the normalization tables never panic, map what they cover, and
+//!   applying them twice changes nothing;
+//! - a malformed element never fails its page;
+//! - the LinkedIn and Microsoft connectors, which build campaigns
+//!   themselves, keep the same invariants on edge values.
+//!
+//! Strategies lean towards the values that break code: zero counts,
+//! budgets up to `f64::MAX`, counters at their maximum and strings no enum
+//! variant knows. Needs `proptest` as a dev-dependency.
+
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+use super::campaign_aggregator::{
+    recompute_rates, Campaign, CampaignMetrics, CampaignObjective, CampaignStatus, CampaignType,
+    ConversionActionMetrics, Platform,
+};
+use super::lenient::{self, Lenient};
+use super::normalization::{builtin_tables, NormalizationRules};
+use super::pagination::{GatewayPage, Page};
+
+/// Values deserializing as a known variant of a unified enum, aliases
+/// included; generated unknown values avoid them
+const KNOWN_VALUES: &[&str] = &[
+    "ENABLED",
+    "PAUSED",
+    "REMOVED",
+    "AWARENESS",
+    "TRAFFIC",
+    "ENGAGEMENT",
+    "LEADS",
+    "LEAD_GEN",
+    "APP_PROMOTION",
+    "APP_INSTALLS",
+    "SALES",
+    "CONVERSIONS",
+    "SEARCH",
+    "DISPLAY",
+    "VIDEO",
+    "SHOPPING",
+    "ADVANTAGE_PLUS",
+];
+
+fn platforms() -> Vec<Platform> {
+    #[allow(unused_mut)]
+    let mut platforms = vec![Platform::Google, Platform::Meta];
+    #[cfg(feature = "linkedin")]
+    platforms.push(Platform::LinkedIn);
+    #[cfg(feature = "microsoft")]
+    platforms.push(Platform::Microsoft);
+    platforms
+}
+
+pub fn arb_platform() -> impl Strategy<Value = Platform> {
+    prop::sample::select(platforms())
+}
+
+/// Platform enum value no unified variant knows; mostly platform-like
+/// (`OUTCOME_LEADS`), sometimes anything
+pub fn arb_unknown_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[A-Z][A-Z_]{0,23}",
+        1 => "\\PC{0,16}",
+    ]
+    .prop_filter("known values deserialize as variants", |v| !KNOWN_VALUES.contains(&v.as_str()))
+}
+
+/// Platform values the built-in tables map, mixed with unknown ones
+fn arb_platform_value() -> impl Strategy<Value = String> {
+    let mapped: Vec<String> = builtin_tables()
+        .values()
+        .flat_map(|t| t.objective.keys().chain(t.campaign_type.keys()).chain(t.status.keys()))
+        .filter(|v| !KNOWN_VALUES.contains(&v.as_str()))
+        .cloned()
+        .collect();
+    prop_oneof![prop::sample::select(mapped), arb_unknown_value()]
+}
+
+pub fn arb_status() -> impl Strategy<Value = CampaignStatus> {
+    prop_oneof![
+        Just(CampaignStatus::Enabled),
+        Just(CampaignStatus::Paused),
+        Just(CampaignStatus::Removed),
+        arb_platform_value().prop_map(CampaignStatus::Other),
+    ]
+}
+
+pub fn arb_objective() -> impl Strategy<Value = Option<CampaignObjective>> {
+    prop::option::of(prop_oneof![
+        Just(CampaignObjective::Awareness),
+        Just(CampaignObjective::Traffic),
+        Just(CampaignObjective::Engagement),
+        Just(CampaignObjective::Leads),
+        Just(CampaignObjective::AppPromotion),
+        Just(CampaignObjective::Sales),
+        arb_platform_value().prop_map(CampaignObjective::Other),
+    ])
+}
+
+pub fn arb_campaign_type() -> impl Strategy<Value = Option<CampaignType>> {
+    prop::option::of(prop_oneof![
+        Just(CampaignType::Search),
+        Just(CampaignType::Display),
+        Just(CampaignType::Video),
+        Just(CampaignType::Shopping),
+        arb_platform_value().prop_map(CampaignType::Other),
+    ])
+}
+
+/// Money amount: zero, everyday values, and budgets far past any real one
+pub fn arb_amount() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        1 => Just(0.0),
+        6 => 0.0..100_000.0f64,
+        1 => Just(1e12),
+        1 => Just(f64::MAX),
+        1 => Just(f64::MIN_POSITIVE),
+    ]
+}
+
+/// Impressions with at most as many clicks, zero and maximal counts included
+fn arb_counts() -> impl Strategy<Value = (u64, u64)> {
+    prop_oneof![
+        1 => Just(0u64),
+        6 => 1..10_000_000u64,
+        1 => Just(u64::MAX),
+    ]
+    .prop_flat_map(|impressions| (Just(impressions), 0..=impressions))
+}
+
+fn arb_conversions() -> impl Strategy<Value = u32> {
+    prop_oneof![1 => Just(0u32), 6 => 1..10_000u32, 1 => Just(u32::MAX)]
+}
+
+fn arb_conversion_action() -> impl Strategy<Value = ConversionActionMetrics> {
+    ("[a-z_]{1,16}", 0.0..1_000.0f64, arb_amount()).prop_map(|(action, conversions, value)| {
+        ConversionActionMetrics { action, conversions, value }
+    })
+}
+
+/// Metrics as a platform may report them; rates are arbitrary, see
+/// `recompute_rates` for consistent ones
+pub fn arb_metrics() -> impl Strategy<Value = CampaignMetrics> {
+    let counts = (arb_counts(), arb_conversions(), arb_amount(), arb_amount());
+    let rates = (0.0..100.0f64, arb_amount(), 0.0..50.0f64);
+    let optional = (
+        prop::collection::vec(arb_conversion_action(), 0..3),
+        prop::option::of(0..10_000_000u64),
+        prop::option::of(0.0..50.0f64),
+        prop::option::of(0..10_000_000u64),
+        prop::option::of(0.0..100.0f64),
+        prop::option::of(0.0..600.0f64),
+    );
+    (counts, rates, optional).prop_map(
+        |(
+            ((impressions, clicks), conversions, cost, conversion_value),
+            (ctr, cpa, roas),
+            (conversion_actions, reach, frequency, video_views, video_view_rate, watch_time),
+        )| CampaignMetrics {
+            impressions,
+            clicks,
+            conversions,
+            cost,
+            ctr,
+            cpa,
+            conversion_value,
+            roas,
+            conversion_actions,
+            reach,
+            frequency,
+            video_views,
+            video_view_rate,
+            average_watch_time_seconds: watch_time,
+        },
+    )
+}
+
+pub fn arb_campaign() -> impl Strategy<Value = Campaign> {
+    let identity = (arb_platform(), "[a-z0-9-]{1,20}", "\\PC{0,40}", "[A-Z]{3}");
+    let delivery = (arb_status(), arb_objective(), arb_campaign_type(), arb_amount());
+    let extra = (
+        prop::option::of("\\PC{0,60}"),
+        prop::option::of("[0-9]{3}-[0-9]{3}-[0-9]{4}|act_[0-9]{1,12}"),
+        prop::collection::vec("[a-z-]{1,12}", 0..3),
+        prop::collection::hash_map("[a-z_]{1,8}", "\\PC{0,12}", 0..3),
+    );
+    (identity, delivery, arb_metrics(), extra).prop_map(
+        |(
+            (platform, id, name, currency),
+            (status, objective, campaign_type, daily_budget),
+            metrics,
+            (tracking_template, account_id, labels, tags),
+        )| Campaign {
+            id: id.into(),
+            platform,
+            name,
+            status,
+            objective,
+            campaign_type,
+            daily_budget,
+            currency,
+            metrics,
+            pacing: None,
+            targeting: None,
+            bidding: None,
+            quality: None,
+            auction: None,
+            tracking_template,
+            account_id: account_id.map(Into::into),
+            labels,
+            tags: tags.into_iter().collect::<HashMap<_, _>>(),
+        },
+    )
+}
+
+/// `a` and `b` equal up to float rounding
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+proptest! {
+    #[test]
+    fn test_campaigns_survive_a_serde_round_trip(campaign in arb_campaign()) {
+        let value = serde_json::to_value(&campaign).unwrap();
+        let decoded: Campaign = serde_json::from_value(value.clone()).unwrap();
+        prop_assert_eq!(serde_json::to_value(&decoded).unwrap(), value);
+    }
+
+    #[test]
+    fn test_recomputed_rates_follow_their_definitions(mut metrics in arb_metrics()) {
+        recompute_rates(&mut metrics);
+
+        if metrics.impressions > 0 {
+            let ctr = metrics.clicks as f64 / metrics.impressions as f64 * 100.0;
+            prop_assert!(close(metrics.ctr, ctr));
+            prop_assert!((0.0..=100.0 + 1e-9).contains(&metrics.ctr));
+        }
+        if metrics.conversions > 0 {
+            prop_assert!(close(metrics.cpa, metrics.cost / metrics.conversions as f64));
+        }
+        prop_assert!(metrics.ctr.is_finite() && metrics.cpa.is_finite());
+        prop_assert!(metrics.roas.is_finite() && metrics.roas >= 0.0);
+        if let Some(frequency) = metrics.frequency {
+            prop_assert!(frequency.is_finite());
+        }
+    }
+
+    #[test]
+    fn test_normalization_maps_what_it_covers_and_is_idempotent(
+        mut campaigns in prop::collection::vec(arb_campaign(), 0..8),
+    ) {
+        let tables = builtin_tables();
+        let rules = NormalizationRules::default();
+
+        rules.apply(&tables, &mut campaigns);
+        for campaign in &campaigns {
+            let table = tables.get(&campaign.platform);
+            if let Some(CampaignObjective::Other(value)) = &campaign.objective {
+                prop_assert!(table.is_none_or(|t| !t.objective.contains_key(value)));
+            }
+            if let Some(CampaignType::Other(value)) = &campaign.campaign_type {
+                prop_assert!(table.is_none_or(|t| !t.campaign_type.contains_key(value)));
+            }
+            if let CampaignStatus::Other(value) = &campaign.status {
+                prop_assert!(table.is_none_or(|t| !t.status.contains_key(value)));
+            }
+        }
+
+        let once = serde_json::to_value(&campaigns).unwrap();
+        rules.apply(&tables, &mut campaigns);
+        prop_assert_eq!(serde_json::to_value(&campaigns).unwrap(), once);
+    }
+
+    #[test]
+    fn test_malformed_elements_never_fail_the_page(
+        campaigns in prop::collection::vec(arb_campaign(), 1..6),
+        broken in any::<prop::sample::Index>(),
+        field in prop::sample::select(&["id", "status", "daily_budget", "metrics", "currency"][..]),
+        junk in prop_oneof![
+            Just(serde_json::Value::Null),
+            Just(serde_json::json!("12.50")),
+            Just(serde_json::json!([])),
+            Just(serde_json::json!({"nested": true})),
+        ],
+    ) {
+        let mut values: Vec<serde_json::Value> =
+            campaigns.iter().map(|c| serde_json::to_value(c).unwrap()).collect();
+        let broken = broken.index(values.len());
+        values[broken][field] = junk;
+
+        let body = serde_json::json!({"results": values, "nextPageToken": null});
+        let page: GatewayPage<Lenient<Campaign>> = serde_json::from_value(body).unwrap();
+        let Page { items, .. } = Page::from(page);
+        let (valid, skipped) = lenient::split(&Platform::Google, "campaigns", items);
+
+        prop_assert_eq!(valid.len() + skipped.len(), campaigns.len());
+        prop_assert!(skipped.len() <= 1);
+        if let Some(record) = skipped.first() {
+            prop_assert_eq!(record.position, broken);
+        }
+    }
+}
+
+#[cfg(feature = "linkedin")]
+mod linkedin {
+    use super::super::linkedin::{
+        normalize, LinkedInAnalytics, LinkedInCampaign, LinkedInMoney, LinkedInRunSchedule,
+    };
+    use super::*;
+
+    /// Decimal strings as LinkedIn sends them, and some it should not
+    fn arb_decimal() -> impl Strategy<Value = String> {
+        prop_oneof![
+            6 => "[0-9]{1,15}(\\.[0-9]{1,2})?",
+            1 => Just("0".to_string()),
+            1 => Just("NaN".to_string()),
+            1 => Just("Infinity".to_string()),
+            1 => Just("-1".to_string()),
+            1 => Just(String::new()),
+        ]
+    }
+
+    fn arb_money() -> impl Strategy<Value = LinkedInMoney> {
+        (arb_decimal(), "[A-Z]{3}")
+            .prop_map(|(amount, currency_code)| LinkedInMoney { amount, currency_code })
+    }
+
+    fn arb_linkedin_campaign() -> impl Strategy<Value = LinkedInCampaign> {
+        let budget = (
+            prop::option::of(arb_money()),
+            prop::option::of(arb_money()),
+            prop::option::of((any::<i64>(), prop::option::of(any::<i64>()))),
+        );
+        let analytics = (
+            arb_counts(),
+            arb_conversions(),
+            arb_decimal(),
+            prop::option::of(arb_decimal()),
+            prop::option::of(any::<u64>()),
+            prop::option::of(any::<u64>()),
+        );
+        (any::<i64>(), "[A-Z_]{0,20}", prop::option::of("[A-Z_]{1,20}"), budget, analytics)
+            .prop_map(|(id, status, objective_type, budget, analytics)| {
+                let (daily_budget, total_budget, run_schedule) = budget;
+                let ((impressions, clicks), conversions, cost, value, reach, video_views) =
+                    analytics;
+                LinkedInCampaign {
+                    id,
+                    name: format!("campaign {}", id),
+                    status,
+                    objective_type,
+                    daily_budget,
+                    total_budget,
+                    run_schedule: run_schedule
+                        .map(|(start, end)| LinkedInRunSchedule { start, end }),
+                    analytics: LinkedInAnalytics {
+                        impressions,
+                        clicks,
+                        external_website_conversions: conversions,
+                        cost_in_local_currency: cost,
+                        conversion_value_in_local_currency: value,
+                        approximate_unique_impressions: reach,
+                        video_views,
+                    },
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_linkedin_campaigns_normalize_on_edge_values(raw in arb_linkedin_campaign()) {
+            let campaign = normalize(raw.clone());
+            let metrics = &campaign.metrics;
+
+            prop_assert_eq!(campaign.id.as_str(), raw.id.to_string());
+            prop_assert!(campaign.daily_budget.is_finite());
+            if metrics.impressions > 0 {
+                let ctr = metrics.clicks as f64 / metrics.impressions as f64 * 100.0;
+                prop_assert!(close(metrics.ctr, ctr));
+            } else {
+                prop_assert_eq!(metrics.ctr, 0.0);
+            }
+            prop_assert!(metrics.cost.is_finite() && metrics.cpa.is_finite());
+            prop_assert!(metrics.roas.is_finite());
+        }
+    }
+}
+
+#[cfg(feature = "microsoft")]
+mod microsoft {
+    use super::super::microsoft::{normalize, MicrosoftCampaign, MicrosoftPerformance};
+    use super::*;
+
+    fn arb_microsoft_campaign() -> impl Strategy<Value = MicrosoftCampaign> {
+        let budget = (
+            prop::option::of(0.0..1e12f64),
+            prop::option::of(0.0..1e12f64),
+            prop::option::of("[A-Z]{3}"),
+        );
+        let performance = (arb_counts(), 0.0..1e6f64, 0.0..1e12f64, 0.0..1e12f64);
+        (any::<i64>(), "[A-Za-z]{0,24}", prop::option::of("[A-Za-z]{1,20}"), budget, performance)
+            .prop_map(|(id, status, campaign_type, budget, performance)| {
+                let (daily_budget, shared_budget_amount, currency_code) = budget;
+                let ((impressions, clicks), conversions, spend, revenue) = performance;
+                MicrosoftCampaign {
+                    id,
+                    name: format!("campaign {}", id),
+                    status,
+                    campaign_type,
+                    budget_type: None,
+                    daily_budget,
+                    shared_budget_amount,
+                    currency_code,
+                    performance: MicrosoftPerformance {
+                        impressions,
+                        clicks,
+                        conversions,
+                        spend,
+                        revenue,
+                    },
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_microsoft_campaigns_normalize_on_edge_values(raw in arb_microsoft_campaign()) {
+            let campaign = normalize(raw.clone());
+            let metrics = &campaign.metrics;
+
+            let budget = raw.shared_budget_amount.or(raw.daily_budget).unwrap_or(0.0);
+            prop_assert_eq!(campaign.daily_budget, budget);
+            if metrics.impressions > 0 {
+                let ctr = metrics.clicks as f64 / metrics.impressions as f64 * 100.0;
+                prop_assert!(close(metrics.ctr, ctr));
+            } else {
+                prop_assert_eq!(metrics.ctr, 0.0);
+            }
+            prop_assert!(metrics.cpa.is_finite() && metrics.roas.is_finite());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! # Archived Campaigns Example
+//!
+//! Removed campaigns (`CampaignStatus::Removed`, Meta's deleted ones) are
+//! left out of `GET /campaigns` unless `?include_removed=true` is given.
+//! They are listed by `GET /campaigns/archived` instead:
+//!
+//! - campaigns the platforms still list as removed;
+//! - campaigns whose latest snapshot shows them removed, after the
+//!   platforms stopped listing them, rebuilt from the metrics store.
+//!
+//! Snapshots of removed campaigns are kept, so their final metrics stay
+//! available and `GET /campaigns/summary?compare=previous_period` still
+//! counts the days they ran.
+
+use axum::{extract::State, response::Json};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{
+    aggregate_campaigns, AppState, Campaign, CampaignMetrics, CampaignStatus, Platform,
+};
+use super::error::ApiError;
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::money::Currency;
+use super::store::{MetricsStore, RemovedCampaign};
+
+/// Removed campaign in `GET /campaigns/archived`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ArchivedCampaign {
+    pub id: CampaignId,
+    pub platform: Platform,
+    /// `None` when only snapshots older than these fields show the campaign
+    pub name: Option<String>,
+    pub currency: Option<Currency>,
+    /// `None` until a sync records the removal
+    pub removed_on: Option<NaiveDate>,
+    pub last_active_on: Option<NaiveDate>,
+    /// Month-to-date totals when it was removed; the current ones while no
+    /// sync recorded the removal
+    pub final_metrics: CampaignMetrics,
+    /// Whether its platform still lists it
+    pub listed: bool,
+}
+
+impl From<RemovedCampaign> for ArchivedCampaign {
+    fn from(removed: RemovedCampaign) -> Self {
+        Self {
+            id: removed.id,
+            platform: removed.platform,
+            name: removed.name,
+            currency: removed.currency,
+            removed_on: Some(removed.removed_on),
+            last_active_on: removed.last_active_on,
+            final_metrics: removed.final_metrics,
+            listed: false,
+        }
+    }
+}
+
+/// Drop removed campaigns from a list
+pub fn exclude_removed(campaigns: &mut Vec<Campaign>) {
+    campaigns.retain(|c| c.status != CampaignStatus::Removed);
+}
+
+/// Removed campaigns of `campaigns` and of the store, most recently
+/// removed first
+pub fn archived_campaigns(campaigns: &[Campaign], store: &MetricsStore) -> Vec<ArchivedCampaign> {
+    let mut recorded: HashMap<GlobalCampaignId, RemovedCampaign> = store
+        .removed()
+        .into_iter()
+        .map(|r| (GlobalCampaignId::new(r.platform.clone(), r.id.clone()), r))
+        .collect();
+
+    let mut archived: Vec<ArchivedCampaign> = campaigns
+        .iter()
+        .filter(|c| c.status == CampaignStatus::Removed)
+        .map(|campaign| {
+            let removal = recorded.remove(&campaign.global_id());
+            ArchivedCampaign {
+                id: campaign.id.clone(),
+                platform: campaign.platform.clone(),
+                name: Some(campaign.name.clone()),
+                currency: Some(campaign.currency()),
+                removed_on: removal.as_ref().map(|r| r.removed_on),
+                last_active_on: removal.as_ref().and_then(|r| r.last_active_on),
+                final_metrics: match removal {
+                    Some(removal) => removal.final_metrics,
+                    None => campaign.metrics.clone(),
+                },
+                listed: true,
+            }
+        })
+        .collect();
+    // A recorded removal the platform lists as active again is not archived
+    let listed: Vec<GlobalCampaignId> = campaigns.iter().map(Campaign::global_id).collect();
+    archived.extend(
+        recorded
+            .into_iter()
+            .filter(|(id, _)| !listed.contains(id))
+            .map(|(_, removed)| ArchivedCampaign::from(removed)),
+    );
+
+    // Not yet recorded (just removed) first, then the latest removals
+    archived.sort_by(|a, b| {
+        (a.removed_on.is_some(), Reverse(a.removed_on), &a.id)
+            .cmp(&(b.removed_on.is_some(), Reverse(b.removed_on), &b.id))
+    });
+    archived
+}
+
+/// Add the campaigns removed from the platforms to `ids`, per currency
+///
+/// Campaigns recorded without a currency are left out.
+pub fn add_removed_ids(
+    store: &MetricsStore,
+    ids: &mut BTreeMap<Currency, Vec<GlobalCampaignId>>,
+) {
+    for removed in store.removed() {
+        let Some(currency) = removed.currency else {
+            continue;
+        };
+        let ids = ids.entry(currency).or_default();
+        let id = GlobalCampaignId::new(removed.platform, removed.id);
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+}
+
+/// GET /campaigns/archived - Removed campaigns with their final metrics
+pub async fn get_archived_campaigns(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ArchivedCampaign>>, ApiError> {
+    let campaigns = aggregate_campaigns(&state).await;
+
+    Ok(Json(archived_campaigns(&campaigns, &state.metrics_store)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    #[test]
+    fn test_archive_merges_listed_and_recorded_removals() {
+        let store = MetricsStore::default();
+        let mut gone = Campaign::sample("g1", Platform::Google);
+        let mut removed = Campaign::sample("m1", Platform::Meta);
+        let active = Campaign::sample("m2", Platform::Meta);
+        store.record(day(1), &[gone.clone(), removed.clone(), active.clone()]);
+        gone.status = CampaignStatus::Removed;
+        gone.metrics.cost = 75.0;
+        store.record(day(2), &[gone, removed.clone(), active.clone()]);
+
+        // g1 is no longer listed, m1 was removed since the last sync
+        removed.status = CampaignStatus::Removed;
+        let mut listed = vec![removed, active];
+
+        let archived = archived_campaigns(&listed, &store);
+        let ids: Vec<&str> = archived.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["m1", "g1"]);
+        assert!(archived[0].listed && archived[0].removed_on.is_none());
+        assert_eq!(archived[1].removed_on, Some(day(2)));
+        assert_eq!(archived[1].final_metrics.cost, 75.0);
+
+        exclude_removed(&mut listed);
+        assert_eq!(listed.len(), 1);
+
+        let mut ids = BTreeMap::new();
+        add_removed_ids(&store, &mut ids);
+        assert_eq!(ids[&Currency::new("BRL")], [GlobalCampaignId::new(Platform::Google, "g1")]);
+    }
+}
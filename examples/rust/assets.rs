@@ -0,0 +1,286 @@
+//! # Asset Performance Example
+//!
+//! Rolls asset-level performance for Performance Max (Google) and
+//! Advantage+ (Meta) campaigns into one report so creative teams know
+//! which headlines, images and videos to replace.
+//!
+//! Google already rates every asset (`LOW` / `GOOD` / `BEST`). Meta only
+//! returns per-asset breakdowns, so Meta assets are labelled by comparing
+//! their CTR with the median CTR of the other assets in the same campaign.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+use super::ids::CampaignId;
+
+/// Impressions a Meta asset needs before it is rated
+pub const MIN_RATED_IMPRESSIONS: u64 = 500;
+
+/// CTR ratio against the campaign median at or above which a Meta asset is `BEST`
+pub const BEST_CTR_RATIO: f64 = 1.2;
+
+/// CTR ratio against the campaign median at or below which a Meta asset is `LOW`
+pub const LOW_CTR_RATIO: f64 = 0.8;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AssetLabel {
+    Low,
+    Learning,
+    Good,
+    Best,
+    /// No rating available (pending review or not enough data)
+    Unrated,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetType {
+    Headline,
+    Description,
+    Image,
+    Video,
+    CallToAction,
+}
+
+/// Asset row as returned by the gateway
+///
+/// `performance_label` is only set for Google assets.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetRow {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub asset_id: String,
+    pub asset_type: AssetType,
+    /// Text for text assets, URL for image and video assets
+    pub content: String,
+    pub performance_label: Option<AssetLabel>,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub cost: f64,
+}
+
+/// Unified asset performance entry
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetPerformance {
+    pub asset_id: String,
+    pub asset_type: AssetType,
+    pub content: String,
+    pub label: AssetLabel,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub cost: f64,
+    pub ctr: f64,
+}
+
+/// Asset report for a single campaign
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignAssetReport {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub label_counts: BTreeMap<String, usize>,
+    /// Assets sorted from worst to best label
+    pub assets: Vec<AssetPerformance>,
+    /// IDs of `LOW` assets that should be replaced
+    pub replace: Vec<String>,
+}
+
+/// Query parameters for `GET /assets/performance`
+#[derive(Debug, Deserialize)]
+pub struct AssetQuery {
+    pub campaign_id: Option<CampaignId>,
+}
+
+fn ctr(clicks: u64, impressions: u64) -> f64 {
+    if impressions == 0 {
+        0.0
+    } else {
+        clicks as f64 / impressions as f64 * 100.0
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Label a Meta asset against the median CTR of its campaign
+fn label_relative(row: &AssetRow, median_ctr: f64) -> AssetLabel {
+    if row.impressions < MIN_RATED_IMPRESSIONS {
+        return AssetLabel::Learning;
+    }
+    if median_ctr <= 0.0 {
+        return AssetLabel::Unrated;
+    }
+
+    let ratio = ctr(row.clicks, row.impressions) / median_ctr;
+    if ratio >= BEST_CTR_RATIO {
+        AssetLabel::Best
+    } else if ratio <= LOW_CTR_RATIO {
+        AssetLabel::Low
+    } else {
+        AssetLabel::Good
+    }
+}
+
+/// Build per-campaign asset reports from gateway rows
+pub fn build_reports(rows: &[AssetRow]) -> Vec<CampaignAssetReport> {
+    let mut by_campaign: BTreeMap<&CampaignId, Vec<&AssetRow>> = BTreeMap::new();
+    for row in rows {
+        by_campaign.entry(&row.campaign_id).or_default().push(row);
+    }
+
+    by_campaign
+        .into_iter()
+        .map(|(campaign_id, rows)| {
+            let mut rated_ctrs: Vec<f64> = rows
+                .iter()
+                .filter(|r| r.impressions >= MIN_RATED_IMPRESSIONS)
+                .map(|r| ctr(r.clicks, r.impressions))
+                .collect();
+            let median_ctr = median(&mut rated_ctrs);
+
+            let mut assets: Vec<AssetPerformance> = rows
+                .iter()
+                .map(|row| AssetPerformance {
+                    asset_id: row.asset_id.clone(),
+                    asset_type: row.asset_type,
+                    content: row.content.clone(),
+                    label: match row.platform {
+                        Platform::Google => row.performance_label.unwrap_or(AssetLabel::Unrated),
+                        _ => label_relative(row, median_ctr),
+                    },
+                    impressions: row.impressions,
+                    clicks: row.clicks,
+                    conversions: row.conversions,
+                    cost: row.cost,
+                    ctr: ctr(row.clicks, row.impressions),
+                })
+                .collect();
+
+            assets.sort_by(|a, b| a.label.cmp(&b.label).then(b.cost.total_cmp(&a.cost)));
+
+            let mut label_counts = BTreeMap::new();
+            for asset in &assets {
+                let key = serde_json::to_value(asset.label)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default();
+                *label_counts.entry(key).or_insert(0) += 1;
+            }
+
+            let replace = assets
+                .iter()
+                .filter(|a| a.label == AssetLabel::Low)
+                .map(|a| a.asset_id.clone())
+                .collect();
+
+            CampaignAssetReport {
+                platform: rows[0].platform.clone(),
+                campaign_id: campaign_id.clone(),
+                label_counts,
+                assets,
+                replace,
+            }
+        })
+        .collect()
+}
+
+/// Fetch PMax / Advantage+ asset rows for one platform from the gateway
+async fn fetch_assets(state: &AppState, platform: &str) -> Result<Vec<AssetRow>, reqwest::Error> {
+    let url = format!("{}/v1/{}/assets", state.gateway_url, platform);
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.json::<Vec<AssetRow>>().await
+}
+
+/// GET /assets/performance - Unified asset performance report
+pub async fn get_asset_performance(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AssetQuery>,
+) -> Result<Json<Vec<CampaignAssetReport>>, ApiError> {
+    let (google, meta) = tokio::join!(
+        fetch_assets(&state, "google"),
+        fetch_assets(&state, "meta")
+    );
+
+    let mut rows = Vec::new();
+    rows.extend(google.unwrap_or_default());
+    rows.extend(meta.unwrap_or_default());
+
+    if let Some(campaign_id) = &query.campaign_id {
+        rows.retain(|row| &row.campaign_id == campaign_id);
+    }
+
+    Ok(Json(build_reports(&rows)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(platform: Platform, id: &str, clicks: u64, label: Option<AssetLabel>) -> AssetRow {
+        AssetRow {
+            platform,
+            campaign_id: "c1".into(),
+            asset_id: id.to_string(),
+            asset_type: AssetType::Headline,
+            content: format!("Headline {}", id),
+            performance_label: label,
+            impressions: 1_000,
+            clicks,
+            conversions: 0,
+            cost: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_google_labels_are_passed_through() {
+        let rows = vec![
+            row(Platform::Google, "a", 10, Some(AssetLabel::Best)),
+            row(Platform::Google, "b", 10, Some(AssetLabel::Low)),
+        ];
+
+        let report = &build_reports(&rows)[0];
+        assert_eq!(report.assets[0].asset_id, "b");
+        assert_eq!(report.replace, vec!["b".to_string()]);
+        assert_eq!(report.label_counts["BEST"], 1);
+    }
+
+    #[test]
+    fn test_meta_assets_are_labelled_against_campaign_median() {
+        let rows = vec![
+            row(Platform::Meta, "low", 10, None),
+            row(Platform::Meta, "mid", 20, None),
+            row(Platform::Meta, "best", 30, None),
+        ];
+
+        let report = &build_reports(&rows)[0];
+        let label = |id: &str| report.assets.iter().find(|a| a.asset_id == id).unwrap().label;
+
+        assert_eq!(label("low"), AssetLabel::Low);
+        assert_eq!(label("mid"), AssetLabel::Good);
+        assert_eq!(label("best"), AssetLabel::Best);
+    }
+}
@@ -0,0 +1,268 @@
+//! # Attribution Comparison Example
+//!
+//! `GET /campaigns/{platform}/{id}/attribution?models=last_click,data_driven`
+//! fetches the campaign's conversions under each requested attribution
+//! model and lines them up, with the shift against the first model, so
+//! analysts can see how much credit moves when the model changes.
+//!
+//! Google Ads reports every model; Meta, LinkedIn and Microsoft only
+//! expose last click through the gateway. Models a platform does not
+//! support are listed under `unsupported` instead of failing the request.
+
+use axum::extract::{Path, Query, State};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::call_budget;
+use super::campaign_aggregator::{find_global_campaign, roas, AppState, Platform};
+use super::error::ApiError;
+use super::finite::Finite;
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Models compared when `?models=` is absent
+pub const DEFAULT_MODELS: &[AttributionModel] =
+    &[AttributionModel::LastClick, AttributionModel::DataDriven];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributionModel {
+    LastClick,
+    FirstClick,
+    Linear,
+    TimeDecay,
+    PositionBased,
+    DataDriven,
+}
+
+impl AttributionModel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributionModel::LastClick => "last_click",
+            AttributionModel::FirstClick => "first_click",
+            AttributionModel::Linear => "linear",
+            AttributionModel::TimeDecay => "time_decay",
+            AttributionModel::PositionBased => "position_based",
+            AttributionModel::DataDriven => "data_driven",
+        }
+    }
+
+    /// Whether the platform can report conversions under this model
+    pub fn supported_by(&self, platform: &Platform) -> bool {
+        match platform {
+            Platform::Google => true,
+            #[allow(unreachable_patterns)]
+            _ => *self == AttributionModel::LastClick,
+        }
+    }
+}
+
+impl FromStr for AttributionModel {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "last_click" => AttributionModel::LastClick,
+            "first_click" => AttributionModel::FirstClick,
+            "linear" => AttributionModel::Linear,
+            "time_decay" => AttributionModel::TimeDecay,
+            "position_based" => AttributionModel::PositionBased,
+            "data_driven" => AttributionModel::DataDriven,
+            other => {
+                return Err(ApiError::Validation(format!("unknown attribution model {:?}", other)))
+            }
+        })
+    }
+}
+
+/// Query parameters for `GET /campaigns/{platform}/{id}/attribution`
+#[derive(Debug, Deserialize, Default)]
+pub struct AttributionQuery {
+    /// Comma-separated models; the first one is the comparison baseline
+    pub models: Option<String>,
+}
+
+impl AttributionQuery {
+    pub fn models(&self) -> Result<Vec<AttributionModel>, ApiError> {
+        let Some(list) = self.models.as_deref().filter(|l| !l.trim().is_empty()) else {
+            return Ok(DEFAULT_MODELS.to_vec());
+        };
+
+        let mut models = Vec::new();
+        for model in list.split(',').map(AttributionModel::from_str) {
+            let model = model?;
+            if !models.contains(&model) {
+                models.push(model);
+            }
+        }
+        Ok(models)
+    }
+}
+
+/// Conversions credited to a campaign under one model, from the gateway
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttributedConversions {
+    /// Fractional for data-driven and multi-touch models
+    pub conversions: f64,
+    pub conversion_value: f64,
+}
+
+/// One column of the comparison
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelResult {
+    pub model: AttributionModel,
+    pub conversions: f64,
+    pub conversion_value: f64,
+    /// `None` without conversions
+    pub cpa: Option<f64>,
+    pub roas: f64,
+    /// Change in conversions against the baseline, in percent
+    pub conversions_shift_pct: Option<f64>,
+}
+
+/// `GET /campaigns/{platform}/{id}/attribution` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttributionComparison {
+    pub campaign_id: CampaignId,
+    pub platform: Platform,
+    pub cost: f64,
+    /// First supported model requested
+    pub baseline: Option<AttributionModel>,
+    pub models: Vec<ModelResult>,
+    pub unsupported: Vec<AttributionModel>,
+}
+
+/// Line up the results of every model against the first one
+pub fn compare(
+    campaign_id: &CampaignId,
+    platform: Platform,
+    cost: f64,
+    results: Vec<(AttributionModel, AttributedConversions)>,
+    unsupported: Vec<AttributionModel>,
+) -> AttributionComparison {
+    let baseline = results.first().map(|(_, r)| r.conversions);
+
+    let models = results
+        .iter()
+        .map(|(model, r)| ModelResult {
+            model: *model,
+            conversions: r.conversions,
+            conversion_value: r.conversion_value,
+            cpa: (r.conversions > 0.0).then(|| cost / r.conversions),
+            roas: roas(r.conversion_value, cost),
+            conversions_shift_pct: baseline
+                .filter(|b| *b > 0.0)
+                .map(|b| (r.conversions - b) / b * 100.0),
+        })
+        .collect();
+
+    AttributionComparison {
+        campaign_id: campaign_id.clone(),
+        platform,
+        cost,
+        baseline: results.first().map(|(model, _)| *model),
+        models,
+        unsupported,
+    }
+}
+
+/// Fetch a campaign's conversions under one model from the gateway
+async fn fetch_attributed(
+    state: &AppState,
+    platform: &Platform,
+    campaign_id: &CampaignId,
+    model: AttributionModel,
+) -> Result<AttributedConversions, reqwest::Error> {
+    let url = format!(
+        "{}/v1/{}/campaigns/{}/conversions?attribution_model={}",
+        state.gateway_url,
+        platform.as_str(),
+        campaign_id,
+        model.as_str()
+    );
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.error_for_status()?.json::<AttributedConversions>().await
+}
+
+/// GET /campaigns/{platform}/{id}/attribution - Conversions under several attribution models
+pub async fn get_attribution(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+    Query(query): Query<AttributionQuery>,
+) -> Result<Finite<AttributionComparison>, ApiError> {
+    let models = query.models()?;
+    let campaign = find_global_campaign(&state, &GlobalCampaignId::new(platform, id.clone()))
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+
+    let (supported, unsupported): (Vec<_>, Vec<_>) =
+        models.into_iter().partition(|m| m.supported_by(&campaign.platform));
+
+    call_budget::charge(supported.len())?;
+    let fetches = supported
+        .iter()
+        .map(|model| fetch_attributed(&state, &campaign.platform, &id, *model));
+    let fetched = futures::future::join_all(fetches).await;
+
+    let mut results = Vec::new();
+    for (model, result) in supported.into_iter().zip(fetched) {
+        results.push((model, result?));
+    }
+
+    Ok(Finite(compare(&id, campaign.platform, campaign.metrics.cost, results, unsupported)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_models_parsed_in_order_without_duplicates() {
+        let query = AttributionQuery {
+            models: Some("data_driven, last_click,data_driven".to_string()),
+        };
+        assert_eq!(
+            query.models().unwrap(),
+            vec![AttributionModel::DataDriven, AttributionModel::LastClick]
+        );
+
+        assert_eq!(AttributionQuery::default().models().unwrap(), DEFAULT_MODELS.to_vec());
+
+        let bad = AttributionQuery {
+            models: Some("last_touch".to_string()),
+        };
+        assert!(bad.models().is_err());
+    }
+
+    #[test]
+    fn test_comparison_shifts_against_first_model() {
+        let attributed = |conversions, conversion_value| AttributedConversions {
+            conversions,
+            conversion_value,
+        };
+        let results = vec![
+            (AttributionModel::LastClick, attributed(40.0, 4_000.0)),
+            (AttributionModel::DataDriven, attributed(50.0, 4_500.0)),
+        ];
+
+        let comparison = compare(&"g1".into(), Platform::Google, 1_000.0, results, Vec::new());
+
+        assert_eq!(comparison.baseline, Some(AttributionModel::LastClick));
+        assert_eq!(comparison.models[0].conversions_shift_pct, Some(0.0));
+        assert_eq!(comparison.models[1].conversions_shift_pct, Some(25.0));
+        assert_eq!(comparison.models[1].cpa, Some(20.0));
+        assert_eq!(comparison.models[1].roas, 4.5);
+    }
+
+    #[test]
+    fn test_only_google_reports_multi_touch_models() {
+        assert!(AttributionModel::DataDriven.supported_by(&Platform::Google));
+        assert!(!AttributionModel::DataDriven.supported_by(&Platform::Meta));
+        assert!(AttributionModel::LastClick.supported_by(&Platform::Meta));
+    }
+}
@@ -0,0 +1,204 @@
+//! # Auction Insights Example
+//!
+//! A campaign spending its whole budget by noon looks healthy in spend
+//! and CPA, yet misses half of the auctions it could enter. Google Search
+//! impression share and Meta auction competition metrics are normalized
+//! into one `AuctionInsights` block per campaign, requested with
+//! `GET /campaigns?include=auction`:
+//!
+//! - Google: share of eligible impressions received, the shares lost to
+//!   budget and to ad rank, top and absolute-top shares, and the
+//!   competing domains of the auction insights report. Google reports
+//!   shares below 10% as `0.0999`, so those read as an upper bound;
+//! - Meta: auction competitiveness and the campaign's average bid against
+//!   the highest competing bid;
+//! - shares are fractions from 0 to 1, bids are in the campaign currency;
+//! - `budget_limited` is set when at least `BUDGET_LIMITED_LOST_SHARE` of
+//!   the eligible impressions were lost to budget, the campaigns a budget
+//!   increase would grow directly.
+//!
+//! Display and other non-search campaigns have no impression share; their
+//! fields are `null`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::call_budget;
+use super::campaign_aggregator::Campaign;
+use super::connectors::{ConnectorError, ConnectorRegistry};
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Share of eligible impressions lost to budget from which a campaign is
+/// budget limited
+pub const BUDGET_LIMITED_LOST_SHARE: f64 = 0.1;
+
+/// Competitor in the auctions of a Google campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuctionCompetitor {
+    /// Display domain, e.g. `example.com`
+    pub domain: String,
+    pub impression_share: Option<f64>,
+    /// How often its ad showed when the campaign's did
+    pub overlap_rate: Option<f64>,
+    /// How often the campaign's ad ranked above it, or showed when it did not
+    pub outranking_share: Option<f64>,
+    /// How often its ad ranked above the campaign's when both showed
+    pub position_above_rate: Option<f64>,
+}
+
+/// Impression share and auction insights of a campaign as returned by the
+/// Google gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleAuctionInsight {
+    pub campaign_id: CampaignId,
+    pub search_impression_share: Option<f64>,
+    pub search_budget_lost_impression_share: Option<f64>,
+    pub search_rank_lost_impression_share: Option<f64>,
+    pub search_top_impression_share: Option<f64>,
+    pub search_absolute_top_impression_share: Option<f64>,
+    #[serde(default)]
+    pub competitors: Vec<AuctionCompetitor>,
+}
+
+/// Auction metrics of a campaign as returned by the Meta gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaAuctionInsight {
+    pub campaign_id: CampaignId,
+    pub auction_competitiveness: Option<f64>,
+    pub auction_bid: Option<f64>,
+    pub auction_max_competitor_bid: Option<f64>,
+}
+
+/// Auction position of a campaign
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AuctionInsights {
+    /// Share of eligible impressions received
+    pub impression_share: Option<f64>,
+    /// Share of eligible impressions lost to budget
+    pub lost_is_budget: Option<f64>,
+    /// Share of eligible impressions lost to ad rank
+    pub lost_is_rank: Option<f64>,
+    pub top_impression_share: Option<f64>,
+    pub absolute_top_impression_share: Option<f64>,
+    /// Meta auction competitiveness
+    pub competitiveness: Option<f64>,
+    /// Meta average bid
+    pub average_bid: Option<f64>,
+    /// Meta highest competing bid
+    pub max_competitor_bid: Option<f64>,
+    /// Highest impression share first; Google only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub competitors: Vec<AuctionCompetitor>,
+    /// Lost at least `BUDGET_LIMITED_LOST_SHARE` of its impressions to budget
+    pub budget_limited: bool,
+}
+
+pub fn normalize_google(raw: GoogleAuctionInsight) -> (CampaignId, AuctionInsights) {
+    let mut competitors = raw.competitors;
+    competitors.sort_by(|a, b| {
+        let share = |c: &AuctionCompetitor| c.impression_share.unwrap_or(0.0);
+        share(b).total_cmp(&share(a))
+    });
+    let insights = AuctionInsights {
+        impression_share: raw.search_impression_share,
+        lost_is_budget: raw.search_budget_lost_impression_share,
+        lost_is_rank: raw.search_rank_lost_impression_share,
+        top_impression_share: raw.search_top_impression_share,
+        absolute_top_impression_share: raw.search_absolute_top_impression_share,
+        competitors,
+        budget_limited: raw
+            .search_budget_lost_impression_share
+            .is_some_and(|lost| lost >= BUDGET_LIMITED_LOST_SHARE),
+        ..Default::default()
+    };
+    (raw.campaign_id, insights)
+}
+
+pub fn normalize_meta(raw: MetaAuctionInsight) -> (CampaignId, AuctionInsights) {
+    let insights = AuctionInsights {
+        competitiveness: raw.auction_competitiveness,
+        average_bid: raw.auction_bid,
+        max_competitor_bid: raw.auction_max_competitor_bid,
+        ..Default::default()
+    };
+    (raw.campaign_id, insights)
+}
+
+/// Fill in the `auction` block of every campaign, one gateway call per
+/// platform
+///
+/// Platforms without auction metrics are skipped; other failures are
+/// logged and leave the block empty.
+pub async fn attach_auction_insights(registry: &ConnectorRegistry, campaigns: &mut [Campaign]) {
+    let mut ids_by_platform: HashMap<_, Vec<CampaignId>> = HashMap::new();
+    for campaign in campaigns.iter() {
+        ids_by_platform
+            .entry(campaign.platform.clone())
+            .or_default()
+            .push(campaign.id.clone());
+    }
+
+    if call_budget::charge(ids_by_platform.len()).is_err() {
+        return;
+    }
+
+    let fetches = ids_by_platform.iter().filter_map(|(platform, ids)| {
+        let connector = registry.get(platform)?;
+        Some(async move { (platform.clone(), connector.fetch_auction_insights(ids).await) })
+    });
+
+    let mut auction = HashMap::new();
+    for (platform, result) in futures::future::join_all(fetches).await {
+        match result {
+            Ok(by_campaign) => {
+                for (campaign_id, insights) in by_campaign {
+                    auction.insert(GlobalCampaignId::new(platform.clone(), campaign_id), insights);
+                }
+            }
+            Err(ConnectorError::NotSupported(_)) => {}
+            Err(e) => tracing::warn!(platform = platform.as_str(), error = %e, "auction fetch failed"),
+        }
+    }
+
+    for campaign in campaigns.iter_mut() {
+        campaign.auction = auction.remove(&campaign.global_id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_lost_share_flags_budget_limited_campaigns() {
+        let competitor = |domain: &str, share| AuctionCompetitor {
+            domain: domain.to_string(),
+            impression_share: share,
+            overlap_rate: None,
+            outranking_share: None,
+            position_above_rate: None,
+        };
+        let (id, insights) = normalize_google(GoogleAuctionInsight {
+            campaign_id: "g1".into(),
+            search_impression_share: Some(0.42),
+            search_budget_lost_impression_share: Some(0.35),
+            search_rank_lost_impression_share: Some(0.23),
+            search_top_impression_share: Some(0.3),
+            search_absolute_top_impression_share: Some(0.1),
+            competitors: vec![competitor("a.com", Some(0.2)), competitor("b.com", Some(0.6))],
+        });
+        assert_eq!(id, "g1");
+        assert!(insights.budget_limited);
+        let domains: Vec<&str> = insights.competitors.iter().map(|c| c.domain.as_str()).collect();
+        assert_eq!(domains, ["b.com", "a.com"]);
+
+        let (_, meta) = normalize_meta(MetaAuctionInsight {
+            campaign_id: "m1".into(),
+            auction_competitiveness: Some(0.7),
+            auction_bid: Some(1.2),
+            auction_max_competitor_bid: Some(2.5),
+        });
+        assert!(!meta.budget_limited);
+        assert_eq!((meta.impression_share, meta.max_competitor_bid), (None, Some(2.5)));
+    }
+}
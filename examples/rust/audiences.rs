@@ -0,0 +1,306 @@
+//! # Audience Monitoring Example
+//!
+//! Tracks the size of targeted audiences over time (recorded by the sync
+//! worker) and flags self-competition: two campaigns targeting audiences
+//! that overlap so much they bid against each other for the same people.
+//!
+//! Overlap estimates come from the gateway (Meta's audience overlap tool
+//! and Google's Audience Insights overlap for Customer Match lists).
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use super::call_budget;
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+use super::ids::CampaignId;
+
+/// Share of the smaller audience that must overlap to flag self-competition
+pub const SELF_COMPETITION_RATIO: f64 = 0.8;
+
+/// Targeted audience as reported by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudienceInfo {
+    pub platform: Platform,
+    pub audience_id: String,
+    pub name: String,
+    /// Estimated number of people in the audience
+    pub size: u64,
+    /// Campaigns currently targeting this audience
+    #[serde(default)]
+    pub campaign_ids: Vec<CampaignId>,
+}
+
+/// Overlap estimate between two audiences of the same platform
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OverlapEstimate {
+    pub audience_a: String,
+    pub audience_b: String,
+    pub overlap_size: u64,
+}
+
+/// Audience with its size trend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudienceSummary {
+    #[serde(flatten)]
+    pub audience: AudienceInfo,
+    /// Size change in percent over the last 7 recorded days
+    pub size_change_7d: Option<f64>,
+}
+
+/// Pair of audiences targeted by different campaigns that largely overlap
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelfCompetition {
+    pub platform: Platform,
+    pub audience_a: String,
+    pub audience_b: String,
+    pub overlap_size: u64,
+    /// Overlap as a share of the smaller audience
+    pub overlap_ratio: f64,
+    pub campaigns_a: Vec<CampaignId>,
+    pub campaigns_b: Vec<CampaignId>,
+}
+
+/// Audience size history keyed by audience ID
+#[derive(Default)]
+pub struct AudienceStore {
+    audiences: RwLock<HashMap<String, AudienceInfo>>,
+    sizes: RwLock<HashMap<String, BTreeMap<NaiveDate, u64>>>,
+}
+
+impl AudienceStore {
+    /// Record the current audiences and their sizes for `date`
+    pub fn record(&self, date: NaiveDate, audiences: &[AudienceInfo]) {
+        let mut latest = self.audiences.write().unwrap();
+        let mut sizes = self.sizes.write().unwrap();
+
+        for audience in audiences {
+            latest.insert(audience.audience_id.clone(), audience.clone());
+            sizes
+                .entry(audience.audience_id.clone())
+                .or_default()
+                .insert(date, audience.size);
+        }
+    }
+
+    pub fn latest(&self) -> Vec<AudienceInfo> {
+        self.audiences.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn size_history(&self, audience_id: &str) -> Option<BTreeMap<NaiveDate, u64>> {
+        self.sizes.read().unwrap().get(audience_id).cloned()
+    }
+
+    /// Size change in percent between the latest size and the size 7 days earlier
+    pub fn size_change_7d(&self, audience_id: &str) -> Option<f64> {
+        let history = self.size_history(audience_id)?;
+        let (latest_date, latest) = history.iter().next_back()?;
+        let week_ago = *latest_date - chrono::Duration::days(7);
+        let (_, previous) = history.range(..=week_ago).next_back()?;
+
+        (*previous > 0).then(|| (*latest as f64 - *previous as f64) / *previous as f64 * 100.0)
+    }
+}
+
+/// Flag audience pairs targeted by different campaigns that overlap heavily
+pub fn find_self_competition(
+    audiences: &[AudienceInfo],
+    overlaps: &[OverlapEstimate],
+) -> Vec<SelfCompetition> {
+    let by_id: HashMap<&str, &AudienceInfo> = audiences
+        .iter()
+        .map(|a| (a.audience_id.as_str(), a))
+        .collect();
+
+    let mut flagged: Vec<SelfCompetition> = overlaps
+        .iter()
+        .filter_map(|overlap| {
+            let a = by_id.get(overlap.audience_a.as_str())?;
+            let b = by_id.get(overlap.audience_b.as_str())?;
+
+            // Same campaign targeting both audiences is not competition
+            let distinct_campaigns = a
+                .campaign_ids
+                .iter()
+                .any(|ca| b.campaign_ids.iter().any(|cb| cb != ca));
+            if !distinct_campaigns {
+                return None;
+            }
+
+            let smaller = a.size.min(b.size);
+            if smaller == 0 {
+                return None;
+            }
+
+            let overlap_ratio = overlap.overlap_size as f64 / smaller as f64;
+            (overlap_ratio >= SELF_COMPETITION_RATIO).then(|| SelfCompetition {
+                platform: a.platform.clone(),
+                audience_a: a.audience_id.clone(),
+                audience_b: b.audience_id.clone(),
+                overlap_size: overlap.overlap_size,
+                overlap_ratio,
+                campaigns_a: a.campaign_ids.clone(),
+                campaigns_b: b.campaign_ids.clone(),
+            })
+        })
+        .collect();
+
+    flagged.sort_by(|x, y| y.overlap_ratio.total_cmp(&x.overlap_ratio));
+    flagged
+}
+
+/// Fetch targeted audiences for one platform from the gateway
+pub async fn fetch_audiences(
+    state: &AppState,
+    platform: &str,
+) -> Result<Vec<AudienceInfo>, reqwest::Error> {
+    let url = format!("{}/v1/{}/audiences", state.gateway_url, platform);
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.json::<Vec<AudienceInfo>>().await
+}
+
+/// Fetch pairwise overlap estimates for one platform from the gateway
+async fn fetch_overlaps(
+    state: &AppState,
+    platform: &str,
+) -> Result<Vec<OverlapEstimate>, reqwest::Error> {
+    let url = format!("{}/v1/{}/audiences/overlaps", state.gateway_url, platform);
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.json::<Vec<OverlapEstimate>>().await
+}
+
+/// Fetch audiences from both platforms and record their sizes (sync worker)
+pub async fn sync_audiences(state: &AppState, date: NaiveDate) {
+    let (google, meta) = tokio::join!(
+        fetch_audiences(state, "google"),
+        fetch_audiences(state, "meta")
+    );
+
+    let mut audiences = Vec::new();
+    audiences.extend(google.unwrap_or_default());
+    audiences.extend(meta.unwrap_or_default());
+
+    state.audiences.record(date, &audiences);
+}
+
+/// GET /audiences - Targeted audiences with their 7-day size trend
+pub async fn list_audiences(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AudienceSummary>>, ApiError> {
+    let summaries = state
+        .audiences
+        .latest()
+        .into_iter()
+        .map(|audience| AudienceSummary {
+            size_change_7d: state.audiences.size_change_7d(&audience.audience_id),
+            audience,
+        })
+        .collect();
+
+    Ok(Json(summaries))
+}
+
+/// GET /audiences/{id}/sizes - Recorded size history of an audience
+pub async fn get_audience_sizes(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BTreeMap<NaiveDate, u64>>, ApiError> {
+    state
+        .audiences
+        .size_history(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("audience {}", id)))
+}
+
+/// GET /audiences/overlap - Audience pairs causing self-competition
+pub async fn get_self_competition(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SelfCompetition>>, ApiError> {
+    call_budget::charge(2)?;
+
+    let (google, meta) = tokio::join!(
+        fetch_overlaps(&state, "google"),
+        fetch_overlaps(&state, "meta")
+    );
+
+    let mut overlaps = Vec::new();
+    overlaps.extend(google.unwrap_or_default());
+    overlaps.extend(meta.unwrap_or_default());
+
+    Ok(Json(find_self_competition(&state.audiences.latest(), &overlaps)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audience(id: &str, size: u64, campaigns: &[&str]) -> AudienceInfo {
+        AudienceInfo {
+            platform: Platform::Meta,
+            audience_id: id.to_string(),
+            name: id.to_string(),
+            size,
+            campaign_ids: campaigns.iter().map(|c| CampaignId::from(*c)).collect(),
+        }
+    }
+
+    fn overlap(a: &str, b: &str, size: u64) -> OverlapEstimate {
+        OverlapEstimate {
+            audience_a: a.to_string(),
+            audience_b: b.to_string(),
+            overlap_size: size,
+        }
+    }
+
+    #[test]
+    fn test_flags_heavily_overlapping_audiences_across_campaigns() {
+        let audiences = vec![
+            audience("buyers", 10_000, &["c1"]),
+            audience("lookalike", 50_000, &["c2"]),
+            audience("visitors", 100_000, &["c3"]),
+        ];
+        let overlaps = vec![
+            overlap("buyers", "lookalike", 9_000),
+            overlap("lookalike", "visitors", 10_000),
+        ];
+
+        let flagged = find_self_competition(&audiences, &overlaps);
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].audience_a, "buyers");
+        assert!((flagged[0].overlap_ratio - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_same_campaign_is_not_self_competition() {
+        let audiences = vec![audience("a", 1_000, &["c1"]), audience("b", 1_000, &["c1"])];
+        assert!(find_self_competition(&audiences, &[overlap("a", "b", 1_000)]).is_empty());
+    }
+
+    #[test]
+    fn test_size_change_over_a_week() {
+        let store = AudienceStore::default();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 11, d).unwrap();
+
+        store.record(day(1), &[audience("a", 1_000, &[])]);
+        store.record(day(8), &[audience("a", 1_200, &[])]);
+
+        assert_eq!(store.size_change_7d("a"), Some(20.0));
+        assert_eq!(store.size_history("a").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,303 @@
+//! # Audit Log Example
+//!
+//! Agencies must show clients who changed what, and when. Every status
+//! change (pause, enable, remove) and budget change forwarded to an ad
+//! platform, whether it comes from `POST /campaigns/bulk`, a bulk rollback
+//! or a spec apply, and every exclusion change is recorded with:
+//!
+//! - the actor from `X-Actor` (the API key ID or JWT subject set by the
+//!   auth proxy), its role and tenant;
+//! - the value before and after the change, when the previous value was
+//!   known;
+//! - the platform response: success, or the error code and message.
+//!
+//! Entries are only ever appended and tagged with the region of the
+//! instance that stored them. With `audit.path` set, each one is also
+//! written as a JSON line to that file and the file is read back on
+//! startup. `GET /audit?campaign_id=&actor=&from=&to=` returns matching
+//! entries, most recent first.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::freeze::{Actor, ActorRole};
+use super::ids::CampaignId;
+use super::negatives::Exclusion;
+use super::region::Region;
+
+/// Changed campaign setting, with its value before and after
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum AuditChange {
+    Status {
+        before: Option<CampaignStatus>,
+        after: CampaignStatus,
+    },
+    DailyBudget {
+        before: Option<f64>,
+        after: f64,
+    },
+    /// Negative keywords, excluded audiences or placements; see `negatives`
+    Exclusions {
+        added: Vec<Exclusion>,
+        removed: Vec<Exclusion>,
+    },
+}
+
+/// What the platform answered
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlatformResponse {
+    pub success: bool,
+    /// `ApiError` code when the change failed
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+}
+
+impl PlatformResponse {
+    pub fn from_result<T>(result: &Result<T, ApiError>) -> Self {
+        match result {
+            Ok(_) => Self {
+                success: true,
+                error_code: None,
+                error: None,
+            },
+            Err(e) => Self {
+                success: false,
+                error_code: Some(e.code().to_string()),
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// One recorded mutation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub id: String,
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    pub role: ActorRole,
+    pub tenant: Option<String>,
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    #[serde(flatten)]
+    pub change: AuditChange,
+    pub response: PlatformResponse,
+    /// Region of the instance that stored the entry; missing on entries
+    /// written before regions were tagged
+    #[serde(default)]
+    pub region: Option<Region>,
+}
+
+impl AuditEntry {
+    pub fn new<T>(
+        actor: &Actor,
+        platform: &Platform,
+        campaign_id: &CampaignId,
+        change: AuditChange,
+        result: &Result<T, ApiError>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            at: Utc::now(),
+            actor: actor.id.clone(),
+            role: actor.role,
+            tenant: actor.tenant.clone(),
+            platform: platform.clone(),
+            campaign_id: campaign_id.clone(),
+            change,
+            response: PlatformResponse::from_result(result),
+            region: None,
+        }
+    }
+}
+
+/// Query parameters for `GET /audit`
+#[derive(Debug, Deserialize, Default)]
+pub struct AuditQuery {
+    pub campaign_id: Option<CampaignId>,
+    pub actor: Option<String>,
+    /// Inclusive lower bound, RFC 3339
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound, RFC 3339
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.campaign_id.as_ref().is_none_or(|id| *id == entry.campaign_id)
+            && self.actor.as_ref().is_none_or(|actor| *actor == entry.actor)
+            && self.from.is_none_or(|from| entry.at >= from)
+            && self.to.is_none_or(|to| entry.at < to)
+    }
+}
+
+/// Append-only record of campaign mutations
+#[derive(Default)]
+pub struct AuditLog {
+    /// JSON Lines file every entry is appended to
+    path: Option<PathBuf>,
+    /// Region entries are tagged with
+    region: Region,
+    entries: RwLock<Vec<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn new(path: Option<PathBuf>, region: Region) -> Self {
+        Self {
+            path,
+            region,
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&self, mut entry: AuditEntry) {
+        entry.region = Some(self.region);
+        tracing::info!(
+            actor = %entry.actor,
+            platform = entry.platform.as_str(),
+            campaign_id = %entry.campaign_id,
+            success = entry.response.success,
+            "campaign mutation audited"
+        );
+
+        // The file is written under the lock so lines keep the in-memory order
+        let mut entries = self.entries.write().unwrap();
+        if let Some(path) = &self.path {
+            if let Err(e) = append_line(path, &entry) {
+                tracing::error!(path = %path.display(), error = %e, "audit entry not persisted");
+            }
+        }
+        entries.push(entry);
+    }
+
+    /// Entries matching `query`, most recent first
+    pub fn query(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        let entries = self.entries.read().unwrap();
+        entries.iter().rev().filter(|e| query.matches(e)).cloned().collect()
+    }
+
+    /// Read back entries written to `path` by earlier runs
+    pub fn load(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let mut loaded = Vec::new();
+        for line in file.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                loaded.push(serde_json::from_str::<AuditEntry>(&line)?);
+            }
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        loaded.append(&mut entries);
+        *entries = loaded;
+        Ok(())
+    }
+}
+
+fn append_line(path: &Path, entry: &AuditEntry) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)
+}
+
+/// GET /audit - Recorded mutations, filtered by campaign, actor and time
+pub async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, ApiError> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(ApiError::Validation("from must not be after to".to_string()));
+        }
+    }
+
+    Ok(Json(state.audit.query(&query)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(actor: &str, campaign_id: &str, result: Result<(), ApiError>) -> AuditEntry {
+        AuditEntry::new(
+            &Actor::automation(actor, None),
+            &Platform::Google,
+            &campaign_id.into(),
+            AuditChange::DailyBudget {
+                before: Some(50.0),
+                after: 80.0,
+            },
+            &result,
+        )
+    }
+
+    #[test]
+    fn test_query_filters_by_campaign_actor_and_time() {
+        let log = AuditLog::default();
+        log.record(entry("alice", "g1", Ok(())));
+        log.record(entry("bob", "g1", Err(ApiError::GatewayTimeout)));
+        log.record(entry("alice", "g2", Ok(())));
+
+        let g1 = log.query(&AuditQuery {
+            campaign_id: Some("g1".into()),
+            ..Default::default()
+        });
+        assert_eq!(g1.len(), 2);
+        // Most recent first
+        assert_eq!(g1[0].actor, "bob");
+        assert!(!g1[0].response.success);
+        assert_eq!(g1[0].response.error_code.as_deref(), Some("gateway_timeout"));
+
+        let alice = log.query(&AuditQuery {
+            actor: Some("alice".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(alice.len(), 2);
+
+        let future = log.query(&AuditQuery {
+            from: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        });
+        assert!(future.is_empty());
+    }
+
+    #[test]
+    fn test_entries_survive_a_restart_through_the_file() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()));
+
+        let log = AuditLog::new(Some(path.clone()), Region::Eu);
+        log.record(entry("alice", "g1", Ok(())));
+        log.record(entry("bob", "g2", Ok(())));
+
+        let restarted = AuditLog::new(Some(path.clone()), Region::Eu);
+        restarted.load(&path).unwrap();
+        let entries = restarted.query(&AuditQuery::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].actor, "alice");
+        assert_eq!(entries[1].region, Some(Region::Eu));
+        assert_eq!(
+            entries[1].change,
+            AuditChange::DailyBudget {
+                before: Some(50.0),
+                after: 80.0
+            }
+        );
+    }
+}
@@ -0,0 +1,661 @@
+//! # Automation Rules Example
+//!
+//! Alert rules tell someone; automation rules act on their own. A rule
+//! pairs a condition on daily metrics with a campaign change, as a JSON
+//! spec posted to `POST /automation/rules`. "Pause any campaign whose CPA
+//! exceeds R$50 for 3 consecutive days" is
+//!
+//! ```json
+//! {"name": "CPA guard",
+//!  "condition": {"metric": "cpa", "operator": "above", "threshold": 50, "days": 3},
+//!  "action": {"type": "pause"}}
+//! ```
+//!
+//! and "raise budget 10% when ROAS > 4" is
+//! `{"condition": {"metric": "roas", "operator": "above", "threshold": 4},
+//! "action": {"type": "adjust_budget", "percent": 10}}`.
+//!
+//! The sync worker evaluates every rule after recording the day's
+//! metrics. Changes run like a bulk request of the actor
+//! `automation:<rule id>` in the tenant of the rule's author, so they are
+//! checked against change freezes and the tenant's spend cap and recorded
+//! in the audit log. Each rule also carries its own guardrails:
+//!
+//! - budgets are kept between `min_daily_budget` and `max_daily_budget`;
+//! - a campaign is changed by a rule at most once per `cooldown_hours`.
+//!
+//! `POST /automation/rules/preview` shows what a rule would change right
+//! now without saving it, and rules created with `"dry_run": true` are
+//! evaluated and logged on every sync without changing anything.
+//! `GET /automation/log` lists what rules did, most recent first.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+use super::bulk::{execute_bulk, BulkOperation};
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, CampaignStatus};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::CampaignId;
+use super::store::{DailyMetrics, MetricsStore};
+
+/// Cooldown of rules created without one
+pub const DEFAULT_COOLDOWN_HOURS: u32 = 24;
+
+/// Executions kept in the log
+pub const MAX_LOG_ENTRIES: usize = 1_000;
+
+fn default_cooldown_hours() -> u32 {
+    DEFAULT_COOLDOWN_HOURS
+}
+
+fn one() -> usize {
+    1
+}
+
+/// Daily metric a condition looks at
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMetric {
+    Cpa,
+    Roas,
+    Ctr,
+    Spend,
+    Conversions,
+}
+
+impl RuleMetric {
+    /// Value of the metric on `day`; `None` when the day says nothing
+    /// about it
+    ///
+    /// A day with spend but no conversions has an infinite CPA, like in
+    /// alert rules; a day without spend has no CPA or ROAS.
+    fn value(self, day: &DailyMetrics) -> Option<f64> {
+        match self {
+            RuleMetric::Cpa => match day.cpa() {
+                Some(cpa) => Some(cpa),
+                None => (day.spend > 0.0).then_some(f64::INFINITY),
+            },
+            RuleMetric::Roas => (day.spend > 0.0).then(|| day.roas()),
+            RuleMetric::Ctr => (day.impressions > 0).then(|| day.ctr()),
+            RuleMetric::Spend => Some(day.spend),
+            RuleMetric::Conversions => Some(day.conversions as f64),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            RuleMetric::Cpa => "CPA",
+            RuleMetric::Roas => "ROAS",
+            RuleMetric::Ctr => "CTR",
+            RuleMetric::Spend => "spend",
+            RuleMetric::Conversions => "conversions",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Above,
+    Below,
+}
+
+/// Metric compared with a threshold on each of the last `days` days
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RuleCondition {
+    pub metric: RuleMetric,
+    pub operator: Operator,
+    pub threshold: f64,
+    /// Consecutive days, today included, the comparison must hold
+    #[serde(default = "one")]
+    pub days: usize,
+}
+
+impl RuleCondition {
+    /// Why the condition holds for a campaign, or `None` when it does not
+    ///
+    /// Campaigns with fewer than `days` days of history never match.
+    pub fn evaluate(&self, campaign: &Campaign, metrics: &MetricsStore) -> Option<String> {
+        let recent = metrics.recent(&campaign.global_id(), self.days);
+        if self.days == 0 || recent.len() < self.days {
+            return None;
+        }
+
+        let holds = recent.iter().all(|day| match self.metric.value(day) {
+            Some(value) => match self.operator {
+                Operator::Above => value > self.threshold,
+                Operator::Below => value < self.threshold,
+            },
+            None => false,
+        });
+
+        holds.then(|| {
+            let operator = match self.operator {
+                Operator::Above => "above",
+                Operator::Below => "below",
+            };
+            let days = match self.days {
+                1 => "today".to_string(),
+                days => format!("for {} consecutive days", days),
+            };
+            format!("{} {} {} {}", self.metric.label(), operator, self.threshold, days)
+        })
+    }
+}
+
+/// Change made to a campaign matching a rule
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    Pause,
+    Enable,
+    /// Change the daily budget by `percent`, e.g. 10 or -20
+    AdjustBudget { percent: f64 },
+}
+
+/// Limits a rule cannot push campaigns past
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Guardrails {
+    #[serde(default)]
+    pub min_daily_budget: Option<f64>,
+    #[serde(default)]
+    pub max_daily_budget: Option<f64>,
+    /// Hours before the rule may change the same campaign again
+    #[serde(default = "default_cooldown_hours")]
+    pub cooldown_hours: u32,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self {
+            min_daily_budget: None,
+            max_daily_budget: None,
+            cooldown_hours: DEFAULT_COOLDOWN_HOURS,
+        }
+    }
+}
+
+impl Guardrails {
+    pub fn cooldown(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.cooldown_hours.into())
+    }
+
+    fn clamp_budget(&self, daily_budget: f64) -> f64 {
+        let budget = self.max_daily_budget.map_or(daily_budget, |max| daily_budget.min(max));
+        self.min_daily_budget.map_or(budget, |min| budget.max(min))
+    }
+}
+
+/// Registered automation rule
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutomationRule {
+    pub id: String,
+    pub name: String,
+    /// Restrict the rule to one campaign; `None` applies it to all campaigns
+    pub campaign_id: Option<CampaignId>,
+    pub condition: RuleCondition,
+    pub action: RuleAction,
+    #[serde(default)]
+    pub guardrails: Guardrails,
+    /// Log what the rule would change instead of changing it
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Tenant of the author, whose spend cap and freezes apply
+    pub tenant: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AutomationRule {
+    /// Actor the rule's changes are made and audited as
+    pub fn actor(&self) -> Actor {
+        Actor::automation(&format!("automation:{}", self.id), self.tenant.clone())
+    }
+
+    /// Operation `action` makes on `campaign`, within the guardrails
+    ///
+    /// `None` when it would change nothing, e.g. pausing a paused campaign
+    /// or raising a budget already at `max_daily_budget`.
+    pub fn operation(&self, campaign: &Campaign) -> Option<BulkOperation> {
+        let (platform, campaign_id) = (campaign.platform.clone(), campaign.id.clone());
+        match &self.action {
+            RuleAction::Pause => (campaign.status == CampaignStatus::Enabled).then_some(
+                BulkOperation::SetStatus {
+                    platform,
+                    campaign_id,
+                    status: CampaignStatus::Paused,
+                },
+            ),
+            RuleAction::Enable => (campaign.status == CampaignStatus::Paused).then_some(
+                BulkOperation::SetStatus {
+                    platform,
+                    campaign_id,
+                    status: CampaignStatus::Enabled,
+                },
+            ),
+            RuleAction::AdjustBudget { percent } => {
+                let target = campaign.daily_budget * (1.0 + percent / 100.0);
+                let daily_budget = (self.guardrails.clamp_budget(target) * 100.0).round() / 100.0;
+                let changes = (daily_budget - campaign.daily_budget).abs() >= 0.01;
+
+                (campaign.status == CampaignStatus::Enabled && changes && daily_budget > 0.0)
+                    .then_some(BulkOperation::SetBudget {
+                        platform,
+                        campaign_id,
+                        daily_budget,
+                    })
+            }
+        }
+    }
+}
+
+/// Request body for `POST /automation/rules` and its preview
+#[derive(Debug, Deserialize)]
+pub struct CreateAutomationRule {
+    pub name: String,
+    pub campaign_id: Option<CampaignId>,
+    pub condition: RuleCondition,
+    pub action: RuleAction,
+    #[serde(default)]
+    pub guardrails: Guardrails,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl CreateAutomationRule {
+    fn validate(&self) -> Result<(), ApiError> {
+        if self.condition.days == 0 || !self.condition.threshold.is_finite() {
+            return Err(ApiError::Validation(
+                "condition needs a finite threshold and at least one day".to_string(),
+            ));
+        }
+        if let RuleAction::AdjustBudget { percent } = self.action {
+            if percent == 0.0 || percent <= -100.0 {
+                return Err(ApiError::Validation(
+                    "percent must be non-zero and above -100".to_string(),
+                ));
+            }
+        }
+        let Guardrails { min_daily_budget, max_daily_budget, .. } = self.guardrails;
+        if min_daily_budget.into_iter().chain(max_daily_budget).any(|b| b <= 0.0)
+            || min_daily_budget.zip(max_daily_budget).is_some_and(|(min, max)| min > max)
+        {
+            return Err(ApiError::Validation(
+                "budget guardrails must be positive, with the minimum below the maximum"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn into_rule(self, actor: &Actor) -> AutomationRule {
+        AutomationRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name,
+            campaign_id: self.campaign_id,
+            condition: self.condition,
+            action: self.action,
+            guardrails: self.guardrails,
+            dry_run: self.dry_run,
+            tenant: actor.tenant.clone(),
+            created_by: actor.id.clone(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Change a rule makes, or would make, to one campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlannedAction {
+    pub rule_id: String,
+    pub campaign_name: String,
+    pub operation: BulkOperation,
+    /// Condition that matched, e.g. `CPA above 50 for 3 consecutive days`
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionOutcome {
+    Executed,
+    /// Logged by a dry-run rule, nothing changed
+    DryRun,
+    /// Rejected by a freeze or spend cap, or by the platform
+    Failed,
+}
+
+/// Log entry of one planned action
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RuleExecution {
+    #[serde(flatten)]
+    pub action: PlannedAction,
+    pub outcome: ExecutionOutcome,
+    /// `ApiError` code when the change failed
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Changes `rule` makes to `campaigns` given their recorded metrics
+///
+/// Campaigns the rule changed, or logged in dry-run, within its cooldown
+/// are left alone.
+pub fn plan_rule(
+    rule: &AutomationRule,
+    campaigns: &[Campaign],
+    metrics: &MetricsStore,
+    log: &AutomationStore,
+    now: DateTime<Utc>,
+) -> Vec<PlannedAction> {
+    campaigns
+        .iter()
+        .filter(|c| rule.campaign_id.iter().all(|id| id == &c.id))
+        .filter(|c| !log.cooling_down(rule, c, now))
+        .filter_map(|campaign| {
+            let reason = rule.condition.evaluate(campaign, metrics)?;
+            Some(PlannedAction {
+                rule_id: rule.id.clone(),
+                campaign_name: campaign.name.clone(),
+                operation: rule.operation(campaign)?,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Rules and their execution log
+#[derive(Default)]
+pub struct AutomationStore {
+    rules: RwLock<Vec<AutomationRule>>,
+    log: RwLock<Vec<RuleExecution>>,
+}
+
+impl AutomationStore {
+    pub fn add_rule(&self, rule: AutomationRule) {
+        self.rules.write().unwrap().push(rule);
+    }
+
+    pub fn rules(&self) -> Vec<AutomationRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Remove a rule; its log entries are kept
+    pub fn remove_rule(&self, id: &str) -> Option<AutomationRule> {
+        let mut rules = self.rules.write().unwrap();
+        let index = rules.iter().position(|r| r.id == id)?;
+        Some(rules.remove(index))
+    }
+
+    pub fn record(&self, execution: RuleExecution) {
+        let mut log = self.log.write().unwrap();
+        if log.len() == MAX_LOG_ENTRIES {
+            log.remove(0);
+        }
+        log.push(execution);
+    }
+
+    /// Whether `rule` acted on `campaign` less than its cooldown ago
+    ///
+    /// Failed changes do not start a cooldown, so they are retried on the
+    /// next sync.
+    pub fn cooling_down(
+        &self,
+        rule: &AutomationRule,
+        campaign: &Campaign,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let since = now - rule.guardrails.cooldown();
+        self.log.read().unwrap().iter().any(|e| {
+            e.action.rule_id == rule.id
+                && e.action.operation.platform() == &campaign.platform
+                && e.action.operation.campaign_id() == &campaign.id
+                && e.outcome != ExecutionOutcome::Failed
+                && e.at > since
+        })
+    }
+
+    /// Log entries, most recent first
+    pub fn log(&self, rule_id: Option<&str>) -> Vec<RuleExecution> {
+        let log = self.log.read().unwrap();
+        log.iter()
+            .rev()
+            .filter(|e| rule_id.iter().all(|id| *id == e.action.rule_id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Evaluate every rule against the synced campaigns and make the changes
+///
+/// Called by the sync worker once the day's metrics are recorded.
+pub async fn run_rules(state: &AppState, campaigns: &[Campaign]) {
+    let now = Utc::now();
+    for rule in state.automation.rules() {
+        let planned = plan_rule(&rule, campaigns, &state.metrics_store, &state.automation, now);
+        if planned.is_empty() {
+            continue;
+        }
+
+        if rule.dry_run {
+            for action in planned {
+                state.automation.record(RuleExecution {
+                    action,
+                    outcome: ExecutionOutcome::DryRun,
+                    error_code: None,
+                    error: None,
+                    at: now,
+                });
+            }
+            continue;
+        }
+
+        let operations = planned.iter().map(|a| a.operation.clone()).collect();
+        let report = execute_bulk(
+            &state.connectors,
+            operations,
+            &state.freezes,
+            &state.spend_caps,
+            &rule.actor(),
+            &state.audit,
+        )
+        .await;
+        tracing::info!(
+            rule_id = %rule.id,
+            succeeded = report.succeeded,
+            failed = report.failed,
+            "automation rule ran"
+        );
+
+        for (action, result) in planned.into_iter().zip(report.results) {
+            state.automation.record(RuleExecution {
+                action,
+                outcome: if result.success {
+                    ExecutionOutcome::Executed
+                } else {
+                    ExecutionOutcome::Failed
+                },
+                error_code: result.error_code,
+                error: result.error,
+                at: now,
+            });
+        }
+    }
+}
+
+/// POST /automation/rules - Register an automation rule
+pub async fn create_automation_rule(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Json(body): Json<CreateAutomationRule>,
+) -> Result<Json<AutomationRule>, ApiError> {
+    body.validate()?;
+    let rule = body.into_rule(&actor);
+
+    state.automation.add_rule(rule.clone());
+
+    Ok(Json(rule))
+}
+
+/// GET /automation/rules - List automation rules
+pub async fn list_automation_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<AutomationRule>>, ApiError> {
+    Ok(Json(state.automation.rules()))
+}
+
+/// DELETE /automation/rules/{id} - Stop and remove an automation rule
+pub async fn delete_automation_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AutomationRule>, ApiError> {
+    state
+        .automation
+        .remove_rule(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("automation rule {}", id)))
+}
+
+/// POST /automation/rules/preview - What a rule would change right now
+///
+/// The rule is not saved. Freezes and spend caps are only checked when
+/// changes run, so a previewed change may still be rejected.
+pub async fn preview_automation_rule(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Json(body): Json<CreateAutomationRule>,
+) -> Result<Json<Vec<PlannedAction>>, ApiError> {
+    body.validate()?;
+    let rule = body.into_rule(&actor);
+    let campaigns = aggregate_campaigns(&state).await;
+
+    Ok(Json(plan_rule(&rule, &campaigns, &state.metrics_store, &state.automation, Utc::now())))
+}
+
+/// Query parameters for `GET /automation/log`
+#[derive(Debug, Deserialize)]
+pub struct AutomationLogQuery {
+    pub rule_id: Option<String>,
+}
+
+/// GET /automation/log - What rules changed, or would have, most recent first
+pub async fn get_automation_log(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AutomationLogQuery>,
+) -> Result<Json<Vec<RuleExecution>>, ApiError> {
+    Ok(Json(state.automation.log(query.rule_id.as_deref())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+    use chrono::NaiveDate;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn rule(condition: RuleCondition, action: RuleAction) -> AutomationRule {
+        CreateAutomationRule {
+            name: "test".to_string(),
+            campaign_id: None,
+            condition,
+            action,
+            guardrails: Guardrails::default(),
+            dry_run: false,
+        }
+        .into_rule(&Actor::automation("ana", Some("acme".to_string())))
+    }
+
+    fn enabled(id: &str) -> Campaign {
+        let mut campaign = Campaign::sample(id, Platform::Google);
+        campaign.status = CampaignStatus::Enabled;
+        campaign.daily_budget = 100.0;
+        campaign
+    }
+
+    #[test]
+    fn test_pause_needs_every_day_above_the_threshold() {
+        let store = MetricsStore::default();
+        let log = AutomationStore::default();
+        let mut campaign = enabled("g1");
+        let cpa = RuleCondition {
+            metric: RuleMetric::Cpa,
+            operator: Operator::Above,
+            threshold: 50.0,
+            days: 3,
+        };
+        let pause = rule(cpa, RuleAction::Pause);
+
+        // Daily CPA of 60, 60, then 60 again
+        for d in 1..=3 {
+            campaign.metrics.cost = d as f64 * 120.0;
+            campaign.metrics.conversions = d * 2;
+            store.record(day(d), &[campaign.clone()]);
+            let planned = plan_rule(&pause, &[campaign.clone()], &store, &log, Utc::now());
+            assert_eq!(planned.len(), usize::from(d == 3));
+        }
+
+        let planned = plan_rule(&pause, &[campaign.clone()], &store, &log, Utc::now());
+        assert_eq!(planned[0].reason, "CPA above 50 for 3 consecutive days");
+        assert_eq!(
+            planned[0].operation,
+            BulkOperation::SetStatus {
+                platform: Platform::Google,
+                campaign_id: "g1".into(),
+                status: CampaignStatus::Paused,
+            }
+        );
+
+        // Nothing to pause once paused
+        campaign.status = CampaignStatus::Paused;
+        assert!(plan_rule(&pause, &[campaign], &store, &log, Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_budget_raises_stop_at_guardrails_and_cool_down() {
+        let store = MetricsStore::default();
+        let log = AutomationStore::default();
+        let mut campaign = enabled("g1");
+        campaign.metrics.cost = 100.0;
+        campaign.metrics.conversion_value = 500.0;
+        store.record(day(1), &[campaign.clone()]);
+
+        let roas = RuleCondition {
+            metric: RuleMetric::Roas,
+            operator: Operator::Above,
+            threshold: 4.0,
+            days: 1,
+        };
+        let mut raise = rule(roas, RuleAction::AdjustBudget { percent: 10.0 });
+        raise.guardrails.max_daily_budget = Some(105.0);
+
+        let now = Utc::now();
+        let planned = plan_rule(&raise, &[campaign.clone()], &store, &log, now);
+        assert!(matches!(
+            planned[0].operation,
+            BulkOperation::SetBudget { daily_budget, .. } if daily_budget == 105.0
+        ));
+
+        log.record(RuleExecution {
+            action: planned[0].clone(),
+            outcome: ExecutionOutcome::Executed,
+            error_code: None,
+            error: None,
+            at: now,
+        });
+        let later = now + chrono::Duration::hours(1);
+        assert!(plan_rule(&raise, &[campaign.clone()], &store, &log, later).is_empty());
+
+        // Past the cooldown, but already at the ceiling
+        campaign.daily_budget = 105.0;
+        let next_day = now + chrono::Duration::hours(25);
+        assert!(plan_rule(&raise, &[campaign], &store, &log, next_day).is_empty());
+    }
+}
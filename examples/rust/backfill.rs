@@ -0,0 +1,473 @@
+//! # Meta Insights Backfill Example
+//!
+//! Backfills historical Meta insights through the gateway. Hourly
+//! breakdowns are expensive for the Insights API, so a date range is
+//! planned as many small windows and fetched one at a time with a pause
+//! between calls. When Meta rejects a window as too large it is split in
+//! half and retried; when the account is throttled the worker waits for
+//! `Retry-After`. Completed windows are recorded on the job, so an
+//! interrupted or failed backfill resumes where it stopped.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+use super::ids::{AccountId, CampaignId};
+
+/// Days per window for hourly breakdowns
+pub const HOURLY_WINDOW_DAYS: i64 = 3;
+
+/// Days per window for daily breakdowns
+pub const DAILY_WINDOW_DAYS: i64 = 30;
+
+/// Pause between two Insights calls of the same job
+pub const REQUEST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Wait applied when a throttled response carries no `Retry-After`
+pub const DEFAULT_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Throttled retries of one window before the job fails
+pub const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Hourly,
+    Daily,
+}
+
+impl Granularity {
+    fn window_days(&self) -> i64 {
+        match self {
+            Granularity::Hourly => HOURLY_WINDOW_DAYS,
+            Granularity::Daily => DAILY_WINDOW_DAYS,
+        }
+    }
+}
+
+/// Inclusive date range sent to the Insights API as `time_range`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BackfillWindow {
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+}
+
+impl BackfillWindow {
+    pub fn days(&self) -> i64 {
+        (self.until - self.since).num_days() + 1
+    }
+
+    /// Split into two halves, `None` for a single-day window
+    pub fn split(&self) -> Option<(BackfillWindow, BackfillWindow)> {
+        if self.days() < 2 {
+            return None;
+        }
+
+        let mid = self.since + Duration::days(self.days() / 2 - 1);
+        Some((
+            BackfillWindow { since: self.since, until: mid },
+            BackfillWindow { since: mid + Duration::days(1), until: self.until },
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BackfillStatus {
+    Running,
+    Completed,
+    /// Stopped on an error; pending windows are kept for resuming
+    Failed,
+}
+
+/// Metrics of one campaign for one hour (or one day for daily backfills)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InsightRow {
+    pub campaign_id: CampaignId,
+    pub date: NaiveDate,
+    /// Hour of day in the ad account's time zone, `None` for daily rows
+    pub hour: Option<u8>,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub spend: f64,
+}
+
+/// Backfill job with its remaining work
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackfillJob {
+    pub id: String,
+    pub account_id: AccountId,
+    pub granularity: Granularity,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub status: BackfillStatus,
+    /// Windows still to fetch, in order
+    pub pending: Vec<BackfillWindow>,
+    pub completed: Vec<BackfillWindow>,
+    pub rows_fetched: usize,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /backfills`
+#[derive(Debug, Deserialize)]
+pub struct CreateBackfill {
+    pub account_id: AccountId,
+    pub granularity: Granularity,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackfillError {
+    #[error("gateway request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// Meta asked to reduce the amount of data requested
+    #[error("window too large")]
+    TooLarge,
+    #[error("rate limited")]
+    RateLimited(Option<std::time::Duration>),
+}
+
+/// Split `start..=end` into consecutive windows sized for `granularity`
+pub fn plan_windows(start: NaiveDate, end: NaiveDate, granularity: Granularity) -> Vec<BackfillWindow> {
+    let mut windows = Vec::new();
+    let mut since = start;
+
+    while since <= end {
+        let until = (since + Duration::days(granularity.window_days() - 1)).min(end);
+        windows.push(BackfillWindow { since, until });
+        since = until + Duration::days(1);
+    }
+
+    windows
+}
+
+/// Insight rows of one campaign by (date, hour)
+type CampaignRows = BTreeMap<(NaiveDate, Option<u8>), InsightRow>;
+
+/// Backfill jobs and the insight rows they fetched
+#[derive(Default)]
+pub struct BackfillStore {
+    jobs: RwLock<HashMap<String, BackfillJob>>,
+    rows: RwLock<HashMap<CampaignId, CampaignRows>>,
+}
+
+impl BackfillStore {
+    pub fn insert(&self, job: BackfillJob) {
+        self.jobs.write().unwrap().insert(job.id.clone(), job);
+    }
+
+    pub fn get(&self, id: &str) -> Option<BackfillJob> {
+        self.jobs.read().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<BackfillJob> {
+        self.jobs.read().unwrap().values().cloned().collect()
+    }
+
+    /// Next window to fetch, `None` once the job has no pending work
+    pub fn next_window(&self, id: &str) -> Option<BackfillWindow> {
+        self.jobs.read().unwrap().get(id)?.pending.first().copied()
+    }
+
+    /// Store the rows of a fetched window and move it to `completed`
+    ///
+    /// Rows are keyed by campaign, date and hour, so re-fetching a window
+    /// after an interruption overwrites instead of duplicating.
+    pub fn complete_window(&self, id: &str, window: BackfillWindow, rows: Vec<InsightRow>) {
+        let mut jobs = self.jobs.write().unwrap();
+        let Some(job) = jobs.get_mut(id) else {
+            return;
+        };
+
+        job.pending.retain(|w| *w != window);
+        job.completed.push(window);
+        job.rows_fetched += rows.len();
+        job.updated_at = Utc::now();
+        if job.pending.is_empty() {
+            job.status = BackfillStatus::Completed;
+        }
+
+        let mut stored = self.rows.write().unwrap();
+        for row in rows {
+            stored
+                .entry(row.campaign_id.clone())
+                .or_default()
+                .insert((row.date, row.hour), row);
+        }
+    }
+
+    /// Replace a pending window with its two halves
+    pub fn split_window(&self, id: &str, window: BackfillWindow) -> bool {
+        let Some((first, second)) = window.split() else {
+            return false;
+        };
+
+        let mut jobs = self.jobs.write().unwrap();
+        let Some(job) = jobs.get_mut(id) else {
+            return false;
+        };
+        let Some(index) = job.pending.iter().position(|w| *w == window) else {
+            return false;
+        };
+
+        job.pending.splice(index..=index, [first, second]);
+        job.updated_at = Utc::now();
+        true
+    }
+
+    pub fn set_status(&self, id: &str, status: BackfillStatus, error: Option<String>) {
+        if let Some(job) = self.jobs.write().unwrap().get_mut(id) {
+            job.status = status;
+            job.error = error;
+            job.updated_at = Utc::now();
+        }
+    }
+
+    /// Stored rows of a campaign, oldest first
+    pub fn rows(&self, campaign_id: &str) -> Vec<InsightRow> {
+        self.rows
+            .read()
+            .unwrap()
+            .get(campaign_id)
+            .map(|rows| rows.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Fetch one window of insights from the gateway
+async fn fetch_window(
+    state: &AppState,
+    job: &BackfillJob,
+    window: BackfillWindow,
+) -> Result<Vec<InsightRow>, BackfillError> {
+    let url = format!("{}/v1/meta/insights", state.gateway_url);
+    let breakdown = match job.granularity {
+        Granularity::Hourly => "hourly",
+        Granularity::Daily => "daily",
+    };
+
+    let response = state.http_client
+        .get(&url)
+        .query(&[
+            ("account_id", job.account_id.to_string()),
+            ("since", window.since.to_string()),
+            ("until", window.until.to_string()),
+            ("breakdown", breakdown.to_string()),
+        ])
+        .send()
+        .await?;
+
+    match response.status() {
+        reqwest::StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .map(std::time::Duration::from_secs);
+            Err(BackfillError::RateLimited(retry_after))
+        }
+        // The gateway maps Meta's "reduce the amount of data" error to 422
+        reqwest::StatusCode::UNPROCESSABLE_ENTITY => Err(BackfillError::TooLarge),
+        _ => Ok(response.error_for_status()?.json::<Vec<InsightRow>>().await?),
+    }
+}
+
+/// Work through the pending windows of a job until done or failed
+pub async fn run_backfill(state: Arc<AppState>, job_id: String) {
+    let mut retries = 0;
+
+    while let Some(window) = state.backfills.next_window(&job_id) {
+        let Some(job) = state.backfills.get(&job_id) else {
+            return;
+        };
+
+        match fetch_window(&state, &job, window).await {
+            Ok(rows) => {
+                retries = 0;
+                state.backfills.complete_window(&job_id, window, rows);
+            }
+            Err(BackfillError::TooLarge) => {
+                if !state.backfills.split_window(&job_id, window) {
+                    let error = format!("window {} is too large to fetch", window.since);
+                    state.backfills.set_status(&job_id, BackfillStatus::Failed, Some(error));
+                    return;
+                }
+            }
+            Err(BackfillError::RateLimited(retry_after)) => {
+                retries += 1;
+                if retries > MAX_RATE_LIMIT_RETRIES {
+                    let error = "rate limit retries exhausted".to_string();
+                    state.backfills.set_status(&job_id, BackfillStatus::Failed, Some(error));
+                    return;
+                }
+                tracing::warn!(job_id = %job_id, retries, "insights throttled, backing off");
+                tokio::time::sleep(retry_after.unwrap_or(DEFAULT_RETRY_AFTER)).await;
+                continue;
+            }
+            Err(e) => {
+                state.backfills.set_status(&job_id, BackfillStatus::Failed, Some(e.to_string()));
+                return;
+            }
+        }
+
+        tokio::time::sleep(REQUEST_INTERVAL).await;
+    }
+}
+
+/// POST /backfills - Plan a backfill and start it in the background
+pub async fn create_backfill(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateBackfill>,
+) -> Result<Json<BackfillJob>, ApiError> {
+    if body.end < body.start {
+        return Err(ApiError::Validation("end must not precede start".to_string()));
+    }
+
+    let now = Utc::now();
+    let job = BackfillJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        account_id: body.account_id,
+        granularity: body.granularity,
+        start: body.start,
+        end: body.end,
+        status: BackfillStatus::Running,
+        pending: plan_windows(body.start, body.end, body.granularity),
+        completed: Vec::new(),
+        rows_fetched: 0,
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    state.backfills.insert(job.clone());
+    tokio::spawn(run_backfill(state.clone(), job.id.clone()));
+
+    Ok(Json(job))
+}
+
+/// GET /backfills - List backfill jobs
+pub async fn list_backfills(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<BackfillJob>>, ApiError> {
+    Ok(Json(state.backfills.list()))
+}
+
+/// GET /backfills/{id} - Progress of one backfill
+pub async fn get_backfill(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BackfillJob>, ApiError> {
+    state
+        .backfills
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("backfill {}", id)))
+}
+
+/// POST /backfills/{id}/resume - Continue a failed backfill from its pending windows
+pub async fn resume_backfill(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BackfillJob>, ApiError> {
+    let job = state
+        .backfills
+        .get(&id)
+        .ok_or_else(|| ApiError::NotFound(format!("backfill {}", id)))?;
+    if job.status != BackfillStatus::Failed {
+        return Err(ApiError::Conflict("only failed backfills can be resumed".to_string()));
+    }
+
+    state.backfills.set_status(&id, BackfillStatus::Running, None);
+    tokio::spawn(run_backfill(state.clone(), id.clone()));
+
+    Ok(Json(state.backfills.get(&id).unwrap_or(job)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn job(pending: Vec<BackfillWindow>) -> BackfillJob {
+        BackfillJob {
+            id: "job-1".to_string(),
+            account_id: "act_1".into(),
+            granularity: Granularity::Hourly,
+            start: day(1),
+            end: day(10),
+            status: BackfillStatus::Running,
+            pending,
+            completed: Vec::new(),
+            rows_fetched: 0,
+            error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn row(date: NaiveDate, hour: u8) -> InsightRow {
+        InsightRow {
+            campaign_id: "m1".into(),
+            date,
+            hour: Some(hour),
+            impressions: 100,
+            clicks: 5,
+            conversions: 1,
+            spend: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_plan_covers_range_in_small_windows() {
+        let windows = plan_windows(day(1), day(10), Granularity::Hourly);
+
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[0], BackfillWindow { since: day(1), until: day(3) });
+        assert_eq!(windows[3], BackfillWindow { since: day(10), until: day(10) });
+        assert_eq!(plan_windows(day(1), day(10), Granularity::Daily).len(), 1);
+    }
+
+    #[test]
+    fn test_split_halves_window_in_place() {
+        let store = BackfillStore::default();
+        let windows = plan_windows(day(1), day(6), Granularity::Hourly);
+        store.insert(job(windows.clone()));
+
+        assert!(store.split_window("job-1", windows[0]));
+
+        let pending = store.get("job-1").unwrap().pending;
+        assert_eq!(pending[0], BackfillWindow { since: day(1), until: day(1) });
+        assert_eq!(pending[1], BackfillWindow { since: day(2), until: day(3) });
+        assert_eq!(pending[2], windows[1]);
+        assert!(!store.split_window("job-1", pending[0]));
+    }
+
+    #[test]
+    fn test_refetched_window_does_not_duplicate_rows() {
+        let store = BackfillStore::default();
+        let window = BackfillWindow { since: day(1), until: day(1) };
+        store.insert(job(vec![window]));
+
+        store.complete_window("job-1", window, vec![row(day(1), 0), row(day(1), 1)]);
+        store.complete_window("job-1", window, vec![row(day(1), 1)]);
+
+        assert_eq!(store.rows("m1").len(), 2);
+        assert_eq!(store.get("job-1").unwrap().status, BackfillStatus::Completed);
+        assert_eq!(store.next_window("job-1"), None);
+    }
+}
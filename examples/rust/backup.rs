@@ -0,0 +1,558 @@
+//! # Disaster Recovery Example
+//!
+//! With `backup.url` set, the service writes a full backup to object
+//! storage every `backup.interval_secs` (daily by default). A backup holds:
+//!
+//! - tenant configuration: naming conventions, the UTM convention,
+//!   campaign group matching rules, alert rules, freeze windows, role
+//!   assignments and insertion orders;
+//! - the historical metrics of the metrics store.
+//!
+//! Backups are JSON encrypted with AES-256-GCM under `backup.key`, so the
+//! bucket never holds readable tenant data. Each one is written to
+//! `backups/<timestamp>.bak` before `backups/LATEST` is pointed at it, so an
+//! upload cut short is never the one restored. An instance holding nothing
+//! skips its backup rather than overwrite the last good one.
+//!
+//! ## Restoring
+//!
+//! The binary's `main` hands its arguments to `Command::from_args`; the
+//! `restore` subcommand rebuilds an instance from the latest backup, or
+//! from the one named with `--backup`:
+//!
+//! ```bash
+//! # Check the backup decrypts and list what it holds, without serving
+//! IBVI_BACKUP_KEY=... ibvi-core restore --dry-run
+//! # Restore, write the metrics snapshot and role assignments to their
+//! # configured paths, then serve
+//! IBVI_BACKUP_KEY=... ibvi-core restore --backup backups/20261014T020000Z.bak
+//! ```
+//!
+//! Restore into an empty instance: rules and windows are added to what the
+//! instance holds, not merged with it.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::alerts::AlertRule;
+use super::campaign_aggregator::AppState;
+use super::campaign_groups::MatchingRule;
+use super::config::BackupConfig;
+use super::freeze::FreezeWindow;
+use super::insertion_orders::InsertionOrder;
+use super::naming::NamingConvention;
+use super::rbac::RoleAssignment;
+use super::region::Region;
+use super::server::{self, ServerOptions};
+use super::store::{series_keys, SnapshotSeries};
+use super::utm::UtmConvention;
+
+/// Format version written into every backup
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Object naming the most recent complete backup
+pub const LATEST_KEY: &str = "backups/LATEST";
+
+/// Leading bytes of an encrypted backup
+const MAGIC: &[u8] = b"IBVIBAK1";
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("backups are not configured (backup.url)")]
+    NotConfigured,
+    #[error("invalid backup key: {0}")]
+    Key(String),
+    #[error("backup {0} not found")]
+    NotFound(String),
+    #[error("object storage request failed: {0}")]
+    Storage(String),
+    #[error("backup cannot be decrypted: wrong key or corrupted object")]
+    Decrypt,
+    #[error("backup version {0} is not supported")]
+    Version(u32),
+    #[error("invalid backup contents: {0}")]
+    Format(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Everything needed to rebuild an instance
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Region of the instance backed up; restore in the same region
+    pub region: Region,
+    pub naming_conventions: Vec<NamingConvention>,
+    /// Missing from backups taken before UTM audits
+    #[serde(default)]
+    pub utm_convention: UtmConvention,
+    pub matching_rules: Vec<MatchingRule>,
+    pub alert_rules: Vec<AlertRule>,
+    pub freeze_windows: Vec<FreezeWindow>,
+    pub role_assignments: Vec<RoleAssignment>,
+    pub insertion_orders: Vec<InsertionOrder>,
+    #[serde(with = "series_keys")]
+    pub metrics: SnapshotSeries,
+}
+
+/// What a backup holds, logged by `restore`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BackupSummary {
+    pub created_at: DateTime<Utc>,
+    pub region: Region,
+    pub naming_conventions: usize,
+    pub matching_rules: usize,
+    pub alert_rules: usize,
+    pub freeze_windows: usize,
+    pub role_assignments: usize,
+    pub insertion_orders: usize,
+    /// Campaigns with a metrics history
+    pub campaigns: usize,
+    pub snapshots: usize,
+}
+
+impl Backup {
+    /// Copy the configuration and metrics history held by `state`
+    pub fn capture(state: &AppState) -> Self {
+        Self {
+            version: BACKUP_VERSION,
+            created_at: Utc::now(),
+            region: state.config.region.current,
+            naming_conventions: state.naming.get(),
+            utm_convention: state.utm.get(),
+            matching_rules: state.matching_rules.get(),
+            alert_rules: state.alerts.rules(),
+            freeze_windows: state.freezes.windows(),
+            role_assignments: state.roles.list(),
+            insertion_orders: state.insertion_orders.list(),
+            metrics: state.metrics_store.snapshots(),
+        }
+    }
+
+    /// Load everything into `state`
+    pub fn restore_into(self, state: &AppState) {
+        state.naming.set(self.naming_conventions);
+        state.utm.set(self.utm_convention);
+        state.matching_rules.set(self.matching_rules);
+        for rule in self.alert_rules {
+            state.alerts.add_rule(rule);
+        }
+        for window in self.freeze_windows {
+            state.freezes.add_window(window);
+        }
+        for assignment in self.role_assignments {
+            state.roles.assign(assignment);
+        }
+        for order in self.insertion_orders {
+            state.insertion_orders.insert(order);
+        }
+        state.metrics_store.merge(self.metrics);
+    }
+
+    pub fn summary(&self) -> BackupSummary {
+        BackupSummary {
+            created_at: self.created_at,
+            region: self.region,
+            naming_conventions: self.naming_conventions.len(),
+            matching_rules: self.matching_rules.len(),
+            alert_rules: self.alert_rules.len(),
+            freeze_windows: self.freeze_windows.len(),
+            role_assignments: self.role_assignments.len(),
+            insertion_orders: self.insertion_orders.len(),
+            campaigns: self.metrics.len(),
+            snapshots: self.metrics.values().map(|series| series.len()).sum(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        let summary = self.summary();
+        summary.naming_conventions
+            + summary.matching_rules
+            + summary.alert_rules
+            + summary.freeze_windows
+            + summary.role_assignments
+            + summary.insertion_orders
+            + summary.snapshots
+            == 0
+    }
+}
+
+/// Bucket backups are written to
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), BackupError>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BackupError>;
+}
+
+/// S3-compatible endpoint addressed as `<url>/<key>`
+pub struct HttpObjectStore {
+    pub http_client: reqwest::Client,
+    pub url: String,
+    pub token: Option<String>,
+}
+
+impl HttpObjectStore {
+    fn request(&self, method: reqwest::Method, key: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.url.trim_end_matches('/'), key);
+        let request = self.http_client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for HttpObjectStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), BackupError> {
+        self.request(reqwest::Method::PUT, key)
+            .body(bytes)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| BackupError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BackupError> {
+        let response = self
+            .request(reqwest::Method::GET, key)
+            .send()
+            .await
+            .map_err(|e| BackupError::Storage(e.to_string()))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(BackupError::NotFound(key.to_string()));
+        }
+        let bytes = response
+            .error_for_status()
+            .map_err(|e| BackupError::Storage(e.to_string()))?
+            .bytes()
+            .await
+            .map_err(|e| BackupError::Storage(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Directory on a mounted volume, for `file://` URLs
+pub struct DirObjectStore {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl ObjectStore for DirObjectStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), BackupError> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Written aside and renamed so a reader never sees half an object
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(tmp, path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BackupError> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(BackupError::NotFound(key.to_string()))
+            }
+            result => Ok(result?),
+        }
+    }
+}
+
+/// Bucket and key backups are written to and read from
+pub struct BackupTarget {
+    store: Box<dyn ObjectStore>,
+    cipher: Aes256Gcm,
+}
+
+impl BackupTarget {
+    pub fn new(store: Box<dyn ObjectStore>, key: &str) -> Result<Self, BackupError> {
+        let key = hex::decode(key.trim()).map_err(|e| BackupError::Key(e.to_string()))?;
+        if key.len() != 32 {
+            return Err(BackupError::Key("expected 32 bytes".to_string()));
+        }
+        Ok(Self {
+            store,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+
+    /// Target configured by `backup`, `None` when backups are disabled
+    pub fn from_config(config: &BackupConfig) -> Result<Option<Self>, BackupError> {
+        let Some(url) = &config.url else {
+            return Ok(None);
+        };
+        let store: Box<dyn ObjectStore> = match url.strip_prefix("file://") {
+            Some(root) => Box::new(DirObjectStore {
+                root: PathBuf::from(root),
+            }),
+            None => Box::new(HttpObjectStore {
+                http_client: reqwest::Client::new(),
+                url: url.clone(),
+                token: config.token.clone(),
+            }),
+        };
+        Self::new(store, &config.key).map(Some)
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, BackupError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| BackupError::Decrypt)?;
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+
+    fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, BackupError> {
+        let sealed = bytes.strip_prefix(MAGIC).ok_or(BackupError::Decrypt)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(BackupError::Decrypt);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| BackupError::Decrypt)
+    }
+
+    /// Encrypt and upload `backup`, then point `LATEST` at it
+    pub async fn write(&self, backup: &Backup) -> Result<String, BackupError> {
+        let key = format!("backups/{}.bak", backup.created_at.format("%Y%m%dT%H%M%SZ"));
+        let sealed = self.encrypt(&serde_json::to_vec(backup)?)?;
+
+        self.store.put(&key, sealed).await?;
+        self.store.put(LATEST_KEY, key.clone().into_bytes()).await?;
+        Ok(key)
+    }
+
+    /// Download and decrypt the backup at `key`, or the latest one
+    pub async fn read(&self, key: Option<&str>) -> Result<Backup, BackupError> {
+        let key = match key {
+            Some(key) => key.to_string(),
+            None => {
+                let latest = self.store.get(LATEST_KEY).await?;
+                String::from_utf8_lossy(&latest).trim().to_string()
+            }
+        };
+
+        let plaintext = self.decrypt(&self.store.get(&key).await?)?;
+        let backup: Backup = serde_json::from_slice(&plaintext)?;
+        if backup.version > BACKUP_VERSION {
+            return Err(BackupError::Version(backup.version));
+        }
+        Ok(backup)
+    }
+}
+
+/// Back up `state` once; skipped while the instance holds nothing
+pub async fn backup_once(state: &AppState, target: &BackupTarget) {
+    let backup = Backup::capture(state);
+    if backup.is_empty() {
+        tracing::info!("instance holds no data yet, backup skipped");
+        return;
+    }
+
+    match target.write(&backup).await {
+        Ok(key) => tracing::info!(key = %key, "backup written"),
+        Err(e) => tracing::error!(error = %e, "backup failed"),
+    }
+}
+
+/// Back up every `interval` until `shutdown` is cancelled
+///
+/// The first backup is taken one interval after startup, so an instance
+/// restarting in a loop does not upload a backup per restart.
+pub async fn run_backup_scheduler(
+    state: Arc<AppState>,
+    target: BackupTarget,
+    interval: Duration,
+    shutdown: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => backup_once(&state, &target).await,
+        }
+    }
+
+    tracing::info!("backup scheduler stopped");
+}
+
+/// Subcommands of the service binary
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Serve the API; no subcommand
+    Serve,
+    /// Rebuild the instance from a backup, then serve
+    Restore {
+        /// Object key of the backup; the latest when `None`
+        backup: Option<String>,
+        /// Only check the backup and log what it holds
+        dry_run: bool,
+    },
+}
+
+impl Command {
+    /// Parse the arguments after the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        match args.next().as_deref() {
+            None => Ok(Command::Serve),
+            Some("restore") => {
+                let (mut backup, mut dry_run) = (None, false);
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--backup" => {
+                            backup = Some(args.next().ok_or("--backup needs an object key")?);
+                        }
+                        "--dry-run" => dry_run = true,
+                        other => return Err(format!("unknown restore option {:?}", other)),
+                    }
+                }
+                Ok(Command::Restore { backup, dry_run })
+            }
+            Some(other) => Err(format!("unknown command {:?}, expected restore", other)),
+        }
+    }
+}
+
+/// Run `restore`: load the backup into `state`, write the parts the server
+/// reads back on startup to their configured paths, then serve
+pub async fn run_restore(
+    state: Arc<AppState>,
+    options: ServerOptions,
+    backup: Option<String>,
+    dry_run: bool,
+) -> Result<(), BackupError> {
+    let target = BackupTarget::from_config(&state.config.backup)?
+        .ok_or(BackupError::NotConfigured)?;
+    let restored = target.read(backup.as_deref()).await?;
+    let summary = restored.summary();
+    tracing::info!(?summary, "backup read");
+
+    if restored.region != state.config.region.current {
+        tracing::warn!(
+            backup_region = restored.region.as_str(),
+            "restoring a backup taken in another region"
+        );
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    restored.restore_into(&state);
+    if let Some(path) = &options.snapshot_path {
+        state.metrics_store.persist(path)?;
+    }
+    if let Some(path) = &state.config.rbac.assignments_path {
+        state.roles.persist(path)?;
+    }
+    tracing::info!("backup restored");
+
+    server::run(state, options).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{Campaign, Platform};
+    use super::super::config::AppConfig;
+    use super::super::rbac::Role;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn state() -> Arc<AppState> {
+        let mut config = AppConfig::default();
+        config.gateway.service_token = "test".to_string();
+        Arc::new(AppState::from_config(config).unwrap())
+    }
+
+    fn target(root: &std::path::Path, key: &str) -> BackupTarget {
+        let store = DirObjectStore {
+            root: root.to_path_buf(),
+        };
+        BackupTarget::new(Box::new(store), key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_restore_rebuilds_configuration_and_metrics() {
+        let root = std::env::temp_dir().join(format!("backup-{}", uuid::Uuid::new_v4()));
+        let source = state();
+        source.matching_rules.set(vec![MatchingRule::UtmCampaign]);
+        source.roles.assign(RoleAssignment {
+            actor: "alice".to_string(),
+            tenant: None,
+            role: Role::Manager,
+            assigned_by: "admin".to_string(),
+            assigned_at: Utc::now(),
+        });
+        let today = Utc::now().date_naive();
+        source
+            .metrics_store
+            .record(today, &[Campaign::sample("1", Platform::Google)]);
+
+        let key = target(&root, KEY).write(&Backup::capture(&source)).await.unwrap();
+        assert!(key.starts_with("backups/"));
+
+        let rebuilt = state();
+        target(&root, KEY).read(None).await.unwrap().restore_into(&rebuilt);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(rebuilt.matching_rules.get(), vec![MatchingRule::UtmCampaign]);
+        assert_eq!(rebuilt.roles.role_for("alice", None), Some(Role::Manager));
+        assert_eq!(rebuilt.metrics_store.snapshots(), source.metrics_store.snapshots());
+    }
+
+    #[tokio::test]
+    async fn test_backup_is_unreadable_without_its_key() {
+        let root = std::env::temp_dir().join(format!("backup-{}", uuid::Uuid::new_v4()));
+        let source = state();
+        source.matching_rules.set(vec![MatchingRule::UtmCampaign]);
+        let key = target(&root, KEY).write(&Backup::capture(&source)).await.unwrap();
+
+        let stored = std::fs::read(root.join(&key)).unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("utm_campaign"));
+
+        let other_key = "ff".repeat(32);
+        let result = target(&root, &other_key).read(Some(&key)).await;
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(matches!(result, Err(BackupError::Decrypt)));
+    }
+
+    #[test]
+    fn test_restore_subcommand_arguments() {
+        let args = |list: &[&str]| Command::from_args(list.iter().map(|a| a.to_string()));
+
+        assert_eq!(args(&[]), Ok(Command::Serve));
+        assert_eq!(
+            args(&["restore", "--backup", "backups/x.bak", "--dry-run"]),
+            Ok(Command::Restore {
+                backup: Some("backups/x.bak".to_string()),
+                dry_run: true
+            })
+        );
+        assert!(args(&["restore", "--backup"]).is_err());
+        assert!(args(&["backup"]).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! # Vertical Benchmarks Example
+//!
+//! A 1.2% CTR is poor for search ads in one industry and fine in another.
+//! `GET /campaigns/benchmark` scores each campaign's month-to-date CTR,
+//! CPC and CPA against the baseline of its vertical (`benchmarks.verticals`),
+//! and flags the campaigns significantly below it for review.
+//!
+//! - A campaign's vertical is its naming-convention tag (`benchmarks.tag`,
+//!   `vertical` by default), else that of its ad account
+//!   (`benchmarks.accounts`), else `benchmarks.default_vertical`. Campaigns
+//!   without one are counted as unbenchmarked.
+//! - Each metric gets an index, 100 meaning on the baseline and higher
+//!   meaning better (a CPC half the baseline's scores 200); the campaign's
+//!   score is the mean of its indices.
+//! - A metric underperforms when it misses the baseline by more than
+//!   `benchmarks.tolerance` (20% by default) on enough data: CTR when a
+//!   one-sided z-test against the baseline rate is significant at 95%, CPC
+//!   from `benchmarks.min_clicks` clicks, CPA from
+//!   `benchmarks.min_conversions` conversions. A campaign that spent twice
+//!   the baseline CPA without converting underperforms on CPA too.
+//! - CPC and CPA are only compared in the baseline's currency; amounts are
+//!   never converted.
+//!
+//! Removed campaigns and campaigns without impressions are left out.
+//! `?flagged=true` keeps the underperformers only, `?vertical=` one vertical.
+//! Baselines changed in the configuration file apply on `POST /admin/reload`.
+
+use axum::extract::{Query, State};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::archive;
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, Platform};
+use super::config::{BenchmarkConfig, VerticalBaseline};
+use super::error::ApiError;
+use super::experiments::{normal_cdf, DEFAULT_CONFIDENCE_LEVEL};
+use super::finite::{safe_div, Finite};
+use super::ids::CampaignId;
+
+/// Spend without conversions, in baseline CPAs, past which CPA underperforms
+pub const ZERO_CONVERSION_CPA_MULTIPLE: f64 = 2.0;
+
+/// Query parameters for `GET /campaigns/benchmark`
+#[derive(Debug, Deserialize, Default)]
+pub struct BenchmarkQuery {
+    pub vertical: Option<String>,
+    /// Only campaigns flagged for review
+    #[serde(default)]
+    pub flagged: bool,
+}
+
+/// One metric of a campaign against its vertical's baseline
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct MetricBenchmark {
+    /// `ctr`, `cpc` or `cpa`
+    pub metric: &'static str,
+    /// `None` for a CPA without conversions
+    pub value: Option<f64>,
+    pub baseline: f64,
+    /// 100 on the baseline, higher is better; `None` without a value
+    pub index: Option<f64>,
+    /// Below the baseline by more than the tolerance, on enough data
+    pub underperforming: bool,
+}
+
+/// Benchmark of one campaign
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CampaignBenchmark {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub name: String,
+    pub vertical: String,
+    /// Mean of the metric indices
+    pub score: Option<f64>,
+    pub metrics: Vec<MetricBenchmark>,
+    /// Some metric underperforms; the campaign should be reviewed
+    pub flagged: bool,
+}
+
+/// Response of `GET /campaigns/benchmark`
+#[derive(Debug, Serialize)]
+pub struct BenchmarkReport {
+    /// Flagged campaigns first, then lowest score first
+    pub campaigns: Vec<CampaignBenchmark>,
+    pub flagged: usize,
+    /// Campaigns without a vertical, or whose vertical has no baseline
+    pub unbenchmarked: usize,
+}
+
+/// Vertical of `campaign`, see the module docs
+pub fn vertical_of<'a>(config: &'a BenchmarkConfig, campaign: &'a Campaign) -> Option<&'a str> {
+    campaign
+        .tags
+        .get(&config.tag)
+        .or_else(|| campaign.account_id.as_ref().and_then(|a| config.accounts.get(a)))
+        .or(config.default_vertical.as_ref())
+        .map(String::as_str)
+}
+
+/// Whether a CTR of `clicks / impressions` is significantly below
+/// `baseline_ctr` (percent), by a one-sided z-test
+fn ctr_significantly_below(clicks: u64, impressions: u64, baseline_ctr: f64) -> bool {
+    let p = baseline_ctr / 100.0;
+    if impressions == 0 || !(p > 0.0 && p < 1.0) {
+        return false;
+    }
+    let observed = clicks as f64 / impressions as f64;
+    let z = (observed - p) / (p * (1.0 - p) / impressions as f64).sqrt();
+    normal_cdf(z) < 1.0 - DEFAULT_CONFIDENCE_LEVEL
+}
+
+/// Benchmark `campaign` against `baseline`
+pub fn benchmark_campaign(
+    config: &BenchmarkConfig,
+    campaign: &Campaign,
+    vertical: &str,
+    baseline: &VerticalBaseline,
+) -> CampaignBenchmark {
+    let metrics = &campaign.metrics;
+    // Below this index a metric misses the baseline by more than the tolerance
+    let floor = 100.0 * (1.0 - config.tolerance);
+
+    let ctr_index = safe_div(metrics.ctr * 100.0, baseline.ctr);
+    let mut benchmarks = vec![MetricBenchmark {
+        metric: "ctr",
+        value: Some(metrics.ctr),
+        baseline: baseline.ctr,
+        index: ctr_index,
+        underperforming: ctr_index.is_some_and(|i| i < floor)
+            && ctr_significantly_below(metrics.clicks, metrics.impressions, baseline.ctr),
+    }];
+
+    if campaign.currency.eq_ignore_ascii_case(&baseline.currency) {
+        let cpc = safe_div(metrics.cost, metrics.clicks as f64);
+        let cpc_index = cpc.and_then(|cpc| safe_div(baseline.cpc * 100.0, cpc));
+        benchmarks.push(MetricBenchmark {
+            metric: "cpc",
+            value: cpc,
+            baseline: baseline.cpc,
+            index: cpc_index,
+            underperforming: cpc_index.is_some_and(|i| i < floor)
+                && metrics.clicks >= config.min_clicks,
+        });
+
+        let converted = metrics.conversions > 0;
+        let cpa = converted.then_some(metrics.cpa);
+        let cpa_index = cpa.and_then(|cpa| safe_div(baseline.cpa * 100.0, cpa));
+        let underperforming = if converted {
+            cpa_index.is_some_and(|i| i < floor) && metrics.conversions >= config.min_conversions
+        } else {
+            metrics.cost >= baseline.cpa * ZERO_CONVERSION_CPA_MULTIPLE
+        };
+        benchmarks.push(MetricBenchmark {
+            metric: "cpa",
+            value: cpa,
+            baseline: baseline.cpa,
+            index: cpa_index,
+            underperforming,
+        });
+    }
+
+    let indices: Vec<f64> = benchmarks.iter().filter_map(|m| m.index).collect();
+    CampaignBenchmark {
+        platform: campaign.platform.clone(),
+        campaign_id: campaign.id.clone(),
+        name: campaign.name.clone(),
+        vertical: vertical.to_string(),
+        score: safe_div(indices.iter().sum(), indices.len() as f64),
+        flagged: benchmarks.iter().any(|m| m.underperforming),
+        metrics: benchmarks,
+    }
+}
+
+/// Benchmark every delivering campaign with a vertical baseline
+pub fn benchmark_campaigns(config: &BenchmarkConfig, campaigns: &[Campaign]) -> BenchmarkReport {
+    let mut benchmarked = Vec::new();
+    let mut unbenchmarked = 0;
+    for campaign in campaigns.iter().filter(|c| c.metrics.impressions > 0) {
+        let found = vertical_of(config, campaign)
+            .and_then(|vertical| Some((vertical, config.verticals.get(vertical)?)));
+        match found {
+            Some((vertical, baseline)) => {
+                benchmarked.push(benchmark_campaign(config, campaign, vertical, baseline))
+            }
+            None => unbenchmarked += 1,
+        }
+    }
+
+    benchmarked.sort_by(|a, b| {
+        b.flagged.cmp(&a.flagged).then_with(|| {
+            let (a, b) = (a.score.unwrap_or(f64::MAX), b.score.unwrap_or(f64::MAX));
+            a.total_cmp(&b)
+        })
+    });
+    BenchmarkReport {
+        flagged: benchmarked.iter().filter(|c| c.flagged).count(),
+        campaigns: benchmarked,
+        unbenchmarked,
+    }
+}
+
+/// GET /campaigns/benchmark - Campaigns scored against their vertical's
+/// baseline, underperformers first
+pub async fn get_campaign_benchmarks(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BenchmarkQuery>,
+) -> Result<Finite<BenchmarkReport>, ApiError> {
+    let live_config = state.live_config.get();
+    let config = &live_config.benchmarks;
+    if let Some(vertical) = &query.vertical {
+        if !config.verticals.contains_key(vertical) {
+            return Err(ApiError::Validation(format!("no baseline for vertical {:?}", vertical)));
+        }
+    }
+
+    let mut campaigns = aggregate_campaigns(&state).await;
+    archive::exclude_removed(&mut campaigns);
+    if let Some(vertical) = &query.vertical {
+        campaigns.retain(|c| vertical_of(config, c) == Some(vertical.as_str()));
+    }
+
+    let mut report = benchmark_campaigns(config, &campaigns);
+    if query.flagged {
+        report.campaigns.retain(|c| c.flagged);
+    }
+    Ok(Finite(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BenchmarkConfig {
+        let mut config = BenchmarkConfig {
+            default_vertical: Some("retail".to_string()),
+            ..Default::default()
+        };
+        let baseline = |ctr, cpc, cpa| VerticalBaseline {
+            ctr,
+            cpc,
+            cpa,
+            currency: "USD".to_string(),
+        };
+        config.verticals.insert("retail".to_string(), baseline(2.0, 1.0, 20.0));
+        config.verticals.insert("real_estate".to_string(), baseline(1.0, 2.0, 80.0));
+        config
+    }
+
+    fn campaign(id: &str, impressions: u64, clicks: u64, conversions: u32, cost: f64) -> Campaign {
+        let mut campaign = Campaign::sample(id, Platform::Google);
+        campaign.currency = "USD".to_string();
+        let metrics = &mut campaign.metrics;
+        metrics.impressions = impressions;
+        metrics.clicks = clicks;
+        metrics.conversions = conversions;
+        metrics.cost = cost;
+        metrics.ctr = clicks as f64 / impressions.max(1) as f64 * 100.0;
+        metrics.cpa = if conversions > 0 { cost / conversions as f64 } else { 0.0 };
+        campaign
+    }
+
+    #[test]
+    fn test_underperformers_need_a_significant_shortfall() {
+        let config = config();
+        // Retail: CTR 1% on 10k impressions, CPC 1.5 on 100 clicks
+        let weak = campaign("weak", 10_000, 100, 10, 150.0);
+        // Same CTR on 100 impressions: too little data to judge
+        let small = campaign("small", 100, 1, 0, 1.0);
+        // Real estate by tag: on its baseline
+        let mut tagged = campaign("tagged", 20_000, 200, 5, 400.0);
+        tagged.tags.insert("vertical".to_string(), "real_estate".to_string());
+        // No conversions after spending three retail CPAs
+        let burning = campaign("burning", 1_000, 20, 0, 60.0);
+
+        let report = benchmark_campaigns(&config, &[weak, small, tagged, burning]);
+        let flagged: Vec<&str> = report
+            .campaigns
+            .iter()
+            .filter(|c| c.flagged)
+            .map(|c| c.campaign_id.as_str())
+            .collect();
+        // Lowest score first: CTR and CPC indices of 100 and 33 against 50, 67 and 133
+        assert_eq!(flagged, ["burning", "weak"]);
+        assert_eq!(report.flagged, 2);
+
+        let weak = &report.campaigns[1];
+        let index = |metric| weak.metrics.iter().find(|m| m.metric == metric).unwrap().index;
+        assert_eq!(index("ctr"), Some(50.0));
+        assert!((index("cpc").unwrap() - 66.67).abs() < 0.01);
+        assert!((index("cpa").unwrap() - 133.33).abs() < 0.01);
+        let underperforming: Vec<_> =
+            weak.metrics.iter().filter(|m| m.underperforming).map(|m| m.metric).collect();
+        assert_eq!(underperforming, ["ctr", "cpc"]);
+
+        let tagged = report.campaigns.iter().find(|c| c.campaign_id == "tagged").unwrap();
+        assert_eq!((tagged.vertical.as_str(), tagged.score), ("real_estate", Some(100.0)));
+    }
+}
@@ -0,0 +1,448 @@
+//! # Bid Strategy Example
+//!
+//! Adds how a campaign bids to the unified model. Google Ads reports a
+//! `bidding_strategy_type` with targets in micros; Meta reports a
+//! `bid_strategy` with amounts in the currency's minor unit and ROAS
+//! floors scaled by 10 000. Both are normalized into one `Bidding` block
+//! with a `BidStrategy` and targets in the campaign currency:
+//!
+//! - `GET /campaigns` carries `bidding` on every campaign and filters with
+//!   `?bid_strategy=target_cpa,target_roas`;
+//! - `PATCH /campaigns/{platform}/{id}/bidding` switches a campaign's
+//!   strategy, subject to change freezes like every other mutation.
+//!
+//! Google's Maximize conversions with a target CPA (and Maximize
+//! conversion value with a target ROAS) is reported as `target_cpa`
+//! (`target_roas`), so the same goal reads the same on both platforms.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::call_budget;
+use super::campaign_aggregator::{AppState, Campaign, Platform};
+use super::connectors::{ConnectorError, ConnectorRegistry};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Google Ads amounts are in millionths of the currency unit
+const MICROS: f64 = 1_000_000.0;
+
+/// Meta amounts are in cents
+const META_MINOR_UNITS: f64 = 100.0;
+
+/// Meta ROAS floors are scaled by 10 000 (`10000` is a ROAS of 1.0)
+const META_ROAS_SCALE: f64 = 10_000.0;
+
+/// Unified bid strategy
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BidStrategy {
+    TargetCpa,
+    TargetRoas,
+    MaximizeConversions,
+    MaximizeConversionValue,
+    MaximizeClicks,
+    ManualCpc,
+    ManualCpm,
+    TargetImpressionShare,
+    /// Meta's default: spend the budget at the lowest cost per result
+    LowestCost,
+    /// Meta: lowest cost with a cap on each auction bid
+    BidCap,
+    /// Meta: lowest cost keeping the average cost per result under a cap
+    CostCap,
+    /// Meta: lowest cost with a minimum ROAS
+    MinRoas,
+    /// Strategy without a unified equivalent, see `platform_strategy`
+    Other,
+}
+
+impl BidStrategy {
+    /// Parse a comma-separated list, e.g. `target_cpa,manual_cpc`
+    pub fn parse_list(list: &str) -> Result<Vec<BidStrategy>, ApiError> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                serde_json::from_value(serde_json::Value::String(item.to_string()))
+                    .map_err(|_| ApiError::Validation(format!("unknown bid strategy {:?}", item)))
+            })
+            .collect()
+    }
+}
+
+/// Normalized bidding of a campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Bidding {
+    pub strategy: BidStrategy,
+    /// Target cost per conversion (Meta cost cap), in the campaign currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_cpa: Option<f64>,
+    /// Target conversion value per unit of spend, e.g. `4.0` for 400%
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_roas: Option<f64>,
+    /// Highest bid per auction (Google CPC ceiling, Meta bid cap)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bid_limit: Option<f64>,
+    /// Strategy as named by the platform, e.g. `TARGET_SPEND`
+    pub platform_strategy: String,
+}
+
+/// Body of `PATCH /campaigns/{platform}/{id}/bidding`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BiddingChange {
+    pub strategy: BidStrategy,
+    #[serde(default)]
+    pub target_cpa: Option<f64>,
+    #[serde(default)]
+    pub target_roas: Option<f64>,
+    #[serde(default)]
+    pub bid_limit: Option<f64>,
+}
+
+impl BiddingChange {
+    /// Check the targets the strategy needs are present and positive
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let required = match self.strategy {
+            BidStrategy::TargetCpa | BidStrategy::CostCap => Some(("target_cpa", self.target_cpa)),
+            BidStrategy::TargetRoas | BidStrategy::MinRoas => {
+                Some(("target_roas", self.target_roas))
+            }
+            BidStrategy::BidCap => Some(("bid_limit", self.bid_limit)),
+            BidStrategy::Other => {
+                let message = "strategy must be a unified bid strategy";
+                return Err(ApiError::Validation(message.to_string()));
+            }
+            _ => None,
+        };
+        if let Some((field, None)) = required {
+            return Err(ApiError::Validation(format!("{} is required for this strategy", field)));
+        }
+
+        for (field, value) in [
+            ("target_cpa", self.target_cpa),
+            ("target_roas", self.target_roas),
+            ("bid_limit", self.bid_limit),
+        ] {
+            if value.is_some_and(|v| !v.is_finite() || v <= 0.0) {
+                return Err(ApiError::Validation(format!("{} must be positive", field)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Google Ads bidding fields, as read and written by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GoogleBiddingSettings {
+    pub bidding_strategy_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_cpa_micros: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_roas: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpc_bid_ceiling_micros: Option<i64>,
+}
+
+/// Campaign bidding as returned by the Google gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleBidding {
+    pub campaign_id: CampaignId,
+    #[serde(flatten)]
+    pub settings: GoogleBiddingSettings,
+}
+
+/// Meta bidding fields, as read and written by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct MetaBiddingSettings {
+    /// Absent when the campaign uses Meta's default (lowest cost)
+    #[serde(default)]
+    pub bid_strategy: Option<String>,
+    /// Bid or cost cap, in cents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bid_amount: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roas_average_floor: Option<i64>,
+}
+
+/// Campaign bidding as returned by the Meta gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaBidding {
+    pub campaign_id: CampaignId,
+    #[serde(flatten)]
+    pub settings: MetaBiddingSettings,
+}
+
+fn from_micros(micros: Option<i64>) -> Option<f64> {
+    micros.map(|m| m as f64 / MICROS)
+}
+
+fn to_micros(amount: Option<f64>) -> Option<i64> {
+    amount.map(|a| (a * MICROS).round() as i64)
+}
+
+pub fn normalize_google(raw: &GoogleBiddingSettings) -> Bidding {
+    let target_cpa = from_micros(raw.target_cpa_micros);
+    let strategy = match raw.bidding_strategy_type.as_str() {
+        "TARGET_CPA" => BidStrategy::TargetCpa,
+        "MAXIMIZE_CONVERSIONS" if target_cpa.is_some() => BidStrategy::TargetCpa,
+        "MAXIMIZE_CONVERSIONS" => BidStrategy::MaximizeConversions,
+        "TARGET_ROAS" => BidStrategy::TargetRoas,
+        "MAXIMIZE_CONVERSION_VALUE" if raw.target_roas.is_some() => BidStrategy::TargetRoas,
+        "MAXIMIZE_CONVERSION_VALUE" => BidStrategy::MaximizeConversionValue,
+        // Maximize clicks is still `TARGET_SPEND` in the API
+        "TARGET_SPEND" | "MAXIMIZE_CLICKS" => BidStrategy::MaximizeClicks,
+        "MANUAL_CPC" | "ENHANCED_CPC" => BidStrategy::ManualCpc,
+        "MANUAL_CPM" => BidStrategy::ManualCpm,
+        "TARGET_IMPRESSION_SHARE" => BidStrategy::TargetImpressionShare,
+        _ => BidStrategy::Other,
+    };
+
+    Bidding {
+        strategy,
+        target_cpa,
+        target_roas: raw.target_roas,
+        bid_limit: from_micros(raw.cpc_bid_ceiling_micros),
+        platform_strategy: raw.bidding_strategy_type.clone(),
+    }
+}
+
+pub fn normalize_meta(raw: &MetaBiddingSettings) -> Bidding {
+    let amount = raw.bid_amount.map(|a| a as f64 / META_MINOR_UNITS);
+    let platform_strategy = raw
+        .bid_strategy
+        .clone()
+        .unwrap_or_else(|| "LOWEST_COST_WITHOUT_CAP".to_string());
+
+    let (strategy, target_cpa, bid_limit) = match platform_strategy.as_str() {
+        "LOWEST_COST_WITHOUT_CAP" => (BidStrategy::LowestCost, None, None),
+        "LOWEST_COST_WITH_BID_CAP" => (BidStrategy::BidCap, None, amount),
+        "COST_CAP" => (BidStrategy::CostCap, amount, None),
+        "LOWEST_COST_WITH_MIN_ROAS" => (BidStrategy::MinRoas, None, None),
+        _ => (BidStrategy::Other, None, amount),
+    };
+
+    Bidding {
+        strategy,
+        target_cpa,
+        target_roas: raw.roas_average_floor.map(|f| f as f64 / META_ROAS_SCALE),
+        bid_limit,
+        platform_strategy,
+    }
+}
+
+/// Google Ads fields implementing `change`; `None` for Meta-only strategies
+pub fn google_settings(change: &BiddingChange) -> Option<GoogleBiddingSettings> {
+    let strategy_type = match change.strategy {
+        BidStrategy::TargetCpa => "TARGET_CPA",
+        BidStrategy::TargetRoas => "TARGET_ROAS",
+        BidStrategy::MaximizeConversions => "MAXIMIZE_CONVERSIONS",
+        BidStrategy::MaximizeConversionValue => "MAXIMIZE_CONVERSION_VALUE",
+        BidStrategy::MaximizeClicks => "TARGET_SPEND",
+        BidStrategy::ManualCpc => "MANUAL_CPC",
+        BidStrategy::ManualCpm => "MANUAL_CPM",
+        BidStrategy::TargetImpressionShare => "TARGET_IMPRESSION_SHARE",
+        _ => return None,
+    };
+
+    Some(GoogleBiddingSettings {
+        bidding_strategy_type: strategy_type.to_string(),
+        target_cpa_micros: to_micros(change.target_cpa),
+        target_roas: change.target_roas,
+        cpc_bid_ceiling_micros: to_micros(change.bid_limit),
+    })
+}
+
+/// Meta fields implementing `change`; `None` for Google-only strategies
+pub fn meta_settings(change: &BiddingChange) -> Option<MetaBiddingSettings> {
+    let cents = |amount: Option<f64>| amount.map(|a| (a * META_MINOR_UNITS).round() as i64);
+
+    let (strategy, bid_amount) = match change.strategy {
+        BidStrategy::LowestCost => ("LOWEST_COST_WITHOUT_CAP", None),
+        BidStrategy::BidCap => ("LOWEST_COST_WITH_BID_CAP", cents(change.bid_limit)),
+        BidStrategy::CostCap => ("COST_CAP", cents(change.target_cpa)),
+        BidStrategy::MinRoas => ("LOWEST_COST_WITH_MIN_ROAS", None),
+        _ => return None,
+    };
+
+    Some(MetaBiddingSettings {
+        bid_strategy: Some(strategy.to_string()),
+        bid_amount,
+        roas_average_floor: change
+            .target_roas
+            .filter(|_| change.strategy == BidStrategy::MinRoas)
+            .map(|r| (r * META_ROAS_SCALE).round() as i64),
+    })
+}
+
+/// Fill in the `bidding` block of every campaign, one gateway call per
+/// platform
+///
+/// Platforms without bidding support are skipped; other failures are
+/// logged and leave the block empty.
+pub async fn attach_bidding(registry: &ConnectorRegistry, campaigns: &mut [Campaign]) {
+    let mut ids_by_platform: HashMap<_, Vec<CampaignId>> = HashMap::new();
+    for campaign in campaigns.iter() {
+        ids_by_platform
+            .entry(campaign.platform.clone())
+            .or_default()
+            .push(campaign.id.clone());
+    }
+
+    if call_budget::charge(ids_by_platform.len()).is_err() {
+        return;
+    }
+
+    let fetches = ids_by_platform.iter().filter_map(|(platform, ids)| {
+        let connector = registry.get(platform)?;
+        Some(async move { (platform.clone(), connector.fetch_bidding(ids).await) })
+    });
+
+    let mut bidding = HashMap::new();
+    for (platform, result) in futures::future::join_all(fetches).await {
+        match result {
+            Ok(by_campaign) => {
+                for (campaign_id, b) in by_campaign {
+                    bidding.insert(GlobalCampaignId::new(platform.clone(), campaign_id), b);
+                }
+            }
+            Err(ConnectorError::NotSupported(_)) => {}
+            Err(e) => {
+                tracing::warn!(platform = platform.as_str(), error = %e, "bidding fetch failed")
+            }
+        }
+    }
+
+    for campaign in campaigns.iter_mut() {
+        campaign.bidding = bidding.remove(&campaign.global_id());
+    }
+}
+
+/// Keep the campaigns bidding with one of `strategies`
+pub fn filter_by_strategy(campaigns: &mut Vec<Campaign>, strategies: &[BidStrategy]) {
+    campaigns.retain(|c| {
+        c.bidding
+            .as_ref()
+            .is_some_and(|b| strategies.contains(&b.strategy))
+    });
+}
+
+/// PATCH /campaigns/{platform}/{id}/bidding - Switch a campaign's bid strategy
+///
+/// Returns the bidding read back from the platform.
+pub async fn patch_campaign_bidding(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+    Json(change): Json<BiddingChange>,
+) -> Result<Json<Bidding>, ApiError> {
+    change.validate()?;
+    let connector = state
+        .connectors
+        .get(&platform)
+        .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+
+    let campaign = connector
+        .fetch_campaigns()
+        .await?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+    let target = format!("set_bidding {}/{}", platform.as_str(), id);
+    state.freezes.check(&actor, &campaign.labels, &target, Utc::now())?;
+
+    connector.mutate_bidding(&id, &change).await?;
+
+    connector
+        .fetch_bidding(std::slice::from_ref(&id))
+        .await?
+        .remove(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("bidding of campaign {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn google(strategy_type: &str, target_cpa_micros: Option<i64>) -> GoogleBiddingSettings {
+        GoogleBiddingSettings {
+            bidding_strategy_type: strategy_type.to_string(),
+            target_cpa_micros,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_google_strategies_normalized() {
+        let tcpa = normalize_google(&google("MAXIMIZE_CONVERSIONS", Some(25_500_000)));
+        assert_eq!(tcpa.strategy, BidStrategy::TargetCpa);
+        assert_eq!(tcpa.target_cpa, Some(25.5));
+        assert_eq!(tcpa.platform_strategy, "MAXIMIZE_CONVERSIONS");
+
+        let max_conv = normalize_google(&google("MAXIMIZE_CONVERSIONS", None));
+        assert_eq!(max_conv.strategy, BidStrategy::MaximizeConversions);
+        let clicks = normalize_google(&google("TARGET_SPEND", None));
+        assert_eq!(clicks.strategy, BidStrategy::MaximizeClicks);
+        assert_eq!(normalize_google(&google("COMMISSION", None)).strategy, BidStrategy::Other);
+    }
+
+    #[test]
+    fn test_meta_strategies_normalized() {
+        let default = normalize_meta(&MetaBiddingSettings::default());
+        assert_eq!(default.strategy, BidStrategy::LowestCost);
+
+        let cost_cap = normalize_meta(&MetaBiddingSettings {
+            bid_strategy: Some("COST_CAP".to_string()),
+            bid_amount: Some(1_250),
+            roas_average_floor: None,
+        });
+        assert_eq!(cost_cap.strategy, BidStrategy::CostCap);
+        assert_eq!(cost_cap.target_cpa, Some(12.5));
+
+        let min_roas = normalize_meta(&MetaBiddingSettings {
+            bid_strategy: Some("LOWEST_COST_WITH_MIN_ROAS".to_string()),
+            bid_amount: None,
+            roas_average_floor: Some(25_000),
+        });
+        assert_eq!(min_roas.target_roas, Some(2.5));
+    }
+
+    #[test]
+    fn test_changes_validated_and_mapped_per_platform() {
+        let change = |strategy, target_cpa| BiddingChange {
+            strategy,
+            target_cpa,
+            target_roas: None,
+            bid_limit: None,
+        };
+
+        assert!(change(BidStrategy::TargetCpa, None).validate().is_err());
+        assert!(change(BidStrategy::TargetCpa, Some(-1.0)).validate().is_err());
+
+        let tcpa = change(BidStrategy::TargetCpa, Some(30.0));
+        assert!(tcpa.validate().is_ok());
+        assert_eq!(google_settings(&tcpa).unwrap().target_cpa_micros, Some(30_000_000));
+        assert!(meta_settings(&tcpa).is_none());
+
+        let cost_cap = change(BidStrategy::CostCap, Some(30.0));
+        assert_eq!(meta_settings(&cost_cap).unwrap().bid_amount, Some(3_000));
+        assert!(google_settings(&cost_cap).is_none());
+    }
+
+    #[test]
+    fn test_strategy_list_parsed() {
+        assert_eq!(
+            BidStrategy::parse_list("target_cpa, lowest_cost").unwrap(),
+            vec![BidStrategy::TargetCpa, BidStrategy::LowestCost]
+        );
+        assert!(BidStrategy::parse_list("cheapest").is_err());
+    }
+}
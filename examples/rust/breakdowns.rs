@@ -0,0 +1,291 @@
+//! # Performance Breakdowns Example
+//!
+//! `GET /campaigns/{id}/breakdowns?dimension=geo|device|age|gender` asks
+//! the campaign's platform for a segmented report and returns metrics per
+//! segment in one shape for charting. Segment values are normalized so
+//! both platforms chart on the same axis:
+//!
+//! - `geo`: ISO 3166-1 alpha-2 country codes; Google reports geo target
+//!   constants (`geoTargetConstants/2076`), Meta country codes;
+//! - `device`: `mobile`, `tablet`, `desktop`, `connected_tv` or `other`,
+//!   from Google device types and Meta impression devices or placements;
+//! - `age`: ranges like `25-34` and `65+`;
+//! - `gender`: `male` or `female`.
+//!
+//! Values the platform could not attribute become `unknown`. Rows landing
+//! on the same segment (Meta's `iphone` and `android_smartphone`) are
+//! combined, with CTR, CPA and ROAS recomputed from the totals.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{
+    combine_metrics, find_campaign, AppState, CampaignMetrics, Platform,
+};
+use super::error::ApiError;
+use super::ids::CampaignId;
+use super::money::Currency;
+
+/// Segment of values a platform could not attribute
+pub const UNKNOWN_SEGMENT: &str = "unknown";
+
+/// Google geo target constant IDs of the countries we advertise in
+const GEO_TARGET_COUNTRIES: &[(&str, &str)] = &[
+    ("2032", "AR"),
+    ("2076", "BR"),
+    ("2152", "CL"),
+    ("2170", "CO"),
+    ("2484", "MX"),
+    ("2600", "PY"),
+    ("2604", "PE"),
+    ("2620", "PT"),
+    ("2724", "ES"),
+    ("2840", "US"),
+    ("2858", "UY"),
+];
+
+/// Dimension a campaign's metrics are split by
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    Geo,
+    Device,
+    Age,
+    Gender,
+}
+
+impl Dimension {
+    /// Segment requested from the platform's report
+    pub fn platform_segment(&self, platform: &Platform) -> &'static str {
+        match (platform, self) {
+            (Platform::Meta, Dimension::Geo) => "country",
+            (Platform::Meta, Dimension::Device) => "impression_device",
+            (Platform::Meta, Dimension::Age) => "age",
+            (_, Dimension::Geo) => "geo_target_country",
+            (_, Dimension::Device) => "device",
+            (_, Dimension::Age) => "age_range",
+            (_, Dimension::Gender) => "gender",
+        }
+    }
+}
+
+/// Query parameters for `GET /campaigns/{id}/breakdowns`
+#[derive(Debug, Deserialize)]
+pub struct BreakdownQuery {
+    pub dimension: Dimension,
+}
+
+/// Report row as returned by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SegmentRow {
+    /// Segment value as named by the platform
+    pub segment: String,
+    pub impressions: u64,
+    pub clicks: u64,
+    #[serde(default)]
+    pub conversions: u32,
+    /// Google `cost`, Meta `spend`
+    #[serde(alias = "spend")]
+    pub cost: f64,
+    #[serde(default)]
+    pub conversion_value: f64,
+}
+
+/// Metrics of one segment
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SegmentMetrics {
+    pub segment: String,
+    #[serde(flatten)]
+    pub metrics: CampaignMetrics,
+    /// Share of the campaign's cost spent on this segment, from 0 to 1
+    pub share_of_cost: f64,
+}
+
+/// Response of `GET /campaigns/{id}/breakdowns`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Breakdown {
+    pub campaign_id: CampaignId,
+    pub platform: Platform,
+    pub dimension: Dimension,
+    pub currency: Currency,
+    /// Highest cost first
+    pub segments: Vec<SegmentMetrics>,
+}
+
+fn normalize_geo(platform: &Platform, value: &str) -> String {
+    let value = value.trim();
+    let code = match platform {
+        Platform::Meta => Some(value),
+        _ => {
+            let id = value.rsplit('/').next().unwrap_or(value);
+            GEO_TARGET_COUNTRIES.iter().find(|(c, _)| *c == id).map(|(_, code)| *code)
+        }
+    };
+
+    match code {
+        Some(code) if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()) => {
+            code.to_ascii_uppercase()
+        }
+        _ => UNKNOWN_SEGMENT.to_string(),
+    }
+}
+
+fn normalize_device(value: &str) -> &'static str {
+    match value.to_ascii_lowercase().as_str() {
+        // Google device types, Meta impression devices and placements
+        "mobile" | "iphone" | "ipod" | "android_smartphone" | "mobile_app" | "mobile_web" => {
+            "mobile"
+        }
+        "tablet" | "ipad" | "android_tablet" => "tablet",
+        "desktop" => "desktop",
+        "connected_tv" => "connected_tv",
+        "other" => "other",
+        _ => UNKNOWN_SEGMENT,
+    }
+}
+
+fn normalize_age(value: &str) -> String {
+    // Google `AGE_RANGE_25_34` and `AGE_RANGE_65_UP`, Meta `25-34` and `65+`
+    let range = value.trim_start_matches("AGE_RANGE_").replace('_', "-");
+    let range = range.strip_suffix("-UP").map(|from| format!("{}+", from)).unwrap_or(range);
+
+    if range.starts_with(|c: char| c.is_ascii_digit())
+        && range.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '+')
+    {
+        range
+    } else {
+        UNKNOWN_SEGMENT.to_string()
+    }
+}
+
+fn normalize_gender(value: &str) -> &'static str {
+    match value.to_ascii_lowercase().as_str() {
+        "male" => "male",
+        "female" => "female",
+        _ => UNKNOWN_SEGMENT,
+    }
+}
+
+/// Segment value of `platform` in the shared vocabulary of `dimension`
+pub fn normalize_segment(platform: &Platform, dimension: Dimension, value: &str) -> String {
+    match dimension {
+        Dimension::Geo => normalize_geo(platform, value),
+        Dimension::Device => normalize_device(value).to_string(),
+        Dimension::Age => normalize_age(value),
+        Dimension::Gender => normalize_gender(value).to_string(),
+    }
+}
+
+/// Metrics per segment of normalized rows, highest cost first
+pub fn build_segments(rows: &[SegmentRow]) -> Vec<SegmentMetrics> {
+    let mut by_segment: BTreeMap<&str, Vec<CampaignMetrics>> = BTreeMap::new();
+    for row in rows {
+        by_segment.entry(&row.segment).or_default().push(CampaignMetrics {
+            impressions: row.impressions,
+            clicks: row.clicks,
+            conversions: row.conversions,
+            cost: row.cost,
+            conversion_value: row.conversion_value,
+            ..Default::default()
+        });
+    }
+    let total_cost: f64 = rows.iter().map(|r| r.cost).sum();
+
+    let mut segments: Vec<SegmentMetrics> = by_segment
+        .into_iter()
+        .map(|(segment, metrics)| {
+            let metrics = combine_metrics(&metrics);
+            SegmentMetrics {
+                segment: segment.to_string(),
+                share_of_cost: if total_cost > 0.0 { metrics.cost / total_cost } else { 0.0 },
+                metrics,
+            }
+        })
+        .collect();
+
+    // Stable sort keeps segments with equal cost in name order
+    segments.sort_by(|a, b| b.metrics.cost.total_cmp(&a.metrics.cost));
+    segments
+}
+
+/// GET /campaigns/{id}/breakdowns - Campaign metrics split by a dimension
+pub async fn get_campaign_breakdowns(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<CampaignId>,
+    Query(query): Query<BreakdownQuery>,
+) -> Result<Json<Breakdown>, ApiError> {
+    let campaign = find_campaign(&state, &id)
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+    let connector = state
+        .connectors
+        .get(&campaign.platform)
+        .ok_or_else(|| ApiError::NotSupported(campaign.platform.as_str().to_string()))?;
+
+    let rows = connector.fetch_breakdown(&id, query.dimension).await?;
+
+    Ok(Json(Breakdown {
+        platform: campaign.platform.clone(),
+        dimension: query.dimension,
+        currency: campaign.currency(),
+        segments: build_segments(&rows),
+        campaign_id: id,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_values_share_one_vocabulary() {
+        let (google, meta) = (Platform::Google, Platform::Meta);
+        let cases = [
+            (&google, Dimension::Geo, "geoTargetConstants/2076", "BR"),
+            (&google, Dimension::Geo, "geoTargetConstants/9999", "unknown"),
+            (&meta, Dimension::Geo, "br", "BR"),
+            (&google, Dimension::Device, "CONNECTED_TV", "connected_tv"),
+            (&meta, Dimension::Device, "android_tablet", "tablet"),
+            (&google, Dimension::Age, "AGE_RANGE_25_34", "25-34"),
+            (&google, Dimension::Age, "AGE_RANGE_65_UP", "65+"),
+            (&google, Dimension::Age, "AGE_RANGE_UNDETERMINED", "unknown"),
+            (&meta, Dimension::Age, "65+", "65+"),
+            (&google, Dimension::Gender, "UNDETERMINED", "unknown"),
+            (&meta, Dimension::Gender, "female", "female"),
+        ];
+
+        for (platform, dimension, value, expected) in cases {
+            assert_eq!(normalize_segment(platform, dimension, value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_rows_of_one_segment_are_combined() {
+        let row = |segment: &str, impressions, clicks, cost| SegmentRow {
+            segment: segment.to_string(),
+            impressions,
+            clicks,
+            cost,
+            ..Default::default()
+        };
+        let rows = [
+            row("mobile", 1_000, 20, 30.0),
+            row("desktop", 500, 25, 50.0),
+            row("mobile", 1_000, 40, 40.0),
+        ];
+
+        let segments = build_segments(&rows);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].segment, "mobile");
+        assert_eq!(segments[0].metrics.clicks, 60);
+        assert_eq!(segments[0].metrics.ctr, 3.0);
+        assert!((segments[0].share_of_cost - 70.0 / 120.0).abs() < 1e-9);
+        assert_eq!(segments[1].metrics.ctr, 5.0);
+    }
+}
@@ -0,0 +1,136 @@
+//! # Budget Advice Example
+//!
+//! `POST /campaigns/bulk?advise=true` adds an `advice` block to every
+//! applied `set_budget` item, so the UI can confirm a budget change made
+//! sense before moving on:
+//!
+//! - `projected_monthly_spend`: month-to-date spend plus the new daily
+//!   budget for each remaining day of the month, assuming the new budget
+//!   is delivered in full;
+//! - `pace_status`: how the campaign was pacing against its previous
+//!   budget, see `pacing`. Raising the budget of an underpacing campaign
+//!   rarely buys more delivery;
+//! - `previously_budget_limited`: whether the campaign was losing at least
+//!   `BUDGET_LIMITED_LOST_SHARE` of its impressions to budget, from the
+//!   auction insights fetched before the batch ran. `null` when the
+//!   platform reports no impression share for the campaign.
+//!
+//! Advice is informational only; it never blocks a change. It costs one
+//! auction insights call per platform touched by a budget change.
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::auction::attach_auction_insights;
+use super::bulk::{BulkOperation, BulkReport};
+use super::campaign_aggregator::Campaign;
+use super::connectors::ConnectorRegistry;
+use super::ids::GlobalCampaignId;
+use super::pacing::{compute_pacing, days_in_month, PaceStatus};
+
+/// Query parameters of `POST /campaigns/bulk`
+#[derive(Debug, Deserialize, Default)]
+pub struct AdviseQuery {
+    /// Attach `advice` to applied budget changes
+    #[serde(default)]
+    pub advise: bool,
+}
+
+/// Advisory figures of an applied budget change
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BudgetAdvice {
+    pub previous_daily_budget: f64,
+    pub daily_budget: f64,
+    pub month_to_date_spend: f64,
+    /// Month-to-date spend plus the new budget for the remaining days
+    pub projected_monthly_spend: f64,
+    /// Pacing against the previous budget
+    pub pace_status: PaceStatus,
+    /// Lost at least `BUDGET_LIMITED_LOST_SHARE` of its impressions to
+    /// budget; `None` without impression share data
+    pub previously_budget_limited: Option<bool>,
+}
+
+/// Advice for setting the daily budget of `campaign`, as it was before the
+/// change, to `daily_budget` on `today`
+pub fn advise(campaign: &Campaign, daily_budget: f64, today: NaiveDate) -> BudgetAdvice {
+    let month_to_date_spend = campaign.metrics.cost;
+    let remaining_days = days_in_month(today) - today.day();
+    let pacing = compute_pacing(campaign.daily_budget, month_to_date_spend, today);
+
+    BudgetAdvice {
+        previous_daily_budget: campaign.daily_budget,
+        daily_budget,
+        month_to_date_spend,
+        projected_monthly_spend: month_to_date_spend + daily_budget * remaining_days as f64,
+        pace_status: pacing.status,
+        previously_budget_limited: campaign
+            .auction
+            .as_ref()
+            .filter(|auction| auction.lost_is_budget.is_some())
+            .map(|auction| auction.budget_limited),
+    }
+}
+
+/// Campaigns that `operations` change the budget of, with their auction
+/// insights, as they are before the batch runs
+pub async fn budget_targets(
+    registry: &ConnectorRegistry,
+    campaigns: Vec<Campaign>,
+    operations: &[BulkOperation],
+) -> Vec<Campaign> {
+    let targets: Vec<GlobalCampaignId> = operations
+        .iter()
+        .filter(|operation| matches!(operation, BulkOperation::SetBudget { .. }))
+        .map(BulkOperation::global_id)
+        .collect();
+    let mut campaigns: Vec<Campaign> =
+        campaigns.into_iter().filter(|c| targets.contains(&c.global_id())).collect();
+    if !campaigns.is_empty() {
+        attach_auction_insights(registry, &mut campaigns).await;
+    }
+    campaigns
+}
+
+/// Fill in the `advice` block of every applied budget change of `report`
+///
+/// Changes of campaigns missing from `before` get no advice.
+pub fn attach_advice(report: &mut BulkReport, before: &[Campaign], today: NaiveDate) {
+    let before: HashMap<GlobalCampaignId, &Campaign> =
+        before.iter().map(|c| (c.global_id(), c)).collect();
+
+    for result in report.results.iter_mut().filter(|r| r.success) {
+        if let BulkOperation::SetBudget { daily_budget, .. } = &result.operation {
+            result.advice = before
+                .get(&result.operation.global_id())
+                .map(|campaign| advise(campaign, *daily_budget, today));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::auction::AuctionInsights;
+    use super::super::campaign_aggregator::Platform;
+
+    #[test]
+    fn test_advice_projects_new_budget_over_remaining_days() {
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        campaign.metrics.cost = 1000.0;
+        let today = NaiveDate::from_ymd_opt(2026, 11, 10).unwrap();
+
+        let advice = advise(&campaign, 150.0, today);
+        assert_eq!(advice.projected_monthly_spend, 1000.0 + 150.0 * 20.0);
+        assert_eq!(advice.pace_status, PaceStatus::OnPace);
+        assert_eq!(advice.previously_budget_limited, None);
+
+        campaign.auction = Some(AuctionInsights {
+            lost_is_budget: Some(0.35),
+            budget_limited: true,
+            ..Default::default()
+        });
+        assert_eq!(advise(&campaign, 150.0, today).previously_budget_limited, Some(true));
+    }
+}
@@ -0,0 +1,652 @@
+//! # Bulk Mutations Example
+//!
+//! `POST /campaigns/bulk` applies a list of campaign changes across
+//! platforms in one request. Operations run concurrently (at most
+//! `BULK_CONCURRENCY` in flight) and each one succeeds or fails on its
+//! own; the response reports every item in request order so agencies can
+//! retry exactly the items that failed.
+//!
+//! Before a batch runs, the current status and budget of the touched
+//! campaigns are captured. Every successful item records the operation
+//! that reverts it, and `POST /bulk/{batch_id}/rollback` replays those
+//! within the configured rollback window. Every executed item is
+//! recorded in the audit log.
+//!
+//! Items are checked against change freezes and the tenant's spend cap
+//! in request order before anything runs, so the cap check of an item
+//! counts the budget increases admitted before it.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::audit::{AuditChange, AuditEntry, AuditLog};
+use super::budget_advice::{self, AdviseQuery, BudgetAdvice};
+use super::call_budget;
+use super::campaign_aggregator::{
+    aggregate_campaigns, aggregate_from, AppState, Campaign, CampaignStatus, Platform,
+};
+use super::connectors::ConnectorRegistry;
+use super::error::ApiError;
+use super::freeze::{Actor, FreezeStore};
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::spend_caps::{SpendCapGuard, SpendCaps, SpendChange};
+
+/// Operations in flight at the same time
+pub const BULK_CONCURRENCY: usize = 8;
+
+/// Largest batch accepted in one request
+pub const MAX_BULK_OPERATIONS: usize = 500;
+
+/// Single change in a bulk request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkOperation {
+    SetStatus {
+        platform: Platform,
+        campaign_id: CampaignId,
+        status: CampaignStatus,
+    },
+    SetBudget {
+        platform: Platform,
+        campaign_id: CampaignId,
+        daily_budget: f64,
+    },
+}
+
+impl BulkOperation {
+    pub fn platform(&self) -> &Platform {
+        match self {
+            BulkOperation::SetStatus { platform, .. } | BulkOperation::SetBudget { platform, .. } => {
+                platform
+            }
+        }
+    }
+
+    pub fn campaign_id(&self) -> &CampaignId {
+        match self {
+            BulkOperation::SetStatus { campaign_id, .. }
+            | BulkOperation::SetBudget { campaign_id, .. } => campaign_id,
+        }
+    }
+
+    pub fn global_id(&self) -> GlobalCampaignId {
+        GlobalCampaignId::new(self.platform().clone(), self.campaign_id().clone())
+    }
+
+    /// What this operation changes, for spend cap checks
+    pub fn spend_change(&self) -> SpendChange {
+        match self {
+            BulkOperation::SetStatus { status, .. } => SpendChange::Status(status.clone()),
+            BulkOperation::SetBudget { daily_budget, .. } => {
+                SpendChange::DailyBudget(*daily_budget)
+            }
+        }
+    }
+
+    /// Short description for audit records, e.g. `set_budget google/123`
+    pub fn target(&self) -> String {
+        let kind = match self {
+            BulkOperation::SetStatus { .. } => "set_status",
+            BulkOperation::SetBudget { .. } => "set_budget",
+        };
+        format!("{} {}/{}", kind, self.platform().as_str(), self.campaign_id())
+    }
+
+    /// Audit record of this change on `current`, when it was known
+    pub fn audit_change(&self, current: Option<&Campaign>) -> AuditChange {
+        match self {
+            BulkOperation::SetStatus { status, .. } => AuditChange::Status {
+                before: current.map(|c| c.status.clone()),
+                after: status.clone(),
+            },
+            BulkOperation::SetBudget { daily_budget, .. } => AuditChange::DailyBudget {
+                before: current.map(|c| c.daily_budget),
+                after: *daily_budget,
+            },
+        }
+    }
+
+    /// Operation restoring what this one changes on `current`
+    pub fn reverse(&self, current: &Campaign) -> BulkOperation {
+        match self {
+            BulkOperation::SetStatus { platform, campaign_id, .. } => BulkOperation::SetStatus {
+                platform: platform.clone(),
+                campaign_id: campaign_id.clone(),
+                status: current.status.clone(),
+            },
+            BulkOperation::SetBudget { platform, campaign_id, .. } => BulkOperation::SetBudget {
+                platform: platform.clone(),
+                campaign_id: campaign_id.clone(),
+                daily_budget: current.daily_budget,
+            },
+        }
+    }
+}
+
+/// Request body for `POST /campaigns/bulk`
+#[derive(Debug, Deserialize)]
+pub struct BulkRequest {
+    pub operations: Vec<BulkOperation>,
+}
+
+/// Outcome of one operation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkItemResult {
+    /// Position of the operation in the request
+    pub index: usize,
+    pub operation: BulkOperation,
+    pub success: bool,
+    /// `ApiError` code when the operation failed
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+    /// Operation that reverts this one, when it succeeded and the previous
+    /// state was known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse: Option<BulkOperation>,
+    /// Advisory figures of an applied budget change, with `?advise=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advice: Option<BudgetAdvice>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkReport {
+    pub batch_id: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+/// Executed batch kept for rollback
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkBatch {
+    pub batch_id: String,
+    pub executed_at: DateTime<Utc>,
+    /// Reverse operations in the order they must run
+    pub reverse: Vec<BulkOperation>,
+    pub rolled_back_at: Option<DateTime<Utc>>,
+}
+
+/// Executed batches by ID
+#[derive(Default)]
+pub struct BulkBatchStore {
+    batches: RwLock<HashMap<String, BulkBatch>>,
+}
+
+impl BulkBatchStore {
+    /// Remember the reverse operations of an executed batch
+    pub fn record(&self, report: &BulkReport, executed_at: DateTime<Utc>) {
+        let reverse = report
+            .results
+            .iter()
+            .rev()
+            .filter_map(|r| r.reverse.clone())
+            .collect();
+
+        self.batches.write().unwrap().insert(
+            report.batch_id.clone(),
+            BulkBatch {
+                batch_id: report.batch_id.clone(),
+                executed_at,
+                reverse,
+                rolled_back_at: None,
+            },
+        );
+    }
+
+    /// Claim a batch for rollback, rejecting repeated or expired rollbacks
+    pub fn start_rollback(
+        &self,
+        batch_id: &str,
+        window: chrono::Duration,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<BulkOperation>, ApiError> {
+        let mut batches = self.batches.write().unwrap();
+        let batch = batches
+            .get_mut(batch_id)
+            .ok_or_else(|| ApiError::NotFound(format!("bulk batch {}", batch_id)))?;
+
+        if batch.rolled_back_at.is_some() {
+            return Err(ApiError::Conflict(format!("bulk batch {} was already rolled back", batch_id)));
+        }
+        if now - batch.executed_at > window {
+            return Err(ApiError::Conflict(format!(
+                "bulk batch {} is older than the {}s rollback window",
+                batch_id,
+                window.num_seconds()
+            )));
+        }
+
+        batch.rolled_back_at = Some(now);
+        Ok(batch.reverse.clone())
+    }
+}
+
+/// Apply one operation through the connector of its platform
+pub async fn execute_operation(
+    registry: &ConnectorRegistry,
+    operation: &BulkOperation,
+) -> Result<(), ApiError> {
+    let connector = registry
+        .get(operation.platform())
+        .ok_or_else(|| ApiError::NotSupported(operation.platform().as_str().to_string()))?;
+
+    match operation {
+        BulkOperation::SetStatus {
+            campaign_id,
+            status,
+            ..
+        } => {
+            if let CampaignStatus::Other(value) = status {
+                return Err(ApiError::Validation(format!("unknown status {:?}", value)));
+            }
+            connector.mutate_status(campaign_id, status.clone()).await?
+        }
+        BulkOperation::SetBudget {
+            campaign_id,
+            daily_budget,
+            ..
+        } => {
+            if *daily_budget <= 0.0 {
+                return Err(ApiError::Validation("daily_budget must be positive".to_string()));
+            }
+            connector.mutate_budget(campaign_id, *daily_budget).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Run every operation with bounded parallelism, keeping request order
+///
+/// Items hitting an active change freeze or the spend cap fail without
+/// being executed; every executed item is audited, whatever its outcome.
+pub async fn execute_bulk(
+    registry: &ConnectorRegistry,
+    operations: Vec<BulkOperation>,
+    freezes: &FreezeStore,
+    spend_caps: &SpendCaps,
+    actor: &Actor,
+    audit: &AuditLog,
+) -> BulkReport {
+    // State before the batch, to record how to revert each item
+    let campaigns = aggregate_from(registry).await;
+    let before: HashMap<GlobalCampaignId, &Campaign> =
+        campaigns.iter().map(|c| (c.global_id(), c)).collect();
+    let before = &before;
+    let now = Utc::now();
+
+    let mut spend_cap = spend_caps.guard(actor, &campaigns, now);
+    let checks: Vec<Result<(), ApiError>> = operations
+        .iter()
+        .map(|operation| check_operation(operation, before, freezes, &mut spend_cap, actor, now))
+        .collect();
+
+    let results: Vec<BulkItemResult> = stream::iter(operations.into_iter().enumerate().zip(checks))
+        .map(|((index, operation), check)| async move {
+            let current = before.get(&operation.global_id()).copied();
+
+            let result = match check {
+                Ok(()) => {
+                    let result = execute_operation(registry, &operation).await;
+                    audit.record(AuditEntry::new(
+                        actor,
+                        operation.platform(),
+                        operation.campaign_id(),
+                        operation.audit_change(current),
+                        &result,
+                    ));
+                    result
+                }
+                Err(e) => Err(e),
+            };
+            let reverse = result.is_ok().then(|| current.map(|c| operation.reverse(c)));
+            BulkItemResult {
+                index,
+                success: result.is_ok(),
+                error_code: result.as_ref().err().map(|e| e.code().to_string()),
+                error: result.err().map(|e| e.to_string()),
+                reverse: reverse.flatten(),
+                advice: None,
+                operation,
+            }
+        })
+        .buffered(BULK_CONCURRENCY)
+        .collect()
+        .await;
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+
+    BulkReport {
+        batch_id: uuid::Uuid::new_v4().to_string(),
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    }
+}
+
+/// Whether `operation` may run under the active freezes and spend cap
+fn check_operation(
+    operation: &BulkOperation,
+    before: &HashMap<GlobalCampaignId, &Campaign>,
+    freezes: &FreezeStore,
+    spend_cap: &mut SpendCapGuard<'_>,
+    actor: &Actor,
+    now: DateTime<Utc>,
+) -> Result<(), ApiError> {
+    let id = operation.global_id();
+    let target = operation.target();
+    let labels = before.get(&id).map(|c| c.labels.as_slice()).unwrap_or_default();
+
+    freezes.check(actor, labels, &target, now)?;
+    spend_cap.check(&id, operation.spend_change(), &target)
+}
+
+/// POST /campaigns/bulk - Apply many campaign changes with per-item results
+///
+/// With `?advise=true`, applied budget changes carry an `advice` block,
+/// see `budget_advice`.
+pub async fn bulk_mutate(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Query(query): Query<AdviseQuery>,
+    Json(body): Json<BulkRequest>,
+) -> Result<Json<BulkReport>, ApiError> {
+    if body.operations.is_empty() || body.operations.len() > MAX_BULK_OPERATIONS {
+        return Err(ApiError::Validation(format!(
+            "operations must contain between 1 and {} items",
+            MAX_BULK_OPERATIONS
+        )));
+    }
+
+    call_budget::charge(body.operations.len())?;
+
+    let before = if query.advise {
+        let campaigns = aggregate_campaigns(&state).await;
+        budget_advice::budget_targets(&state.connectors, campaigns, &body.operations).await
+    } else {
+        Vec::new()
+    };
+
+    let executed_at = Utc::now();
+    let mut report = execute_bulk(
+        &state.connectors,
+        body.operations,
+        &state.freezes,
+        &state.spend_caps,
+        &actor,
+        &state.audit,
+    )
+    .await;
+    state.bulk_batches.record(&report, executed_at);
+    if query.advise {
+        budget_advice::attach_advice(&mut report, &before, executed_at.date_naive());
+    }
+
+    Ok(Json(report))
+}
+
+/// POST /bulk/{batch_id}/rollback - Restore what a bulk batch changed
+///
+/// The rollback runs as a batch of its own and is recorded like one.
+pub async fn rollback_bulk(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path(batch_id): Path<String>,
+) -> Result<Json<BulkReport>, ApiError> {
+    let window = chrono::Duration::from_std(state.config.bulk_rollback_window())
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let executed_at = Utc::now();
+    let operations = state.bulk_batches.start_rollback(&batch_id, window, executed_at)?;
+
+    call_budget::charge(operations.len())?;
+
+    let report = execute_bulk(
+        &state.connectors,
+        operations,
+        &state.freezes,
+        &state.spend_caps,
+        &actor,
+        &state.audit,
+    )
+    .await;
+    state.bulk_batches.record(&report, executed_at);
+
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::config::{SpendCapConfig, TenantSpendCap};
+    use super::super::connectors::MockConnector;
+    use super::super::freeze::FreezeWindow;
+    use super::super::spend_caps::remaining_days;
+    use super::super::timezones;
+
+    fn registry() -> ConnectorRegistry {
+        let mut registry = ConnectorRegistry::default();
+        registry.register(Arc::new(MockConnector {
+            platform: Platform::Google,
+            campaigns: vec![Campaign::sample("g1", Platform::Google)],
+            fail: false,
+        }));
+        registry
+    }
+
+    fn actor() -> Actor {
+        Actor::automation("test", None)
+    }
+
+    fn no_caps() -> SpendCaps {
+        SpendCaps::new(Default::default())
+    }
+
+    #[tokio::test]
+    async fn test_reports_each_item_in_request_order() {
+        let operations = vec![
+            BulkOperation::SetStatus {
+                platform: Platform::Google,
+                campaign_id: "g1".into(),
+                status: CampaignStatus::Paused,
+            },
+            BulkOperation::SetBudget {
+                platform: Platform::Google,
+                campaign_id: "missing".into(),
+                daily_budget: 50.0,
+            },
+            BulkOperation::SetBudget {
+                platform: Platform::Meta,
+                campaign_id: "m1".into(),
+                daily_budget: 50.0,
+            },
+        ];
+
+        let report = execute_bulk(
+            &registry(),
+            operations,
+            &FreezeStore::default(),
+            &no_caps(),
+            &actor(),
+            &AuditLog::default(),
+        )
+        .await;
+
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(report.failed, 2);
+        assert_eq!(
+            report.results.iter().map(|r| r.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(report.results[1].error_code.as_deref(), Some("not_found"));
+        assert_eq!(report.results[2].error_code.as_deref(), Some("not_supported"));
+    }
+
+    #[tokio::test]
+    async fn test_records_reverse_operations_for_rollback() {
+        let original = Campaign::sample("g1", Platform::Google);
+        let operations = vec![BulkOperation::SetBudget {
+            platform: Platform::Google,
+            campaign_id: "g1".into(),
+            daily_budget: original.daily_budget * 2.0,
+        }];
+
+        let report = execute_bulk(
+            &registry(),
+            operations,
+            &FreezeStore::default(),
+            &no_caps(),
+            &actor(),
+            &AuditLog::default(),
+        )
+        .await;
+        let store = BulkBatchStore::default();
+        let executed_at = Utc::now();
+        store.record(&report, executed_at);
+
+        let window = chrono::Duration::hours(1);
+        let reverse = store.start_rollback(&report.batch_id, window, executed_at).unwrap();
+        assert_eq!(
+            reverse,
+            vec![BulkOperation::SetBudget {
+                platform: Platform::Google,
+                campaign_id: "g1".into(),
+                daily_budget: original.daily_budget,
+            }]
+        );
+
+        // A batch is rolled back at most once
+        assert!(store.start_rollback(&report.batch_id, window, executed_at).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_frozen_items_fail_without_executing() {
+        let freezes = FreezeStore::default();
+        freezes.add_window(FreezeWindow {
+            id: "w1".to_string(),
+            name: "Black Friday".to_string(),
+            tenant: None,
+            labels: Vec::new(),
+            starts_at: Utc::now() - chrono::Duration::hours(1),
+            ends_at: Utc::now() + chrono::Duration::hours(1),
+            created_by: "ops".to_string(),
+        });
+        let operations = vec![BulkOperation::SetStatus {
+            platform: Platform::Google,
+            campaign_id: "g1".into(),
+            status: CampaignStatus::Paused,
+        }];
+
+        let audit = AuditLog::default();
+        let report =
+            execute_bulk(&registry(), operations, &freezes, &no_caps(), &actor(), &audit).await;
+
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.results[0].error_code.as_deref(), Some("change_frozen"));
+        assert!(report.results[0].reverse.is_none());
+        // Never reached the platform, so nothing to audit
+        assert!(audit.query(&Default::default()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_items_over_the_spend_cap_fail_without_executing() {
+        // Room for g1 at 150 a day for the rest of the month
+        let days = remaining_days(timezones::today()) as f64;
+        let caps = SpendCaps::new(SpendCapConfig {
+            tenants: HashMap::from([(
+                "acme".to_string(),
+                TenantSpendCap {
+                    monthly_cap: 150.0 * days,
+                    account_ids: Vec::new(),
+                    notifier: None,
+                },
+            )]),
+            ..Default::default()
+        });
+        let budget = |daily_budget: f64| BulkOperation::SetBudget {
+            platform: Platform::Google,
+            campaign_id: "g1".into(),
+            daily_budget,
+        };
+
+        let audit = AuditLog::default();
+        let actor = Actor::automation("scheduler", Some("acme".to_string()));
+        let freezes = FreezeStore::default();
+        let operations = vec![budget(140.0), budget(200.0)];
+        let report = execute_bulk(&registry(), operations, &freezes, &caps, &actor, &audit).await;
+
+        assert!(report.results[0].success);
+        assert_eq!(report.results[1].error_code.as_deref(), Some("spend_cap_exceeded"));
+        assert_eq!(audit.query(&Default::default()).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_executed_items_are_audited_with_before_and_after() {
+        let original = Campaign::sample("g1", Platform::Google);
+        let operations = vec![
+            BulkOperation::SetBudget {
+                platform: Platform::Google,
+                campaign_id: "g1".into(),
+                daily_budget: 80.0,
+            },
+            BulkOperation::SetStatus {
+                platform: Platform::Google,
+                campaign_id: "missing".into(),
+                status: CampaignStatus::Paused,
+            },
+        ];
+
+        let audit = AuditLog::default();
+        let actor = Actor::automation("alice", Some("acme".to_string()));
+        let freezes = FreezeStore::default();
+        execute_bulk(&registry(), operations, &freezes, &no_caps(), &actor, &audit).await;
+
+        let entries = audit.query(&Default::default());
+        assert_eq!(entries.len(), 2);
+        let budget = entries.iter().find(|e| e.campaign_id == "g1").unwrap();
+        assert_eq!(budget.actor, "alice");
+        assert_eq!(budget.tenant.as_deref(), Some("acme"));
+        assert_eq!(
+            budget.change,
+            AuditChange::DailyBudget {
+                before: Some(original.daily_budget),
+                after: 80.0
+            }
+        );
+        assert!(budget.response.success);
+
+        let missing = entries.iter().find(|e| e.campaign_id == "missing").unwrap();
+        assert_eq!(
+            missing.change,
+            AuditChange::Status {
+                before: None,
+                after: CampaignStatus::Paused
+            }
+        );
+        assert_eq!(missing.response.error_code.as_deref(), Some("not_found"));
+    }
+
+    #[test]
+    fn test_rollback_window_expires() {
+        let store = BulkBatchStore::default();
+        let executed_at = Utc::now();
+        let report = BulkReport {
+            batch_id: "b1".to_string(),
+            succeeded: 0,
+            failed: 0,
+            results: Vec::new(),
+        };
+        store.record(&report, executed_at);
+
+        let err = store
+            .start_rollback("b1", chrono::Duration::hours(1), executed_at + chrono::Duration::hours(2))
+            .unwrap_err();
+        assert_eq!(err.code(), "conflict");
+    }
+}
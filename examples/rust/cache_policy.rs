@@ -0,0 +1,120 @@
+//! # Cache Policy Example
+//!
+//! Read routes answer with `Cache-Control: no-cache` by default: clients
+//! may keep a response but must revalidate it (with its `ETag`) before
+//! every use. Routes listed in `cache.routes` get their own policy
+//! instead, sent on their successful `GET` responses:
+//!
+//! ```toml
+//! [cache.routes."/campaigns"]
+//! max_age_secs = 30
+//! stale_while_revalidate_secs = 120
+//!
+//! [cache.routes."/meta/schema"]
+//! max_age_secs = 86400
+//! visibility = "public"
+//! ```
+//!
+//! gives `Cache-Control: private, max-age=30, stale-while-revalidate=120`
+//! on `/campaigns`, `/v1/campaigns` and `/v2/campaigns`. Routes are keyed
+//! by their template (`/campaigns/{id}`), without version prefix.
+//! Responses are tenant data, so policies are `private` unless a route is
+//! marked `public`, which lets shared caches and CDNs store it.
+//!
+//! Server-side, `cache.campaigns_stale_secs` lets the entity cache serve
+//! an expired campaign list while it is fetched again, see `entity_cache`.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::config::AppConfig;
+use super::versioning;
+
+/// Who may store a response
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheVisibility {
+    /// The client only
+    #[default]
+    Private,
+    /// Shared caches and CDNs too
+    Public,
+}
+
+/// Caching policy of one route
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RoutePolicy {
+    /// How long a response is fresh; 0 requires revalidation on every use
+    pub max_age_secs: u64,
+    /// How long past `max_age_secs` a response may be used while it is
+    /// revalidated in the background; 0 omits the directive
+    #[serde(default)]
+    pub stale_while_revalidate_secs: u64,
+    #[serde(default)]
+    pub visibility: CacheVisibility,
+}
+
+/// `Cache-Control` value of `policy`
+pub fn cache_control(policy: &RoutePolicy) -> String {
+    let visibility = match policy.visibility {
+        CacheVisibility::Private => "private",
+        CacheVisibility::Public => "public",
+    };
+    let mut value = format!("{}, max-age={}", visibility, policy.max_age_secs);
+    if policy.stale_while_revalidate_secs > 0 {
+        value.push_str(&format!(", stale-while-revalidate={}", policy.stale_while_revalidate_secs));
+    }
+    value
+}
+
+/// Route layer setting the configured `Cache-Control` of the matched route
+/// on successful and not-modified `GET` responses
+pub async fn apply_cache_policy(
+    State(config): State<Arc<AppConfig>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let policy = match (req.method(), req.extensions().get::<MatchedPath>()) {
+        (&Method::GET | &Method::HEAD, Some(route)) => {
+            config.cache.routes.get(versioning::unversioned(route.as_str())).cloned()
+        }
+        _ => None,
+    };
+
+    let mut response = next.run(req).await;
+    let Some(policy) = policy else {
+        return response;
+    };
+    let status = response.status();
+    if status.is_success() || status == StatusCode::NOT_MODIFIED {
+        if let Ok(value) = HeaderValue::from_str(&cache_control(&policy)) {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_control_renders_policy() {
+        let mut policy = RoutePolicy {
+            max_age_secs: 30,
+            stale_while_revalidate_secs: 120,
+            visibility: CacheVisibility::Private,
+        };
+        assert_eq!(cache_control(&policy), "private, max-age=30, stale-while-revalidate=120");
+
+        policy.stale_while_revalidate_secs = 0;
+        policy.visibility = CacheVisibility::Public;
+        assert_eq!(cache_control(&policy), "public, max-age=30");
+    }
+}
@@ -0,0 +1,152 @@
+//! # Upstream Call Budget Example
+//!
+//! Caps how many gateway calls a single API request may trigger. The
+//! middleware scopes a `CallBudget` to the request's task; code that fans
+//! out to the gateway charges the calls it is about to make before making
+//! them. Once a request overdraws its budget it is answered with 422 and a
+//! hint to narrow the query, instead of amplifying into hundreds of
+//! platform calls.
+//!
+//! Work outside a request (sync worker, backfills) runs unscoped and is
+//! never limited.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::stream::Stream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::error::ApiError;
+
+/// Default number of gateway calls a single request may trigger
+pub const DEFAULT_CALL_BUDGET: usize = 50;
+
+tokio::task_local! {
+    static BUDGET: CallBudget;
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("request needs {attempted} upstream calls but its budget is {limit}")]
+pub struct BudgetExceeded {
+    pub limit: usize,
+    pub attempted: usize,
+}
+
+/// Gateway calls charged against one request
+#[derive(Debug, Clone)]
+pub struct CallBudget {
+    limit: usize,
+    used: Arc<AtomicUsize>,
+}
+
+impl CallBudget {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            used: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Calls charged so far, including a rejected charge
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.used() > self.limit
+    }
+
+    fn charge(&self, calls: usize) -> Result<(), BudgetExceeded> {
+        let attempted = self.used.fetch_add(calls, Ordering::SeqCst) + calls;
+
+        if attempted > self.limit {
+            Err(BudgetExceeded {
+                limit: self.limit,
+                attempted,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Charge `calls` gateway calls against the current request's budget
+///
+/// Always succeeds outside a request scope.
+pub fn charge(calls: usize) -> Result<(), BudgetExceeded> {
+    BUDGET
+        .try_with(|budget| budget.charge(calls))
+        .unwrap_or(Ok(()))
+}
+
+/// Budget of the current request; `None` outside a request scope
+pub fn current() -> Option<CallBudget> {
+    BUDGET.try_with(CallBudget::clone).ok()
+}
+
+/// Poll `stream` within `budget`
+///
+/// A streamed response body is polled after the handler returned, outside
+/// the middleware's scope; its upstream calls still count against the
+/// request that produced it. Overdrawing the budget at that point fails the
+/// calls, but the response has already started and keeps its status.
+pub fn scope_stream<S: Stream>(
+    budget: Option<CallBudget>,
+    stream: S,
+) -> impl Stream<Item = S::Item> {
+    let mut stream = Box::pin(stream);
+    futures::stream::poll_fn(move |cx| match &budget {
+        Some(budget) => BUDGET.sync_scope(budget.clone(), || stream.as_mut().poll_next(cx)),
+        None => stream.as_mut().poll_next(cx),
+    })
+}
+
+/// Middleware giving each request a fresh budget of `limit` calls
+pub async fn enforce_call_budget(State(limit): State<usize>, req: Request, next: Next) -> Response {
+    let budget = CallBudget::new(limit);
+    let response = BUDGET.scope(budget.clone(), next.run(req)).await;
+
+    if !budget.is_exceeded() {
+        return response;
+    }
+
+    tracing::warn!(limit, attempted = budget.used(), "upstream call budget exceeded");
+
+    ApiError::BudgetExceeded(BudgetExceeded {
+        limit,
+        attempted: budget.used(),
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_charges_within_request_scope() {
+        let budget = CallBudget::new(3);
+
+        BUDGET
+            .scope(budget.clone(), async {
+                assert!(charge(2).is_ok());
+                assert!(charge(2).is_err());
+            })
+            .await;
+
+        assert!(budget.is_exceeded());
+        assert_eq!(budget.used(), 4);
+    }
+
+    #[test]
+    fn test_unscoped_work_is_unlimited() {
+        assert!(charge(10_000).is_ok());
+    }
+}
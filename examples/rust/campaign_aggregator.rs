@@ -5,41 +5,299 @@
 //! unified response.
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
 };
-use serde::{Deserialize, Serialize};
+use chrono::{Datelike, NaiveDate};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use super::alerts::{AlertDispatcher, AlertStore};
+#[cfg(feature = "email")]
+use super::alerts::SmtpSettings;
+use super::accounts::AccountDirectory;
+use super::annotations::AnnotationStore;
+use super::api_usage::ApiUsage;
+use super::archive;
+use super::auction::{self, AuctionInsights};
+use super::audiences::AudienceStore;
+use super::audit::AuditLog;
+use super::automation::AutomationStore;
+use super::backfill::BackfillStore;
+use super::bidding::{self, BidStrategy, Bidding};
+use super::bulk::BulkBatchStore;
+use super::call_budget;
+use super::campaign_groups::MatchingRules;
+use super::coalesce::{self, CampaignFlights, CampaignPage};
+use super::columnar::ColumnStore;
+use super::concurrency::ConcurrencyLimiter;
+use super::config::{AppConfig, ConfigError};
+use super::connectors::ConnectorRegistry;
+use super::cost_guard::CostGuard;
+use super::credentials::CredentialManager;
+use super::delta_sync::DeltaSync;
+use super::demo;
+use super::entity_cache::EntityCache;
+use super::error::ApiError;
+use super::etag::Conditional;
+use super::experiments::ExperimentStore;
+use super::fields::{FieldSelection, Sparse};
+use super::filter::Filter;
+use super::finite::{safe_div, Finite};
+use super::freeze::FreezeStore;
+use super::freshness::{self, DataSource, Freshness};
+use super::history::{self, PeriodComparison, SummaryQuery};
+use super::ids::{AccountId, CampaignId, GlobalCampaignId};
+use super::idempotency::IdempotencyStore;
+use super::insertion_orders::InsertionOrderStore;
+use super::labels::{self, LabelIndex};
+use super::latency_budget;
+use super::lenient::DecodeErrors;
+use super::load_shedding::LoadShedder;
+use super::locale;
+use super::money::{self, Currency, MoneyByCurrency};
+use super::naming::{self, NamingConventions};
+use super::ndjson;
+use super::normalization::{self, NormalizationRules};
+use super::pacing::{self, Pacing};
+use super::quality::{self, QualityDiagnostics};
+use super::quota::QuotaTracker;
+use super::raw::RawCaptureStore;
+use super::rbac::RoleStore;
+use super::reauth::CredentialHealth;
+use super::refresh::RefreshJobStore;
+use super::region::RegionRouter;
+use super::reload::LiveConfig;
+#[cfg(feature = "reports")]
+use super::reports::ReportStore;
+use super::schedules::ScheduleStore;
+use super::segments::SegmentStore;
+#[cfg(feature = "redis")]
+use super::shared::SharedStore;
+use super::sort::SortSpec;
+use super::spend_caps::SpendCaps;
+use super::store::MetricsStore;
+use super::summary_cache::{self, SummaryCache};
+use super::targeting::{self, Targeting};
+use super::timezones;
+use super::timing;
+use super::units;
+use super::updates::{self, CampaignUpdate, DashboardEvent};
+use super::utm::UtmConventions;
+use super::warmup::{UsageStats, WarmupStatus};
+
 /// Unified campaign representation across platforms
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Campaign {
-    pub id: String,
+    pub id: CampaignId,
     pub platform: Platform,
     pub name: String,
     pub status: CampaignStatus,
+    /// `None` for platforms that do not report one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective: Option<CampaignObjective>,
+    /// `None` for platforms that do not report one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign_type: Option<CampaignType>,
     pub daily_budget: f64,
     pub currency: String,
     pub metrics: CampaignMetrics,
+    /// Budget pacing, only present when requested with `?include=pacing`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pacing: Option<Pacing>,
+    /// Normalized targeting, only present when requested with `?expand=targeting`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targeting: Option<Targeting>,
+    /// Normalized bid strategy and targets, when the platform reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bidding: Option<Bidding>,
+    /// Quality score and relevance diagnostics, only present when requested
+    /// with `?include=quality`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<QualityDiagnostics>,
+    /// Impression share and auction competition, only present when
+    /// requested with `?include=auction`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auction: Option<AuctionInsights>,
+    /// Tracking template / final URL suffix carrying the campaign's UTM tags
+    #[serde(default)]
+    pub tracking_template: Option<String>,
+    /// Ad account owning the campaign (Google customer ID, Meta `act_` ID)
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+    /// Platform labels (Google labels, Meta ad labels), used to filter
+    /// lists and scope change freezes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Fields parsed from the name by the naming conventions
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Campaign {
+    /// The campaign's ID qualified by its platform
+    pub fn global_id(&self) -> GlobalCampaignId {
+        GlobalCampaignId::new(self.platform.clone(), self.id.clone())
+    }
+
+    /// Currency of the budget and metrics
+    pub fn currency(&self) -> Currency {
+        Currency::new(&self.currency)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum Platform {
     Google,
     Meta,
+    #[cfg(feature = "linkedin")]
+    LinkedIn,
+    #[cfg(feature = "microsoft")]
+    Microsoft,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Platform {
+    /// Parse a comma-separated list, e.g. `google,meta`
+    pub fn parse_list(list: &str) -> Result<Vec<Platform>, ApiError> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                serde_json::from_value(serde_json::Value::String(item.to_lowercase()))
+                    .map_err(|_| ApiError::Validation(format!("unknown platform {:?}", item)))
+            })
+            .collect()
+    }
+
+    /// Path segment used by the gateway routes (`/v1/{platform}/...`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Google => "google",
+            Platform::Meta => "meta",
+            #[cfg(feature = "linkedin")]
+            Platform::LinkedIn => "linkedin",
+            #[cfg(feature = "microsoft")]
+            Platform::Microsoft => "microsoft",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum CampaignStatus {
     Enabled,
     Paused,
     Removed,
+    /// Platform value no normalization rule maps, e.g. `PENDING`
+    #[serde(untagged)]
+    Other(String),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl CampaignStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CampaignStatus::Enabled => "ENABLED",
+            CampaignStatus::Paused => "PAUSED",
+            CampaignStatus::Removed => "REMOVED",
+            CampaignStatus::Other(value) => value,
+        }
+    }
+
+    /// Parse a comma-separated list, e.g. `enabled,paused`
+    pub fn parse_list(list: &str) -> Result<Vec<CampaignStatus>, ApiError> {
+        parse_enum_list(list, "status", |s| matches!(s, CampaignStatus::Other(_)))
+    }
+}
+
+/// What a campaign is optimized for, across platforms
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CampaignObjective {
+    Awareness,
+    Traffic,
+    Engagement,
+    #[serde(alias = "LEAD_GEN")]
+    Leads,
+    #[serde(alias = "APP_INSTALLS")]
+    AppPromotion,
+    /// Conversions and purchases
+    #[serde(alias = "CONVERSIONS")]
+    Sales,
+    /// Platform value no normalization rule maps, e.g. `OUTCOME_LEADS`
+    /// before a table covers it
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl CampaignObjective {
+    /// Parse a comma-separated list, e.g. `sales,leads`
+    pub fn parse_list(list: &str) -> Result<Vec<CampaignObjective>, ApiError> {
+        parse_enum_list(list, "objective", |o| matches!(o, CampaignObjective::Other(_)))
+    }
+}
+
+/// Kind of inventory a campaign buys, across platforms
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CampaignType {
+    Search,
+    Display,
+    Video,
+    /// Google Shopping and Meta Advantage+ shopping campaigns
+    #[serde(alias = "ADVANTAGE_PLUS")]
+    Shopping,
+    /// Platform value no normalization rule maps, e.g. `PERFORMANCE_MAX`
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl CampaignType {
+    /// Parse a comma-separated list, e.g. `search,shopping`
+    pub fn parse_list(list: &str) -> Result<Vec<CampaignType>, ApiError> {
+        parse_enum_list(list, "campaign type", |t| matches!(t, CampaignType::Other(_)))
+    }
+}
+
+/// Whether `campaign` has one of `objectives` and one of `types`
+///
+/// A `None` list keeps every campaign; a campaign without an objective or
+/// type only passes filters on the other one.
+pub fn matches_kind(
+    campaign: &Campaign,
+    objectives: Option<&[CampaignObjective]>,
+    types: Option<&[CampaignType]>,
+) -> bool {
+    let objective = campaign.objective.as_ref();
+    let campaign_type = campaign.campaign_type.as_ref();
+    objectives.iter().all(|list| objective.is_some_and(|o| list.contains(o)))
+        && types.iter().all(|list| campaign_type.is_some_and(|t| list.contains(t)))
+}
+
+/// Parse a comma-separated list of unified values, case-insensitive
+///
+/// Values only a platform knows are rejected: they are mapped by the
+/// normalization rules, so filtering by them would depend on the rules.
+fn parse_enum_list<T: DeserializeOwned>(
+    list: &str,
+    what: &str,
+    is_other: impl Fn(&T) -> bool,
+) -> Result<Vec<T>, ApiError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            serde_json::from_value(serde_json::Value::String(item.to_uppercase()))
+                .ok()
+                .filter(|value| !is_other(value))
+                .ok_or_else(|| ApiError::Validation(format!("unknown {} {:?}", what, item)))
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct CampaignMetrics {
     pub impressions: u64,
     pub clicks: u64,
@@ -47,87 +305,828 @@ pub struct CampaignMetrics {
     pub cost: f64,
     pub ctr: f64,
     pub cpa: f64,
+    /// Value of all conversions, in the campaign currency (Google
+    /// `conversions_value`, Meta `action_values`)
+    #[serde(default)]
+    pub conversion_value: f64,
+    /// Return on ad spend: `conversion_value / cost`
+    #[serde(default)]
+    pub roas: f64,
+    /// Conversions and value per conversion action
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conversion_actions: Vec<ConversionActionMetrics>,
+    /// People reached (Google `unique_users` on Video and Display, Meta
+    /// `reach`); `None` where the platform does not report it. The
+    /// platform de-duplicates people within the campaign only, see
+    /// `combine_metrics` for totals
+    #[serde(default)]
+    pub reach: Option<u64>,
+    /// Average impressions per person reached: `impressions / reach`
+    #[serde(default)]
+    pub frequency: Option<f64>,
+    /// Video views (Google `video_trueview_views`, Meta ThruPlays)
+    #[serde(default)]
+    pub video_views: Option<u64>,
+    /// Video views per impression, in percent like `ctr`
+    #[serde(default)]
+    pub video_view_rate: Option<f64>,
+    /// Average seconds watched per video view
+    #[serde(default)]
+    pub average_watch_time_seconds: Option<f64>,
+}
+
+/// Conversions of one conversion action (purchase, lead, ...)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversionActionMetrics {
+    pub action: String,
+    /// Fractional under data-driven attribution
+    pub conversions: f64,
+    pub value: f64,
+}
+
+/// ROAS of `value` earned on `cost`; 0 without spend
+pub fn roas(value: f64, cost: f64) -> f64 {
+    if cost > 0.0 {
+        safe_div(value, cost).unwrap_or(0.0)
+    } else {
+        0.0
+    }
 }
 
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
+    pub config: Arc<AppConfig>,
+    /// Gateway client; sends `X-Service-Auth` on every request
     pub http_client: reqwest::Client,
     pub gateway_url: String,
+    /// Platform connectors used by the aggregator
+    pub connectors: ConnectorRegistry,
+    pub insertion_orders: Arc<InsertionOrderStore>,
+    pub metrics_store: Arc<MetricsStore>,
+    pub alerts: Arc<AlertStore>,
+    pub alert_dispatcher: Arc<AlertDispatcher>,
+    /// Rules changing campaigns after each sync, and what they did
+    pub automation: Arc<AutomationStore>,
+    /// `GET /campaigns` fetches in flight, shared by identical requests
+    pub campaign_flights: Arc<CampaignFlights>,
+    pub matching_rules: Arc<MatchingRules>,
+    pub naming: Arc<NamingConventions>,
+    /// Mapping tables for statuses and objectives the connectors do not know
+    pub normalization: Arc<NormalizationRules>,
+    /// Configuration as last reloaded, for the sections applied without a restart
+    pub live_config: Arc<LiveConfig>,
+    pub utm: Arc<UtmConventions>,
+    pub segments: Arc<SegmentStore>,
+    pub audiences: Arc<AudienceStore>,
+    pub backfills: Arc<BackfillStore>,
+    /// Metrics refresh jobs polled through `GET /jobs/{id}`
+    pub refresh_jobs: Arc<RefreshJobStore>,
+    pub bulk_batches: Arc<BulkBatchStore>,
+    pub freezes: Arc<FreezeStore>,
+    pub spend_caps: Arc<SpendCaps>,
+    /// Runaway campaigns paused by the cost guard
+    pub cost_guard: Arc<CostGuard>,
+    /// Append-only record of status and budget changes
+    pub audit: Arc<AuditLog>,
+    /// Today's upstream calls per platform
+    pub quota: Arc<QuotaTracker>,
+    /// Upstream calls in flight and waiting, see `concurrency`
+    pub concurrency: Arc<ConcurrencyLimiter>,
+    /// Requests in flight and shed under load, see `load_shedding`
+    pub load_shedder: Arc<LoadShedder>,
+    /// Gateway list elements skipped because they did not decode
+    pub decode_errors: Arc<DecodeErrors>,
+    /// Roles of actors, globally and per tenant
+    pub roles: Arc<RoleStore>,
+    pub experiments: Arc<ExperimentStore>,
+    /// Labels of every platform, unified by name
+    pub labels: Arc<LabelIndex>,
+    /// Campaigns of the last sync laid out by column, see `columnar`
+    pub columns: Arc<ColumnStore>,
+    /// Summary of the last sync, see `summary_cache`
+    pub summaries: Arc<SummaryCache>,
+    /// Manager and Business Manager hierarchies, see `accounts`
+    pub accounts: Arc<AccountDirectory>,
+    /// Dated notes on campaigns, see `annotations`
+    pub annotations: Arc<AnnotationStore>,
+    /// Future status changes, see `schedules`
+    pub schedules: Arc<ScheduleStore>,
+    /// Raw platform payloads kept while `raw_capture.enabled`
+    pub raw_captures: Arc<RawCaptureStore>,
+    /// Campaigns and metrics shared by list, detail and hydration reads
+    pub entity_cache: Arc<EntityCache>,
+    /// Campaign lists of the last sync, for delta syncs
+    pub delta_sync: Arc<DeltaSync>,
+    /// Responses of write requests sent with an `Idempotency-Key`
+    pub idempotency: Arc<IdempotencyStore>,
+    /// Redis shared by the replicas, when configured
+    #[cfg(feature = "redis")]
+    pub shared: Option<Arc<SharedStore>>,
+    #[cfg(feature = "reports")]
+    pub reports: Arc<ReportStore>,
+    /// Token refresh outcomes reported by the connectors
+    pub credential_health: Arc<CredentialHealth>,
+    /// Tenants' OAuth connections to the platforms
+    pub credentials: Arc<CredentialManager>,
+    pub updates: tokio::sync::broadcast::Sender<CampaignUpdate>,
+    /// Summaries and alerts for WebSocket subscribers
+    pub dashboard_events: tokio::sync::broadcast::Sender<DashboardEvent>,
+    /// Requests per tenant and path, used to plan the startup warm-up
+    pub usage: Arc<UsageStats>,
+    /// Requests, bytes and upstream calls per tenant, for billing
+    pub api_usage: Arc<ApiUsage>,
+    pub warmup: Arc<WarmupStatus>,
+    /// Home regions of tenants and the instances serving them
+    pub regions: Arc<RegionRouter>,
+}
+
+impl AppState {
+    /// Build the state, gateway client and connectors from a validated config
+    pub fn from_config(config: AppConfig) -> Result<Self, ConfigError> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let token = reqwest::header::HeaderValue::from_str(&config.gateway.service_token)
+            .map_err(|_| ConfigError::invalid("gateway.service_token", "not a valid header value"))?;
+        headers.insert("X-Service-Auth", token);
+
+        let http_client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(config.request_timeout())
+            .connect_timeout(config.connect_timeout())
+            .build()
+            .map_err(|e| ConfigError::invalid("timeouts", e.to_string()))?;
+
+        // Notifiers post to third-party URLs and must not carry the service token
+        let alert_dispatcher = AlertDispatcher {
+            http_client: reqwest::Client::new(),
+            #[cfg(feature = "email")]
+            smtp: config.smtp.as_ref().map(SmtpSettings::from_config).transpose()?,
+        };
+
+        let credential_health = Arc::new(CredentialHealth::default());
+        let credentials = Arc::new(CredentialManager::from_config(&config.credentials)?);
+        let entity_cache = EntityCache::new(config.campaigns_ttl())
+            .with_stale_while_revalidate(config.campaigns_stale());
+        let idempotency = IdempotencyStore::new(config.idempotency_ttl());
+        #[cfg(feature = "redis")]
+        let shared = match &config.redis {
+            Some(redis) => Some(Arc::new(SharedStore::from_config(redis)?)),
+            None => None,
+        };
+        #[cfg(feature = "redis")]
+        let (entity_cache, idempotency) = match &shared {
+            Some(shared) => (
+                entity_cache.with_shared(shared.clone()),
+                idempotency.with_shared(shared.clone()),
+            ),
+            None => (entity_cache, idempotency),
+        };
+        let entity_cache = Arc::new(entity_cache);
+        let quota = Arc::new(QuotaTracker::new(config.quota.clone()));
+        let decode_errors = Arc::new(DecodeErrors::default());
+        let concurrency = Arc::new(ConcurrencyLimiter::new(config.concurrency.clone()));
+
+        // Demo data replaces the gateway, behind the same layers
+        let connectors = if config.demo.enabled {
+            demo::registry(&config.demo)
+        } else {
+            ConnectorRegistry::gateway(
+                http_client.clone(),
+                &config.gateway,
+                quota.clone(),
+                decode_errors.clone(),
+            )
+        };
+        // Innermost first: calls hold an upstream slot only once throttled,
+        // only calls reaching the gateway count against the quota, and the
+        // cache sits in front of refreshed credentials
+        let mut connectors = connectors
+            .with_concurrency_limit(concurrency.clone())
+            .with_quota(quota.clone())
+            .with_reauth(credential_health.clone(), credentials.clone());
+        if entity_cache.is_enabled() {
+            connectors = connectors.with_cache(entity_cache.clone());
+        }
+
+        // Instances in other regions get the caller's headers, not the service token
+        let peer_client = reqwest::Client::builder()
+            .timeout(config.request_timeout())
+            .connect_timeout(config.connect_timeout())
+            .build()
+            .map_err(|e| ConfigError::invalid("timeouts", e.to_string()))?;
+
+        Ok(Self {
+            connectors,
+            gateway_url: config.gateway.url.clone(),
+            http_client,
+            insertion_orders: Arc::default(),
+            metrics_store: Arc::default(),
+            alerts: Arc::default(),
+            alert_dispatcher: Arc::new(alert_dispatcher),
+            automation: Arc::default(),
+            campaign_flights: Arc::default(),
+            matching_rules: Arc::default(),
+            naming: Arc::default(),
+            normalization: Arc::default(),
+            live_config: Arc::new(LiveConfig::new(config.clone())),
+            utm: Arc::default(),
+            segments: Arc::default(),
+            audiences: Arc::default(),
+            backfills: Arc::default(),
+            refresh_jobs: Arc::default(),
+            bulk_batches: Arc::default(),
+            freezes: Arc::default(),
+            spend_caps: Arc::new(SpendCaps::new(config.spend_caps.clone())),
+            cost_guard: Arc::new(CostGuard::new(config.cost_guard.clone())),
+            audit: Arc::new(AuditLog::new(config.audit.path.clone(), config.region.current)),
+            quota,
+            concurrency,
+            load_shedder: Arc::new(LoadShedder::new(config.load_shedding.clone())),
+            decode_errors,
+            roles: Arc::default(),
+            experiments: Arc::default(),
+            labels: Arc::default(),
+            columns: Arc::default(),
+            summaries: Arc::default(),
+            accounts: Arc::default(),
+            annotations: Arc::default(),
+            schedules: Arc::default(),
+            raw_captures: Arc::default(),
+            entity_cache,
+            delta_sync: Arc::new(DeltaSync::new(config.sync.clone())),
+            idempotency: Arc::new(idempotency),
+            #[cfg(feature = "redis")]
+            shared,
+            #[cfg(feature = "reports")]
+            reports: Arc::default(),
+            credential_health,
+            credentials,
+            updates: updates::update_channel(),
+            dashboard_events: updates::dashboard_channel(),
+            usage: Arc::default(),
+            api_usage: Arc::default(),
+            warmup: Arc::default(),
+            regions: Arc::new(RegionRouter::new(config.region.clone(), peer_client)),
+            config: Arc::new(config),
+        })
+    }
+}
+
+/// Query parameters accepted by `GET /campaigns`
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CampaignQuery {
+    /// Comma-separated list of optional blocks to embed (e.g. `pacing`,
+    /// `targeting`, `quality`, `auction`); also accepted as `?expand=`
+    #[serde(alias = "expand")]
+    pub include: Option<String>,
+    /// Sparse fieldset, e.g. `id,name,metrics.cost`
+    pub fields: Option<String>,
+    /// Sort keys, e.g. `platform,-cost,name`; daily budget, highest first,
+    /// by default. See `sort`
+    pub sort: Option<String>,
+    /// Comma-separated bid strategies to keep, e.g. `target_cpa,target_roas`
+    pub bid_strategy: Option<String>,
+    /// Comma-separated labels to keep, e.g. `brand,black friday`; any
+    /// platform, case-insensitive
+    pub label: Option<String>,
+    /// Comma-separated objectives to keep, e.g. `sales,leads`
+    pub objective: Option<String>,
+    /// Comma-separated campaign types to keep, e.g. `search,shopping`
+    pub campaign_type: Option<String>,
+    /// Keep removed campaigns, otherwise listed by `/campaigns/archived`
+    #[serde(default)]
+    pub include_removed: bool,
+    /// Comma-separated platforms to keep, e.g. `google,meta`
+    pub platform: Option<String>,
+    /// Ad account, manager or Business Manager to keep the campaigns of,
+    /// every account below it included; see `accounts`
+    pub account_id: Option<AccountId>,
+    /// Comma-separated statuses to keep, e.g. `enabled,paused`; listing
+    /// `removed` implies `include_removed`
+    pub status: Option<String>,
+    /// Filter expression, e.g. `platform = google AND metrics.cpa < 30`;
+    /// see `filter`
+    pub filter: Option<String>,
+    /// First day of the metrics; with either date, metrics are the totals
+    /// of the range recorded by the sync worker instead of month-to-date.
+    /// Defaults to the first day of `end_date`'s month
+    pub start_date: Option<NaiveDate>,
+    /// Last day of the metrics, inclusive; defaults to today in the
+    /// reporting time zone, see `timezones`
+    pub end_date: Option<NaiveDate>,
+    /// Campaigns per page; without it every campaign is returned at once
+    pub limit: Option<usize>,
+    /// `X-Next-Cursor` of the previous page
+    pub cursor: Option<String>,
+}
+
+impl CampaignQuery {
+    /// Whether `block` was requested through `?include=`
+    pub fn includes(&self, block: &str) -> bool {
+        self.include
+            .as_deref()
+            .map(|list| list.split(',').any(|item| item.trim() == block))
+            .unwrap_or(false)
+    }
+
+    /// Range the metrics are totalled over, `None` for month-to-date
+    pub fn date_range(&self) -> Result<Option<(NaiveDate, NaiveDate)>, ApiError> {
+        if self.start_date.is_none() && self.end_date.is_none() {
+            return Ok(None);
+        }
+        let end = self.end_date.unwrap_or_else(timezones::today);
+        let start = self.start_date.unwrap_or_else(|| end.with_day(1).unwrap_or(end));
+        if start > end {
+            return Err(ApiError::Validation("start_date must not be after end_date".to_string()));
+        }
+        Ok(Some((start, end)))
+    }
+}
+
+/// Largest `limit` accepted by `GET /campaigns`
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Response header of `GET /campaigns` carrying the cursor of the next page
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Keep the page of `campaigns` starting at `cursor` and return the cursor
+/// of the next one, `None` on the last page
+///
+/// The cursor is the offset of the page in the filtered, sorted list. The
+/// list is aggregated again for every page, so a campaign created or
+/// removed between two requests can shift it by one.
+pub fn paginate(
+    campaigns: &mut Vec<Campaign>,
+    limit: Option<usize>,
+    cursor: Option<&str>,
+) -> Result<Option<usize>, ApiError> {
+    let Some(limit) = limit else {
+        if cursor.is_some() {
+            return Err(ApiError::Validation("cursor requires limit".to_string()));
+        }
+        return Ok(None);
+    };
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::Validation(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+    let offset = match cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| ApiError::Validation(format!("invalid cursor {:?}", cursor)))?,
+        None => 0,
+    };
+
+    let end = offset.saturating_add(limit);
+    let next = (end < campaigns.len()).then_some(end);
+    campaigns.truncate(end);
+    campaigns.drain(..offset.min(campaigns.len()));
+    Ok(next)
 }
 
 /// GET /campaigns - Unified campaigns endpoint
 /// 
-/// Fetches campaigns from both Google Ads and Meta Ads in parallel,
-/// normalizes the data, and returns a unified response.
+/// Fetches campaigns from every registered platform in parallel,
+/// normalizes the data, and returns a unified response. Carries an ETag;
+/// a matching `If-None-Match` gets `304 Not Modified`. With
+/// `Accept: application/x-ndjson` campaigns are streamed instead, see `ndjson`.
+/// With `?limit=` campaigns are returned a page at a time, see `paginate`.
+/// Identical concurrent requests share one fetch, see `coalesce`.
 pub async fn get_campaigns(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<Campaign>>, StatusCode> {
-    // Parallel requests to gateway for both platforms
-    let google_future = fetch_google_campaigns(&state);
-    let meta_future = fetch_meta_campaigns(&state);
-    
-    // Wait for both requests concurrently
-    let (google_result, meta_result) = tokio::join!(google_future, meta_future);
-    
-    // Handle results
-    let mut campaigns = Vec::new();
-    
-    if let Ok(google_campaigns) = google_result {
-        campaigns.extend(google_campaigns);
+    Query(query): Query<CampaignQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let selection = FieldSelection::from_query(query.fields.as_deref())?;
+    if ndjson::accepts_ndjson(&headers) {
+        return ndjson::stream_campaigns(state, &query, selection);
     }
-    
-    if let Ok(meta_campaigns) = meta_result {
-        campaigns.extend(meta_campaigns);
+    let key = coalesce::campaigns_key(&headers, &query);
+    let fetch = {
+        let state = state.clone();
+        async move {
+            // Collected with the page, so requests joining the fetch get them too
+            let fetch = freshness::collect(list_campaigns(&state, &query));
+            let ((page, freshness), missed) = latency_budget::collect(fetch).await;
+            page.map(|page| Arc::new(CampaignPage { freshness, missed, ..page }))
+        }
+    };
+    let page = state.campaign_flights.run(key, fetch).await?;
+    freshness::extend(&page.freshness);
+    latency_budget::extend(&page.missed);
+
+    let mut response =
+        Conditional::new(Sparse(&page.campaigns, selection), &headers).into_response();
+    if let Some(next) = page.next_cursor {
+        response.headers_mut().insert(NEXT_CURSOR_HEADER, HeaderValue::from(next));
     }
-    
-    // Sort by daily budget (highest first)
-    campaigns.sort_by(|a, b| {
-        b.daily_budget
-            .partial_cmp(&a.daily_budget)
-            .unwrap_or(std::cmp::Ordering::Equal)
+    Ok(response)
+}
+
+/// Campaigns of a `GET /campaigns` request: filtered, sorted, paged and
+/// with the requested blocks embedded
+pub async fn list_campaigns(
+    state: &AppState,
+    query: &CampaignQuery,
+) -> Result<CampaignPage, ApiError> {
+    let strategies = query.bid_strategy.as_deref().map(BidStrategy::parse_list).transpose()?;
+    let objectives = query.objective.as_deref().map(CampaignObjective::parse_list).transpose()?;
+    let types = query.campaign_type.as_deref().map(CampaignType::parse_list).transpose()?;
+    let platforms = query.platform.as_deref().map(Platform::parse_list).transpose()?;
+    let statuses = query.status.as_deref().map(CampaignStatus::parse_list).transpose()?;
+    let filter = query.filter.as_deref().map(Filter::parse).transpose()?;
+    let range = query.date_range()?;
+    let sort = query.sort.as_deref().map(SortSpec::parse).transpose()?.unwrap_or_default();
+    let scope = match &query.account_id {
+        Some(id) => Some(
+            state
+                .accounts
+                .hierarchy(&state.connectors)
+                .await?
+                .scope(id)
+                .ok_or_else(|| ApiError::NotFound(format!("account {}", id)))?,
+        ),
+        None => None,
+    };
+    let mut campaigns = aggregate_campaigns(state).await;
+    let lists_removed = statuses.iter().flatten().any(|s| *s == CampaignStatus::Removed);
+    if !query.include_removed && !lists_removed {
+        archive::exclude_removed(&mut campaigns);
+    }
+    campaigns.retain(|c| {
+        matches_kind(c, objectives.as_deref(), types.as_deref())
+            && platforms.iter().all(|list| list.contains(&c.platform))
+            && statuses.iter().all(|list| list.contains(&c.status))
+            && scope.iter().all(|scope| scope.contains(c))
     });
+    if let Some((start, end)) = range {
+        for campaign in &mut campaigns {
+            campaign.metrics =
+                history::period_totals(&state.metrics_store, &campaign.global_id(), start, end);
+            freshness::record_store(&state.metrics_store, &campaign.platform);
+        }
+    }
+    if let Some(filter) = &filter {
+        campaigns.retain(|c| filter.matches(c));
+    }
+    sort.sort(&mut campaigns);
+
+    bidding::attach_bidding(&state.connectors, &mut campaigns).await;
+    if let Some(strategies) = strategies {
+        bidding::filter_by_strategy(&mut campaigns, &strategies);
+    }
+    if let Some(list) = &query.label {
+        labels::filter_by_label(&mut campaigns, &labels::parse_label_list(list));
+    }
+    let next_cursor = paginate(&mut campaigns, query.limit, query.cursor.as_deref())?;
+    
+    if query.includes("pacing") {
+        pacing::attach_pacing(&mut campaigns, timezones::today());
+    }
+
+    if query.includes("targeting") {
+        targeting::attach_targeting(&state.connectors, &mut campaigns).await;
+    }
+
+    if query.includes("quality") {
+        quality::attach_quality(&state.connectors, &mut campaigns).await;
+    }
+
+    if query.includes("auction") {
+        auction::attach_auction_insights(&state.connectors, &mut campaigns).await;
+    }
     
-    Ok(Json(campaigns))
+    Ok(CampaignPage {
+        campaigns,
+        next_cursor,
+        freshness: Freshness::new(),
+        missed: Vec::new(),
+    })
+}
+
+/// Fetch campaigns from every platform and merge them into one list
+/// sorted by daily budget (highest first), tagged from their names
+pub async fn aggregate_campaigns(state: &AppState) -> Vec<Campaign> {
+    let mut campaigns = aggregate_from(&state.connectors).await;
+    latency_budget::fill_missed(state, &mut campaigns);
+    normalization::normalize_campaigns(state, &mut campaigns);
+    naming::tag_campaigns(&state.naming.get(), &mut campaigns);
+    campaigns
+}
+
+/// Campaign `id` from the entity cache, or from a fresh aggregation
+pub async fn find_campaign(state: &AppState, id: &str) -> Option<Campaign> {
+    if let Some(mut campaign) = state.entity_cache.find_campaign(id) {
+        normalization::normalize_campaigns(state, std::slice::from_mut(&mut campaign));
+        naming::tag_campaigns(&state.naming.get(), std::slice::from_mut(&mut campaign));
+        return Some(campaign);
+    }
+
+    aggregate_campaigns(state).await.into_iter().find(|c| c.id == id)
 }
 
-/// Fetch Google Ads campaigns from gateway
-async fn fetch_google_campaigns(
+/// `campaign` from the entity cache, or from a fresh aggregation
+pub async fn find_global_campaign(
     state: &AppState,
-) -> Result<Vec<Campaign>, reqwest::Error> {
-    let url = format!("{}/v1/google/campaigns", state.gateway_url);
+    campaign: &GlobalCampaignId,
+) -> Option<Campaign> {
+    if let Some(mut cached) = state.entity_cache.campaign(&campaign.platform, &campaign.id) {
+        normalization::normalize_campaigns(state, std::slice::from_mut(&mut cached));
+        naming::tag_campaigns(&state.naming.get(), std::slice::from_mut(&mut cached));
+        return Some(cached);
+    }
+
+    aggregate_campaigns(state).await.into_iter().find(|c| c.global_id() == *campaign)
+}
+
+/// Fetch campaigns from every registered connector in parallel
+///
+/// A failing platform is logged and skipped so the others still render.
+/// One missing the request's latency budget is skipped too, and answered
+/// from the cache or store by `aggregate_campaigns`.
+pub async fn aggregate_from(registry: &ConnectorRegistry) -> Vec<Campaign> {
+    // One gateway call per connector; an overdrawn request fails in the
+    // call budget middleware
+    if call_budget::charge(registry.all().len()).is_err() {
+        return Vec::new();
+    }
+
+    // Parallel requests to every platform connector
+    let fetches = registry.all().iter().map(|connector| async move {
+        let platform = connector.platform();
+        let stage = format!("adapter.{}", platform.as_str());
+        let fetch = timing::timed(&stage, connector.fetch_campaigns());
+        let result = latency_budget::within(&platform, fetch).await;
+        (platform, result)
+    });
     
-    let response = state.http_client
-        .get(&url)
-        .header("X-Service-Auth", "internal-token")
-        .send()
-        .await?;
+    let results = futures::future::join_all(fetches).await;
     
-    response.json::<Vec<Campaign>>().await
+    timing::time("merge", || {
+        // Handle results
+        let mut campaigns = Vec::new();
+
+        for (platform, result) in results {
+            match result {
+                Some(Ok(platform_campaigns)) => {
+                    // Reported as cached instead when the entity cache served the list
+                    freshness::record(&platform, DataSource::Live, Some(chrono::Utc::now()));
+                    campaigns.extend(platform_campaigns)
+                }
+                Some(Err(e)) => tracing::warn!(
+                    platform = platform.as_str(),
+                    error = %e,
+                    "platform fetch failed"
+                ),
+                // Past the request's latency budget, see `latency_budget::fill_missed`
+                None => {}
+            }
+        }
+
+        // Sort by daily budget (highest first)
+        SortSpec::default().sort(&mut campaigns);
+
+        campaigns
+    })
 }
 
-/// Fetch Meta Ads campaigns from gateway
-async fn fetch_meta_campaigns(
-    state: &AppState,
-) -> Result<Vec<Campaign>, reqwest::Error> {
-    let url = format!("{}/v1/meta/campaigns", state.gateway_url);
+/// Example: Calculate total daily budget of enabled campaigns, per currency
+pub fn calculate_total_budget(campaigns: &[Campaign]) -> MoneyByCurrency {
+    money::sum_by_currency(
+        campaigns
+            .iter()
+            .filter(|c| matches!(c.status, CampaignStatus::Enabled))
+            .map(|c| (c.currency(), c.daily_budget)),
+    )
+}
+
+/// Example: Combine the metrics of several campaigns in one currency
+///
+/// Counts, cost and conversion value are summed, per conversion action
+/// too; CTR (percent), CPA and ROAS are recomputed from the totals rather
+/// than averaged. Campaigns in different currencies go through
+/// `combine_by_currency` instead.
+///
+/// Reach is summed only when every campaign reports it, and a person
+/// reached by two campaigns counts twice: platforms de-duplicate within a
+/// campaign, not across campaigns. Video metrics combine over the
+/// campaigns reporting them; see `ReachTotals`.
+pub fn combine_metrics<'a>(
+    metrics: impl IntoIterator<Item = &'a CampaignMetrics>,
+) -> CampaignMetrics {
+    let mut total = CampaignMetrics::default();
+    let mut reach = ReachTotals::default();
     
-    let response = state.http_client
-        .get(&url)
-        .header("X-Service-Auth", "internal-token")
-        .send()
-        .await?;
+    for m in metrics {
+        total.impressions += m.impressions;
+        total.clicks += m.clicks;
+        total.conversions += m.conversions;
+        total.cost += m.cost;
+        total.conversion_value += m.conversion_value;
+        reach.add(m.impressions, m.reach, m.video_views, m.average_watch_time_seconds);
+
+        for action in &m.conversion_actions {
+            match total.conversion_actions.iter_mut().find(|a| a.action == action.action) {
+                Some(existing) => {
+                    existing.conversions += action.conversions;
+                    existing.value += action.value;
+                }
+                None => total.conversion_actions.push(action.clone()),
+            }
+        }
+    }
     
-    response.json::<Vec<Campaign>>().await
+    reach.apply(&mut total);
+    recompute_rates(&mut total);
+    total
 }
 
-/// Example: Calculate total daily budget across all campaigns
-pub fn calculate_total_budget(campaigns: &[Campaign]) -> f64 {
-    campaigns
+/// Running totals of the optional reach and video metrics of several rows
+#[derive(Debug, Default, Clone)]
+pub struct ReachTotals {
+    rows: usize,
+    rows_with_reach: usize,
+    reach: u64,
+    rows_with_video: usize,
+    video_views: u64,
+    /// Impressions of the rows reporting video views
+    video_impressions: u64,
+    /// Views of the rows reporting a watch time, and seconds watched
+    timed_views: u64,
+    watch_seconds: f64,
+}
+
+impl ReachTotals {
+    pub fn add(
+        &mut self,
+        impressions: u64,
+        reach: Option<u64>,
+        video_views: Option<u64>,
+        average_watch_time_seconds: Option<f64>,
+    ) {
+        self.rows += 1;
+        if let Some(reach) = reach {
+            self.rows_with_reach += 1;
+            self.reach += reach;
+        }
+        if let Some(views) = video_views {
+            self.rows_with_video += 1;
+            self.video_views += views;
+            self.video_impressions += impressions;
+            if let Some(seconds) = average_watch_time_seconds {
+                self.timed_views += views;
+                self.watch_seconds += seconds * views as f64;
+            }
+        }
+    }
+
+    /// Set the reach and video metrics of `metrics`; frequency is left to
+    /// `recompute_rates`
+    pub fn apply(&self, metrics: &mut CampaignMetrics) {
+        let reported = self.rows > 0 && self.rows_with_reach == self.rows;
+        metrics.reach = reported.then_some(self.reach);
+
+        metrics.video_views = (self.rows_with_video > 0).then_some(self.video_views);
+        metrics.video_view_rate = safe_div(self.video_views as f64, self.video_impressions as f64)
+            .map(|rate| rate * 100.0);
+        metrics.average_watch_time_seconds = safe_div(self.watch_seconds, self.timed_views as f64);
+    }
+}
+
+/// Set CTR (percent), CPA, ROAS and frequency of summed `metrics` from its
+/// totals
+pub fn recompute_rates(metrics: &mut CampaignMetrics) {
+    if metrics.impressions > 0 {
+        metrics.ctr = metrics.clicks as f64 / metrics.impressions as f64 * 100.0;
+    }
+    if metrics.conversions > 0 {
+        metrics.cpa = metrics.cost / metrics.conversions as f64;
+    }
+    metrics.roas = roas(metrics.conversion_value, metrics.cost);
+    metrics.frequency =
+        metrics.reach.and_then(|reach| safe_div(metrics.impressions as f64, reach as f64));
+}
+
+/// Clear the reach and frequency of totals spanning several platforms
+///
+/// People reached on Google and on Meta cannot be matched, so reach is
+/// only ever totalled per platform; the `frequency` module estimates the
+/// combined reach of several platforms instead.
+pub fn drop_cross_platform_reach(metrics: &mut CampaignMetrics) {
+    metrics.reach = None;
+    metrics.frequency = None;
+}
+
+/// Combine the metrics of campaigns separately for each currency
+///
+/// Totals of campaigns from several platforms have no reach, see
+/// `drop_cross_platform_reach`.
+pub fn combine_by_currency<'a>(
+    campaigns: impl IntoIterator<Item = &'a Campaign>,
+) -> BTreeMap<Currency, CampaignMetrics> {
+    let mut by_currency: BTreeMap<Currency, Vec<&Campaign>> = BTreeMap::new();
+    for campaign in campaigns {
+        by_currency.entry(campaign.currency()).or_default().push(campaign);
+    }
+
+    by_currency
+        .into_iter()
+        .map(|(currency, campaigns)| {
+            let mut metrics = combine_metrics(campaigns.iter().map(|c| &c.metrics));
+            if campaigns.iter().any(|c| c.platform != campaigns[0].platform) {
+                drop_cross_platform_reach(&mut metrics);
+            }
+            (currency, metrics)
+        })
+        .collect()
+}
+
+/// Response of `GET /campaigns/summary`
+///
+/// Money is never added across currencies: every total is keyed by the
+/// currency it is in.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignSummary {
+    /// Daily budget of enabled campaigns
+    pub total_daily_budget: MoneyByCurrency,
+    pub metrics: BTreeMap<Currency, CampaignMetrics>,
+    pub by_platform: BTreeMap<String, BTreeMap<Currency, CampaignMetrics>>,
+    /// Present with `?compare=previous_period`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compare: Option<BTreeMap<Currency, PeriodComparison>>,
+    /// Display label of each metric in the request's locale
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Totals across campaigns per currency, overall and per platform
+pub fn summarize(campaigns: &[Campaign]) -> CampaignSummary {
+    let mut by_platform: BTreeMap<String, Vec<&Campaign>> = BTreeMap::new();
+    for campaign in campaigns {
+        by_platform
+            .entry(campaign.platform.as_str().to_string())
+            .or_default()
+            .push(campaign);
+    }
+
+    CampaignSummary {
+        total_daily_budget: calculate_total_budget(campaigns),
+        metrics: combine_by_currency(campaigns),
+        by_platform: by_platform
+            .into_iter()
+            .map(|(platform, campaigns)| (platform, combine_by_currency(campaigns)))
+            .collect(),
+        compare: None,
+        labels: BTreeMap::new(),
+    }
+}
+
+/// GET /campaigns/summary - Spend, conversions and ROAS totals per currency
+///
+/// Totals come from the columns of the last sync while fresh, see
+/// `columnar`. With `?compare=previous_period` the recorded daily values
+/// of the last `days` days are compared against the `days` before, per
+/// currency.
+pub async fn get_campaign_summary(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SummaryQuery>,
+) -> Result<Finite<CampaignSummary>, ApiError> {
+    Ok(Finite(campaign_summary(&state, &query).await?))
+}
+
+/// Summary of a `GET /campaigns/summary` request, whatever its API version
+pub async fn campaign_summary(
+    state: &AppState,
+    query: &SummaryQuery,
+) -> Result<CampaignSummary, ApiError> {
+    let precomputed = summary_cache::current_summary(state).await;
+    let mut summary = CampaignSummary::clone(&precomputed.summary);
+    if query.compare.is_some() {
+        let mut campaign_ids = BTreeMap::clone(&precomputed.ids_by_currency);
+        // Campaigns no longer listed still ran in the compared periods
+        archive::add_removed_ids(&state.metrics_store, &mut campaign_ids);
+
+        let mut compare = BTreeMap::new();
+        for (currency, ids) in campaign_ids {
+            let comparison = history::summary_comparison(&state.metrics_store, &ids, query)?;
+            if let Some(comparison) = comparison {
+                compare.insert(currency, comparison);
+            }
+        }
+        for platform in &precomputed.platforms {
+            freshness::record_store(&state.metrics_store, platform);
+        }
+        summary.compare = Some(compare);
+    }
+    let locale = locale::current();
+    summary.labels = units::METRICS
         .iter()
-        .filter(|c| matches!(c.status, CampaignStatus::Enabled))
-        .map(|c| c.daily_budget)
-        .sum()
+        .map(|def| (def.name.to_string(), locale.metric_label(def.name)))
+        .collect();
+
+    Ok(summary)
 }
 
 /// Example: Group campaigns by platform
@@ -137,6 +1136,33 @@ pub fn group_by_platform(
     campaigns.into_iter().partition(|c| matches!(c.platform, Platform::Google))
 }
 
+// Sample campaign constructor (for tests across modules)
+#[cfg(test)]
+impl Campaign {
+    pub fn sample(id: &str, platform: Platform) -> Self {
+        Self {
+            id: id.into(),
+            platform,
+            name: format!("Campaign {}", id),
+            status: CampaignStatus::Enabled,
+            objective: None,
+            campaign_type: None,
+            daily_budget: 100.0,
+            currency: "BRL".to_string(),
+            metrics: Default::default(),
+            pacing: None,
+            targeting: None,
+            bidding: None,
+            quality: None,
+            auction: None,
+            tracking_template: None,
+            account_id: None,
+            labels: Vec::new(),
+            tags: HashMap::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,10 +1171,12 @@ mod tests {
     fn test_calculate_total_budget() {
         let campaigns = vec![
             Campaign {
-                id: "1".to_string(),
+                id: "1".into(),
                 platform: Platform::Google,
                 name: "Test 1".to_string(),
                 status: CampaignStatus::Enabled,
+                objective: None,
+                campaign_type: None,
                 daily_budget: 100.0,
                 currency: "BRL".to_string(),
                 metrics: CampaignMetrics {
@@ -158,13 +1186,32 @@ mod tests {
                     cost: 90.0,
                     ctr: 5.0,
                     cpa: 18.0,
+                    conversion_value: 450.0,
+                    roas: 5.0,
+                    conversion_actions: Vec::new(),
+                    reach: None,
+                    frequency: None,
+                    video_views: None,
+                    video_view_rate: None,
+                    average_watch_time_seconds: None,
                 },
+                pacing: None,
+                targeting: None,
+                bidding: None,
+                quality: None,
+                auction: None,
+                tracking_template: None,
+                account_id: None,
+                labels: Vec::new(),
+                tags: HashMap::new(),
             },
             Campaign {
-                id: "2".to_string(),
+                id: "2".into(),
                 platform: Platform::Meta,
                 name: "Test 2".to_string(),
                 status: CampaignStatus::Paused,
+                objective: None,
+                campaign_type: None,
                 daily_budget: 200.0,
                 currency: "BRL".to_string(),
                 metrics: CampaignMetrics {
@@ -174,34 +1221,75 @@ mod tests {
                     cost: 45.0,
                     ctr: 5.0,
                     cpa: 22.5,
+                    conversion_value: 0.0,
+                    roas: 0.0,
+                    conversion_actions: Vec::new(),
+                    reach: None,
+                    frequency: None,
+                    video_views: None,
+                    video_view_rate: None,
+                    average_watch_time_seconds: None,
                 },
+                pacing: None,
+                targeting: None,
+                bidding: None,
+                quality: None,
+                auction: None,
+                tracking_template: None,
+                account_id: None,
+                labels: Vec::new(),
+                tags: HashMap::new(),
             },
         ];
         
         // Only enabled campaigns should be counted
-        assert_eq!(calculate_total_budget(&campaigns), 100.0);
+        let totals = calculate_total_budget(&campaigns);
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[&Currency::new("BRL")].amount, 100.0);
     }
     
     #[test]
     fn test_group_by_platform() {
         let campaigns = vec![
             Campaign {
-                id: "g1".to_string(),
+                id: "g1".into(),
                 platform: Platform::Google,
                 name: "Google Campaign".to_string(),
                 status: CampaignStatus::Enabled,
+                objective: None,
+                campaign_type: None,
                 daily_budget: 100.0,
                 currency: "BRL".to_string(),
                 metrics: Default::default(),
+                pacing: None,
+                targeting: None,
+                bidding: None,
+                quality: None,
+                auction: None,
+                tracking_template: None,
+                account_id: None,
+                labels: Vec::new(),
+                tags: HashMap::new(),
             },
             Campaign {
-                id: "m1".to_string(),
+                id: "m1".into(),
                 platform: Platform::Meta,
                 name: "Meta Campaign".to_string(),
                 status: CampaignStatus::Enabled,
+                objective: None,
+                campaign_type: None,
                 daily_budget: 200.0,
                 currency: "BRL".to_string(),
                 metrics: Default::default(),
+                pacing: None,
+                targeting: None,
+                bidding: None,
+                quality: None,
+                auction: None,
+                tracking_template: None,
+                account_id: None,
+                labels: Vec::new(),
+                tags: HashMap::new(),
             },
         ];
         
@@ -209,6 +1297,118 @@ mod tests {
         assert_eq!(google.len(), 1);
         assert_eq!(meta.len(), 1);
     }
+
+    #[test]
+    fn test_summary_recomputes_roas_from_totals() {
+        let action = |name: &str, value: f64| ConversionActionMetrics {
+            action: name.to_string(),
+            conversions: 1.0,
+            value,
+        };
+        let mut google = Campaign::sample("g1", Platform::Google);
+        google.metrics.cost = 100.0;
+        google.metrics.conversion_value = 500.0;
+        google.metrics.roas = 5.0;
+        google.metrics.conversion_actions = vec![action("purchase", 500.0)];
+        let mut meta = Campaign::sample("m1", Platform::Meta);
+        meta.metrics.cost = 300.0;
+        meta.metrics.conversion_value = 300.0;
+        meta.metrics.roas = 1.0;
+        meta.metrics.conversion_actions = vec![action("purchase", 200.0), action("lead", 100.0)];
+
+        let summary = summarize(&[google.clone(), meta.clone()]);
+
+        // 800 / 400, not the average of 5.0 and 1.0
+        let brl = Currency::new("BRL");
+        assert_eq!(summary.metrics[&brl].roas, 2.0);
+        assert_eq!(summary.by_platform["meta"][&brl].roas, 1.0);
+        let purchase = &summary.metrics[&brl].conversion_actions[0];
+        assert_eq!(purchase.action, "purchase");
+        assert_eq!(purchase.value, 700.0);
+
+        let mut campaigns = vec![meta, google];
+        SortSpec::parse("roas").unwrap().sort(&mut campaigns);
+        assert_eq!(campaigns[0].id, "g1");
+    }
+
+    #[test]
+    fn test_reach_totalled_per_platform_only() {
+        let meta = |id: &str, impressions, reach, views| {
+            let mut campaign = Campaign::sample(id, Platform::Meta);
+            campaign.metrics.impressions = impressions;
+            campaign.metrics.reach = Some(reach);
+            campaign.metrics.video_views = views;
+            campaign.metrics.average_watch_time_seconds = views.map(|_| 10.0);
+            campaign
+        };
+        let mut google = Campaign::sample("g1", Platform::Google);
+        google.metrics.impressions = 5_000;
+
+        let summary = summarize(&[
+            meta("m1", 3_000, 1_000, Some(600)),
+            meta("m2", 1_000, 1_000, None),
+            google,
+        ]);
+
+        let brl = Currency::new("BRL");
+        let meta = &summary.by_platform["meta"][&brl];
+        assert_eq!((meta.reach, meta.frequency), (Some(2_000), Some(2.0)));
+        // Over the impressions of the campaign reporting video views
+        assert_eq!((meta.video_views, meta.video_view_rate), (Some(600), Some(20.0)));
+        assert_eq!(meta.average_watch_time_seconds, Some(10.0));
+        let google = &summary.by_platform["google"][&brl];
+        assert_eq!((google.reach, google.video_views), (None, None));
+        assert_eq!((summary.metrics[&brl].reach, summary.metrics[&brl].frequency), (None, None));
+        let json = serde_json::to_value(google).unwrap();
+        assert!(json["reach"].is_null() && json["average_watch_time_seconds"].is_null());
+    }
+
+    #[test]
+    fn test_objective_and_type_filters() {
+        let objectives = CampaignObjective::parse_list("conversions, app_installs").unwrap();
+        assert_eq!(objectives, [CampaignObjective::Sales, CampaignObjective::AppPromotion]);
+        assert!(CampaignType::parse_list("search,carousel").is_err());
+        let types = CampaignType::parse_list("search").unwrap();
+
+        let mut search = Campaign::sample("g1", Platform::Google);
+        search.objective = Some(CampaignObjective::Sales);
+        search.campaign_type = Some(CampaignType::Search);
+        let mut video = search.clone();
+        video.campaign_type = Some(CampaignType::Video);
+        let untyped = Campaign::sample("m1", Platform::Meta);
+
+        assert!(matches_kind(&search, Some(&objectives), Some(&types)));
+        assert!(!matches_kind(&video, Some(&objectives), Some(&types)));
+        assert!(matches_kind(&untyped, None, None));
+        assert!(!matches_kind(&untyped, None, Some(&types)));
+    }
+
+    #[test]
+    fn test_pages_follow_cursors_to_the_end() {
+        let campaigns: Vec<Campaign> =
+            (1..=5).map(|i| Campaign::sample(&format!("g{}", i), Platform::Google)).collect();
+        let mut ids = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut page = campaigns.clone();
+            let next = paginate(&mut page, Some(2), cursor.as_deref()).unwrap();
+            ids.extend(page.into_iter().map(|c| c.id));
+            match next {
+                Some(next) => cursor = Some(next.to_string()),
+                None => break,
+            }
+        }
+        assert_eq!(ids, ["g1", "g2", "g3", "g4", "g5"]);
+
+        let mut all = campaigns.clone();
+        assert_eq!(paginate(&mut all, None, None).unwrap(), None);
+        assert_eq!(all.len(), 5);
+        assert!(paginate(&mut all, None, Some("2")).is_err());
+        assert!(paginate(&mut all, Some(0), None).is_err());
+        assert!(paginate(&mut all, Some(2), Some("abc")).is_err());
+        let platforms = Platform::parse_list("Google, meta").unwrap();
+        assert_eq!(platforms, [Platform::Google, Platform::Meta]);
+    }
 }
 
 // Default implementation for CampaignMetrics (for tests)
@@ -221,6 +1421,14 @@ impl Default for CampaignMetrics {
             cost: 0.0,
             ctr: 0.0,
             cpa: 0.0,
+            conversion_value: 0.0,
+            roas: 0.0,
+            conversion_actions: Vec::new(),
+            reach: None,
+            frequency: None,
+            video_views: None,
+            video_view_rate: None,
+            average_watch_time_seconds: None,
         }
     }
 }
@@ -0,0 +1,254 @@
+//! # Campaign Groups Example
+//!
+//! Agencies often mirror the same campaign on Google and Meta using a
+//! shared naming convention or UTM tags. Campaign groups link those
+//! mirrors into one logical campaign with combined metrics. Mirrors may
+//! run in different currencies, so budgets and metrics are combined per
+//! currency.
+//!
+//! Each campaign is assigned to the group key produced by the first
+//! matching rule; campaigns no rule matches are left ungrouped.
+
+use axum::{extract::State, response::Json};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{
+    aggregate_campaigns, combine_by_currency, AppState, Campaign, CampaignMetrics, Platform,
+};
+use super::error::ApiError;
+use super::ids::CampaignId;
+use super::money::{self, Currency, MoneyByCurrency};
+
+/// Rule deriving a group key from a campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MatchingRule {
+    /// Regex over the campaign name; the `key` capture group (or the whole
+    /// match when absent) becomes the group key
+    NamePattern { pattern: String },
+    /// Group by the `utm_campaign` value of the tracking template
+    UtmCampaign,
+}
+
+/// Configured matching rules, evaluated in order
+pub struct MatchingRules {
+    rules: RwLock<Vec<MatchingRule>>,
+}
+
+impl MatchingRules {
+    pub fn get(&self) -> Vec<MatchingRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    pub fn set(&self, rules: Vec<MatchingRule>) {
+        *self.rules.write().unwrap() = rules;
+    }
+}
+
+impl Default for MatchingRules {
+    fn default() -> Self {
+        Self {
+            rules: RwLock::new(vec![
+                // "[G] Black Friday SP" and "[M] Black Friday SP" → "black friday sp"
+                MatchingRule::NamePattern {
+                    pattern: r"^\[(?:G|M|GOOGLE|META)\]\s*(?P<key>.+)$".to_string(),
+                },
+                MatchingRule::UtmCampaign,
+            ]),
+        }
+    }
+}
+
+/// Reference to a campaign inside a group
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupMember {
+    pub id: CampaignId,
+    pub platform: Platform,
+    pub name: String,
+}
+
+/// Logical campaign spanning one or more platforms
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignGroup {
+    pub key: String,
+    pub campaigns: Vec<GroupMember>,
+    pub platform_count: usize,
+    pub daily_budget: MoneyByCurrency,
+    pub metrics: BTreeMap<Currency, CampaignMetrics>,
+}
+
+/// Compiled form of a rule; invalid patterns never reach this point
+enum CompiledRule {
+    Name(Regex),
+    Utm,
+}
+
+fn compile(rules: &[MatchingRule]) -> Result<Vec<CompiledRule>, regex::Error> {
+    rules
+        .iter()
+        .map(|rule| match rule {
+            MatchingRule::NamePattern { pattern } => {
+                Regex::new(&format!("(?i){}", pattern)).map(CompiledRule::Name)
+            }
+            MatchingRule::UtmCampaign => Ok(CompiledRule::Utm),
+        })
+        .collect()
+}
+
+/// Extract `utm_campaign` from a tracking template or URL suffix
+pub fn utm_campaign(tracking_template: &str) -> Option<String> {
+    let query = tracking_template
+        .split_once('?')
+        .map(|(_, q)| q)
+        .unwrap_or(tracking_template);
+
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "utm_campaign")
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty() && !value.starts_with('{'))
+}
+
+fn group_key(campaign: &Campaign, rules: &[CompiledRule]) -> Option<String> {
+    let key = rules.iter().find_map(|rule| match rule {
+        CompiledRule::Name(regex) => regex.captures(&campaign.name).map(|caps| {
+            caps.name("key")
+                .or_else(|| caps.get(0))
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default()
+        }),
+        CompiledRule::Utm => campaign.tracking_template.as_deref().and_then(utm_campaign),
+    })?;
+
+    let normalized = key
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    (!normalized.is_empty()).then_some(normalized)
+}
+
+/// Link campaigns into groups using the given rules
+pub fn build_groups(
+    campaigns: &[Campaign],
+    rules: &[MatchingRule],
+) -> Result<Vec<CampaignGroup>, regex::Error> {
+    let compiled = compile(rules)?;
+    let mut by_key: BTreeMap<String, Vec<&Campaign>> = BTreeMap::new();
+
+    for campaign in campaigns {
+        if let Some(key) = group_key(campaign, &compiled) {
+            by_key.entry(key).or_default().push(campaign);
+        }
+    }
+
+    Ok(by_key
+        .into_iter()
+        .map(|(key, members)| {
+            let platforms: HashSet<&Platform> = members.iter().map(|c| &c.platform).collect();
+
+            CampaignGroup {
+                key,
+                platform_count: platforms.len(),
+                daily_budget: money::sum_by_currency(
+                    members.iter().map(|c| (c.currency(), c.daily_budget)),
+                ),
+                metrics: combine_by_currency(members.iter().copied()),
+                campaigns: members
+                    .iter()
+                    .map(|c| GroupMember {
+                        id: c.id.clone(),
+                        platform: c.platform.clone(),
+                        name: c.name.clone(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+/// GET /campaign-groups - Logical campaigns with combined cross-platform metrics
+pub async fn get_campaign_groups(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CampaignGroup>>, ApiError> {
+    let campaigns = aggregate_campaigns(&state).await;
+
+    build_groups(&campaigns, &state.matching_rules.get())
+        .map(Json)
+        .map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+/// GET /campaign-groups/rules - Current matching rules
+pub async fn get_matching_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<MatchingRule>>, ApiError> {
+    Ok(Json(state.matching_rules.get()))
+}
+
+/// PUT /campaign-groups/rules - Replace the matching rules
+///
+/// Rejects the whole list if any name pattern is not a valid regex.
+pub async fn put_matching_rules(
+    State(state): State<Arc<AppState>>,
+    Json(rules): Json<Vec<MatchingRule>>,
+) -> Result<Json<Vec<MatchingRule>>, ApiError> {
+    compile(&rules).map_err(|e| ApiError::Validation(e.to_string()))?;
+    state.matching_rules.set(rules.clone());
+    Ok(Json(rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(id: &str, platform: Platform, name: &str, cost: f64) -> Campaign {
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.name = name.to_string();
+        campaign.metrics.cost = cost;
+        campaign
+    }
+
+    #[test]
+    fn test_groups_mirrored_campaigns_by_name() {
+        let campaigns = vec![
+            campaign("g1", Platform::Google, "[G] Black Friday SP", 100.0),
+            campaign("m1", Platform::Meta, "[M]  black friday sp", 50.0),
+            campaign("g2", Platform::Google, "Brand Search", 10.0),
+        ];
+
+        let groups = build_groups(&campaigns, &MatchingRules::default().get()).unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, "black friday sp");
+        assert_eq!(groups[0].platform_count, 2);
+        assert_eq!(groups[0].metrics[&Currency::new("BRL")].cost, 150.0);
+    }
+
+    #[test]
+    fn test_groups_by_utm_campaign() {
+        let mut google = campaign("g1", Platform::Google, "Search - Casa Jardim", 10.0);
+        google.tracking_template =
+            Some("{lpurl}?utm_source=google&utm_campaign=casa_jardim".to_string());
+        let mut meta = campaign("m1", Platform::Meta, "Casa Jardim Leads", 20.0);
+        meta.tracking_template = Some("utm_source=meta&utm_campaign=casa_jardim".to_string());
+
+        let groups = build_groups(&[google, meta], &[MatchingRule::UtmCampaign]).unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, "casa_jardim");
+        assert_eq!(groups[0].campaigns.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        let rules = vec![MatchingRule::NamePattern {
+            pattern: "([unclosed".to_string(),
+        }];
+        assert!(build_groups(&[], &rules).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! # Campaign Changelog Example
+//!
+//! Not every change reaches us through a webhook or our own mutations:
+//! agencies edit campaigns in the platform UIs too. The sync worker's
+//! daily snapshots record each campaign's budget, status and bid strategy,
+//! and `GET /campaigns/{platform}/{id}/changes` lists what differs
+//! between consecutive snapshots, whatever platform the campaign runs on.
+//!
+//! A snapshot holds the last state synced on its day, so a change is only
+//! known to fall between two snapshots: after the last sync of
+//! `previous_snapshot` and at the latest by the last sync of
+//! `detected_on`. Values missing from either snapshot (older snapshots,
+//! bidding not fetched) are not reported as changes.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::bidding::BidStrategy;
+use super::campaign_aggregator::{AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::store::Snapshot;
+
+/// What changed between two snapshots
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChangeKind {
+    BudgetRaised { before: f64, after: f64 },
+    BudgetLowered { before: f64, after: f64 },
+    StatusChanged { before: CampaignStatus, after: CampaignStatus },
+    BidStrategyChanged { before: BidStrategy, after: BidStrategy },
+}
+
+/// Change detected between two consecutive snapshots
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CampaignChange {
+    /// Day of the last snapshot before the change
+    pub previous_snapshot: NaiveDate,
+    /// Day of the first snapshot showing the change
+    pub detected_on: NaiveDate,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// Response of `GET /campaigns/{platform}/{id}/changes`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Changelog {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    /// Days covered by the snapshots
+    pub first_snapshot: NaiveDate,
+    pub last_snapshot: NaiveDate,
+    /// Most recent first
+    pub changes: Vec<CampaignChange>,
+}
+
+/// Changes from `before` to `after`, in budget, status, bid strategy order
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<ChangeKind> {
+    let mut changes = Vec::new();
+
+    if let (Some(before), Some(after)) = (before.daily_budget, after.daily_budget) {
+        // Sub-cent differences are float noise, not edits
+        if after - before >= 0.01 {
+            changes.push(ChangeKind::BudgetRaised { before, after });
+        } else if before - after >= 0.01 {
+            changes.push(ChangeKind::BudgetLowered { before, after });
+        }
+    }
+    if let (Some(before), Some(after)) = (&before.status, &after.status) {
+        if before != after {
+            changes.push(ChangeKind::StatusChanged {
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+    }
+    if let (Some(before), Some(after)) = (before.bid_strategy, after.bid_strategy) {
+        if before != after {
+            changes.push(ChangeKind::BidStrategyChanged { before, after });
+        }
+    }
+
+    changes
+}
+
+/// Changes between consecutive snapshots of `series`, most recent first
+pub fn detect_changes(series: &BTreeMap<NaiveDate, Snapshot>) -> Vec<CampaignChange> {
+    let snapshots: Vec<(&NaiveDate, &Snapshot)> = series.iter().collect();
+    let mut changes: Vec<CampaignChange> = snapshots
+        .windows(2)
+        .flat_map(|pair| {
+            let ((previous_snapshot, before), (detected_on, after)) = (pair[0], pair[1]);
+            diff(before, after).into_iter().map(|kind| CampaignChange {
+                previous_snapshot: *previous_snapshot,
+                detected_on: *detected_on,
+                kind,
+            })
+        })
+        .collect();
+
+    // Stable, so changes of one day keep their order
+    changes.sort_by_key(|c| std::cmp::Reverse(c.detected_on));
+    changes
+}
+
+/// GET /campaigns/{platform}/{id}/changes - Changes detected between daily
+/// snapshots
+pub async fn get_campaign_changes(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<Changelog>, ApiError> {
+    let campaign = GlobalCampaignId::new(platform, id);
+    let series = state.metrics_store.series(&campaign);
+    let (Some((first, _)), Some((last, _))) = (series.first_key_value(), series.last_key_value())
+    else {
+        return Err(ApiError::NotFound(format!("snapshots of campaign {}", campaign)));
+    };
+
+    Ok(Json(Changelog {
+        first_snapshot: *first,
+        last_snapshot: *last,
+        changes: detect_changes(&series),
+        platform: campaign.platform,
+        campaign_id: campaign.id,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::bidding::Bidding;
+    use super::super::campaign_aggregator::Campaign;
+    use super::super::store::MetricsStore;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn bidding(strategy: BidStrategy) -> Option<Bidding> {
+        Some(Bidding {
+            strategy,
+            target_cpa: None,
+            target_roas: None,
+            bid_limit: None,
+            platform_strategy: String::new(),
+        })
+    }
+
+    #[test]
+    fn test_changes_between_consecutive_snapshots() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        campaign.bidding = bidding(BidStrategy::MaximizeClicks);
+        store.record(day(1), &[campaign.clone()]);
+
+        campaign.daily_budget = 150.0;
+        store.record(day(2), &[campaign.clone()]);
+        // Nothing changed on day 3
+        store.record(day(3), &[campaign.clone()]);
+
+        campaign.status = CampaignStatus::Paused;
+        campaign.bidding = bidding(BidStrategy::TargetCpa);
+        store.record(day(5), &[campaign.clone()]);
+
+        // Bidding not fetched: unknown, not a change
+        campaign.bidding = None;
+        campaign.daily_budget = 120.0;
+        store.record(day(6), &[campaign]);
+
+        let g1 = GlobalCampaignId::new(Platform::Google, "g1");
+        let changes = detect_changes(&store.series(&g1));
+
+        let kinds: Vec<&ChangeKind> = changes.iter().map(|c| &c.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &ChangeKind::BudgetLowered { before: 150.0, after: 120.0 },
+                &ChangeKind::StatusChanged {
+                    before: CampaignStatus::Enabled,
+                    after: CampaignStatus::Paused
+                },
+                &ChangeKind::BidStrategyChanged {
+                    before: BidStrategy::MaximizeClicks,
+                    after: BidStrategy::TargetCpa
+                },
+                &ChangeKind::BudgetRaised { before: 100.0, after: 150.0 },
+            ]
+        );
+        // The status flip happened after day 3's last sync, by day 5's
+        assert_eq!((changes[1].previous_snapshot, changes[1].detected_on), (day(3), day(5)));
+    }
+
+    #[test]
+    fn test_changes_serialize_flat() {
+        let change = CampaignChange {
+            previous_snapshot: day(1),
+            detected_on: day(2),
+            kind: ChangeKind::BudgetRaised { before: 100.0, after: 150.0 },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            serde_json::json!({
+                "previous_snapshot": "2025-11-01",
+                "detected_on": "2025-11-02",
+                "type": "budget_raised",
+                "before": 100.0,
+                "after": 150.0
+            })
+        );
+    }
+}
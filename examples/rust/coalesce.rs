@@ -0,0 +1,165 @@
+//! # Request Coalescing Example
+//!
+//! When fifty dashboards open at once on a cold cache, each
+//! `GET /campaigns` fetches every platform on its own: fifty requests,
+//! a hundred upstream calls. Identical concurrent requests are coalesced
+//! instead (single flight):
+//!
+//! - the first request runs the fetch;
+//! - requests arriving while it is in flight await the same fetch and
+//!   share its result, errors included;
+//! - once it completes the flight is over, and the next request fetches
+//!   again. Nothing is kept afterwards; caching is the entity cache's job.
+//!
+//! Requests are identical when they have the same `X-Tenant-Id` and the
+//! same filters, paging and embedded blocks. Only the campaign list is
+//! shared: field selection, ETags and NDJSON streams stay per request.
+//!
+//! Upstream calls are charged to the call budget of the request running
+//! the fetch; requests joining it are charged nothing. A fetch whose
+//! first request went away (client disconnect) keeps being driven by the
+//! requests that joined it.
+
+use axum::http::HeaderMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::campaign_aggregator::{Campaign, CampaignQuery, Platform};
+use super::error::ApiError;
+use super::freshness::Freshness;
+
+/// Fetches in flight by key; each runs once however many await it
+pub struct SingleFlight<K, V> {
+    flights: Mutex<HashMap<K, Shared<BoxFuture<'static, V>>>>,
+    /// Requests served by a fetch another request started
+    joined: AtomicU64,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            flights: Mutex::new(HashMap::new()),
+            joined: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Result of the fetch in flight for `key`, or of `fetch` started as a
+    /// new one when there is none
+    pub async fn run<F>(&self, key: K, fetch: F) -> V
+    where
+        F: Future<Output = V> + Send + 'static,
+    {
+        let flight = {
+            let mut flights = self.flights.lock().unwrap();
+            match flights.get(&key) {
+                Some(flight) => {
+                    self.joined.fetch_add(1, Ordering::Relaxed);
+                    flight.clone()
+                }
+                None => {
+                    let flight = fetch.boxed().shared();
+                    flights.insert(key.clone(), flight.clone());
+                    flight
+                }
+            }
+        };
+
+        let value = flight.clone().await;
+
+        // The first to finish ends the flight; a newer one under the same
+        // key is left alone
+        let mut flights = self.flights.lock().unwrap();
+        if flights.get(&key).is_some_and(|current| current.ptr_eq(&flight)) {
+            flights.remove(&key);
+        }
+        value
+    }
+
+    /// Requests served by a fetch another request started, since startup
+    pub fn joined(&self) -> u64 {
+        self.joined.load(Ordering::Relaxed)
+    }
+}
+
+/// Campaign list computed for a `GET /campaigns` request, before field
+/// selection
+#[derive(Debug, Clone)]
+pub struct CampaignPage {
+    pub campaigns: Vec<Campaign>,
+    /// `X-Next-Cursor` of the response
+    pub next_cursor: Option<usize>,
+    /// Freshness of the platforms read, reported to every request sharing
+    /// the page
+    pub freshness: Freshness,
+    /// Platforms that missed the latency budget of the request running the
+    /// fetch, see `latency_budget`
+    pub missed: Vec<Platform>,
+}
+
+/// In-flight `GET /campaigns` fetches
+pub type CampaignFlights = SingleFlight<String, Result<Arc<CampaignPage>, ApiError>>;
+
+/// Key of a `GET /campaigns` request: its tenant and every parameter
+/// changing the list; `fields` only shapes the response
+pub fn campaigns_key(headers: &HeaderMap, query: &CampaignQuery) -> String {
+    let tenant = headers.get("x-tenant-id").and_then(|v| v.to_str().ok()).unwrap_or("");
+    let filters = CampaignQuery {
+        fields: None,
+        ..query.clone()
+    };
+    format!("{}\n{:?}", tenant, filters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_identical_fetches_run_once() {
+        let flights: Arc<SingleFlight<&str, usize>> = Arc::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fetch = |calls: Arc<AtomicUsize>| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            calls.fetch_add(1, Ordering::SeqCst) + 1
+        };
+
+        let requests = (0..50).map(|_| flights.run("acme", fetch(calls.clone())));
+        let results = futures::future::join_all(requests).await;
+
+        assert!(results.iter().all(|r| *r == 1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(flights.joined(), 49);
+
+        // Over once completed: the next request fetches again
+        assert_eq!(flights.run("acme", fetch(calls.clone())).await, 2);
+        assert_eq!(flights.run("globex", fetch(calls.clone())).await, 3);
+    }
+
+    #[test]
+    fn test_keys_separate_tenants_but_not_field_selections() {
+        let mut acme = HeaderMap::new();
+        acme.insert("x-tenant-id", "acme".parse().unwrap());
+        let query = |platform: &str, fields: Option<&str>| CampaignQuery {
+            platform: Some(platform.to_string()),
+            fields: fields.map(str::to_string),
+            ..Default::default()
+        };
+
+        let key = campaigns_key(&acme, &query("meta", None));
+        assert_eq!(key, campaigns_key(&acme, &query("meta", Some("id,name"))));
+        assert_ne!(key, campaigns_key(&acme, &query("google", None)));
+        assert_ne!(key, campaigns_key(&HeaderMap::new(), &query("meta", None)));
+    }
+}
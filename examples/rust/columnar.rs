@@ -0,0 +1,572 @@
+//! # Columnar Campaign Store Example
+//!
+//! Aggregating the campaign list row by row on every summary request
+//! dominates its latency at 100k campaigns. Each sync therefore also lays
+//! the synced campaigns out column by column (`CampaignColumns`): one
+//! vector per metric, with platform, currency and status stored as small
+//! codes into per-column dictionaries. Summaries, filters and group-bys
+//! are tight loops over those vectors:
+//!
+//! - `GET /campaigns/summary` is served from the summary the sync worker
+//!   computes from the columns (see `summary_cache`) while it is at most
+//!   `COLUMNS_MAX_AGE` old, reported as `store` data in the freshness
+//!   block, and from columns of a fresh aggregation otherwise;
+//! - `GET /campaigns/breakdown?group_by=platform,status` returns totals
+//!   per group, filtered with `platform`, `status` and `currency`. Groups
+//!   are always split by currency too: money is never added across
+//!   currencies.
+//!
+//! Rows are summed in campaign order, so totals equal those of
+//! `summarize` on the same campaigns. The ignored
+//! `test_summary_of_100k_campaigns_is_fast` benchmark
+//! (`cargo test --release -- --ignored`) keeps the P99 of a summary of
+//! 100k campaigns under 10ms.
+
+use axum::extract::{Query, State};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::campaign_aggregator::{
+    aggregate_campaigns, drop_cross_platform_reach, recompute_rates, AppState, Campaign,
+    CampaignMetrics, CampaignStatus, CampaignSummary, ConversionActionMetrics, Platform,
+    ReachTotals,
+};
+use super::error::ApiError;
+use super::finite::Finite;
+use super::freshness::{self, DataSource};
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::money::{Currency, Money, MoneyByCurrency};
+use super::sync::SYNC_INTERVAL;
+
+/// Age past which the columns of the last sync are no longer served: two
+/// sync intervals, so one failed sync goes unnoticed
+pub const COLUMNS_MAX_AGE: Duration = Duration::from_secs(2 * SYNC_INTERVAL.as_secs());
+
+/// Distinct values of a column, rows holding the code of their value
+#[derive(Debug)]
+struct Dictionary<T> {
+    values: Vec<T>,
+    codes: HashMap<T, u32>,
+}
+
+impl<T> Default for Dictionary<T> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            codes: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> Dictionary<T> {
+    fn encode(&mut self, value: &T) -> u32 {
+        if let Some(&code) = self.codes.get(value) {
+            return code;
+        }
+        let code = self.values.len() as u32;
+        self.values.push(value.clone());
+        self.codes.insert(value.clone(), code);
+        code
+    }
+
+    fn code(&self, value: &T) -> Option<u32> {
+        self.codes.get(value).copied()
+    }
+
+    fn value(&self, code: u32) -> &T {
+        &self.values[code as usize]
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+/// Campaigns of a sync, one vector per field
+#[derive(Debug, Default)]
+pub struct CampaignColumns {
+    /// When the campaigns were fetched
+    pub built_at: DateTime<Utc>,
+    ids: Vec<CampaignId>,
+    platforms: Dictionary<Platform>,
+    platform: Vec<u32>,
+    currencies: Dictionary<Currency>,
+    currency: Vec<u32>,
+    statuses: Dictionary<CampaignStatus>,
+    status: Vec<u32>,
+    daily_budget: Vec<f64>,
+    impressions: Vec<u64>,
+    clicks: Vec<u64>,
+    conversions: Vec<u32>,
+    cost: Vec<f64>,
+    conversion_value: Vec<f64>,
+    reach: Vec<Option<u64>>,
+    video_views: Vec<Option<u64>>,
+    average_watch_time_seconds: Vec<Option<f64>>,
+    /// Conversion actions of row `i` are at `action_offsets[i]..action_offsets[i + 1]`
+    action_offsets: Vec<u32>,
+    actions: Dictionary<String>,
+    action: Vec<u32>,
+    action_conversions: Vec<f64>,
+    action_value: Vec<f64>,
+}
+
+/// Rows to aggregate; `None` fields keep every row
+#[derive(Debug, Default, Clone)]
+pub struct ColumnFilter {
+    pub platform: Option<Platform>,
+    /// e.g. `ENABLED`, case-insensitive
+    pub status: Option<String>,
+    pub currency: Option<Currency>,
+}
+
+/// Sums of one group, in campaign order
+#[derive(Debug, Default, Clone)]
+struct Totals {
+    campaigns: usize,
+    impressions: u64,
+    clicks: u64,
+    conversions: u32,
+    cost: f64,
+    conversion_value: f64,
+    /// Action code, conversions and value, in order of first appearance
+    actions: Vec<(u32, f64, f64)>,
+    reach: ReachTotals,
+    /// Platform code of the first row, and whether other rows differ
+    platform: Option<u32>,
+    mixed_platforms: bool,
+}
+
+/// Totals of one group of `GET /campaigns/breakdown`
+#[derive(Debug, Serialize, Clone)]
+pub struct BreakdownRow {
+    /// Present when grouped by platform
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+    /// Present when grouped by status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    pub currency: Currency,
+    pub campaigns: usize,
+    pub metrics: CampaignMetrics,
+}
+
+impl CampaignColumns {
+    pub fn build(campaigns: &[Campaign], built_at: DateTime<Utc>) -> Self {
+        let mut columns = Self {
+            built_at,
+            action_offsets: Vec::with_capacity(campaigns.len() + 1),
+            ..Default::default()
+        };
+        columns.action_offsets.push(0);
+
+        for campaign in campaigns {
+            columns.ids.push(campaign.id.clone());
+            columns.platform.push(columns.platforms.encode(&campaign.platform));
+            columns.currency.push(columns.currencies.encode(&campaign.currency()));
+            columns.status.push(columns.statuses.encode(&campaign.status));
+            columns.daily_budget.push(campaign.daily_budget);
+
+            let metrics = &campaign.metrics;
+            columns.impressions.push(metrics.impressions);
+            columns.clicks.push(metrics.clicks);
+            columns.conversions.push(metrics.conversions);
+            columns.cost.push(metrics.cost);
+            columns.conversion_value.push(metrics.conversion_value);
+            columns.reach.push(metrics.reach);
+            columns.video_views.push(metrics.video_views);
+            columns.average_watch_time_seconds.push(metrics.average_watch_time_seconds);
+            for action in &metrics.conversion_actions {
+                columns.action.push(columns.actions.encode(&action.action));
+                columns.action_conversions.push(action.conversions);
+                columns.action_value.push(action.value);
+            }
+            columns.action_offsets.push(columns.action.len() as u32);
+        }
+        columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Platforms with at least one campaign
+    pub fn platforms(&self) -> &[Platform] {
+        &self.platforms.values
+    }
+
+    /// Campaigns per currency
+    pub fn ids_by_currency(&self) -> BTreeMap<Currency, Vec<GlobalCampaignId>> {
+        let mut ids: BTreeMap<Currency, Vec<GlobalCampaignId>> = BTreeMap::new();
+        for (row, id) in self.ids.iter().enumerate() {
+            let platform = self.platforms.value(self.platform[row]).clone();
+            ids.entry(self.currencies.value(self.currency[row]).clone())
+                .or_default()
+                .push(GlobalCampaignId::new(platform, id.clone()));
+        }
+        ids
+    }
+
+    /// Rows matching `filter`, in campaign order
+    pub fn select(&self, filter: &ColumnFilter) -> Vec<u32> {
+        let platform = filter.platform.as_ref().map(|p| self.platforms.code(p));
+        let currency = filter.currency.as_ref().map(|c| self.currencies.code(c));
+        let status = filter.status.as_deref().map(|status| {
+            let position =
+                self.statuses.values.iter().position(|s| s.as_str().eq_ignore_ascii_case(status));
+            position.map(|code| code as u32)
+        });
+        // A value no row holds selects nothing
+        if [platform, currency, status].contains(&Some(None)) {
+            return Vec::new();
+        }
+
+        let mut rows: Vec<u32> = (0..self.len() as u32).collect();
+        for (column, code) in
+            [(&self.platform, platform), (&self.currency, currency), (&self.status, status)]
+        {
+            if let Some(Some(code)) = code {
+                rows.retain(|&row| column[row as usize] == code);
+            }
+        }
+        rows
+    }
+
+    /// Sums of `rows` in `groups` groups, `group_of` giving a row's group
+    fn totals_by(
+        &self,
+        rows: &[u32],
+        groups: usize,
+        group_of: impl Fn(usize) -> usize,
+    ) -> Vec<Totals> {
+        let mut totals = vec![Totals::default(); groups];
+        for &row in rows {
+            let row = row as usize;
+            let group = &mut totals[group_of(row)];
+            group.campaigns += 1;
+            group.impressions += self.impressions[row];
+            group.clicks += self.clicks[row];
+            group.conversions += self.conversions[row];
+            group.cost += self.cost[row];
+            group.conversion_value += self.conversion_value[row];
+            group.reach.add(
+                self.impressions[row],
+                self.reach[row],
+                self.video_views[row],
+                self.average_watch_time_seconds[row],
+            );
+            let platform = self.platform[row];
+            group.mixed_platforms |= *group.platform.get_or_insert(platform) != platform;
+
+            let actions = self.action_offsets[row] as usize..self.action_offsets[row + 1] as usize;
+            for i in actions {
+                let (code, conversions, value) =
+                    (self.action[i], self.action_conversions[i], self.action_value[i]);
+                match group.actions.iter_mut().find(|a| a.0 == code) {
+                    Some(existing) => {
+                        existing.1 += conversions;
+                        existing.2 += value;
+                    }
+                    None => group.actions.push((code, conversions, value)),
+                }
+            }
+        }
+        totals
+    }
+
+    fn metrics(&self, totals: Totals) -> CampaignMetrics {
+        let mut metrics = CampaignMetrics {
+            impressions: totals.impressions,
+            clicks: totals.clicks,
+            conversions: totals.conversions,
+            cost: totals.cost,
+            conversion_value: totals.conversion_value,
+            conversion_actions: totals
+                .actions
+                .into_iter()
+                .map(|(code, conversions, value)| ConversionActionMetrics {
+                    action: self.actions.value(code).clone(),
+                    conversions,
+                    value,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        totals.reach.apply(&mut metrics);
+        recompute_rates(&mut metrics);
+        if totals.mixed_platforms {
+            drop_cross_platform_reach(&mut metrics);
+        }
+        metrics
+    }
+
+    /// Same totals as `summarize` over the campaigns of these columns
+    pub fn summarize(&self) -> CampaignSummary {
+        let rows: Vec<u32> = (0..self.len() as u32).collect();
+        let currencies = self.currencies.len();
+
+        let mut budgets = vec![None; currencies];
+        if let Some(enabled) = self.statuses.code(&CampaignStatus::Enabled) {
+            for row in rows.iter().map(|&row| row as usize) {
+                if self.status[row] == enabled {
+                    *budgets[self.currency[row] as usize].get_or_insert(0.0) +=
+                        self.daily_budget[row];
+                }
+            }
+        }
+        let total_daily_budget: MoneyByCurrency = budgets
+            .into_iter()
+            .enumerate()
+            .filter_map(|(code, amount)| {
+                let currency = self.currencies.value(code as u32).clone();
+                Some((currency.clone(), Money { amount: amount?, currency }))
+            })
+            .collect();
+
+        let metrics = self
+            .totals_by(&rows, currencies, |row| self.currency[row] as usize)
+            .into_iter()
+            .enumerate()
+            .map(|(code, totals)| {
+                (self.currencies.value(code as u32).clone(), self.metrics(totals))
+            })
+            .collect();
+
+        let group_of =
+            |row: usize| self.platform[row] as usize * currencies + self.currency[row] as usize;
+        let mut by_platform: BTreeMap<String, BTreeMap<Currency, CampaignMetrics>> =
+            BTreeMap::new();
+        let totals = self.totals_by(&rows, self.platforms.len() * currencies, group_of);
+        for (group, totals) in totals.into_iter().enumerate() {
+            if totals.campaigns == 0 {
+                continue;
+            }
+            let platform = self.platforms.value((group / currencies) as u32);
+            let currency = self.currencies.value((group % currencies) as u32).clone();
+            by_platform
+                .entry(platform.as_str().to_string())
+                .or_default()
+                .insert(currency, self.metrics(totals));
+        }
+
+        CampaignSummary {
+            total_daily_budget,
+            metrics,
+            by_platform,
+            compare: None,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Totals of the rows matching `filter` per currency, and per platform
+    /// and status when asked; sorted by platform, status and currency
+    pub fn breakdown(
+        &self,
+        filter: &ColumnFilter,
+        by_platform: bool,
+        by_status: bool,
+    ) -> Vec<BreakdownRow> {
+        let rows = self.select(filter);
+        let platforms = if by_platform { self.platforms.len() } else { 1 };
+        let statuses = if by_status { self.statuses.len() } else { 1 };
+        let currencies = self.currencies.len();
+
+        let group_of = |row: usize| {
+            let platform = if by_platform { self.platform[row] as usize } else { 0 };
+            let status = if by_status { self.status[row] as usize } else { 0 };
+            (platform * statuses + status) * currencies + self.currency[row] as usize
+        };
+        let totals = self.totals_by(&rows, platforms * statuses * currencies, group_of);
+
+        let mut breakdown: Vec<BreakdownRow> = totals
+            .into_iter()
+            .enumerate()
+            .filter(|(_, totals)| totals.campaigns > 0)
+            .map(|(group, totals)| {
+                let (rest, currency) = (group / currencies, group % currencies);
+                let (platform, status) = (rest / statuses, rest % statuses);
+                BreakdownRow {
+                    platform: by_platform.then(|| self.platforms.value(platform as u32).clone()),
+                    status: by_status
+                        .then(|| self.statuses.value(status as u32).as_str().to_string()),
+                    currency: self.currencies.value(currency as u32).clone(),
+                    campaigns: totals.campaigns,
+                    metrics: self.metrics(totals),
+                }
+            })
+            .collect();
+        breakdown.sort_by(|a, b| {
+            let key = |r: &BreakdownRow| {
+                (r.platform.as_ref().map(Platform::as_str), r.status.clone(), r.currency.clone())
+            };
+            key(a).cmp(&key(b))
+        });
+        breakdown
+    }
+}
+
+/// Columns of the last sync
+#[derive(Default)]
+pub struct ColumnStore {
+    current: RwLock<Option<Arc<CampaignColumns>>>,
+}
+
+impl ColumnStore {
+    pub fn replace(&self, columns: CampaignColumns) -> Arc<CampaignColumns> {
+        let columns = Arc::new(columns);
+        *self.current.write().unwrap() = Some(columns.clone());
+        columns
+    }
+
+    /// Columns of the last sync, unless built more than `max_age` before `now`
+    pub fn fresh(&self, now: DateTime<Utc>, max_age: Duration) -> Option<Arc<CampaignColumns>> {
+        let columns = self.current.read().unwrap().clone()?;
+        let age = (now - columns.built_at).to_std().unwrap_or_default();
+        (age <= max_age).then_some(columns)
+    }
+}
+
+/// Columns of the last sync when fresh, else of a fresh aggregation
+pub async fn current_columns(state: &AppState) -> Arc<CampaignColumns> {
+    if let Some(columns) = state.columns.fresh(Utc::now(), COLUMNS_MAX_AGE) {
+        for platform in columns.platforms() {
+            freshness::record(platform, DataSource::Store, Some(columns.built_at));
+        }
+        return columns;
+    }
+
+    let campaigns = aggregate_campaigns(state).await;
+    Arc::new(CampaignColumns::build(&campaigns, Utc::now()))
+}
+
+/// Query parameters for `GET /campaigns/breakdown`
+#[derive(Debug, Deserialize, Default)]
+pub struct BreakdownQuery {
+    /// Comma-separated `platform`, `status`; `platform` when absent
+    pub group_by: Option<String>,
+    pub platform: Option<Platform>,
+    pub status: Option<String>,
+    pub currency: Option<String>,
+}
+
+/// GET /campaigns/breakdown - Totals per platform, status and currency
+pub async fn get_campaign_breakdown(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BreakdownQuery>,
+) -> Result<Finite<Vec<BreakdownRow>>, ApiError> {
+    let (mut by_platform, mut by_status) = (false, false);
+    for key in query.group_by.as_deref().unwrap_or("platform").split(',').map(str::trim) {
+        match key {
+            "platform" => by_platform = true,
+            "status" => by_status = true,
+            // Always grouped by
+            "currency" => {}
+            _ => {
+                return Err(ApiError::Validation(format!(
+                    "unknown group_by {:?}; expected platform, status or currency",
+                    key
+                )))
+            }
+        }
+    }
+
+    let filter = ColumnFilter {
+        platform: query.platform,
+        status: query.status,
+        currency: query.currency.as_deref().map(Currency::new),
+    };
+    let columns = current_columns(&state).await;
+    Ok(Finite(columns.breakdown(&filter, by_platform, by_status)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::campaign_aggregator::summarize;
+    use super::*;
+
+    fn campaigns(count: usize) -> Vec<Campaign> {
+        (0..count)
+            .map(|i| {
+                let platform = if i % 3 == 0 { Platform::Meta } else { Platform::Google };
+                let mut campaign = Campaign::sample(&i.to_string(), platform);
+                if i % 5 == 0 {
+                    campaign.currency = "usd".to_string();
+                }
+                if i % 4 == 0 {
+                    campaign.status = CampaignStatus::Paused;
+                }
+                let metrics = &mut campaign.metrics;
+                metrics.impressions = 1_000 + i as u64;
+                metrics.clicks = 10 + (i % 7) as u64;
+                metrics.conversions = (i % 3) as u32;
+                metrics.cost = 12.34 + i as f64 * 0.1;
+                metrics.conversion_value = i as f64 * 0.7;
+                if i % 2 == 0 {
+                    metrics.conversion_actions.push(ConversionActionMetrics {
+                        action: ["purchase", "lead"][i % 4 / 2].to_string(),
+                        conversions: 0.5,
+                        value: 3.3,
+                    });
+                }
+                if campaign.platform == Platform::Meta {
+                    metrics.reach = Some(400 + i as u64);
+                    metrics.video_views = Some(i as u64 * 3);
+                    metrics.average_watch_time_seconds = Some(6.5);
+                }
+                campaign
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_columns_match_row_aggregation() {
+        let campaigns = campaigns(1_000);
+        let columns = CampaignColumns::build(&campaigns, Utc::now());
+        assert_eq!(
+            serde_json::to_value(columns.summarize()).unwrap(),
+            serde_json::to_value(summarize(&campaigns)).unwrap()
+        );
+
+        let filter = ColumnFilter {
+            platform: Some(Platform::Google),
+            status: Some("enabled".to_string()),
+            ..Default::default()
+        };
+        let breakdown = columns.breakdown(&filter, true, true);
+        let keys: Vec<(&str, &str)> =
+            breakdown.iter().map(|r| (r.status.as_deref().unwrap(), r.currency.as_str())).collect();
+        assert_eq!(keys, [("ENABLED", "BRL"), ("ENABLED", "USD")]);
+        let expected = campaigns
+            .iter()
+            .filter(|c| c.platform == Platform::Google && c.status == CampaignStatus::Enabled)
+            .count();
+        assert_eq!(breakdown.iter().map(|r| r.campaigns).sum::<usize>(), expected);
+
+        let unknown = ColumnFilter { currency: Some(Currency::new("EUR")), ..Default::default() };
+        assert!(columns.breakdown(&unknown, false, false).is_empty());
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with cargo test --release -- --ignored"]
+    fn test_summary_of_100k_campaigns_is_fast() {
+        let columns = CampaignColumns::build(&campaigns(100_000), Utc::now());
+        let mut timings: Vec<Duration> = (0..200)
+            .map(|_| {
+                let started = std::time::Instant::now();
+                std::hint::black_box(columns.summarize());
+                started.elapsed()
+            })
+            .collect();
+        timings.sort();
+        let p99 = timings[timings.len() * 99 / 100];
+        assert!(p99 < Duration::from_millis(10), "P99 {:?}", p99);
+    }
+}
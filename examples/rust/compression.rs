@@ -0,0 +1,103 @@
+//! # Response Compression Example
+//!
+//! Campaign lists and account spec exports serialize to several MB of
+//! JSON. Responses are compressed with gzip or Brotli, whichever the
+//! client prefers in `Accept-Encoding`; small bodies, images and the SSE
+//! stream are sent as is.
+
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+use super::config::CompressionConfig;
+
+/// Compression middleware for the router
+pub fn compression_layer(config: &CompressionConfig) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(config.min_size_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        // Compressed event streams sit in the encoder until it flushes
+        .and(NotForContentType::SSE);
+
+    CompressionLayer::new()
+        .gzip(config.gzip)
+        .br(config.brotli)
+        .compress_when(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::campaign_aggregator::{AppState, Campaign, Platform};
+    use super::super::config::AppConfig;
+    use super::super::connectors::{ConnectorRegistry, MockConnector};
+    use super::super::server::{build_router, ServerOptions};
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use axum::response::Response;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn state() -> Arc<AppState> {
+        let mut config = AppConfig::default();
+        config.gateway.service_token = "test".to_string();
+        let mut state = AppState::from_config(config).unwrap();
+
+        let mut registry = ConnectorRegistry::default();
+        registry.register(Arc::new(MockConnector {
+            platform: Platform::Google,
+            campaigns: (0..200)
+                .map(|i| Campaign::sample(&i.to_string(), Platform::Google))
+                .collect(),
+            fail: false,
+        }));
+        state.connectors = registry;
+
+        Arc::new(state)
+    }
+
+    async fn get(path: &str, accept_encoding: Option<&str>) -> Response {
+        let router = build_router(state(), &ServerOptions::default());
+
+        let mut request = Request::get(path);
+        if let Some(encoding) = accept_encoding {
+            request = request.header(header::ACCEPT_ENCODING, encoding);
+        }
+
+        router.oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    fn content_encoding(response: &Response) -> Option<&str> {
+        response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn test_campaign_list_negotiates_encoding() {
+        let brotli = get("/campaigns", Some("br")).await;
+        assert_eq!(content_encoding(&brotli), Some("br"));
+
+        let gzip = get("/campaigns", Some("gzip")).await;
+        assert_eq!(content_encoding(&gzip), Some("gzip"));
+
+        let identity = get("/campaigns", None).await;
+        assert_eq!(content_encoding(&identity), None);
+    }
+
+    #[tokio::test]
+    async fn test_spec_export_is_compressed_in_both_formats() {
+        let json = get("/accounts/spec", Some("gzip")).await;
+        assert_eq!(content_encoding(&json), Some("gzip"));
+
+        let yaml = get("/accounts/spec?format=yaml", Some("br")).await;
+        assert_eq!(content_encoding(&yaml), Some("br"));
+    }
+
+    #[tokio::test]
+    async fn test_small_responses_are_not_compressed() {
+        let response = get("/healthz", Some("gzip, br")).await;
+        assert_eq!(content_encoding(&response), None);
+    }
+}
@@ -0,0 +1,472 @@
+//! # Upstream Concurrency Example
+//!
+//! When many tenants sync at once, every request fans out into platform
+//! calls and nothing stopped the gateway from receiving hundreds of them
+//! at the same time. Every connector operation now takes a slot before it
+//! reaches the gateway:
+//!
+//! - at most `concurrency.max_in_flight` calls run at once across all
+//!   platforms, and at most `concurrency.platforms.<name>` per platform
+//!   (16 each for Google and Meta by default), so a slow platform cannot
+//!   take every slot;
+//! - a call without a free slot waits in line, first come first served,
+//!   for up to `concurrency.queue_timeout_ms`, then fails as
+//!   `rate_limited` with a `Retry-After` of one second instead of piling
+//!   up behind the others;
+//! - a slot covers one operation, so a paginated list fetching
+//!   `gateway.pagination.concurrency` pages at once holds a single slot;
+//! - quota throttling waits before a call takes its slot, so a throttled
+//!   platform does not hold slots while sleeping.
+//!
+//! `GET /internal/concurrency` (admins) reports the calls in flight and
+//! waiting per platform, the longest line seen and the calls that timed
+//! out waiting.
+
+use async_trait::async_trait;
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::accounts::AccountNode;
+use super::auction::AuctionInsights;
+use super::audiences::AudienceInfo;
+use super::bidding::{Bidding, BiddingChange};
+use super::breakdowns::{Dimension, SegmentRow};
+use super::campaign_aggregator::{AppState, Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::config::ConcurrencyConfig;
+use super::connectors::{ConnectorError, PlatformConnector};
+use super::conversions::ConversionActionRow;
+use super::hydration::{Ad, AdGroup};
+use super::ids::{AdGroupId, CampaignId};
+use super::launch::LaunchPayload;
+use super::negatives::{Exclusion, ExclusionUpdate};
+use super::platform_errors::{PlatformError, PlatformErrorCode};
+use super::products::ProductRow;
+use super::quality::QualityDiagnostics;
+use super::spec::{AdGroupSpec, CampaignSpec};
+use super::targeting::Targeting;
+
+/// Slots and line of one platform
+#[derive(Default)]
+struct PlatformSlots {
+    /// `None` when the platform is only bound by the global limit
+    semaphore: Option<Arc<Semaphore>>,
+    limit: Option<usize>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    max_queued: AtomicUsize,
+    timeouts: AtomicU64,
+}
+
+/// Slot held by a call in flight, released when dropped
+pub struct Slot {
+    _platform: Option<OwnedSemaphorePermit>,
+    _global: OwnedSemaphorePermit,
+    slots: Arc<PlatformSlots>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.slots.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Calls in flight and waiting on one platform
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PlatformConcurrency {
+    pub platform: Platform,
+    /// `None` when only bound by `max_in_flight`
+    pub limit: Option<usize>,
+    pub in_flight: usize,
+    pub queued: usize,
+    /// Longest line since startup
+    pub max_queued: usize,
+    /// Calls that gave up waiting since startup
+    pub timeouts: u64,
+}
+
+/// Response of `GET /internal/concurrency`
+#[derive(Debug, Serialize)]
+pub struct ConcurrencyReport {
+    pub max_in_flight: usize,
+    pub in_flight: usize,
+    pub queue_timeout_ms: u64,
+    pub platforms: Vec<PlatformConcurrency>,
+    pub at: DateTime<Utc>,
+}
+
+/// Global and per-platform limits on upstream calls in flight
+pub struct ConcurrencyLimiter {
+    config: ConcurrencyConfig,
+    global: Arc<Semaphore>,
+    platforms: Mutex<HashMap<Platform, Arc<PlatformSlots>>>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(config.max_in_flight)),
+            config,
+            platforms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn slots(&self, platform: &Platform) -> Arc<PlatformSlots> {
+        let mut platforms = self.platforms.lock().unwrap();
+        platforms
+            .entry(platform.clone())
+            .or_insert_with(|| {
+                let limit = self.config.limit_for(platform);
+                Arc::new(PlatformSlots {
+                    semaphore: limit.map(|limit| Arc::new(Semaphore::new(limit))),
+                    limit,
+                    ..Default::default()
+                })
+            })
+            .clone()
+    }
+
+    /// Wait for a slot on `platform`, at most `concurrency.queue_timeout_ms`
+    ///
+    /// The platform's slot is taken before the global one, so calls queued
+    /// on a busy platform do not hold global slots other platforms could use.
+    pub async fn acquire(&self, platform: &Platform) -> Result<Slot, PlatformError> {
+        let slots = self.slots(platform);
+        let queued = slots.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        slots.max_queued.fetch_max(queued, Ordering::Relaxed);
+
+        let permits = async {
+            let platform_permit = match &slots.semaphore {
+                Some(semaphore) => Some(semaphore.clone().acquire_owned().await),
+                None => None,
+            }
+            .transpose();
+            let global_permit = self.global.clone().acquire_owned().await;
+            (platform_permit, global_permit)
+        };
+        let result = tokio::time::timeout(self.config.queue_timeout(), permits).await;
+        slots.queued.fetch_sub(1, Ordering::Relaxed);
+
+        match result {
+            // The semaphores are never closed
+            Ok((Ok(platform_permit), Ok(global_permit))) => {
+                slots.in_flight.fetch_add(1, Ordering::Relaxed);
+                Ok(Slot {
+                    _platform: platform_permit,
+                    _global: global_permit,
+                    slots,
+                })
+            }
+            _ => {
+                slots.timeouts.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    platform = platform.as_str(),
+                    queued,
+                    "no upstream slot within the queue timeout"
+                );
+                Err(PlatformError {
+                    platform: platform.clone(),
+                    code: PlatformErrorCode::RateLimited,
+                    upstream_code: "queue_timeout".to_string(),
+                    message: format!(
+                        "no free {} upstream slot within {}ms",
+                        platform.as_str(),
+                        self.config.queue_timeout_ms
+                    ),
+                    retry_after: Some(1),
+                })
+            }
+        }
+    }
+
+    pub fn report(&self, now: DateTime<Utc>) -> ConcurrencyReport {
+        let platforms = self.platforms.lock().unwrap();
+        let mut report: Vec<PlatformConcurrency> = platforms
+            .iter()
+            .map(|(platform, slots)| PlatformConcurrency {
+                platform: platform.clone(),
+                limit: slots.limit,
+                in_flight: slots.in_flight.load(Ordering::Relaxed),
+                queued: slots.queued.load(Ordering::Relaxed),
+                max_queued: slots.max_queued.load(Ordering::Relaxed),
+                timeouts: slots.timeouts.load(Ordering::Relaxed),
+            })
+            .collect();
+        report.sort_by(|a, b| a.platform.as_str().cmp(b.platform.as_str()));
+
+        ConcurrencyReport {
+            max_in_flight: self.config.max_in_flight,
+            in_flight: self.config.max_in_flight - self.global.available_permits(),
+            queue_timeout_ms: self.config.queue_timeout_ms,
+            platforms: report,
+            at: now,
+        }
+    }
+}
+
+/// Connector decorator holding a slot of the limiter for every operation
+pub struct LimitedConnector {
+    pub inner: Arc<dyn PlatformConnector>,
+    pub limiter: Arc<ConcurrencyLimiter>,
+}
+
+impl LimitedConnector {
+    async fn slot(&self) -> Result<Slot, ConnectorError> {
+        Ok(self.limiter.acquire(&self.inner.platform()).await?)
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for LimitedConnector {
+    fn platform(&self) -> Platform {
+        self.inner.platform()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_campaigns().await
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_metrics(campaign_id).await
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.mutate_status(campaign_id, status).await
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.health_check().await
+    }
+
+    async fn fetch_ad_groups(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<Vec<AdGroup>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_ad_groups(campaign_ids).await
+    }
+
+    async fn fetch_audiences_by_id(
+        &self,
+        audience_ids: &[String],
+    ) -> Result<Vec<AudienceInfo>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_audiences_by_id(audience_ids).await
+    }
+
+    async fn mutate_budget(
+        &self,
+        campaign_id: &CampaignId,
+        daily_budget: f64,
+    ) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.mutate_budget(campaign_id, daily_budget).await
+    }
+
+    async fn fetch_ads(&self, ad_group_ids: &[AdGroupId]) -> Result<Vec<Ad>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_ads(ad_group_ids).await
+    }
+
+    async fn rename_campaign(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.rename_campaign(campaign_id, name).await
+    }
+
+    async fn create_campaign(&self, campaign: &CampaignSpec) -> Result<CampaignId, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.create_campaign(campaign).await
+    }
+
+    async fn launch_campaign(&self, payload: &LaunchPayload) -> Result<CampaignId, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.launch_campaign(payload).await
+    }
+
+    async fn create_ad_group(
+        &self,
+        campaign_id: &CampaignId,
+        ad_group: &AdGroupSpec,
+    ) -> Result<AdGroupId, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.create_ad_group(campaign_id, ad_group).await
+    }
+
+    async fn update_ad_group(&self, ad_group: &AdGroupSpec) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.update_ad_group(ad_group).await
+    }
+
+    async fn fetch_targeting(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Targeting>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_targeting(campaign_ids).await
+    }
+
+    async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.refresh_credentials().await
+    }
+
+    async fn fetch_bidding(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Bidding>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_bidding(campaign_ids).await
+    }
+
+    async fn mutate_bidding(
+        &self,
+        campaign_id: &CampaignId,
+        change: &BiddingChange,
+    ) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.mutate_bidding(campaign_id, change).await
+    }
+
+    async fn fetch_quality(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, QualityDiagnostics>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_quality(campaign_ids).await
+    }
+
+    async fn fetch_auction_insights(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, AuctionInsights>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_auction_insights(campaign_ids).await
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        campaign_id: &CampaignId,
+        dimension: Dimension,
+    ) -> Result<Vec<SegmentRow>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_breakdown(campaign_id, dimension).await
+    }
+
+    async fn fetch_products(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ProductRow>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_products(campaign_id).await
+    }
+
+    async fn add_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.add_label(campaign_id, name).await
+    }
+
+    async fn remove_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.remove_label(campaign_id, name).await
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_raw_campaign(campaign_id).await
+    }
+
+    async fn fetch_changed_campaigns(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Campaign>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_changed_campaigns(since).await
+    }
+
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_accounts().await
+    }
+
+    async fn fetch_conversion_actions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ConversionActionRow>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_conversion_actions(campaign_id).await
+    }
+
+    async fn fetch_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<Exclusion>, ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.fetch_exclusions(campaign_id).await
+    }
+
+    async fn update_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+        update: &ExclusionUpdate,
+    ) -> Result<(), ConnectorError> {
+        let _slot = self.slot().await?;
+        self.inner.update_exclusions(campaign_id, update).await
+    }
+}
+
+/// GET /internal/concurrency - Upstream calls in flight and waiting per platform
+pub async fn get_concurrency(State(state): State<Arc<AppState>>) -> Json<ConcurrencyReport> {
+    Json(state.concurrency.report(Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_calls_wait_for_a_slot_and_time_out() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(ConcurrencyConfig {
+            max_in_flight: 2,
+            platforms: HashMap::from([("google".to_string(), 1)]),
+            queue_timeout_ms: 50,
+        }));
+
+        let held = limiter.acquire(&Platform::Google).await.unwrap();
+        // Meta is only bound by the global limit
+        let meta = limiter.acquire(&Platform::Meta).await.unwrap();
+
+        let waiting = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire(&Platform::Google).await.map(|_| ()) })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let report = limiter.report(Utc::now());
+        assert_eq!(report.in_flight, 2);
+        let google = &report.platforms[0];
+        assert_eq!((google.in_flight, google.queued, google.limit), (1, 1, Some(1)));
+
+        let err = waiting.await.unwrap().unwrap_err();
+        assert_eq!((err.code, err.retry_after), (PlatformErrorCode::RateLimited, Some(1)));
+        assert_eq!(limiter.report(Utc::now()).platforms[0].timeouts, 1);
+
+        drop((held, meta));
+        assert!(limiter.acquire(&Platform::Google).await.is_ok());
+        assert_eq!(limiter.report(Utc::now()).in_flight, 0);
+    }
+}
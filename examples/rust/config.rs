@@ -0,0 +1,1823 @@
+//! # Configuration Example
+//!
+//! Typed application config loaded in layers: built-in defaults, then an
+//! optional TOML file (path in `IBVI_CONFIG`), then `IBVI_*` environment
+//! variables. The result is validated once at startup so a bad deployment
+//! fails immediately with a message naming the offending setting.
+//!
+//! ```toml
+//! [server]
+//! addr = "0.0.0.0:8080"
+//! upstream_call_budget = 50
+//!
+//! [server.compression]
+//! min_size_bytes = 1024
+//!
+//! [gateway]
+//! url = "http://gateway:8000"
+//! service_token = "..."        # prefer IBVI_GATEWAY_TOKEN
+//!
+//! [gateway.platform_urls]
+//! meta = "http://meta-gateway:8000"
+//!
+//! [gateway.pagination]
+//! page_size = 500
+//! max_pages = 20
+//! concurrency = 4
+//!
+//! [quota]
+//! warn_ratio = 0.8
+//! throttle_ratio = 0.9
+//! max_throttle_ms = 2000
+//! daily_calls = { google = 15000 }
+//!
+//! [concurrency]
+//! max_in_flight = 64
+//! queue_timeout_ms = 10000
+//! platforms = { google = 16, meta = 24 }
+//!
+//! [cache]
+//! campaigns_ttl_secs = 60
+//! campaigns_stale_secs = 300
+//!
+//! [cache.routes."/campaigns"]    # see `cache_policy`
+//! max_age_secs = 30
+//! stale_while_revalidate_secs = 120
+//!
+//! [latency_budget]
+//! default_ms = 800
+//! routes = { "/campaigns/summary" = 1500, "/campaigns/{id}" = 0 }
+//!
+//! [load_shedding]              # see `load_shedding`
+//! max_in_flight = 512
+//! low_max_in_flight = 128
+//! latency_threshold_ms = 1500
+//! routes = { "/campaigns" = "low", "/alerts/history" = "high" }
+//!
+//! [spend_caps]
+//! alert_ratio = 0.9
+//!
+//! [spend_caps.tenants.acme]
+//! monthly_cap = 50000.0
+//! account_ids = ["123-456-7890", "act_42"]
+//! notifier = { type = "slack", webhook_url = "https://hooks.slack.com/..." }
+//!
+//! [cost_guard]                 # see `cost_guard`
+//! enabled = true
+//! multiplier = 3.0
+//! notifier = { type = "slack", webhook_url = "https://hooks.slack.com/..." }
+//!
+//! [cost_guard.tenants.acme]
+//! account_ids = ["123-456-7890"]
+//! multiplier = 5.0
+//!
+//! [rbac]
+//! default_role = "viewer"
+//! assignments_path = "/var/lib/ibvi/roles.json"
+//!
+//! [audit]
+//! path = "/var/lib/ibvi/audit.jsonl"
+//!
+//! [backup]
+//! url = "https://storage.example.com/ibvi-backups"
+//! key = "..."                  # prefer IBVI_BACKUP_KEY
+//! interval_secs = 86400
+//!
+//! [credentials]                # see `credentials`
+//! key = "..."                  # prefer IBVI_CREDENTIALS_KEY
+//! path = "/var/lib/ibvi/connections.json"
+//! refresh_margin_secs = 300
+//!
+//! [credentials.platforms.google]
+//! token_url = "https://oauth2.googleapis.com/token"
+//! client_id = "..."
+//! client_secret = "..."        # prefer IBVI_CREDENTIALS_GOOGLE_SECRET
+//!
+//! [warehouse]                  # feature `warehouse`
+//! url = "https://storage.googleapis.com/acme-ads-lake"
+//! token = "..."                # prefer IBVI_WAREHOUSE_TOKEN
+//! export_at = "03:00:00"
+//! bigquery = { project = "acme-analytics", dataset = "ads", table = "campaign_snapshots" }
+//!
+//! [region]
+//! current = "us"
+//! peers = { eu = "https://eu.ads-api.internal" }
+//! tenants = { acme-eu = "eu" }
+//!
+//! [normalization]
+//! rules_path = "/etc/ibvi/normalization.json"
+//!
+//! [raw_capture]
+//! enabled = true
+//! retention_days = 7
+//! redact_keys = ["customer_id"]
+//!
+//! [warmup]
+//! enabled = true
+//! tenants = ["acme"]
+//! usage_path = "/var/lib/ibvi/usage.json"
+//!
+//! [schedules]
+//! timezone = "America/Sao_Paulo"
+//!
+//! [timezones]                  # see `timezones`
+//! default = "America/Sao_Paulo"
+//! platforms = { meta = "America/Los_Angeles" }
+//!
+//! [benchmarks]
+//! accounts = { "123-456-7890" = "real_estate" }
+//! verticals.real_estate = { ctr = 1.8, cpc = 2.4, cpa = 85.0, currency = "BRL" }
+//!
+//! [api_versions.deprecated]
+//! v1 = { since = "2026-11-01", sunset = "2027-05-01" }
+//!
+//! [demo]                       # or `--demo`, see `apply_args`
+//! enabled = true
+//! seed = 42
+//! campaigns_per_platform = 500
+//! platforms = ["google", "meta"]
+//!
+//! [redis]                      # feature `redis`, for several replicas
+//! url = "redis://redis:6379/0"  # prefer IBVI_REDIS_URL
+//! key_prefix = "ibvi"
+//! ```
+
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::alerts::NotifierConfig;
+use super::cache_policy::RoutePolicy;
+use super::call_budget::DEFAULT_CALL_BUDGET;
+use super::campaign_aggregator::Platform;
+use super::demo::MAX_DEMO_CAMPAIGNS;
+use super::ids::AccountId;
+use super::load_shedding::{self, Priority};
+use super::rbac::Role;
+use super::region::Region;
+use super::versioning::{self, ApiVersion};
+
+/// Environment variable holding the path of the TOML config file
+pub const CONFIG_PATH_ENV: &str = "IBVI_CONFIG";
+
+/// Platforms accepted as keys of `gateway.platform_urls`
+const KNOWN_PLATFORMS: &[&str] = &["google", "meta", "linkedin", "microsoft"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("cannot parse config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("environment variable {key}={value:?} is invalid: {reason}")]
+    Env {
+        key: String,
+        value: String,
+        reason: String,
+    },
+    #[error("invalid config `{setting}`: {reason}")]
+    Invalid { setting: &'static str, reason: String },
+}
+
+impl ConfigError {
+    pub fn invalid(setting: &'static str, reason: impl Into<String>) -> Self {
+        ConfigError::Invalid {
+            setting,
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub addr: SocketAddr,
+    /// Metrics store snapshot, loaded on startup and flushed on shutdown
+    pub snapshot_path: Option<PathBuf>,
+    pub drain_timeout_secs: u64,
+    /// Gateway calls a single request may trigger
+    pub upstream_call_budget: usize,
+    pub compression: CompressionConfig,
+    /// Add the per-stage `X-Timing` breakdown to every response
+    pub timing_header: bool,
+    /// How long responses to `Idempotency-Key` requests are replayed
+    pub idempotency_ttl_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            snapshot_path: None,
+            drain_timeout_secs: 20,
+            upstream_call_budget: DEFAULT_CALL_BUDGET,
+            compression: CompressionConfig::default(),
+            timing_header: false,
+            idempotency_ttl_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+/// Response compression, negotiated through `Accept-Encoding`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub gzip: bool,
+    pub brotli: bool,
+    /// Responses smaller than this are sent uncompressed
+    pub min_size_bytes: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            brotli: true,
+            min_size_bytes: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GatewayConfig {
+    /// Gateway base URL used for every platform without an override
+    pub url: String,
+    /// Sent as `X-Service-Auth` on every gateway request
+    pub service_token: String,
+    /// Per-platform base URL overrides, keyed by platform (`google`, `meta`, ...)
+    pub platform_urls: HashMap<String, String>,
+    pub pagination: PaginationConfig,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://gateway:8000".to_string(),
+            service_token: String::new(),
+            platform_urls: HashMap::new(),
+            pagination: PaginationConfig::default(),
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// Base URL serving `platform`
+    pub fn url_for(&self, platform: &Platform) -> &str {
+        self.platform_urls
+            .get(platform.as_str())
+            .map(String::as_str)
+            .unwrap_or(&self.url)
+    }
+}
+
+/// Upstream pagination followed by the connectors on list routes
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PaginationConfig {
+    /// Items requested per page
+    pub page_size: u32,
+    /// Pages read per list; the rest of a longer list is dropped
+    pub max_pages: u32,
+    /// Pages in flight at once on platforms addressing pages by offset
+    pub concurrency: usize,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            page_size: 500,
+            max_pages: 20,
+            concurrency: 4,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Total time allowed for one gateway request
+    pub request_secs: u64,
+    pub connect_secs: u64,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            request_secs: 30,
+            connect_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// How long cached campaigns and their metrics stay fresh; 0 disables
+    /// the entity cache
+    pub campaigns_ttl_secs: u64,
+    /// How long audience sizes and overlaps stay fresh
+    pub audiences_ttl_secs: u64,
+    /// How long past their TTL campaign lists are still served while
+    /// fetched again in the background; 0 fetches them in the request
+    pub campaigns_stale_secs: u64,
+    /// `Cache-Control` policies by route template, e.g. `/campaigns`
+    pub routes: HashMap<String, RoutePolicy>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            campaigns_ttl_secs: 60,
+            audiences_ttl_secs: 3600,
+            campaigns_stale_secs: 0,
+            routes: HashMap::new(),
+        }
+    }
+}
+
+/// Startup warm-up, see `warmup`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WarmupConfig {
+    pub enabled: bool,
+    /// Tenants always primed, in this order
+    pub tenants: Vec<String>,
+    /// Busiest tenants from recorded usage primed after `tenants`
+    pub learned_tenants: usize,
+    pub paths_per_tenant: usize,
+    pub connections_per_platform: usize,
+    /// Longest the instance stays unready while warming up
+    pub timeout_secs: u64,
+    /// Per-tenant request counts, loaded on startup and flushed on shutdown
+    pub usage_path: Option<PathBuf>,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tenants: Vec::new(),
+            learned_tenants: 5,
+            paths_per_tenant: 5,
+            connections_per_platform: 4,
+            timeout_secs: 60,
+            usage_path: None,
+        }
+    }
+}
+
+/// Industry baselines campaigns are scored against, see `benchmarks`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BenchmarkConfig {
+    /// Baselines by vertical, e.g. `real_estate`
+    pub verticals: HashMap<String, VerticalBaseline>,
+    /// Vertical of the campaigns of an ad account
+    pub accounts: HashMap<AccountId, String>,
+    /// Naming-convention tag holding a campaign's vertical, which takes
+    /// precedence over its account's
+    pub tag: String,
+    /// Vertical of campaigns neither tagged nor in a listed account
+    pub default_vertical: Option<String>,
+    /// Shortfall from a baseline, as a share of it, past which a metric
+    /// underperforms
+    pub tolerance: f64,
+    /// Clicks needed before CPC is judged
+    pub min_clicks: u64,
+    /// Conversions needed before CPA is judged
+    pub min_conversions: u32,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            verticals: HashMap::new(),
+            accounts: HashMap::new(),
+            tag: "vertical".to_string(),
+            default_vertical: None,
+            tolerance: 0.2,
+            min_clicks: 100,
+            min_conversions: 10,
+        }
+    }
+}
+
+/// Typical metrics of one vertical
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VerticalBaseline {
+    /// Click-through rate in percent
+    pub ctr: f64,
+    pub cpc: f64,
+    pub cpa: f64,
+    /// Currency of `cpc` and `cpa`; campaigns in another are only judged
+    /// on CTR
+    pub currency: String,
+}
+
+/// Deprecation of API versions, see `versioning`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ApiVersionsConfig {
+    /// Deprecated versions; unversioned paths follow `v1`
+    pub deprecated: HashMap<ApiVersion, VersionDeprecation>,
+}
+
+/// When a version was deprecated and stops being served
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VersionDeprecation {
+    /// Day (UTC) from which the version is deprecated
+    pub since: NaiveDate,
+    /// Day (UTC) from which its requests are refused with `410 Gone`
+    pub sunset: Option<NaiveDate>,
+}
+
+/// Generated campaigns served instead of the gateway's, see `demo`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DemoConfig {
+    pub enabled: bool,
+    /// Same seed, same campaigns
+    pub seed: u64,
+    pub campaigns_per_platform: usize,
+    pub platforms: Vec<Platform>,
+}
+
+impl Default for DemoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seed: 42,
+            campaigns_per_platform: 50,
+            platforms: vec![Platform::Google, Platform::Meta],
+        }
+    }
+}
+
+/// Incremental campaign syncs, see `delta_sync`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Between full syncs, fetch only campaigns changed since the last sync
+    pub delta: bool,
+    /// UTC hour from which the next sync is a full one, once a day
+    pub full_sync_hour: u32,
+    /// How much earlier than the last sync changes are asked for, covering
+    /// the delay of the platforms' change history
+    pub overlap_secs: u64,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            delta: false,
+            full_sync_hour: 23,
+            overlap_secs: 300,
+        }
+    }
+}
+
+/// Scheduled campaign changes, see `schedules`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Time zone of local times given without one, as an IANA name
+    pub timezone: Tz,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            timezone: Tz::America__Sao_Paulo,
+        }
+    }
+}
+
+/// Time zones of reporting days, see `timezones`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TimezoneConfig {
+    /// Reporting time zone of tenants without their own, as an IANA name
+    pub default: Tz,
+    /// Reporting time zone by tenant (`X-Tenant-Id`)
+    pub tenants: HashMap<String, Tz>,
+    /// Time zone of each platform's ad accounts, e.g. `meta`
+    pub platforms: HashMap<String, Tz>,
+}
+
+impl Default for TimezoneConfig {
+    fn default() -> Self {
+        Self {
+            default: Tz::UTC,
+            tenants: HashMap::new(),
+            platforms: HashMap::new(),
+        }
+    }
+}
+
+impl TimezoneConfig {
+    /// Reporting time zone of `tenant`
+    pub fn for_tenant(&self, tenant: Option<&str>) -> Tz {
+        tenant.and_then(|t| self.tenants.get(t)).copied().unwrap_or(self.default)
+    }
+
+    /// Time zone `platform` reports days in, for requests in `local`
+    pub fn for_platform(&self, platform: &str, local: Tz) -> Tz {
+        self.platforms.get(platform).copied().unwrap_or(local)
+    }
+}
+
+/// Daily platform API quotas, when clients are warned and when upstream
+/// calls are slowed down, see `quota`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct QuotaConfig {
+    /// Share of a quota past which responses carry a warning
+    pub warn_ratio: f64,
+    /// Share of a quota past which upstream calls are delayed
+    pub throttle_ratio: f64,
+    /// Delay of a call made with the quota used up
+    pub max_throttle_ms: u64,
+    /// Upstream calls allowed per UTC day, by platform name; platforms
+    /// without an entry are counted but never warned about
+    pub daily_calls: HashMap<String, u64>,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            warn_ratio: 0.8,
+            throttle_ratio: 0.9,
+            max_throttle_ms: 2000,
+            // Google Ads API basic access
+            daily_calls: HashMap::from([("google".to_string(), 15_000)]),
+        }
+    }
+}
+
+impl QuotaConfig {
+    pub fn daily_calls_for(&self, platform: &Platform) -> Option<u64> {
+        self.daily_calls.get(platform.as_str()).copied()
+    }
+
+    pub fn max_throttle(&self) -> Duration {
+        Duration::from_millis(self.max_throttle_ms)
+    }
+}
+
+/// Upstream calls in flight at once, see `concurrency`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    /// Calls in flight across every platform
+    pub max_in_flight: usize,
+    /// Calls in flight per platform, by platform name; platforms without
+    /// an entry are only bound by `max_in_flight`
+    pub platforms: HashMap<String, usize>,
+    /// Time a call waits for a free slot before failing
+    pub queue_timeout_ms: u64,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 64,
+            platforms: HashMap::from([("google".to_string(), 16), ("meta".to_string(), 16)]),
+            queue_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Time read requests wait for the platforms, see `latency_budget`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LatencyBudgetConfig {
+    /// Budget of every `GET` route, in milliseconds; 0 waits for every
+    /// platform
+    pub default_ms: u64,
+    /// Budgets by route template (`/campaigns/summary`), overriding
+    /// `default_ms`; 0 waits for every platform
+    pub routes: HashMap<String, u64>,
+}
+
+impl LatencyBudgetConfig {
+    /// Budget of `route`, `None` when unlimited
+    pub fn budget_for(&self, route: &str) -> Option<Duration> {
+        let ms = self.routes.get(route).copied().unwrap_or(self.default_ms);
+        (ms > 0).then(|| Duration::from_millis(ms))
+    }
+}
+
+/// Shedding of low-priority requests under load, see `load_shedding`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoadSheddingConfig {
+    pub enabled: bool,
+    /// Requests in flight from which `normal` requests are shed
+    pub max_in_flight: usize,
+    /// Requests in flight from which `low` requests are shed
+    pub low_max_in_flight: usize,
+    /// Smoothed response time from which `low` requests are shed; 0 sheds
+    /// on concurrency only
+    pub latency_threshold_ms: u64,
+    /// `Retry-After` of shed requests
+    pub retry_after_secs: u64,
+    /// Priority of reads by route template (`/campaigns`); reads of other
+    /// routes are `normal`, writes are always `high`
+    pub routes: HashMap<String, Priority>,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        let routes = load_shedding::HIGH_PRIORITY_ROUTES
+            .iter()
+            .map(|route| (route.to_string(), Priority::High))
+            .chain(
+                load_shedding::LOW_PRIORITY_ROUTES
+                    .iter()
+                    .map(|route| (route.to_string(), Priority::Low)),
+            )
+            .collect();
+        Self {
+            enabled: true,
+            max_in_flight: 512,
+            low_max_in_flight: 128,
+            latency_threshold_ms: 1500,
+            retry_after_secs: 2,
+            routes,
+        }
+    }
+}
+
+impl LoadSheddingConfig {
+    /// Priority of reads of the route template `route`
+    pub fn read_priority(&self, route: &str) -> Priority {
+        self.routes.get(route).copied().unwrap_or(Priority::Normal)
+    }
+
+    pub fn latency_threshold(&self) -> Option<Duration> {
+        (self.latency_threshold_ms > 0).then(|| Duration::from_millis(self.latency_threshold_ms))
+    }
+}
+
+impl ConcurrencyConfig {
+    pub fn limit_for(&self, platform: &Platform) -> Option<usize> {
+        self.platforms.get(platform.as_str()).copied()
+    }
+
+    pub fn queue_timeout(&self) -> Duration {
+        Duration::from_millis(self.queue_timeout_ms)
+    }
+}
+
+/// Hard monthly spend caps per tenant, see `spend_caps`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SpendCapConfig {
+    /// Share of a cap past which projected spend raises a critical alert
+    pub alert_ratio: f64,
+    /// Caps by tenant (`X-Tenant-Id`); tenants without one are not capped
+    pub tenants: HashMap<String, TenantSpendCap>,
+}
+
+impl Default for SpendCapConfig {
+    fn default() -> Self {
+        Self {
+            alert_ratio: 0.9,
+            tenants: HashMap::new(),
+        }
+    }
+}
+
+/// Spend cap of one tenant
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TenantSpendCap {
+    /// Most the tenant may spend in a calendar month, in its campaigns' currency
+    pub monthly_cap: f64,
+    /// Ad accounts the cap covers; empty covers every campaign
+    #[serde(default)]
+    pub account_ids: Vec<AccountId>,
+    /// Where alerts about nearing the cap are delivered
+    pub notifier: Option<NotifierConfig>,
+}
+
+impl TenantSpendCap {
+    pub fn covers(&self, account_id: Option<&AccountId>) -> bool {
+        self.account_ids.is_empty() || account_id.is_some_and(|a| self.account_ids.contains(a))
+    }
+}
+
+/// Automatic pausing of runaway campaigns, see `cost_guard`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CostGuardConfig {
+    pub enabled: bool,
+    /// Hourly spend, in multiples of the daily budget's hourly share, past
+    /// which a campaign is a runaway
+    pub multiplier: f64,
+    /// Shortest time spend is measured over
+    pub min_window_minutes: u64,
+    /// Spend within a window below which a campaign never trips
+    pub min_spend: f64,
+    /// Pause runaway campaigns; otherwise only alert
+    pub pause: bool,
+    /// Where alerts about campaigns of no listed tenant are delivered
+    pub notifier: Option<NotifierConfig>,
+    /// Settings by tenant (`X-Tenant-Id`), overriding the ones above for
+    /// the tenant's campaigns
+    pub tenants: HashMap<String, TenantCostGuard>,
+}
+
+impl Default for CostGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            multiplier: 3.0,
+            min_window_minutes: 30,
+            min_spend: 10.0,
+            pause: true,
+            notifier: None,
+            tenants: HashMap::new(),
+        }
+    }
+}
+
+/// Cost guard settings of one tenant
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TenantCostGuard {
+    /// Ad accounts of the tenant; empty covers campaigns of no other tenant
+    pub account_ids: Vec<AccountId>,
+    pub multiplier: Option<f64>,
+    pub pause: Option<bool>,
+    pub notifier: Option<NotifierConfig>,
+}
+
+/// Route access by role, see `rbac`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RbacConfig {
+    /// Role of actors without an assignment
+    pub default_role: Role,
+    /// Reject actors without an assignment instead of giving them
+    /// `default_role`
+    pub require_assignment: bool,
+    /// Role assignments, loaded on startup and written on every change
+    pub assignments_path: Option<PathBuf>,
+}
+
+impl Default for RbacConfig {
+    fn default() -> Self {
+        Self {
+            default_role: Role::Viewer,
+            require_assignment: false,
+            assignments_path: None,
+        }
+    }
+}
+
+/// Multi-region deployment, see `region`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RegionConfig {
+    /// Region this instance runs in
+    pub current: Region,
+    /// Base URLs of the instances in other regions, keyed by region name
+    pub peers: HashMap<String, String>,
+    /// Home region by tenant ID; tenants without an entry are served here
+    pub tenants: HashMap<String, Region>,
+    /// Regions whose data must not leave them; other regions' admin views
+    /// only get entry counts from these
+    pub restricted: Vec<Region>,
+}
+
+impl Default for RegionConfig {
+    fn default() -> Self {
+        Self {
+            current: Region::Us,
+            peers: HashMap::new(),
+            tenants: HashMap::new(),
+            restricted: vec![Region::Eu],
+        }
+    }
+}
+
+impl RegionConfig {
+    /// Region whose instance serves `tenant`
+    pub fn home_of(&self, tenant: Option<&str>) -> Region {
+        tenant
+            .and_then(|t| self.tenants.get(t))
+            .copied()
+            .unwrap_or(self.current)
+    }
+
+    pub fn peer_url(&self, region: Region) -> Option<&str> {
+        self.peers.get(region.as_str()).map(String::as_str)
+    }
+
+    /// Instances in other regions, in region order
+    pub fn peers(&self) -> Vec<(Region, &str)> {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .filter_map(|(region, url)| Some((region.parse().ok()?, url.as_str())))
+            .collect();
+        peers.sort_by_key(|(region, _)| *region);
+        peers
+    }
+
+    pub fn is_restricted(&self, region: Region) -> bool {
+        self.restricted.contains(&region)
+    }
+}
+
+/// Audit log of campaign mutations, see `audit`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// JSON Lines file entries are appended to, read back on startup
+    pub path: Option<PathBuf>,
+}
+
+/// Scheduled encrypted backups, see `backup`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Bucket backups are written to: `https://...` for an S3-compatible
+    /// endpoint, `file:///...` for a mounted volume; unset disables backups
+    pub url: Option<String>,
+    /// Bearer token for the bucket endpoint; prefer IBVI_BACKUP_TOKEN
+    pub token: Option<String>,
+    /// AES-256 key as 64 hex characters; prefer IBVI_BACKUP_KEY
+    pub key: String,
+    pub interval_secs: u64,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token: None,
+            key: String::new(),
+            interval_secs: 24 * 3600,
+        }
+    }
+}
+
+/// Per-tenant platform OAuth connections, see `credentials`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CredentialsConfig {
+    /// OAuth clients by platform (`google`, `meta`, ...); platforms without
+    /// one keep relying on the gateway's own credentials
+    pub platforms: HashMap<String, OAuthClientConfig>,
+    /// AES-256 key sealing refresh tokens, as 64 hex characters; prefer
+    /// IBVI_CREDENTIALS_KEY
+    pub key: String,
+    /// Connections file, loaded on startup and written on every change;
+    /// unset keeps connections in memory
+    pub path: Option<PathBuf>,
+    /// Access tokens are refreshed this long before they expire
+    pub refresh_margin_secs: u64,
+}
+
+impl Default for CredentialsConfig {
+    fn default() -> Self {
+        Self {
+            platforms: HashMap::new(),
+            key: String::new(),
+            path: None,
+            refresh_margin_secs: 300,
+        }
+    }
+}
+
+impl CredentialsConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.platforms.is_empty()
+    }
+
+    pub fn refresh_margin(&self) -> Duration {
+        Duration::from_secs(self.refresh_margin_secs)
+    }
+}
+
+/// OAuth client the instance refreshes a platform's access tokens with
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OAuthClientConfig {
+    pub token_url: String,
+    pub client_id: String,
+    /// Prefer IBVI_CREDENTIALS_<PLATFORM>_SECRET
+    #[serde(default)]
+    pub client_secret: String,
+}
+
+/// Daily snapshot exports for the analytics team, see `warehouse`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WarehouseConfig {
+    /// Bucket Parquet files are written to, like `backup.url`; unset
+    /// disables exports
+    pub url: Option<String>,
+    /// Bearer token for the bucket endpoint; prefer IBVI_WAREHOUSE_TOKEN
+    pub token: Option<String>,
+    /// Time of day (UTC) the previous day is exported
+    pub export_at: NaiveTime,
+    /// Table the rows are also streamed to
+    pub bigquery: Option<BigQueryConfig>,
+}
+
+impl Default for WarehouseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token: None,
+            export_at: NaiveTime::from_hms_opt(3, 0, 0).unwrap(),
+            bigquery: None,
+        }
+    }
+}
+
+/// BigQuery table rows are streamed to with `tabledata.insertAll`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BigQueryConfig {
+    pub project: String,
+    pub dataset: String,
+    pub table: String,
+    /// OAuth access token; prefer IBVI_BIGQUERY_TOKEN, else `warehouse.token`
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BulkConfig {
+    /// How long after execution a bulk batch can still be rolled back
+    pub rollback_window_secs: u64,
+}
+
+impl Default for BulkConfig {
+    fn default() -> Self {
+        Self {
+            rollback_window_secs: 24 * 3600,
+        }
+    }
+}
+
+/// Status, objective and campaign type mapping tables, see `normalization`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct NormalizationConfig {
+    /// JSON tables loaded over the built-in ones, at startup and on reload
+    pub rules_path: Option<PathBuf>,
+}
+
+/// Capture of raw platform payloads for debugging mappings, see `raw`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RawCaptureConfig {
+    /// Keep every payload read through `GET /campaigns/{platform}/{id}/raw`
+    pub enabled: bool,
+    pub retention_days: u32,
+    /// Object keys redacted besides credentials and contact details
+    pub redact_keys: Vec<String>,
+}
+
+impl Default for RawCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: 7,
+            redact_keys: Vec::new(),
+        }
+    }
+}
+
+/// Redis instance shared by the replicas of one deployment
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedisConfig {
+    pub url: String,
+    /// Prepended to every key, so deployments can share an instance
+    #[serde(default = "default_redis_key_prefix")]
+    pub key_prefix: String,
+}
+
+fn default_redis_key_prefix() -> String {
+    "ibvi".to_string()
+}
+
+/// SMTP relay for email alerts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub gateway: GatewayConfig,
+    pub timeouts: TimeoutConfig,
+    pub cache: CacheConfig,
+    pub bulk: BulkConfig,
+    pub normalization: NormalizationConfig,
+    pub raw_capture: RawCaptureConfig,
+    pub audit: AuditConfig,
+    pub backup: BackupConfig,
+    pub credentials: CredentialsConfig,
+    pub warehouse: WarehouseConfig,
+    pub rbac: RbacConfig,
+    pub quota: QuotaConfig,
+    pub concurrency: ConcurrencyConfig,
+    pub latency_budget: LatencyBudgetConfig,
+    pub load_shedding: LoadSheddingConfig,
+    pub spend_caps: SpendCapConfig,
+    pub cost_guard: CostGuardConfig,
+    pub warmup: WarmupConfig,
+    pub sync: SyncConfig,
+    pub schedules: ScheduleConfig,
+    pub timezones: TimezoneConfig,
+    pub benchmarks: BenchmarkConfig,
+    pub region: RegionConfig,
+    pub api_versions: ApiVersionsConfig,
+    pub demo: DemoConfig,
+    pub smtp: Option<SmtpConfig>,
+    pub redis: Option<RedisConfig>,
+}
+
+impl AppConfig {
+    /// Load defaults, the file named by `IBVI_CONFIG` and `IBVI_*` overrides, then validate
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_with_args(std::iter::empty())
+    }
+
+    /// `load`, with command-line `args` applied last; binaries pass
+    /// `std::env::args().skip(1)`
+    pub fn load_with_args(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut config = match std::env::var(CONFIG_PATH_ENV) {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) => Self::default(),
+        };
+
+        config.apply_env(|key| std::env::var(key).ok())?;
+        config.apply_args(args)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Override settings from command-line arguments, program name excluded
+    ///
+    /// `--demo`, `--demo-seed=N` and `--demo-campaigns=N` turn on demo data.
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), ConfigError> {
+        for arg in args {
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (arg.as_str(), None),
+            };
+            let number = || {
+                value.and_then(|v| v.parse().ok()).ok_or_else(|| {
+                    ConfigError::invalid("args", format!("{} needs a number, e.g. {}=42", flag, flag))
+                })
+            };
+            match flag {
+                "--demo" => {}
+                "--demo-seed" => self.demo.seed = number()?,
+                "--demo-campaigns" => self.demo.campaigns_per_platform = number()? as usize,
+                _ => return Err(ConfigError::invalid("args", format!("unknown argument {:?}", arg))),
+            }
+            self.demo.enabled = true;
+        }
+        Ok(())
+    }
+
+    /// Override settings from environment variables looked up through `var`
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(value) = var("IBVI_ADDR") {
+            self.server.addr = parse_env("IBVI_ADDR", value)?;
+        }
+        if let Some(value) = var("IBVI_SNAPSHOT_PATH") {
+            self.server.snapshot_path = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("IBVI_DRAIN_TIMEOUT_SECS") {
+            self.server.drain_timeout_secs = parse_env("IBVI_DRAIN_TIMEOUT_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_UPSTREAM_CALL_BUDGET") {
+            self.server.upstream_call_budget = parse_env("IBVI_UPSTREAM_CALL_BUDGET", value)?;
+        }
+        if let Some(value) = var("IBVI_COMPRESSION_MIN_SIZE") {
+            self.server.compression.min_size_bytes = parse_env("IBVI_COMPRESSION_MIN_SIZE", value)?;
+        }
+        if let Some(value) = var("IBVI_TIMING_HEADER") {
+            self.server.timing_header = parse_env("IBVI_TIMING_HEADER", value)?;
+        }
+        if let Some(value) = var("IBVI_IDEMPOTENCY_TTL_SECS") {
+            self.server.idempotency_ttl_secs = parse_env("IBVI_IDEMPOTENCY_TTL_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_GATEWAY_URL") {
+            self.gateway.url = value;
+        }
+        if let Some(value) = var("IBVI_GATEWAY_TOKEN") {
+            self.gateway.service_token = value;
+        }
+        for platform in KNOWN_PLATFORMS {
+            let key = format!("IBVI_GATEWAY_URL_{}", platform.to_uppercase());
+            if let Some(value) = var(&key) {
+                self.gateway.platform_urls.insert(platform.to_string(), value);
+            }
+        }
+        if let Some(value) = var("IBVI_PAGE_SIZE") {
+            self.gateway.pagination.page_size = parse_env("IBVI_PAGE_SIZE", value)?;
+        }
+        if let Some(value) = var("IBVI_MAX_PAGES") {
+            self.gateway.pagination.max_pages = parse_env("IBVI_MAX_PAGES", value)?;
+        }
+        if let Some(value) = var("IBVI_PAGE_CONCURRENCY") {
+            self.gateway.pagination.concurrency = parse_env("IBVI_PAGE_CONCURRENCY", value)?;
+        }
+        if let Some(value) = var("IBVI_REQUEST_TIMEOUT_SECS") {
+            self.timeouts.request_secs = parse_env("IBVI_REQUEST_TIMEOUT_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_CAMPAIGNS_TTL_SECS") {
+            self.cache.campaigns_ttl_secs = parse_env("IBVI_CAMPAIGNS_TTL_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_CAMPAIGNS_STALE_SECS") {
+            self.cache.campaigns_stale_secs = parse_env("IBVI_CAMPAIGNS_STALE_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_LATENCY_BUDGET_MS") {
+            self.latency_budget.default_ms = parse_env("IBVI_LATENCY_BUDGET_MS", value)?;
+        }
+        if let Some(value) = var("IBVI_LOAD_SHEDDING_ENABLED") {
+            self.load_shedding.enabled = parse_env("IBVI_LOAD_SHEDDING_ENABLED", value)?;
+        }
+        if let Some(value) = var("IBVI_LOAD_SHEDDING_MAX_IN_FLIGHT") {
+            self.load_shedding.max_in_flight =
+                parse_env("IBVI_LOAD_SHEDDING_MAX_IN_FLIGHT", value)?;
+        }
+        if let Some(value) = var("IBVI_BULK_ROLLBACK_WINDOW_SECS") {
+            self.bulk.rollback_window_secs = parse_env("IBVI_BULK_ROLLBACK_WINDOW_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_NORMALIZATION_RULES_PATH") {
+            self.normalization.rules_path = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("IBVI_RAW_CAPTURE") {
+            self.raw_capture.enabled = parse_env("IBVI_RAW_CAPTURE", value)?;
+        }
+        if let Some(value) = var("IBVI_RAW_CAPTURE_RETENTION_DAYS") {
+            self.raw_capture.retention_days =
+                parse_env("IBVI_RAW_CAPTURE_RETENTION_DAYS", value)?;
+        }
+        if let Some(value) = var("IBVI_WARMUP") {
+            self.warmup.enabled = parse_env("IBVI_WARMUP", value)?;
+        }
+        if let Some(value) = var("IBVI_WARMUP_TENANTS") {
+            self.warmup.tenants = value
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(value) = var("IBVI_QUOTA_WARN_RATIO") {
+            self.quota.warn_ratio = parse_env("IBVI_QUOTA_WARN_RATIO", value)?;
+        }
+        if let Some(value) = var("IBVI_QUOTA_THROTTLE_RATIO") {
+            self.quota.throttle_ratio = parse_env("IBVI_QUOTA_THROTTLE_RATIO", value)?;
+        }
+        if let Some(value) = var("IBVI_QUOTA_MAX_THROTTLE_MS") {
+            self.quota.max_throttle_ms = parse_env("IBVI_QUOTA_MAX_THROTTLE_MS", value)?;
+        }
+        if let Some(value) = var("IBVI_UPSTREAM_MAX_IN_FLIGHT") {
+            self.concurrency.max_in_flight = parse_env("IBVI_UPSTREAM_MAX_IN_FLIGHT", value)?;
+        }
+        for platform in KNOWN_PLATFORMS {
+            let key = format!("IBVI_DAILY_CALLS_{}", platform.to_uppercase());
+            if let Some(value) = var(&key) {
+                let calls = parse_env(&key, value)?;
+                self.quota.daily_calls.insert(platform.to_string(), calls);
+            }
+        }
+        if let Some(value) = var("IBVI_SPEND_CAP_ALERT_RATIO") {
+            self.spend_caps.alert_ratio = parse_env("IBVI_SPEND_CAP_ALERT_RATIO", value)?;
+        }
+        if let Some(value) = var("IBVI_COST_GUARD_ENABLED") {
+            self.cost_guard.enabled = parse_env("IBVI_COST_GUARD_ENABLED", value)?;
+        }
+        if let Some(value) = var("IBVI_COST_GUARD_MULTIPLIER") {
+            self.cost_guard.multiplier = parse_env("IBVI_COST_GUARD_MULTIPLIER", value)?;
+        }
+        if let Some(value) = var("IBVI_SYNC_DELTA") {
+            self.sync.delta = parse_env("IBVI_SYNC_DELTA", value)?;
+        }
+        if let Some(value) = var("IBVI_TIMEZONE") {
+            self.timezones.default = parse_env("IBVI_TIMEZONE", value)?;
+        }
+        if let Some(value) = var("IBVI_SCHEDULE_TIMEZONE") {
+            self.schedules.timezone = parse_env("IBVI_SCHEDULE_TIMEZONE", value)?;
+        }
+        if let Some(value) = var("IBVI_RBAC_DEFAULT_ROLE") {
+            self.rbac.default_role = parse_env("IBVI_RBAC_DEFAULT_ROLE", value)?;
+        }
+        if let Some(value) = var("IBVI_RBAC_REQUIRE_ASSIGNMENT") {
+            self.rbac.require_assignment = parse_env("IBVI_RBAC_REQUIRE_ASSIGNMENT", value)?;
+        }
+        if let Some(value) = var("IBVI_RBAC_ASSIGNMENTS_PATH") {
+            self.rbac.assignments_path = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("IBVI_REGION") {
+            self.region.current = parse_env("IBVI_REGION", value)?;
+        }
+        for region in Region::ALL {
+            let key = format!("IBVI_REGION_PEER_{}", region.as_str().to_uppercase());
+            if let Some(value) = var(&key) {
+                self.region.peers.insert(region.as_str().to_string(), value);
+            }
+        }
+        if let Some(value) = var("IBVI_AUDIT_PATH") {
+            self.audit.path = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("IBVI_BACKUP_URL") {
+            self.backup.url = Some(value);
+        }
+        if let Some(value) = var("IBVI_BACKUP_TOKEN") {
+            self.backup.token = Some(value);
+        }
+        if let Some(value) = var("IBVI_BACKUP_KEY") {
+            self.backup.key = value;
+        }
+        if let Some(value) = var("IBVI_BACKUP_INTERVAL_SECS") {
+            self.backup.interval_secs = parse_env("IBVI_BACKUP_INTERVAL_SECS", value)?;
+        }
+        if let Some(value) = var("IBVI_CREDENTIALS_KEY") {
+            self.credentials.key = value;
+        }
+        for (platform, client) in &mut self.credentials.platforms {
+            let key = format!("IBVI_CREDENTIALS_{}_SECRET", platform.to_uppercase());
+            if let Some(value) = var(&key) {
+                client.client_secret = value;
+            }
+        }
+        if let Some(value) = var("IBVI_WAREHOUSE_URL") {
+            self.warehouse.url = Some(value);
+        }
+        if let Some(value) = var("IBVI_WAREHOUSE_TOKEN") {
+            self.warehouse.token = Some(value);
+        }
+        if let Some(value) = var("IBVI_BIGQUERY_TOKEN") {
+            if let Some(bigquery) = &mut self.warehouse.bigquery {
+                bigquery.token = Some(value);
+            }
+        }
+        if let Some(value) = var("IBVI_USAGE_PATH") {
+            self.warmup.usage_path = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("IBVI_DEMO_MODE") {
+            self.demo.enabled = parse_env("IBVI_DEMO_MODE", value)?;
+        }
+        if let Some(value) = var("IBVI_DEMO_SEED") {
+            self.demo.seed = parse_env("IBVI_DEMO_SEED", value)?;
+        }
+        if let Some(value) = var("IBVI_DEMO_CAMPAIGNS") {
+            self.demo.campaigns_per_platform = parse_env("IBVI_DEMO_CAMPAIGNS", value)?;
+        }
+        if let Some(host) = var("IBVI_SMTP_HOST") {
+            self.smtp = Some(SmtpConfig {
+                host,
+                username: var("IBVI_SMTP_USERNAME").unwrap_or_default(),
+                password: var("IBVI_SMTP_PASSWORD").unwrap_or_default(),
+                from: var("IBVI_SMTP_FROM").unwrap_or_default(),
+            });
+        }
+        if let Some(url) = var("IBVI_REDIS_URL") {
+            self.redis = Some(RedisConfig {
+                url,
+                key_prefix: var("IBVI_REDIS_KEY_PREFIX").unwrap_or_else(default_redis_key_prefix),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reject configs the service cannot run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        validate_url("gateway.url", &self.gateway.url)?;
+
+        for (platform, url) in &self.gateway.platform_urls {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                return Err(ConfigError::invalid(
+                    "gateway.platform_urls",
+                    format!("unknown platform {:?}, expected one of {:?}", platform, KNOWN_PLATFORMS),
+                ));
+            }
+            validate_url("gateway.platform_urls", url)?;
+        }
+
+        for platform in self.quota.daily_calls.keys() {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                return Err(ConfigError::invalid(
+                    "quota.daily_calls",
+                    format!("unknown platform {:?}, expected one of {:?}", platform, KNOWN_PLATFORMS),
+                ));
+            }
+        }
+        if !(self.quota.warn_ratio > 0.0 && self.quota.warn_ratio <= 1.0) {
+            return Err(ConfigError::invalid("quota.warn_ratio", "must be in (0, 1]"));
+        }
+        if !(self.quota.throttle_ratio > 0.0 && self.quota.throttle_ratio <= 1.0) {
+            return Err(ConfigError::invalid("quota.throttle_ratio", "must be in (0, 1]"));
+        }
+
+        for platform in self.timezones.platforms.keys() {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                return Err(ConfigError::invalid(
+                    "timezones.platforms",
+                    format!("unknown platform {:?}, expected one of {:?}", platform, KNOWN_PLATFORMS),
+                ));
+            }
+        }
+
+        if self.concurrency.max_in_flight == 0 {
+            return Err(ConfigError::invalid("concurrency.max_in_flight", "must be at least 1"));
+        }
+        for (platform, limit) in &self.concurrency.platforms {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                return Err(ConfigError::invalid(
+                    "concurrency.platforms",
+                    format!("unknown platform {:?}, expected one of {:?}", platform, KNOWN_PLATFORMS),
+                ));
+            }
+            if *limit == 0 {
+                return Err(ConfigError::invalid(
+                    "concurrency.platforms",
+                    format!("limit of {} must be at least 1", platform),
+                ));
+            }
+        }
+        if self.concurrency.queue_timeout_ms == 0 {
+            return Err(ConfigError::invalid("concurrency.queue_timeout_ms", "must be at least 1"));
+        }
+
+        if self.raw_capture.enabled && self.raw_capture.retention_days == 0 {
+            return Err(ConfigError::invalid("raw_capture.retention_days", "must be at least 1"));
+        }
+
+        for (vertical, baseline) in &self.benchmarks.verticals {
+            let rates = [baseline.ctr, baseline.cpc, baseline.cpa];
+            if !rates.iter().all(|r| r.is_finite() && *r > 0.0) || baseline.ctr > 100.0 {
+                return Err(ConfigError::invalid(
+                    "benchmarks.verticals",
+                    format!("baselines of {:?} must be positive, CTR at most 100", vertical),
+                ));
+            }
+        }
+        let assigned = self.benchmarks.accounts.values().chain(&self.benchmarks.default_vertical);
+        for vertical in assigned {
+            if !self.benchmarks.verticals.contains_key(vertical) {
+                return Err(ConfigError::invalid(
+                    "benchmarks",
+                    format!("vertical {:?} has no baseline", vertical),
+                ));
+            }
+        }
+        if !(self.benchmarks.tolerance > 0.0 && self.benchmarks.tolerance < 1.0) {
+            return Err(ConfigError::invalid("benchmarks.tolerance", "must be in (0, 1)"));
+        }
+
+        for (version, deprecation) in &self.api_versions.deprecated {
+            if deprecation.sunset.is_some_and(|sunset| sunset <= deprecation.since) {
+                return Err(ConfigError::invalid(
+                    "api_versions.deprecated",
+                    format!("{} must be deprecated before its sunset", version.as_str()),
+                ));
+            }
+        }
+
+        if self.sync.full_sync_hour > 23 {
+            return Err(ConfigError::invalid("sync.full_sync_hour", "must be an hour from 0 to 23"));
+        }
+
+        if !(self.spend_caps.alert_ratio > 0.0 && self.spend_caps.alert_ratio <= 1.0) {
+            return Err(ConfigError::invalid("spend_caps.alert_ratio", "must be in (0, 1]"));
+        }
+        for (tenant, cap) in &self.spend_caps.tenants {
+            if !(cap.monthly_cap.is_finite() && cap.monthly_cap > 0.0) {
+                return Err(ConfigError::invalid(
+                    "spend_caps.tenants",
+                    format!("monthly_cap of tenant {:?} must be greater than zero", tenant),
+                ));
+            }
+        }
+
+        let multipliers = std::iter::once(self.cost_guard.multiplier)
+            .chain(self.cost_guard.tenants.values().filter_map(|t| t.multiplier));
+        for multiplier in multipliers {
+            if !(multiplier.is_finite() && multiplier > 1.0) {
+                return Err(ConfigError::invalid(
+                    "cost_guard.multiplier",
+                    "must be greater than 1, or campaigns on pace would trip",
+                ));
+            }
+        }
+        if self.cost_guard.min_window_minutes == 0 {
+            return Err(ConfigError::invalid("cost_guard.min_window_minutes", "must be positive"));
+        }
+
+        for (name, url) in &self.region.peers {
+            let region: Region = name
+                .parse()
+                .map_err(|e: String| ConfigError::invalid("region.peers", e))?;
+            if region == self.region.current {
+                return Err(ConfigError::invalid(
+                    "region.peers",
+                    format!("{} is this instance's own region", region),
+                ));
+            }
+            validate_url("region.peers", url)?;
+        }
+        for (tenant, home) in &self.region.tenants {
+            if *home != self.region.current && self.region.peer_url(*home).is_none() {
+                return Err(ConfigError::invalid(
+                    "region.tenants",
+                    format!("tenant {:?} is homed in {} but no peer serves it", tenant, home),
+                ));
+            }
+        }
+
+        if self.demo.enabled {
+            if !(1..=MAX_DEMO_CAMPAIGNS).contains(&self.demo.campaigns_per_platform) {
+                return Err(ConfigError::invalid(
+                    "demo.campaigns_per_platform",
+                    format!("must be between 1 and {}", MAX_DEMO_CAMPAIGNS),
+                ));
+            }
+            if self.demo.platforms.is_empty() {
+                return Err(ConfigError::invalid(
+                    "demo.platforms",
+                    "must list at least one platform",
+                ));
+            }
+        }
+        // Demo data never reaches the gateway
+        if self.gateway.service_token.trim().is_empty() && !self.demo.enabled {
+            return Err(ConfigError::invalid(
+                "gateway.service_token",
+                "must be set (IBVI_GATEWAY_TOKEN)",
+            ));
+        }
+        let pagination = &self.gateway.pagination;
+        if pagination.page_size == 0 || pagination.max_pages == 0 || pagination.concurrency == 0 {
+            return Err(ConfigError::invalid(
+                "gateway.pagination",
+                "page_size, max_pages and concurrency must be greater than zero",
+            ));
+        }
+        if self.timeouts.request_secs == 0 || self.timeouts.connect_secs == 0 {
+            return Err(ConfigError::invalid("timeouts", "timeouts must be greater than zero"));
+        }
+        if self.server.upstream_call_budget == 0 {
+            return Err(ConfigError::invalid(
+                "server.upstream_call_budget",
+                "must allow at least one call",
+            ));
+        }
+        if self.server.idempotency_ttl_secs == 0 {
+            return Err(ConfigError::invalid(
+                "server.idempotency_ttl_secs",
+                "must be greater than zero",
+            ));
+        }
+        if let Some(url) = &self.backup.url {
+            if !url.starts_with("file://") {
+                validate_url("backup.url", url)?;
+            }
+            let key = hex::decode(self.backup.key.trim()).unwrap_or_default();
+            if key.len() != 32 {
+                return Err(ConfigError::invalid(
+                    "backup.key",
+                    "must be 64 hex characters (IBVI_BACKUP_KEY)",
+                ));
+            }
+            if self.backup.interval_secs == 0 {
+                return Err(ConfigError::invalid(
+                    "backup.interval_secs",
+                    "must be greater than zero",
+                ));
+            }
+        }
+        if self.credentials.is_enabled() {
+            let key = hex::decode(self.credentials.key.trim()).unwrap_or_default();
+            if key.len() != 32 {
+                return Err(ConfigError::invalid(
+                    "credentials.key",
+                    "must be 64 hex characters (IBVI_CREDENTIALS_KEY)",
+                ));
+            }
+            for (platform, client) in &self.credentials.platforms {
+                if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                    return Err(ConfigError::invalid(
+                        "credentials.platforms",
+                        format!("unknown platform {:?}, expected one of {:?}", platform, KNOWN_PLATFORMS),
+                    ));
+                }
+                validate_url("credentials.platforms.token_url", &client.token_url)?;
+                if client.client_id.is_empty() {
+                    return Err(ConfigError::invalid(
+                        "credentials.platforms.client_id",
+                        format!("is required for {}", platform),
+                    ));
+                }
+            }
+        }
+        if let Some(url) = &self.warehouse.url {
+            if !url.starts_with("file://") {
+                validate_url("warehouse.url", url)?;
+            }
+            if let Some(bigquery) = &self.warehouse.bigquery {
+                let names = [&bigquery.project, &bigquery.dataset, &bigquery.table];
+                if names.iter().any(|name| name.trim().is_empty()) {
+                    return Err(ConfigError::invalid(
+                        "warehouse.bigquery",
+                        "project, dataset and table must not be empty",
+                    ));
+                }
+            }
+            #[cfg(not(feature = "warehouse"))]
+            return Err(ConfigError::invalid(
+                "warehouse",
+                "snapshot exports need a build with the `warehouse` feature",
+            ));
+        }
+        if self.cache.campaigns_stale_secs > 0 && self.cache.campaigns_ttl_secs == 0 {
+            return Err(ConfigError::invalid(
+                "cache.campaigns_stale_secs",
+                "needs the entity cache, cache.campaigns_ttl_secs is 0",
+            ));
+        }
+        for route in self.cache.routes.keys() {
+            validate_route("cache.routes", route)?;
+        }
+        for route in self.latency_budget.routes.keys() {
+            validate_route("latency_budget.routes", route)?;
+        }
+        for route in self.load_shedding.routes.keys() {
+            validate_route("load_shedding.routes", route)?;
+        }
+        let shedding = &self.load_shedding;
+        if shedding.low_max_in_flight == 0 || shedding.low_max_in_flight > shedding.max_in_flight {
+            return Err(ConfigError::invalid(
+                "load_shedding.low_max_in_flight",
+                "must be between 1 and load_shedding.max_in_flight",
+            ));
+        }
+        if self.warmup.enabled && self.warmup.timeout_secs == 0 {
+            return Err(ConfigError::invalid("warmup.timeout_secs", "must be greater than zero"));
+        }
+        if let Some(smtp) = &self.smtp {
+            if smtp.host.is_empty() {
+                return Err(ConfigError::invalid("smtp.host", "must not be empty"));
+            }
+            #[cfg(feature = "email")]
+            smtp.from
+                .parse::<lettre::message::Mailbox>()
+                .map_err(|e| ConfigError::invalid("smtp.from", e.to_string()))?;
+            #[cfg(not(feature = "email"))]
+            return Err(ConfigError::invalid(
+                "smtp",
+                "email alerts need a build with the `email` feature",
+            ));
+        }
+        if let Some(redis) = &self.redis {
+            let parsed = reqwest::Url::parse(&redis.url).map_err(|e| {
+                ConfigError::invalid("redis.url", format!("{:?} is not a URL: {}", redis.url, e))
+            })?;
+            if !matches!(parsed.scheme(), "redis" | "rediss") {
+                return Err(ConfigError::invalid(
+                    "redis.url",
+                    format!("{:?} must use redis or rediss", redis.url),
+                ));
+            }
+            if redis.key_prefix.is_empty() {
+                return Err(ConfigError::invalid("redis.key_prefix", "must not be empty"));
+            }
+            #[cfg(not(feature = "redis"))]
+            return Err(ConfigError::invalid(
+                "redis",
+                "shared state needs a build with the `redis` feature",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeouts.request_secs)
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeouts.connect_secs)
+    }
+
+    pub fn idempotency_ttl(&self) -> Duration {
+        Duration::from_secs(self.server.idempotency_ttl_secs)
+    }
+
+    pub fn campaigns_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache.campaigns_ttl_secs)
+    }
+
+    pub fn campaigns_stale(&self) -> Duration {
+        Duration::from_secs(self.cache.campaigns_stale_secs)
+    }
+
+    pub fn backup_interval(&self) -> Duration {
+        Duration::from_secs(self.backup.interval_secs)
+    }
+
+    pub fn bulk_rollback_window(&self) -> Duration {
+        Duration::from_secs(self.bulk.rollback_window_secs)
+    }
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str, value: String) -> Result<T, ConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e: T::Err| ConfigError::Env {
+        key: key.to_string(),
+        reason: e.to_string(),
+        value,
+    })
+}
+
+/// Route templates are keyed without version prefix, e.g. `/campaigns`
+fn validate_route(setting: &'static str, route: &str) -> Result<(), ConfigError> {
+    if !route.starts_with('/') || versioning::unversioned(route) != route {
+        return Err(ConfigError::invalid(
+            setting,
+            format!("{:?} must be a template without version, e.g. /campaigns", route),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_url(setting: &'static str, url: &str) -> Result<(), ConfigError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| ConfigError::invalid(setting, format!("{:?} is not a URL: {}", url, e)))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ConfigError::invalid(
+            setting,
+            format!("{:?} must use http or https", url),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_env_overrides_file_values() {
+        let mut config: AppConfig = toml::from_str(
+            r#"
+            [gateway]
+            url = "http://gateway:8000"
+            service_token = "from-file"
+
+            [gateway.platform_urls]
+            meta = "http://meta-gateway:8000"
+            "#,
+        )
+        .unwrap();
+
+        config
+            .apply_env(env(&[
+                ("IBVI_GATEWAY_TOKEN", "from-env"),
+                ("IBVI_UPSTREAM_CALL_BUDGET", "10"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.gateway.service_token, "from-env");
+        assert_eq!(config.server.upstream_call_budget, 10);
+        assert_eq!(config.gateway.url_for(&Platform::Meta), "http://meta-gateway:8000");
+        assert_eq!(config.gateway.url_for(&Platform::Google), "http://gateway:8000");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_env_value_names_the_variable() {
+        let err = AppConfig::default()
+            .apply_env(env(&[("IBVI_REQUEST_TIMEOUT_SECS", "soon")]))
+            .unwrap_err();
+
+        assert!(err.to_string().contains("IBVI_REQUEST_TIMEOUT_SECS"));
+    }
+
+    #[test]
+    fn test_validation_requires_token_and_known_platforms() {
+        let mut config = AppConfig::default();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { setting: "gateway.service_token", .. })
+        ));
+
+        config.gateway.service_token = "secret".to_string();
+        config
+            .gateway
+            .platform_urls
+            .insert("tiktok".to_string(), "http://tiktok:8000".to_string());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { setting: "gateway.platform_urls", .. })
+        ));
+    }
+
+    #[test]
+    fn test_redis_url_comes_from_env_and_needs_redis_scheme() {
+        let mut config = AppConfig::default();
+        config.gateway.service_token = "secret".to_string();
+        config.apply_env(env(&[("IBVI_REDIS_URL", "http://redis:6379")])).unwrap();
+
+        assert_eq!(config.redis.as_ref().unwrap().key_prefix, "ibvi");
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { setting: "redis.url", .. })
+        ));
+
+        config.redis.as_mut().unwrap().url = "redis://redis:6379/0".to_string();
+        #[cfg(feature = "redis")]
+        assert!(config.validate().is_ok());
+        #[cfg(not(feature = "redis"))]
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid { setting: "redis", .. })));
+    }
+
+    #[test]
+    fn test_tenants_need_an_instance_in_their_home_region() {
+        let mut config = AppConfig::default();
+        config.gateway.service_token = "secret".to_string();
+        config.region.tenants.insert("acme-eu".to_string(), Region::Eu);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { setting: "region.tenants", .. })
+        ));
+
+        config
+            .apply_env(env(&[("IBVI_REGION_PEER_EU", "https://eu.ads-api.internal")]))
+            .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.region.home_of(Some("acme-eu")), Region::Eu);
+        assert_eq!(config.region.home_of(Some("acme")), Region::Us);
+    }
+
+    #[test]
+    fn test_demo_mode_needs_no_gateway_token() {
+        let mut config = AppConfig::default();
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        config.apply_args(args(&["--demo", "--demo-campaigns=2000"])).unwrap();
+        assert_eq!((config.demo.enabled, config.demo.campaigns_per_platform), (true, 2000));
+        assert!(config.validate().is_ok());
+
+        assert!(config.apply_args(args(&["--demo-seed=soon"])).is_err());
+        assert!(config.apply_args(args(&["--verbose"])).is_err());
+        config.apply_env(env(&[("IBVI_DEMO_CAMPAIGNS", "0")])).unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { setting: "demo.campaigns_per_platform", .. })
+        ));
+    }
+}
@@ -0,0 +1,1088 @@
+//! # Platform Connectors Example
+//!
+//! Every ad platform is reached through a `PlatformConnector`. The
+//! aggregator only talks to the `ConnectorRegistry` stored in `AppState`,
+//! so adding a platform means implementing one trait and registering it,
+//! and tests can register mock connectors instead of a live gateway.
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::accounts::{self, AccountNode, GoogleCustomerClient, MetaAdAccount};
+use super::auction::{self, AuctionInsights, GoogleAuctionInsight, MetaAuctionInsight};
+use super::audiences::AudienceInfo;
+use super::bidding::{self, Bidding, BiddingChange, GoogleBidding, MetaBidding};
+use super::breakdowns::{self, Dimension, SegmentRow};
+use super::call_budget::BudgetExceeded;
+use super::concurrency::{ConcurrencyLimiter, LimitedConnector};
+use super::campaign_aggregator::{Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::config::{GatewayConfig, PaginationConfig};
+use super::conversions::ConversionActionRow;
+use super::credentials::{self, CredentialManager};
+use super::entity_cache::{CachingConnector, EntityCache};
+use super::hydration::{Ad, AdGroup};
+use super::ids::{AdGroupId, CampaignId};
+use super::labels;
+use super::launch::LaunchPayload;
+use super::lenient::{self, DecodeErrors, Lenient};
+use super::negatives::{Exclusion, ExclusionUpdate};
+use super::pagination::{self, GatewayPage, Page};
+use super::platform_errors::{self, PlatformError, PlatformErrorCode};
+use super::products::{self, ProductRow};
+use super::quality::{
+    self, GoogleKeywordQuality, MetaAdQuality, QualityDiagnostics, QualitySignal,
+};
+use super::quota::{MeteredConnector, QuotaTracker};
+use super::reauth::{CredentialHealth, ReauthConnector};
+use super::spec::{AdGroupSpec, CampaignSpec};
+use super::targeting::{self, GoogleTargeting, MetaTargeting, Targeting};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectorError {
+    #[error("gateway request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("campaign {0} not found")]
+    NotFound(String),
+    #[error("{0} is not supported by this platform")]
+    NotSupported(&'static str),
+    #[error(transparent)]
+    BudgetExceeded(#[from] BudgetExceeded),
+    #[error(transparent)]
+    Platform(#[from] PlatformError),
+}
+
+/// Fetch and mutate campaigns on one advertising platform
+#[async_trait]
+pub trait PlatformConnector: Send + Sync {
+    /// Platform served by this connector
+    fn platform(&self) -> Platform;
+
+    /// All campaigns of the platform, normalized into the unified model
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError>;
+
+    /// Current metrics of a single campaign
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError>;
+
+    /// Change the delivery status of a campaign
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError>;
+
+    /// Probe the platform's gateway route without fetching data
+    async fn health_check(&self) -> Result<(), ConnectorError>;
+
+    /// Ad groups (ad sets on Meta) of several campaigns in one call
+    async fn fetch_ad_groups(&self, _campaign_ids: &[CampaignId]) -> Result<Vec<AdGroup>, ConnectorError> {
+        Err(ConnectorError::NotSupported("ad group lookup"))
+    }
+
+    /// Audiences by ID in one call
+    async fn fetch_audiences_by_id(
+        &self,
+        _audience_ids: &[String],
+    ) -> Result<Vec<AudienceInfo>, ConnectorError> {
+        Err(ConnectorError::NotSupported("audience lookup"))
+    }
+
+    /// Change the daily budget of a campaign
+    async fn mutate_budget(&self, _campaign_id: &CampaignId, _daily_budget: f64) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("budget changes"))
+    }
+
+    /// Ads of several ad groups in one call, with their creatives
+    async fn fetch_ads(&self, _ad_group_ids: &[AdGroupId]) -> Result<Vec<Ad>, ConnectorError> {
+        Err(ConnectorError::NotSupported("ad lookup"))
+    }
+
+    /// Rename a campaign
+    async fn rename_campaign(&self, _campaign_id: &CampaignId, _name: &str) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign renames"))
+    }
+
+    /// Create a campaign with its ad groups, returning the new campaign ID
+    async fn create_campaign(&self, _campaign: &CampaignSpec) -> Result<CampaignId, ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign creation"))
+    }
+
+    /// Create a campaign from a cross-platform template, returning its ID
+    async fn launch_campaign(&self, _payload: &LaunchPayload) -> Result<CampaignId, ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign launch"))
+    }
+
+    /// Create an ad group under an existing campaign, returning its ID
+    async fn create_ad_group(
+        &self,
+        _campaign_id: &CampaignId,
+        _ad_group: &AdGroupSpec,
+    ) -> Result<AdGroupId, ConnectorError> {
+        Err(ConnectorError::NotSupported("ad group creation"))
+    }
+
+    /// Replace name, status and audiences of an existing ad group
+    async fn update_ad_group(&self, _ad_group: &AdGroupSpec) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("ad group updates"))
+    }
+
+    /// Normalized targeting of several campaigns, keyed by campaign ID
+    async fn fetch_targeting(
+        &self,
+        _campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Targeting>, ConnectorError> {
+        Err(ConnectorError::NotSupported("targeting lookup"))
+    }
+
+    /// Have the gateway refresh the platform's OAuth token
+    async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("token refresh"))
+    }
+
+    /// Normalized bidding of several campaigns, keyed by campaign ID
+    async fn fetch_bidding(
+        &self,
+        _campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Bidding>, ConnectorError> {
+        Err(ConnectorError::NotSupported("bidding lookup"))
+    }
+
+    /// Switch the bid strategy of a campaign
+    async fn mutate_bidding(
+        &self,
+        _campaign_id: &CampaignId,
+        _change: &BiddingChange,
+    ) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("bidding changes"))
+    }
+
+    /// Quality diagnostics of several campaigns, keyed by campaign ID
+    async fn fetch_quality(
+        &self,
+        _campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, QualityDiagnostics>, ConnectorError> {
+        Err(ConnectorError::NotSupported("quality diagnostics"))
+    }
+
+    /// Impression share and auction competition of several campaigns,
+    /// keyed by campaign ID
+    async fn fetch_auction_insights(
+        &self,
+        _campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, AuctionInsights>, ConnectorError> {
+        Err(ConnectorError::NotSupported("auction insights"))
+    }
+
+    /// Metrics of a campaign split by `dimension`, segments normalized
+    async fn fetch_breakdown(
+        &self,
+        _campaign_id: &CampaignId,
+        _dimension: Dimension,
+    ) -> Result<Vec<SegmentRow>, ConnectorError> {
+        Err(ConnectorError::NotSupported("performance breakdowns"))
+    }
+
+    /// Metrics of a shopping campaign per advertised product, IDs normalized
+    async fn fetch_products(
+        &self,
+        _campaign_id: &CampaignId,
+    ) -> Result<Vec<ProductRow>, ConnectorError> {
+        Err(ConnectorError::NotSupported("product metrics"))
+    }
+
+    /// Attach the label `name` to a campaign, creating it where needed
+    async fn add_label(&self, _campaign_id: &CampaignId, _name: &str) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign labels"))
+    }
+
+    /// Detach the label `name` from a campaign
+    async fn remove_label(&self, _campaign_id: &CampaignId, _name: &str) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign labels"))
+    }
+
+    /// A campaign as the platform returned it, before normalization
+    async fn fetch_raw_campaign(
+        &self,
+        _campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        Err(ConnectorError::NotSupported("raw campaign payloads"))
+    }
+
+    /// Campaigns created, modified or removed since `since`, from the
+    /// platform's change history; see `delta_sync`
+    async fn fetch_changed_campaigns(
+        &self,
+        _since: DateTime<Utc>,
+    ) -> Result<Vec<Campaign>, ConnectorError> {
+        Err(ConnectorError::NotSupported("incremental campaign fetches"))
+    }
+
+    /// Accounts reachable by the platform credentials, with their manager
+    /// or business; see `accounts`
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        Err(ConnectorError::NotSupported("account hierarchies"))
+    }
+
+    /// Conversions of a campaign per conversion action; see `conversions`
+    async fn fetch_conversion_actions(
+        &self,
+        _campaign_id: &CampaignId,
+    ) -> Result<Vec<ConversionActionRow>, ConnectorError> {
+        Err(ConnectorError::NotSupported("conversion action reports"))
+    }
+
+    /// Negative keywords, excluded audiences and excluded placements of a
+    /// campaign; see `negatives`
+    async fn fetch_exclusions(
+        &self,
+        _campaign_id: &CampaignId,
+    ) -> Result<Vec<Exclusion>, ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign exclusions"))
+    }
+
+    /// Add and remove exclusions set on a campaign
+    async fn update_exclusions(
+        &self,
+        _campaign_id: &CampaignId,
+        _update: &ExclusionUpdate,
+    ) -> Result<(), ConnectorError> {
+        Err(ConnectorError::NotSupported("campaign exclusions"))
+    }
+}
+
+/// Connectors registered for the running instance
+#[derive(Clone, Default)]
+pub struct ConnectorRegistry {
+    connectors: Vec<Arc<dyn PlatformConnector>>,
+}
+
+impl ConnectorRegistry {
+    /// Register a connector, replacing any connector for the same platform
+    pub fn register(&mut self, connector: Arc<dyn PlatformConnector>) {
+        self.connectors.retain(|c| c.platform() != connector.platform());
+        self.connectors.push(connector);
+    }
+
+    pub fn get(&self, platform: &Platform) -> Option<Arc<dyn PlatformConnector>> {
+        self.connectors
+            .iter()
+            .find(|c| &c.platform() == platform)
+            .cloned()
+    }
+
+    /// Wrap every connector so calls use the tenant's credentials, and
+    /// expired tokens are refreshed and the call retried once
+    pub fn with_reauth(
+        self,
+        health: Arc<CredentialHealth>,
+        credentials: Arc<CredentialManager>,
+    ) -> Self {
+        Self {
+            connectors: self
+                .connectors
+                .into_iter()
+                .map(|c| {
+                    Arc::new(ReauthConnector::new(c, health.clone(), credentials.clone()))
+                        as Arc<dyn PlatformConnector>
+                })
+                .collect(),
+        }
+    }
+
+    /// Hold a slot of `limiter` for every connector operation
+    pub fn with_concurrency_limit(self, limiter: Arc<ConcurrencyLimiter>) -> Self {
+        Self {
+            connectors: self
+                .connectors
+                .into_iter()
+                .map(|inner| {
+                    Arc::new(LimitedConnector {
+                        inner,
+                        limiter: limiter.clone(),
+                    }) as Arc<dyn PlatformConnector>
+                })
+                .collect(),
+        }
+    }
+
+    /// Count every connector operation against the platform quotas
+    pub fn with_quota(self, quota: Arc<QuotaTracker>) -> Self {
+        Self {
+            connectors: self
+                .connectors
+                .into_iter()
+                .map(|inner| {
+                    Arc::new(MeteredConnector {
+                        inner,
+                        quota: quota.clone(),
+                    }) as Arc<dyn PlatformConnector>
+                })
+                .collect(),
+        }
+    }
+
+    /// Read every connector through the shared entity cache
+    pub fn with_cache(self, cache: Arc<EntityCache>) -> Self {
+        Self {
+            connectors: self
+                .connectors
+                .into_iter()
+                .map(|inner| {
+                    Arc::new(CachingConnector {
+                        inner,
+                        cache: cache.clone(),
+                    }) as Arc<dyn PlatformConnector>
+                })
+                .collect(),
+        }
+    }
+
+    pub fn all(&self) -> &[Arc<dyn PlatformConnector>] {
+        &self.connectors
+    }
+
+    /// Registry with the gateway connectors of every compiled-in platform
+    ///
+    /// `http_client` must already carry the gateway's `X-Service-Auth` header.
+    /// Google and Meta responses report their usage and rate limits to `quota`,
+    /// and list elements they could not decode to `decode_errors`.
+    pub fn gateway(
+        http_client: reqwest::Client,
+        config: &GatewayConfig,
+        quota: Arc<QuotaTracker>,
+        decode_errors: Arc<DecodeErrors>,
+    ) -> Self {
+        let mut registry = Self::default();
+
+        for platform in [Platform::Google, Platform::Meta] {
+            registry.register(Arc::new(GatewayConnector {
+                gateway_url: config.url_for(&platform).to_string(),
+                platform,
+                http_client: http_client.clone(),
+                pagination: config.pagination.clone(),
+                quota: quota.clone(),
+                decode_errors: decode_errors.clone(),
+            }));
+        }
+
+        #[cfg(feature = "linkedin")]
+        registry.register(Arc::new(super::linkedin::LinkedInConnector {
+            http_client: http_client.clone(),
+            gateway_url: config.url_for(&Platform::LinkedIn).to_string(),
+            pagination: config.pagination.clone(),
+        }));
+
+        #[cfg(feature = "microsoft")]
+        registry.register(Arc::new(super::microsoft::MicrosoftConnector {
+            http_client: http_client.clone(),
+            gateway_url: config.url_for(&Platform::Microsoft).to_string(),
+            pagination: config.pagination.clone(),
+        }));
+
+        registry
+    }
+}
+
+/// Body of the gateway's status mutation route
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub status: CampaignStatus,
+}
+
+/// Body of the gateway's budget mutation route
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BudgetChange {
+    pub daily_budget: f64,
+}
+
+/// Body of the gateway's rename route
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NameChange {
+    pub name: String,
+}
+
+/// Response of the gateway's create routes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Created {
+    pub id: String,
+}
+
+/// Connector for platforms whose gateway routes already return the
+/// unified `Campaign` shape (Google Ads, Meta Ads)
+pub struct GatewayConnector {
+    pub platform: Platform,
+    pub http_client: reqwest::Client,
+    pub gateway_url: String,
+    pub pagination: PaginationConfig,
+    pub quota: Arc<QuotaTracker>,
+    /// Skipped list elements, see `lenient`
+    pub decode_errors: Arc<DecodeErrors>,
+}
+
+impl GatewayConnector {
+    fn url(&self, path: &str) -> String {
+        format!("{}/v1/{}/{}", self.gateway_url, self.platform.as_str(), path)
+    }
+
+    /// Request to the platform's `path`, carrying the tenant's access token
+    /// when the call is made with one, see `credentials`
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        credentials::authorize(self.http_client.request(method, self.url(path)))
+    }
+
+    /// Pass successful responses through; translate platform error bodies
+    /// relayed by the gateway into our error taxonomy
+    ///
+    /// Every response's usage headers and rate limits go to the quota tracker.
+    async fn check(&self, response: reqwest::Response) -> Result<reqwest::Response, ConnectorError> {
+        let status = response.status();
+        self.quota.observe(&self.platform, response.headers(), Utc::now());
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        let body = response.text().await?;
+
+        let error = platform_errors::translate(&self.platform, status.as_u16(), retry_after, &body);
+        if matches!(
+            error.code,
+            PlatformErrorCode::RateLimited | PlatformErrorCode::QuotaExhausted
+        ) {
+            self.quota.record_rate_limit(&self.platform, error.retry_after, Utc::now());
+        }
+
+        Err(error.into())
+    }
+
+    /// Label request of the platform: Google campaign labels, Meta ad labels
+    fn label_request(
+        &self,
+        method: reqwest::Method,
+        campaign_id: &CampaignId,
+        name: &str,
+    ) -> Result<reqwest::RequestBuilder, ConnectorError> {
+        Ok(match self.platform {
+            Platform::Google => self
+                .request(method, &format!("campaigns/{}/labels", campaign_id))
+                .json(&labels::GoogleCampaignLabel { label_name: name.to_string() }),
+            Platform::Meta => self
+                .request(method, &format!("campaigns/{}/adlabels", campaign_id))
+                .json(&labels::MetaAdLabels::named(name)),
+            #[allow(unreachable_patterns)]
+            _ => return Err(ConnectorError::NotSupported("campaign labels")),
+        })
+    }
+
+    /// Send a label request, mapping 404 to the campaign not being found
+    async fn send_label_request(
+        &self,
+        method: reqwest::Method,
+        campaign_id: &CampaignId,
+        name: &str,
+    ) -> Result<(), ConnectorError> {
+        let response = self.label_request(method, campaign_id, name)?.send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        self.check(response).await?;
+        Ok(())
+    }
+
+    /// Page size and position parameters of the platform's list routes
+    fn page_query(&self, cursor: Option<&str>) -> Vec<(&'static str, String)> {
+        let (size, position) = match self.platform {
+            Platform::Meta => ("limit", "after"),
+            _ => ("page_size", "page_token"),
+        };
+
+        let mut query = vec![(size, self.pagination.page_size.to_string())];
+        if let Some(cursor) = cursor {
+            query.push((position, cursor.to_string()));
+        }
+        query
+    }
+
+    /// GET a list route, following the platform's pagination to the last page
+    ///
+    /// Elements that do not decode as `T` are skipped and reported to
+    /// `decode_errors` instead of failing the list.
+    async fn get_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Vec<T>, ConnectorError> {
+        pagination::collect_pages(&self.platform, &self.pagination, |cursor| async move {
+            let response = self
+                .request(reqwest::Method::GET, path)
+                .query(query)
+                .query(&self.page_query(cursor.as_deref()))
+                .send()
+                .await?;
+
+            let page = self.check(response).await?.json::<GatewayPage<Lenient<T>>>().await?;
+            let Page { items, next } = Page::from(page);
+            let (items, skipped) = lenient::split(&self.platform, path, items);
+            self.decode_errors.record(skipped);
+            Ok(Page { items, next })
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for GatewayConnector {
+    fn platform(&self) -> Platform {
+        self.platform.clone()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        self.get_all("campaigns", &[]).await
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("campaigns/{}/metrics", campaign_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        Ok(self.check(response).await?.json::<CampaignMetrics>().await?)
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        if let CampaignStatus::Other(_) = status {
+            return Err(ConnectorError::NotSupported("setting an unmapped status"));
+        }
+
+        let response = self
+            .request(reqwest::Method::POST, &format!("campaigns/{}/status", campaign_id))
+            .json(&StatusChange { status })
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        self.check(response).await?;
+        Ok(())
+    }
+
+    async fn mutate_budget(&self, campaign_id: &CampaignId, daily_budget: f64) -> Result<(), ConnectorError> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("campaigns/{}/budget", campaign_id))
+            .json(&BudgetChange { daily_budget })
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        self.check(response).await?;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        let response = self
+            .request(reqwest::Method::GET, "health")
+            .send()
+            .await?;
+
+        self.check(response).await?;
+
+        Ok(())
+    }
+
+    async fn fetch_ad_groups(&self, campaign_ids: &[CampaignId]) -> Result<Vec<AdGroup>, ConnectorError> {
+        self.get_all("ad-groups", &[("campaign_ids", campaign_ids.join(","))]).await
+    }
+
+    async fn fetch_audiences_by_id(
+        &self,
+        audience_ids: &[String],
+    ) -> Result<Vec<AudienceInfo>, ConnectorError> {
+        self.get_all("audiences", &[("ids", audience_ids.join(","))]).await
+    }
+
+    async fn fetch_ads(&self, ad_group_ids: &[AdGroupId]) -> Result<Vec<Ad>, ConnectorError> {
+        self.get_all("ads", &[("ad_group_ids", ad_group_ids.join(","))]).await
+    }
+
+    async fn rename_campaign(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("campaigns/{}/name", campaign_id))
+            .json(&NameChange { name: name.to_string() })
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        self.check(response).await?;
+        Ok(())
+    }
+
+    async fn create_campaign(&self, campaign: &CampaignSpec) -> Result<CampaignId, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::POST, "campaigns")
+            .json(campaign)
+            .send()
+            .await?;
+
+        Ok(self.check(response).await?.json::<Created>().await?.id.into())
+    }
+
+    async fn launch_campaign(&self, payload: &LaunchPayload) -> Result<CampaignId, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::POST, "campaigns")
+            .json(payload)
+            .send()
+            .await?;
+
+        Ok(self.check(response).await?.json::<Created>().await?.id.into())
+    }
+
+    async fn create_ad_group(
+        &self,
+        campaign_id: &CampaignId,
+        ad_group: &AdGroupSpec,
+    ) -> Result<AdGroupId, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("campaigns/{}/ad-groups", campaign_id))
+            .json(ad_group)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        Ok(self.check(response).await?.json::<Created>().await?.id.into())
+    }
+
+    async fn update_ad_group(&self, ad_group: &AdGroupSpec) -> Result<(), ConnectorError> {
+        let id = ad_group
+            .id
+            .as_deref()
+            .ok_or(ConnectorError::NotSupported("ad group updates without an ID"))?;
+
+        let response = self
+            .request(reqwest::Method::PUT, &format!("ad-groups/{}", id))
+            .json(ad_group)
+            .send()
+            .await?;
+
+        self.check(response).await?;
+
+        Ok(())
+    }
+
+    async fn fetch_targeting(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Targeting>, ConnectorError> {
+        let query = [("campaign_ids", campaign_ids.join(","))];
+
+        // The gateway passes each platform's targeting through as is
+        let targeting = match self.platform {
+            Platform::Google => self
+                .get_all::<GoogleTargeting>("targeting", &query)
+                .await?
+                .into_iter()
+                .map(|raw| (raw.campaign_id.clone(), targeting::normalize_google(&raw)))
+                .collect(),
+            Platform::Meta => self
+                .get_all::<MetaTargeting>("targeting", &query)
+                .await?
+                .into_iter()
+                .map(|raw| (raw.campaign_id, targeting::normalize_meta(&raw.targeting)))
+                .collect(),
+            #[allow(unreachable_patterns)]
+            _ => return Err(ConnectorError::NotSupported("targeting lookup")),
+        };
+
+        Ok(targeting)
+    }
+
+    async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+        let response = self
+            .request(reqwest::Method::POST, "auth/refresh")
+            .send()
+            .await?;
+
+        self.check(response).await?;
+
+        Ok(())
+    }
+
+    async fn fetch_bidding(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Bidding>, ConnectorError> {
+        let query = [("campaign_ids", campaign_ids.join(","))];
+
+        // Like targeting, bidding settings are passed through as is
+        let bidding = match self.platform {
+            Platform::Google => self
+                .get_all::<GoogleBidding>("bidding", &query)
+                .await?
+                .into_iter()
+                .map(|raw| (raw.campaign_id, bidding::normalize_google(&raw.settings)))
+                .collect(),
+            Platform::Meta => self
+                .get_all::<MetaBidding>("bidding", &query)
+                .await?
+                .into_iter()
+                .map(|raw| (raw.campaign_id, bidding::normalize_meta(&raw.settings)))
+                .collect(),
+            #[allow(unreachable_patterns)]
+            _ => return Err(ConnectorError::NotSupported("bidding lookup")),
+        };
+
+        Ok(bidding)
+    }
+
+    async fn mutate_bidding(
+        &self,
+        campaign_id: &CampaignId,
+        change: &BiddingChange,
+    ) -> Result<(), ConnectorError> {
+        let unsupported = || ConnectorError::NotSupported("this bid strategy");
+
+        let request =
+            self.request(reqwest::Method::POST, &format!("campaigns/{}/bidding", campaign_id));
+        let request = match self.platform {
+            Platform::Google => {
+                request.json(&bidding::google_settings(change).ok_or_else(unsupported)?)
+            },
+            Platform::Meta => {
+                request.json(&bidding::meta_settings(change).ok_or_else(unsupported)?)
+            },
+            #[allow(unreachable_patterns)]
+            _ => return Err(ConnectorError::NotSupported("bidding changes")),
+        };
+        let response = request.send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        self.check(response).await?;
+        Ok(())
+    }
+
+    async fn fetch_quality(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, QualityDiagnostics>, ConnectorError> {
+        let query = [("campaign_ids", campaign_ids.join(","))];
+
+        // Google reports quality per keyword, Meta per ad
+        let signals: Vec<QualitySignal> = match self.platform {
+            Platform::Google => self
+                .get_all::<GoogleKeywordQuality>("quality", &query)
+                .await?
+                .iter()
+                .map(quality::normalize_google)
+                .collect(),
+            Platform::Meta => self
+                .get_all::<MetaAdQuality>("quality", &query)
+                .await?
+                .iter()
+                .map(quality::normalize_meta)
+                .collect(),
+            #[allow(unreachable_patterns)]
+            _ => return Err(ConnectorError::NotSupported("quality diagnostics")),
+        };
+
+        Ok(quality::diagnostics(&signals))
+    }
+
+    async fn fetch_auction_insights(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, AuctionInsights>, ConnectorError> {
+        let query = [("campaign_ids", campaign_ids.join(","))];
+
+        let insights = match self.platform {
+            Platform::Google => self
+                .get_all::<GoogleAuctionInsight>("auction_insights", &query)
+                .await?
+                .into_iter()
+                .map(auction::normalize_google)
+                .collect(),
+            Platform::Meta => self
+                .get_all::<MetaAuctionInsight>("auction_insights", &query)
+                .await?
+                .into_iter()
+                .map(auction::normalize_meta)
+                .collect(),
+            #[allow(unreachable_patterns)]
+            _ => return Err(ConnectorError::NotSupported("auction insights")),
+        };
+
+        Ok(insights)
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        campaign_id: &CampaignId,
+        dimension: Dimension,
+    ) -> Result<Vec<SegmentRow>, ConnectorError> {
+        let query = [("segment", dimension.platform_segment(&self.platform).to_string())];
+        let mut rows: Vec<SegmentRow> = self
+            .get_all(&format!("campaigns/{}/breakdowns", campaign_id), &query)
+            .await?;
+
+        for row in &mut rows {
+            row.segment = breakdowns::normalize_segment(&self.platform, dimension, &row.segment);
+        }
+        Ok(rows)
+    }
+
+    async fn fetch_products(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ProductRow>, ConnectorError> {
+        let mut rows: Vec<ProductRow> = self
+            .get_all(&format!("campaigns/{}/products", campaign_id), &[])
+            .await?;
+
+        for row in &mut rows {
+            row.item_id = products::normalize_product_id(&row.item_id);
+        }
+        Ok(rows)
+    }
+
+    async fn add_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.send_label_request(reqwest::Method::POST, campaign_id, name).await
+    }
+
+    async fn remove_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.send_label_request(reqwest::Method::DELETE, campaign_id, name).await
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        // The gateway relays the platform's own response on this route
+        let response = self
+            .request(reqwest::Method::GET, &format!("campaigns/{}/raw", campaign_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        Ok(self.check(response).await?.json().await?)
+    }
+
+    async fn fetch_changed_campaigns(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Campaign>, ConnectorError> {
+        match self.platform {
+            // The gateway reads Google Ads' change status, campaign budgets
+            // included, and returns the changed campaigns
+            Platform::Google => {
+                let since = since.to_rfc3339_opts(SecondsFormat::Secs, true);
+                self.get_all("campaigns/changes", &[("since", since)]).await
+            }
+            Platform::Meta => {
+                let filtering = serde_json::json!([{
+                    "field": "updated_time",
+                    "operator": "GREATER_THAN",
+                    "value": since.timestamp(),
+                }]);
+                self.get_all("campaigns", &[("filtering", filtering.to_string())]).await
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(ConnectorError::NotSupported("incremental campaign fetches")),
+        }
+    }
+
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        match self.platform {
+            // Customer clients of the login manager account, at every level
+            Platform::Google => {
+                let clients = self.get_all::<GoogleCustomerClient>("customer_clients", &[]).await?;
+                Ok(accounts::normalize_google(clients))
+            }
+            Platform::Meta => {
+                let fields = [("fields", "id,name,currency,business".to_string())];
+                let ad_accounts = self.get_all::<MetaAdAccount>("ad_accounts", &fields).await?;
+                Ok(accounts::normalize_meta(ad_accounts))
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(ConnectorError::NotSupported("account hierarchies")),
+        }
+    }
+
+    async fn fetch_conversion_actions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ConversionActionRow>, ConnectorError> {
+        // Google rows carry the conversion action category, Meta rows the
+        // action type; both are classified by `conversions::classify`
+        self.get_all(&format!("campaigns/{}/conversion_actions", campaign_id), &[]).await
+    }
+
+    async fn fetch_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<Exclusion>, ConnectorError> {
+        // Google campaign negatives and shared set members, Meta ad set
+        // excluded audiences and placements, flattened by the gateway
+        self.get_all(&format!("campaigns/{}/exclusions", campaign_id), &[]).await
+    }
+
+    async fn update_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+        update: &ExclusionUpdate,
+    ) -> Result<(), ConnectorError> {
+        let response = self
+            .request(reqwest::Method::PATCH, &format!("campaigns/{}/exclusions", campaign_id))
+            .json(update)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        self.check(response).await?;
+        Ok(())
+    }
+}
+
+/// In-memory connector for tests
+#[cfg(test)]
+pub struct MockConnector {
+    pub platform: Platform,
+    pub campaigns: Vec<Campaign>,
+    pub fail: bool,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl PlatformConnector for MockConnector {
+    fn platform(&self) -> Platform {
+        self.platform.clone()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        if self.fail {
+            return Err(ConnectorError::NotSupported("mock failure"));
+        }
+        Ok(self.campaigns.clone())
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        self.campaigns
+            .iter()
+            .find(|c| &c.id == campaign_id)
+            .map(|c| c.metrics.clone())
+            .ok_or_else(|| ConnectorError::NotFound(campaign_id.to_string()))
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        _status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        self.fetch_metrics(campaign_id).await.map(|_| ())
+    }
+
+    async fn mutate_budget(&self, campaign_id: &CampaignId, _daily_budget: f64) -> Result<(), ConnectorError> {
+        self.fetch_metrics(campaign_id).await.map(|_| ())
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        if self.fail {
+            return Err(ConnectorError::NotSupported("mock failure"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::aggregate_from;
+
+    fn mock(platform: Platform, budgets: &[f64], fail: bool) -> Arc<dyn PlatformConnector> {
+        let campaigns = budgets
+            .iter()
+            .enumerate()
+            .map(|(i, budget)| {
+                let mut c = Campaign::sample(&format!("{}{}", platform.as_str(), i), platform.clone());
+                c.daily_budget = *budget;
+                c
+            })
+            .collect();
+
+        Arc::new(MockConnector {
+            platform,
+            campaigns,
+            fail,
+        })
+    }
+
+    #[test]
+    fn test_register_replaces_same_platform() {
+        let mut registry = ConnectorRegistry::default();
+        registry.register(mock(Platform::Google, &[1.0], false));
+        registry.register(mock(Platform::Google, &[2.0, 3.0], false));
+        registry.register(mock(Platform::Meta, &[], false));
+
+        assert_eq!(registry.all().len(), 2);
+        assert!(registry.get(&Platform::Google).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_merges_and_sorts_connectors() {
+        let mut registry = ConnectorRegistry::default();
+        registry.register(mock(Platform::Google, &[50.0, 300.0], false));
+        registry.register(mock(Platform::Meta, &[100.0], false));
+
+        let budgets: Vec<f64> = aggregate_from(&registry)
+            .await
+            .iter()
+            .map(|c| c.daily_budget)
+            .collect();
+
+        assert_eq!(budgets, vec![300.0, 100.0, 50.0]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_connector_does_not_blank_others() {
+        let mut registry = ConnectorRegistry::default();
+        registry.register(mock(Platform::Google, &[50.0], true));
+        registry.register(mock(Platform::Meta, &[100.0], false));
+
+        let campaigns = aggregate_from(&registry).await;
+        assert_eq!(campaigns.len(), 1);
+        assert_eq!(campaigns[0].platform, Platform::Meta);
+    }
+}
@@ -0,0 +1,291 @@
+//! # Conversion Actions Example
+//!
+//! `metrics.conversions` lumps every conversion into one count: a campaign
+//! with 200 conversions may have sold nothing and collected 200 page-view
+//! events. `GET /campaigns/{platform}/{id}/conversions` splits the
+//! campaign's conversions by action, named in one taxonomy across
+//! platforms:
+//!
+//! - Google conversion actions are classified by their category
+//!   (`PURCHASE`, `SUBMIT_LEAD_FORM`, `ADD_TO_CART`, ...), falling back to
+//!   the action name when the category is missing;
+//! - Meta action types are classified by their event name, whatever the
+//!   prefix (`offsite_conversion.fb_pixel_lead`, `onsite_conversion.lead_grouped`,
+//!   `omni_purchase`);
+//! - actions landing on the same category are combined, and each category
+//!   lists the platform actions it came from;
+//! - actions matching no category are reported as `other`, never dropped.
+//!
+//! Platforms without a conversion action report are answered from the
+//! per-action totals of the campaign's metrics.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{find_global_campaign, AppState, Platform};
+use super::connectors::ConnectorError;
+use super::error::ApiError;
+use super::finite::safe_div;
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::money::Currency;
+
+/// Shared taxonomy of conversion actions
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionCategory {
+    Purchase,
+    Lead,
+    AddToCart,
+    InitiateCheckout,
+    CompleteRegistration,
+    Contact,
+    AppInstall,
+    ViewContent,
+    Other,
+}
+
+/// Google conversion action categories
+const GOOGLE_CATEGORIES: &[(&str, ConversionCategory)] = &[
+    ("PURCHASE", ConversionCategory::Purchase),
+    ("STORE_SALE", ConversionCategory::Purchase),
+    ("SUBMIT_LEAD_FORM", ConversionCategory::Lead),
+    ("IMPORTED_LEAD", ConversionCategory::Lead),
+    ("QUALIFIED_LEAD", ConversionCategory::Lead),
+    ("CONVERTED_LEAD", ConversionCategory::Lead),
+    ("REQUEST_QUOTE", ConversionCategory::Lead),
+    ("BOOK_APPOINTMENT", ConversionCategory::Lead),
+    ("ADD_TO_CART", ConversionCategory::AddToCart),
+    ("BEGIN_CHECKOUT", ConversionCategory::InitiateCheckout),
+    ("SIGNUP", ConversionCategory::CompleteRegistration),
+    ("CONTACT", ConversionCategory::Contact),
+    ("PHONE_CALL_LEAD", ConversionCategory::Contact),
+    ("GET_DIRECTIONS", ConversionCategory::Contact),
+    ("DOWNLOAD", ConversionCategory::AppInstall),
+    ("PAGE_VIEW", ConversionCategory::ViewContent),
+];
+
+/// Event names of Meta action types, once their prefix is removed
+const META_EVENTS: &[(&str, ConversionCategory)] = &[
+    ("purchase", ConversionCategory::Purchase),
+    ("lead", ConversionCategory::Lead),
+    ("lead_grouped", ConversionCategory::Lead),
+    ("leadgen_grouped", ConversionCategory::Lead),
+    ("add_to_cart", ConversionCategory::AddToCart),
+    ("initiate_checkout", ConversionCategory::InitiateCheckout),
+    ("complete_registration", ConversionCategory::CompleteRegistration),
+    ("contact", ConversionCategory::Contact),
+    ("messaging_conversation_started_7d", ConversionCategory::Contact),
+    ("mobile_app_install", ConversionCategory::AppInstall),
+    ("app_install", ConversionCategory::AppInstall),
+    ("view_content", ConversionCategory::ViewContent),
+];
+
+/// Prefixes of Meta action types in front of the event name
+const META_PREFIXES: &[&str] = &[
+    "offsite_conversion.fb_pixel_",
+    "offsite_conversion.",
+    "onsite_conversion.",
+    "app_custom_event.fb_mobile_",
+    "omni_",
+];
+
+/// Category of a platform action; Google passes its action category, when
+/// reported, as `category`
+pub fn classify(platform: &Platform, action: &str, category: Option<&str>) -> ConversionCategory {
+    let lookup = |table: &[(&str, ConversionCategory)], key: &str| {
+        table.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, c)| *c)
+    };
+
+    match platform {
+        Platform::Meta => {
+            let event = META_PREFIXES
+                .iter()
+                .find_map(|prefix| action.strip_prefix(prefix))
+                .unwrap_or(action);
+            lookup(META_EVENTS, event).unwrap_or(ConversionCategory::Other)
+        }
+        _ => category
+            .and_then(|category| lookup(GOOGLE_CATEGORIES, category))
+            .or_else(|| classify_name(action))
+            .unwrap_or(ConversionCategory::Other),
+    }
+}
+
+/// Category guessed from an action's display name, e.g. `Lead form - site`
+fn classify_name(name: &str) -> Option<ConversionCategory> {
+    let name = name.to_lowercase();
+    let keywords = [
+        ("purchase", ConversionCategory::Purchase),
+        ("compra", ConversionCategory::Purchase),
+        ("lead", ConversionCategory::Lead),
+        ("cart", ConversionCategory::AddToCart),
+        ("carrinho", ConversionCategory::AddToCart),
+        ("checkout", ConversionCategory::InitiateCheckout),
+        ("signup", ConversionCategory::CompleteRegistration),
+        ("cadastro", ConversionCategory::CompleteRegistration),
+        ("whatsapp", ConversionCategory::Contact),
+        ("contact", ConversionCategory::Contact),
+        ("contato", ConversionCategory::Contact),
+    ];
+    keywords.iter().find(|(keyword, _)| name.contains(keyword)).map(|(_, c)| *c)
+}
+
+/// Conversion action row as returned by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversionActionRow {
+    /// Google conversion action name, Meta action type
+    pub action: String,
+    /// Google conversion action category
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Fractional under data-driven attribution
+    pub conversions: f64,
+    #[serde(default)]
+    pub value: f64,
+}
+
+/// Conversions of one category
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversionCategoryMetrics {
+    pub category: ConversionCategory,
+    pub conversions: f64,
+    pub value: f64,
+    /// `None` without conversions
+    pub value_per_conversion: Option<f64>,
+    /// Share of the campaign's conversions, from 0 to 1
+    pub share_of_conversions: f64,
+    /// Platform actions counted in this category
+    pub actions: Vec<String>,
+}
+
+/// Response of `GET /campaigns/{platform}/{id}/conversions`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversionBreakdown {
+    pub campaign_id: CampaignId,
+    pub platform: Platform,
+    pub currency: Currency,
+    pub conversions: f64,
+    pub value: f64,
+    /// Most conversions first
+    pub categories: Vec<ConversionCategoryMetrics>,
+}
+
+/// Combine `rows` by category
+pub fn build_categories(
+    platform: &Platform,
+    rows: &[ConversionActionRow],
+) -> Vec<ConversionCategoryMetrics> {
+    let mut by_category: BTreeMap<ConversionCategory, ConversionCategoryMetrics> = BTreeMap::new();
+    for row in rows {
+        let category = classify(platform, &row.action, row.category.as_deref());
+        let entry = by_category.entry(category).or_insert_with(|| ConversionCategoryMetrics {
+            category,
+            conversions: 0.0,
+            value: 0.0,
+            value_per_conversion: None,
+            share_of_conversions: 0.0,
+            actions: Vec::new(),
+        });
+        entry.conversions += row.conversions;
+        entry.value += row.value;
+        if !entry.actions.contains(&row.action) {
+            entry.actions.push(row.action.clone());
+        }
+    }
+
+    let total: f64 = by_category.values().map(|c| c.conversions).sum();
+    let mut categories: Vec<ConversionCategoryMetrics> = by_category.into_values().collect();
+    for category in &mut categories {
+        category.value_per_conversion = safe_div(category.value, category.conversions);
+        category.share_of_conversions = safe_div(category.conversions, total).unwrap_or(0.0);
+    }
+    categories.sort_by(|a, b| b.conversions.total_cmp(&a.conversions));
+    categories
+}
+
+/// GET /campaigns/{platform}/{id}/conversions - Campaign conversions by action category
+pub async fn get_campaign_conversions(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<ConversionBreakdown>, ApiError> {
+    let campaign = find_global_campaign(&state, &GlobalCampaignId::new(platform, id.clone()))
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+    let connector = state
+        .connectors
+        .get(&campaign.platform)
+        .ok_or_else(|| ApiError::NotSupported(campaign.platform.as_str().to_string()))?;
+
+    let rows = match connector.fetch_conversion_actions(&id).await {
+        Ok(rows) => rows,
+        Err(ConnectorError::NotSupported(_)) => campaign
+            .metrics
+            .conversion_actions
+            .iter()
+            .map(|a| ConversionActionRow {
+                action: a.action.clone(),
+                category: None,
+                conversions: a.conversions,
+                value: a.value,
+            })
+            .collect(),
+        Err(e) => return Err(e.into()),
+    };
+    let categories = build_categories(&campaign.platform, &rows);
+
+    Ok(Json(ConversionBreakdown {
+        platform: campaign.platform.clone(),
+        currency: campaign.currency(),
+        conversions: categories.iter().map(|c| c.conversions).sum(),
+        value: categories.iter().map(|c| c.value).sum(),
+        categories,
+        campaign_id: id,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_actions_share_one_taxonomy() {
+        let (google, meta) = (Platform::Google, Platform::Meta);
+        let cases = [
+            (&google, "Compra - site", Some("PURCHASE"), ConversionCategory::Purchase),
+            (&google, "Formulário - site", Some("SUBMIT_LEAD_FORM"), ConversionCategory::Lead),
+            (&google, "Clique WhatsApp", None, ConversionCategory::Contact),
+            (&google, "Scroll 75%", Some("ENGAGEMENT"), ConversionCategory::Other),
+            (&meta, "offsite_conversion.fb_pixel_lead", None, ConversionCategory::Lead),
+            (&meta, "onsite_conversion.lead_grouped", None, ConversionCategory::Lead),
+            (&meta, "omni_add_to_cart", None, ConversionCategory::AddToCart),
+            (&meta, "link_click", None, ConversionCategory::Other),
+        ];
+        for (platform, action, category, expected) in cases {
+            assert_eq!(classify(platform, action, category), expected, "{}", action);
+        }
+
+        let row = |action: &str, conversions, value| ConversionActionRow {
+            action: action.to_string(),
+            category: None,
+            conversions,
+            value,
+        };
+        let categories = build_categories(
+            &meta,
+            &[
+                row("offsite_conversion.fb_pixel_lead", 30.0, 0.0),
+                row("onsite_conversion.lead_grouped", 10.0, 0.0),
+                row("offsite_conversion.fb_pixel_purchase", 10.0, 5000.0),
+            ],
+        );
+        assert_eq!(categories[0].category, ConversionCategory::Lead);
+        assert_eq!((categories[0].conversions, categories[0].share_of_conversions), (40.0, 0.8));
+        assert_eq!(categories[0].actions.len(), 2);
+        assert_eq!(categories[1].value_per_conversion, Some(500.0));
+    }
+}
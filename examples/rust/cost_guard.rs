@@ -0,0 +1,455 @@
+//! # Cost Guard Example
+//!
+//! A bid strategy gone wrong can spend a day's budget in an hour, and the
+//! platforms only cap spend at twice the daily budget. With
+//! `cost_guard.enabled`, every sync measures each enabled campaign's hourly
+//! spend since the previous sync and trips the guard when it exceeds
+//! `cost_guard.multiplier` times the expected pace, the daily budget's
+//! hourly share:
+//!
+//! - spend is measured over at least `cost_guard.min_window_minutes`, and
+//!   windows spending less than `cost_guard.min_spend` never trip;
+//! - the campaign is paused through the bulk path, as the actor
+//!   `cost-guard` in the campaign's tenant, so change freezes apply and
+//!   the pause is recorded in the audit log;
+//! - a critical alert goes out through the tenant's notifier, or
+//!   `cost_guard.notifier`;
+//! - with `pause = false` the guard only alerts.
+//!
+//! Tenants (`cost_guard.tenants`) own the campaigns of their
+//! `account_ids` and may override the multiplier, pausing and notifier.
+//! `GET /cost-guard/trips` lists what tripped, most recent first, and
+//! `POST /cost-guard/trips/{id}/re-enable` enables the campaign again in
+//! one click, as the caller; measuring then starts over.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::alerts::{AlertEvent, AlertSeverity, NotifierConfig};
+use super::bulk::{execute_bulk, BulkOperation, BulkReport};
+use super::call_budget;
+use super::campaign_aggregator::{AppState, Campaign, CampaignStatus, Platform};
+use super::config::{CostGuardConfig, TenantCostGuard};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Actor runaway campaigns are paused as
+pub const COST_GUARD_ACTOR: &str = "cost-guard";
+
+/// Campaign spend seen by a sync
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    /// Month-to-date spend
+    cost: f64,
+    at: DateTime<Utc>,
+}
+
+/// Campaign spending faster than its guard allows
+#[derive(Debug, Clone)]
+pub struct Runaway {
+    pub campaign: Campaign,
+    pub tenant: Option<String>,
+    pub hourly_spend: f64,
+    pub expected_hourly_spend: f64,
+    pub multiplier: f64,
+}
+
+/// Record of a tripped guard
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CostGuardTrip {
+    pub id: String,
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub campaign_name: String,
+    pub tenant: Option<String>,
+    pub hourly_spend: f64,
+    /// Daily budget's hourly share
+    pub expected_hourly_spend: f64,
+    pub multiplier: f64,
+    /// Whether the campaign was paused
+    pub paused: bool,
+    /// Why pausing failed
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+    pub re_enabled_at: Option<DateTime<Utc>>,
+    pub re_enabled_by: Option<String>,
+}
+
+impl CostGuardTrip {
+    /// Paused by the guard and not enabled again since
+    fn is_open(&self) -> bool {
+        self.paused && self.re_enabled_at.is_none()
+    }
+}
+
+/// Spend observations and the trips they caused
+pub struct CostGuard {
+    config: CostGuardConfig,
+    observations: RwLock<HashMap<GlobalCampaignId, Observation>>,
+    trips: RwLock<Vec<CostGuardTrip>>,
+}
+
+impl CostGuard {
+    pub fn new(config: CostGuardConfig) -> Self {
+        Self { config, observations: RwLock::default(), trips: RwLock::default() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Tenant owning `campaign`: one listing its account, else one listing
+    /// no account, by name
+    fn tenant_of(&self, campaign: &Campaign) -> Option<(&str, &TenantCostGuard)> {
+        let mut tenants: Vec<(&String, &TenantCostGuard)> = self.config.tenants.iter().collect();
+        tenants.sort_by(|a, b| a.0.cmp(b.0));
+        let listed = tenants.iter().find(|(_, tenant)| {
+            campaign.account_id.as_ref().is_some_and(|a| tenant.account_ids.contains(a))
+        });
+        listed
+            .or_else(|| tenants.iter().find(|(_, tenant)| tenant.account_ids.is_empty()))
+            .map(|(name, tenant)| (name.as_str(), *tenant))
+    }
+
+    /// Record the spend of `campaigns` and return those spending past
+    /// their guard
+    ///
+    /// Campaigns paused by a trip not yet re-enabled are left alone.
+    pub fn check(&self, campaigns: &[Campaign], now: DateTime<Utc>) -> Vec<Runaway> {
+        let open: Vec<GlobalCampaignId> = self
+            .trips
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|t| t.is_open())
+            .map(|t| GlobalCampaignId::new(t.platform.clone(), t.campaign_id.clone()))
+            .collect();
+        let min_window = chrono::Duration::minutes(self.config.min_window_minutes as i64);
+
+        let mut runaways = Vec::new();
+        let mut observations = self.observations.write().unwrap();
+        for campaign in campaigns {
+            let id = campaign.global_id();
+            let observed = Observation { cost: campaign.metrics.cost, at: now };
+            let Some(previous) = observations.get(&id).copied() else {
+                observations.insert(id, observed);
+                continue;
+            };
+            let elapsed = now - previous.at;
+            // Month-to-date spend drops when a month starts
+            let measurable = campaign.status == CampaignStatus::Enabled
+                && campaign.daily_budget > 0.0
+                && observed.cost >= previous.cost;
+            if measurable && elapsed < min_window {
+                continue;
+            }
+            observations.insert(id.clone(), observed);
+            if !measurable || open.contains(&id) {
+                continue;
+            }
+
+            let spend = observed.cost - previous.cost;
+            let tenant = self.tenant_of(campaign);
+            let multiplier =
+                tenant.and_then(|(_, t)| t.multiplier).unwrap_or(self.config.multiplier);
+            let hours = elapsed.num_seconds() as f64 / 3600.0;
+            let hourly_spend = spend / hours;
+            let expected_hourly_spend = campaign.daily_budget / 24.0;
+            if spend >= self.config.min_spend && hourly_spend > expected_hourly_spend * multiplier {
+                runaways.push(Runaway {
+                    campaign: campaign.clone(),
+                    tenant: tenant.map(|(name, _)| name.to_string()),
+                    hourly_spend,
+                    expected_hourly_spend,
+                    multiplier,
+                });
+            }
+        }
+        runaways
+    }
+
+    /// Whether runaways of `tenant` are paused
+    pub fn pauses(&self, tenant: Option<&str>) -> bool {
+        tenant
+            .and_then(|name| self.config.tenants.get(name))
+            .and_then(|t| t.pause)
+            .unwrap_or(self.config.pause)
+    }
+
+    /// Where alerts about runaways of `tenant` are delivered
+    pub fn notifier(&self, tenant: Option<&str>) -> Option<NotifierConfig> {
+        tenant
+            .and_then(|name| self.config.tenants.get(name))
+            .and_then(|t| t.notifier.clone())
+            .or_else(|| self.config.notifier.clone())
+    }
+
+    pub fn record(&self, trip: CostGuardTrip) {
+        self.trips.write().unwrap().push(trip);
+    }
+
+    /// Trips, most recent first
+    pub fn trips(&self) -> Vec<CostGuardTrip> {
+        let mut trips = self.trips.read().unwrap().clone();
+        trips.reverse();
+        trips
+    }
+
+    /// Trip `id`, which must still hold its campaign paused
+    pub fn open_trip(&self, id: &str) -> Result<CostGuardTrip, ApiError> {
+        let trips = self.trips.read().unwrap();
+        let trip = trips
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| ApiError::NotFound(format!("cost guard trip {}", id)))?;
+        if !trip.is_open() {
+            return Err(ApiError::Conflict(format!(
+                "cost guard trip {} holds no paused campaign",
+                id
+            )));
+        }
+        Ok(trip.clone())
+    }
+
+    /// Mark trip `id` re-enabled by `actor`; the campaign's spend is
+    /// measured from scratch
+    pub fn re_enabled(&self, id: &str, actor: &str, now: DateTime<Utc>) {
+        let mut trips = self.trips.write().unwrap();
+        let Some(trip) = trips.iter_mut().find(|t| t.id == id) else {
+            return;
+        };
+        trip.re_enabled_at = Some(now);
+        trip.re_enabled_by = Some(actor.to_string());
+        let campaign = GlobalCampaignId::new(trip.platform.clone(), trip.campaign_id.clone());
+        self.observations.write().unwrap().remove(&campaign);
+    }
+}
+
+fn alert(runaway: &Runaway, trip: &CostGuardTrip) -> AlertEvent {
+    let action = match (trip.paused, &trip.error) {
+        (true, _) => "paused".to_string(),
+        (false, Some(error)) => format!("could not be paused: {}", error),
+        (false, None) => "left running".to_string(),
+    };
+    AlertEvent {
+        rule_id: format!("cost-guard:{}", runaway.campaign.global_id()),
+        campaign_id: runaway.campaign.id.to_string(),
+        campaign_name: runaway.campaign.name.clone(),
+        message: format!(
+            "Campaign {} spent {:.2} an hour, over {}x its expected {:.2}, and was {}",
+            runaway.campaign.name,
+            runaway.hourly_spend,
+            runaway.multiplier,
+            runaway.expected_hourly_spend,
+            action
+        ),
+        severity: AlertSeverity::Critical,
+        triggered_at: trip.at,
+        delivered: false,
+        occurrences: 1,
+        last_triggered_at: None,
+        summary_sent: false,
+    }
+}
+
+/// Check the synced campaigns, pause the runaways and return the alerts
+/// to deliver, with where to deliver them
+///
+/// Called by the sync worker once the day's metrics are recorded.
+pub async fn run_cost_guard(
+    state: &AppState,
+    campaigns: &[Campaign],
+) -> Vec<(Option<NotifierConfig>, AlertEvent)> {
+    let guard = &state.cost_guard;
+    if !guard.is_enabled() {
+        return Vec::new();
+    }
+
+    let now = Utc::now();
+    let mut alerts = Vec::new();
+    for runaway in guard.check(campaigns, now) {
+        let tenant = runaway.tenant.as_deref();
+        let mut trip = CostGuardTrip {
+            id: uuid::Uuid::new_v4().to_string(),
+            platform: runaway.campaign.platform.clone(),
+            campaign_id: runaway.campaign.id.clone(),
+            campaign_name: runaway.campaign.name.clone(),
+            tenant: runaway.tenant.clone(),
+            hourly_spend: runaway.hourly_spend,
+            expected_hourly_spend: runaway.expected_hourly_spend,
+            multiplier: runaway.multiplier,
+            paused: false,
+            error: None,
+            at: now,
+            re_enabled_at: None,
+            re_enabled_by: None,
+        };
+
+        if guard.pauses(tenant) {
+            let operation = BulkOperation::SetStatus {
+                platform: trip.platform.clone(),
+                campaign_id: trip.campaign_id.clone(),
+                status: CampaignStatus::Paused,
+            };
+            let report = execute_bulk(
+                &state.connectors,
+                vec![operation],
+                &state.freezes,
+                &state.spend_caps,
+                &Actor::automation(COST_GUARD_ACTOR, runaway.tenant.clone()),
+                &state.audit,
+            )
+            .await;
+            if let Some(result) = report.results.into_iter().next() {
+                trip.paused = result.success;
+                trip.error = result.error;
+            }
+        }
+        tracing::error!(
+            platform = trip.platform.as_str(),
+            campaign_id = %trip.campaign_id,
+            hourly_spend = trip.hourly_spend,
+            paused = trip.paused,
+            "cost guard tripped"
+        );
+
+        alerts.push((guard.notifier(tenant), alert(&runaway, &trip)));
+        guard.record(trip);
+    }
+    alerts
+}
+
+/// GET /cost-guard/trips - Campaigns the guard tripped on, most recent first
+pub async fn list_cost_guard_trips(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CostGuardTrip>>, ApiError> {
+    Ok(Json(state.cost_guard.trips()))
+}
+
+/// POST /cost-guard/trips/{id}/re-enable - Enable a campaign the guard paused
+///
+/// The change runs as a batch of the caller and is recorded like one.
+pub async fn re_enable_campaign(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path(id): Path<String>,
+) -> Result<Json<BulkReport>, ApiError> {
+    let trip = state.cost_guard.open_trip(&id)?;
+    call_budget::charge(1)?;
+
+    let operation = BulkOperation::SetStatus {
+        platform: trip.platform,
+        campaign_id: trip.campaign_id,
+        status: CampaignStatus::Enabled,
+    };
+    let report = execute_bulk(
+        &state.connectors,
+        vec![operation],
+        &state.freezes,
+        &state.spend_caps,
+        &actor,
+        &state.audit,
+    )
+    .await;
+    if report.failed == 0 {
+        state.cost_guard.re_enabled(&id, &actor.id, Utc::now());
+    }
+
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        chrono::NaiveDate::from_ymd_opt(2026, 11, 27)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    /// Enabled, 240 a day: 10 an hour on pace
+    fn campaign(id: &str, account_id: &str, cost: f64) -> Campaign {
+        let mut campaign = Campaign::sample(id, Platform::Google);
+        campaign.status = CampaignStatus::Enabled;
+        campaign.daily_budget = 240.0;
+        campaign.metrics.cost = cost;
+        campaign.account_id = Some(account_id.into());
+        campaign
+    }
+
+    fn guard() -> CostGuard {
+        CostGuard::new(CostGuardConfig {
+            enabled: true,
+            tenants: HashMap::from([(
+                "acme".to_string(),
+                TenantCostGuard {
+                    account_ids: vec!["123".into()],
+                    multiplier: Some(6.0),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_campaigns_spending_past_their_multiple_trip() {
+        let guard = guard();
+        let first = [campaign("g1", "999", 100.0), campaign("g2", "123", 100.0)];
+        assert!(guard.check(&first, at(10, 0)).is_empty());
+
+        // Too soon to measure: the 10:00 observation is kept
+        let early = [campaign("g1", "999", 110.0), campaign("g2", "123", 110.0)];
+        assert!(guard.check(&early, at(10, 10)).is_empty());
+
+        // 50 in an hour: past 3x 10 for g1, within acme's 6x for g2
+        let later = [campaign("g1", "999", 150.0), campaign("g2", "123", 150.0)];
+        let runaways = guard.check(&later, at(11, 0));
+        assert_eq!(runaways.len(), 1);
+        assert_eq!(runaways[0].campaign.id, "g1");
+        assert_eq!(runaways[0].hourly_spend, 50.0);
+        assert_eq!(runaways[0].tenant, None);
+        assert!(guard.pauses(None));
+    }
+
+    #[test]
+    fn test_open_trips_are_left_alone_until_re_enabled() {
+        let guard = guard();
+        guard.check(&[campaign("g1", "999", 0.0)], at(10, 0));
+        let runaway = guard.check(&[campaign("g1", "999", 100.0)], at(11, 0)).remove(0);
+        guard.record(CostGuardTrip {
+            id: "t1".to_string(),
+            platform: Platform::Google,
+            campaign_id: runaway.campaign.id.clone(),
+            campaign_name: runaway.campaign.name.clone(),
+            tenant: None,
+            hourly_spend: runaway.hourly_spend,
+            expected_hourly_spend: runaway.expected_hourly_spend,
+            multiplier: runaway.multiplier,
+            paused: true,
+            error: None,
+            at: at(11, 0),
+            re_enabled_at: None,
+            re_enabled_by: None,
+        });
+
+        assert!(guard.check(&[campaign("g1", "999", 200.0)], at(12, 0)).is_empty());
+        assert!(guard.open_trip("t1").is_ok());
+
+        guard.re_enabled("t1", "ana", at(12, 5));
+        assert_eq!(guard.open_trip("t1").unwrap_err().code(), "conflict");
+        // Measuring starts over from the re-enable
+        assert!(guard.check(&[campaign("g1", "999", 300.0)], at(13, 0)).is_empty());
+        assert_eq!(guard.check(&[campaign("g1", "999", 400.0)], at(14, 0)).len(), 1);
+    }
+}
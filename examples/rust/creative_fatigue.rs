@@ -0,0 +1,275 @@
+//! # Creative Fatigue Example
+//!
+//! Detects ads whose CTR or conversion rate is decaying against their own
+//! baseline (the first week they delivered) and returns, per account, a
+//! ranked list of creatives that should be refreshed.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+use super::ids::{AccountId, CampaignId};
+
+/// Days used for both the baseline and the recent window
+pub const WINDOW_DAYS: usize = 7;
+
+/// Relative decline (in percent) that marks a creative as fatigued
+pub const FATIGUE_DECLINE_PERCENTAGE: f64 = 20.0;
+
+/// Impressions each window needs before the comparison is trusted
+pub const MIN_WINDOW_IMPRESSIONS: u64 = 1_000;
+
+/// One day of delivery for an ad, as reported by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdDailyMetrics {
+    pub ad_id: String,
+    pub ad_name: String,
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    pub platform: Platform,
+    pub date: NaiveDate,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+}
+
+/// Fatigue assessment for a single creative
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreativeFatigue {
+    pub ad_id: String,
+    pub ad_name: String,
+    pub campaign_id: CampaignId,
+    pub platform: Platform,
+    pub baseline_ctr: f64,
+    pub recent_ctr: f64,
+    /// Relative CTR decline in percent (positive means worse)
+    pub ctr_decline: f64,
+    pub baseline_cvr: f64,
+    pub recent_cvr: f64,
+    /// Relative conversion-rate decline in percent (positive means worse)
+    pub cvr_decline: f64,
+    pub recent_impressions: u64,
+}
+
+impl CreativeFatigue {
+    /// Ranking score: worst decline weighted by how much the ad still delivers
+    pub fn severity(&self) -> f64 {
+        self.ctr_decline.max(self.cvr_decline) * (self.recent_impressions as f64).ln_1p()
+    }
+}
+
+/// Ranked refresh list for one account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountFatigueReport {
+    pub account_id: AccountId,
+    pub creatives: Vec<CreativeFatigue>,
+}
+
+/// Query parameters for `GET /creatives/fatigue`
+#[derive(Debug, Deserialize)]
+pub struct FatigueQuery {
+    pub account_id: Option<AccountId>,
+}
+
+struct Totals {
+    impressions: u64,
+    clicks: u64,
+    conversions: u32,
+}
+
+impl Totals {
+    fn of(days: &[&AdDailyMetrics]) -> Self {
+        Self {
+            impressions: days.iter().map(|d| d.impressions).sum(),
+            clicks: days.iter().map(|d| d.clicks).sum(),
+            conversions: days.iter().map(|d| d.conversions).sum(),
+        }
+    }
+
+    fn ctr(&self) -> f64 {
+        if self.impressions == 0 {
+            0.0
+        } else {
+            self.clicks as f64 / self.impressions as f64 * 100.0
+        }
+    }
+
+    fn cvr(&self) -> f64 {
+        if self.clicks == 0 {
+            0.0
+        } else {
+            self.conversions as f64 / self.clicks as f64 * 100.0
+        }
+    }
+}
+
+fn decline(baseline: f64, recent: f64) -> f64 {
+    if baseline <= 0.0 {
+        0.0
+    } else {
+        (baseline - recent) / baseline * 100.0
+    }
+}
+
+/// Assess one ad's daily history, `None` if it is too young or healthy
+pub fn assess_creative(days: &[&AdDailyMetrics]) -> Option<CreativeFatigue> {
+    if days.len() < WINDOW_DAYS * 2 {
+        return None;
+    }
+
+    let mut days = days.to_vec();
+    days.sort_by_key(|d| d.date);
+
+    let baseline = Totals::of(&days[..WINDOW_DAYS]);
+    let recent = Totals::of(&days[days.len() - WINDOW_DAYS..]);
+
+    if baseline.impressions < MIN_WINDOW_IMPRESSIONS || recent.impressions < MIN_WINDOW_IMPRESSIONS {
+        return None;
+    }
+
+    let ctr_decline = decline(baseline.ctr(), recent.ctr());
+    let cvr_decline = decline(baseline.cvr(), recent.cvr());
+
+    if ctr_decline < FATIGUE_DECLINE_PERCENTAGE && cvr_decline < FATIGUE_DECLINE_PERCENTAGE {
+        return None;
+    }
+
+    let first = days[0];
+    Some(CreativeFatigue {
+        ad_id: first.ad_id.clone(),
+        ad_name: first.ad_name.clone(),
+        campaign_id: first.campaign_id.clone(),
+        platform: first.platform.clone(),
+        baseline_ctr: baseline.ctr(),
+        recent_ctr: recent.ctr(),
+        ctr_decline,
+        baseline_cvr: baseline.cvr(),
+        recent_cvr: recent.cvr(),
+        cvr_decline,
+        recent_impressions: recent.impressions,
+    })
+}
+
+/// Group daily rows by account and ad, returning ranked refresh lists
+pub fn fatigue_reports(rows: &[AdDailyMetrics]) -> Vec<AccountFatigueReport> {
+    let mut by_account: BTreeMap<&str, HashMap<&str, Vec<&AdDailyMetrics>>> = BTreeMap::new();
+
+    for row in rows {
+        by_account
+            .entry(row.account_id.as_str())
+            .or_default()
+            .entry(row.ad_id.as_str())
+            .or_default()
+            .push(row);
+    }
+
+    by_account
+        .into_iter()
+        .map(|(account_id, ads)| {
+            let mut creatives: Vec<CreativeFatigue> =
+                ads.values().filter_map(|days| assess_creative(days)).collect();
+
+            creatives.sort_by(|a, b| {
+                b.severity()
+                    .partial_cmp(&a.severity())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            AccountFatigueReport {
+                account_id: account_id.into(),
+                creatives,
+            }
+        })
+        .filter(|report| !report.creatives.is_empty())
+        .collect()
+}
+
+/// Fetch per-ad daily metrics for one platform from the gateway
+async fn fetch_ad_daily_metrics(
+    state: &AppState,
+    platform: &str,
+    days: usize,
+) -> Result<Vec<AdDailyMetrics>, reqwest::Error> {
+    let url = format!("{}/v1/{}/ads/daily?days={}", state.gateway_url, platform, days);
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.json::<Vec<AdDailyMetrics>>().await
+}
+
+/// GET /creatives/fatigue - Creatives to refresh, ranked per account
+pub async fn get_creative_fatigue(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FatigueQuery>,
+) -> Result<Json<Vec<AccountFatigueReport>>, ApiError> {
+    // Four weeks leaves room between the baseline and the recent window
+    let days = WINDOW_DAYS * 4;
+
+    let (google, meta) = tokio::join!(
+        fetch_ad_daily_metrics(&state, "google", days),
+        fetch_ad_daily_metrics(&state, "meta", days)
+    );
+
+    let mut rows = Vec::new();
+    rows.extend(google.unwrap_or_default());
+    rows.extend(meta.unwrap_or_default());
+
+    if let Some(account_id) = &query.account_id {
+        rows.retain(|row| &row.account_id == account_id);
+    }
+
+    Ok(Json(fatigue_reports(&rows)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(ad_id: &str, clicks_per_day: &[u64]) -> Vec<AdDailyMetrics> {
+        clicks_per_day
+            .iter()
+            .enumerate()
+            .map(|(i, clicks)| AdDailyMetrics {
+                ad_id: ad_id.to_string(),
+                ad_name: format!("Ad {}", ad_id),
+                campaign_id: "c1".into(),
+                account_id: "acc-1".into(),
+                platform: Platform::Meta,
+                date: NaiveDate::from_ymd_opt(2025, 11, i as u32 + 1).unwrap(),
+                impressions: 1_000,
+                clicks: *clicks,
+                conversions: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flags_declining_ctr_and_ranks_worst_first() {
+        let mut data = rows("healthy", &[20; 14]);
+        data.extend(rows("tired", &[[20u64; 7], [14; 7]].concat()));
+        data.extend(rows("exhausted", &[[20u64; 7], [8; 7]].concat()));
+
+        let reports = fatigue_reports(&data);
+        assert_eq!(reports.len(), 1);
+
+        let ids: Vec<&str> = reports[0].creatives.iter().map(|c| c.ad_id.as_str()).collect();
+        assert_eq!(ids, vec!["exhausted", "tired"]);
+        assert!((reports[0].creatives[1].ctr_decline - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_young_creatives_are_not_assessed() {
+        let data = rows("new", &[20, 20, 5]);
+        assert!(fatigue_reports(&data).is_empty());
+    }
+}
@@ -0,0 +1,849 @@
+//! # Platform Credentials Example
+//!
+//! By default the gateway holds the platform tokens and every instance
+//! reaches the platforms through it with its service token. A platform
+//! listed in `credentials.platforms` is instead reached with the calling
+//! tenant's own OAuth connection:
+//!
+//! - `PUT /connections/{platform}` stores the tenant's refresh token,
+//!   sealed with AES-256-GCM under `credentials.key`; it is only unsealed
+//!   to refresh, and the connections file (`credentials.path`) never
+//!   holds it readable;
+//! - access tokens are kept in memory and refreshed
+//!   `credentials.refresh_margin_secs` before they expire, by a background
+//!   task and on demand; the gateway gets them as `Authorization: Bearer`;
+//! - a refresh the platform rejects as revoked (`invalid_grant`) marks the
+//!   connection broken: the tenant's calls to the platform fail with
+//!   `auth_expired` until it is connected again.
+//!
+//! `GET /connections` shows the caller's connection to each platform.
+//! Requests are attributed to their `X-Tenant-Id`; work outside a request,
+//! like the sync worker, and tenants without a connection keep using the
+//! gateway's credentials.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use axum::{
+    extract::{Path, Request, State},
+    middleware::Next,
+    response::{Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::config::{ConfigError, CredentialsConfig, OAuthClientConfig};
+use super::connectors::ConnectorError;
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::platform_errors::{PlatformError, PlatformErrorCode};
+
+/// How often access tokens close to expiry are looked for
+pub const REFRESH_TICK: Duration = Duration::from_secs(60);
+
+/// Lifetime assumed when the token endpoint does not give one
+const DEFAULT_EXPIRES_IN_SECS: i64 = 3600;
+
+/// Leading bytes of a sealed refresh token
+const MAGIC: &[u8] = b"IBVITOK1";
+
+const NONCE_LEN: usize = 12;
+
+tokio::task_local! {
+    static TENANT: String;
+    static ACCESS_TOKEN: String;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("no OAuth client is configured for {0}")]
+    NotConfigured(&'static str),
+    #[error("{0} is not connected")]
+    NotConnected(&'static str),
+    #[error("the {platform} connection is broken and must be reconnected: {reason}")]
+    Broken { platform: &'static str, reason: String },
+    #[error("token refresh failed: {0}")]
+    Refresh(String),
+    #[error("token refresh failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("refresh token could not be sealed or unsealed")]
+    Seal,
+}
+
+impl CredentialError {
+    /// Error of a platform call that could not get an access token
+    pub fn into_connector_error(self, platform: &Platform) -> ConnectorError {
+        let upstream_code = match self {
+            CredentialError::Broken { .. } => "connection_broken",
+            _ => "token_refresh_failed",
+        };
+        ConnectorError::Platform(PlatformError {
+            platform: platform.clone(),
+            code: PlatformErrorCode::AuthExpired,
+            upstream_code: upstream_code.to_string(),
+            message: self.to_string(),
+            retry_after: None,
+        })
+    }
+}
+
+impl From<CredentialError> for ApiError {
+    fn from(e: CredentialError) -> Self {
+        match e {
+            CredentialError::NotConfigured(_) | CredentialError::Broken { .. } => {
+                ApiError::Validation(e.to_string())
+            }
+            CredentialError::NotConnected(platform) => {
+                ApiError::NotFound(format!("{} connection", platform))
+            }
+            CredentialError::Refresh(_) | CredentialError::Http(_) | CredentialError::Seal => {
+                ApiError::Internal(e.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStatus {
+    /// Access tokens are refreshed as needed
+    Connected,
+    /// The refresh token was revoked; the tenant must connect again
+    Broken,
+    /// Calls use the gateway's credentials
+    NotConnected,
+}
+
+/// Connection as written to the connections file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredConnection {
+    pub tenant: String,
+    pub platform: Platform,
+    /// Refresh token sealed under `credentials.key`, hex-encoded
+    sealed_refresh_token: String,
+    pub status: ConnectionStatus,
+    pub connected_at: DateTime<Utc>,
+    /// Why the connection broke
+    pub error: Option<String>,
+    pub broken_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+struct AccessToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+    refreshed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct Connection {
+    stored: StoredConnection,
+    /// Never persisted: a restart refreshes it
+    access: Option<AccessToken>,
+}
+
+/// Auth health of one platform for a tenant, as listed by `GET /connections`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConnectionHealth {
+    pub platform: Platform,
+    pub status: ConnectionStatus,
+    pub connected_at: Option<DateTime<Utc>>,
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+    pub access_token_expires_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub broken_at: Option<DateTime<Utc>>,
+}
+
+impl ConnectionHealth {
+    fn not_connected(platform: Platform) -> Self {
+        Self {
+            platform,
+            status: ConnectionStatus::NotConnected,
+            connected_at: None,
+            last_refreshed_at: None,
+            access_token_expires_at: None,
+            error: None,
+            broken_at: None,
+        }
+    }
+
+    fn of(connection: &Connection) -> Self {
+        let stored = &connection.stored;
+        Self {
+            platform: stored.platform.clone(),
+            status: stored.status,
+            connected_at: Some(stored.connected_at),
+            last_refreshed_at: connection.access.as_ref().map(|a| a.refreshed_at),
+            access_token_expires_at: connection.access.as_ref().map(|a| a.expires_at),
+            error: stored.error.clone(),
+            broken_at: stored.broken_at,
+        }
+    }
+}
+
+/// Successful answer of an OAuth token endpoint
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<i64>,
+    /// Set by platforms rotating refresh tokens
+    refresh_token: Option<String>,
+}
+
+/// Error answer of an OAuth token endpoint (RFC 6749, section 5.2)
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+type ConnectionKey = (String, Platform);
+
+type RefreshLock = Arc<tokio::sync::Mutex<()>>;
+
+/// Tenants' OAuth connections to the platforms with a configured client
+#[derive(Default)]
+pub struct CredentialManager {
+    /// Talks to the platforms' token endpoints, without the service token
+    http_client: reqwest::Client,
+    clients: HashMap<String, OAuthClientConfig>,
+    cipher: Option<Aes256Gcm>,
+    refresh_margin: Duration,
+    path: Option<PathBuf>,
+    connections: RwLock<HashMap<ConnectionKey, Connection>>,
+    /// One lock per connection: requests needing the same new token
+    /// trigger one refresh, other connections refresh meanwhile
+    refresh_locks: Mutex<HashMap<ConnectionKey, RefreshLock>>,
+}
+
+impl CredentialManager {
+    pub fn from_config(config: &CredentialsConfig) -> Result<Self, ConfigError> {
+        if !config.is_enabled() {
+            return Ok(Self::default());
+        }
+        let key = hex::decode(config.key.trim())
+            .ok()
+            .filter(|key| key.len() == 32)
+            .ok_or_else(|| ConfigError::invalid("credentials.key", "must be 64 hex characters"))?;
+
+        Ok(Self {
+            http_client: reqwest::Client::new(),
+            clients: config.platforms.clone(),
+            cipher: Some(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))),
+            refresh_margin: config.refresh_margin(),
+            path: config.path.clone(),
+            connections: RwLock::default(),
+            refresh_locks: Mutex::default(),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.clients.is_empty()
+    }
+
+    fn refresh_margin(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.refresh_margin).unwrap_or(chrono::Duration::zero())
+    }
+
+    /// Lock serializing the refreshes of `key`
+    fn refresh_lock(&self, key: &ConnectionKey) -> RefreshLock {
+        self.refresh_locks.lock().unwrap().entry(key.clone()).or_default().clone()
+    }
+
+    fn client(&self, platform: &Platform) -> Result<&OAuthClientConfig, CredentialError> {
+        self.clients.get(platform.as_str()).ok_or(CredentialError::NotConfigured(platform.as_str()))
+    }
+
+    fn seal(&self, token: &str) -> Result<String, CredentialError> {
+        let cipher = self.cipher.as_ref().ok_or(CredentialError::Seal)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext =
+            cipher.encrypt(&nonce, token.as_bytes()).map_err(|_| CredentialError::Seal)?;
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(hex::encode(bytes))
+    }
+
+    fn unseal(&self, sealed: &str) -> Result<String, CredentialError> {
+        let cipher = self.cipher.as_ref().ok_or(CredentialError::Seal)?;
+        let bytes = hex::decode(sealed).map_err(|_| CredentialError::Seal)?;
+        let sealed = bytes.strip_prefix(MAGIC).ok_or(CredentialError::Seal)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(CredentialError::Seal);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let token = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CredentialError::Seal)?;
+        String::from_utf8(token).map_err(|_| CredentialError::Seal)
+    }
+
+    /// Store `tenant`'s refresh token for `platform`, replacing any previous
+    /// connection, and check it by getting a first access token
+    pub async fn connect(
+        &self,
+        tenant: &str,
+        platform: &Platform,
+        refresh_token: &str,
+    ) -> Result<ConnectionHealth, CredentialError> {
+        let client = self.client(platform)?;
+        let key = (tenant.to_string(), platform.clone());
+        let lock = self.refresh_lock(&key);
+        let _guard = lock.lock().await;
+        let (access, rotated) = self.exchange(client, platform, refresh_token).await?;
+
+        let connection = Connection {
+            stored: StoredConnection {
+                tenant: tenant.to_string(),
+                platform: platform.clone(),
+                sealed_refresh_token: self.seal(rotated.as_deref().unwrap_or(refresh_token))?,
+                status: ConnectionStatus::Connected,
+                connected_at: Utc::now(),
+                error: None,
+                broken_at: None,
+            },
+            access: Some(access),
+        };
+        let health = ConnectionHealth::of(&connection);
+        self.connections.write().unwrap().insert(key, connection);
+        self.save();
+        Ok(health)
+    }
+
+    /// Forget `tenant`'s connection to `platform`
+    pub fn disconnect(&self, tenant: &str, platform: &Platform) -> Result<(), CredentialError> {
+        let removed =
+            self.connections.write().unwrap().remove(&(tenant.to_string(), platform.clone()));
+        if removed.is_none() {
+            return Err(CredentialError::NotConnected(platform.as_str()));
+        }
+        self.save();
+        Ok(())
+    }
+
+    /// Auth health of `tenant` on each of `platforms`
+    pub fn health(&self, tenant: &str, platforms: &[Platform]) -> Vec<ConnectionHealth> {
+        let connections = self.connections.read().unwrap();
+        platforms
+            .iter()
+            .map(|platform| match connections.get(&(tenant.to_string(), platform.clone())) {
+                Some(connection) => ConnectionHealth::of(connection),
+                None => ConnectionHealth::not_connected(platform.clone()),
+            })
+            .collect()
+    }
+
+    /// Current access token of `tenant` on `platform`, refreshed first when
+    /// it expires within the margin; `None` when the tenant has no
+    /// connection, so the gateway's credentials apply
+    pub async fn access_token(
+        &self,
+        tenant: &str,
+        platform: &Platform,
+    ) -> Result<Option<String>, CredentialError> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let key = (tenant.to_string(), platform.clone());
+        match self.usable_token(&key)? {
+            Some(Some(token)) => Ok(Some(token)),
+            Some(None) => {
+                let lock = self.refresh_lock(&key);
+                let _guard = lock.lock().await;
+                // Refreshed by another request while this one waited
+                if let Some(Some(token)) = self.usable_token(&key)? {
+                    return Ok(Some(token));
+                }
+                self.refresh_locked(&key).await.map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get a new access token for `tenant` on `platform`, e.g. after the
+    /// platform rejected the current one
+    pub async fn refresh(
+        &self,
+        tenant: &str,
+        platform: &Platform,
+    ) -> Result<String, CredentialError> {
+        let key = (tenant.to_string(), platform.clone());
+        let lock = self.refresh_lock(&key);
+        let _guard = lock.lock().await;
+        self.refresh_locked(&key).await
+    }
+
+    /// `None` without a connection, `Some(None)` when the access token must
+    /// be refreshed first
+    fn usable_token(&self, key: &ConnectionKey) -> Result<Option<Option<String>>, CredentialError> {
+        let connections = self.connections.read().unwrap();
+        let Some(connection) = connections.get(key) else {
+            return Ok(None);
+        };
+        if connection.stored.status == ConnectionStatus::Broken {
+            return Err(CredentialError::Broken {
+                platform: key.1.as_str(),
+                reason: connection.stored.error.clone().unwrap_or_default(),
+            });
+        }
+        let fresh = connection
+            .access
+            .as_ref()
+            .filter(|access| access.expires_at - self.refresh_margin() > Utc::now())
+            .map(|access| access.token.clone());
+        Ok(Some(fresh))
+    }
+
+    /// Refresh the access token of `key`; the caller holds its refresh lock
+    async fn refresh_locked(&self, key: &ConnectionKey) -> Result<String, CredentialError> {
+        let (tenant, platform) = key;
+        let client = self.client(platform)?;
+        let sealed = {
+            let connections = self.connections.read().unwrap();
+            let connection =
+                connections.get(key).ok_or(CredentialError::NotConnected(platform.as_str()))?;
+            connection.stored.sealed_refresh_token.clone()
+        };
+        let refresh_token = self.unseal(&sealed)?;
+
+        match self.exchange(client, platform, &refresh_token).await {
+            Ok((access, rotated)) => {
+                let rotated = rotated.map(|token| self.seal(&token)).transpose()?;
+                let token = access.token.clone();
+                if let Some(connection) = self.connections.write().unwrap().get_mut(key) {
+                    connection.access = Some(access);
+                    if let Some(sealed) = &rotated {
+                        connection.stored.sealed_refresh_token = sealed.clone();
+                    }
+                }
+                if rotated.is_some() {
+                    self.save();
+                }
+                Ok(token)
+            }
+            Err(CredentialError::Broken { platform, reason }) => {
+                tracing::error!(tenant, platform, reason, "platform connection broken");
+                if let Some(connection) = self.connections.write().unwrap().get_mut(key) {
+                    connection.access = None;
+                    connection.stored.status = ConnectionStatus::Broken;
+                    connection.stored.error = Some(reason.clone());
+                    connection.stored.broken_at = Some(Utc::now());
+                }
+                self.save();
+                Err(CredentialError::Broken { platform, reason })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Trade `refresh_token` for an access token and, when rotated, a new
+    /// refresh token; revoked refresh tokens give `Broken`
+    async fn exchange(
+        &self,
+        client: &OAuthClientConfig,
+        platform: &Platform,
+        refresh_token: &str,
+    ) -> Result<(AccessToken, Option<String>), CredentialError> {
+        let response = self
+            .http_client
+            .post(&client.token_url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", client.client_id.as_str()),
+                ("client_secret", client.client_secret.as_str()),
+            ])
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(match serde_json::from_str::<TokenErrorResponse>(&body) {
+                Ok(e) if e.error == "invalid_grant" => CredentialError::Broken {
+                    platform: platform.as_str(),
+                    reason: e.error_description.unwrap_or(e.error),
+                },
+                Ok(e) => CredentialError::Refresh(format!("{}: {}", status, e.error)),
+                Err(_) => CredentialError::Refresh(status.to_string()),
+            });
+        }
+
+        let token: TokenResponse = response.json().await?;
+        let now = Utc::now();
+        let expires_in = token.expires_in.unwrap_or(DEFAULT_EXPIRES_IN_SECS);
+        let access = AccessToken {
+            token: token.access_token,
+            expires_at: now + chrono::Duration::seconds(expires_in),
+            refreshed_at: now,
+        };
+        Ok((access, token.refresh_token))
+    }
+
+    /// Refresh every connected access token expiring within the margin
+    pub async fn refresh_expiring(&self) {
+        let due: Vec<ConnectionKey> = {
+            let deadline = Utc::now() + self.refresh_margin();
+            let connections = self.connections.read().unwrap();
+            connections
+                .iter()
+                .filter(|(_, c)| c.stored.status == ConnectionStatus::Connected)
+                .filter(|(_, c)| c.access.as_ref().is_none_or(|a| a.expires_at <= deadline))
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+
+        for key in due {
+            let (tenant, platform) = &key;
+            if let Err(e) = self.access_token(tenant, platform).await {
+                let platform = platform.as_str();
+                tracing::warn!(tenant, platform, error = %e, "token refresh failed");
+            }
+        }
+    }
+
+    /// Write the connections file, when configured; failures are logged
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(e) = self.persist(path) {
+            tracing::error!(path = %path.display(), error = %e, "could not write connections");
+        }
+    }
+
+    pub fn persist(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let stored: Vec<StoredConnection> =
+            self.connections.read().unwrap().values().map(|c| c.stored.clone()).collect();
+        let json = serde_json::to_vec_pretty(&stored)?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Add connections previously written by `persist`; their access
+    /// tokens are refreshed on first use
+    pub fn load(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let loaded: Vec<StoredConnection> = serde_json::from_slice(&bytes)?;
+
+        let mut connections = self.connections.write().unwrap();
+        for stored in loaded {
+            let key = (stored.tenant.clone(), stored.platform.clone());
+            connections.insert(key, Connection { stored, access: None });
+        }
+        Ok(())
+    }
+}
+
+/// Tenant of the current request, if any
+pub fn tenant() -> Option<String> {
+    TENANT.try_with(|tenant| tenant.clone()).ok()
+}
+
+/// Access token the current platform call is made with, if any
+pub fn access_token() -> Option<String> {
+    ACCESS_TOKEN.try_with(|token| token.clone()).ok()
+}
+
+/// Add the current call's access token to a gateway `request`
+pub fn authorize(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match access_token() {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+/// Run the platform call `fut` with `token`, or with the gateway's
+/// credentials when `None`
+pub async fn with_access_token<F: Future>(token: Option<String>, fut: F) -> F::Output {
+    match token {
+        Some(token) => ACCESS_TOKEN.scope(token, fut).await,
+        None => fut.await,
+    }
+}
+
+/// Layer attributing the request's platform calls to its `X-Tenant-Id`
+pub async fn scope_tenant(req: Request, next: Next) -> Response {
+    match Actor::from_headers(req.headers()).tenant {
+        Some(tenant) => TENANT.scope(tenant, next.run(req)).await,
+        None => next.run(req).await,
+    }
+}
+
+/// Refresh access tokens before they expire, until `shutdown`
+pub async fn run_credential_refresher(state: Arc<AppState>, shutdown: CancellationToken) {
+    let mut ticker = tokio::time::interval(REFRESH_TICK);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => state.credentials.refresh_expiring().await,
+        }
+    }
+
+    tracing::info!("credential refresher stopped");
+}
+
+fn require_tenant(actor: &Actor) -> Result<&str, ApiError> {
+    actor
+        .tenant
+        .as_deref()
+        .ok_or_else(|| ApiError::Validation("X-Tenant-Id is required".to_string()))
+}
+
+/// GET /connections - Auth health of the caller's connection to each platform
+pub async fn list_connections(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+) -> Result<Json<Vec<ConnectionHealth>>, ApiError> {
+    let tenant = require_tenant(&actor)?;
+    let platforms: Vec<Platform> = state.connectors.all().iter().map(|c| c.platform()).collect();
+    Ok(Json(state.credentials.health(tenant, &platforms)))
+}
+
+/// Body of `PUT /connections/{platform}`
+#[derive(Debug, Deserialize)]
+pub struct ConnectRequest {
+    pub refresh_token: String,
+}
+
+/// PUT /connections/{platform} - Connect the caller's platform account, or
+/// reconnect a broken connection
+pub async fn put_connection(
+    State(state): State<Arc<AppState>>,
+    Path(platform): Path<Platform>,
+    actor: Actor,
+    Json(body): Json<ConnectRequest>,
+) -> Result<Json<ConnectionHealth>, ApiError> {
+    let tenant = require_tenant(&actor)?;
+    if body.refresh_token.trim().is_empty() {
+        return Err(ApiError::Validation("refresh_token is required".to_string()));
+    }
+    let health = state.credentials.connect(tenant, &platform, body.refresh_token.trim()).await?;
+    tracing::info!(tenant, platform = platform.as_str(), actor = %actor.id, "platform connected");
+    Ok(Json(health))
+}
+
+/// DELETE /connections/{platform} - Go back to the gateway's credentials
+pub async fn delete_connection(
+    State(state): State<Arc<AppState>>,
+    Path(platform): Path<Platform>,
+    actor: Actor,
+) -> Result<Json<ConnectionHealth>, ApiError> {
+    let tenant = require_tenant(&actor)?;
+    state.credentials.disconnect(tenant, &platform)?;
+    Ok(Json(ConnectionHealth::not_connected(platform)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Form, http::StatusCode, response::IntoResponse, routing::post, Router};
+
+    fn manager() -> CredentialManager {
+        manager_with_token_url("http://127.0.0.1:9/token")
+    }
+
+    fn manager_with_token_url(token_url: &str) -> CredentialManager {
+        let mut config = CredentialsConfig { key: "11".repeat(32), ..CredentialsConfig::default() };
+        config.platforms.insert(
+            "google".to_string(),
+            OAuthClientConfig {
+                token_url: token_url.to_string(),
+                client_id: "client".to_string(),
+                client_secret: String::new(),
+            },
+        );
+        CredentialManager::from_config(&config).unwrap()
+    }
+
+    /// Refresh tokens the fake token endpoint was sent, in order
+    type Exchanges = Arc<Mutex<Vec<String>>>;
+
+    /// Fake OAuth token endpoint: `revoked` is rejected as `invalid_grant`,
+    /// `rotating` is traded for a new refresh token, others for an access
+    /// token only
+    async fn exchange_token(
+        State(exchanges): State<Exchanges>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Response {
+        let refresh_token = form.get("refresh_token").cloned().unwrap_or_default();
+        let access_token = {
+            let mut exchanges = exchanges.lock().unwrap();
+            exchanges.push(refresh_token.clone());
+            format!("access-{}", exchanges.len())
+        };
+
+        match refresh_token.as_str() {
+            "revoked" => {
+                let error = serde_json::json!({
+                    "error": "invalid_grant",
+                    "error_description": "Token has been expired or revoked."
+                });
+                (StatusCode::BAD_REQUEST, Json(error)).into_response()
+            }
+            "rotating" => Json(serde_json::json!({
+                "access_token": access_token,
+                "expires_in": 3600,
+                "refresh_token": "rotated"
+            }))
+            .into_response(),
+            _ => Json(serde_json::json!({ "access_token": access_token, "expires_in": 3600 }))
+                .into_response(),
+        }
+    }
+
+    /// URL of a running `exchange_token`, and what it was sent
+    async fn fake_token_endpoint() -> (String, Exchanges) {
+        let exchanges = Exchanges::default();
+        let router = Router::new()
+            .route("/token", post(exchange_token))
+            .with_state(exchanges.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (url, exchanges)
+    }
+
+    /// Connect `acme` to Google with `refresh_token`, without exchanging it
+    fn insert_connection(
+        manager: &CredentialManager,
+        refresh_token: &str,
+        access: Option<AccessToken>,
+    ) {
+        let connection = Connection {
+            stored: StoredConnection {
+                tenant: "acme".to_string(),
+                platform: Platform::Google,
+                sealed_refresh_token: manager.seal(refresh_token).unwrap(),
+                status: ConnectionStatus::Connected,
+                connected_at: Utc::now(),
+                error: None,
+                broken_at: None,
+            },
+            access,
+        };
+        let key = ("acme".to_string(), Platform::Google);
+        manager.connections.write().unwrap().insert(key, connection);
+    }
+
+    fn access_expiring_in(token: &str, expires_in: chrono::Duration) -> AccessToken {
+        AccessToken {
+            token: token.to_string(),
+            expires_at: Utc::now() + expires_in,
+            refreshed_at: Utc::now(),
+        }
+    }
+
+    fn sealed_refresh_token(manager: &CredentialManager) -> String {
+        let connections = manager.connections.read().unwrap();
+        let key = ("acme".to_string(), Platform::Google);
+        connections[&key].stored.sealed_refresh_token.clone()
+    }
+
+    #[tokio::test]
+    async fn test_refresh_tokens_are_sealed_and_broken_connections_fail() {
+        let manager = manager();
+        let sealed = manager.seal("1//refresh").unwrap();
+        assert!(!sealed.contains("refresh"));
+        assert_eq!(manager.unseal(&sealed).unwrap(), "1//refresh");
+
+        let key = ("acme".to_string(), Platform::Google);
+        let connection = Connection {
+            stored: StoredConnection {
+                tenant: "acme".to_string(),
+                platform: Platform::Google,
+                sealed_refresh_token: sealed,
+                status: ConnectionStatus::Broken,
+                connected_at: Utc::now(),
+                error: Some("Token has been expired or revoked.".to_string()),
+                broken_at: Some(Utc::now()),
+            },
+            access: None,
+        };
+        manager.connections.write().unwrap().insert(key, connection);
+
+        // Tenants without a connection use the gateway's credentials
+        assert_eq!(manager.access_token("globex", &Platform::Google).await.unwrap(), None);
+        let error = manager.access_token("acme", &Platform::Google).await.unwrap_err();
+        assert!(matches!(error, CredentialError::Broken { .. }));
+
+        let health = manager.health("acme", &[Platform::Google, Platform::Meta]);
+        assert_eq!(health[0].status, ConnectionStatus::Broken);
+        assert_eq!(health[1].status, ConnectionStatus::NotConnected);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_grant_marks_the_connection_broken() {
+        let (token_url, exchanges) = fake_token_endpoint().await;
+        let manager = manager_with_token_url(&token_url);
+        insert_connection(&manager, "revoked", None);
+
+        let error = manager.access_token("acme", &Platform::Google).await.unwrap_err();
+        assert!(matches!(error, CredentialError::Broken { .. }));
+
+        let health = manager.health("acme", &[Platform::Google]);
+        assert_eq!(health[0].status, ConnectionStatus::Broken);
+        assert_eq!(health[0].error.as_deref(), Some("Token has been expired or revoked."));
+        assert!(health[0].broken_at.is_some());
+
+        // Broken connections fail without asking the platform again
+        let error = manager.access_token("acme", &Platform::Google).await.unwrap_err();
+        assert!(matches!(error, CredentialError::Broken { .. }));
+        assert_eq!(exchanges.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_access_tokens_are_refreshed_within_the_margin() {
+        let (token_url, exchanges) = fake_token_endpoint().await;
+        let manager = manager_with_token_url(&token_url);
+
+        let fresh = access_expiring_in("current", chrono::Duration::hours(1));
+        insert_connection(&manager, "1//refresh", Some(fresh));
+        let token = manager.access_token("acme", &Platform::Google).await.unwrap();
+        assert_eq!(token.as_deref(), Some("current"));
+        assert!(exchanges.lock().unwrap().is_empty());
+
+        // Expires within the 5 minute margin
+        let expiring = access_expiring_in("current", chrono::Duration::minutes(2));
+        insert_connection(&manager, "1//refresh", Some(expiring));
+        let token = manager.access_token("acme", &Platform::Google).await.unwrap();
+        assert_eq!(token.as_deref(), Some("access-1"));
+        let token = manager.access_token("acme", &Platform::Google).await.unwrap();
+        assert_eq!(token.as_deref(), Some("access-1"));
+        assert_eq!(*exchanges.lock().unwrap(), ["1//refresh"]);
+
+        let health = manager.health("acme", &[Platform::Google]);
+        let expires_at = health[0].access_token_expires_at.unwrap();
+        assert!(expires_at > Utc::now() + chrono::Duration::minutes(55));
+    }
+
+    #[tokio::test]
+    async fn test_rotated_refresh_tokens_are_sealed_again() {
+        let (token_url, exchanges) = fake_token_endpoint().await;
+        let manager = manager_with_token_url(&token_url);
+        insert_connection(&manager, "rotating", None);
+
+        assert_eq!(manager.refresh("acme", &Platform::Google).await.unwrap(), "access-1");
+        let sealed = sealed_refresh_token(&manager);
+        assert!(!sealed.contains("rotated"));
+        assert_eq!(manager.unseal(&sealed).unwrap(), "rotated");
+
+        // The next refresh trades the rotated token
+        assert_eq!(manager.refresh("acme", &Platform::Google).await.unwrap(), "access-2");
+        assert_eq!(*exchanges.lock().unwrap(), ["rotating", "rotated"]);
+        assert_eq!(sealed_refresh_token(&manager), sealed);
+    }
+}
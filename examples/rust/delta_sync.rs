@@ -0,0 +1,278 @@
+//! # Delta Sync Example
+//!
+//! Every sync used to fetch every campaign of every platform, although
+//! most of them had not changed since the sync before. With `sync.delta`
+//! enabled, a platform's campaigns are fetched in full once a day, and in
+//! between only those its change history reports:
+//!
+//! - Google Ads: campaigns (and campaign budgets) in the change status
+//!   since the last sync, read by the gateway on `campaigns/changes`;
+//! - Meta: campaigns filtered on `updated_time`;
+//! - other platforms have no change history and are always synced in full.
+//!
+//! Changes are asked for from `sync.overlap_secs` before the last sync, as
+//! the change history lags behind edits. Changed campaigns replace their
+//! previous version, so a removed campaign stays listed as `REMOVED`.
+//!
+//! A full sync runs on a platform's first sync, on the first sync from
+//! `sync.full_sync_hour` (UTC) each day, and whenever its delta fetch
+//! fails. Metrics come with the campaigns, so the metrics of unchanged
+//! campaigns are those of their last fetch: only fetched campaigns are
+//! recorded in the store, and the nightly full sync records every
+//! campaign's totals before the day ends.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use super::campaign_aggregator::{AppState, Campaign, Platform};
+use super::config::SyncConfig;
+use super::connectors::{ConnectorError, ConnectorRegistry, PlatformConnector};
+use super::ids::GlobalCampaignId;
+use super::naming;
+use super::normalization;
+use super::sort::SortSpec;
+
+/// Campaigns of a platform as of its last sync
+struct Baseline {
+    /// As the connector returned them, before normalization
+    campaigns: Vec<Campaign>,
+    synced_at: DateTime<Utc>,
+    full_synced_at: DateTime<Utc>,
+}
+
+/// Campaigns of one sync
+#[derive(Debug, Default)]
+pub struct SyncedCampaigns {
+    /// Every campaign, normalized and tagged like `aggregate_campaigns`
+    pub campaigns: Vec<Campaign>,
+    /// Campaigns kept from an earlier sync rather than fetched by this one
+    pub carried: HashSet<GlobalCampaignId>,
+}
+
+impl SyncedCampaigns {
+    /// Whether `campaign` was kept from an earlier sync, with its metrics
+    pub fn is_carried(&self, campaign: &Campaign) -> bool {
+        self.carried.contains(&campaign.global_id())
+    }
+}
+
+/// Campaign lists of the last sync, by platform
+pub struct DeltaSync {
+    config: SyncConfig,
+    baselines: Mutex<HashMap<Platform, Baseline>>,
+}
+
+impl DeltaSync {
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            config,
+            baselines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Campaigns of every platform for a sync at `now`
+    pub async fn fetch_campaigns(&self, state: &AppState, now: DateTime<Utc>) -> SyncedCampaigns {
+        let mut synced = self.fetch_from(&state.connectors, now).await;
+        normalization::normalize_campaigns(state, &mut synced.campaigns);
+        naming::tag_campaigns(&state.naming.get(), &mut synced.campaigns);
+        synced
+    }
+
+    async fn fetch_from(
+        &self,
+        registry: &ConnectorRegistry,
+        now: DateTime<Utc>,
+    ) -> SyncedCampaigns {
+        let fetches =
+            registry.all().iter().map(|connector| self.sync_platform(connector.as_ref(), now));
+        let results = futures::future::join_all(fetches).await;
+
+        let mut synced = SyncedCampaigns::default();
+        for (campaigns, carried) in results.into_iter().flatten() {
+            synced.campaigns.extend(campaigns);
+            synced.carried.extend(carried);
+        }
+        SortSpec::default().sort(&mut synced.campaigns);
+        synced
+    }
+
+    /// Campaigns of one platform and those carried over, `None` when it
+    /// could not be fetched
+    async fn sync_platform(
+        &self,
+        connector: &dyn PlatformConnector,
+        now: DateTime<Utc>,
+    ) -> Option<(Vec<Campaign>, HashSet<GlobalCampaignId>)> {
+        let platform = connector.platform();
+        if let Some(since) = self.delta_since(&platform, now) {
+            match connector.fetch_changed_campaigns(since).await {
+                Ok(changed) => return Some(self.apply_changes(&platform, changed, now)),
+                Err(ConnectorError::NotSupported(_)) => {}
+                Err(e) => tracing::warn!(
+                    platform = platform.as_str(),
+                    error = %e,
+                    "delta sync failed, syncing in full"
+                ),
+            }
+        }
+
+        match connector.fetch_campaigns().await {
+            Ok(campaigns) => {
+                let baseline = Baseline {
+                    campaigns: campaigns.clone(),
+                    synced_at: now,
+                    full_synced_at: now,
+                };
+                self.baselines.lock().unwrap().insert(platform, baseline);
+                Some((campaigns, HashSet::new()))
+            }
+            Err(e) => {
+                tracing::warn!(platform = platform.as_str(), error = %e, "platform fetch failed");
+                None
+            }
+        }
+    }
+
+    /// Time to ask `platform`'s changes from, `None` when a full sync is due
+    fn delta_since(&self, platform: &Platform, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !self.config.delta {
+            return None;
+        }
+        let baselines = self.baselines.lock().unwrap();
+        let baseline = baselines.get(platform)?;
+        if baseline.full_synced_at < last_full_sync_time(now, self.config.full_sync_hour) {
+            return None;
+        }
+        Some(baseline.synced_at - Duration::seconds(self.config.overlap_secs as i64))
+    }
+
+    /// Replace changed campaigns in `platform`'s baseline
+    fn apply_changes(
+        &self,
+        platform: &Platform,
+        changed: Vec<Campaign>,
+        now: DateTime<Utc>,
+    ) -> (Vec<Campaign>, HashSet<GlobalCampaignId>) {
+        tracing::info!(platform = platform.as_str(), changed = changed.len(), "delta sync");
+        let mut baselines = self.baselines.lock().unwrap();
+        let Some(baseline) = baselines.get_mut(platform) else {
+            return (changed, HashSet::new());
+        };
+
+        let changed_ids: HashSet<GlobalCampaignId> =
+            changed.iter().map(|c| c.global_id()).collect();
+        baseline.campaigns.retain(|c| !changed_ids.contains(&c.global_id()));
+        let carried = baseline.campaigns.iter().map(|c| c.global_id()).collect();
+        baseline.campaigns.extend(changed);
+        baseline.synced_at = now;
+        (baseline.campaigns.clone(), carried)
+    }
+}
+
+/// Most recent `hour`:00 UTC at or before `now`
+fn last_full_sync_time(now: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let today = now
+        .date_naive()
+        .and_hms_opt(hour, 0, 0)
+        .expect("sync.full_sync_hour is validated")
+        .and_utc();
+    if today <= now {
+        today
+    } else {
+        today - Duration::days(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{CampaignMetrics, CampaignStatus};
+    use super::super::ids::CampaignId;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    /// Google connector whose change history holds a paused campaign `2`
+    #[derive(Default)]
+    struct Changing {
+        full: Mutex<usize>,
+        since: Mutex<Vec<DateTime<Utc>>>,
+    }
+
+    #[async_trait]
+    impl PlatformConnector for Changing {
+        fn platform(&self) -> Platform {
+            Platform::Google
+        }
+
+        async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+            *self.full.lock().unwrap() += 1;
+            Ok(vec![
+                Campaign::sample("1", Platform::Google),
+                Campaign::sample("2", Platform::Google),
+            ])
+        }
+
+        async fn fetch_changed_campaigns(
+            &self,
+            since: DateTime<Utc>,
+        ) -> Result<Vec<Campaign>, ConnectorError> {
+            self.since.lock().unwrap().push(since);
+            let mut campaign = Campaign::sample("2", Platform::Google);
+            campaign.status = CampaignStatus::Paused;
+            Ok(vec![campaign])
+        }
+
+        async fn fetch_metrics(
+            &self,
+            campaign_id: &CampaignId,
+        ) -> Result<CampaignMetrics, ConnectorError> {
+            Ok(Campaign::sample(campaign_id, Platform::Google).metrics)
+        }
+
+        async fn mutate_status(
+            &self,
+            _campaign_id: &CampaignId,
+            _status: CampaignStatus,
+        ) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delta_syncs_between_nightly_full_syncs() {
+        let connector = Arc::new(Changing::default());
+        let mut registry = ConnectorRegistry::default();
+        registry.register(connector.clone());
+        let sync = DeltaSync::new(SyncConfig {
+            delta: true,
+            ..Default::default()
+        });
+        let at = |hour, min| Utc.with_ymd_and_hms(2026, 10, 15, hour, min, 0).unwrap();
+
+        let fetched = |synced: &SyncedCampaigns| -> Vec<Campaign> {
+            synced.campaigns.iter().filter(|c| !synced.is_carried(c)).cloned().collect()
+        };
+
+        let first = sync.fetch_from(&registry, at(10, 0)).await;
+        assert_eq!((first.campaigns.len(), fetched(&first).len()), (2, 2));
+
+        let delta = sync.fetch_from(&registry, at(10, 15)).await;
+        assert_eq!(*connector.full.lock().unwrap(), 1);
+        assert_eq!(*connector.since.lock().unwrap(), [at(9, 55)]);
+        assert_eq!(delta.campaigns.len(), 2);
+        let changed = fetched(&delta);
+        assert_eq!((changed.len(), &changed[0].status), (1, &CampaignStatus::Paused));
+
+        // From 23:00 UTC the next sync is a full one
+        sync.fetch_from(&registry, at(22, 45)).await;
+        sync.fetch_from(&registry, at(23, 0)).await;
+        assert_eq!(*connector.full.lock().unwrap(), 2);
+        assert_eq!(connector.since.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,344 @@
+//! # Demo Data Example
+//!
+//! With `demo.enabled` (`--demo`, or `IBVI_DEMO_MODE=true`) every platform
+//! is served by an in-process `DemoConnector` instead of the gateway, so
+//! the API can be demoed and load-tested without ad accounts, gateway or
+//! credentials:
+//!
+//! - campaigns are generated from `demo.seed`: the same seed, scale and
+//!   day give the same campaigns, names, budgets and metrics;
+//! - `demo.campaigns_per_platform` sets the scale, up to
+//!   `MAX_DEMO_CAMPAIGNS` per platform;
+//! - metrics are month-to-date and grow with the day of the month, with
+//!   CTR, CPC and conversion rates typical of the platform;
+//! - status, budget and name changes are applied in memory, so later reads
+//!   see them until the process exits;
+//! - each platform has a manager account or Business Manager with three
+//!   ad accounts, for `accounts`.
+//!
+//! The connectors sit behind the same concurrency, quota and cache layers
+//! as gateway connectors, so load tests exercise the same paths.
+
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::accounts::{AccountKind, AccountNode};
+use super::campaign_aggregator::{
+    recompute_rates, Campaign, CampaignMetrics, CampaignObjective, CampaignStatus, CampaignType,
+    Platform,
+};
+use super::config::DemoConfig;
+use super::connectors::{ConnectorError, ConnectorRegistry, PlatformConnector};
+use super::ids::{AccountId, CampaignId};
+use super::money::Currency;
+
+/// Largest `demo.campaigns_per_platform` accepted
+pub const MAX_DEMO_CAMPAIGNS: usize = 100_000;
+
+/// Ad accounts generated under each platform's manager or business
+const DEMO_ACCOUNTS: usize = 3;
+
+const DEVELOPMENTS: &[&str] = &[
+    "Vila Nova",
+    "Jardim Europa",
+    "Parque das Flores",
+    "Alto da Serra",
+    "Reserva do Lago",
+    "Bela Vista",
+    "Porto Belo",
+    "Villa Toscana",
+];
+
+const REGIONS: &[&str] = &["SP", "RJ", "MG", "PR", "SC", "BR"];
+
+/// SplitMix64: small, fast and stable across releases, so a seed always
+/// yields the same data
+struct DemoRng(u64);
+
+impl DemoRng {
+    fn new(seed: u64, platform: &Platform) -> Self {
+        let salt =
+            platform.as_str().bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64));
+        Self(seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[low, high)`
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        low + unit * (high - low)
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[(self.next_u64() % items.len() as u64) as usize]
+    }
+}
+
+/// Typical CTR (%), CPC and conversion rate (%) of a campaign type
+fn rates(platform: &Platform, campaign_type: &CampaignType) -> (f64, f64, f64) {
+    match (platform, campaign_type) {
+        (Platform::Google, CampaignType::Search) => (4.5, 2.8, 6.0),
+        (Platform::Google, CampaignType::Shopping) => (1.2, 0.9, 2.5),
+        (_, CampaignType::Video) => (0.6, 0.4, 0.8),
+        (Platform::Meta, _) => (1.4, 1.1, 3.0),
+        _ => (0.8, 0.7, 1.5),
+    }
+}
+
+fn account_ids(platform: &Platform) -> Vec<AccountId> {
+    (1..=DEMO_ACCOUNTS)
+        .map(|n| match platform {
+            Platform::Meta => AccountId::from(format!("act_90000{}", n)),
+            other => AccountId::from(format!("{}-demo-000{}", other.as_str(), n)),
+        })
+        .collect()
+}
+
+/// Objective as written in the generated names
+fn label(objective: &CampaignObjective) -> &'static str {
+    match objective {
+        CampaignObjective::Leads => "Leads",
+        CampaignObjective::Traffic => "Tráfego",
+        CampaignObjective::Awareness => "Alcance",
+        _ => "Vendas",
+    }
+}
+
+/// `count` campaigns of `platform` generated from `seed`, metrics
+/// month-to-date as of `today`
+pub fn generate(platform: &Platform, seed: u64, count: usize, today: NaiveDate) -> Vec<Campaign> {
+    let mut rng = DemoRng::new(seed, platform);
+    let accounts = account_ids(platform);
+    let prefix = match platform {
+        Platform::Google => "G",
+        Platform::Meta => "M",
+        #[cfg(any(feature = "linkedin", feature = "microsoft"))]
+        _ => "X",
+    };
+    let types: &[CampaignType] = match platform {
+        Platform::Google => &[
+            CampaignType::Search,
+            CampaignType::Search,
+            CampaignType::Display,
+            CampaignType::Video,
+            CampaignType::Shopping,
+        ],
+        _ => &[CampaignType::Display, CampaignType::Video, CampaignType::Shopping],
+    };
+    let objectives = [
+        CampaignObjective::Leads,
+        CampaignObjective::Leads,
+        CampaignObjective::Traffic,
+        CampaignObjective::Awareness,
+        CampaignObjective::Sales,
+    ];
+
+    (0..count)
+        .map(|n| {
+            let campaign_type = rng.pick(types).clone();
+            let objective = rng.pick(&objectives).clone();
+            let development = *rng.pick(DEVELOPMENTS);
+            let region = *rng.pick(REGIONS);
+            let status = match rng.range(0.0, 1.0) {
+                x if x < 0.75 => CampaignStatus::Enabled,
+                x if x < 0.95 => CampaignStatus::Paused,
+                _ => CampaignStatus::Removed,
+            };
+            let daily_budget = (rng.range(20.0, 500.0) / 5.0).round() * 5.0;
+
+            let (ctr, cpc, conversion_rate) = rates(platform, &campaign_type);
+            let mut metrics = CampaignMetrics::default();
+            if status == CampaignStatus::Enabled {
+                let cost = daily_budget * today.day() as f64 * rng.range(0.6, 1.1);
+                let clicks = (cost / (cpc * rng.range(0.7, 1.4))).round();
+                let impressions = (clicks / (ctr * rng.range(0.7, 1.4) / 100.0)).round();
+                let conversions = (clicks * conversion_rate * rng.range(0.5, 1.5) / 100.0).round();
+                metrics.cost = (cost * 100.0).round() / 100.0;
+                metrics.clicks = clicks as u64;
+                metrics.impressions = impressions as u64;
+                metrics.conversions = conversions as u32;
+                metrics.conversion_value = (conversions * rng.range(150.0, 900.0)).round();
+                if *platform == Platform::Meta {
+                    metrics.reach = Some((impressions / rng.range(1.3, 3.5)).round() as u64);
+                }
+                recompute_rates(&mut metrics);
+            }
+
+            Campaign {
+                id: CampaignId::from(format!("demo-{}-{}", platform.as_str(), n + 1)),
+                platform: platform.clone(),
+                name: format!("[{}] {} | {} | {}", prefix, label(&objective), development, region),
+                status,
+                objective: Some(objective),
+                campaign_type: Some(campaign_type),
+                daily_budget,
+                currency: "BRL".to_string(),
+                metrics,
+                pacing: None,
+                targeting: None,
+                bidding: None,
+                quality: None,
+                auction: None,
+                tracking_template: None,
+                account_id: Some(accounts[n % accounts.len()].clone()),
+                labels: vec![development.to_lowercase()],
+                tags: HashMap::new(),
+            }
+        })
+        .collect()
+}
+
+/// Manager account or Business Manager of `platform` and its ad accounts
+fn demo_accounts(platform: &Platform) -> Vec<AccountNode> {
+    let (root_id, kind) = match platform {
+        Platform::Meta => (AccountId::from("demo-business"), AccountKind::Business),
+        other => (AccountId::from(format!("{}-demo-mcc", other.as_str())), AccountKind::Manager),
+    };
+    let root = AccountNode {
+        platform: platform.clone(),
+        id: root_id.clone(),
+        name: "IBVI Demo".to_string(),
+        kind,
+        parent_id: None,
+        currency: (kind == AccountKind::Manager).then(|| Currency::new("BRL")),
+    };
+    let accounts = account_ids(platform).into_iter().enumerate().map(|(n, id)| AccountNode {
+        platform: platform.clone(),
+        id,
+        name: format!("Demo Account {}", n + 1),
+        kind: AccountKind::AdAccount,
+        parent_id: Some(root_id.clone()),
+        currency: Some(Currency::new("BRL")),
+    });
+    std::iter::once(root).chain(accounts).collect()
+}
+
+/// Connector answering from generated campaigns held in memory
+pub struct DemoConnector {
+    platform: Platform,
+    campaigns: RwLock<Vec<Campaign>>,
+}
+
+impl DemoConnector {
+    pub fn new(platform: Platform, config: &DemoConfig) -> Self {
+        let today = Utc::now().date_naive();
+        let campaigns = generate(&platform, config.seed, config.campaigns_per_platform, today);
+        Self { platform, campaigns: RwLock::new(campaigns) }
+    }
+
+    fn update(
+        &self,
+        campaign_id: &CampaignId,
+        change: impl FnOnce(&mut Campaign),
+    ) -> Result<(), ConnectorError> {
+        let mut campaigns = self.campaigns.write().unwrap();
+        let campaign = campaigns
+            .iter_mut()
+            .find(|c| &c.id == campaign_id)
+            .ok_or_else(|| ConnectorError::NotFound(campaign_id.to_string()))?;
+        change(campaign);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for DemoConnector {
+    fn platform(&self) -> Platform {
+        self.platform.clone()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        Ok(self.campaigns.read().unwrap().clone())
+    }
+
+    async fn fetch_metrics(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<CampaignMetrics, ConnectorError> {
+        self.campaigns
+            .read()
+            .unwrap()
+            .iter()
+            .find(|c| &c.id == campaign_id)
+            .map(|c| c.metrics.clone())
+            .ok_or_else(|| ConnectorError::NotFound(campaign_id.to_string()))
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        self.update(campaign_id, |c| c.status = status)
+    }
+
+    async fn mutate_budget(
+        &self,
+        campaign_id: &CampaignId,
+        daily_budget: f64,
+    ) -> Result<(), ConnectorError> {
+        self.update(campaign_id, |c| c.daily_budget = daily_budget)
+    }
+
+    async fn rename_campaign(
+        &self,
+        campaign_id: &CampaignId,
+        name: &str,
+    ) -> Result<(), ConnectorError> {
+        self.update(campaign_id, |c| c.name = name.to_string())
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        Ok(())
+    }
+
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        Ok(demo_accounts(&self.platform))
+    }
+}
+
+/// Demo connectors of every platform in `config.platforms`
+pub fn registry(config: &DemoConfig) -> ConnectorRegistry {
+    let mut registry = ConnectorRegistry::default();
+    for platform in &config.platforms {
+        registry.register(Arc::new(DemoConnector::new(platform.clone(), config)));
+    }
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_generates_same_campaigns() {
+        let today = NaiveDate::from_ymd_opt(2026, 11, 10).unwrap();
+        let names = |seed| -> Vec<(String, f64, f64)> {
+            generate(&Platform::Google, seed, 200, today)
+                .into_iter()
+                .map(|c| (c.name, c.daily_budget, c.metrics.cost))
+                .collect()
+        };
+
+        assert_eq!(names(42), names(42));
+        assert_ne!(names(42), names(7));
+        assert_eq!(names(42).len(), 200);
+
+        let meta = generate(&Platform::Meta, 42, 200, today);
+        assert_ne!(meta[0].name, names(42)[0].0);
+        assert!(meta.iter().all(|c| c.account_id.as_ref().is_some_and(|a| a.starts_with("act_"))));
+        let enabled: Vec<&Campaign> =
+            meta.iter().filter(|c| c.status == CampaignStatus::Enabled).collect();
+        assert!(enabled.len() > 100);
+        assert!(enabled.iter().all(|c| c.metrics.cost > 0.0 && c.metrics.clicks > 0));
+    }
+}
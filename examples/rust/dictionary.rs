@@ -0,0 +1,581 @@
+//! # Data Dictionary Example
+//!
+//! `GET /meta/schema` describes every entity the API returns: each field
+//! with its JSON type, meaning and where it comes from, either the field
+//! of each platform it is read from or the formula it is derived with.
+//! The metric section joins those descriptions with the metric registry
+//! of `units` (unit, aggregation), so "how is CPA computed?" has one
+//! answer that cannot drift from the code:
+//!
+//! - the field lists are checked against the serialized Rust types by the
+//!   tests below, so adding a field without documenting it fails CI;
+//! - every metric of `units::METRICS` must have a field entry here.
+
+use axum::response::Json;
+use serde::Serialize;
+
+use super::error::ApiError;
+use super::units::{MetricDef, METRICS};
+
+/// Where a field's value comes from
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldSource {
+    /// Read from the platform; each entry names the platform's own field
+    Platform {
+        #[serde(skip_serializing_if = "str::is_empty")]
+        google: &'static str,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        meta: &'static str,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        linkedin: &'static str,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        microsoft: &'static str,
+    },
+    /// Computed from other fields
+    Derived { formula: &'static str },
+    /// Set by this service rather than any platform
+    Service { note: &'static str },
+}
+
+/// One field of an entity
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct FieldDef {
+    pub name: &'static str,
+    /// JSON type: `string`, `integer`, `number`, `enum`, `object`, `array`
+    #[serde(rename = "type")]
+    pub field_type: &'static str,
+    pub description: &'static str,
+    /// Omitted from responses when empty or not requested
+    pub optional: bool,
+    pub source: FieldSource,
+}
+
+/// One entity returned by the API
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct EntityDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub fields: &'static [FieldDef],
+}
+
+const fn field(
+    name: &'static str,
+    field_type: &'static str,
+    description: &'static str,
+    source: FieldSource,
+) -> FieldDef {
+    FieldDef {
+        name,
+        field_type,
+        description,
+        optional: false,
+        source,
+    }
+}
+
+const fn optional(
+    name: &'static str,
+    field_type: &'static str,
+    description: &'static str,
+    source: FieldSource,
+) -> FieldDef {
+    FieldDef {
+        optional: true,
+        ..field(name, field_type, description, source)
+    }
+}
+
+const fn platform(
+    google: &'static str,
+    meta: &'static str,
+    linkedin: &'static str,
+    microsoft: &'static str,
+) -> FieldSource {
+    FieldSource::Platform {
+        google,
+        meta,
+        linkedin,
+        microsoft,
+    }
+}
+
+const fn derived(formula: &'static str) -> FieldSource {
+    FieldSource::Derived { formula }
+}
+
+const fn service(note: &'static str) -> FieldSource {
+    FieldSource::Service { note }
+}
+
+pub const CAMPAIGN_FIELDS: &[FieldDef] = &[
+    field(
+        "id",
+        "string",
+        "Campaign ID on its platform; unique per platform only",
+        platform("campaign.id", "id", "id", "Id"),
+    ),
+    field(
+        "platform",
+        "enum",
+        "Ad platform: google, meta, linkedin or microsoft",
+        service("connector the campaign was read through"),
+    ),
+    field(
+        "name",
+        "string",
+        "Campaign name",
+        platform("campaign.name", "name", "name", "Name"),
+    ),
+    field(
+        "status",
+        "enum",
+        "ENABLED, PAUSED or REMOVED; unmapped platform values as sent, see /admin/normalization",
+        platform("campaign.status", "effective_status", "status", "Status"),
+    ),
+    optional(
+        "objective",
+        "enum",
+        "AWARENESS, TRAFFIC, ENGAGEMENT, LEADS, APP_PROMOTION or SALES; other values as sent",
+        platform("", "objective", "objectiveType", ""),
+    ),
+    optional(
+        "campaign_type",
+        "enum",
+        "SEARCH, DISPLAY, VIDEO or SHOPPING; other values as sent",
+        platform(
+            "campaign.advertising_channel_type",
+            "smart_promotion_type",
+            "",
+            "CampaignType",
+        ),
+    ),
+    field(
+        "daily_budget",
+        "number",
+        "Daily budget in `currency`; lifetime and shared budgets are converted to a daily amount",
+        platform(
+            "campaign_budget.amount_micros / 1e6",
+            "daily_budget / 100",
+            "dailyBudget.amount",
+            "SharedBudgetAmount or DailyBudget",
+        ),
+    ),
+    field(
+        "currency",
+        "string",
+        "ISO 4217 code of the ad account currency",
+        platform(
+            "customer.currency_code",
+            "account.currency",
+            "dailyBudget.currencyCode",
+            "CurrencyCode",
+        ),
+    ),
+    field(
+        "metrics",
+        "object",
+        "Delivery metrics of the reporting period, see the CampaignMetrics entity",
+        service("normalized from each platform's reporting API"),
+    ),
+    optional(
+        "pacing",
+        "object",
+        "Budget pacing, with `?include=pacing`",
+        derived("spend to date against the budget elapsed, see /campaigns/pacing"),
+    ),
+    optional(
+        "targeting",
+        "object",
+        "Normalized locations, languages, demographics and audiences, with `?expand=targeting`",
+        platform("campaign_criterion", "adset.targeting", "targetingCriteria", "Criterions"),
+    ),
+    optional(
+        "bidding",
+        "object",
+        "Bid strategy and targets, when the platform reports them",
+        platform("campaign.bidding_strategy_type", "bid_strategy", "", ""),
+    ),
+    optional(
+        "quality",
+        "object",
+        "Quality score and relevance diagnostics, per ad group too, with `?include=quality`",
+        platform("ad_group_criterion.quality_info", "ad quality_ranking", "", ""),
+    ),
+    optional(
+        "auction",
+        "object",
+        "Impression share and auction competition, with `?include=auction`",
+        platform("metrics.search_impression_share", "auction_competitiveness", "", ""),
+    ),
+    field(
+        "tracking_template",
+        "string",
+        "Tracking template or final URL suffix carrying the UTM tags; null when unset",
+        platform("campaign.final_url_suffix", "url_tags", "", "TrackingUrlTemplate"),
+    ),
+    field(
+        "account_id",
+        "string",
+        "Ad account owning the campaign; null when the platform does not report it",
+        platform("customer.id", "account_id", "account", "AccountId"),
+    ),
+    optional(
+        "labels",
+        "array",
+        "Platform labels, used to filter lists and scope change freezes",
+        platform("campaign.labels", "adlabels", "", "Labels"),
+    ),
+    optional(
+        "tags",
+        "object",
+        "Fields parsed from the name by the configured naming conventions",
+        service("see /naming/conventions"),
+    ),
+];
+
+pub const METRICS_FIELDS: &[FieldDef] = &[
+    field(
+        "impressions",
+        "integer",
+        "Times an ad was shown",
+        platform("metrics.impressions", "impressions", "impressions", "Impressions"),
+    ),
+    field(
+        "clicks",
+        "integer",
+        "Clicks on ads",
+        platform("metrics.clicks", "clicks", "clicks", "Clicks"),
+    ),
+    field(
+        "conversions",
+        "integer",
+        "Conversions attributed to the campaign; fractional platform values are rounded",
+        platform(
+            "metrics.conversions",
+            "actions[offsite_conversion]",
+            "externalWebsiteConversions",
+            "Conversions",
+        ),
+    ),
+    field(
+        "cost",
+        "number",
+        "Spend in the campaign currency",
+        platform("metrics.cost_micros / 1e6", "spend", "costInLocalCurrency", "Spend"),
+    ),
+    field(
+        "ctr",
+        "number",
+        "Click-through rate, in percent",
+        derived("clicks / impressions * 100; 0 without impressions"),
+    ),
+    field(
+        "cpa",
+        "number",
+        "Cost per acquisition, in the campaign currency",
+        derived("cost / conversions; 0 without conversions"),
+    ),
+    field(
+        "conversion_value",
+        "number",
+        "Value of all conversions, in the campaign currency",
+        platform(
+            "metrics.conversions_value",
+            "action_values",
+            "conversionValueInLocalCurrency",
+            "Revenue",
+        ),
+    ),
+    field(
+        "roas",
+        "number",
+        "Return on ad spend",
+        derived("conversion_value / cost; 0 without spend"),
+    ),
+    optional(
+        "conversion_actions",
+        "array",
+        "Conversions and value per conversion action (purchase, lead, ...)",
+        platform("segments.conversion_action", "actions[action_type]", "", ""),
+    ),
+    field(
+        "reach",
+        "integer",
+        "People reached, de-duplicated within the campaign; null where not reported. \
+         Summed across campaigns of one platform, null on totals spanning platforms",
+        platform("metrics.unique_users", "reach", "approximateUniqueImpressions", ""),
+    ),
+    field(
+        "frequency",
+        "number",
+        "Average impressions per person reached; null without reach",
+        derived("impressions / reach"),
+    ),
+    field(
+        "video_views",
+        "integer",
+        "Video views; null where not reported",
+        platform(
+            "metrics.video_trueview_views",
+            "video_thruplay_watched_actions",
+            "videoViews",
+            "",
+        ),
+    ),
+    field(
+        "video_view_rate",
+        "number",
+        "Video views per impression, in percent; null without video views",
+        derived("video_views / impressions * 100, over rows reporting video views"),
+    ),
+    field(
+        "average_watch_time_seconds",
+        "number",
+        "Average seconds watched per video view; null where not reported",
+        platform(
+            "metrics.average_video_watch_time_duration_millis / 1e3",
+            "video_avg_time_watched_actions",
+            "",
+            "",
+        ),
+    ),
+];
+
+pub const AD_GROUP_FIELDS: &[FieldDef] = &[
+    field(
+        "id",
+        "string",
+        "Ad group ID (ad set on Meta)",
+        platform("ad_group.id", "adset.id", "", ""),
+    ),
+    field(
+        "campaign_id",
+        "string",
+        "Campaign the ad group belongs to",
+        platform("ad_group.campaign", "adset.campaign_id", "", ""),
+    ),
+    field(
+        "name",
+        "string",
+        "Ad group name",
+        platform("ad_group.name", "adset.name", "", ""),
+    ),
+    field(
+        "status",
+        "enum",
+        "ENABLED, PAUSED or REMOVED",
+        platform("ad_group.status", "adset.effective_status", "", ""),
+    ),
+    field(
+        "audience_ids",
+        "array",
+        "Audiences targeted by the ad group",
+        platform("ad_group_criterion.user_list", "adset.targeting.custom_audiences", "", ""),
+    ),
+];
+
+pub const AD_FIELDS: &[FieldDef] = &[
+    field("id", "string", "Ad ID", platform("ad_group_ad.ad.id", "ad.id", "", "")),
+    field(
+        "ad_group_id",
+        "string",
+        "Ad group (ad set) the ad belongs to",
+        platform("ad_group_ad.ad_group", "ad.adset_id", "", ""),
+    ),
+    field("name", "string", "Ad name", platform("ad_group_ad.ad.name", "ad.name", "", "")),
+    field(
+        "status",
+        "enum",
+        "ENABLED, PAUSED or REMOVED",
+        platform("ad_group_ad.status", "ad.effective_status", "", ""),
+    ),
+    field(
+        "creative",
+        "object",
+        "Creative ID, name and format; null when not reported",
+        platform("ad_group_ad.ad.type", "ad.creative", "", ""),
+    ),
+    field(
+        "headline",
+        "string",
+        "First headline of responsive ads, title of Meta ads",
+        platform("responsive_search_ad.headlines[0]", "creative.title", "", ""),
+    ),
+    field(
+        "description",
+        "string",
+        "First description line",
+        platform("responsive_search_ad.descriptions[0]", "creative.body", "", ""),
+    ),
+    field(
+        "assets",
+        "array",
+        "Images and videos used by the ad",
+        platform("ad_group_ad_asset_view", "creative.asset_feed_spec", "", ""),
+    ),
+    field(
+        "approval_status",
+        "enum",
+        "approved, limited, pending, disapproved or unknown",
+        platform("ad_group_ad.policy_summary.approval_status", "ad.review_feedback", "", ""),
+    ),
+    field(
+        "preview_url",
+        "string",
+        "Shareable preview of the ad",
+        platform("ad preview", "preview_shareable_link", "", ""),
+    ),
+    optional(
+        "metrics",
+        "object",
+        "Lifetime delivery metrics of the ad, see the CampaignMetrics entity",
+        service("normalized from each platform's reporting API"),
+    ),
+];
+
+pub const ENTITIES: &[EntityDef] = &[
+    EntityDef {
+        name: "Campaign",
+        description: "Campaign unified across platforms (GET /campaigns)",
+        fields: CAMPAIGN_FIELDS,
+    },
+    EntityDef {
+        name: "CampaignMetrics",
+        description: "Delivery metrics of a campaign or ad",
+        fields: METRICS_FIELDS,
+    },
+    EntityDef {
+        name: "AdGroup",
+        description: "Ad group, or ad set on Meta (GET /campaigns/{id}/detail)",
+        fields: AD_GROUP_FIELDS,
+    },
+    EntityDef {
+        name: "Ad",
+        description: "Ad unified across platforms (GET /campaigns/{platform}/{id}/ads)",
+        fields: AD_FIELDS,
+    },
+];
+
+/// Metric registry entry with its description and derivation
+#[derive(Debug, Serialize, Clone)]
+pub struct MetricDoc {
+    #[serde(flatten)]
+    pub def: &'static MetricDef,
+    pub description: &'static str,
+    pub source: FieldSource,
+}
+
+/// `GET /meta/schema` response
+#[derive(Debug, Serialize, Clone)]
+pub struct DataDictionary {
+    pub entities: &'static [EntityDef],
+    pub metrics: Vec<MetricDoc>,
+}
+
+pub fn data_dictionary() -> DataDictionary {
+    let metrics = METRICS
+        .iter()
+        .filter_map(|def| {
+            let doc = METRICS_FIELDS.iter().find(|f| f.name == def.name)?;
+            Some(MetricDoc {
+                def,
+                description: doc.description,
+                source: doc.source,
+            })
+        })
+        .collect();
+
+    DataDictionary {
+        entities: ENTITIES,
+        metrics,
+    }
+}
+
+/// GET /meta/schema - Entities, fields and metric definitions
+pub async fn get_data_dictionary() -> Result<Json<DataDictionary>, ApiError> {
+    Ok(Json(data_dictionary()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{
+        Campaign, CampaignMetrics, CampaignObjective, CampaignStatus, CampaignType, Platform,
+    };
+    use super::super::hydration::{Ad, AdGroup, ApprovalStatus};
+    use std::collections::BTreeSet;
+
+    /// Fields serialized from `value` must match the documented ones, the
+    /// optional ones only when present
+    fn assert_documented(value: impl Serialize, fields: &[FieldDef]) {
+        let serialized: BTreeSet<String> = serde_json::to_value(value)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        let documented: BTreeSet<String> = fields.iter().map(|f| f.name.to_string()).collect();
+        let required: BTreeSet<String> = fields
+            .iter()
+            .filter(|f| !f.optional)
+            .map(|f| f.name.to_string())
+            .collect();
+
+        let undocumented: Vec<_> = serialized.difference(&documented).collect();
+        assert!(undocumented.is_empty(), "undocumented fields: {:?}", undocumented);
+        let missing: Vec<_> = required.difference(&serialized).collect();
+        assert!(missing.is_empty(), "documented but not serialized: {:?}", missing);
+    }
+
+    #[test]
+    fn test_entities_match_serialized_types() {
+        let mut campaign = Campaign::sample("1", Platform::Google);
+        campaign.labels = vec!["brand".to_string()];
+        campaign.objective = Some(CampaignObjective::Leads);
+        campaign.campaign_type = Some(CampaignType::Search);
+        campaign.tags.insert("market".to_string(), "br".to_string());
+        assert_documented(&campaign, CAMPAIGN_FIELDS);
+        assert_documented(CampaignMetrics::default(), METRICS_FIELDS);
+
+        assert_documented(
+            AdGroup {
+                id: "ag1".into(),
+                campaign_id: "1".into(),
+                name: "Brand".to_string(),
+                status: CampaignStatus::Enabled,
+                audience_ids: Vec::new(),
+            },
+            AD_GROUP_FIELDS,
+        );
+        assert_documented(
+            Ad {
+                id: "ad1".to_string(),
+                ad_group_id: "ag1".into(),
+                name: "Brand ad".to_string(),
+                status: CampaignStatus::Enabled,
+                creative: None,
+                headline: None,
+                description: None,
+                assets: Vec::new(),
+                approval_status: ApprovalStatus::Approved,
+                preview_url: None,
+                metrics: Some(CampaignMetrics::default()),
+            },
+            AD_FIELDS,
+        );
+    }
+
+    #[test]
+    fn test_every_registered_metric_is_documented() {
+        let dictionary = data_dictionary();
+
+        assert_eq!(dictionary.metrics.len(), METRICS.len());
+        let cpa = dictionary.metrics.iter().find(|m| m.def.name == "cpa").unwrap();
+        assert!(matches!(
+            cpa.source,
+            FieldSource::Derived { formula } if formula.starts_with("cost /")
+        ));
+    }
+}
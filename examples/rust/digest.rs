@@ -0,0 +1,302 @@
+//! # Digest Example
+//!
+//! `GET /digest?period=daily` answers "what changed since yesterday" in
+//! one ready-to-render response for the dashboard home screen:
+//!
+//! - spend of the period against the one before, per currency;
+//! - top movers: the campaigns whose spend changed most, per currency
+//!   (`?top=`, 5 by default);
+//! - campaigns new in the period (first recorded in it) and campaigns
+//!   removed in it;
+//! - alerts triggered in the period, most recent first;
+//! - enabled campaigns over- or under-pacing their budget, furthest off
+//!   first.
+//!
+//! `period=weekly` covers the last 7 days against the 7 before. Periods
+//! end today, like the summary's period comparison: today's values are
+//! those recorded by the latest sync. Everything but pacing is read from
+//! the metrics store, so the digest only knows what the sync worker
+//! recorded; `build_digest` is the one place it is assembled, for a
+//! scheduled email as much as for the endpoint.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use super::alerts::AlertEvent;
+use super::campaign_aggregator::{
+    aggregate_campaigns, AppState, Campaign, CampaignStatus, Platform,
+};
+use super::error::ApiError;
+use super::history::{period_totals, MetricDelta, Period};
+use super::ids::CampaignId;
+use super::money::Currency;
+use super::pacing::{compute_pacing, CampaignPacing, PaceStatus};
+use super::store::MetricsStore;
+use super::timezones;
+
+/// Movers listed per currency when `top` is not given
+pub const DEFAULT_TOP_MOVERS: usize = 5;
+
+/// Largest `top` accepted
+pub const MAX_TOP_MOVERS: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestPeriod {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+impl DigestPeriod {
+    pub fn days(self) -> i64 {
+        match self {
+            DigestPeriod::Daily => 1,
+            DigestPeriod::Weekly => 7,
+        }
+    }
+}
+
+/// Query parameters for `GET /digest`
+#[derive(Debug, Deserialize, Default)]
+pub struct DigestQuery {
+    #[serde(default)]
+    pub period: DigestPeriod,
+    /// Movers listed per currency
+    pub top: Option<usize>,
+}
+
+/// Campaign named in a digest
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DigestCampaign {
+    pub id: CampaignId,
+    pub platform: Platform,
+    /// `None` for campaigns recorded before names were
+    pub name: Option<String>,
+}
+
+/// Campaign whose spend changed between the two periods
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SpendMover {
+    #[serde(flatten)]
+    pub campaign: DigestCampaign,
+    pub spend: MetricDelta,
+}
+
+/// Response of `GET /digest`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Digest {
+    pub period: DigestPeriod,
+    pub generated_at: DateTime<Utc>,
+    pub current: Period,
+    pub previous: Period,
+    /// Spend of both periods, per currency
+    pub spend: BTreeMap<Currency, MetricDelta>,
+    /// Largest spend changes first, per currency
+    pub top_movers: BTreeMap<Currency, Vec<SpendMover>>,
+    pub new_campaigns: Vec<DigestCampaign>,
+    pub removed_campaigns: Vec<DigestCampaign>,
+    /// Most recent first
+    pub alerts: Vec<AlertEvent>,
+    /// Furthest from 100% pace first
+    pub pacing_violations: Vec<CampaignPacing>,
+}
+
+/// Assemble the digest of the `period` ending on `today`
+///
+/// `alerts` is the alert history and `campaigns` the current campaign
+/// list, used for pacing.
+pub fn build_digest(
+    store: &MetricsStore,
+    alerts: Vec<AlertEvent>,
+    campaigns: &[Campaign],
+    period: DigestPeriod,
+    today: NaiveDate,
+    top: usize,
+) -> Digest {
+    let length = Duration::days(period.days());
+    let start = today - length + Duration::days(1);
+    let previous_end = start - Duration::days(1);
+    let previous_start = previous_end - length + Duration::days(1);
+
+    let mut spend: BTreeMap<Currency, (f64, f64)> = BTreeMap::new();
+    let mut movers: BTreeMap<Currency, Vec<SpendMover>> = BTreeMap::new();
+    let mut new_campaigns = Vec::new();
+    let (mut current_days, mut previous_days) = (BTreeSet::new(), BTreeSet::new());
+    for id in store.campaign_ids() {
+        let series = store.series(&id);
+        let (Some((first, _)), Some((_, latest))) =
+            (series.first_key_value(), series.last_key_value())
+        else {
+            continue;
+        };
+        let campaign = DigestCampaign {
+            id: id.id.clone(),
+            platform: id.platform.clone(),
+            name: latest.name.clone(),
+        };
+        current_days.extend(series.range(start..=today).map(|(day, _)| *day));
+        previous_days.extend(series.range(previous_start..=previous_end).map(|(day, _)| *day));
+        if (start..=today).contains(first) {
+            new_campaigns.push(campaign.clone());
+        }
+
+        // Campaigns recorded without a currency cannot be totalled
+        let Some(currency) = latest.currency.clone() else {
+            continue;
+        };
+        let current = period_totals(store, &id, start, today).cost;
+        let previous = period_totals(store, &id, previous_start, previous_end).cost;
+        let totals = spend.entry(currency.clone()).or_default();
+        totals.0 += current;
+        totals.1 += previous;
+        if current != previous {
+            let spend = MetricDelta::new(current, previous, false);
+            movers.entry(currency).or_default().push(SpendMover { campaign, spend });
+        }
+    }
+    for list in movers.values_mut() {
+        list.sort_by(|a, b| {
+            b.spend
+                .absolute
+                .abs()
+                .total_cmp(&a.spend.absolute.abs())
+                .then_with(|| {
+                    (&a.campaign.id, &a.campaign.platform)
+                        .cmp(&(&b.campaign.id, &b.campaign.platform))
+                })
+        });
+        list.truncate(top);
+    }
+    new_campaigns.sort_by(|a, b| (&a.id, &a.platform).cmp(&(&b.id, &b.platform)));
+
+    let removed_campaigns = store
+        .removed()
+        .into_iter()
+        .filter(|removed| (start..=today).contains(&removed.removed_on))
+        .map(|removed| DigestCampaign {
+            id: removed.id,
+            platform: removed.platform,
+            name: removed.name,
+        })
+        .collect();
+
+    let alerts =
+        alerts.into_iter().filter(|alert| alert.triggered_at.date_naive() >= start).collect();
+
+    let mut pacing_violations: Vec<CampaignPacing> = campaigns
+        .iter()
+        .filter(|c| c.status == CampaignStatus::Enabled)
+        .map(|c| CampaignPacing {
+            pacing: compute_pacing(c.daily_budget, c.metrics.cost, today),
+            id: c.id.clone(),
+            platform: c.platform.clone(),
+            name: c.name.clone(),
+            daily_budget: c.daily_budget,
+        })
+        .filter(|c| c.pacing.status != PaceStatus::OnPace)
+        .collect();
+    let off_pace = |c: &CampaignPacing| (c.pacing.pace_percentage - 100.0).abs();
+    pacing_violations.sort_by(|a, b| off_pace(b).total_cmp(&off_pace(a)));
+
+    Digest {
+        period,
+        generated_at: Utc::now(),
+        current: Period { start, end: today, days_with_data: current_days.len() },
+        previous: Period {
+            start: previous_start,
+            end: previous_end,
+            days_with_data: previous_days.len(),
+        },
+        spend: spend
+            .into_iter()
+            .map(|(currency, (current, previous))| {
+                (currency, MetricDelta::new(current, previous, false))
+            })
+            .collect(),
+        top_movers: movers,
+        new_campaigns,
+        removed_campaigns,
+        alerts,
+        pacing_violations,
+    }
+}
+
+/// GET /digest - What changed over the last day or week
+pub async fn get_digest(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DigestQuery>,
+) -> Result<Json<Digest>, ApiError> {
+    let top = query.top.unwrap_or(DEFAULT_TOP_MOVERS);
+    if top == 0 || top > MAX_TOP_MOVERS {
+        return Err(ApiError::Validation(format!("top must be between 1 and {}", MAX_TOP_MOVERS)));
+    }
+
+    let campaigns = aggregate_campaigns(&state).await;
+    let digest = build_digest(
+        &state.metrics_store,
+        state.alerts.history(),
+        &campaigns,
+        query.period,
+        timezones::today(),
+        top,
+    );
+    Ok(Json(digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    fn with_cost(campaign: &Campaign, cost: f64) -> Campaign {
+        let mut campaign = campaign.clone();
+        campaign.metrics.cost = cost;
+        campaign
+    }
+
+    #[test]
+    fn test_daily_digest_compares_today_with_yesterday() {
+        let store = MetricsStore::default();
+        let steady = Campaign::sample("1", Platform::Google);
+        let surging = Campaign::sample("2", Platform::Google);
+        let launched = Campaign::sample("3", Platform::Meta);
+        let mut removed = Campaign::sample("4", Platform::Meta);
+
+        store.record(day(12), &[with_cost(&steady, 100.0), with_cost(&surging, 100.0)]);
+        store.record(day(13), &[with_cost(&steady, 200.0), with_cost(&surging, 150.0)]);
+        store.record(day(13), &[with_cost(&removed, 50.0)]);
+        removed.status = CampaignStatus::Removed;
+        store.record(
+            day(14),
+            &[
+                with_cost(&steady, 300.0),
+                with_cost(&surging, 400.0),
+                with_cost(&launched, 30.0),
+                with_cost(&removed, 50.0),
+            ],
+        );
+
+        let digest = build_digest(&store, Vec::new(), &[], DigestPeriod::Daily, day(14), 2);
+
+        let brl = Currency::new("BRL");
+        assert_eq!((digest.spend[&brl].current, digest.spend[&brl].previous), (380.0, 200.0));
+        let movers: Vec<&str> =
+            digest.top_movers[&brl].iter().map(|m| m.campaign.id.as_str()).collect();
+        assert_eq!(movers, ["2", "4"]);
+        assert_eq!(digest.top_movers[&brl][0].spend.absolute, 200.0);
+        assert_eq!(digest.new_campaigns.len(), 1);
+        assert_eq!(digest.new_campaigns[0].id, "3");
+        assert_eq!(digest.removed_campaigns[0].id, "4");
+        assert_eq!(digest.previous.start, day(13));
+    }
+}
@@ -0,0 +1,721 @@
+//! # Entity Cache Example
+//!
+//! Caches normalized entities at the adapter level instead of whole
+//! responses: campaigns by `(platform, id)`, metrics by `(platform, id,
+//! date)`, and for each platform only the IDs of its last campaign list.
+//! Every registered connector is wrapped in a `CachingConnector`, so the
+//! list endpoints, detail endpoints and graph hydration all read the same
+//! entries.
+//!
+//! A list is served from cache while its ID list and every member are
+//! fresh (`cache.campaigns_ttl_secs`). Mutations invalidate precisely:
+//! a budget, status or name change drops that campaign and its metrics,
+//! so only its platform is fetched again; creating or removing a campaign
+//! also drops the platform's ID list. A TTL of 0 disables the cache.
+//!
+//! With `cache.campaigns_stale_secs`, a list past its TTL but within that
+//! window is still answered at once, as stale-while-revalidate: the
+//! request that finds it stale starts one background refresh for the
+//! platform, and later requests get the refreshed list. Invalidated
+//! entries are gone, never stale, so a mutation is still read back.
+//!
+//! With shared state (feature `redis`), campaign lists are also kept in
+//! Redis for the same TTL: a replica missing a list reads the one another
+//! replica fetched, and a mutation on any replica drops its platform's
+//! shared list.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use super::accounts::AccountNode;
+use super::auction::AuctionInsights;
+use super::audiences::AudienceInfo;
+use super::bidding::{Bidding, BiddingChange};
+use super::breakdowns::{Dimension, SegmentRow};
+use super::campaign_aggregator::{Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::connectors::{ConnectorError, PlatformConnector};
+use super::conversions::ConversionActionRow;
+use super::freshness::{self, DataSource};
+use super::hydration::{Ad, AdGroup};
+use super::ids::{AdGroupId, CampaignId, GlobalCampaignId};
+use super::launch::LaunchPayload;
+use super::negatives::{Exclusion, ExclusionUpdate};
+use super::products::ProductRow;
+use super::quality::QualityDiagnostics;
+#[cfg(feature = "redis")]
+use super::shared::SharedStore;
+use super::spec::{AdGroupSpec, CampaignSpec};
+use super::targeting::Targeting;
+
+struct Entry<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+impl<T> Entry<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            fetched_at: Instant::now(),
+        }
+    }
+}
+
+/// Normalized entities shared by every reader of the connectors
+#[derive(Default)]
+pub struct EntityCache {
+    ttl: Duration,
+    /// How long past `ttl` a campaign list is served while refreshed
+    stale: Duration,
+    campaigns: RwLock<HashMap<GlobalCampaignId, Entry<Campaign>>>,
+    metrics: RwLock<HashMap<(GlobalCampaignId, NaiveDate), Entry<CampaignMetrics>>>,
+    /// IDs of each platform's last campaign list, in platform order
+    lists: RwLock<HashMap<Platform, Entry<Vec<CampaignId>>>>,
+    /// Platforms whose stale list is being fetched again
+    refreshing: Mutex<HashSet<Platform>>,
+    /// Campaign lists shared with the other replicas
+    #[cfg(feature = "redis")]
+    shared: Option<Arc<SharedStore>>,
+}
+
+impl EntityCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            ..Default::default()
+        }
+    }
+
+    /// Share campaign lists with the other replicas through `shared`
+    #[cfg(feature = "redis")]
+    pub fn with_shared(mut self, shared: Arc<SharedStore>) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// Serve campaign lists up to `stale` past their TTL while they are
+    /// fetched again in the background
+    pub fn with_stale_while_revalidate(mut self, stale: Duration) -> Self {
+        self.stale = stale;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    fn fresh<T>(&self, entry: &Entry<T>) -> bool {
+        entry.fetched_at.elapsed() < self.ttl
+    }
+
+    /// Fresh, or stale but still servable
+    fn usable(&self, fetched_at: Instant) -> bool {
+        self.is_enabled() && fetched_at.elapsed() < self.ttl + self.stale
+    }
+
+    /// Cached campaign, while fresh
+    pub fn campaign(&self, platform: &Platform, id: &CampaignId) -> Option<Campaign> {
+        let campaigns = self.campaigns.read().unwrap();
+        let entry = campaigns.get(&GlobalCampaignId::new(platform.clone(), id.clone()))?;
+        self.fresh(entry).then(|| entry.value.clone())
+    }
+
+    /// Fresh campaign with `id` on any platform
+    pub fn find_campaign(&self, id: &str) -> Option<Campaign> {
+        let campaigns = self.campaigns.read().unwrap();
+        campaigns
+            .iter()
+            .find(|(key, entry)| key.id == id && self.fresh(entry))
+            .map(|(_, entry)| entry.value.clone())
+    }
+
+    /// `platform`'s campaign list, while the list and all its members are fresh
+    pub fn list(&self, platform: &Platform) -> Option<Vec<Campaign>> {
+        self.list_where(platform, |fetched_at| fetched_at.elapsed() < self.ttl)
+    }
+
+    /// `platform`'s campaign list once past its TTL, while within the stale
+    /// window; `None` while fresh
+    pub fn stale_list(&self, platform: &Platform) -> Option<Vec<Campaign>> {
+        if self.list(platform).is_some() {
+            return None;
+        }
+        self.list_where(platform, |fetched_at| self.usable(fetched_at))
+    }
+
+    /// `platform`'s last campaign list whatever its age, with when it was
+    /// fetched, while all its members are still held; for requests that
+    /// cannot wait for the platform
+    pub fn last_list(&self, platform: &Platform) -> Option<(Vec<Campaign>, DateTime<Utc>)> {
+        let campaigns = self.list_where(platform, |_| true)?;
+        let lists = self.lists.read().unwrap();
+        let age = chrono::Duration::from_std(lists.get(platform)?.fetched_at.elapsed()).ok()?;
+        Some((campaigns, Utc::now() - age))
+    }
+
+    fn list_where(
+        &self,
+        platform: &Platform,
+        keep: impl Fn(Instant) -> bool,
+    ) -> Option<Vec<Campaign>> {
+        let ids = {
+            let lists = self.lists.read().unwrap();
+            let entry = lists.get(platform)?;
+            if !keep(entry.fetched_at) {
+                return None;
+            }
+            entry.value.clone()
+        };
+
+        let campaigns = self.campaigns.read().unwrap();
+        ids.iter()
+            .map(|id| {
+                let entry = campaigns.get(&GlobalCampaignId::new(platform.clone(), id.clone()))?;
+                keep(entry.fetched_at).then(|| entry.value.clone())
+            })
+            .collect()
+    }
+
+    /// Claim `platform`'s background refresh; false while one is running
+    pub fn begin_refresh(&self, platform: &Platform) -> bool {
+        self.refreshing.lock().unwrap().insert(platform.clone())
+    }
+
+    pub fn end_refresh(&self, platform: &Platform) {
+        self.refreshing.lock().unwrap().remove(platform);
+    }
+
+    /// When `platform`'s campaign list was fetched, while servable
+    pub fn list_fetched_at(&self, platform: &Platform) -> Option<DateTime<Utc>> {
+        let lists = self.lists.read().unwrap();
+        let entry = lists.get(platform).filter(|entry| self.usable(entry.fetched_at))?;
+        let age = chrono::Duration::from_std(entry.fetched_at.elapsed()).ok()?;
+        Some(Utc::now() - age)
+    }
+
+    pub fn metrics(
+        &self,
+        platform: &Platform,
+        id: &CampaignId,
+        date: NaiveDate,
+    ) -> Option<CampaignMetrics> {
+        let metrics = self.metrics.read().unwrap();
+        let entry = metrics.get(&(GlobalCampaignId::new(platform.clone(), id.clone()), date))?;
+        self.fresh(entry).then(|| entry.value.clone())
+    }
+
+    /// Store a complete campaign list; each campaign's metrics are cached
+    /// for `date`. Expired entries are dropped on the way.
+    pub fn put_list(&self, platform: &Platform, campaigns: &[Campaign], date: NaiveDate) {
+        {
+            let mut cached = self.campaigns.write().unwrap();
+            cached.retain(|_, entry| self.usable(entry.fetched_at));
+            for campaign in campaigns {
+                cached.insert(campaign.global_id(), Entry::new(campaign.clone()));
+            }
+        }
+        {
+            let mut metrics = self.metrics.write().unwrap();
+            metrics.retain(|_, entry| self.fresh(entry));
+            for campaign in campaigns {
+                let key = (campaign.global_id(), date);
+                metrics.insert(key, Entry::new(campaign.metrics.clone()));
+            }
+        }
+
+        let ids = campaigns.iter().map(|c| c.id.clone()).collect();
+        self.lists.write().unwrap().insert(platform.clone(), Entry::new(ids));
+    }
+
+    pub fn put_metrics(
+        &self,
+        platform: &Platform,
+        id: &CampaignId,
+        date: NaiveDate,
+        metrics: CampaignMetrics,
+    ) {
+        let key = (GlobalCampaignId::new(platform.clone(), id.clone()), date);
+        self.metrics.write().unwrap().insert(key, Entry::new(metrics));
+    }
+
+    /// Drop a campaign and all its metrics
+    pub fn invalidate_campaign(&self, platform: &Platform, id: &CampaignId) {
+        let key = GlobalCampaignId::new(platform.clone(), id.clone());
+        self.campaigns.write().unwrap().remove(&key);
+        self.metrics.write().unwrap().retain(|(campaign, _), _| *campaign != key);
+    }
+
+    /// Drop `platform`'s ID list, keeping its entities
+    pub fn invalidate_list(&self, platform: &Platform) {
+        self.lists.write().unwrap().remove(platform);
+    }
+
+    /// `platform`'s list as shared by a replica, kept locally from then on
+    #[cfg(feature = "redis")]
+    pub async fn shared_list(&self, platform: &Platform) -> Option<Vec<Campaign>> {
+        let shared = self.shared.as_ref()?;
+        match shared.get::<Vec<Campaign>>(&shared_list_key(platform)).await {
+            Ok(campaigns) => {
+                let campaigns = campaigns?;
+                self.put_list(platform, &campaigns, Utc::now().date_naive());
+                Some(campaigns)
+            }
+            Err(e) => {
+                let platform = platform.as_str();
+                tracing::warn!(platform, error = %e, "shared cache read failed");
+                None
+            }
+        }
+    }
+
+    /// Share a complete campaign list with the other replicas
+    #[cfg(feature = "redis")]
+    pub async fn share_list(&self, platform: &Platform, campaigns: &[Campaign]) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        if let Err(e) = shared.set(&shared_list_key(platform), &campaigns, self.ttl).await {
+            let platform = platform.as_str();
+            tracing::warn!(platform, error = %e, "shared cache write failed");
+        }
+    }
+
+    /// Drop `platform`'s shared list, for every replica
+    #[cfg(feature = "redis")]
+    pub async fn invalidate_shared(&self, platform: &Platform) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        if let Err(e) = shared.delete(&shared_list_key(platform)).await {
+            // Other replicas serve the list until it expires
+            let platform = platform.as_str();
+            tracing::warn!(platform, error = %e, "shared cache invalidation failed");
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+fn shared_list_key(platform: &Platform) -> String {
+    format!("campaigns:{}", platform.as_str())
+}
+
+/// Connector decorator reading through the entity cache
+pub struct CachingConnector {
+    pub inner: Arc<dyn PlatformConnector>,
+    pub cache: Arc<EntityCache>,
+}
+
+#[async_trait]
+impl PlatformConnector for CachingConnector {
+    fn platform(&self) -> Platform {
+        self.inner.platform()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        let platform = self.inner.platform();
+        if let Some(campaigns) = self.cache.list(&platform) {
+            let fetched_at = self.cache.list_fetched_at(&platform);
+            freshness::record(&platform, DataSource::Cache, fetched_at);
+            return Ok(campaigns);
+        }
+        #[cfg(feature = "redis")]
+        if let Some(campaigns) = self.cache.shared_list(&platform).await {
+            freshness::record(&platform, DataSource::Cache, Some(Utc::now()));
+            return Ok(campaigns);
+        }
+        if let Some(campaigns) = self.cache.stale_list(&platform) {
+            let fetched_at = self.cache.list_fetched_at(&platform);
+            if self.cache.begin_refresh(&platform) {
+                let (inner, cache) = (self.inner.clone(), self.cache.clone());
+                let platform = platform.clone();
+                tokio::spawn(async move {
+                    match inner.fetch_campaigns().await {
+                        Ok(campaigns) => {
+                            cache.put_list(&platform, &campaigns, Utc::now().date_naive());
+                            #[cfg(feature = "redis")]
+                            cache.share_list(&platform, &campaigns).await;
+                        }
+                        Err(e) => {
+                            // The stale list is served until the window ends
+                            let platform = platform.as_str();
+                            tracing::warn!(platform, error = %e, "background refresh failed");
+                        }
+                    }
+                    cache.end_refresh(&platform);
+                });
+            }
+            freshness::record(&platform, DataSource::Cache, fetched_at);
+            return Ok(campaigns);
+        }
+
+        let campaigns = self.inner.fetch_campaigns().await?;
+        self.cache.put_list(&platform, &campaigns, Utc::now().date_naive());
+        #[cfg(feature = "redis")]
+        self.cache.share_list(&platform, &campaigns).await;
+        Ok(campaigns)
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        let platform = self.inner.platform();
+        let today = Utc::now().date_naive();
+        if let Some(metrics) = self.cache.metrics(&platform, campaign_id, today) {
+            return Ok(metrics);
+        }
+
+        let metrics = self.inner.fetch_metrics(campaign_id).await?;
+        self.cache.put_metrics(&platform, campaign_id, today, metrics.clone());
+        Ok(metrics)
+    }
+
+    // A failed mutation may still have been applied upstream (e.g. on a
+    // timeout), so mutations invalidate whatever their outcome
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        let platform = self.inner.platform();
+        let removed = status == CampaignStatus::Removed;
+        let result = self.inner.mutate_status(campaign_id, status).await;
+
+        self.cache.invalidate_campaign(&platform, campaign_id);
+        if removed {
+            self.cache.invalidate_list(&platform);
+        }
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&platform).await;
+        result
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        self.inner.health_check().await
+    }
+
+    async fn fetch_ad_groups(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<Vec<AdGroup>, ConnectorError> {
+        self.inner.fetch_ad_groups(campaign_ids).await
+    }
+
+    async fn fetch_audiences_by_id(
+        &self,
+        audience_ids: &[String],
+    ) -> Result<Vec<AudienceInfo>, ConnectorError> {
+        self.inner.fetch_audiences_by_id(audience_ids).await
+    }
+
+    async fn mutate_budget(
+        &self,
+        campaign_id: &CampaignId,
+        daily_budget: f64,
+    ) -> Result<(), ConnectorError> {
+        let result = self.inner.mutate_budget(campaign_id, daily_budget).await;
+        self.cache.invalidate_campaign(&self.inner.platform(), campaign_id);
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&self.inner.platform()).await;
+        result
+    }
+
+    async fn fetch_ads(&self, ad_group_ids: &[AdGroupId]) -> Result<Vec<Ad>, ConnectorError> {
+        self.inner.fetch_ads(ad_group_ids).await
+    }
+
+    async fn rename_campaign(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let result = self.inner.rename_campaign(campaign_id, name).await;
+        self.cache.invalidate_campaign(&self.inner.platform(), campaign_id);
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&self.inner.platform()).await;
+        result
+    }
+
+    async fn create_campaign(&self, campaign: &CampaignSpec) -> Result<CampaignId, ConnectorError> {
+        let result = self.inner.create_campaign(campaign).await;
+        self.cache.invalidate_list(&self.inner.platform());
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&self.inner.platform()).await;
+        result
+    }
+
+    async fn launch_campaign(&self, payload: &LaunchPayload) -> Result<CampaignId, ConnectorError> {
+        let result = self.inner.launch_campaign(payload).await;
+        self.cache.invalidate_list(&self.inner.platform());
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&self.inner.platform()).await;
+        result
+    }
+
+    async fn create_ad_group(
+        &self,
+        campaign_id: &CampaignId,
+        ad_group: &AdGroupSpec,
+    ) -> Result<AdGroupId, ConnectorError> {
+        self.inner.create_ad_group(campaign_id, ad_group).await
+    }
+
+    async fn update_ad_group(&self, ad_group: &AdGroupSpec) -> Result<(), ConnectorError> {
+        self.inner.update_ad_group(ad_group).await
+    }
+
+    async fn fetch_targeting(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Targeting>, ConnectorError> {
+        self.inner.fetch_targeting(campaign_ids).await
+    }
+
+    async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+        self.inner.refresh_credentials().await
+    }
+
+    async fn fetch_bidding(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Bidding>, ConnectorError> {
+        self.inner.fetch_bidding(campaign_ids).await
+    }
+
+    async fn mutate_bidding(
+        &self,
+        campaign_id: &CampaignId,
+        change: &BiddingChange,
+    ) -> Result<(), ConnectorError> {
+        self.inner.mutate_bidding(campaign_id, change).await
+    }
+
+    async fn fetch_quality(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, QualityDiagnostics>, ConnectorError> {
+        self.inner.fetch_quality(campaign_ids).await
+    }
+
+    async fn fetch_auction_insights(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, AuctionInsights>, ConnectorError> {
+        self.inner.fetch_auction_insights(campaign_ids).await
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        campaign_id: &CampaignId,
+        dimension: Dimension,
+    ) -> Result<Vec<SegmentRow>, ConnectorError> {
+        self.inner.fetch_breakdown(campaign_id, dimension).await
+    }
+
+    async fn fetch_products(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ProductRow>, ConnectorError> {
+        self.inner.fetch_products(campaign_id).await
+    }
+
+    async fn add_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let result = self.inner.add_label(campaign_id, name).await;
+        self.cache.invalidate_campaign(&self.inner.platform(), campaign_id);
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&self.inner.platform()).await;
+        result
+    }
+
+    async fn remove_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        let result = self.inner.remove_label(campaign_id, name).await;
+        self.cache.invalidate_campaign(&self.inner.platform(), campaign_id);
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&self.inner.platform()).await;
+        result
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        // Never cached: it is read to compare against the cached campaign
+        self.inner.fetch_raw_campaign(campaign_id).await
+    }
+
+    async fn fetch_changed_campaigns(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Campaign>, ConnectorError> {
+        let platform = self.inner.platform();
+        let campaigns = self.inner.fetch_changed_campaigns(since).await?;
+        if campaigns.is_empty() {
+            return Ok(campaigns);
+        }
+
+        // Changed elsewhere, like a mutation: the campaigns are read again,
+        // and the list too as some may be new
+        for campaign in &campaigns {
+            self.cache.invalidate_campaign(&platform, &campaign.id);
+        }
+        self.cache.invalidate_list(&platform);
+        #[cfg(feature = "redis")]
+        self.cache.invalidate_shared(&platform).await;
+        Ok(campaigns)
+    }
+
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        self.inner.fetch_accounts().await
+    }
+
+    async fn fetch_conversion_actions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ConversionActionRow>, ConnectorError> {
+        self.inner.fetch_conversion_actions(campaign_id).await
+    }
+
+    async fn fetch_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<Exclusion>, ConnectorError> {
+        self.inner.fetch_exclusions(campaign_id).await
+    }
+
+    async fn update_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+        update: &ExclusionUpdate,
+    ) -> Result<(), ConnectorError> {
+        self.inner.update_exclusions(campaign_id, update).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves two campaigns and counts upstream calls
+    #[derive(Default)]
+    struct Counting {
+        list_calls: AtomicUsize,
+        metrics_calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PlatformConnector for Counting {
+        fn platform(&self) -> Platform {
+            Platform::Google
+        }
+
+        async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+            self.list_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![
+                Campaign::sample("1", Platform::Google),
+                Campaign::sample("2", Platform::Google),
+            ])
+        }
+
+        async fn fetch_metrics(
+            &self,
+            campaign_id: &CampaignId,
+        ) -> Result<CampaignMetrics, ConnectorError> {
+            self.metrics_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Campaign::sample(campaign_id, Platform::Google).metrics)
+        }
+
+        async fn mutate_status(
+            &self,
+            _campaign_id: &CampaignId,
+            _status: CampaignStatus,
+        ) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+
+        async fn mutate_budget(
+            &self,
+            _campaign_id: &CampaignId,
+            _daily_budget: f64,
+        ) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+    }
+
+    fn caching(ttl: Duration) -> (Arc<Counting>, CachingConnector, Arc<EntityCache>) {
+        let inner = Arc::new(Counting::default());
+        let cache = Arc::new(EntityCache::new(ttl));
+        let connector = CachingConnector {
+            inner: inner.clone(),
+            cache: cache.clone(),
+        };
+        (inner, connector, cache)
+    }
+
+    #[tokio::test]
+    async fn test_list_and_metrics_share_cached_entities() {
+        let (inner, connector, cache) = caching(Duration::from_secs(60));
+
+        assert_eq!(connector.fetch_campaigns().await.unwrap().len(), 2);
+        assert_eq!(connector.fetch_campaigns().await.unwrap().len(), 2);
+        connector.fetch_metrics(&"1".into()).await.unwrap();
+
+        assert_eq!(inner.list_calls.load(Ordering::SeqCst), 1);
+        // Metrics came with the list
+        assert_eq!(inner.metrics_calls.load(Ordering::SeqCst), 0);
+        assert!(cache.find_campaign("2").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_mutation_invalidates_only_its_campaign() {
+        let (inner, connector, cache) = caching(Duration::from_secs(60));
+        connector.fetch_campaigns().await.unwrap();
+
+        connector.mutate_budget(&"1".into(), 80.0).await.unwrap();
+
+        assert!(cache.campaign(&Platform::Google, &"1".into()).is_none());
+        assert!(cache.campaign(&Platform::Google, &"2".into()).is_some());
+        connector.fetch_metrics(&"1".into()).await.unwrap();
+        assert_eq!(inner.metrics_calls.load(Ordering::SeqCst), 1);
+
+        // The list misses a member, so it is fetched again
+        connector.fetch_campaigns().await.unwrap();
+        assert_eq!(inner.list_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_never_serves_from_cache() {
+        let (inner, connector, cache) = caching(Duration::ZERO);
+
+        connector.fetch_campaigns().await.unwrap();
+        connector.fetch_campaigns().await.unwrap();
+
+        assert!(!cache.is_enabled());
+        assert_eq!(inner.list_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_list_served_while_refreshed() {
+        let inner = Arc::new(Counting::default());
+        let cache = Arc::new(
+            EntityCache::new(Duration::from_millis(50))
+                .with_stale_while_revalidate(Duration::from_secs(60)),
+        );
+        let connector = CachingConnector {
+            inner: inner.clone(),
+            cache: cache.clone(),
+        };
+        connector.fetch_campaigns().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // Answered from the stale list, with one refresh in the background
+        assert!(cache.list(&Platform::Google).is_none());
+        assert_eq!(connector.fetch_campaigns().await.unwrap().len(), 2);
+        assert_eq!(connector.fetch_campaigns().await.unwrap().len(), 2);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert_eq!(inner.list_calls.load(Ordering::SeqCst), 2);
+        assert!(cache.list(&Platform::Google).is_some());
+    }
+}
@@ -0,0 +1,294 @@
+//! # API Error Example
+//!
+//! Every handler fails with an `ApiError`, rendered as an RFC 7807
+//! `application/problem+json` body. The `code` member is stable and meant
+//! for clients to branch on; `detail` is human-readable and may change.
+//! Errors reported by an ad platform use the codes of `platform_errors`
+//! and add `remediation` and `upstream_code` members.
+//!
+//! ```json
+//! {
+//!   "type": "/errors/gateway_unavailable",
+//!   "title": "Gateway unavailable",
+//!   "status": 502,
+//!   "code": "gateway_unavailable",
+//!   "detail": "gateway request failed: connection refused"
+//! }
+//! ```
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+use super::call_budget::BudgetExceeded;
+use super::connectors::ConnectorError;
+use super::platform_errors::PlatformError;
+
+/// `Clone` so requests sharing one fetch each get its error, see `coalesce`
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0} not found")]
+    NotFound(String),
+    /// Request body or query parameters failed validation
+    #[error("{0}")]
+    Validation(String),
+    /// Request conflicts with the current state of the resource
+    #[error("{0}")]
+    Conflict(String),
+    #[error("authentication required")]
+    Unauthorized,
+    #[error("{0}")]
+    Forbidden(String),
+    /// Blocked by an active change freeze window
+    #[error("{0}")]
+    ChangeFrozen(String),
+    /// Would push a tenant's projected monthly spend over its hard cap
+    #[error("{0}")]
+    SpendCapExceeded(String),
+    /// Throttled by us or by an upstream platform
+    #[error("rate limited")]
+    RateLimited { retry_after: Option<u64> },
+    /// Shed under load, see `load_shedding`
+    #[error("service overloaded, retry later")]
+    Overloaded { retry_after: u64 },
+    #[error(transparent)]
+    BudgetExceeded(#[from] BudgetExceeded),
+    #[error("{0} is not supported by this platform")]
+    NotSupported(String),
+    /// API version past its sunset date, see `versioning`
+    #[error("{0}")]
+    Gone(String),
+    /// Rejected by the ad platform; carries the mapped platform code
+    #[error(transparent)]
+    Platform(PlatformError),
+    #[error("gateway request failed: {0}")]
+    Gateway(String),
+    #[error("gateway did not answer in time")]
+    GatewayTimeout,
+    #[error("{0}")]
+    Internal(String),
+}
+
+/// `application/problem+json` body
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub code: String,
+    pub detail: String,
+    /// Seconds to wait before retrying, for rate limits and shed requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    /// What to do about a platform error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// Platform's own error code, for support tickets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_code: Option<String>,
+}
+
+impl ApiError {
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::ChangeFrozen(_) => "change_frozen",
+            ApiError::SpendCapExceeded(_) => "spend_cap_exceeded",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::Overloaded { .. } => "overloaded",
+            ApiError::BudgetExceeded(_) => "upstream_call_budget_exceeded",
+            ApiError::NotSupported(_) => "not_supported",
+            ApiError::Gone(_) => "api_version_sunset",
+            ApiError::Platform(e) => e.code.as_str(),
+            ApiError::Gateway(_) => "gateway_unavailable",
+            ApiError::GatewayTimeout => "gateway_timeout",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Validation(_)
+            | ApiError::BudgetExceeded(_)
+            | ApiError::SpendCapExceeded(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::ChangeFrozen(_) => StatusCode::LOCKED,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Overloaded { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::NotSupported(_) => StatusCode::NOT_IMPLEMENTED,
+            ApiError::Gone(_) => StatusCode::GONE,
+            ApiError::Platform(e) => {
+                StatusCode::from_u16(e.code.status()).unwrap_or(StatusCode::BAD_GATEWAY)
+            }
+            ApiError::Gateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::GatewayTimeout => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "Resource not found",
+            ApiError::Validation(_) => "Invalid request",
+            ApiError::Conflict(_) => "Conflict",
+            ApiError::Unauthorized => "Unauthorized",
+            ApiError::Forbidden(_) => "Forbidden",
+            ApiError::ChangeFrozen(_) => "Change freeze active",
+            ApiError::SpendCapExceeded(_) => "Spend cap exceeded",
+            ApiError::RateLimited { .. } => "Too many requests",
+            ApiError::Overloaded { .. } => "Service overloaded",
+            ApiError::BudgetExceeded(_) => "Upstream call budget exceeded",
+            ApiError::NotSupported(_) => "Not supported",
+            ApiError::Gone(_) => "API version sunset",
+            ApiError::Platform(_) => "Rejected by ad platform",
+            ApiError::Gateway(_) => "Gateway unavailable",
+            ApiError::GatewayTimeout => "Gateway timeout",
+            ApiError::Internal(_) => "Internal error",
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            ApiError::BudgetExceeded(e) => format!(
+                "{}. Narrow the request (filter by platform or campaign, request fewer fields) or split it into smaller requests.",
+                e
+            ),
+            // Internal details stay in the logs
+            ApiError::Internal(_) => "unexpected error".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        Problem {
+            problem_type: format!("/errors/{}", self.code()),
+            title: self.title().to_string(),
+            status: self.status().as_u16(),
+            code: self.code().to_string(),
+            detail: self.detail(),
+            retry_after: self.retry_after(),
+            remediation: match self {
+                ApiError::Platform(e) => Some(e.code.remediation().to_string()),
+                _ => None,
+            },
+            upstream_code: match self {
+                ApiError::Platform(e) => Some(e.upstream_code.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            ApiError::RateLimited { retry_after } => *retry_after,
+            ApiError::Overloaded { retry_after } => Some(*retry_after),
+            ApiError::Platform(e) => e.retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status().is_server_error() {
+            tracing::error!(code = self.code(), error = %self, "request failed");
+        }
+
+        let mut response = (self.status(), Json(self.problem())).into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        if let Some(secs) = self.retry_after() {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+
+        response
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return ApiError::GatewayTimeout;
+        }
+
+        match e.status() {
+            Some(reqwest::StatusCode::TOO_MANY_REQUESTS) => ApiError::RateLimited { retry_after: None },
+            Some(reqwest::StatusCode::NOT_FOUND) => ApiError::NotFound("upstream resource".to_string()),
+            _ => ApiError::Gateway(e.to_string()),
+        }
+    }
+}
+
+impl From<ConnectorError> for ApiError {
+    fn from(e: ConnectorError) -> Self {
+        match e {
+            ConnectorError::Http(e) => e.into(),
+            ConnectorError::NotFound(id) => ApiError::NotFound(format!("campaign {}", id)),
+            ConnectorError::NotSupported(what) => ApiError::NotSupported(what.to_string()),
+            ConnectorError::BudgetExceeded(e) => ApiError::BudgetExceeded(e),
+            ConnectorError::Platform(e) => ApiError::Platform(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+    use super::super::platform_errors;
+
+    #[test]
+    fn test_problem_body_carries_stable_code() {
+        let problem = ApiError::NotFound("campaign 42".to_string()).problem();
+
+        assert_eq!(problem.status, 404);
+        assert_eq!(problem.code, "not_found");
+        assert_eq!(problem.problem_type, "/errors/not_found");
+        assert_eq!(problem.detail, "campaign 42 not found");
+    }
+
+    #[test]
+    fn test_response_uses_problem_content_type() {
+        let response = ApiError::RateLimited { retry_after: Some(30) }.into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/problem+json");
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+
+    #[test]
+    fn test_connector_errors_map_to_api_errors() {
+        let err: ApiError = ConnectorError::NotSupported("ad lookup").into();
+        assert_eq!(err.status(), StatusCode::NOT_IMPLEMENTED);
+
+        let err: ApiError = ConnectorError::NotFound("7".to_string()).into();
+        assert_eq!(err.code(), "not_found");
+    }
+
+    #[test]
+    fn test_platform_errors_expose_stable_code_and_remediation() {
+        let body = r#"{"error":{"message":"Calls limit reached","code":17}}"#;
+        let err = ApiError::Platform(platform_errors::translate(&Platform::Meta, 400, Some(60), body));
+
+        let problem = err.problem();
+        assert_eq!(err.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(problem.code, "rate_limited");
+        assert_eq!(problem.upstream_code.as_deref(), Some("17"));
+        assert!(problem.remediation.is_some());
+        assert_eq!(err.into_response().headers()[header::RETRY_AFTER], "60");
+    }
+}
@@ -0,0 +1,124 @@
+//! # ETag Example
+//!
+//! Dashboards poll `GET /campaigns` every few seconds and mostly get back
+//! the same multi-megabyte list. Responses wrapped in `Conditional` carry
+//! a strong ETag computed from the serialized body (after filtering and
+//! field selection), and a request whose `If-None-Match` matches gets an
+//! empty `304 Not Modified` instead of the body.
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::error::ApiError;
+use super::timing;
+
+/// Hex digits of the body hash kept in the tag
+const ETAG_HEX_LEN: usize = 32;
+
+/// Quoted strong ETag of a response body
+pub fn etag_for(body: &[u8]) -> String {
+    let digest = hex::encode(Sha256::digest(body));
+    format!("\"{}\"", &digest[..ETAG_HEX_LEN])
+}
+
+/// Whether an `If-None-Match` value matches `etag`
+///
+/// Uses weak comparison as RFC 9110 requires for `If-None-Match`: a
+/// `W/` prefix is ignored, and `*` matches any current representation.
+pub fn if_none_match_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// JSON response honoring `If-None-Match`
+pub struct Conditional<T> {
+    pub body: T,
+    pub if_none_match: Option<String>,
+}
+
+impl<T> Conditional<T> {
+    pub fn new(body: T, headers: &HeaderMap) -> Self {
+        Self {
+            body,
+            if_none_match: headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Conditional<T> {
+    fn into_response(self) -> Response {
+        let body = match timing::time("serialize", || serde_json::to_vec(&self.body)) {
+            Ok(body) => body,
+            Err(e) => return ApiError::Internal(e.to_string()).into_response(),
+        };
+        let etag = etag_for(&body);
+
+        let not_modified = self
+            .if_none_match
+            .as_deref()
+            .is_some_and(|value| if_none_match_matches(value, &etag));
+
+        let mut response = if not_modified {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            (
+                [(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+                body,
+            )
+                .into_response()
+        };
+
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            headers.insert(header::ETAG, value);
+        }
+        // Caches may store the list but must revalidate every time
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditional(if_none_match: Option<&str>) -> Conditional<serde_json::Value> {
+        Conditional {
+            body: serde_json::json!([{ "id": "1", "name": "Brand" }]),
+            if_none_match: if_none_match.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_matching_if_none_match_returns_304() {
+        let first = conditional(None).into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let second = conditional(Some(&etag)).into_response();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[header::ETAG], etag.as_str());
+
+        let stale = conditional(Some("\"0000\"")).into_response();
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_if_none_match_accepts_lists_weak_tags_and_wildcard() {
+        let etag = etag_for(b"[]");
+
+        assert!(if_none_match_matches(&format!("\"a\", W/{}", etag), &etag));
+        assert!(if_none_match_matches("*", &etag));
+        assert!(!if_none_match_matches("\"a\"", &etag));
+    }
+}
@@ -0,0 +1,212 @@
+//! # Mock Gateway
+//!
+//! Shared by the runnable examples: serves the gateway routes the
+//! connectors call (`/v1/{platform}/...`) from an in-memory campaign list
+//! on an ephemeral port, so the examples exercise the real HTTP client,
+//! connectors and handlers without platform credentials.
+//!
+//! - `GET /v1/{platform}/campaigns` and `GET /v1/{platform}/health`
+//! - `POST /v1/{platform}/campaigns/{id}/status` and `.../budget` update
+//!   the stored campaign, so later reads see the change
+//! - `POST /hooks` records the body, as the target of webhook notifiers
+//!
+//! Other gateway routes (bidding, targeting, ...) answer 404, which the
+//! aggregator treats as "not reported by the platform".
+
+#![allow(dead_code)]
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use std::sync::{Arc, RwLock};
+
+use ibvi_core::routes::campaign_aggregator::{
+    AppState, Campaign, CampaignMetrics, CampaignStatus, Platform,
+};
+use ibvi_core::routes::config::AppConfig;
+
+/// Campaigns served and requests received by the mock
+#[derive(Default)]
+pub struct Recorded {
+    pub campaigns: RwLock<Vec<Campaign>>,
+    /// Bodies posted to `/hooks`, oldest first
+    pub hooks: RwLock<Vec<serde_json::Value>>,
+    /// `(platform, campaign_id, body)` of every mutation, oldest first
+    pub mutations: RwLock<Vec<(String, String, serde_json::Value)>>,
+}
+
+/// Running mock gateway
+pub struct MockGateway {
+    pub url: String,
+    pub recorded: Arc<Recorded>,
+}
+
+impl MockGateway {
+    /// Serve `campaigns` until the process exits
+    pub async fn start(campaigns: Vec<Campaign>) -> MockGateway {
+        let recorded = Arc::new(Recorded::default());
+        *recorded.campaigns.write().unwrap() = campaigns;
+
+        let router = Router::new()
+            .route("/v1/{platform}/health", get(health))
+            .route("/v1/{platform}/campaigns", get(list_campaigns))
+            .route("/v1/{platform}/campaigns/{id}/status", post(set_status))
+            .route("/v1/{platform}/campaigns/{id}/budget", post(set_budget))
+            .route("/hooks", post(record_hook))
+            .with_state(recorded.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock gateway");
+        let url = format!("http://{}", listener.local_addr().expect("mock gateway address"));
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("mock gateway stopped");
+        });
+
+        MockGateway { url, recorded }
+    }
+
+    /// URL of the webhook capture endpoint
+    pub fn hooks_url(&self) -> String {
+        format!("{}/hooks", self.url)
+    }
+
+    /// Application state whose connectors all point at this mock
+    pub fn state(&self) -> Arc<AppState> {
+        let mut config = AppConfig::default();
+        config.gateway.url = self.url.clone();
+        config.gateway.service_token = "example".to_string();
+
+        Arc::new(AppState::from_config(config).expect("valid example config"))
+    }
+
+    pub fn hooks(&self) -> Vec<serde_json::Value> {
+        self.recorded.hooks.read().unwrap().clone()
+    }
+
+    pub fn mutations(&self) -> Vec<(String, String, serde_json::Value)> {
+        self.recorded.mutations.read().unwrap().clone()
+    }
+}
+
+async fn health() -> StatusCode {
+    StatusCode::OK
+}
+
+async fn list_campaigns(
+    State(recorded): State<Arc<Recorded>>,
+    Path(platform): Path<String>,
+) -> Json<Vec<Campaign>> {
+    let campaigns = recorded.campaigns.read().unwrap();
+    Json(campaigns.iter().filter(|c| c.platform.as_str() == platform).cloned().collect())
+}
+
+/// Apply `change` to the campaign and record the mutation; 404 when unknown
+fn mutate(
+    recorded: &Recorded,
+    platform: String,
+    id: String,
+    body: serde_json::Value,
+    change: impl FnOnce(&mut Campaign, &serde_json::Value) -> Option<()>,
+) -> StatusCode {
+    let mut campaigns = recorded.campaigns.write().unwrap();
+    let Some(campaign) = campaigns
+        .iter_mut()
+        .find(|c| c.platform.as_str() == platform && c.id == id)
+    else {
+        return StatusCode::NOT_FOUND;
+    };
+    if change(campaign, &body).is_none() {
+        return StatusCode::BAD_REQUEST;
+    }
+
+    recorded.mutations.write().unwrap().push((platform, id, body));
+    StatusCode::OK
+}
+
+async fn set_status(
+    State(recorded): State<Arc<Recorded>>,
+    Path((platform, id)): Path<(String, String)>,
+    Json(body): Json<serde_json::Value>,
+) -> StatusCode {
+    mutate(&recorded, platform, id, body, |campaign, body| {
+        campaign.status = serde_json::from_value(body.get("status")?.clone()).ok()?;
+        Some(())
+    })
+}
+
+async fn set_budget(
+    State(recorded): State<Arc<Recorded>>,
+    Path((platform, id)): Path<(String, String)>,
+    Json(body): Json<serde_json::Value>,
+) -> StatusCode {
+    mutate(&recorded, platform, id, body, |campaign, body| {
+        campaign.daily_budget = body.get("daily_budget")?.as_f64()?;
+        Some(())
+    })
+}
+
+async fn record_hook(
+    State(recorded): State<Arc<Recorded>>,
+    Json(body): Json<serde_json::Value>,
+) -> StatusCode {
+    recorded.hooks.write().unwrap().push(body);
+    StatusCode::OK
+}
+
+/// Enabled campaign with today's delivery
+pub fn campaign(
+    id: &str,
+    platform: Platform,
+    daily_budget: f64,
+    cost: f64,
+    conversions: u32,
+) -> Campaign {
+    Campaign {
+        id: id.into(),
+        platform,
+        name: format!("BR_Imoveis_{}", id),
+        status: CampaignStatus::Enabled,
+        objective: None,
+        campaign_type: None,
+        daily_budget,
+        currency: "BRL".to_string(),
+        metrics: CampaignMetrics {
+            impressions: 10_000,
+            clicks: 300,
+            conversions,
+            cost,
+            ctr: 3.0,
+            cpa: if conversions > 0 { cost / conversions as f64 } else { 0.0 },
+            conversion_value: 0.0,
+            roas: 0.0,
+            conversion_actions: Vec::new(),
+            reach: None,
+            frequency: None,
+            video_views: None,
+            video_view_rate: None,
+            average_watch_time_seconds: None,
+        },
+        pacing: None,
+        targeting: None,
+        bidding: None,
+        quality: None,
+        auction: None,
+        tracking_template: None,
+        account_id: None,
+        labels: Vec::new(),
+        tags: Default::default(),
+    }
+}
+
+/// Two Google campaigns and one Meta campaign, one of them overspending
+pub fn sample_campaigns() -> Vec<Campaign> {
+    vec![
+        campaign("g-100", Platform::Google, 100.0, 80.0, 8),
+        campaign("g-200", Platform::Google, 50.0, 75.0, 3),
+        campaign("m-300", Platform::Meta, 120.0, 90.0, 12),
+    ]
+}
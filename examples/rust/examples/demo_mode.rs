@@ -0,0 +1,65 @@
+//! # Demo Mode Example
+//!
+//! Serves the full API router from generated demo data, with no gateway
+//! and no credentials, and reads it over HTTP: the campaign list, the
+//! summary and the account tree. Extra arguments set the scale and seed,
+//! and the same seed gives the same campaigns.
+//!
+//! ```bash
+//! cargo run --example demo_mode
+//! cargo run --example demo_mode -- --demo-campaigns=5000 --demo-seed=7
+//! ```
+
+use std::sync::Arc;
+
+use ibvi_core::routes::accounts::AccountTree;
+use ibvi_core::routes::campaign_aggregator::{AppState, Campaign};
+use ibvi_core::routes::config::AppConfig;
+use ibvi_core::routes::server::{build_router, ServerOptions};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::iter::once("--demo".to_string()).chain(std::env::args().skip(1));
+    let config = AppConfig::load_with_args(args)?;
+    let per_platform = config.demo.campaigns_per_platform;
+    let platforms = config.demo.platforms.len();
+    let options = ServerOptions::from_config(&config);
+    let router = build_router(Arc::new(AppState::from_config(config)?), &options);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let api = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    let client = reqwest::Client::new();
+
+    // Removed campaigns are listed by /campaigns/archived
+    let campaigns: Vec<Campaign> = client
+        .get(format!("{}/campaigns?include_removed=true", api))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    assert_eq!(campaigns.len(), per_platform * platforms);
+    println!("GET /campaigns: {} generated campaigns", campaigns.len());
+
+    let summary: serde_json::Value = client
+        .get(format!("{}/campaigns/summary", api))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    println!("GET /campaigns/summary: {}", summary["metrics"]);
+
+    let accounts: Vec<AccountTree> = client
+        .get(format!("{}/accounts", api))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    assert_eq!(accounts.len(), platforms);
+    println!("GET /accounts: {} account trees", accounts.len());
+
+    Ok(())
+}
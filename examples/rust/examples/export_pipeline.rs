@@ -0,0 +1,75 @@
+//! # Export Pipeline Example
+//!
+//! Schedules one report per export format against the mock gateway, runs
+//! the scheduler tick that renders every due report, and writes the
+//! artifacts to `$TMPDIR/ibvi-export-pipeline/`. Needs the `reports`
+//! feature (on by default).
+//!
+//! ```bash
+//! cargo run --example export_pipeline
+//! ```
+
+mod common;
+
+use chrono::{Duration, Utc};
+
+use ibvi_core::routes::locale::Locale;
+use ibvi_core::routes::reports::{
+    run_due_reports, ReportFilters, ReportFormat, ReportGrouping, ReportSpec,
+};
+
+use common::MockGateway;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let gateway = MockGateway::start(common::sample_campaigns()).await;
+    let state = gateway.state();
+    let now = Utc::now();
+
+    let formats = [ReportFormat::Csv, ReportFormat::Json, ReportFormat::Pdf];
+    for format in formats {
+        state.reports.insert(ReportSpec {
+            id: format!("daily-{:?}", format).to_lowercase(),
+            name: "Daily spend by platform".to_string(),
+            filters: ReportFilters::default(),
+            metrics: ["cost", "conversions", "cpa", "ctr"].map(String::from).to_vec(),
+            group_by: ReportGrouping::Platform,
+            format,
+            schedule: "0 6 * * *".to_string(),
+            locale: Locale::default(),
+            next_run_at: Some(now - Duration::minutes(1)),
+            created_at: now,
+        });
+    }
+
+    run_due_reports(&state, now).await;
+
+    let dir = std::env::temp_dir().join("ibvi-export-pipeline");
+    std::fs::create_dir_all(&dir)?;
+    for spec in state.reports.list() {
+        let runs = state.reports.runs(&spec.id);
+        assert_eq!(runs.len(), 1, "{} ran {} times", spec.id, runs.len());
+        // Rescheduled to the next 06:00 UTC
+        assert!(spec.next_run_at.is_some_and(|next| next > now));
+
+        let artifact = state
+            .reports
+            .artifact(&spec.id, &runs[0].id)
+            .expect("artifact of the recorded run");
+        // Google and Meta rows
+        assert_eq!(runs[0].rows, 2);
+        match artifact.format {
+            ReportFormat::Csv => assert!(artifact.bytes.starts_with(b"group,")),
+            ReportFormat::Json => {
+                serde_json::from_slice::<serde_json::Value>(&artifact.bytes)?;
+            }
+            ReportFormat::Pdf => assert!(artifact.bytes.starts_with(b"%PDF")),
+        }
+
+        let path = dir.join(&artifact.file_name);
+        std::fs::write(&path, &artifact.bytes)?;
+        println!("{}: {} bytes -> {}", spec.id, artifact.bytes.len(), path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! # Mock Gateway Example
+//!
+//! Serves the full API router against the mock gateway and drives it
+//! over HTTP, the way integration tests run without platform credentials:
+//! readiness, the unified campaign list, the summary, and a bulk budget
+//! change that must reach the gateway once an admin has made the caller a
+//! manager.
+//!
+//! ```bash
+//! cargo run --example mock_gateway
+//! ```
+
+mod common;
+
+use ibvi_core::routes::bulk::BulkReport;
+use ibvi_core::routes::campaign_aggregator::Campaign;
+use ibvi_core::routes::health::Readiness;
+use ibvi_core::routes::server::{build_router, ServerOptions};
+use ibvi_core::routes::warmup;
+
+use common::MockGateway;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let gateway = MockGateway::start(common::sample_campaigns()).await;
+    let state = gateway.state();
+    let router = build_router(state.clone(), &ServerOptions::default());
+    // Warm-up is disabled by default: this only marks the instance ready
+    warmup::run_warmup(state, router.clone()).await;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let api = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    let client = reqwest::Client::new();
+
+    let readiness: Readiness = client.get(format!("{}/readyz", api)).send().await?.json().await?;
+    assert!(readiness.ready, "instance not ready: {:?}", readiness);
+
+    let campaigns: Vec<Campaign> = client
+        .get(format!("{}/campaigns", api))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    assert_eq!(campaigns.len(), 3);
+    println!("GET /campaigns: {} campaigns across platforms", campaigns.len());
+
+    let summary: serde_json::Value = client
+        .get(format!("{}/campaigns/summary", api))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    println!("GET /campaigns/summary: {}", summary);
+
+    let bulk = || {
+        client
+            .post(format!("{}/campaigns/bulk", api))
+            .header("x-actor", "mock-gateway-example")
+            .json(&serde_json::json!({
+                "operations": [
+                    { "type": "set_budget", "platform": "google", "campaign_id": "g-100",
+                      "daily_budget": 150.0 }
+                ]
+            }))
+    };
+
+    // Unassigned actors are viewers
+    let forbidden = bulk().send().await?;
+    assert_eq!(forbidden.status(), reqwest::StatusCode::FORBIDDEN);
+
+    client
+        .put(format!("{}/rbac/assignments", api))
+        .header("x-actor", "ops")
+        .header("x-actor-role", "admin")
+        .json(&serde_json::json!({ "actor": "mock-gateway-example", "role": "manager" }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let report: BulkReport = bulk()
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    assert_eq!((report.succeeded, report.failed), (1, 0));
+
+    let mutations = gateway.mutations();
+    assert_eq!(mutations.len(), 1, "gateway received {:?}", mutations);
+    assert_eq!(mutations[0].2["daily_budget"], 150.0);
+    println!("POST /campaigns/bulk: budget change reached the gateway");
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! # Rules Engine Example
+//!
+//! Registers alert rules, syncs against the mock gateway and checks what
+//! was delivered: rules are evaluated on every sync, firing alerts are
+//! posted to the rule's webhook, and repeats within the dedup window are
+//! grouped into the first alert instead of being delivered again.
+//!
+//! ```bash
+//! cargo run --example rules_engine
+//! ```
+
+mod common;
+
+use chrono::Utc;
+
+use ibvi_core::routes::alerts::{
+    AlertCondition, AlertRule, DeliveryMode, NotifierConfig, DEFAULT_DEDUP_WINDOW_HOURS,
+};
+use ibvi_core::routes::sync::sync_once;
+
+use common::MockGateway;
+
+#[tokio::main]
+async fn main() {
+    // g-200 spends 75 of a 50 daily budget
+    let gateway = MockGateway::start(common::sample_campaigns()).await;
+    let state = gateway.state();
+
+    let rule = |id: &str, campaign_id: Option<&str>, condition| AlertRule {
+        id: id.to_string(),
+        name: id.to_string(),
+        campaign_id: campaign_id.map(Into::into),
+        condition,
+        notifier: NotifierConfig::Webhook {
+            url: gateway.hooks_url(),
+        },
+        dedup_window_hours: DEFAULT_DEDUP_WINDOW_HOURS,
+        delivery: DeliveryMode::Immediate,
+        created_at: Utc::now(),
+    };
+    state.alerts.add_rule(rule(
+        "overspend",
+        None,
+        AlertCondition::SpendOverBudget { percentage: 100.0 },
+    ));
+    // CPA of m-300 is 7.50: must not fire
+    state.alerts.add_rule(rule(
+        "m-300-cpa",
+        Some("m-300"),
+        AlertCondition::CpaAbove { max_cpa: 20.0, days: 1 },
+    ));
+
+    sync_once(&state).await;
+
+    let hooks = gateway.hooks();
+    assert_eq!(hooks.len(), 1, "webhook received {:?}", hooks);
+    assert_eq!(hooks[0]["rule_id"], "overspend");
+    assert_eq!(hooks[0]["campaign_id"], "g-200");
+    println!("alert delivered: {}", hooks[0]["message"]);
+
+    // Still overspending on the next sync: grouped, not delivered again
+    sync_once(&state).await;
+
+    assert_eq!(gateway.hooks().len(), 1);
+    let history = state.alerts.history();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].delivered);
+    assert_eq!(history[0].occurrences, 2);
+    println!("repeat grouped: {} occurrences, 1 delivery", history[0].occurrences);
+}
@@ -0,0 +1,56 @@
+//! # Sync Worker Example
+//!
+//! Runs the background sync loop against the mock gateway: every tick
+//! fetches all platforms, records a daily snapshot in the metrics store
+//! and publishes updates to live subscribers. The loop stops on
+//! cancellation once the running sync has finished.
+//!
+//! ```bash
+//! cargo run --example sync_worker
+//! ```
+
+mod common;
+
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use ibvi_core::routes::sync::{run_sync_worker, sync_once};
+
+use common::MockGateway;
+
+#[tokio::main]
+async fn main() {
+    let gateway = MockGateway::start(common::sample_campaigns()).await;
+    let state = gateway.state();
+
+    // One sync fills the store with today's snapshot of every campaign
+    sync_once(&state).await;
+    let mut ids: Vec<String> =
+        state.metrics_store.campaign_ids().iter().map(ToString::to_string).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["google:g-100", "google:g-200", "meta:m-300"]);
+
+    let today = state.metrics_store.daily_series(&"meta:m-300".parse().unwrap());
+    assert_eq!(today.len(), 1);
+    assert_eq!(today[0].conversions, 12);
+    println!("sync_once: {} campaigns recorded", ids.len());
+
+    // The worker keeps syncing until cancelled; subscribers see each sync
+    let mut updates = state.updates.subscribe();
+    let shutdown = CancellationToken::new();
+    let worker = tokio::spawn(run_sync_worker(
+        state.clone(),
+        Duration::from_millis(100),
+        shutdown.clone(),
+    ));
+
+    let update = tokio::time::timeout(Duration::from_secs(5), updates.recv())
+        .await
+        .expect("no update within 5s")
+        .expect("update channel closed");
+    println!("run_sync_worker: update for {}", update.campaign.id);
+
+    shutdown.cancel();
+    worker.await.expect("sync worker panicked");
+    println!("run_sync_worker: stopped on cancellation");
+}
@@ -0,0 +1,439 @@
+//! # A/B Experiment Example
+//!
+//! An experiment pairs a control campaign with a variant campaign, on the
+//! same platform or on different ones (e.g. the same offer on Google and
+//! on Meta), and compares one rate between them over the experiment
+//! period:
+//!
+//! - `conversion_rate`: conversions per click (default);
+//! - `ctr`: clicks per impression.
+//!
+//! `GET /experiments/{id}/results` sums the synced daily metrics of both
+//! campaigns from `start_date` to `end_date` (or today) and reports the
+//! relative lift of the variant, a chi-square test of independence
+//! (2x2, one degree of freedom) with its p-value, and the Bayesian
+//! probability that the variant's rate is the higher one.
+//!
+//! Platforms count conversions differently (attribution windows, view-
+//! through conversions), so cross-platform results are flagged: a lift
+//! between platforms measures the platforms as much as the creative.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+use super::finite::{safe_div, Finite};
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::store::MetricsStore;
+use super::timezones;
+
+/// Confidence level without `confidence_level` in the request
+pub const DEFAULT_CONFIDENCE_LEVEL: f64 = 0.95;
+
+/// Smallest expected count per cell for the chi-square approximation to hold
+pub const MIN_EXPECTED_COUNT: f64 = 5.0;
+
+/// Rate compared between the arms
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentMetric {
+    /// Conversions per click
+    #[default]
+    ConversionRate,
+    /// Clicks per impression
+    Ctr,
+}
+
+/// Campaign taking part in an experiment
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExperimentArm {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+}
+
+/// Registered experiment
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Experiment {
+    pub id: String,
+    pub name: String,
+    pub control: ExperimentArm,
+    pub variant: ExperimentArm,
+    pub metric: ExperimentMetric,
+    /// Results are significant when the p-value is below `1 - confidence_level`
+    pub confidence_level: f64,
+    pub start_date: NaiveDate,
+    /// `None` while the experiment is running
+    pub end_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /experiments`
+#[derive(Debug, Deserialize)]
+pub struct CreateExperiment {
+    pub name: String,
+    pub control: ExperimentArm,
+    pub variant: ExperimentArm,
+    #[serde(default)]
+    pub metric: ExperimentMetric,
+    pub confidence_level: Option<f64>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Delivery of one arm over the experiment period
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ArmResult {
+    #[serde(flatten)]
+    pub arm: ExperimentArm,
+    pub days: usize,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u64,
+    pub spend: f64,
+    /// Value of the experiment metric, 0 without trials
+    pub rate: f64,
+}
+
+impl ArmResult {
+    /// `(successes, trials)` of `metric`
+    fn counts(&self, metric: ExperimentMetric) -> (u64, u64) {
+        match metric {
+            ExperimentMetric::ConversionRate => (self.conversions, self.clicks),
+            ExperimentMetric::Ctr => (self.clicks, self.impressions),
+        }
+    }
+}
+
+/// `GET /experiments/{id}/results` response
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExperimentResults {
+    pub experiment_id: String,
+    pub metric: ExperimentMetric,
+    pub control: ArmResult,
+    pub variant: ArmResult,
+    /// Relative change of the variant rate over the control rate, in
+    /// percent; `None` while control has no successes
+    pub lift: Option<f64>,
+    /// `None` when an expected cell count is below `MIN_EXPECTED_COUNT`
+    pub chi_square: Option<f64>,
+    pub p_value: Option<f64>,
+    pub significant: bool,
+    /// Probability that the variant rate is higher, under uniform priors
+    pub probability_variant_better: Option<f64>,
+    /// Arms on different platforms, whose conversions are not counted alike
+    pub cross_platform: bool,
+}
+
+/// In-memory experiment registry
+#[derive(Default)]
+pub struct ExperimentStore {
+    experiments: RwLock<HashMap<String, Experiment>>,
+}
+
+impl ExperimentStore {
+    pub fn insert(&self, experiment: Experiment) {
+        self.experiments
+            .write()
+            .unwrap()
+            .insert(experiment.id.clone(), experiment);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Experiment> {
+        self.experiments.read().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Experiment> {
+        self.experiments.read().unwrap().values().cloned().collect()
+    }
+}
+
+/// Complementary error function (Numerical Recipes `erfcc`, relative
+/// error below 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Standard normal cumulative distribution function
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// Chi-square statistic and p-value of the 2x2 table of successes and
+/// failures of both arms, given as `(successes, trials)`
+///
+/// `None` when an expected count is below `MIN_EXPECTED_COUNT` or
+/// successes exceed trials.
+pub fn chi_square_test(control: (u64, u64), variant: (u64, u64)) -> Option<(f64, f64)> {
+    let (cs, cn) = control;
+    let (vs, vn) = variant;
+    if cs > cn || vs > vn {
+        return None;
+    }
+
+    let (a, b) = (cs as f64, (cn - cs) as f64);
+    let (c, d) = (vs as f64, (vn - vs) as f64);
+    let n = a + b + c + d;
+    if n == 0.0 {
+        return None;
+    }
+    let rows = [a + b, c + d];
+    let cols = [a + c, b + d];
+    let min_expected = rows
+        .iter()
+        .flat_map(|r| cols.iter().map(move |c| r * c / n))
+        .fold(f64::INFINITY, f64::min);
+    if min_expected < MIN_EXPECTED_COUNT {
+        return None;
+    }
+
+    let chi_square = n * (a * d - b * c).powi(2) / (rows[0] * rows[1] * cols[0] * cols[1]);
+    // One degree of freedom: P(X > x) = erfc(sqrt(x / 2))
+    Some((chi_square, erfc((chi_square / 2.0).sqrt())))
+}
+
+/// Probability that the variant rate is higher, with Beta(1, 1) priors
+///
+/// Uses the normal approximation of the Beta posteriors, which is close
+/// for the sample sizes ads campaigns reach.
+pub fn probability_variant_better(control: (u64, u64), variant: (u64, u64)) -> Option<f64> {
+    let posterior = |(successes, trials): (u64, u64)| {
+        let alpha = 1.0 + successes as f64;
+        let beta = 1.0 + trials.checked_sub(successes)? as f64;
+        let total = alpha + beta;
+        Some((alpha / total, alpha * beta / (total * total * (total + 1.0))))
+    };
+    let (control_mean, control_var) = posterior(control)?;
+    let (variant_mean, variant_var) = posterior(variant)?;
+
+    Some(normal_cdf((variant_mean - control_mean) / (control_var + variant_var).sqrt()))
+}
+
+/// Delivery of `arm` from `start` to `end`, inclusive
+pub fn arm_result(
+    store: &MetricsStore,
+    arm: &ExperimentArm,
+    metric: ExperimentMetric,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> ArmResult {
+    let days: Vec<_> = store
+        .daily_series(&GlobalCampaignId::new(arm.platform.clone(), arm.campaign_id.clone()))
+        .into_iter()
+        .filter(|day| day.date >= start && day.date <= end)
+        .collect();
+
+    let mut result = ArmResult {
+        arm: arm.clone(),
+        days: days.len(),
+        impressions: days.iter().map(|d| d.impressions).sum(),
+        clicks: days.iter().map(|d| d.clicks).sum(),
+        conversions: days.iter().map(|d| u64::from(d.conversions)).sum(),
+        spend: days.iter().map(|d| d.spend).sum(),
+        rate: 0.0,
+    };
+    let (successes, trials) = result.counts(metric);
+    result.rate = safe_div(successes as f64, trials as f64).unwrap_or(0.0);
+    result
+}
+
+/// Compare the arms of `experiment`
+pub fn evaluate(
+    experiment: &Experiment,
+    control: ArmResult,
+    variant: ArmResult,
+) -> ExperimentResults {
+    let control_counts = control.counts(experiment.metric);
+    let variant_counts = variant.counts(experiment.metric);
+    let test = chi_square_test(control_counts, variant_counts);
+
+    ExperimentResults {
+        experiment_id: experiment.id.clone(),
+        metric: experiment.metric,
+        lift: (control.rate > 0.0).then(|| (variant.rate / control.rate - 1.0) * 100.0),
+        chi_square: test.map(|(chi_square, _)| chi_square),
+        p_value: test.map(|(_, p)| p),
+        significant: test.is_some_and(|(_, p)| p < 1.0 - experiment.confidence_level),
+        probability_variant_better: probability_variant_better(control_counts, variant_counts),
+        cross_platform: control.arm.platform != variant.arm.platform,
+        control,
+        variant,
+    }
+}
+
+/// POST /experiments - Register an experiment
+pub async fn create_experiment(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateExperiment>,
+) -> Result<Json<Experiment>, ApiError> {
+    if body.control == body.variant {
+        return Err(ApiError::Validation(
+            "control and variant must be different campaigns".to_string(),
+        ));
+    }
+    let confidence_level = body.confidence_level.unwrap_or(DEFAULT_CONFIDENCE_LEVEL);
+    if !(confidence_level > 0.0 && confidence_level < 1.0) {
+        return Err(ApiError::Validation("confidence_level must be within (0, 1)".to_string()));
+    }
+    if body.end_date.is_some_and(|end| end < body.start_date) {
+        return Err(ApiError::Validation("end_date must not precede start_date".to_string()));
+    }
+
+    let experiment = Experiment {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: body.name,
+        control: body.control,
+        variant: body.variant,
+        metric: body.metric,
+        confidence_level,
+        start_date: body.start_date,
+        end_date: body.end_date,
+        created_at: Utc::now(),
+    };
+
+    state.experiments.insert(experiment.clone());
+
+    Ok(Json(experiment))
+}
+
+/// GET /experiments - List experiments
+pub async fn list_experiments(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Experiment>>, ApiError> {
+    Ok(Json(state.experiments.list()))
+}
+
+/// GET /experiments/{id}/results - Lift and significance of an experiment
+pub async fn get_experiment_results(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Finite<ExperimentResults>, ApiError> {
+    let experiment = state
+        .experiments
+        .get(&id)
+        .ok_or_else(|| ApiError::NotFound(format!("experiment {}", id)))?;
+
+    let end = experiment.end_date.unwrap_or_else(timezones::today);
+    let arm = |arm: &ExperimentArm| {
+        arm_result(&state.metrics_store, arm, experiment.metric, experiment.start_date, end)
+    };
+
+    Ok(Finite(evaluate(&experiment, arm(&experiment.control), arm(&experiment.variant))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Campaign;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn experiment(variant_platform: Platform) -> Experiment {
+        let arm = |platform, campaign_id: &str| ExperimentArm {
+            platform,
+            campaign_id: campaign_id.into(),
+        };
+        Experiment {
+            id: "exp-1".to_string(),
+            name: "Headline test".to_string(),
+            control: arm(Platform::Google, "g1"),
+            variant: arm(variant_platform, "v1"),
+            metric: ExperimentMetric::ConversionRate,
+            confidence_level: DEFAULT_CONFIDENCE_LEVEL,
+            start_date: day(1),
+            end_date: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn arm(arm: &ExperimentArm, clicks: u64, conversions: u64) -> ArmResult {
+        ArmResult {
+            arm: arm.clone(),
+            days: 7,
+            impressions: clicks * 20,
+            clicks,
+            conversions,
+            spend: clicks as f64,
+            rate: conversions as f64 / clicks as f64,
+        }
+    }
+
+    #[test]
+    fn test_chi_square_matches_reference_value() {
+        // 10% vs 13% over 1000 trials each
+        let (chi_square, p) = chi_square_test((100, 1_000), (130, 1_000)).unwrap();
+
+        assert!((chi_square - 4.4215).abs() < 1e-3);
+        assert!((p - 0.0355).abs() < 1e-3);
+        // Too few events for the approximation
+        assert!(chi_square_test((1, 10), (3, 10)).is_none());
+        assert!(chi_square_test((11, 10), (3, 10)).is_none());
+    }
+
+    #[test]
+    fn test_results_report_lift_and_significance() {
+        let exp = experiment(Platform::Google);
+        let results = evaluate(&exp, arm(&exp.control, 1_000, 100), arm(&exp.variant, 1_000, 130));
+
+        assert!((results.lift.unwrap() - 30.0).abs() < 1e-9);
+        assert!(results.significant);
+        assert!(results.probability_variant_better.unwrap() > 0.95);
+        assert!(!results.cross_platform);
+
+        let flat = evaluate(&exp, arm(&exp.control, 1_000, 100), arm(&exp.variant, 1_000, 102));
+        assert!(!flat.significant);
+        assert!((0.4..0.7).contains(&flat.probability_variant_better.unwrap()));
+    }
+
+    #[test]
+    fn test_cross_platform_arms_flagged() {
+        let exp = experiment(Platform::Meta);
+        let results = evaluate(&exp, arm(&exp.control, 200, 0), arm(&exp.variant, 200, 4));
+
+        assert!(results.cross_platform);
+        // No control conversions: no lift, and expected counts too low
+        assert_eq!(results.lift, None);
+        assert_eq!(results.p_value, None);
+    }
+
+    #[test]
+    fn test_arm_result_sums_days_within_period() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        for (d, clicks, conversions) in [(1, 100, 10), (2, 250, 20), (3, 400, 30)] {
+            campaign.metrics.clicks = clicks;
+            campaign.metrics.conversions = conversions;
+            store.record(day(d), &[campaign.clone()]);
+        }
+
+        let exp = experiment(Platform::Google);
+        let result = arm_result(&store, &exp.control, exp.metric, day(2), day(3));
+
+        assert_eq!(result.days, 2);
+        assert_eq!((result.clicks, result.conversions), (300, 20));
+        assert!((result.rate - 20.0 / 300.0).abs() < 1e-12);
+    }
+}
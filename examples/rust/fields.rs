@@ -0,0 +1,151 @@
+//! # Sparse Fieldsets Example
+//!
+//! `?fields=id,name,metrics.cost` trims responses down to the listed
+//! fields. The response is serialized with serde as usual and then
+//! projected, so every endpoint returning `Sparse<T>` supports field
+//! selection without per-type code. Dotted paths select nested fields;
+//! selecting a parent (`metrics`) keeps the whole object. Arrays are
+//! projected element by element.
+
+use axum::response::{IntoResponse, Json, Response};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use super::error::ApiError;
+use super::finite::{annotate, non_finite_fields, to_checked_value};
+use super::timing;
+
+/// Parsed `?fields=` selection
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldSelection {
+    /// Keep the whole value at this level
+    all: bool,
+    children: BTreeMap<String, FieldSelection>,
+}
+
+impl FieldSelection {
+    /// Parse a comma-separated list of dotted field paths
+    pub fn parse(fields: &str) -> Result<Self, ApiError> {
+        let mut root = FieldSelection::default();
+
+        for path in fields.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let mut node = &mut root;
+            for segment in path.split('.') {
+                if segment.is_empty() {
+                    return Err(ApiError::Validation(format!("invalid field path {:?}", path)));
+                }
+                node = node.children.entry(segment.to_string()).or_default();
+            }
+            node.all = true;
+        }
+
+        if root.children.is_empty() {
+            return Err(ApiError::Validation("fields must list at least one field".to_string()));
+        }
+
+        Ok(root)
+    }
+
+    /// Parse an optional `?fields=` value; absent means no projection
+    pub fn from_query(fields: Option<&str>) -> Result<Option<Self>, ApiError> {
+        fields.map(Self::parse).transpose()
+    }
+
+    /// Keep only the selected fields of `value`
+    pub fn project(&self, value: Value) -> Value {
+        if self.all {
+            return value;
+        }
+
+        match value {
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.project(v)).collect()),
+            Value::Object(mut object) => {
+                let mut projected = Map::new();
+                for (key, selection) in &self.children {
+                    if let Some(child) = object.remove(key) {
+                        projected.insert(key.clone(), selection.project(child));
+                    }
+                }
+                Value::Object(projected)
+            }
+            other => other,
+        }
+    }
+}
+
+/// JSON response projected through an optional field selection
+pub struct Sparse<T>(pub T, pub Option<FieldSelection>);
+
+/// Serializes the projected body, so `Sparse` composes with other
+/// response wrappers
+impl<T: Serialize> Sparse<T> {
+    /// Projected body, with non-finite fields that survive the projection
+    /// listed under `undefined_metrics`
+    fn to_value(&self) -> Result<Value, serde_json::Error> {
+        let Some(selection) = &self.1 else {
+            return to_checked_value(&self.0);
+        };
+
+        let mut value = selection.project(serde_json::to_value(&self.0)?);
+        annotate(&mut value, &non_finite_fields(&self.0));
+        Ok(value)
+    }
+}
+
+impl<T: Serialize> Serialize for Sparse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<T: Serialize> IntoResponse for Sparse<T> {
+    fn into_response(self) -> Response {
+        match timing::time("serialize", || self.to_value()) {
+            Ok(value) => Json(value).into_response(),
+            Err(e) => ApiError::Internal(e.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projects_nested_fields_in_arrays() {
+        let selection = FieldSelection::parse("id, name,metrics.cost").unwrap();
+        let value = serde_json::json!([
+            { "id": "1", "name": "Brand", "status": "ENABLED", "metrics": { "cost": 10.0, "clicks": 3 } },
+            { "id": "2", "name": "Generic", "status": "PAUSED", "metrics": { "cost": 0.0, "clicks": 0 } }
+        ]);
+
+        assert_eq!(
+            selection.project(value),
+            serde_json::json!([
+                { "id": "1", "name": "Brand", "metrics": { "cost": 10.0 } },
+                { "id": "2", "name": "Generic", "metrics": { "cost": 0.0 } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parent_selection_keeps_whole_object() {
+        let selection = FieldSelection::parse("metrics,metrics.cost").unwrap();
+        let value = serde_json::json!({ "id": "1", "metrics": { "cost": 1.0, "clicks": 2 } });
+
+        assert_eq!(
+            selection.project(value),
+            serde_json::json!({ "metrics": { "cost": 1.0, "clicks": 2 } })
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_paths() {
+        assert!(FieldSelection::parse("metrics..cost").is_err());
+        assert!(FieldSelection::parse(" , ").is_err());
+        assert_eq!(FieldSelection::from_query(None).unwrap(), None);
+    }
+}
@@ -0,0 +1,676 @@
+//! # Filter Expression Example
+//!
+//! The list parameters of `GET /campaigns` (`platform`, `status`, `label`,
+//! ...) each keep campaigns matching one field, and combine with AND.
+//! `?filter=` takes a whole expression instead:
+//!
+//! ```text
+//! (platform = google AND metrics.cpa < 30) OR tags.market = "SP"
+//! status IN (enabled, paused) AND NOT labels ~ test
+//! ```
+//!
+//! - comparisons: `=`, `!=`, `<`, `<=`, `>`, `>=`, `~` (contains) and
+//!   `IN (a, b)`. Numbers compare as numbers; text ignores case and
+//!   `~` only applies to text;
+//! - `AND` binds tighter than `OR`; `NOT` and parentheses as usual.
+//!   Keywords are case-insensitive;
+//! - values are numbers, quoted strings (`"black friday"`, `'SP'`) or
+//!   bare words (`google`, `enabled`);
+//! - fields: `id`, `name`, `platform`, `status`, `objective`,
+//!   `campaign_type`, `currency`, `account_id`, `daily_budget` (or
+//!   `budget`), `metrics.<metric>` for every metric of the campaign,
+//!   `tags.<tag>` for the naming convention tags (also `labels.<tag>`)
+//!   and `labels`, matching when one of the campaign's labels does.
+//!
+//! A campaign missing a value (no objective, no reach reported) matches
+//! no comparison on it but `!=`. Filters apply after the date range, so
+//! `metrics.*` compare the totals of the requested period.
+//!
+//! Malformed expressions are refused with 422 and a message pointing at
+//! the column of the problem, e.g. `expected a value after "<" at column 30`.
+
+use std::fmt;
+
+use super::campaign_aggregator::{Campaign, CampaignMetrics, Platform};
+use super::error::ApiError;
+use super::labels::label_key;
+
+/// Longest expression accepted, in characters
+pub const MAX_FILTER_LEN: usize = 2000;
+
+/// Deepest nesting of parentheses and `NOT` accepted
+const MAX_DEPTH: usize = 32;
+
+/// Metrics a filter can compare, as named under `metrics.`
+const METRICS: &[&str] = &[
+    "impressions",
+    "clicks",
+    "conversions",
+    "cost",
+    "ctr",
+    "cpa",
+    "conversion_value",
+    "roas",
+    "reach",
+    "frequency",
+    "video_views",
+    "video_view_rate",
+    "average_watch_time_seconds",
+];
+
+/// Fields listed in unknown field errors
+const FIELDS: &str = "id, name, platform, status, objective, campaign_type, currency, \
+                      account_id, daily_budget, metrics.<metric>, tags.<tag>, labels";
+
+/// Malformed filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterError {
+    /// Column of the problem, from 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl From<FilterError> for ApiError {
+    fn from(e: FilterError) -> Self {
+        ApiError::Validation(format!("invalid filter: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    In,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "~",
+            Op::In => "IN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Comma,
+    Op(Op),
+    /// Field name, keyword or bare word
+    Word(String),
+    Str(String),
+    /// Parsed value and the text as written, for text comparisons
+    Number(f64, String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::LParen => "\"(\"".to_string(),
+            Token::RParen => "\")\"".to_string(),
+            Token::Comma => "\",\"".to_string(),
+            Token::Op(op) => format!("{:?}", op.as_str()),
+            Token::Word(word) => format!("{:?}", word),
+            Token::Str(text) => format!("string {:?}", text),
+            Token::Number(_, text) => text.clone(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Split `input` into tokens, each with its column
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, FilterError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            ',' => (Token::Comma, 1),
+            '=' if next == Some('=') => (Token::Op(Op::Eq), 2),
+            '=' => (Token::Op(Op::Eq), 1),
+            '!' if next == Some('=') => (Token::Op(Op::Ne), 2),
+            '<' if next == Some('>') => (Token::Op(Op::Ne), 2),
+            '<' if next == Some('=') => (Token::Op(Op::Le), 2),
+            '<' => (Token::Op(Op::Lt), 1),
+            '>' if next == Some('=') => (Token::Op(Op::Ge), 2),
+            '>' => (Token::Op(Op::Gt), 1),
+            '~' => (Token::Op(Op::Contains), 1),
+            '"' | '\'' => {
+                let mut text = String::new();
+                let mut end = i + 1;
+                loop {
+                    match chars.get(end) {
+                        None => {
+                            return Err(FilterError {
+                                column,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                        Some('\\') if chars.get(end + 1).is_some() => {
+                            text.push(chars[end + 1]);
+                            end += 2;
+                        }
+                        Some(&q) if q == c => break,
+                        Some(&other) => {
+                            text.push(other);
+                            end += 1;
+                        }
+                    }
+                }
+                (Token::Str(text), end + 1 - i)
+            }
+            c if c.is_ascii_digit()
+                || (matches!(c, '-' | '.') && next.is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let rest = chars[i + 1..].iter().take_while(|c| c.is_ascii_digit() || **c == '.');
+                let len = rest.count() + 1;
+                let text: String = chars[i..i + len].iter().collect();
+                let value = text.parse().map_err(|_| FilterError {
+                    column,
+                    message: format!("invalid number {:?}", text),
+                })?;
+                (Token::Number(value, text), len)
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+                    .count();
+                (Token::Word(chars[i..i + len].iter().collect()), len)
+            }
+            other => {
+                return Err(FilterError {
+                    column,
+                    message: format!("unexpected character {:?}", other),
+                })
+            }
+        };
+        tokens.push((token, column));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+/// Campaign value a filter compares
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Id,
+    Name,
+    Platform,
+    Status,
+    Objective,
+    CampaignType,
+    Currency,
+    AccountId,
+    Budget,
+    Metric(&'static str),
+    Tag(String),
+    Labels,
+}
+
+impl Field {
+    fn parse(name: &str, column: usize) -> Result<Field, FilterError> {
+        let lower = name.to_lowercase();
+        let field = match lower.as_str() {
+            "id" => Field::Id,
+            "name" => Field::Name,
+            "platform" => Field::Platform,
+            "status" => Field::Status,
+            "objective" => Field::Objective,
+            "campaign_type" | "type" => Field::CampaignType,
+            "currency" => Field::Currency,
+            "account_id" => Field::AccountId,
+            "daily_budget" | "budget" => Field::Budget,
+            "labels" | "label" => Field::Labels,
+            _ => {
+                if let Some(metric) = lower.strip_prefix("metrics.") {
+                    let metric =
+                        METRICS.iter().find(|m| **m == metric).ok_or_else(|| FilterError {
+                            column,
+                            message: format!(
+                                "unknown metric {:?}, expected one of {}",
+                                metric,
+                                METRICS.join(", ")
+                            ),
+                        })?;
+                    return Ok(Field::Metric(metric));
+                }
+                // Tag names keep their case, as extracted by the conventions
+                let tag = name.split_once('.').and_then(|(prefix, tag)| {
+                    matches!(prefix.to_lowercase().as_str(), "tags" | "labels").then_some(tag)
+                });
+                match tag.filter(|tag| !tag.is_empty()) {
+                    Some(tag) => Field::Tag(tag.to_string()),
+                    None => {
+                        return Err(FilterError {
+                            column,
+                            message: format!(
+                                "unknown field {:?}, expected one of {}",
+                                name, FIELDS
+                            ),
+                        })
+                    }
+                }
+            }
+        };
+        Ok(field)
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Field::Budget | Field::Metric(_))
+    }
+
+    fn number(&self, campaign: &Campaign) -> Option<f64> {
+        match self {
+            Field::Budget => Some(campaign.daily_budget),
+            Field::Metric(metric) => metric_value(&campaign.metrics, metric),
+            _ => None,
+        }
+    }
+
+    fn text(&self, campaign: &Campaign) -> Option<String> {
+        Some(match self {
+            Field::Id => campaign.id.to_string(),
+            Field::Name => campaign.name.clone(),
+            Field::Platform => campaign.platform.as_str().to_string(),
+            Field::Status => campaign.status.as_str().to_string(),
+            Field::Objective => enum_text(campaign.objective.as_ref()?)?,
+            Field::CampaignType => enum_text(campaign.campaign_type.as_ref()?)?,
+            Field::Currency => campaign.currency.clone(),
+            Field::AccountId => campaign.account_id.as_ref()?.to_string(),
+            Field::Tag(tag) => campaign.tags.get(tag)?.clone(),
+            Field::Budget | Field::Metric(_) | Field::Labels => return None,
+        })
+    }
+}
+
+fn metric_value(metrics: &CampaignMetrics, metric: &str) -> Option<f64> {
+    Some(match metric {
+        "impressions" => metrics.impressions as f64,
+        "clicks" => metrics.clicks as f64,
+        "conversions" => metrics.conversions as f64,
+        "cost" => metrics.cost,
+        "ctr" => metrics.ctr,
+        "cpa" => metrics.cpa,
+        "conversion_value" => metrics.conversion_value,
+        "roas" => metrics.roas,
+        "reach" => metrics.reach? as f64,
+        "frequency" => metrics.frequency?,
+        "video_views" => metrics.video_views? as f64,
+        "video_view_rate" => metrics.video_view_rate?,
+        "average_watch_time_seconds" => metrics.average_watch_time_seconds?,
+        _ => return None,
+    })
+}
+
+/// Serialized name of an enum value, e.g. `SALES`
+fn enum_text<T: serde::Serialize>(value: &T) -> Option<String> {
+    serde_json::to_value(value).ok()?.as_str().map(str::to_string)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// `values` holds one value, or the list of `IN`
+    Compare {
+        field: Field,
+        op: Op,
+        values: Vec<Value>,
+    },
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Column just past the input, for errors at its end
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, column)| *column)
+    }
+
+    fn error(&self, message: String) -> FilterError {
+        FilterError { column: self.column(), message }
+    }
+
+    /// `what` was expected at the current token
+    fn expected(&self, what: &str) -> FilterError {
+        match self.peek() {
+            Some(token) => self.error(format!("expected {}, found {}", what, token.describe())),
+            None => self.error(format!("expected {}, found the end of the filter", what)),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn nest(&mut self) -> Result<(), FilterError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error(format!("filter nested deeper than {} levels", MAX_DEPTH)));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.and()?;
+        while self.peek().is_some_and(|t| t.is_keyword("or")) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.not()?;
+        while self.peek().is_some_and(|t| t.is_keyword("and")) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, FilterError> {
+        if self.peek().is_some_and(|t| t.is_keyword("not")) {
+            self.pos += 1;
+            self.nest()?;
+            let expr = Expr::Not(Box::new(self.not()?));
+            self.depth -= 1;
+            return Ok(expr);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, FilterError> {
+        if self.peek() == Some(&Token::LParen) {
+            let open = self.column();
+            self.pos += 1;
+            self.nest()?;
+            let expr = self.or()?;
+            self.depth -= 1;
+            if self.next() != Some(Token::RParen) {
+                self.pos -= 1;
+                return Err(match self.peek() {
+                    None => FilterError { column: open, message: "unclosed \"(\"".to_string() },
+                    Some(_) => self.expected("AND, OR or \")\""),
+                });
+            }
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FilterError> {
+        let (name, column) = match self.peek() {
+            Some(Token::Word(word))
+                if !["and", "or", "not", "in"].iter().any(|k| word.eq_ignore_ascii_case(k)) =>
+            {
+                (word.clone(), self.column())
+            }
+            _ => return Err(self.expected("a field")),
+        };
+        self.pos += 1;
+        let field = Field::parse(&name, column)?;
+
+        let op_column = self.column();
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(token) if token.is_keyword("in") => Op::In,
+            _ => {
+                self.pos -= 1;
+                return Err(self.expected(&format!("a comparison after {:?}", name)));
+            }
+        };
+        let values = match op {
+            Op::In => self.list()?,
+            _ => vec![self.value(op)?],
+        };
+
+        let invalid = |message: String| FilterError { column: op_column, message };
+        if field.is_number() {
+            if op == Op::Contains {
+                return Err(invalid(format!("\"~\" compares text, but {} is a number", name)));
+            }
+            if values.iter().any(|v| matches!(v, Value::Text(_))) {
+                return Err(invalid(format!("{} compares with numbers", name)));
+            }
+        } else if matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) {
+            return Err(invalid(format!(
+                "{:?} compares numbers, but {} is text",
+                op.as_str(),
+                name
+            )));
+        }
+        if field == Field::Platform {
+            for value in &values {
+                if let Value::Text(text) = value {
+                    Platform::parse_list(text).map_err(|e| invalid(e.to_string()))?;
+                }
+            }
+        }
+        Ok(Expr::Compare { field, op, values })
+    }
+
+    /// Value after `op`; numbers also keep their text for text fields
+    fn value(&mut self, op: Op) -> Result<Value, FilterError> {
+        match self.next() {
+            Some(Token::Number(value, text)) => {
+                Ok(if op == Op::Contains { Value::Text(text) } else { Value::Number(value) })
+            }
+            Some(Token::Str(text)) => Ok(Value::Text(text)),
+            Some(Token::Word(word)) => Ok(Value::Text(word)),
+            _ => {
+                self.pos -= 1;
+                Err(self.expected(&format!("a value after {:?}", op.as_str())))
+            }
+        }
+    }
+
+    /// `(a, b, ...)` after `IN`
+    fn list(&mut self) -> Result<Vec<Value>, FilterError> {
+        if self.next() != Some(Token::LParen) {
+            self.pos -= 1;
+            return Err(self.expected("\"(\" after IN"));
+        }
+        let mut values = vec![self.value(Op::In)?];
+        loop {
+            match self.next() {
+                Some(Token::Comma) => values.push(self.value(Op::In)?),
+                Some(Token::RParen) => return Ok(values),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.expected("\",\" or \")\" in the IN list"));
+                }
+            }
+        }
+    }
+}
+
+/// Compiled filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Filter, FilterError> {
+        let len = input.chars().count();
+        if len > MAX_FILTER_LEN {
+            return Err(FilterError {
+                column: MAX_FILTER_LEN + 1,
+                message: format!("filter longer than {} characters", MAX_FILTER_LEN),
+            });
+        }
+        let mut parser = Parser { tokens: tokenize(input)?, pos: 0, end: len + 1, depth: 0 };
+        if parser.peek().is_none() {
+            return Err(parser.error("empty filter".to_string()));
+        }
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return Err(parser.expected("AND or OR"));
+        }
+        Ok(Filter { expr })
+    }
+
+    pub fn matches(&self, campaign: &Campaign) -> bool {
+        eval(&self.expr, campaign)
+    }
+}
+
+fn eval(expr: &Expr, campaign: &Campaign) -> bool {
+    match expr {
+        Expr::And(a, b) => eval(a, campaign) && eval(b, campaign),
+        Expr::Or(a, b) => eval(a, campaign) || eval(b, campaign),
+        Expr::Not(a) => !eval(a, campaign),
+        Expr::Compare { field, op, values } => compare(field, *op, values, campaign),
+    }
+}
+
+fn compare(field: &Field, op: Op, values: &[Value], campaign: &Campaign) -> bool {
+    if *field == Field::Labels {
+        let keys: Vec<String> = campaign.labels.iter().map(|l| label_key(l)).collect();
+        let has = |value: &Value| keys.contains(&label_key(&value_text(value)));
+        return match op {
+            Op::Ne => !values.iter().any(has),
+            Op::Contains => {
+                let needle = value_text(&values[0]).to_lowercase();
+                keys.iter().any(|key| key.contains(&needle))
+            }
+            _ => values.iter().any(has),
+        };
+    }
+
+    if field.is_number() {
+        let Some(actual) = field.number(campaign) else {
+            return op == Op::Ne;
+        };
+        let equal = |value: &Value| matches!(value, Value::Number(v) if *v == actual);
+        let Some(Value::Number(value)) = values.first() else {
+            return false;
+        };
+        return match op {
+            Op::Eq => actual == *value,
+            Op::Ne => actual != *value,
+            Op::Lt => actual < *value,
+            Op::Le => actual <= *value,
+            Op::Gt => actual > *value,
+            Op::Ge => actual >= *value,
+            Op::In => values.iter().any(equal),
+            Op::Contains => false,
+        };
+    }
+
+    let Some(actual) = field.text(campaign) else {
+        return op == Op::Ne;
+    };
+    let equal = |value: &Value| actual.eq_ignore_ascii_case(&value_text(value));
+    match op {
+        Op::Eq | Op::In => values.iter().any(equal),
+        Op::Ne => !values.iter().any(equal),
+        Op::Contains => actual.to_lowercase().contains(&value_text(&values[0]).to_lowercase()),
+        Op::Lt | Op::Le | Op::Gt | Op::Ge => false,
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Number(number) => number.to_string(),
+        Value::Text(text) => text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expression_selects_campaigns() {
+        let mut cheap = Campaign::sample("1", Platform::Google);
+        cheap.metrics.cpa = 20.0;
+        let mut pricey = Campaign::sample("2", Platform::Google);
+        pricey.metrics.cpa = 45.0;
+        let mut sp = Campaign::sample("3", Platform::Meta);
+        sp.metrics.cpa = 80.0;
+        sp.tags.insert("market".to_string(), "SP".to_string());
+        sp.labels.push("Black Friday".to_string());
+        let campaigns = [cheap, pricey, sp];
+
+        let selected = |input: &str| -> Vec<String> {
+            let filter = Filter::parse(input).unwrap();
+            campaigns.iter().filter(|c| filter.matches(c)).map(|c| c.id.to_string()).collect()
+        };
+        assert_eq!(
+            selected(r#"(platform = google AND metrics.cpa < 30) OR labels.market = "SP""#),
+            ["1", "3"]
+        );
+        assert_eq!(selected("platform IN (meta) and not metrics.cpa >= 100"), ["3"]);
+        assert_eq!(selected("labels = 'black friday' OR name ~ 'campaign 2'"), ["2", "3"]);
+        // Missing values only match `!=`
+        assert_eq!(selected("metrics.reach > 0 OR objective != sales"), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        let error = |input: &str| Filter::parse(input).unwrap_err();
+
+        assert_eq!(error("metrics.cpa <").column, 14);
+        assert!(error("metrics.cpa <").message.contains("a value after \"<\""));
+        assert!(error("metrics.cpx > 1").message.contains("unknown metric \"cpx\""));
+        assert!(error("name > 3").message.contains("is text"));
+        assert!(error("platform = gogle").message.contains("unknown platform"));
+        assert_eq!(error("(status = enabled").column, 1);
+        assert_eq!(error("name = \"open").message, "unterminated string");
+        assert!(error("status = enabled paused").message.contains("expected AND or OR"));
+    }
+}
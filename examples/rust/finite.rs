@@ -0,0 +1,520 @@
+//! # Finite Numbers Example
+//!
+//! Derived metrics divide by counts that can be zero, and upstream
+//! amounts can arrive as `"NaN"` or `"Infinity"` strings that Rust parses
+//! happily. JSON has no representation for either: serde_json silently
+//! writes `null` and strict parsers reject the literals other encoders
+//! emit.
+//!
+//! Two guarantees:
+//! - derived math goes through `safe_div`, which returns `None` instead of
+//!   a non-finite result;
+//! - response bodies are checked at serialization time. Any non-finite
+//!   float left in a body is written as `null` and its path is listed,
+//!   with a reason code, under `undefined_metrics` of the enclosing object
+//!   (of each element, for list responses).
+
+use axum::response::{IntoResponse, Json, Response};
+use serde::ser::{self, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt;
+
+use super::error::ApiError;
+use super::timing;
+
+/// Key listing the non-finite fields of an object
+pub const UNDEFINED_METRICS_KEY: &str = "undefined_metrics";
+
+/// `numerator / denominator`, `None` for a zero denominator or a
+/// non-finite result
+pub fn safe_div(numerator: f64, denominator: f64) -> Option<f64> {
+    if denominator == 0.0 {
+        return None;
+    }
+    Some(numerator / denominator).filter(|v| v.is_finite())
+}
+
+/// Why a float could not be written to JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFiniteReason {
+    Nan,
+    Infinity,
+}
+
+impl NonFiniteReason {
+    pub fn of(value: f64) -> Option<Self> {
+        if value.is_nan() {
+            Some(NonFiniteReason::Nan)
+        } else if value.is_infinite() {
+            Some(NonFiniteReason::Infinity)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NonFiniteReason::Nan => "nan",
+            NonFiniteReason::Infinity => "infinity",
+        }
+    }
+}
+
+/// Path segments and reason of every non-finite float in `value`
+pub fn non_finite_fields<T: Serialize + ?Sized>(value: &T) -> Vec<(Vec<String>, NonFiniteReason)> {
+    let mut collector = Collector::default();
+    // The collector itself never fails; custom `Serialize` impls may
+    match value.serialize(&mut collector) {
+        Ok(()) => collector.found,
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Record `fields` under `undefined_metrics` of the object that holds
+/// them; paths that no longer exist (e.g. projected away) are skipped
+pub fn annotate(value: &mut Value, fields: &[(Vec<String>, NonFiniteReason)]) {
+    for (path, reason) in fields {
+        let (target, rest) = match value {
+            Value::Array(items) => {
+                let Some(index) = path.first().and_then(|i| i.parse::<usize>().ok()) else {
+                    continue;
+                };
+                let Some(item) = items.get_mut(index) else {
+                    continue;
+                };
+                (item, &path[1..])
+            }
+            _ => (&mut *value, &path[..]),
+        };
+
+        if resolve(target, rest).is_none() {
+            continue;
+        }
+        if let Value::Object(object) = target {
+            object
+                .entry(UNDEFINED_METRICS_KEY)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .map(|undefined| undefined.insert(rest.join("."), Value::from(reason.as_str())));
+        }
+    }
+}
+
+fn resolve<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, segment| match value {
+        Value::Object(object) => object.get(segment),
+        Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Serialize `body` to a JSON value with non-finite floats annotated
+pub fn to_checked_value<T: Serialize + ?Sized>(body: &T) -> Result<Value, serde_json::Error> {
+    let mut value = serde_json::to_value(body)?;
+    let fields = non_finite_fields(body);
+
+    if !fields.is_empty() {
+        let paths: Vec<String> = fields.iter().map(|(p, _)| p.join(".")).collect();
+        tracing::warn!(?paths, "non-finite values replaced with null");
+        annotate(&mut value, &fields);
+    }
+
+    Ok(value)
+}
+
+/// JSON response checked for non-finite floats
+pub struct Finite<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Finite<T> {
+    fn into_response(self) -> Response {
+        match timing::time("serialize", || to_checked_value(&self.0)) {
+            Ok(value) => Json(value).into_response(),
+            Err(e) => ApiError::Internal(e.to_string()).into_response(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CheckError(String);
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+impl ser::Error for CheckError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        CheckError(msg.to_string())
+    }
+}
+
+/// Serializer that writes nothing and records where non-finite floats are
+#[derive(Default)]
+struct Collector {
+    path: Vec<String>,
+    found: Vec<(Vec<String>, NonFiniteReason)>,
+}
+
+impl Collector {
+    fn check(&mut self, value: f64) {
+        if let Some(reason) = NonFiniteReason::of(value) {
+            self.found.push((self.path.clone(), reason));
+        }
+    }
+
+    fn nested<T: Serialize + ?Sized>(
+        &mut self,
+        segment: String,
+        value: &T,
+    ) -> Result<(), CheckError> {
+        self.path.push(segment);
+        let result = value.serialize(&mut *self);
+        self.path.pop();
+        result
+    }
+
+    fn compound(&mut self, variant: Option<&str>) -> Compound<'_> {
+        if let Some(variant) = variant {
+            self.path.push(variant.to_string());
+        }
+        Compound {
+            collector: self,
+            index: 0,
+            key: None,
+            pushed: variant.is_some(),
+        }
+    }
+}
+
+/// Sequence, map and struct state of the `Collector`
+struct Compound<'a> {
+    collector: &'a mut Collector,
+    index: usize,
+    key: Option<String>,
+    /// Variant name pushed onto the path, popped in `end`
+    pushed: bool,
+}
+
+impl Compound<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CheckError> {
+        self.collector.nested(self.index.to_string(), value)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), CheckError> {
+        if self.pushed {
+            self.collector.path.pop();
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Serializer for &'a mut Collector {
+    type Ok = ();
+    type Error = CheckError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_f32(self, v: f32) -> Result<(), CheckError> {
+        self.check(v.into());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), CheckError> {
+        self.check(v);
+        Ok(())
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_char(self, _: char) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_str(self, _: &str) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), CheckError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<(), CheckError> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), CheckError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), CheckError> {
+        self.nested(variant.to_string(), value)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(Some(variant)))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Compound<'a>, CheckError> {
+        Ok(self.compound(Some(variant)))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CheckError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CheckError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CheckError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CheckError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), CheckError> {
+        // Same key text serde_json writes
+        self.key = Some(match serde_json::to_value(key) {
+            Ok(Value::String(key)) => key,
+            Ok(other) => other.to_string(),
+            Err(e) => return Err(ser::Error::custom(e)),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CheckError> {
+        let key = self.key.take().unwrap_or_default();
+        self.collector.nested(key, value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), CheckError> {
+        self.collector.nested(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), CheckError> {
+        self.collector.nested(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<(), CheckError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{Campaign, Platform};
+
+    #[test]
+    fn test_safe_div_never_returns_non_finite() {
+        assert_eq!(safe_div(10.0, 4.0), Some(2.5));
+        assert_eq!(safe_div(10.0, 0.0), None);
+        assert_eq!(safe_div(f64::NAN, 2.0), None);
+        assert_eq!(safe_div(f64::MAX, 1e-300), None);
+    }
+
+    #[test]
+    fn test_non_finite_fields_annotated_per_list_element() {
+        let mut broken = Campaign::sample("g2", Platform::Google);
+        broken.metrics.cpa = f64::INFINITY;
+        broken.metrics.roas = f64::NAN;
+        let campaigns = vec![Campaign::sample("g1", Platform::Google), broken];
+
+        let value = to_checked_value(&campaigns).unwrap();
+
+        assert!(value[0].get(UNDEFINED_METRICS_KEY).is_none());
+        assert_eq!(value[1]["metrics"]["cpa"], Value::Null);
+        assert_eq!(
+            value[1][UNDEFINED_METRICS_KEY],
+            serde_json::json!({ "metrics.cpa": "infinity", "metrics.roas": "nan" })
+        );
+    }
+
+    #[test]
+    fn test_projected_away_fields_are_not_annotated() {
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        campaign.metrics.cpa = f64::NAN;
+        let fields = non_finite_fields(&campaign);
+
+        let mut value = serde_json::json!({ "id": "g1" });
+        annotate(&mut value, &fields);
+
+        assert_eq!(value, serde_json::json!({ "id": "g1" }));
+    }
+}
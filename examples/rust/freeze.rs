@@ -0,0 +1,355 @@
+//! # Change Freeze Example
+//!
+//! Admins declare freeze windows (Black Friday week, end of quarter)
+//! during which campaign mutations are blocked. A window applies to one
+//! tenant or to all of them, and to campaigns carrying one of its labels
+//! or to every campaign when it lists none.
+//!
+//! Inside an active window:
+//! - automated callers (rule engines, schedulers) are always blocked;
+//! - regular users are blocked;
+//! - admins may override by sending `X-Freeze-Override: <reason>`, and
+//!   every override is written to an audit trail.
+//!
+//! The caller comes from headers set by the authenticating proxy in
+//! front of the API: `X-Actor`, `X-Actor-Role` and `X-Tenant-Id`.
+
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, HeaderMap},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActorRole {
+    User,
+    Admin,
+    /// Rule engines and schedulers; can never override a freeze
+    Automation,
+}
+
+/// Caller of a mutating request
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub id: String,
+    pub role: ActorRole,
+    pub tenant: Option<String>,
+    /// Reason given in `X-Freeze-Override`
+    pub override_reason: Option<String>,
+    /// Reason given in `X-Spend-Cap-Override`, see `spend_caps`
+    pub spend_cap_override: Option<String>,
+}
+
+impl Actor {
+    /// Actor for mutations triggered by the service itself
+    pub fn automation(id: &str, tenant: Option<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            role: ActorRole::Automation,
+            tenant,
+            override_reason: None,
+            spend_cap_override: None,
+        }
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        Self {
+            id: header("X-Actor").unwrap_or_else(|| "anonymous".to_string()),
+            role: match header("X-Actor-Role").as_deref() {
+                Some("admin") => ActorRole::Admin,
+                Some("automation") => ActorRole::Automation,
+                _ => ActorRole::User,
+            },
+            tenant: header("X-Tenant-Id"),
+            override_reason: header("X-Freeze-Override"),
+            spend_cap_override: header("X-Spend-Cap-Override"),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Actor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Actor::from_headers(&parts.headers))
+    }
+}
+
+/// Period during which mutations are blocked
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FreezeWindow {
+    pub id: String,
+    pub name: String,
+    /// Tenant the freeze applies to; `None` freezes every tenant
+    pub tenant: Option<String>,
+    /// Campaign labels covered; empty covers every campaign
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_by: String,
+}
+
+impl FreezeWindow {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+
+    /// Whether the window covers a campaign of `tenant` carrying `labels`
+    pub fn covers(&self, tenant: Option<&str>, labels: &[String]) -> bool {
+        self.tenant.as_deref().is_none_or(|t| Some(t) == tenant)
+            && (self.labels.is_empty() || self.labels.iter().any(|l| labels.contains(l)))
+    }
+}
+
+/// Request body for `POST /freeze-windows`
+#[derive(Debug, Deserialize)]
+pub struct CreateFreezeWindow {
+    pub name: String,
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Audit record of a mutation let through a freeze
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FreezeOverride {
+    pub window_id: String,
+    pub actor: String,
+    pub tenant: Option<String>,
+    pub reason: String,
+    /// What was changed, e.g. `set_budget google/123`
+    pub target: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Freeze windows and the override audit trail
+#[derive(Default)]
+pub struct FreezeStore {
+    windows: RwLock<Vec<FreezeWindow>>,
+    overrides: RwLock<Vec<FreezeOverride>>,
+}
+
+impl FreezeStore {
+    pub fn add_window(&self, window: FreezeWindow) {
+        self.windows.write().unwrap().push(window);
+    }
+
+    pub fn windows(&self) -> Vec<FreezeWindow> {
+        self.windows.read().unwrap().clone()
+    }
+
+    pub fn remove_window(&self, id: &str) -> bool {
+        let mut windows = self.windows.write().unwrap();
+        let before = windows.len();
+        windows.retain(|w| w.id != id);
+        windows.len() != before
+    }
+
+    /// Override audit trail, most recent first
+    pub fn overrides(&self) -> Vec<FreezeOverride> {
+        let mut overrides = self.overrides.read().unwrap().clone();
+        overrides.reverse();
+        overrides
+    }
+
+    /// Allow or block a mutation of `target`, a campaign carrying `labels`
+    ///
+    /// Admin overrides are recorded before the mutation is allowed.
+    pub fn check(
+        &self,
+        actor: &Actor,
+        labels: &[String],
+        target: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        let windows = self.windows.read().unwrap();
+        let Some(window) = windows
+            .iter()
+            .find(|w| w.is_active(now) && w.covers(actor.tenant.as_deref(), labels))
+        else {
+            return Ok(());
+        };
+
+        match (actor.role, &actor.override_reason) {
+            (ActorRole::Admin, Some(reason)) => {
+                tracing::warn!(window = %window.id, actor = %actor.id, %target, "change freeze overridden");
+                self.overrides.write().unwrap().push(FreezeOverride {
+                    window_id: window.id.clone(),
+                    actor: actor.id.clone(),
+                    tenant: actor.tenant.clone(),
+                    reason: reason.clone(),
+                    target: target.to_string(),
+                    at: now,
+                });
+                Ok(())
+            }
+            _ => Err(ApiError::ChangeFrozen(format!(
+                "change freeze {:?} is active until {}",
+                window.name,
+                window.ends_at.to_rfc3339()
+            ))),
+        }
+    }
+}
+
+fn require_admin(actor: &Actor) -> Result<(), ApiError> {
+    if actor.role != ActorRole::Admin {
+        return Err(ApiError::Forbidden("freeze windows are managed by admins".to_string()));
+    }
+    Ok(())
+}
+
+/// POST /freeze-windows - Declare a freeze window (admins only)
+pub async fn create_freeze_window(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Json(body): Json<CreateFreezeWindow>,
+) -> Result<Json<FreezeWindow>, ApiError> {
+    require_admin(&actor)?;
+    if body.ends_at <= body.starts_at {
+        return Err(ApiError::Validation("ends_at must be after starts_at".to_string()));
+    }
+
+    let window = FreezeWindow {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: body.name,
+        tenant: body.tenant,
+        labels: body.labels,
+        starts_at: body.starts_at,
+        ends_at: body.ends_at,
+        created_by: actor.id,
+    };
+
+    state.freezes.add_window(window.clone());
+
+    Ok(Json(window))
+}
+
+/// GET /freeze-windows - List freeze windows
+pub async fn list_freeze_windows(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<FreezeWindow>>, ApiError> {
+    Ok(Json(state.freezes.windows()))
+}
+
+/// DELETE /freeze-windows/{id} - Lift a freeze window (admins only)
+pub async fn delete_freeze_window(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path(id): Path<String>,
+) -> Result<Json<FreezeWindow>, ApiError> {
+    require_admin(&actor)?;
+
+    let window = state
+        .freezes
+        .windows()
+        .into_iter()
+        .find(|w| w.id == id)
+        .ok_or_else(|| ApiError::NotFound(format!("freeze window {}", id)))?;
+    state.freezes.remove_window(&id);
+
+    Ok(Json(window))
+}
+
+/// GET /freeze-windows/overrides - Override audit trail, most recent first
+pub async fn list_freeze_overrides(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<FreezeOverride>>, ApiError> {
+    Ok(Json(state.freezes.overrides()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(tenant: Option<&str>, labels: &[&str]) -> (FreezeStore, DateTime<Utc>) {
+        let now = Utc::now();
+        let store = FreezeStore::default();
+        store.add_window(FreezeWindow {
+            id: "w1".to_string(),
+            name: "Black Friday".to_string(),
+            tenant: tenant.map(str::to_string),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            starts_at: now - chrono::Duration::hours(1),
+            ends_at: now + chrono::Duration::days(7),
+            created_by: "ops".to_string(),
+        });
+        (store, now)
+    }
+
+    fn actor(role: ActorRole, override_reason: Option<&str>) -> Actor {
+        Actor {
+            id: "ana".to_string(),
+            role,
+            tenant: Some("acme".to_string()),
+            override_reason: override_reason.map(str::to_string),
+            spend_cap_override: None,
+        }
+    }
+
+    #[test]
+    fn test_active_window_blocks_users_and_automation() {
+        let (store, now) = store(None, &[]);
+
+        let err = store.check(&actor(ActorRole::User, None), &[], "set_budget google/1", now);
+        assert_eq!(err.unwrap_err().code(), "change_frozen");
+
+        // Automation cannot override even when it asks to
+        let automation = actor(ActorRole::Automation, Some("rule"));
+        assert!(store.check(&automation, &[], "set_status google/1", now).is_err());
+
+        // Outside the window everything passes
+        let later = now + chrono::Duration::days(8);
+        assert!(store.check(&actor(ActorRole::User, None), &[], "set_budget google/1", later).is_ok());
+    }
+
+    #[test]
+    fn test_window_scoped_by_tenant_and_label() {
+        let (store, now) = store(Some("acme"), &["black-friday"]);
+        let user = actor(ActorRole::User, None);
+
+        assert!(store.check(&user, &["always-on".to_string()], "x", now).is_ok());
+        assert!(store.check(&user, &["black-friday".to_string()], "x", now).is_err());
+
+        let other_tenant = Actor {
+            tenant: Some("globex".to_string()),
+            ..user
+        };
+        assert!(store.check(&other_tenant, &["black-friday".to_string()], "x", now).is_ok());
+    }
+
+    #[test]
+    fn test_admin_override_is_audited() {
+        let (store, now) = store(None, &[]);
+
+        assert!(store.check(&actor(ActorRole::Admin, None), &[], "x", now).is_err());
+        store
+            .check(&actor(ActorRole::Admin, Some("pricing error")), &[], "set_budget google/1", now)
+            .unwrap();
+
+        let overrides = store.overrides();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].reason, "pricing error");
+        assert_eq!(overrides[0].target, "set_budget google/1");
+    }
+}
@@ -0,0 +1,230 @@
+//! # Frequency Capping Example
+//!
+//! Estimates how often the same person sees our ads across Meta and
+//! Google programmatic placements (Display / YouTube) and recommends a
+//! per-platform frequency cap that keeps the combined frequency within a
+//! target.
+//!
+//! Platforms only report their own reach, so the combined reach is
+//! estimated assuming audiences overlap at random within a shared
+//! universe (the Sainsbury formula):
+//!
+//! `combined_reach = U * (1 - Π(1 - reach_i / U))`
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+
+/// Default combined frequency target per reporting window
+pub const DEFAULT_TARGET_FREQUENCY: f64 = 3.0;
+
+/// Reach and impressions a platform delivered over the reporting window
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReachData {
+    pub platform: Platform,
+    pub impressions: u64,
+    pub reach: u64,
+}
+
+/// Per-platform numbers backing a recommendation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlatformExposure {
+    pub platform: Platform,
+    pub impressions: u64,
+    pub reach: u64,
+    /// Average impressions per reached user on this platform
+    pub frequency: f64,
+    /// Suggested impressions-per-user cap for the window
+    pub recommended_cap: u32,
+}
+
+/// Frequency capping insight returned by `GET /insights/frequency`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FrequencyInsight {
+    pub window_days: u32,
+    pub audience_size: u64,
+    pub combined_reach: f64,
+    pub combined_frequency: f64,
+    pub target_frequency: f64,
+    pub platforms: Vec<PlatformExposure>,
+    pub message: String,
+}
+
+/// Query parameters for `GET /insights/frequency`
+#[derive(Debug, Deserialize)]
+pub struct FrequencyQuery {
+    /// Size of the targetable population shared by both platforms
+    pub audience_size: u64,
+    pub target_frequency: Option<f64>,
+    pub window_days: Option<u32>,
+}
+
+/// Estimate combined reach assuming random overlap within `audience_size`
+pub fn combined_reach(data: &[ReachData], audience_size: u64) -> f64 {
+    if audience_size == 0 {
+        return 0.0;
+    }
+
+    let universe = audience_size as f64;
+    let unreached: f64 = data
+        .iter()
+        .map(|d| 1.0 - (d.reach as f64 / universe).min(1.0))
+        .product();
+
+    universe * (1.0 - unreached)
+}
+
+/// Build the capping insight for the given per-platform reach data
+pub fn recommend_caps(
+    data: &[ReachData],
+    audience_size: u64,
+    target_frequency: f64,
+    window_days: u32,
+) -> FrequencyInsight {
+    let reach = combined_reach(data, audience_size);
+    let total_impressions: u64 = data.iter().map(|d| d.impressions).sum();
+
+    let combined_frequency = if reach > 0.0 {
+        total_impressions as f64 / reach
+    } else {
+        0.0
+    };
+
+    // Scale every platform down by the same factor so the combined
+    // frequency lands on the target; never recommend raising exposure
+    let scale = if combined_frequency > target_frequency {
+        target_frequency / combined_frequency
+    } else {
+        1.0
+    };
+
+    let platforms = data
+        .iter()
+        .map(|d| {
+            let frequency = if d.reach > 0 {
+                d.impressions as f64 / d.reach as f64
+            } else {
+                0.0
+            };
+
+            PlatformExposure {
+                platform: d.platform.clone(),
+                impressions: d.impressions,
+                reach: d.reach,
+                frequency,
+                recommended_cap: ((frequency * scale).floor() as u32).max(1),
+            }
+        })
+        .collect();
+
+    let message = if combined_frequency > target_frequency {
+        format!(
+            "Estimated combined frequency {:.1} exceeds the target of {:.1} over {} days; apply the recommended per-platform caps",
+            combined_frequency, target_frequency, window_days
+        )
+    } else {
+        format!(
+            "Estimated combined frequency {:.1} is within the target of {:.1} over {} days",
+            combined_frequency, target_frequency, window_days
+        )
+    };
+
+    FrequencyInsight {
+        window_days,
+        audience_size,
+        combined_reach: reach,
+        combined_frequency,
+        target_frequency,
+        platforms,
+        message,
+    }
+}
+
+/// Fetch account-level reach and impressions for one platform from the gateway
+async fn fetch_reach(
+    state: &AppState,
+    platform: &str,
+    window_days: u32,
+) -> Result<ReachData, reqwest::Error> {
+    let url = format!(
+        "{}/v1/{}/reach?days={}",
+        state.gateway_url, platform, window_days
+    );
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.json::<ReachData>().await
+}
+
+/// GET /insights/frequency - Cross-platform frequency capping recommendation
+pub async fn get_frequency_insight(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FrequencyQuery>,
+) -> Result<Json<FrequencyInsight>, ApiError> {
+    let window_days = query.window_days.unwrap_or(7);
+    let target = query.target_frequency.unwrap_or(DEFAULT_TARGET_FREQUENCY);
+
+    if query.audience_size == 0 || target <= 0.0 {
+        return Err(ApiError::Validation(
+            "audience_size and target_frequency must be greater than zero".to_string(),
+        ));
+    }
+
+    let (google, meta) = tokio::join!(
+        fetch_reach(&state, "google", window_days),
+        fetch_reach(&state, "meta", window_days)
+    );
+
+    let data: Vec<ReachData> = [google, meta].into_iter().filter_map(Result::ok).collect();
+    if data.is_empty() {
+        return Err(ApiError::Gateway("no platform returned reach data".to_string()));
+    }
+
+    Ok(Json(recommend_caps(&data, query.audience_size, target, window_days)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> Vec<ReachData> {
+        vec![
+            ReachData {
+                platform: Platform::Meta,
+                impressions: 300_000,
+                reach: 50_000,
+            },
+            ReachData {
+                platform: Platform::Google,
+                impressions: 200_000,
+                reach: 50_000,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_combined_reach_accounts_for_overlap() {
+        // Two 50% reaches in a universe of 100k overlap by 25k at random
+        let reach = combined_reach(&data(), 100_000);
+        assert!((reach - 75_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_recommend_caps_scales_to_target() {
+        let insight = recommend_caps(&data(), 100_000, 3.0, 7);
+
+        // 500k impressions over 75k people
+        assert!((insight.combined_frequency - 500_000.0 / 75_000.0).abs() < 1e-9);
+        assert_eq!(insight.platforms[0].recommended_cap, 2);
+        assert_eq!(insight.platforms[1].recommended_cap, 1);
+    }
+}
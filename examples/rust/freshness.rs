@@ -0,0 +1,259 @@
+//! # Data Freshness Example
+//!
+//! A campaign showing no spend may have really stopped delivering, or its
+//! numbers may simply be old. Responses report where each platform's data
+//! came from and when it was fetched, so analysts can tell the two apart:
+//!
+//! ```json
+//! "meta": {"freshness": {
+//!   "google": {"source": "cache", "fetched_at": "2026-10-15T09:12:03Z", "age_secs": 312},
+//!   "meta": {"source": "live", "fetched_at": "2026-10-15T09:17:15Z", "age_secs": 0}
+//! }}
+//! ```
+//!
+//! - `live`: fetched from the platform while serving the request;
+//! - `cache`: a campaign list kept by the entity cache, fetched at
+//!   `fetched_at`; a list read from another replica's shared cache is
+//!   reported from when this replica read it;
+//! - `store`: metrics recorded by the sync worker (date ranges, history,
+//!   period comparisons), last recorded at `fetched_at`; `null` when the
+//!   snapshots were loaded from disk and no sync has run since.
+//!
+//! When a platform's data came from several sources, the stalest one is
+//! reported. A platform missing from the block contributed no data, e.g.
+//! because its fetch failed.
+//!
+//! JSON object bodies get the block under `meta.freshness`, and it is
+//! also sent as `X-Data-Freshness` (e.g. `google=cache;age=312, meta=live;age=0`)
+//! so list responses carry it too. Like quota warnings, bodies carrying an
+//! ETag are not rewritten. Streamed (NDJSON) responses are under way before
+//! the platforms are read, so they carry neither.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use super::campaign_aggregator::Platform;
+use super::store::MetricsStore;
+
+/// Header carrying the freshness of each platform's data
+pub const DATA_FRESHNESS_HEADER: &str = "x-data-freshness";
+
+tokio::task_local! {
+    static FRESHNESS: Arc<Mutex<Freshness>>;
+}
+
+/// Where a platform's data was read from
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    Live,
+    Cache,
+    Store,
+}
+
+impl DataSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataSource::Live => "live",
+            DataSource::Cache => "cache",
+            DataSource::Store => "store",
+        }
+    }
+}
+
+/// Freshness of one platform's data in a response
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PlatformFreshness {
+    pub source: DataSource,
+    /// When the data was fetched or recorded; `None` when unknown
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Seconds between `fetched_at` and the response
+    pub age_secs: Option<i64>,
+}
+
+/// Freshness by platform, e.g. `google`
+pub type Freshness = BTreeMap<String, PlatformFreshness>;
+
+/// Add `record` to `freshness`, keeping the stalest record per platform
+fn merge_record(freshness: &mut Freshness, platform: &str, record: PlatformFreshness) {
+    match freshness.get(platform) {
+        // `None` (unknown) sorts before any time, so it is kept
+        Some(current) if current.fetched_at <= record.fetched_at => {}
+        _ => {
+            freshness.insert(platform.to_string(), record);
+        }
+    }
+}
+
+/// Report that the current request read `platform`'s data from `source`
+pub fn record(platform: &Platform, source: DataSource, fetched_at: Option<DateTime<Utc>>) {
+    let record = PlatformFreshness {
+        source,
+        fetched_at,
+        age_secs: None,
+    };
+    let _ = FRESHNESS.try_with(|freshness| {
+        merge_record(&mut freshness.lock().unwrap(), platform.as_str(), record)
+    });
+}
+
+/// Report that the current request read `platform`'s metrics from `store`
+pub fn record_store(store: &MetricsStore, platform: &Platform) {
+    record(platform, DataSource::Store, store.recorded_at(platform));
+}
+
+/// Report the records of `freshness` to the current request, e.g. those
+/// of a fetch shared with other requests
+pub fn extend(freshness: &Freshness) {
+    let _ = FRESHNESS.try_with(|current| {
+        let mut current = current.lock().unwrap();
+        for (platform, record) in freshness {
+            merge_record(&mut current, platform, record.clone());
+        }
+    });
+}
+
+/// Run `fut`, collecting what it reports apart from the current request
+pub async fn collect<F: Future>(fut: F) -> (F::Output, Freshness) {
+    let freshness = Arc::new(Mutex::new(Freshness::new()));
+    let output = FRESHNESS.scope(freshness.clone(), fut).await;
+    let freshness = std::mem::take(&mut *freshness.lock().unwrap());
+    (output, freshness)
+}
+
+/// Fill in `age_secs` as of `now`
+fn with_ages(mut freshness: Freshness, now: DateTime<Utc>) -> Freshness {
+    for record in freshness.values_mut() {
+        record.age_secs = record.fetched_at.map(|at| (now - at).num_seconds().max(0));
+    }
+    freshness
+}
+
+/// `X-Data-Freshness` value, e.g. `google=cache;age=312, meta=store;age=unknown`
+fn header_value(freshness: &Freshness) -> String {
+    freshness
+        .iter()
+        .map(|(platform, record)| {
+            let age = record.age_secs.map_or("unknown".to_string(), |age| age.to_string());
+            format!("{}={};age={}", platform, record.source.as_str(), age)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Add `meta.freshness` to a JSON object body; other bodies are left as is
+fn with_freshness(body: &[u8], freshness: &Freshness) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let meta = value
+        .as_object_mut()?
+        .entry("meta")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()?;
+    meta.insert("freshness".to_string(), serde_json::to_value(freshness).ok()?);
+    serde_json::to_vec(&value).ok()
+}
+
+/// Middleware reporting the freshness of the data a request read
+pub async fn attach_freshness(req: Request, next: Next) -> Response {
+    let (response, freshness) = collect(next.run(req)).await;
+    if freshness.is_empty() {
+        return response;
+    }
+    let freshness = with_ages(freshness, Utc::now());
+
+    let (mut parts, body) = response.into_parts();
+    if let Ok(value) = HeaderValue::from_str(&header_value(&freshness)) {
+        parts.headers.insert(DATA_FRESHNESS_HEADER, value);
+    }
+
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes() == b"application/json");
+    if !is_json || parts.status != StatusCode::OK || parts.headers.contains_key(header::ETAG) {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not buffer response for data freshness");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    match with_freshness(&bytes, &freshness) {
+        Some(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Json, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_stalest_source_is_reported_per_platform() {
+        let now = Utc::now();
+        let cached = now - chrono::Duration::seconds(300);
+        let ((), freshness) = collect(async {
+            record(&Platform::Google, DataSource::Cache, Some(cached));
+            record(&Platform::Google, DataSource::Live, Some(now));
+            record(&Platform::Meta, DataSource::Live, Some(now));
+            record(&Platform::Meta, DataSource::Store, None);
+        })
+        .await;
+
+        let freshness = with_ages(freshness, now);
+        assert_eq!(freshness["google"].source, DataSource::Cache);
+        assert_eq!(freshness["google"].age_secs, Some(300));
+        assert_eq!(freshness["meta"].source, DataSource::Store);
+        assert_eq!(header_value(&freshness), "google=cache;age=300, meta=store;age=unknown");
+
+        // Outside a request nothing is recorded
+        record(&Platform::Google, DataSource::Live, Some(now));
+    }
+
+    #[tokio::test]
+    async fn test_object_bodies_get_a_meta_block() {
+        let app = Router::new()
+            .route(
+                "/summary",
+                get(|| async {
+                    record(&Platform::Google, DataSource::Live, Some(Utc::now()));
+                    Json(serde_json::json!({"total": 1}))
+                }),
+            )
+            .route("/status", get(|| async { Json(serde_json::json!({"ok": true})) }))
+            .layer(middleware::from_fn(attach_freshness));
+
+        let request = Request::get("/summary").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert!(response.headers()[DATA_FRESHNESS_HEADER]
+            .to_str()
+            .unwrap()
+            .starts_with("google=live;age="));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["meta"]["freshness"]["google"]["source"], "live");
+
+        let request = Request::get("/status").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert!(!response.headers().contains_key(DATA_FRESHNESS_HEADER));
+    }
+}
@@ -0,0 +1,124 @@
+//! # Golden Normalization Tests
+//!
+//! Gateway list pages of Google Ads and Meta, as recorded from the
+//! gateway with identifiers and names replaced, are kept under
+//! `fixtures/normalization/` next to the campaigns they must normalize
+//! into (`<platform>.golden.json`). Each page goes through what a connector
+//! does with it: element-by-element decoding, page envelope, then the
+//! built-in normalization tables.
+//!
+//! The fixtures cover the values that broke normalization before: statuses
+//! and types no table maps (`PENDING`, `PERFORMANCE_MAX`), Meta's
+//! outcome-based objectives, zero impressions, a budget in the trillions,
+//! amounts sent as strings and elements that are not campaigns at all.
+//!
+//! A deliberate change of the unified model shows up as a diff against the
+//! golden files; rewrite them with
+//!
+//! ```bash
+//! UPDATE_GOLDEN=1 cargo test golden
+//! ```
+//!
+//! and review the result like code.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+use super::campaign_aggregator::{Campaign, Platform};
+use super::lenient::{self, Lenient};
+use super::normalization::{builtin_tables, NormalizationRules};
+use super::pagination::{GatewayPage, Page};
+
+/// Set to rewrite the golden files from the current output
+const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(file!()).with_file_name("fixtures").join("normalization").join(name)
+}
+
+fn read_fixture<T: DeserializeOwned>(name: &str) -> T {
+    let path = fixture_path(name);
+    let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    serde_json::from_slice(&bytes).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// Decode and normalize the gateway page `<platform>.json`
+fn normalize_page(platform: &Platform) -> Value {
+    let page: GatewayPage<Lenient<Campaign>> = read_fixture(&format!("{}.json", platform.as_str()));
+    let Page { items, next } = Page::from(page);
+    let (mut campaigns, skipped) = lenient::split(platform, "campaigns", items);
+    NormalizationRules::default().apply(&builtin_tables(), &mut campaigns);
+
+    for campaign in &campaigns {
+        assert_eq!(&campaign.platform, platform);
+        assert_rates_consistent(campaign);
+    }
+    json!({
+        "next": next,
+        "skipped": skipped.iter().map(|r| r.id.clone()).collect::<Vec<_>>(),
+        "campaigns": campaigns,
+    })
+}
+
+/// The gateway's rates agree with its counts
+fn assert_rates_consistent(campaign: &Campaign) {
+    let metrics = &campaign.metrics;
+    let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(1.0);
+
+    if metrics.impressions > 0 {
+        let ctr = metrics.clicks as f64 / metrics.impressions as f64 * 100.0;
+        assert!(close(metrics.ctr, ctr), "{}: ctr {} != {}", campaign.id, metrics.ctr, ctr);
+    } else {
+        assert_eq!(metrics.ctr, 0.0, "{}: ctr without impressions", campaign.id);
+    }
+    if metrics.conversions > 0 {
+        let cpa = metrics.cost / metrics.conversions as f64;
+        assert!(close(metrics.cpa, cpa), "{}: cpa {} != {}", campaign.id, metrics.cpa, cpa);
+    }
+    if let (Some(reach), Some(frequency)) = (metrics.reach, metrics.frequency) {
+        let expected = metrics.impressions as f64 / reach as f64;
+        assert!(close(frequency, expected), "{}: frequency {}", campaign.id, frequency);
+    }
+}
+
+/// Compare `actual` with the golden file `name`, or rewrite it
+fn assert_golden(name: &str, actual: &Value) {
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        let path = fixture_path(name);
+        let mut json = serde_json::to_string_pretty(actual).unwrap();
+        json.push('\n');
+        std::fs::write(&path, json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        return;
+    }
+
+    let expected: Value = read_fixture(name);
+    assert!(
+        *actual == expected,
+        "{} differs from the normalized output (rewrite with {}=1):\n{}",
+        name,
+        UPDATE_ENV,
+        serde_json::to_string_pretty(actual).unwrap()
+    );
+}
+
+#[test]
+fn test_google_page_matches_golden() {
+    assert_golden("google.golden.json", &normalize_page(&Platform::Google));
+}
+
+#[test]
+fn test_meta_page_matches_golden() {
+    assert_golden("meta.golden.json", &normalize_page(&Platform::Meta));
+}
+
+#[test]
+fn test_golden_campaigns_survive_a_serde_round_trip() {
+    for name in ["google.golden.json", "meta.golden.json"] {
+        let golden: Value = read_fixture(name);
+        for value in golden["campaigns"].as_array().unwrap() {
+            let campaign: Campaign = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(&serde_json::to_value(&campaign).unwrap(), value, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! # Campaign Graph Example
+//!
+//! Returns the whole structure around a campaign (account → campaign →
+//! ad group / ad set → ad → creative, with audiences under their ad
+//! groups) as one nested document, so clients can render a structure tree
+//! without stitching the detail, ad and audience endpoints together.
+//! Related entities are loaded through the hydration loaders, one batch
+//! per level.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::hydration::{unsupported_as_empty, Ad, AdsByAdGroup, CampaignDetail, Hydrator, Loader};
+use super::ids::{AdGroupId, CampaignId};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Account,
+    Campaign,
+    AdGroup,
+    Ad,
+    Creative,
+    Audience,
+}
+
+/// Node of the campaign structure tree
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GraphNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<CampaignStatus>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<GraphNode>,
+}
+
+impl GraphNode {
+    fn leaf(id: &str, kind: NodeKind, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            kind,
+            name: name.to_string(),
+            status: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// `GET /campaigns/{id}/graph` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignGraph {
+    pub platform: Platform,
+    /// Account node when the account is known, otherwise the campaign node
+    pub root: GraphNode,
+}
+
+/// Assemble the tree from a hydrated campaign and its ads keyed by ad group
+pub fn build_graph(detail: CampaignDetail, ads: &HashMap<AdGroupId, Vec<Ad>>) -> CampaignGraph {
+    let ad_groups = detail
+        .ad_groups
+        .into_iter()
+        .map(|group| {
+            let ad_nodes = ads
+                .get(&group.ad_group.id)
+                .into_iter()
+                .flatten()
+                .map(|ad| GraphNode {
+                    id: ad.id.clone(),
+                    kind: NodeKind::Ad,
+                    name: ad.name.clone(),
+                    status: Some(ad.status.clone()),
+                    children: ad
+                        .creative
+                        .iter()
+                        .map(|c| GraphNode::leaf(&c.id, NodeKind::Creative, &c.name))
+                        .collect(),
+                });
+
+            let audience_nodes = group
+                .audiences
+                .iter()
+                .map(|a| GraphNode::leaf(&a.audience_id, NodeKind::Audience, &a.name));
+
+            GraphNode {
+                id: group.ad_group.id.into(),
+                kind: NodeKind::AdGroup,
+                name: group.ad_group.name,
+                status: Some(group.ad_group.status),
+                children: ad_nodes.chain(audience_nodes).collect(),
+            }
+        })
+        .collect();
+
+    let campaign = detail.campaign;
+    let campaign_node = GraphNode {
+        id: campaign.id.into(),
+        kind: NodeKind::Campaign,
+        name: campaign.name,
+        status: Some(campaign.status),
+        children: ad_groups,
+    };
+
+    let root = match campaign.account_id {
+        Some(account_id) => GraphNode {
+            name: account_id.to_string(),
+            id: account_id.into(),
+            kind: NodeKind::Account,
+            status: None,
+            children: vec![campaign_node],
+        },
+        None => campaign_node,
+    };
+
+    CampaignGraph {
+        platform: campaign.platform,
+        root,
+    }
+}
+
+/// GET /campaigns/{id}/graph - Structure tree of one campaign
+pub async fn get_campaign_graph(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<CampaignId>,
+) -> Result<Json<CampaignGraph>, ApiError> {
+    let campaign = aggregate_campaigns(&state)
+        .await
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+    let connector = state
+        .connectors
+        .get(&campaign.platform)
+        .ok_or_else(|| ApiError::NotSupported(campaign.platform.as_str().to_string()))?;
+
+    let detail = Hydrator::new(&state.connectors)
+        .hydrate(vec![campaign])
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+
+    let ad_group_ids: Vec<AdGroupId> =
+        detail.ad_groups.iter().map(|g| g.ad_group.id.clone()).collect();
+    let ads = unsupported_as_empty(Loader::new(AdsByAdGroup(connector)).load_many(&ad_group_ids).await)?;
+
+    Ok(Json(build_graph(detail, &ads)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::audiences::AudienceInfo;
+    use super::super::campaign_aggregator::Campaign;
+    use super::super::hydration::{AdGroup, AdGroupDetail, ApprovalStatus, Creative};
+
+    #[test]
+    fn test_graph_nests_account_campaign_ad_group_ad_creative() {
+        let mut campaign = Campaign::sample("m1", Platform::Meta);
+        campaign.account_id = Some("act_42".into());
+
+        let detail = CampaignDetail {
+            campaign,
+            ad_groups: vec![AdGroupDetail {
+                ad_group: AdGroup {
+                    id: "as1".into(),
+                    campaign_id: "m1".into(),
+                    name: "Lookalike 1%".to_string(),
+                    status: CampaignStatus::Enabled,
+                    audience_ids: vec!["aud1".to_string()],
+                },
+                audiences: vec![AudienceInfo {
+                    platform: Platform::Meta,
+                    audience_id: "aud1".to_string(),
+                    name: "Buyers LAL".to_string(),
+                    size: 1_000,
+                    campaign_ids: vec!["m1".into()],
+                }],
+            }],
+        };
+        let ads = HashMap::from([(
+            "as1".into(),
+            vec![Ad {
+                id: "ad1".to_string(),
+                ad_group_id: "as1".into(),
+                name: "Video A".to_string(),
+                status: CampaignStatus::Paused,
+                creative: Some(Creative {
+                    id: "cr1".to_string(),
+                    name: "video_a.mp4".to_string(),
+                    format: "video".to_string(),
+                }),
+                headline: None,
+                description: None,
+                assets: Vec::new(),
+                approval_status: ApprovalStatus::Approved,
+                preview_url: None,
+                metrics: None,
+            }],
+        )]);
+
+        let graph = build_graph(detail, &ads);
+
+        assert_eq!(graph.root.kind, NodeKind::Account);
+        let campaign = &graph.root.children[0];
+        let ad_group = &campaign.children[0];
+        assert_eq!(ad_group.kind, NodeKind::AdGroup);
+        assert_eq!(ad_group.children[0].kind, NodeKind::Ad);
+        assert_eq!(ad_group.children[0].children[0].kind, NodeKind::Creative);
+        assert_eq!(ad_group.children[1].kind, NodeKind::Audience);
+    }
+}
@@ -0,0 +1,130 @@
+//! # Health Check Example
+//!
+//! `GET /healthz` answers as long as the process is serving requests and
+//! is meant for liveness probes. `GET /readyz` probes the gateway route of
+//! every registered platform and returns 503 once none of them is
+//! reachable, so load balancers stop routing traffic to an instance that
+//! would only answer with empty campaign lists. While the startup
+//! warm-up runs (see `warmup`), `GET /readyz` answers 503 as well.
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::campaign_aggregator::AppState;
+use super::connectors::{ConnectorError, PlatformConnector};
+
+/// Time a single platform probe may take before the platform counts as down
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Probes slower than this report the platform as degraded
+pub const DEGRADED_LATENCY: Duration = Duration::from_millis(800);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlatformHealth {
+    Ok,
+    /// Gateway reachable but slow or answering with errors
+    Degraded,
+    /// Gateway unreachable or timed out
+    Down,
+}
+
+/// Body of `GET /readyz`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Startup warm-up still running
+    #[serde(default)]
+    pub warming_up: bool,
+    pub platforms: BTreeMap<String, PlatformHealth>,
+}
+
+/// Classify a probe outcome
+pub fn classify(result: &Result<(), ConnectorError>, latency: Duration) -> PlatformHealth {
+    match result {
+        Ok(()) if latency > DEGRADED_LATENCY => PlatformHealth::Degraded,
+        Ok(()) => PlatformHealth::Ok,
+        Err(ConnectorError::Http(e)) if e.is_connect() || e.is_timeout() => PlatformHealth::Down,
+        Err(_) => PlatformHealth::Degraded,
+    }
+}
+
+/// Probe one connector, bounded by `PROBE_TIMEOUT`
+pub async fn probe(connector: &dyn PlatformConnector) -> PlatformHealth {
+    let started = Instant::now();
+
+    match tokio::time::timeout(PROBE_TIMEOUT, connector.health_check()).await {
+        Ok(result) => classify(&result, started.elapsed()),
+        Err(_) => PlatformHealth::Down,
+    }
+}
+
+/// Probe every registered platform concurrently
+///
+/// The instance is ready while at least one platform is not down.
+pub async fn check_readiness(state: &AppState) -> Readiness {
+    let connectors = state.connectors.all();
+    let results = futures::future::join_all(connectors.iter().map(|c| probe(c.as_ref()))).await;
+
+    let platforms: BTreeMap<String, PlatformHealth> = connectors
+        .iter()
+        .map(|c| c.platform().as_str().to_string())
+        .zip(results)
+        .collect();
+
+    let warming_up = state.config.warmup.enabled && !state.warmup.is_done();
+
+    Readiness {
+        ready: !warming_up && platforms.values().any(|h| *h != PlatformHealth::Down),
+        warming_up,
+        platforms,
+    }
+}
+
+/// GET /healthz - Liveness probe
+pub async fn healthz() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// GET /readyz - Readiness probe with per-platform gateway status
+pub async fn readyz(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Readiness>) {
+    let readiness = check_readiness(&state).await;
+
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(readiness))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+    use super::super::connectors::MockConnector;
+
+    fn mock(platform: Platform, fail: bool) -> MockConnector {
+        MockConnector {
+            platform,
+            campaigns: Vec::new(),
+            fail,
+        }
+    }
+
+    #[test]
+    fn test_slow_success_is_degraded() {
+        assert_eq!(classify(&Ok(()), Duration::from_millis(10)), PlatformHealth::Ok);
+        assert_eq!(classify(&Ok(()), Duration::from_secs(1)), PlatformHealth::Degraded);
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_failing_connector() {
+        assert_eq!(probe(&mock(Platform::Google, false)).await, PlatformHealth::Ok);
+        assert_eq!(probe(&mock(Platform::Meta, true)).await, PlatformHealth::Degraded);
+    }
+}
@@ -0,0 +1,355 @@
+//! # Campaign History Example
+//!
+//! The sync worker records one snapshot of every campaign per day (see
+//! `store`). Two views are built on it:
+//!
+//! - `GET /campaigns/{platform}/{id}/history?days=30` returns the
+//!   campaign's daily delivery and budget, with the annotations dated
+//!   within it;
+//! - `GET /campaigns/summary?compare=previous_period&days=7` adds the
+//!   totals of the last `days` days next to the `days` before, with the
+//!   absolute and percentage change of each metric. Changes of metrics
+//!   that are already percentages (CTR) are in percentage points.
+
+use axum::extract::{Path, Query, State};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::annotations::Annotation;
+use super::campaign_aggregator::{combine_metrics, AppState, CampaignMetrics, Platform};
+use super::error::ApiError;
+use super::finite::{safe_div, Finite};
+use super::freshness;
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::store::{DailyMetrics, MetricsStore};
+use super::timezones;
+use super::units;
+
+/// Days returned by `GET /campaigns/{platform}/{id}/history` without `?days=`
+pub const DEFAULT_HISTORY_DAYS: u32 = 30;
+
+/// Period length compared without `?days=`
+pub const DEFAULT_PERIOD_DAYS: u32 = 7;
+
+/// Longest history or comparison period
+pub const MAX_DAYS: u32 = 365;
+
+/// Metrics compared between periods; the daily series has no conversion
+/// value, so ROAS is not among them
+pub const COMPARED_METRICS: &[&str] =
+    &["impressions", "clicks", "conversions", "cost", "ctr", "cpa"];
+
+fn validate_days(days: Option<u32>, default: u32) -> Result<u32, ApiError> {
+    match days.unwrap_or(default) {
+        days @ 1..=MAX_DAYS => Ok(days),
+        _ => Err(ApiError::Validation(format!("days must be between 1 and {}", MAX_DAYS))),
+    }
+}
+
+/// Query parameters for `GET /campaigns/{platform}/{id}/history`
+#[derive(Debug, Deserialize, Default)]
+pub struct HistoryQuery {
+    pub days: Option<u32>,
+}
+
+/// One day of a campaign's history
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HistoryPoint {
+    pub date: NaiveDate,
+    /// `None` for days recorded before budgets were
+    pub daily_budget: Option<f64>,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub spend: f64,
+    pub ctr: f64,
+    pub cpa: Option<f64>,
+}
+
+/// `GET /campaigns/{platform}/{id}/history` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignHistory {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    /// Oldest first
+    pub days: Vec<HistoryPoint>,
+    /// Notes dated within `days`, oldest first; see `annotations`
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+/// The last `days` recorded days of a campaign
+pub fn campaign_history(
+    store: &MetricsStore,
+    campaign: &GlobalCampaignId,
+    days: usize,
+) -> CampaignHistory {
+    let budgets = store.budget_series(campaign);
+
+    CampaignHistory {
+        platform: campaign.platform.clone(),
+        campaign_id: campaign.id.clone(),
+        days: store
+            .recent(campaign, days)
+            .into_iter()
+            .map(|day| HistoryPoint {
+                date: day.date,
+                daily_budget: budgets.get(&day.date).copied(),
+                impressions: day.impressions,
+                clicks: day.clicks,
+                conversions: day.conversions,
+                spend: day.spend,
+                ctr: day.ctr(),
+                cpa: day.cpa(),
+            })
+            .collect(),
+        annotations: Vec::new(),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// The `days` days before the current period
+    PreviousPeriod,
+}
+
+/// Query parameters for `GET /campaigns/summary`
+#[derive(Debug, Deserialize, Default)]
+pub struct SummaryQuery {
+    pub compare: Option<Comparison>,
+    /// Length of the compared periods
+    pub days: Option<u32>,
+}
+
+/// Inclusive date range
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// Days of the period with at least one snapshot
+    pub days_with_data: usize,
+}
+
+/// Change of one metric between two periods
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MetricDelta {
+    pub current: f64,
+    pub previous: f64,
+    /// `current - previous`
+    pub absolute: f64,
+    /// Relative change in percent, `None` when the previous value is 0
+    pub percent: Option<f64>,
+    /// `absolute` is in percentage points, the metric being a percentage
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub percentage_points: bool,
+}
+
+impl MetricDelta {
+    pub fn new(current: f64, previous: f64, percentage_points: bool) -> Self {
+        let absolute = current - previous;
+        Self {
+            current,
+            previous,
+            absolute,
+            percent: safe_div(absolute, previous.abs()).map(|r| r * 100.0),
+            percentage_points,
+        }
+    }
+}
+
+/// `compare` block of the summary
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodComparison {
+    pub current: Period,
+    pub previous: Period,
+    pub deltas: BTreeMap<String, MetricDelta>,
+}
+
+/// Day values of `campaigns` within `start..=end`, as metrics rows
+fn period_rows(
+    store: &MetricsStore,
+    campaigns: &[GlobalCampaignId],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> (Vec<CampaignMetrics>, Period) {
+    let days: Vec<DailyMetrics> = campaigns
+        .iter()
+        .flat_map(|campaign| store.daily_series(campaign))
+        .filter(|day| (start..=end).contains(&day.date))
+        .collect();
+
+    let mut dates: Vec<NaiveDate> = days.iter().map(|d| d.date).collect();
+    dates.sort();
+    dates.dedup();
+
+    let rows = days
+        .into_iter()
+        .map(|day| CampaignMetrics {
+            impressions: day.impressions,
+            clicks: day.clicks,
+            conversions: day.conversions,
+            cost: day.spend,
+            conversion_value: day.conversion_value,
+            ..Default::default()
+        })
+        .collect();
+
+    (rows, Period { start, end, days_with_data: dates.len() })
+}
+
+/// Totals of one campaign over `start..=end`, zero without snapshots in it
+pub fn period_totals(
+    store: &MetricsStore,
+    campaign: &GlobalCampaignId,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> CampaignMetrics {
+    let (rows, _) = period_rows(store, std::slice::from_ref(campaign), start, end);
+    combine_metrics(&rows)
+}
+
+/// Totals of the `days` days ending on `today` against the `days` before
+pub fn compare_periods(
+    store: &MetricsStore,
+    campaigns: &[GlobalCampaignId],
+    today: NaiveDate,
+    days: u32,
+) -> PeriodComparison {
+    let length = Duration::days(days as i64);
+    let current_start = today - length + Duration::days(1);
+    let previous_end = current_start - Duration::days(1);
+
+    let (current_rows, current) = period_rows(store, campaigns, current_start, today);
+    let (previous_rows, previous) =
+        period_rows(store, campaigns, previous_end - length + Duration::days(1), previous_end);
+    let current_rows: Vec<&CampaignMetrics> = current_rows.iter().collect();
+    let previous_rows: Vec<&CampaignMetrics> = previous_rows.iter().collect();
+
+    let deltas = COMPARED_METRICS
+        .iter()
+        .filter_map(|name| units::metric(name))
+        .map(|def| {
+            let delta = MetricDelta::new(
+                units::aggregate(def, &current_rows),
+                units::aggregate(def, &previous_rows),
+                def.percent,
+            );
+            (def.name.to_string(), delta)
+        })
+        .collect();
+
+    PeriodComparison { current, previous, deltas }
+}
+
+/// Comparison requested by `query`, if any
+pub fn summary_comparison(
+    store: &MetricsStore,
+    campaigns: &[GlobalCampaignId],
+    query: &SummaryQuery,
+) -> Result<Option<PeriodComparison>, ApiError> {
+    let Some(Comparison::PreviousPeriod) = query.compare else {
+        return Ok(None);
+    };
+    let days = validate_days(query.days, DEFAULT_PERIOD_DAYS)?;
+
+    Ok(Some(compare_periods(store, campaigns, timezones::today(), days)))
+}
+
+/// GET /campaigns/{platform}/{id}/history - Daily metrics and budget of a campaign
+pub async fn get_campaign_history(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Finite<CampaignHistory>, ApiError> {
+    let days = validate_days(query.days, DEFAULT_HISTORY_DAYS)?;
+    let campaign = GlobalCampaignId::new(platform, id);
+
+    let mut history = campaign_history(&state.metrics_store, &campaign, days as usize);
+    let (Some(first), Some(last)) = (history.days.first(), history.days.last()) else {
+        return Err(ApiError::NotFound(format!("history of campaign {}", campaign)));
+    };
+    history.annotations = state.annotations.between(&campaign, first.date, last.date);
+    freshness::record_store(&state.metrics_store, &campaign.platform);
+
+    Ok(Finite(history))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Campaign;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn google(id: &str) -> GlobalCampaignId {
+        GlobalCampaignId::new(Platform::Google, id)
+    }
+
+    /// Record month-to-date totals growing by `per_day` each day
+    fn record_days(
+        store: &MetricsStore,
+        campaign: &mut Campaign,
+        days: std::ops::RangeInclusive<u32>,
+        per_day: (u64, u64, f64),
+    ) {
+        for d in days {
+            campaign.metrics.impressions += per_day.0;
+            campaign.metrics.clicks += per_day.1;
+            campaign.metrics.cost += per_day.2;
+            store.record(day(d), std::slice::from_ref(campaign));
+        }
+    }
+
+    #[test]
+    fn test_history_includes_budget_of_each_day() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        record_days(&store, &mut campaign, 1..=2, (1_000, 10, 50.0));
+        campaign.daily_budget = 150.0;
+        record_days(&store, &mut campaign, 3..=3, (1_000, 30, 50.0));
+
+        let history = campaign_history(&store, &google("g1"), 2);
+
+        assert_eq!(history.days.len(), 2);
+        assert_eq!(history.days[0].date, day(2));
+        assert_eq!(history.days[0].daily_budget, Some(100.0));
+        assert_eq!(history.days[1].daily_budget, Some(150.0));
+        assert!((history.days[1].ctr - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_previous_period_deltas() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        // Days 1-7: 100 spend, 2% CTR; days 8-14: 123 spend, 1.6% CTR
+        record_days(&store, &mut campaign, 1..=7, (1_000, 20, 100.0));
+        record_days(&store, &mut campaign, 8..=14, (1_000, 16, 123.0));
+
+        let comparison = compare_periods(&store, &[google("g1")], day(14), 7);
+
+        assert_eq!(comparison.current.start, day(8));
+        assert_eq!(comparison.previous.end, day(7));
+        assert_eq!(comparison.previous.days_with_data, 7);
+
+        let cost = &comparison.deltas["cost"];
+        assert!((cost.absolute - 161.0).abs() < 1e-9);
+        assert!((cost.percent.unwrap() - 23.0).abs() < 1e-9);
+        assert!(!cost.percentage_points);
+
+        let ctr = &comparison.deltas["ctr"];
+        assert!((ctr.absolute + 0.4).abs() < 1e-9);
+        assert!(ctr.percentage_points);
+    }
+
+    #[test]
+    fn test_percent_change_undefined_without_previous_data() {
+        let delta = MetricDelta::new(50.0, 0.0, false);
+        assert_eq!(delta.absolute, 50.0);
+        assert_eq!(delta.percent, None);
+    }
+}
@@ -0,0 +1,413 @@
+//! # Hydration Example
+//!
+//! Detail responses embed related entities (campaign → ad groups →
+//! audiences). Fetching them one parent at a time costs one gateway call
+//! per campaign and per ad group; instead every level of the tree is
+//! loaded through a `Loader` that deduplicates keys, serves repeats from a
+//! per-request cache, and fetches the rest in batched calls. Hydrating any
+//! number of campaigns costs at most two calls per platform (plus one per
+//! `MAX_BATCH` keys).
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use super::audiences::AudienceInfo;
+use super::call_budget;
+use super::campaign_aggregator::{
+    find_campaign, AppState, Campaign, CampaignMetrics, CampaignStatus, Platform,
+};
+use super::connectors::{ConnectorError, ConnectorRegistry, PlatformConnector};
+use super::error::ApiError;
+use super::ids::{AdGroupId, CampaignId};
+
+/// Ad group (ad set on Meta) as returned by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdGroup {
+    pub id: AdGroupId,
+    pub campaign_id: CampaignId,
+    pub name: String,
+    pub status: CampaignStatus,
+    #[serde(default)]
+    pub audience_ids: Vec<String>,
+}
+
+/// Creative attached to an ad
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Creative {
+    pub id: String,
+    pub name: String,
+    /// Image, video, carousel, responsive search ad, ...
+    pub format: String,
+}
+
+/// Platform review state of an ad
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Approved,
+    /// Approved with restrictions (Google `APPROVED_LIMITED`)
+    Limited,
+    Pending,
+    Disapproved,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Image,
+    Video,
+}
+
+/// Image or video used by an ad
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetRef {
+    pub id: String,
+    pub kind: AssetKind,
+    /// Image URL or video thumbnail
+    pub url: Option<String>,
+}
+
+/// Ad as returned by the gateway, unified across platforms
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Ad {
+    pub id: String,
+    pub ad_group_id: AdGroupId,
+    pub name: String,
+    pub status: CampaignStatus,
+    pub creative: Option<Creative>,
+    /// First headline of responsive ads, title of Meta ads
+    #[serde(default)]
+    pub headline: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<AssetRef>,
+    #[serde(default)]
+    pub approval_status: ApprovalStatus,
+    /// Shareable preview (Google ad preview, Meta `preview_shareable_link`)
+    #[serde(default)]
+    pub preview_url: Option<String>,
+    /// Lifetime delivery metrics of the ad
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<CampaignMetrics>,
+}
+
+/// Ad group with its audiences resolved
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdGroupDetail {
+    #[serde(flatten)]
+    pub ad_group: AdGroup,
+    pub audiences: Vec<AudienceInfo>,
+}
+
+/// Campaign with its ad groups and audiences resolved
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignDetail {
+    #[serde(flatten)]
+    pub campaign: Campaign,
+    pub ad_groups: Vec<AdGroupDetail>,
+}
+
+/// Fetch values for many keys in one upstream call
+#[async_trait]
+pub trait BatchLoad: Send + Sync {
+    type Key: Eq + Hash + Clone + Send + Sync;
+    type Value: Clone + Send + Sync;
+
+    /// Largest number of keys sent in a single call
+    const MAX_BATCH: usize = 100;
+
+    /// Values for the keys that exist; missing keys are simply absent
+    async fn load(&self, keys: &[Self::Key]) -> Result<HashMap<Self::Key, Self::Value>, ConnectorError>;
+}
+
+/// Deduplicating, caching front of a `BatchLoad`; lives for one request
+pub struct Loader<L: BatchLoad> {
+    batch: L,
+    cache: Mutex<HashMap<L::Key, L::Value>>,
+}
+
+impl<L: BatchLoad> Loader<L> {
+    pub fn new(batch: L) -> Self {
+        Self {
+            batch,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Values for `keys`, fetching only keys not loaded before
+    pub async fn load_many(&self, keys: &[L::Key]) -> Result<HashMap<L::Key, L::Value>, ConnectorError> {
+        let missing: Vec<L::Key> = {
+            let cache = self.cache.lock().unwrap();
+            let mut seen = HashSet::new();
+            keys.iter()
+                .filter(|k| !cache.contains_key(*k) && seen.insert(*k))
+                .cloned()
+                .collect()
+        };
+
+        for chunk in missing.chunks(L::MAX_BATCH) {
+            call_budget::charge(1)?;
+            let loaded = self.batch.load(chunk).await?;
+            self.cache.lock().unwrap().extend(loaded);
+        }
+
+        let cache = self.cache.lock().unwrap();
+        Ok(keys
+            .iter()
+            .filter_map(|k| cache.get(k).map(|v| (k.clone(), v.clone())))
+            .collect())
+    }
+}
+
+/// Ad groups keyed by campaign ID
+pub struct AdGroupsByCampaign(pub Arc<dyn PlatformConnector>);
+
+#[async_trait]
+impl BatchLoad for AdGroupsByCampaign {
+    type Key = CampaignId;
+    type Value = Vec<AdGroup>;
+
+    async fn load(
+        &self,
+        keys: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Vec<AdGroup>>, ConnectorError> {
+        // Campaigns without ad groups still get an entry, so they are cached
+        let mut grouped: HashMap<CampaignId, Vec<AdGroup>> =
+            keys.iter().map(|k| (k.clone(), Vec::new())).collect();
+
+        for ad_group in self.0.fetch_ad_groups(keys).await? {
+            grouped.entry(ad_group.campaign_id.clone()).or_default().push(ad_group);
+        }
+
+        Ok(grouped)
+    }
+}
+
+/// Audiences keyed by audience ID
+pub struct AudiencesById(pub Arc<dyn PlatformConnector>);
+
+#[async_trait]
+impl BatchLoad for AudiencesById {
+    type Key = String;
+    type Value = AudienceInfo;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, AudienceInfo>, ConnectorError> {
+        Ok(self
+            .0
+            .fetch_audiences_by_id(keys)
+            .await?
+            .into_iter()
+            .map(|a| (a.audience_id.clone(), a))
+            .collect())
+    }
+}
+
+/// Ads keyed by ad group ID
+pub struct AdsByAdGroup(pub Arc<dyn PlatformConnector>);
+
+#[async_trait]
+impl BatchLoad for AdsByAdGroup {
+    type Key = AdGroupId;
+    type Value = Vec<Ad>;
+
+    async fn load(&self, keys: &[AdGroupId]) -> Result<HashMap<AdGroupId, Vec<Ad>>, ConnectorError> {
+        let mut grouped: HashMap<AdGroupId, Vec<Ad>> =
+            keys.iter().map(|k| (k.clone(), Vec::new())).collect();
+
+        for ad in self.0.fetch_ads(keys).await? {
+            grouped.entry(ad.ad_group_id.clone()).or_default().push(ad);
+        }
+
+        Ok(grouped)
+    }
+}
+
+/// Loaders of one platform
+struct PlatformLoaders {
+    ad_groups: Loader<AdGroupsByCampaign>,
+    audiences: Loader<AudiencesById>,
+}
+
+/// Per-request hydration of campaigns into `CampaignDetail`s
+pub struct Hydrator {
+    loaders: HashMap<Platform, PlatformLoaders>,
+}
+
+impl Hydrator {
+    pub fn new(registry: &ConnectorRegistry) -> Self {
+        let loaders = registry
+            .all()
+            .iter()
+            .map(|connector| {
+                (
+                    connector.platform(),
+                    PlatformLoaders {
+                        ad_groups: Loader::new(AdGroupsByCampaign(connector.clone())),
+                        audiences: Loader::new(AudiencesById(connector.clone())),
+                    },
+                )
+            })
+            .collect();
+
+        Self { loaders }
+    }
+
+    /// Resolve ad groups and audiences of every campaign, level by level
+    ///
+    /// Platforms whose connector cannot look up ad groups hydrate to an
+    /// empty list instead of failing the whole response.
+    pub async fn hydrate(&self, campaigns: Vec<Campaign>) -> Result<Vec<CampaignDetail>, ConnectorError> {
+        let mut by_platform: HashMap<Platform, Vec<CampaignId>> = HashMap::new();
+        for campaign in &campaigns {
+            by_platform
+                .entry(campaign.platform.clone())
+                .or_default()
+                .push(campaign.id.clone());
+        }
+
+        // Level 1: ad groups, one batch per platform, platforms in parallel
+        let ad_group_loads = by_platform.iter().filter_map(|(platform, ids)| {
+            let loaders = self.loaders.get(platform)?;
+            Some(async move { (platform.clone(), loaders.ad_groups.load_many(ids).await) })
+        });
+        let mut ad_groups: HashMap<Platform, HashMap<CampaignId, Vec<AdGroup>>> = HashMap::new();
+        for (platform, result) in futures::future::join_all(ad_group_loads).await {
+            ad_groups.insert(platform, unsupported_as_empty(result)?);
+        }
+
+        // Level 2: audiences referenced by any of those ad groups
+        let audience_loads = ad_groups.iter().filter_map(|(platform, groups)| {
+            let loaders = self.loaders.get(platform)?;
+            let ids: Vec<String> = groups
+                .values()
+                .flatten()
+                .flat_map(|g| g.audience_ids.iter().cloned())
+                .collect();
+            Some(async move { (platform.clone(), loaders.audiences.load_many(&ids).await) })
+        });
+        let mut audiences: HashMap<Platform, HashMap<String, AudienceInfo>> = HashMap::new();
+        for (platform, result) in futures::future::join_all(audience_loads).await {
+            audiences.insert(platform, unsupported_as_empty(result)?);
+        }
+
+        Ok(campaigns
+            .into_iter()
+            .map(|campaign| {
+                let platform_audiences = audiences.get(&campaign.platform);
+                let ad_groups = ad_groups
+                    .get(&campaign.platform)
+                    .and_then(|groups| groups.get(&campaign.id))
+                    .cloned()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|ad_group| AdGroupDetail {
+                        audiences: ad_group
+                            .audience_ids
+                            .iter()
+                            .filter_map(|id| platform_audiences?.get(id).cloned())
+                            .collect(),
+                        ad_group,
+                    })
+                    .collect();
+
+                CampaignDetail { campaign, ad_groups }
+            })
+            .collect())
+    }
+}
+
+pub fn unsupported_as_empty<K, V>(
+    result: Result<HashMap<K, V>, ConnectorError>,
+) -> Result<HashMap<K, V>, ConnectorError> {
+    match result {
+        Err(ConnectorError::NotSupported(_)) => Ok(HashMap::new()),
+        other => other,
+    }
+}
+
+/// GET /campaigns/{id}/detail - Campaign with ad groups and audiences
+pub async fn get_campaign_detail(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<CampaignId>,
+) -> Result<Json<CampaignDetail>, ApiError> {
+    let campaign = find_campaign(&state, &id)
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+
+    let detail = Hydrator::new(&state.connectors)
+        .hydrate(vec![campaign])
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+
+    Ok(Json(detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Squares its keys and counts upstream calls
+    #[derive(Default)]
+    struct Squares {
+        calls: AtomicUsize,
+        keys_requested: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl BatchLoad for Squares {
+        type Key = u32;
+        type Value = u32;
+        const MAX_BATCH: usize = 2;
+
+        async fn load(&self, keys: &[u32]) -> Result<HashMap<u32, u32>, ConnectorError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.keys_requested.fetch_add(keys.len(), Ordering::SeqCst);
+            Ok(keys.iter().map(|k| (*k, k * k)).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loader_dedupes_batches_and_caches() {
+        let loader = Loader::new(Squares::default());
+
+        let first = loader.load_many(&[1, 2, 2, 3, 1]).await.unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[&3], 9);
+
+        loader.load_many(&[3, 4]).await.unwrap();
+
+        // [1, 2] and [3] in chunks of two, then only [4]
+        assert_eq!(loader.batch.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(loader.batch.keys_requested.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_platform_hydrates_empty() {
+        let mut registry = ConnectorRegistry::default();
+        registry.register(Arc::new(super::super::connectors::MockConnector {
+            platform: Platform::Google,
+            campaigns: Vec::new(),
+            fail: false,
+        }));
+
+        let details = Hydrator::new(&registry)
+            .hydrate(vec![Campaign::sample("g1", Platform::Google)])
+            .await
+            .unwrap();
+
+        assert_eq!(details.len(), 1);
+        assert!(details[0].ad_groups.is_empty());
+    }
+}
@@ -0,0 +1,525 @@
+//! # Idempotency Key Example
+//!
+//! Clients retry writes after timeouts and dropped connections, and a
+//! retried budget change or campaign creation must not reach the ad
+//! platform twice. Every write request (`POST`, `PUT`, `PATCH`, `DELETE`)
+//! may carry an `Idempotency-Key` header:
+//!
+//! - the first request with a key runs normally and its response is kept
+//!   for `server.idempotency_ttl_secs` (24 hours by default);
+//! - a repeat with the same key, method, path and body gets the stored
+//!   response back, marked `Idempotent-Replayed: true`, without running
+//!   the handler again;
+//! - a repeat while the first request is still running gets `409`, and a
+//!   repeat with a different body gets `422`.
+//!
+//! Keys are scoped per `X-Tenant-Id`. `423` (change freeze) and `429`
+//! (rate limit) responses are not kept: nothing was forwarded, and the
+//! client is meant to retry later with the same key.
+//!
+//! With shared state (feature `redis`), keys are claimed in Redis so a
+//! retry reaching another replica is still replayed. A replica that dies
+//! mid-request holds its claim for at most `SHARED_IN_FLIGHT_TTL`; while
+//! Redis is unreachable, keys are claimed in the replica's memory.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+#[cfg(feature = "redis")]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::error::ApiError;
+#[cfg(feature = "redis")]
+use super::shared::{SharedError, SharedStore};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set on responses served from the store
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Longest accepted key
+pub const MAX_KEY_LEN: usize = 255;
+
+/// Largest request body read to fingerprint it; axum's default body limit
+const MAX_REQUEST_BYTES: usize = 2 * 1024 * 1024;
+
+/// Longest a shared claim outlives a replica that died mid-request
+#[cfg(feature = "redis")]
+pub const SHARED_IN_FLIGHT_TTL: Duration = Duration::from_secs(300);
+
+/// Tenant, method, path and key
+type ScopedKey = (String, Method, String, String);
+
+/// Response kept for replay
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl IntoResponse for StoredResponse {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, self.body).into_response();
+        *response.headers_mut() = self.headers;
+        response
+            .headers_mut()
+            .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+/// Claim or stored response of a key, as kept in Redis
+#[cfg(feature = "redis")]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct SharedSlot {
+    fingerprint: String,
+    /// `None` while the request runs
+    response: Option<SharedResponse>,
+}
+
+#[cfg(feature = "redis")]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct SharedResponse {
+    status: u16,
+    /// Headers with a non-UTF-8 value are not kept
+    headers: Vec<(String, String)>,
+    /// Hex encoded
+    body: String,
+}
+
+#[cfg(feature = "redis")]
+impl From<&StoredResponse> for SharedResponse {
+    fn from(response: &StoredResponse) -> Self {
+        Self {
+            status: response.status.as_u16(),
+            headers: response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: hex::encode(&response.body),
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl SharedResponse {
+    fn into_stored(self) -> Result<StoredResponse, SharedError> {
+        let invalid = |what: &str| {
+            SharedError::Json(serde::de::Error::custom(format!("invalid stored {}", what)))
+        };
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name: axum::http::HeaderName = name.parse().map_err(|_| invalid("header"))?;
+            headers.insert(name, HeaderValue::from_str(value).map_err(|_| invalid("header"))?);
+        }
+
+        Ok(StoredResponse {
+            status: StatusCode::from_u16(self.status).map_err(|_| invalid("status"))?,
+            headers,
+            body: hex::decode(&self.body).map_err(|_| invalid("body"))?.into(),
+        })
+    }
+}
+
+/// Key of a scoped key in Redis
+#[cfg(feature = "redis")]
+fn shared_key((tenant, method, path, key): &ScopedKey) -> String {
+    let scope = [tenant.as_str(), method.as_str(), path, key].join("\0");
+    format!("idempotency:{}", hex::encode(Sha256::digest(scope)))
+}
+
+enum Slot {
+    InFlight {
+        fingerprint: String,
+    },
+    Done {
+        fingerprint: String,
+        response: StoredResponse,
+        stored_at: Instant,
+    },
+}
+
+/// What to do with a keyed request
+enum Claim {
+    /// First request with this key: run it
+    Run,
+    Replay(StoredResponse),
+    InFlight,
+    Mismatch,
+}
+
+/// Responses of keyed write requests, kept for `ttl`
+#[derive(Default)]
+pub struct IdempotencyStore {
+    ttl: Duration,
+    slots: Mutex<HashMap<ScopedKey, Slot>>,
+    /// Claims and responses shared with the other replicas
+    #[cfg(feature = "redis")]
+    shared: Option<Arc<SharedStore>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            ..Default::default()
+        }
+    }
+
+    /// Claim keys in `shared` rather than in memory
+    #[cfg(feature = "redis")]
+    pub fn with_shared(mut self, shared: Arc<SharedStore>) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// Claim `key`, in Redis when shared
+    async fn acquire(&self, key: &ScopedKey, fingerprint: &str) -> Claim {
+        #[cfg(feature = "redis")]
+        if let Some(shared) = &self.shared {
+            match claim_shared(shared, key, fingerprint).await {
+                Ok(claim) => return claim,
+                Err(e) => tracing::warn!(error = %e, "shared idempotency claim failed"),
+            }
+        }
+        self.claim(key, fingerprint)
+    }
+
+    /// Keep the response of a claimed key, in Redis when shared
+    async fn finish(&self, key: &ScopedKey, fingerprint: &str, response: StoredResponse) {
+        #[cfg(feature = "redis")]
+        if let Some(shared) = &self.shared {
+            let slot = SharedSlot {
+                fingerprint: fingerprint.to_string(),
+                response: Some(SharedResponse::from(&response)),
+            };
+            match shared.set(&shared_key(key), &slot, self.ttl).await {
+                Ok(()) => return,
+                Err(e) => tracing::warn!(error = %e, "shared idempotency store failed"),
+            }
+        }
+        self.complete(key, fingerprint.to_string(), response);
+    }
+
+    fn claim(&self, key: &ScopedKey, fingerprint: &str) -> Claim {
+        let mut slots = self.slots.lock().unwrap();
+        slots.retain(|_, slot| match slot {
+            Slot::InFlight { .. } => true,
+            Slot::Done { stored_at, .. } => stored_at.elapsed() < self.ttl,
+        });
+
+        match slots.get(key) {
+            Some(Slot::InFlight { fingerprint: f }) | Some(Slot::Done { fingerprint: f, .. })
+                if f != fingerprint =>
+            {
+                Claim::Mismatch
+            }
+            Some(Slot::InFlight { .. }) => Claim::InFlight,
+            Some(Slot::Done { response, .. }) => Claim::Replay(response.clone()),
+            None => {
+                let fingerprint = fingerprint.to_string();
+                slots.insert(key.clone(), Slot::InFlight { fingerprint });
+                Claim::Run
+            }
+        }
+    }
+
+    fn complete(&self, key: &ScopedKey, fingerprint: String, response: StoredResponse) {
+        let slot = Slot::Done {
+            fingerprint,
+            response,
+            stored_at: Instant::now(),
+        };
+        self.slots.lock().unwrap().insert(key.clone(), slot);
+    }
+
+    fn release(&self, key: &ScopedKey) {
+        let mut slots = self.slots.lock().unwrap();
+        if matches!(slots.get(key), Some(Slot::InFlight { .. })) {
+            slots.remove(key);
+        }
+    }
+
+    /// Drop the shared claim of `key` unless a response was stored since
+    #[cfg(feature = "redis")]
+    fn release_shared(&self, key: &ScopedKey, fingerprint: &str) {
+        let Some(shared) = self.shared.clone() else {
+            return;
+        };
+        let key = shared_key(key);
+        let in_flight = SharedSlot {
+            fingerprint: fingerprint.to_string(),
+            response: None,
+        };
+
+        // Called from `Drop`, which cannot wait for Redis
+        tokio::spawn(async move {
+            if let Err(e) = shared.delete_if(&key, &in_flight).await {
+                tracing::warn!(error = %e, "shared idempotency release failed");
+            }
+        });
+    }
+}
+
+#[cfg(feature = "redis")]
+async fn claim_shared(
+    shared: &SharedStore,
+    key: &ScopedKey,
+    fingerprint: &str,
+) -> Result<Claim, SharedError> {
+    let key = shared_key(key);
+    let in_flight = SharedSlot {
+        fingerprint: fingerprint.to_string(),
+        response: None,
+    };
+    if shared.set_new(&key, &in_flight, SHARED_IN_FLIGHT_TTL).await? {
+        return Ok(Claim::Run);
+    }
+
+    Ok(match shared.get::<SharedSlot>(&key).await? {
+        Some(slot) if slot.fingerprint != fingerprint => Claim::Mismatch,
+        Some(SharedSlot {
+            response: Some(response),
+            ..
+        }) => Claim::Replay(response.into_stored()?),
+        // Expired between the two calls; the client's retry claims it
+        Some(_) | None => Claim::InFlight,
+    })
+}
+
+/// Frees a claimed key when its request ends without a stored response,
+/// e.g. because the client disconnected and the handler was dropped
+struct Release<'a> {
+    store: &'a IdempotencyStore,
+    key: &'a ScopedKey,
+    #[cfg(feature = "redis")]
+    fingerprint: &'a str,
+}
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        self.store.release(self.key);
+        #[cfg(feature = "redis")]
+        self.store.release_shared(self.key, self.fingerprint);
+    }
+}
+
+fn is_write(method: &Method) -> bool {
+    matches!(*method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE)
+}
+
+/// Whether a response is kept for replay
+fn is_final(status: StatusCode) -> bool {
+    !matches!(status, StatusCode::LOCKED | StatusCode::TOO_MANY_REQUESTS)
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+}
+
+/// Middleware replaying the response of a repeated `Idempotency-Key`
+pub async fn enforce_idempotency(
+    State(store): State<Arc<IdempotencyStore>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !is_write(req.method()) {
+        return next.run(req).await;
+    }
+    let Some(key) = header(req.headers(), IDEMPOTENCY_KEY_HEADER).map(str::to_string) else {
+        return next.run(req).await;
+    };
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return ApiError::Validation(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_KEY_LEN
+        ))
+        .into_response();
+    }
+
+    let (parts, body) = req.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_REQUEST_BYTES).await {
+        Ok(body) => body,
+        Err(e) => return ApiError::Validation(e.to_string()).into_response(),
+    };
+    let fingerprint = hex::encode(Sha256::digest(&body));
+    let scoped = (
+        header(&parts.headers, "x-tenant-id").unwrap_or_default().to_string(),
+        parts.method.clone(),
+        parts.uri.path().to_string(),
+        key,
+    );
+
+    match store.acquire(&scoped, &fingerprint).await {
+        Claim::Run => {}
+        Claim::Replay(response) => return response.into_response(),
+        Claim::InFlight => {
+            return ApiError::Conflict(
+                "a request with this Idempotency-Key is still in progress".to_string(),
+            )
+            .into_response();
+        }
+        Claim::Mismatch => {
+            return ApiError::Validation(
+                "Idempotency-Key was already used with a different request".to_string(),
+            )
+            .into_response();
+        }
+    }
+
+    let _release = Release {
+        store: &store,
+        key: &scoped,
+        #[cfg(feature = "redis")]
+        fingerprint: &fingerprint,
+    };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !is_final(response.status()) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => return ApiError::Internal(e.to_string()).into_response(),
+    };
+    store
+        .finish(
+            &scoped,
+            &fingerprint,
+            StoredResponse {
+                status: parts.status,
+                headers: parts.headers.clone(),
+                body: body.clone(),
+            },
+        )
+        .await;
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    /// Router whose single write endpoint counts its calls
+    fn router(calls: Arc<AtomicUsize>) -> Router {
+        let store = Arc::new(IdempotencyStore::new(Duration::from_secs(60)));
+        Router::new()
+            .route(
+                "/campaigns/bulk",
+                post(move |body: String| {
+                    let calls = calls.clone();
+                    async move {
+                        let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                        format!("call {} with {}", n, body)
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(store, enforce_idempotency))
+    }
+
+    async fn send(router: &Router, key: Option<&str>, body: &str) -> Response {
+        let mut request = Request::post("/campaigns/bulk");
+        if let Some(key) = key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        let request = request.body(Body::from(body.to_string())).unwrap();
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repeated_key_replays_first_response() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(calls.clone());
+
+        let first = send(&router, Some("k1"), "budget=80").await;
+        assert!(first.headers().get(REPLAYED_HEADER).is_none());
+        assert_eq!(text(first).await, "call 1 with budget=80");
+
+        let retry = send(&router, Some("k1"), "budget=80").await;
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert_eq!(retry.headers()[REPLAYED_HEADER], "true");
+        assert_eq!(text(retry).await, "call 1 with budget=80");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_other_body_is_rejected() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(calls.clone());
+
+        send(&router, Some("k1"), "budget=80").await;
+        let reused = send(&router, Some("k1"), "budget=90").await;
+
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_requests_without_key_always_run() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(calls.clone());
+
+        send(&router, None, "budget=80").await;
+        send(&router, None, "budget=80").await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_in_flight_key_conflicts_until_released() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let key = (String::new(), Method::POST, "/campaigns".to_string(), "k1".to_string());
+
+        assert!(matches!(store.claim(&key, "f"), Claim::Run));
+        assert!(matches!(store.claim(&key, "f"), Claim::InFlight));
+
+        store.release(&key);
+        assert!(matches!(store.claim(&key, "f"), Claim::Run));
+    }
+
+    #[cfg(feature = "redis")]
+    #[test]
+    fn test_shared_responses_round_trip() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        let stored = StoredResponse {
+            status: StatusCode::CREATED,
+            headers,
+            body: Bytes::from_static(b"{\"id\":\"42\"}"),
+        };
+
+        let json = serde_json::to_string(&SharedResponse::from(&stored)).unwrap();
+        let shared: SharedResponse = serde_json::from_str(&json).unwrap();
+        let restored = shared.into_stored().unwrap();
+
+        assert_eq!(restored.status, StatusCode::CREATED);
+        assert_eq!(restored.headers, stored.headers);
+        assert_eq!(restored.body, stored.body);
+    }
+}
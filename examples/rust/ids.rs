@@ -0,0 +1,196 @@
+//! # Typed IDs Example
+//!
+//! Platform IDs are opaque strings, and a bare `String` happily carries a
+//! Meta ad set ID into a Google campaign mutation. Each kind of ID gets its
+//! own newtype, so the compiler rejects a mixed-up argument:
+//!
+//! - `CampaignId`, `AdGroupId`, `AccountId`: serialized as the bare string,
+//!   so request and response bodies are unchanged; they deref to `str` for
+//!   formatting into gateway URLs and compare with string literals;
+//! - `GlobalCampaignId { platform, id }`: a campaign across platforms, for
+//!   keys of cross-platform maps and for resolving the connector a
+//!   mutation goes to. Written `google:123` in paths and logs.
+//!
+//! Conversions from `String` are explicit (`CampaignId::from(..)`,
+//! `.into()`): that is where a raw ID from a request or a platform payload
+//! becomes a typed one.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use super::campaign_aggregator::Platform;
+
+macro_rules! string_id {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(
+            Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord,
+        )]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(id: impl Into<String>) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> String {
+                id.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Campaign ID on its platform
+    CampaignId
+);
+string_id!(
+    /// Ad group ID (ad set on Meta) on its platform
+    AdGroupId
+);
+string_id!(
+    /// Ad account ID (Google customer ID, Meta `act_` ID)
+    AccountId
+);
+
+/// A campaign on a specific platform
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GlobalCampaignId {
+    pub platform: Platform,
+    pub id: CampaignId,
+}
+
+impl GlobalCampaignId {
+    pub fn new(platform: Platform, id: impl Into<CampaignId>) -> Self {
+        Self {
+            platform,
+            id: id.into(),
+        }
+    }
+}
+
+impl fmt::Display for GlobalCampaignId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.platform.as_str(), self.id)
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("invalid campaign reference {0:?}, expected platform:id")]
+pub struct InvalidGlobalId(String);
+
+impl FromStr for GlobalCampaignId {
+    type Err = InvalidGlobalId;
+
+    /// Parse `google:123`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidGlobalId(s.to_string());
+        let (platform, id) = s.split_once(':').ok_or_else(invalid)?;
+        if id.is_empty() {
+            return Err(invalid());
+        }
+        let platform = serde_json::from_value(serde_json::Value::String(platform.to_string()))
+            .map_err(|_| invalid())?;
+
+        Ok(Self::new(platform, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_serialize_as_bare_strings() {
+        let id = CampaignId::from("123");
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""123""#);
+        assert_eq!(serde_json::from_str::<CampaignId>(r#""123""#).unwrap(), "123");
+
+        let global = GlobalCampaignId::new(Platform::Meta, "456");
+        assert_eq!(
+            serde_json::to_value(&global).unwrap(),
+            serde_json::json!({ "platform": "meta", "id": "456" })
+        );
+    }
+
+    #[test]
+    fn test_global_id_round_trips_through_its_string_form() {
+        let global = GlobalCampaignId::new(Platform::Google, "123");
+        assert_eq!(global.to_string(), "google:123");
+        assert_eq!("google:123".parse::<GlobalCampaignId>(), Ok(global));
+
+        assert!("123".parse::<GlobalCampaignId>().is_err());
+        assert!("google:".parse::<GlobalCampaignId>().is_err());
+        assert!("tiktok:1".parse::<GlobalCampaignId>().is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! # Insertion Order Example
+//!
+//! Insertion orders (IOs) are the signed agreements between the agency and
+//! a client: a budget, a flight period and the campaigns that deliver
+//! against it. This module tracks how much of each IO has been burned and
+//! raises alerts as delivery approaches or exceeds the signed value.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+use super::ids::GlobalCampaignId;
+use super::store::MetricsStore;
+use super::timezones;
+
+/// Share of the signed budget (in percent) at which an IO is flagged as
+/// approaching its limit
+pub const IO_WARNING_THRESHOLD: f64 = 90.0;
+
+/// Signed insertion order
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsertionOrder {
+    pub id: String,
+    pub client: String,
+    pub signed_budget: f64,
+    pub currency: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    /// Campaigns whose spend counts against this IO
+    pub campaign_ids: Vec<GlobalCampaignId>,
+}
+
+/// Request body for `POST /insertion-orders`
+#[derive(Debug, Deserialize)]
+pub struct CreateInsertionOrder {
+    pub client: String,
+    pub signed_budget: f64,
+    pub currency: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    #[serde(default)]
+    pub campaign_ids: Vec<GlobalCampaignId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IoAlert {
+    /// Delivery crossed `IO_WARNING_THRESHOLD` percent of the signed budget
+    Approaching,
+    /// Delivery reached or passed the signed budget
+    Exceeded,
+}
+
+/// Burn-down of an insertion order at a point in time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BurnDown {
+    pub insertion_order_id: String,
+    pub delivered: f64,
+    pub remaining: f64,
+    /// Delivered spend as a percentage of the signed budget
+    pub delivered_percentage: f64,
+    /// Elapsed share of the flight period, in percent
+    pub period_elapsed_percentage: f64,
+    /// Daily spend needed to deliver the remaining budget by `period_end`
+    pub required_daily_spend: f64,
+    pub alert: Option<IoAlert>,
+}
+
+/// In-memory insertion order registry
+#[derive(Default)]
+pub struct InsertionOrderStore {
+    orders: RwLock<HashMap<String, InsertionOrder>>,
+}
+
+impl InsertionOrderStore {
+    pub fn insert(&self, order: InsertionOrder) {
+        self.orders
+            .write()
+            .unwrap()
+            .insert(order.id.clone(), order);
+    }
+
+    pub fn get(&self, id: &str) -> Option<InsertionOrder> {
+        self.orders.read().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<InsertionOrder> {
+        self.orders.read().unwrap().values().cloned().collect()
+    }
+}
+
+/// Compute the burn-down of `order` from the recorded daily spend of its
+/// linked campaigns within the flight period
+///
+/// Snapshot totals are month-to-date, so delivery is summed from the daily
+/// history instead, which keeps flights spanning several months whole.
+pub fn compute_burn_down(
+    order: &InsertionOrder,
+    store: &MetricsStore,
+    today: NaiveDate,
+) -> BurnDown {
+    let flight = order.period_start..=order.period_end;
+    let delivered: f64 = order
+        .campaign_ids
+        .iter()
+        .flat_map(|campaign| store.daily_series(campaign))
+        .filter(|day| flight.contains(&day.date))
+        .map(|day| day.spend)
+        .sum();
+
+    let remaining = (order.signed_budget - delivered).max(0.0);
+
+    let delivered_percentage = if order.signed_budget > 0.0 {
+        delivered / order.signed_budget * 100.0
+    } else {
+        0.0
+    };
+
+    let total_days = (order.period_end - order.period_start).num_days() + 1;
+    let elapsed_days = ((today - order.period_start).num_days() + 1).clamp(0, total_days);
+    let remaining_days = total_days - elapsed_days;
+
+    let period_elapsed_percentage = if total_days > 0 {
+        elapsed_days as f64 / total_days as f64 * 100.0
+    } else {
+        100.0
+    };
+
+    let required_daily_spend = if remaining_days > 0 {
+        remaining / remaining_days as f64
+    } else {
+        0.0
+    };
+
+    let alert = if delivered_percentage >= 100.0 {
+        Some(IoAlert::Exceeded)
+    } else if delivered_percentage >= IO_WARNING_THRESHOLD {
+        Some(IoAlert::Approaching)
+    } else {
+        None
+    };
+
+    BurnDown {
+        insertion_order_id: order.id.clone(),
+        delivered,
+        remaining,
+        delivered_percentage,
+        period_elapsed_percentage,
+        required_daily_spend,
+        alert,
+    }
+}
+
+/// POST /insertion-orders - Register a signed insertion order
+pub async fn create_insertion_order(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateInsertionOrder>,
+) -> Result<Json<InsertionOrder>, ApiError> {
+    if body.signed_budget <= 0.0 || body.period_end < body.period_start {
+        return Err(ApiError::Validation(
+            "signed_budget must be positive and period_end must not precede period_start".to_string(),
+        ));
+    }
+
+    let order = InsertionOrder {
+        id: uuid::Uuid::new_v4().to_string(),
+        client: body.client,
+        signed_budget: body.signed_budget,
+        currency: body.currency,
+        period_start: body.period_start,
+        period_end: body.period_end,
+        campaign_ids: body.campaign_ids,
+    };
+
+    state.insertion_orders.insert(order.clone());
+
+    Ok(Json(order))
+}
+
+/// GET /insertion-orders - List insertion orders
+pub async fn list_insertion_orders(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<InsertionOrder>>, ApiError> {
+    Ok(Json(state.insertion_orders.list()))
+}
+
+/// GET /insertion-orders/{id}/burndown - Burn-down and alert state of one IO
+pub async fn get_burn_down(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BurnDown>, ApiError> {
+    let order = state
+        .insertion_orders
+        .get(&id)
+        .ok_or_else(|| ApiError::NotFound(format!("insertion order {}", id)))?;
+
+    Ok(Json(compute_burn_down(&order, &state.metrics_store, timezones::today())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{Campaign, Platform};
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    fn campaign(id: &str, cost: f64) -> Campaign {
+        let platform = if id.starts_with('m') { Platform::Meta } else { Platform::Google };
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.metrics.cost = cost;
+        campaign
+    }
+
+    fn order() -> InsertionOrder {
+        InsertionOrder {
+            id: "io-1".to_string(),
+            client: "STOC Casa Jardim".to_string(),
+            signed_budget: 10_000.0,
+            currency: "BRL".to_string(),
+            period_start: day(11, 1),
+            period_end: day(11, 30),
+            campaign_ids: vec![
+                GlobalCampaignId::new(Platform::Google, "g1"),
+                GlobalCampaignId::new(Platform::Meta, "m1"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_burn_down_counts_only_linked_campaigns() {
+        let store = MetricsStore::default();
+        // Same ID as the linked Meta campaign, on Google
+        let mut google_m1 = campaign("m1", 7_777.0);
+        google_m1.platform = Platform::Google;
+        store.record(
+            day(11, 15),
+            &[
+                campaign("g1", 3_000.0),
+                campaign("m1", 2_000.0),
+                campaign("other", 9_999.0),
+                google_m1,
+            ],
+        );
+
+        let burn = compute_burn_down(&order(), &store, day(11, 15));
+
+        assert_eq!(burn.delivered, 5_000.0);
+        assert_eq!(burn.remaining, 5_000.0);
+        assert_eq!(burn.period_elapsed_percentage, 50.0);
+        assert_eq!(burn.required_daily_spend, 5_000.0 / 15.0);
+        assert_eq!(burn.alert, None);
+    }
+
+    #[test]
+    fn test_burn_down_sums_the_flight_across_months() {
+        let store = MetricsStore::default();
+        // Month-to-date totals reset on December 1st
+        store.record(day(10, 31), &[campaign("g1", 700.0)]);
+        store.record(day(11, 20), &[campaign("g1", 1_000.0)]);
+        store.record(day(11, 30), &[campaign("g1", 4_000.0)]);
+        store.record(day(12, 1), &[campaign("g1", 500.0)]);
+        store.record(day(12, 10), &[campaign("g1", 1_500.0)]);
+        store.record(day(12, 20), &[campaign("g1", 2_500.0)]);
+
+        let order = InsertionOrder { period_end: day(12, 10), ..order() };
+        let burn = compute_burn_down(&order, &store, day(12, 20));
+
+        assert_eq!(burn.delivered, 5_500.0);
+    }
+
+    #[test]
+    fn test_burn_down_alerts() {
+        let today = day(11, 20);
+
+        let store = MetricsStore::default();
+        store.record(today, &[campaign("g1", 9_200.0)]);
+        let approaching = compute_burn_down(&order(), &store, today);
+        assert_eq!(approaching.alert, Some(IoAlert::Approaching));
+
+        let store = MetricsStore::default();
+        store.record(today, &[campaign("g1", 10_500.0)]);
+        let exceeded = compute_burn_down(&order(), &store, today);
+        assert_eq!(exceeded.alert, Some(IoAlert::Exceeded));
+        assert_eq!(exceeded.remaining, 0.0);
+    }
+}
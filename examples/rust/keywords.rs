@@ -0,0 +1,356 @@
+//! # Keyword and Search Term Example
+//!
+//! Budget decisions on search campaigns need term-level visibility.
+//! `GET /campaigns/google/{id}/keywords` returns every keyword of a Google
+//! Ads campaign with its match type, quality score and delivery metrics.
+//! `GET /search-terms` returns the queries that actually triggered ads,
+//! unified across search platforms, with the spend on queries that never
+//! converted so they can be added as negatives.
+
+use axum::extract::{Path, Query, State};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::finite::{safe_div, Finite};
+use super::ids::{AdGroupId, CampaignId};
+use super::timezones;
+
+/// Quality scores at or below this are flagged for review
+pub const LOW_QUALITY_SCORE: u8 = 4;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MatchType {
+    Exact,
+    Phrase,
+    Broad,
+}
+
+/// Keyword row as returned by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeywordRow {
+    pub criterion_id: String,
+    pub ad_group_id: AdGroupId,
+    pub text: String,
+    pub match_type: MatchType,
+    pub status: CampaignStatus,
+    /// 1-10; absent until the keyword has enough traffic
+    pub quality_score: Option<u8>,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: f64,
+    pub cost: f64,
+}
+
+/// Keyword with derived metrics
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeywordPerformance {
+    #[serde(flatten)]
+    pub row: KeywordRow,
+    pub ctr: f64,
+    pub cpc: f64,
+    /// `None` without conversions
+    pub cpa: Option<f64>,
+}
+
+/// `GET /campaigns/google/{id}/keywords` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeywordReport {
+    pub campaign_id: CampaignId,
+    /// Keywords sorted by cost, highest first
+    pub keywords: Vec<KeywordPerformance>,
+    /// Keywords with a quality score at or below `LOW_QUALITY_SCORE`
+    pub low_quality: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchTermStatus {
+    /// Already added as a keyword
+    Added,
+    /// Already excluded as a negative keyword
+    Excluded,
+    None,
+}
+
+/// Query that triggered an ad, unified across search platforms
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchTerm {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub ad_group_id: AdGroupId,
+    pub search_term: String,
+    /// Keyword the query matched, when the platform reports it
+    pub matched_keyword: Option<String>,
+    pub match_type: Option<MatchType>,
+    pub status: SearchTermStatus,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: f64,
+    pub cost: f64,
+}
+
+/// `GET /search-terms` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchTermReport {
+    /// Terms sorted by cost, highest first
+    pub terms: Vec<SearchTerm>,
+    pub total_cost: f64,
+    /// Spend on terms without conversions that are not excluded yet
+    pub wasted_cost: f64,
+}
+
+/// Date range shared by both endpoints, in days of the reporting time zone;
+/// defaults to the last 30 days
+#[derive(Debug, Deserialize, Default)]
+pub struct KeywordQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Query parameters for `GET /search-terms`
+#[derive(Debug, Deserialize, Default)]
+pub struct SearchTermQuery {
+    pub platform: Option<Platform>,
+    pub campaign_id: Option<CampaignId>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    /// Hide terms that spent less than this
+    pub min_cost: Option<f64>,
+}
+
+pub fn date_range(
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let end = end.unwrap_or_else(timezones::today);
+    let start = start.unwrap_or(end - chrono::Duration::days(30));
+
+    if start > end {
+        return Err(ApiError::Validation("start_date must not be after end_date".to_string()));
+    }
+
+    Ok((start, end))
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    safe_div(numerator, denominator).unwrap_or(0.0)
+}
+
+/// Derive CTR, CPC and CPA and flag low quality keywords
+pub fn build_keyword_report(campaign_id: CampaignId, rows: Vec<KeywordRow>) -> KeywordReport {
+    let mut keywords: Vec<KeywordPerformance> = rows
+        .into_iter()
+        .map(|row| KeywordPerformance {
+            ctr: ratio(row.clicks as f64, row.impressions as f64) * 100.0,
+            cpc: ratio(row.cost, row.clicks as f64),
+            cpa: (row.conversions > 0.0).then(|| row.cost / row.conversions),
+            row,
+        })
+        .collect();
+
+    keywords.sort_by(|a, b| b.row.cost.total_cmp(&a.row.cost));
+
+    let low_quality = keywords
+        .iter()
+        .filter(|k| k.row.quality_score.is_some_and(|q| q <= LOW_QUALITY_SCORE))
+        .map(|k| k.row.criterion_id.clone())
+        .collect();
+
+    KeywordReport {
+        campaign_id,
+        keywords,
+        low_quality,
+    }
+}
+
+/// Filter, sort and total search terms from every platform
+pub fn build_search_term_report(mut terms: Vec<SearchTerm>, query: &SearchTermQuery) -> SearchTermReport {
+    terms.retain(|t| {
+        query.platform.as_ref().is_none_or(|p| *p == t.platform)
+            && query.campaign_id.as_ref().is_none_or(|c| *c == t.campaign_id)
+            && query.min_cost.is_none_or(|min| t.cost >= min)
+    });
+    terms.sort_by(|a, b| b.cost.total_cmp(&a.cost));
+
+    let total_cost = terms.iter().map(|t| t.cost).sum();
+    let wasted_cost = terms
+        .iter()
+        .filter(|t| t.conversions == 0.0 && t.status != SearchTermStatus::Excluded)
+        .map(|t| t.cost)
+        .sum();
+
+    SearchTermReport {
+        terms,
+        total_cost,
+        wasted_cost,
+    }
+}
+
+/// Fetch keyword rows of one Google Ads campaign from the gateway, for the
+/// Google days covering the reporting days `start..=end`
+async fn fetch_keywords(
+    state: &AppState,
+    campaign_id: &CampaignId,
+    (start, end): (NaiveDate, NaiveDate),
+) -> Result<Vec<KeywordRow>, reqwest::Error> {
+    let (start, end) = timezones::platform_range(&state.config.timezones, "google", (start, end));
+    let url = format!(
+        "{}/v1/google/campaigns/{}/keywords?start_date={}&end_date={}",
+        state.gateway_url, campaign_id, start, end
+    );
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.error_for_status()?.json::<Vec<KeywordRow>>().await
+}
+
+/// Fetch search terms of one platform from the gateway, for the platform
+/// days covering the reporting days `start..=end`
+pub async fn fetch_search_terms(
+    state: &AppState,
+    platform: &str,
+    (start, end): (NaiveDate, NaiveDate),
+) -> Result<Vec<SearchTerm>, reqwest::Error> {
+    let (start, end) = timezones::platform_range(&state.config.timezones, platform, (start, end));
+    let url = format!(
+        "{}/v1/{}/search-terms?start_date={}&end_date={}",
+        state.gateway_url, platform, start, end
+    );
+
+    let response = state.http_client
+        .get(&url)
+        .send()
+        .await?;
+
+    response.error_for_status()?.json::<Vec<SearchTerm>>().await
+}
+
+/// GET /campaigns/google/{id}/keywords - Keyword-level metrics of a Google campaign
+pub async fn get_keywords(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<CampaignId>,
+    Query(query): Query<KeywordQuery>,
+) -> Result<Finite<KeywordReport>, ApiError> {
+    let range = date_range(query.start_date, query.end_date)?;
+    let rows = fetch_keywords(&state, &id, range).await?;
+
+    Ok(Finite(build_keyword_report(id, rows)))
+}
+
+/// GET /search-terms - Unified search term report
+pub async fn get_search_terms(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchTermQuery>,
+) -> Result<Finite<SearchTermReport>, ApiError> {
+    let range = date_range(query.start_date, query.end_date)?;
+
+    // Meta has no search ads, so it has no search terms either
+    #[cfg(feature = "microsoft")]
+    let platforms: &[&str] = &["google", "microsoft"];
+    #[cfg(not(feature = "microsoft"))]
+    let platforms: &[&str] = &["google"];
+
+    let results =
+        futures::future::join_all(platforms.iter().map(|p| fetch_search_terms(&state, p, range))).await;
+
+    let mut terms = Vec::new();
+    for (platform, result) in platforms.iter().zip(results) {
+        match result {
+            Ok(rows) => terms.extend(rows),
+            Err(e) => tracing::warn!(platform, error = %e, "search term fetch failed"),
+        }
+    }
+
+    Ok(Finite(build_search_term_report(terms, &query)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyword(id: &str, quality_score: Option<u8>, cost: f64, conversions: f64) -> KeywordRow {
+        KeywordRow {
+            criterion_id: id.to_string(),
+            ad_group_id: "ag1".into(),
+            text: format!("keyword {}", id),
+            match_type: MatchType::Phrase,
+            status: CampaignStatus::Enabled,
+            quality_score,
+            impressions: 1_000,
+            clicks: 50,
+            conversions,
+            cost,
+        }
+    }
+
+    fn term(search_term: &str, cost: f64, conversions: f64, status: SearchTermStatus) -> SearchTerm {
+        SearchTerm {
+            platform: Platform::Google,
+            campaign_id: "c1".into(),
+            ad_group_id: "ag1".into(),
+            search_term: search_term.to_string(),
+            matched_keyword: Some("apartamento".to_string()),
+            match_type: Some(MatchType::Broad),
+            status,
+            impressions: 100,
+            clicks: 10,
+            conversions,
+            cost,
+        }
+    }
+
+    #[test]
+    fn test_keyword_report_derives_metrics_and_flags_low_quality() {
+        let report = build_keyword_report(
+            "c1".into(),
+            vec![
+                keyword("a", Some(7), 20.0, 2.0),
+                keyword("b", Some(3), 80.0, 0.0),
+                keyword("c", None, 5.0, 0.0),
+            ],
+        );
+
+        assert_eq!(report.keywords[0].row.criterion_id, "b");
+        assert_eq!(report.keywords[0].cpa, None);
+        assert_eq!(report.keywords[1].cpa, Some(10.0));
+        assert_eq!(report.keywords[1].ctr, 5.0);
+        assert_eq!(report.keywords[1].cpc, 0.4);
+        assert_eq!(report.low_quality, vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn test_search_term_report_counts_unconverted_spend_as_wasted() {
+        let terms = vec![
+            term("apartamento 2 quartos", 40.0, 3.0, SearchTermStatus::Added),
+            term("apartamento grátis", 25.0, 0.0, SearchTermStatus::None),
+            term("aluguel temporada", 10.0, 0.0, SearchTermStatus::Excluded),
+            term("apartamento barato", 1.0, 0.0, SearchTermStatus::None),
+        ];
+        let query = SearchTermQuery {
+            min_cost: Some(5.0),
+            ..Default::default()
+        };
+
+        let report = build_search_term_report(terms, &query);
+
+        assert_eq!(report.terms.len(), 3);
+        assert_eq!(report.terms[0].search_term, "apartamento 2 quartos");
+        assert_eq!(report.total_cost, 75.0);
+        assert_eq!(report.wasted_cost, 25.0);
+    }
+
+    #[test]
+    fn test_date_range_rejects_inverted_ranges() {
+        let start = NaiveDate::from_ymd_opt(2025, 11, 30);
+        let end = NaiveDate::from_ymd_opt(2025, 11, 1);
+
+        assert!(date_range(start, end).is_err());
+    }
+}
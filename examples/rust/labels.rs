@@ -0,0 +1,383 @@
+//! # Campaign Labels Example
+//!
+//! Labels group campaigns across platforms (`black-friday`, `brand`) and
+//! scope change freezes. They are managed on the platforms themselves, as
+//! Google labels and Meta ad labels:
+//!
+//! - `GET`/`POST /campaigns/{platform}/{id}/labels` read and attach labels,
+//!   and `DELETE /campaigns/{platform}/{id}/labels/{label}` detaches one.
+//!   Google labels are created by the gateway on first use; Meta ad labels
+//!   are attached by name;
+//! - `GET /labels` lists the unified label index: labels named alike on
+//!   both platforms (case and spacing aside) are one label. The index is
+//!   rebuilt by every sync and kept current by the label endpoints;
+//! - `GET /campaigns?label=brand,black-friday` keeps the campaigns carrying
+//!   one of the labels, whatever platform they run on.
+//!
+//! Label changes respect freeze windows like any other campaign change.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, Platform};
+use super::connectors::PlatformConnector;
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Longest label name accepted; Google's limit, stricter than Meta's
+pub const MAX_LABEL_LEN: usize = 40;
+
+/// Key of `name` in the label index: lower-cased, whitespace collapsed
+pub fn label_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Parse a comma-separated list of labels into index keys
+pub fn parse_label_list(list: &str) -> Vec<String> {
+    list.split(',').map(label_key).filter(|key| !key.is_empty()).collect()
+}
+
+/// Keep the campaigns carrying a label with one of `keys`
+pub fn filter_by_label(campaigns: &mut Vec<Campaign>, keys: &[String]) {
+    campaigns.retain(|c| carries_label(c, keys));
+}
+
+/// Whether `campaign` carries a label with one of `keys`
+pub fn carries_label(campaign: &Campaign, keys: &[String]) -> bool {
+    campaign.labels.iter().any(|label| keys.contains(&label_key(label)))
+}
+
+/// Request body for `POST /campaigns/{platform}/{id}/labels`
+#[derive(Debug, Deserialize)]
+pub struct LabelRequest {
+    pub label: String,
+}
+
+impl LabelRequest {
+    /// Trimmed label name, if acceptable to both platforms
+    pub fn validate(&self) -> Result<&str, ApiError> {
+        let label = self.label.trim();
+        if label.is_empty() {
+            return Err(ApiError::Validation("label must not be empty".to_string()));
+        }
+        if label.chars().count() > MAX_LABEL_LEN {
+            return Err(ApiError::Validation(format!(
+                "label must be at most {} characters",
+                MAX_LABEL_LEN
+            )));
+        }
+        if label.contains(',') {
+            return Err(ApiError::Validation("label must not contain commas".to_string()));
+        }
+        Ok(label)
+    }
+}
+
+/// Labels of one campaign
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignLabels {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub labels: Vec<String>,
+}
+
+/// Google campaign label; the gateway resolves or creates the label
+#[derive(Debug, Serialize)]
+pub struct GoogleCampaignLabel {
+    pub label_name: String,
+}
+
+/// Meta `adlabels` parameter of a campaign
+#[derive(Debug, Serialize)]
+pub struct MetaAdLabels {
+    pub adlabels: Vec<MetaAdLabel>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetaAdLabel {
+    pub name: String,
+}
+
+impl MetaAdLabels {
+    pub fn named(name: &str) -> Self {
+        Self {
+            adlabels: vec![MetaAdLabel {
+                name: name.to_string(),
+            }],
+        }
+    }
+}
+
+/// A label across platforms
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndexedLabel {
+    /// See `label_key`
+    pub key: String,
+    /// Name as first seen on a platform
+    pub name: String,
+    /// Campaigns carrying the label, ordered by platform and ID
+    pub campaigns: Vec<GlobalCampaignId>,
+}
+
+impl IndexedLabel {
+    fn insert(&mut self, campaign: &GlobalCampaignId) {
+        if !self.campaigns.contains(campaign) {
+            self.campaigns.push(campaign.clone());
+            self.campaigns.sort_by_cached_key(|c| c.to_string());
+        }
+    }
+}
+
+/// Labels of every platform, keyed by `label_key`
+///
+/// Empty until the first sync or `GET /labels`.
+#[derive(Default)]
+pub struct LabelIndex {
+    labels: RwLock<Option<BTreeMap<String, IndexedLabel>>>,
+}
+
+impl LabelIndex {
+    /// Replace the index with the labels of `campaigns`
+    pub fn rebuild(&self, campaigns: &[Campaign]) {
+        let mut labels: BTreeMap<String, IndexedLabel> = BTreeMap::new();
+        for campaign in campaigns {
+            let id = campaign.global_id();
+            for name in &campaign.labels {
+                let key = label_key(name);
+                if key.is_empty() {
+                    continue;
+                }
+                labels
+                    .entry(key.clone())
+                    .or_insert_with(|| IndexedLabel {
+                        key,
+                        name: name.trim().to_string(),
+                        campaigns: Vec::new(),
+                    })
+                    .insert(&id);
+            }
+        }
+
+        *self.labels.write().unwrap() = Some(labels);
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.labels.read().unwrap().is_some()
+    }
+
+    /// Note that `campaign` now carries `name`
+    pub fn attach(&self, campaign: &GlobalCampaignId, name: &str) {
+        let mut labels = self.labels.write().unwrap();
+        let Some(labels) = labels.as_mut() else {
+            return;
+        };
+        let key = label_key(name);
+        labels
+            .entry(key.clone())
+            .or_insert_with(|| IndexedLabel {
+                key,
+                name: name.to_string(),
+                campaigns: Vec::new(),
+            })
+            .insert(campaign);
+    }
+
+    /// Note that `campaign` no longer carries `name`; labels left without
+    /// campaigns are dropped
+    pub fn detach(&self, campaign: &GlobalCampaignId, name: &str) {
+        let mut labels = self.labels.write().unwrap();
+        let Some(labels) = labels.as_mut() else {
+            return;
+        };
+        let key = label_key(name);
+        if let Some(label) = labels.get_mut(&key) {
+            label.campaigns.retain(|c| c != campaign);
+            if label.campaigns.is_empty() {
+                labels.remove(&key);
+            }
+        }
+    }
+
+    /// Labels in key order
+    pub fn list(&self) -> Vec<IndexedLabel> {
+        self.labels
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|labels| labels.values().cloned())
+            .collect()
+    }
+}
+
+/// Connector of `platform` and its campaign `id`
+pub async fn find_on_platform(
+    state: &AppState,
+    platform: &Platform,
+    id: &CampaignId,
+) -> Result<(Arc<dyn PlatformConnector>, Campaign), ApiError> {
+    let connector = state
+        .connectors
+        .get(platform)
+        .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+    let campaign = connector
+        .fetch_campaigns()
+        .await?
+        .into_iter()
+        .find(|c| &c.id == id)
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+
+    Ok((connector, campaign))
+}
+
+/// Labels of `campaign` read back from its platform
+async fn read_back(
+    connector: &dyn PlatformConnector,
+    campaign: Campaign,
+) -> Result<CampaignLabels, ApiError> {
+    // Mutations invalidated the cached list, so this is a fresh read
+    let labels = connector
+        .fetch_campaigns()
+        .await?
+        .into_iter()
+        .find(|c| c.id == campaign.id)
+        .map(|c| c.labels)
+        .unwrap_or_default();
+
+    Ok(CampaignLabels {
+        platform: campaign.platform,
+        campaign_id: campaign.id,
+        labels,
+    })
+}
+
+/// GET /labels - Labels of every platform, unified by name
+pub async fn list_labels(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<IndexedLabel>>, ApiError> {
+    if !state.labels.is_built() {
+        state.labels.rebuild(&aggregate_campaigns(&state).await);
+    }
+
+    Ok(Json(state.labels.list()))
+}
+
+/// GET /campaigns/{platform}/{id}/labels - Labels of a campaign
+pub async fn get_campaign_labels(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<CampaignLabels>, ApiError> {
+    let (_, campaign) = find_on_platform(&state, &platform, &id).await?;
+
+    Ok(Json(CampaignLabels {
+        platform: campaign.platform,
+        campaign_id: campaign.id,
+        labels: campaign.labels,
+    }))
+}
+
+/// POST /campaigns/{platform}/{id}/labels - Attach a label to a campaign
+///
+/// Returns the campaign's labels read back from the platform.
+pub async fn add_campaign_label(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+    Json(request): Json<LabelRequest>,
+) -> Result<Json<CampaignLabels>, ApiError> {
+    let label = request.validate()?;
+    let (connector, campaign) = find_on_platform(&state, &platform, &id).await?;
+    let target = format!("add_label {}/{}", platform.as_str(), id);
+    state.freezes.check(&actor, &campaign.labels, &target, Utc::now())?;
+
+    connector.add_label(&id, label).await?;
+    state.labels.attach(&campaign.global_id(), label);
+
+    Ok(Json(read_back(connector.as_ref(), campaign).await?))
+}
+
+/// DELETE /campaigns/{platform}/{id}/labels/{label} - Detach a label
+///
+/// Returns the campaign's labels read back from the platform.
+pub async fn remove_campaign_label(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path((platform, id, label)): Path<(Platform, CampaignId, String)>,
+) -> Result<Json<CampaignLabels>, ApiError> {
+    let (connector, campaign) = find_on_platform(&state, &platform, &id).await?;
+    // Detach the label as the platform names it
+    let key = label_key(&label);
+    let label = campaign
+        .labels
+        .iter()
+        .find(|l| label_key(l) == key)
+        .cloned()
+        .ok_or_else(|| ApiError::NotFound(format!("label {:?} on campaign {}", label, id)))?;
+    let target = format!("remove_label {}/{}", platform.as_str(), id);
+    state.freezes.check(&actor, &campaign.labels, &target, Utc::now())?;
+
+    connector.remove_label(&id, &label).await?;
+    state.labels.detach(&campaign.global_id(), &label);
+
+    Ok(Json(read_back(connector.as_ref(), campaign).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(id: &str, platform: Platform, labels: &[&str]) -> Campaign {
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.labels = labels.iter().map(|l| l.to_string()).collect();
+        campaign
+    }
+
+    #[test]
+    fn test_labels_named_alike_are_one_label_across_platforms() {
+        let campaigns = [
+            campaign("g1", Platform::Google, &["Black Friday", "brand"]),
+            campaign("m1", Platform::Meta, &["black  friday"]),
+            campaign("m2", Platform::Meta, &[]),
+        ];
+        let index = LabelIndex::default();
+        assert!(index.list().is_empty());
+        index.rebuild(&campaigns);
+
+        let labels = index.list();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0].key, "black friday");
+        assert_eq!(labels[0].name, "Black Friday");
+        assert_eq!(
+            labels[0].campaigns,
+            [
+                GlobalCampaignId::new(Platform::Google, "g1"),
+                GlobalCampaignId::new(Platform::Meta, "m1"),
+            ]
+        );
+
+        index.detach(&GlobalCampaignId::new(Platform::Google, "g1"), "BRAND");
+        index.attach(&GlobalCampaignId::new(Platform::Meta, "m2"), "Retargeting");
+        let keys: Vec<String> = index.list().into_iter().map(|l| l.key).collect();
+        assert_eq!(keys, ["black friday", "retargeting"]);
+    }
+
+    #[test]
+    fn test_filter_matches_labels_case_insensitively() {
+        let mut campaigns = vec![
+            campaign("g1", Platform::Google, &["Brand"]),
+            campaign("m1", Platform::Meta, &["Prospecting"]),
+            campaign("m2", Platform::Meta, &["Black Friday"]),
+        ];
+
+        filter_by_label(&mut campaigns, &parse_label_list("brand, BLACK FRIDAY,"));
+
+        let ids: Vec<&str> = campaigns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["g1", "m2"]);
+    }
+}
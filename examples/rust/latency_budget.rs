@@ -0,0 +1,246 @@
+//! # Latency Budget Example
+//!
+//! One slow platform should not hold a whole dashboard: a read request
+//! gets a latency budget (`latency_budget.default_ms`, per route in
+//! `latency_budget.routes`), and platform fetches still running when it
+//! runs out are abandoned. The missed platforms are answered from what
+//! is already at hand instead:
+//!
+//! - the entity cache's last list of the platform, whatever its age;
+//! - otherwise the campaigns of the store's latest snapshots;
+//! - otherwise nothing, and the platform is missing from the response.
+//!
+//! Such responses are marked as degraded: `X-Degraded` lists the missed
+//! platforms, JSON object bodies get `"meta": {"degraded": true, ...}`,
+//! and `meta.freshness` tells where the missed platforms' data came from.
+//! They are sent with `Cache-Control: no-store` and no ETag, so clients
+//! and shared caches never keep a partial answer.
+//!
+//! Only `GET` routes are budgeted: writes read the current state of the
+//! campaigns they change. An abandoned fetch does not fill the entity
+//! cache; the sync worker, which runs without a budget, keeps it and the
+//! store current.
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+use super::campaign_aggregator::{AppState, Campaign, Platform};
+use super::config::AppConfig;
+use super::freshness::{self, DataSource};
+use super::sort::SortSpec;
+use super::versioning;
+
+/// Header listing the platforms a degraded response missed
+pub const DEGRADED_HEADER: &str = "x-degraded";
+
+tokio::task_local! {
+    static BUDGET: LatencyBudget;
+}
+
+/// Deadline of one request's platform fetches
+#[derive(Debug, Clone)]
+pub struct LatencyBudget {
+    deadline: Instant,
+    /// Platforms whose fetch missed the deadline
+    missed: Arc<Mutex<Vec<Platform>>>,
+}
+
+impl LatencyBudget {
+    pub fn new(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            missed: Arc::default(),
+        }
+    }
+
+    /// Platforms missed so far, in the order they were missed
+    pub fn missed(&self) -> Vec<Platform> {
+        self.missed.lock().unwrap().clone()
+    }
+
+    fn miss(&self, platform: &Platform) {
+        let mut missed = self.missed.lock().unwrap();
+        if !missed.contains(platform) {
+            missed.push(platform.clone());
+        }
+    }
+}
+
+/// Budget of the current request, if any
+pub fn current() -> Option<LatencyBudget> {
+    BUDGET.try_with(|budget| budget.clone()).ok()
+}
+
+/// Run `platform`'s fetch `fut` within the current request's budget;
+/// `None` when the budget ran out first. Unbudgeted work always completes.
+pub async fn within<F: Future>(platform: &Platform, fut: F) -> Option<F::Output> {
+    let Some(budget) = current() else {
+        return Some(fut.await);
+    };
+    match tokio::time::timeout_at(budget.deadline, fut).await {
+        Ok(output) => Some(output),
+        Err(_) => {
+            tracing::warn!(platform = platform.as_str(), "fetch missed the latency budget");
+            budget.miss(platform);
+            None
+        }
+    }
+}
+
+/// Run `fut` under the current deadline, collecting the platforms it
+/// misses apart from the current request, e.g. for a fetch shared with
+/// other requests
+pub async fn collect<F: Future>(fut: F) -> (F::Output, Vec<Platform>) {
+    let Some(current) = current() else {
+        return (fut.await, Vec::new());
+    };
+    let budget = LatencyBudget::new(current.deadline);
+    let output = BUDGET.scope(budget.clone(), fut).await;
+    (output, budget.missed())
+}
+
+/// Report platforms missed by a fetch shared with other requests
+pub fn extend(missed: &[Platform]) {
+    if let Some(budget) = current() {
+        for platform in missed {
+            budget.miss(platform);
+        }
+    }
+}
+
+/// Add the campaigns of the platforms the current request missed, from
+/// the entity cache or the store
+pub fn fill_missed(state: &AppState, campaigns: &mut Vec<Campaign>) {
+    let Some(budget) = current() else {
+        return;
+    };
+    let mut filled = false;
+    for platform in budget.missed() {
+        if campaigns.iter().any(|c| c.platform == platform) {
+            continue;
+        }
+        if let Some((cached, fetched_at)) = state.entity_cache.last_list(&platform) {
+            freshness::record(&platform, DataSource::Cache, Some(fetched_at));
+            campaigns.extend(cached);
+            filled = true;
+            continue;
+        }
+        let stored = state.metrics_store.latest_campaigns(&platform);
+        if !stored.is_empty() {
+            freshness::record_store(&state.metrics_store, &platform);
+            campaigns.extend(stored);
+            filled = true;
+        }
+    }
+    if filled {
+        SortSpec::default().sort(campaigns);
+    }
+}
+
+/// Mark a JSON object body as degraded; other bodies are left as is
+fn with_degraded(body: &[u8], missed: &[Platform]) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let meta = value
+        .as_object_mut()?
+        .entry("meta")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()?;
+    let platforms: Vec<&str> = missed.iter().map(|p| p.as_str()).collect();
+    meta.insert("degraded".to_string(), serde_json::Value::Bool(true));
+    meta.insert("degraded_platforms".to_string(), serde_json::to_value(platforms).ok()?);
+    serde_json::to_vec(&value).ok()
+}
+
+/// Route layer giving `GET` requests the latency budget of their route
+/// and marking the responses that missed platforms
+pub async fn enforce_latency_budget(
+    State(config): State<Arc<AppConfig>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let budget = match (req.method(), req.extensions().get::<MatchedPath>()) {
+        (&Method::GET, Some(route)) => {
+            config.latency_budget.budget_for(versioning::unversioned(route.as_str()))
+        }
+        _ => None,
+    };
+    let Some(budget) = budget else {
+        return next.run(req).await;
+    };
+
+    let budget = LatencyBudget::new(Instant::now() + budget);
+    let response = BUDGET.scope(budget.clone(), next.run(req)).await;
+    let missed = budget.missed();
+    if missed.is_empty() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let platforms: Vec<&str> = missed.iter().map(|p| p.as_str()).collect();
+    if let Ok(value) = HeaderValue::from_str(&platforms.join(", ")) {
+        parts.headers.insert(DEGRADED_HEADER, value);
+    }
+    parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    parts.headers.remove(header::ETAG);
+
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes() == b"application/json");
+    if !is_json || parts.status != StatusCode::OK {
+        return Response::from_parts(parts, body);
+    }
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not buffer degraded response");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    match with_degraded(&bytes, &missed) {
+        Some(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_fetches_past_the_deadline_are_missed() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            1
+        };
+        let budget = LatencyBudget::new(Instant::now() + Duration::from_millis(20));
+        let (fast, slow) = BUDGET
+            .scope(budget.clone(), async {
+                let fast = within(&Platform::Google, async { 2 }).await;
+                (fast, within(&Platform::Meta, slow).await)
+            })
+            .await;
+
+        assert_eq!((fast, slow), (Some(2), None));
+        assert_eq!(budget.missed(), vec![Platform::Meta]);
+        // Outside a request fetches run to completion
+        assert_eq!(within(&Platform::Meta, async { 3 }).await, Some(3));
+
+        let body = with_degraded(br#"{"total": 1}"#, &budget.missed()).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["meta"]["degraded"], true);
+        assert_eq!(body["meta"]["degraded_platforms"][0], "meta");
+    }
+}
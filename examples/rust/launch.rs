@@ -0,0 +1,455 @@
+//! # Cross-Platform Launch Example
+//!
+//! `POST /campaigns` launches one campaign on several platforms in one
+//! call. The body is platform-agnostic:
+//!
+//! ```json
+//! {
+//!   "name": "[ALL] Leads - Lançamento Vila Nova",
+//!   "objective": "LEADS",
+//!   "daily_budget": 150.0,
+//!   "currency": "BRL",
+//!   "platforms": ["google", "meta"],
+//!   "account_ids": {"google": "123-456-7890", "meta": "act_42"},
+//!   "targeting": {"countries": ["BR"], "age_min": 25, "age_max": 54}
+//! }
+//! ```
+//!
+//! and is translated into each platform's creation call through the
+//! gateway: Google gets an advertising channel, geo target constants and
+//! age range criteria; Meta an ODAX objective (`OUTCOME_LEADS`) and a
+//! targeting spec. Each platform gets the full daily budget.
+//!
+//! - Campaigns are created `PAUSED` unless the body sets `status`, so ads
+//!   can be added before anything spends;
+//! - the launch is all or nothing: when one platform fails, campaigns
+//!   already created on the others are removed again, like a failed
+//!   `apply`;
+//! - the new campaigns are registered in the metrics store, so they can be
+//!   annotated and reported on before the next sync lists them;
+//! - launches respect change freezes and the tenant's spend cap, and are
+//!   recorded in the audit log.
+//!
+//! Only Google and Meta campaigns can be launched. Google countries are
+//! resolved through `targeting`'s geo target table; countries it does not
+//! cover are rejected before anything is created.
+
+use axum::{extract::State, response::Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::audit::{AuditChange, AuditEntry};
+use super::call_budget;
+use super::campaign_aggregator::{
+    aggregate_campaigns, AppState, CampaignMetrics, CampaignObjective, CampaignStatus, Platform,
+};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{AccountId, CampaignId, GlobalCampaignId};
+use super::money::Currency;
+use super::store::Snapshot;
+use super::targeting::{self, MetaGeoLocations, MetaTargetingSpec};
+use super::timezones;
+
+/// Youngest age the platforms let campaigns target
+pub const MIN_TARGET_AGE: u8 = 18;
+
+/// Oldest age a template can name; Meta's `age_max` reads 65 as "and over"
+pub const MAX_TARGET_AGE: u8 = 65;
+
+/// Audience of a launched campaign, the same on every platform
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TargetingTemplate {
+    /// ISO 3166 country codes, e.g. `BR`; every country when empty
+    #[serde(default)]
+    pub countries: Vec<String>,
+    pub age_min: Option<u8>,
+    /// 65 means 65 and over
+    pub age_max: Option<u8>,
+}
+
+fn paused() -> CampaignStatus {
+    CampaignStatus::Paused
+}
+
+/// Request body for `POST /campaigns`
+#[derive(Debug, Deserialize, Clone)]
+pub struct LaunchRequest {
+    pub name: String,
+    pub objective: CampaignObjective,
+    /// Daily budget on each platform
+    pub daily_budget: f64,
+    pub currency: String,
+    pub platforms: Vec<Platform>,
+    /// Ad account per platform; the gateway's default account when absent
+    #[serde(default)]
+    pub account_ids: HashMap<Platform, AccountId>,
+    #[serde(default)]
+    pub targeting: TargetingTemplate,
+    #[serde(default = "paused")]
+    pub status: CampaignStatus,
+}
+
+/// Google creation body for the gateway's `POST campaigns` route
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GoogleCampaignCreate {
+    pub name: String,
+    pub status: CampaignStatus,
+    /// e.g. `SEARCH`, `DISPLAY`
+    pub advertising_channel_type: &'static str,
+    pub daily_budget: f64,
+    pub currency: String,
+    pub customer_id: Option<AccountId>,
+    pub location_ids: Vec<u64>,
+    pub age_range_ids: Vec<u64>,
+}
+
+/// Meta creation body for the gateway's `POST campaigns` route; the
+/// gateway creates the campaign and one ad set carrying the targeting
+#[derive(Debug, Serialize, Clone)]
+pub struct MetaCampaignCreate {
+    pub name: String,
+    pub status: CampaignStatus,
+    /// ODAX objective, e.g. `OUTCOME_LEADS`
+    pub objective: &'static str,
+    pub daily_budget: f64,
+    pub currency: String,
+    pub ad_account_id: Option<AccountId>,
+    pub targeting: MetaTargetingSpec,
+}
+
+/// Creation call of one platform
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum LaunchPayload {
+    Google(GoogleCampaignCreate),
+    Meta(MetaCampaignCreate),
+}
+
+/// Campaign created by a launch
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LaunchedCampaign {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+}
+
+/// Platform whose creation call failed
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LaunchFailure {
+    pub platform: Platform,
+    pub error_code: String,
+    pub error: String,
+}
+
+/// Response of `POST /campaigns`
+#[derive(Debug, Serialize)]
+pub struct LaunchReport {
+    /// Created campaigns; empty when the launch was rolled back
+    pub campaigns: Vec<LaunchedCampaign>,
+    /// Failed platforms; the launch was rolled back when any
+    pub failures: Vec<LaunchFailure>,
+    /// Campaigns created then removed again by the rollback
+    pub rolled_back: Vec<LaunchedCampaign>,
+}
+
+impl LaunchRequest {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.name.trim().is_empty() {
+            return Err(ApiError::Validation("name must not be empty".to_string()));
+        }
+        if !(self.daily_budget.is_finite() && self.daily_budget > 0.0) {
+            return Err(ApiError::Validation("daily_budget must be greater than zero".to_string()));
+        }
+        if self.platforms.is_empty() {
+            return Err(ApiError::Validation("platforms must not be empty".to_string()));
+        }
+        for (i, platform) in self.platforms.iter().enumerate() {
+            if self.platforms[..i].contains(platform) {
+                return Err(ApiError::Validation(format!(
+                    "platform {} is listed twice",
+                    platform.as_str()
+                )));
+            }
+        }
+        if matches!(self.status, CampaignStatus::Removed | CampaignStatus::Other(_)) {
+            return Err(ApiError::Validation("status must be ENABLED or PAUSED".to_string()));
+        }
+
+        let (min, max) = self.age_bounds();
+        if min < MIN_TARGET_AGE || max > MAX_TARGET_AGE || min > max {
+            return Err(ApiError::Validation(format!(
+                "targeting ages must satisfy {} <= age_min <= age_max <= {}",
+                MIN_TARGET_AGE, MAX_TARGET_AGE
+            )));
+        }
+        Ok(())
+    }
+
+    fn age_bounds(&self) -> (u8, u8) {
+        let targeting = &self.targeting;
+        (targeting.age_min.unwrap_or(MIN_TARGET_AGE), targeting.age_max.unwrap_or(MAX_TARGET_AGE))
+    }
+
+    fn ages_targeted(&self) -> bool {
+        self.targeting.age_min.is_some() || self.targeting.age_max.is_some()
+    }
+
+    /// Creation call of `platform` for this request
+    pub fn payload(&self, platform: &Platform) -> Result<LaunchPayload, ApiError> {
+        let objective = match &self.objective {
+            CampaignObjective::Other(other) => {
+                return Err(ApiError::Validation(format!("unknown objective {:?}", other)))
+            }
+            objective => objective,
+        };
+        let countries: Vec<String> =
+            self.targeting.countries.iter().map(|c| c.trim().to_ascii_uppercase()).collect();
+        let (age_min, age_max) = self.age_bounds();
+
+        match platform {
+            Platform::Google => {
+                let location_ids = countries
+                    .iter()
+                    .map(|code| {
+                        targeting::google_country_target(code).ok_or_else(|| {
+                            ApiError::Validation(format!(
+                                "no Google geo target for country {}",
+                                code
+                            ))
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                let age_range_ids = if self.ages_targeted() {
+                    targeting::google_age_ranges(age_min, age_max)
+                } else {
+                    Vec::new()
+                };
+                Ok(LaunchPayload::Google(GoogleCampaignCreate {
+                    name: self.name.trim().to_string(),
+                    status: self.status.clone(),
+                    advertising_channel_type: google_channel(objective),
+                    daily_budget: self.daily_budget,
+                    currency: Currency::new(&self.currency).to_string(),
+                    customer_id: self.account_ids.get(platform).cloned(),
+                    location_ids,
+                    age_range_ids,
+                }))
+            }
+            Platform::Meta => Ok(LaunchPayload::Meta(MetaCampaignCreate {
+                name: self.name.trim().to_string(),
+                status: self.status.clone(),
+                objective: meta_objective(objective),
+                daily_budget: self.daily_budget,
+                currency: Currency::new(&self.currency).to_string(),
+                ad_account_id: self.account_ids.get(platform).cloned(),
+                targeting: MetaTargetingSpec {
+                    geo_locations: MetaGeoLocations { countries, ..Default::default() },
+                    age_min: self.targeting.age_min.map(|_| age_min),
+                    age_max: self.targeting.age_max.map(|_| age_max),
+                    ..Default::default()
+                },
+            })),
+            #[cfg(any(feature = "linkedin", feature = "microsoft"))]
+            other => Err(ApiError::NotSupported(format!("launching on {}", other.as_str()))),
+        }
+    }
+}
+
+/// Google advertising channel serving `objective`
+fn google_channel(objective: &CampaignObjective) -> &'static str {
+    match objective {
+        CampaignObjective::Awareness => "DISPLAY",
+        CampaignObjective::Engagement => "VIDEO",
+        CampaignObjective::AppPromotion => "MULTI_CHANNEL",
+        _ => "SEARCH",
+    }
+}
+
+/// Meta ODAX objective of `objective`
+fn meta_objective(objective: &CampaignObjective) -> &'static str {
+    match objective {
+        CampaignObjective::Awareness => "OUTCOME_AWARENESS",
+        CampaignObjective::Traffic => "OUTCOME_TRAFFIC",
+        CampaignObjective::Engagement => "OUTCOME_ENGAGEMENT",
+        CampaignObjective::Leads => "OUTCOME_LEADS",
+        CampaignObjective::AppPromotion => "OUTCOME_APP_PROMOTION",
+        _ => "OUTCOME_SALES",
+    }
+}
+
+/// Register launched campaigns in the metrics store with empty metrics,
+/// without marking their platforms as recorded
+fn register(state: &AppState, request: &LaunchRequest, launched: &[LaunchedCampaign]) {
+    let today = timezones::today();
+    let snapshots = launched
+        .iter()
+        .map(|campaign| {
+            let snapshot = Snapshot {
+                metrics: CampaignMetrics::default(),
+                daily_budget: Some(request.daily_budget),
+                status: Some(request.status.clone()),
+                bid_strategy: None,
+                name: Some(request.name.trim().to_string()),
+                platform: Some(campaign.platform.clone()),
+                currency: Some(Currency::new(&request.currency)),
+            };
+            let id = GlobalCampaignId::new(campaign.platform.clone(), campaign.campaign_id.clone());
+            (id, BTreeMap::from([(today, snapshot)]))
+        })
+        .collect();
+    state.metrics_store.merge(snapshots);
+}
+
+/// POST /campaigns - Launch a campaign on several platforms at once
+pub async fn launch_campaigns(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Json(request): Json<LaunchRequest>,
+) -> Result<Json<LaunchReport>, ApiError> {
+    request.validate()?;
+    let payloads = request
+        .platforms
+        .iter()
+        .map(|platform| {
+            let connector = state
+                .connectors
+                .get(platform)
+                .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+            Ok((platform.clone(), connector, request.payload(platform)?))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let now = Utc::now();
+    let campaigns = aggregate_campaigns(&state).await;
+    let mut spend_cap = state.spend_caps.guard(&actor, &campaigns, now);
+    for platform in &request.platforms {
+        let target = format!("launch_campaign {}/{}", platform.as_str(), request.name.trim());
+        state.freezes.check(&actor, &[], &target, now)?;
+        let account_id = request.account_ids.get(platform);
+        spend_cap.check_new(account_id, &request.status, request.daily_budget, &target)?;
+    }
+
+    // Worst case every campaign is removed again
+    call_budget::charge(payloads.len() * 2)?;
+
+    let launches = payloads.iter().map(|(platform, connector, payload)| async move {
+        (platform.clone(), connector.launch_campaign(payload).await.map_err(ApiError::from))
+    });
+    let mut report =
+        LaunchReport { campaigns: Vec::new(), failures: Vec::new(), rolled_back: Vec::new() };
+    for (platform, result) in futures::future::join_all(launches).await {
+        match result {
+            Ok(campaign_id) => {
+                let created = AuditChange::Status { before: None, after: request.status.clone() };
+                let entry = AuditEntry::new(
+                    &actor,
+                    &platform,
+                    &campaign_id,
+                    created,
+                    &Ok::<_, ApiError>(()),
+                );
+                state.audit.record(entry);
+                report.campaigns.push(LaunchedCampaign { platform, campaign_id });
+            }
+            Err(e) => {
+                tracing::warn!(platform = platform.as_str(), error = %e, "campaign launch failed");
+                report.failures.push(LaunchFailure {
+                    platform,
+                    error_code: e.code().to_string(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    if report.failures.is_empty() {
+        register(&state, &request, &report.campaigns);
+        return Ok(Json(report));
+    }
+
+    for campaign in std::mem::take(&mut report.campaigns) {
+        let Some(connector) = state.connectors.get(&campaign.platform) else {
+            continue;
+        };
+        let result = connector
+            .mutate_status(&campaign.campaign_id, CampaignStatus::Removed)
+            .await
+            .map_err(ApiError::from);
+        let removed = AuditChange::Status {
+            before: Some(request.status.clone()),
+            after: CampaignStatus::Removed,
+        };
+        state.audit.record(AuditEntry::new(
+            &actor,
+            &campaign.platform,
+            &campaign.campaign_id,
+            removed,
+            &result,
+        ));
+        match result {
+            Ok(()) => report.rolled_back.push(campaign),
+            Err(e) => {
+                let id =
+                    GlobalCampaignId::new(campaign.platform.clone(), campaign.campaign_id.clone());
+                tracing::error!(campaign = %id, error = %e, "launch rollback failed");
+                // Still live on its platform, so still reported as created
+                report.campaigns.push(campaign);
+            }
+        }
+    }
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> LaunchRequest {
+        serde_json::from_value(serde_json::json!({
+            "name": " [ALL] Leads - Vila Nova ",
+            "objective": "LEADS",
+            "daily_budget": 150.0,
+            "currency": "brl",
+            "platforms": ["google", "meta"],
+            "account_ids": {"meta": "act_42"},
+            "targeting": {"countries": ["br", "US"], "age_min": 25, "age_max": 65}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_template_translates_to_each_platform() {
+        let request = request();
+        request.validate().unwrap();
+        assert_eq!(request.status, CampaignStatus::Paused);
+
+        let LaunchPayload::Google(google) = request.payload(&Platform::Google).unwrap() else {
+            panic!("expected a Google payload");
+        };
+        assert_eq!(google.advertising_channel_type, "SEARCH");
+        assert_eq!(google.location_ids, [2076, 2840]);
+        // 25-34 up to 65+
+        assert_eq!(google.age_range_ids, [503002, 503003, 503004, 503005, 503006]);
+        assert_eq!(
+            (google.name.as_str(), google.currency.as_str()),
+            ("[ALL] Leads - Vila Nova", "BRL")
+        );
+
+        let LaunchPayload::Meta(meta) = request.payload(&Platform::Meta).unwrap() else {
+            panic!("expected a Meta payload");
+        };
+        assert_eq!(meta.objective, "OUTCOME_LEADS");
+        assert_eq!(meta.ad_account_id.as_ref().map(|a| a.as_str()), Some("act_42"));
+        assert_eq!(meta.targeting.geo_locations.countries, ["BR", "US"]);
+        assert_eq!((meta.targeting.age_min, meta.targeting.age_max), (Some(25), Some(65)));
+
+        let mut unknown = request.clone();
+        unknown.targeting.countries = vec!["JP".to_string()];
+        assert!(unknown.payload(&Platform::Google).is_err());
+        let mut twice = request;
+        twice.platforms.push(Platform::Google);
+        assert!(twice.validate().is_err());
+    }
+}
@@ -0,0 +1,293 @@
+//! # Tolerant List Decoding Example
+//!
+//! `Vec<Campaign>` rejects a whole page as soon as one element does not
+//! match, so one malformed campaign in a gateway response used to blank
+//! its platform on the dashboard. List pages of the gateway are decoded
+//! element by element instead:
+//!
+//! - an element that does not deserialize is skipped and recorded with
+//!   its position on the page, its `id` when it has one, and the error;
+//! - responses of requests that read such a page report the skipped
+//!   records: JSON object bodies get a `skipped_records` member, and the
+//!   count is sent as `X-Skipped-Records` so list responses carry it too;
+//! - skipped records are counted per platform and route, and
+//!   `GET /internal/decode-errors` shows the counters with the most recent
+//!   records.
+//!
+//! A list kept by the entity cache is reported when it is read from the
+//! gateway, not again on each cache hit. Streamed (NDJSON) responses are
+//! already under way when pages are decoded, so they only count them.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{Json, Response},
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+
+use super::campaign_aggregator::{AppState, Platform};
+use super::error::ApiError;
+
+/// Header carrying the number of records skipped for a response
+pub const SKIPPED_RECORDS_HEADER: &str = "x-skipped-records";
+
+/// Skipped records kept for `GET /internal/decode-errors`
+pub const MAX_RECENT_RECORDS: usize = 100;
+
+tokio::task_local! {
+    static SKIPPED: Arc<Mutex<Vec<SkippedRecord>>>;
+}
+
+/// List element decoded on its own, so a bad one does not fail the list
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lenient<T> {
+    Valid(T),
+    Invalid {
+        id: Option<String>,
+        error: String,
+    },
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Malformed JSON still fails the page; only the shape is tolerated
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        Ok(match T::deserialize(&value) {
+            Ok(item) => Lenient::Valid(item),
+            Err(e) => Lenient::Invalid {
+                id: record_id(&value),
+                error: e.to_string(),
+            },
+        })
+    }
+}
+
+/// `id` of a record, when it has a string or numeric one
+fn record_id(value: &serde_json::Value) -> Option<String> {
+    match value.get("id")? {
+        serde_json::Value::String(id) => Some(id.clone()),
+        serde_json::Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Element of a gateway list that could not be decoded
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SkippedRecord {
+    pub platform: Platform,
+    /// Gateway route of the list, e.g. `campaigns`
+    pub route: String,
+    /// Position on its page, from 0
+    pub position: usize,
+    pub id: Option<String>,
+    pub error: String,
+}
+
+/// Split decoded elements into the valid ones and records of the others
+pub fn split<T>(
+    platform: &Platform,
+    route: &str,
+    items: Vec<Lenient<T>>,
+) -> (Vec<T>, Vec<SkippedRecord>) {
+    let mut valid = Vec::with_capacity(items.len());
+    let mut skipped = Vec::new();
+    for (position, item) in items.into_iter().enumerate() {
+        match item {
+            Lenient::Valid(item) => valid.push(item),
+            Lenient::Invalid { id, error } => skipped.push(SkippedRecord {
+                platform: platform.clone(),
+                route: route.to_string(),
+                position,
+                id,
+                error,
+            }),
+        }
+    }
+    (valid, skipped)
+}
+
+/// Skipped records counted per platform and route
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SkippedCount {
+    pub platform: Platform,
+    pub route: String,
+    pub skipped: u64,
+}
+
+/// Response of `GET /internal/decode-errors`
+#[derive(Debug, Serialize)]
+pub struct DecodeErrorReport {
+    pub counts: Vec<SkippedCount>,
+    /// Most recent first
+    pub recent: Vec<SkippedRecord>,
+}
+
+/// Counters and most recent skipped records of the process
+#[derive(Default)]
+pub struct DecodeErrors {
+    counts: RwLock<HashMap<(Platform, String), u64>>,
+    recent: Mutex<VecDeque<SkippedRecord>>,
+}
+
+impl DecodeErrors {
+    /// Count and log `records`, and report them to the current request
+    pub fn record(&self, records: Vec<SkippedRecord>) {
+        if records.is_empty() {
+            return;
+        }
+
+        {
+            let mut counts = self.counts.write().unwrap();
+            for record in &records {
+                tracing::warn!(
+                    platform = record.platform.as_str(),
+                    route = %record.route,
+                    position = record.position,
+                    id = ?record.id,
+                    error = %record.error,
+                    "skipped a record that could not be decoded"
+                );
+                *counts.entry((record.platform.clone(), record.route.clone())).or_default() += 1;
+            }
+        }
+        let _ = SKIPPED.try_with(|skipped| skipped.lock().unwrap().extend(records.iter().cloned()));
+
+        let mut recent = self.recent.lock().unwrap();
+        for record in records {
+            if recent.len() == MAX_RECENT_RECORDS {
+                recent.pop_back();
+            }
+            recent.push_front(record);
+        }
+    }
+
+    pub fn report(&self) -> DecodeErrorReport {
+        let mut counts: Vec<SkippedCount> = self
+            .counts
+            .read()
+            .unwrap()
+            .iter()
+            .map(|((platform, route), skipped)| SkippedCount {
+                platform: platform.clone(),
+                route: route.clone(),
+                skipped: *skipped,
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            (a.platform.as_str(), &a.route).cmp(&(b.platform.as_str(), &b.route))
+        });
+
+        DecodeErrorReport {
+            counts,
+            recent: self.recent.lock().unwrap().iter().cloned().collect(),
+        }
+    }
+}
+
+/// Add `skipped_records` to a JSON object body; other bodies are left as is
+fn with_skipped_records(body: &[u8], records: &[SkippedRecord]) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    value
+        .as_object_mut()?
+        .insert("skipped_records".to_string(), serde_json::to_value(records).ok()?);
+    serde_json::to_vec(&value).ok()
+}
+
+/// Middleware reporting the records skipped while serving a request
+///
+/// Like quota warnings, bodies carrying an ETag are not rewritten.
+pub async fn report_skipped_records(req: Request, next: Next) -> Response {
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let response = SKIPPED.scope(skipped.clone(), next.run(req)).await;
+    let records = std::mem::take(&mut *skipped.lock().unwrap());
+    if records.is_empty() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(SKIPPED_RECORDS_HEADER, HeaderValue::from(records.len()));
+
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes() == b"application/json");
+    if !is_json || parts.status != StatusCode::OK || parts.headers.contains_key(header::ETAG) {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not buffer response for skipped records");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    match with_skipped_records(&bytes, &records) {
+        Some(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// GET /internal/decode-errors - Skipped records per platform and route
+pub async fn get_decode_errors(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DecodeErrorReport>, ApiError> {
+    Ok(Json(state.decode_errors.report()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Campaign;
+    use super::super::pagination::{GatewayPage, Page};
+
+    #[test]
+    fn test_bad_elements_are_skipped_not_fatal() {
+        let good = serde_json::to_value(Campaign::sample("1", Platform::Google)).unwrap();
+        let body = serde_json::json!({
+            "results": [good, {"id": 2, "name": "No status"}, "garbage"],
+            "nextPageToken": "t2",
+        });
+
+        let page: GatewayPage<Lenient<Campaign>> = serde_json::from_value(body).unwrap();
+        let Page { items, next } = Page::from(page);
+        let (campaigns, skipped) = split(&Platform::Google, "campaigns", items);
+
+        assert_eq!(next.as_deref(), Some("t2"));
+        assert_eq!(campaigns.len(), 1);
+        let skipped: Vec<(usize, Option<&str>)> =
+            skipped.iter().map(|r| (r.position, r.id.as_deref())).collect();
+        assert_eq!(skipped, [(1, Some("2")), (2, None)]);
+    }
+
+    #[tokio::test]
+    async fn test_records_reach_the_request_and_the_counters() {
+        let errors = DecodeErrors::default();
+        let record = |position| SkippedRecord {
+            platform: Platform::Meta,
+            route: "campaigns".to_string(),
+            position,
+            id: None,
+            error: "missing field `status`".to_string(),
+        };
+
+        let skipped = Arc::new(Mutex::new(Vec::new()));
+        SKIPPED
+            .scope(skipped.clone(), async { errors.record(vec![record(0), record(3)]) })
+            .await;
+        errors.record(vec![record(1)]);
+
+        assert_eq!(skipped.lock().unwrap().len(), 2);
+        let report = errors.report();
+        assert_eq!(report.counts[0].skipped, 3);
+        assert_eq!(report.recent[0].position, 1);
+    }
+}
@@ -0,0 +1,433 @@
+//! # LinkedIn Ads Connector Example
+//!
+//! Fetches LinkedIn campaigns through the gateway (which owns the
+//! LinkedIn OAuth 2.0 three-legged flow and token refresh) and normalizes
+//! them into the unified `Campaign` model.
+//!
+//! Campaign lists are paginated with `start`/`count` offsets, read several
+//! pages at a time up to `gateway.pagination.max_pages`.
+//!
+//! Compiled only with the `linkedin` cargo feature.
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::campaign_aggregator::{
+    roas, Campaign, CampaignMetrics, CampaignObjective, CampaignStatus, Platform,
+};
+use super::config::PaginationConfig;
+use super::connectors::{ConnectorError, PlatformConnector};
+use super::credentials;
+use super::finite::safe_div;
+use super::ids::CampaignId;
+use super::pagination::{self, Page};
+
+/// Largest `count` LinkedIn serves per page
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// LinkedIn money amount; amounts are decimal strings
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedInMoney {
+    pub amount: String,
+    pub currency_code: String,
+}
+
+/// Campaign run schedule (epoch milliseconds)
+#[derive(Debug, Deserialize, Clone)]
+pub struct LinkedInRunSchedule {
+    pub start: i64,
+    pub end: Option<i64>,
+}
+
+/// Analytics attached by the gateway to each campaign
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedInAnalytics {
+    pub impressions: u64,
+    pub clicks: u64,
+    pub external_website_conversions: u32,
+    pub cost_in_local_currency: String,
+    /// Decimal string like the cost; absent without value tracking
+    #[serde(default)]
+    pub conversion_value_in_local_currency: Option<String>,
+    /// Members reached; LinkedIn approximates it
+    #[serde(default)]
+    pub approximate_unique_impressions: Option<u64>,
+    #[serde(default)]
+    pub video_views: Option<u64>,
+}
+
+/// Campaign as returned by the gateway's LinkedIn route
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedInCampaign {
+    pub id: i64,
+    pub name: String,
+    pub status: String,
+    /// e.g. `LEAD_GENERATION`, mapped by the normalization rules
+    #[serde(default)]
+    pub objective_type: Option<String>,
+    pub daily_budget: Option<LinkedInMoney>,
+    pub total_budget: Option<LinkedInMoney>,
+    pub run_schedule: Option<LinkedInRunSchedule>,
+    #[serde(default)]
+    pub analytics: LinkedInAnalytics,
+}
+
+/// `paging` block of LinkedIn collection responses
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LinkedInPaging {
+    pub start: u64,
+    pub count: u64,
+    /// Absent on some finders; a full page then means more may follow
+    pub total: Option<u64>,
+}
+
+/// Campaign list of the gateway's LinkedIn route
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum LinkedInPage {
+    /// `{"elements": [...], "paging": {"start": 0, "count": 100, "total": 250}}`
+    Paged {
+        elements: Vec<LinkedInCampaign>,
+        #[serde(default)]
+        paging: Option<LinkedInPaging>,
+    },
+    Unpaged(Vec<LinkedInCampaign>),
+}
+
+impl From<LinkedInPage> for Page<LinkedInCampaign> {
+    fn from(page: LinkedInPage) -> Self {
+        match page {
+            LinkedInPage::Paged {
+                elements,
+                paging: Some(paging),
+            } => {
+                let next_start = paging.start + elements.len() as u64;
+                let more = !elements.is_empty()
+                    && match paging.total {
+                        Some(total) => next_start < total,
+                        None => elements.len() as u64 >= paging.count,
+                    };
+                Page {
+                    next: more.then(|| next_start.to_string()),
+                    items: elements,
+                }
+            }
+            LinkedInPage::Paged { elements, .. } | LinkedInPage::Unpaged(elements) => Page {
+                items: elements,
+                next: None,
+            },
+        }
+    }
+}
+
+/// Map a LinkedIn campaign status into `CampaignStatus`
+///
+/// `DRAFT` and `COMPLETED` campaigns do not deliver, so they count as paused.
+/// Other values are left to the normalization rules.
+pub fn map_status(status: &str) -> CampaignStatus {
+    match status {
+        "ACTIVE" => CampaignStatus::Enabled,
+        "PAUSED" | "DRAFT" | "COMPLETED" => CampaignStatus::Paused,
+        "ARCHIVED" | "CANCELED" | "PENDING_DELETION" | "REMOVED" => CampaignStatus::Removed,
+        other => CampaignStatus::Other(other.to_string()),
+    }
+}
+
+/// Decimal string amount; `"NaN"` and `"Infinity"` parse as floats but
+/// are not amounts, so they count as missing
+fn parse_decimal(amount: &str) -> Option<f64> {
+    amount.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn parse_amount(money: &LinkedInMoney) -> f64 {
+    parse_decimal(&money.amount).unwrap_or(0.0)
+}
+
+fn millis_to_date(millis: i64) -> Option<NaiveDate> {
+    chrono::DateTime::from_timestamp_millis(millis).map(|dt| dt.date_naive())
+}
+
+/// Daily budget, spreading a lifetime budget over the run schedule when
+/// the campaign has no daily budget
+pub fn normalize_daily_budget(campaign: &LinkedInCampaign) -> (f64, Option<String>) {
+    if let Some(daily) = &campaign.daily_budget {
+        return (parse_amount(daily), Some(daily.currency_code.clone()));
+    }
+
+    let Some(total) = &campaign.total_budget else {
+        return (0.0, None);
+    };
+
+    let days = campaign
+        .run_schedule
+        .as_ref()
+        .and_then(|s| Some((millis_to_date(s.start)?, millis_to_date(s.end?)?)))
+        .map(|(start, end)| (end - start).num_days() + 1)
+        .filter(|days| *days > 0);
+
+    match days {
+        Some(days) => (
+            parse_amount(total) / days as f64,
+            Some(total.currency_code.clone()),
+        ),
+        // Open-ended lifetime budgets have no meaningful daily figure
+        None => (0.0, Some(total.currency_code.clone())),
+    }
+}
+
+/// Normalize a LinkedIn campaign into the unified model
+pub fn normalize(raw: LinkedInCampaign) -> Campaign {
+    let (daily_budget, currency) = normalize_daily_budget(&raw);
+    let analytics = &raw.analytics;
+
+    let cost = parse_decimal(&analytics.cost_in_local_currency).unwrap_or(0.0);
+    let conversion_value: f64 = analytics
+        .conversion_value_in_local_currency
+        .as_deref()
+        .and_then(parse_decimal)
+        .unwrap_or(0.0);
+    let impressions = analytics.impressions as f64;
+    let reach = analytics.approximate_unique_impressions;
+    let video_views = analytics.video_views;
+    let metrics = CampaignMetrics {
+        impressions: analytics.impressions,
+        clicks: analytics.clicks,
+        conversions: analytics.external_website_conversions,
+        cost,
+        ctr: if analytics.impressions > 0 {
+            analytics.clicks as f64 / analytics.impressions as f64 * 100.0
+        } else {
+            0.0
+        },
+        cpa: if analytics.external_website_conversions > 0 {
+            cost / analytics.external_website_conversions as f64
+        } else {
+            0.0
+        },
+        conversion_value,
+        roas: roas(conversion_value, cost),
+        conversion_actions: Vec::new(),
+        reach,
+        frequency: reach.and_then(|reach| safe_div(impressions, reach as f64)),
+        video_views,
+        video_view_rate: video_views
+            .and_then(|views| safe_div(views as f64, impressions))
+            .map(|rate| rate * 100.0),
+        // Not reported by the LinkedIn analytics finder
+        average_watch_time_seconds: None,
+    };
+
+    Campaign {
+        id: raw.id.to_string().into(),
+        platform: Platform::LinkedIn,
+        name: raw.name,
+        status: map_status(&raw.status),
+        objective: raw.objective_type.map(CampaignObjective::Other),
+        // LinkedIn formats (sponsored content, text ads) are not a campaign type
+        campaign_type: None,
+        daily_budget,
+        currency: currency.unwrap_or_else(|| "USD".to_string()),
+        metrics,
+        pacing: None,
+        targeting: None,
+        bidding: None,
+        quality: None,
+        auction: None,
+        tracking_template: None,
+        account_id: None,
+        labels: Vec::new(),
+        tags: HashMap::new(),
+    }
+}
+
+/// Platform status value for a unified status change; `None` for
+/// unmapped statuses, which cannot be set
+pub fn platform_status(status: &CampaignStatus) -> Option<&'static str> {
+    match status {
+        CampaignStatus::Enabled => Some("ACTIVE"),
+        CampaignStatus::Paused => Some("PAUSED"),
+        CampaignStatus::Removed => Some("ARCHIVED"),
+        CampaignStatus::Other(_) => None,
+    }
+}
+
+/// Connector for the gateway's LinkedIn routes
+pub struct LinkedInConnector {
+    pub http_client: reqwest::Client,
+    pub gateway_url: String,
+    pub pagination: PaginationConfig,
+}
+
+impl LinkedInConnector {
+    fn url(&self, path: &str) -> String {
+        format!("{}/v1/linkedin/{}", self.gateway_url, path)
+    }
+
+    /// Request to `path`, carrying the tenant's access token when the call
+    /// is made with one, see `credentials`
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        credentials::authorize(self.http_client.request(method, self.url(path)))
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for LinkedInConnector {
+    fn platform(&self) -> Platform {
+        Platform::LinkedIn
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        // Offsets of later pages assume every page is full, so the page
+        // size must not exceed what LinkedIn serves
+        let count = self.pagination.page_size.min(MAX_PAGE_SIZE);
+        let fetch_page = |page: u32| async move {
+            let response = self
+                .request(reqwest::Method::GET, "campaigns")
+                .query(&[("start", u64::from(page) * u64::from(count)), ("count", count.into())])
+                .send()
+                .await?;
+
+            let page = response.error_for_status()?.json::<LinkedInPage>().await?;
+            Ok::<_, ConnectorError>(Page::from(page))
+        };
+        let raw = pagination::collect_pages_concurrently(
+            &Platform::LinkedIn,
+            &self.pagination,
+            fetch_page,
+        )
+        .await?;
+
+        Ok(raw.into_iter().map(normalize).collect())
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("campaigns/{}", campaign_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        let raw = response.error_for_status()?.json::<LinkedInCampaign>().await?;
+        Ok(normalize(raw).metrics)
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        let status = platform_status(&status)
+            .ok_or(ConnectorError::NotSupported("setting an unmapped status"))?;
+        self.request(reqwest::Method::POST, &format!("campaigns/{}/status", campaign_id))
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        self.request(reqwest::Method::GET, "health")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("campaigns/{}", campaign_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(amount: &str) -> LinkedInMoney {
+        LinkedInMoney {
+            amount: amount.to_string(),
+            currency_code: "BRL".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_map_status() {
+        assert!(matches!(map_status("ACTIVE"), CampaignStatus::Enabled));
+        assert!(matches!(map_status("DRAFT"), CampaignStatus::Paused));
+        assert!(matches!(map_status("ARCHIVED"), CampaignStatus::Removed));
+        assert_eq!(map_status("ON_HOLD"), CampaignStatus::Other("ON_HOLD".to_string()));
+    }
+
+    #[test]
+    fn test_pages_follow_start_offsets() {
+        let page = |start: u64, total: Option<u64>, len: usize| -> Page<LinkedInCampaign> {
+            let elements: Vec<serde_json::Value> = (0..len)
+                .map(|i| serde_json::json!({ "id": i, "name": "c", "status": "ACTIVE" }))
+                .collect();
+            let raw: LinkedInPage = serde_json::from_value(serde_json::json!({
+                "elements": elements,
+                "paging": { "start": start, "count": 2, "total": total }
+            }))
+            .unwrap();
+            raw.into()
+        };
+
+        assert_eq!(page(0, Some(5), 2).next.as_deref(), Some("2"));
+        assert_eq!(page(4, Some(5), 1).next, None);
+        // Without a total, only a full page asks for another
+        assert_eq!(page(2, None, 2).next.as_deref(), Some("4"));
+        assert_eq!(page(4, None, 1).next, None);
+    }
+
+    #[test]
+    fn test_lifetime_budget_is_spread_over_schedule() {
+        let campaign = LinkedInCampaign {
+            id: 42,
+            name: "B2B Leads".to_string(),
+            status: "ACTIVE".to_string(),
+            objective_type: Some("LEAD_GENERATION".to_string()),
+            daily_budget: None,
+            total_budget: Some(money("3000.00")),
+            // 2025-11-01 to 2025-11-30
+            run_schedule: Some(LinkedInRunSchedule {
+                start: 1_761_955_200_000,
+                end: Some(1_764_460_800_000),
+            }),
+            analytics: LinkedInAnalytics::default(),
+        };
+
+        let normalized = normalize(campaign);
+        assert_eq!(normalized.daily_budget, 100.0);
+        assert_eq!(normalized.currency, "BRL");
+        assert_eq!(normalized.id, "42");
+    }
+
+    #[test]
+    fn test_non_finite_amounts_count_as_missing() {
+        assert_eq!(parse_amount(&money("NaN")), 0.0);
+        assert_eq!(parse_amount(&money("inf")), 0.0);
+        assert_eq!(parse_amount(&money("12.50")), 12.5);
+    }
+}
@@ -0,0 +1,331 @@
+//! # Load Shedding Example
+//!
+//! Under a spike of dashboard refreshes, pauses and alert rule changes
+//! waited behind pages of campaign lists. Every routed request now has a
+//! priority, and the service turns away the least important ones first:
+//!
+//! - `high`: writes (any method but `GET` and `HEAD`), alert rules and
+//!   history, cost guard trips, health checks. Never shed;
+//! - `normal`: reads of any other route. Shed while
+//!   `load_shedding.max_in_flight` requests are in flight;
+//! - `low`: bulk lists (`/campaigns`, `/campaigns/search`, `/search-terms`,
+//!   `/audit`, ...). Shed from `load_shedding.low_max_in_flight` requests
+//!   in flight, and while the smoothed response time of recent requests is
+//!   past `load_shedding.latency_threshold_ms`.
+//!
+//! `load_shedding.routes` sets the priority of reads by route template and
+//! replaces the built-in lists. A shed request is answered with `503
+//! overloaded` and a `Retry-After` of `load_shedding.retry_after_secs`
+//! before authorization or any handler work.
+//!
+//! Response time is measured up to the response head, so streams and
+//! websockets count only until they are established. A smoothed time
+//! with no request finished for `LATENCY_STALE` no longer sheds: with
+//! only low-priority traffic, the next request is let through to measure
+//! again.
+//!
+//! `GET /internal/load-shedding` (admins) reports the requests in flight,
+//! the smoothed response time and the requests shed since startup by
+//! route, priority and reason.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::campaign_aggregator::AppState;
+use super::config::LoadSheddingConfig;
+use super::error::ApiError;
+use super::versioning;
+
+/// Reads never shed by default
+pub const HIGH_PRIORITY_ROUTES: &[&str] = &[
+    "/alerts/rules",
+    "/alerts/history",
+    "/cost-guard/trips",
+    "/healthz",
+    "/readyz",
+    "/internal/load-shedding",
+];
+
+/// Bulk lists shed first by default
+pub const LOW_PRIORITY_ROUTES: &[&str] = &[
+    "/campaigns",
+    "/campaigns/breakdown",
+    "/campaigns/search",
+    "/campaigns/archived",
+    "/search-terms",
+    "/audit",
+    "/automation/log",
+];
+
+/// Age past which the smoothed response time no longer sheds
+pub const LATENCY_STALE: Duration = Duration::from_secs(5);
+
+/// Weight of the latest response time in the smoothed one
+const LATENCY_WEIGHT: f64 = 0.2;
+
+/// Priority class of a request
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+/// Why a request was shed
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ShedReason {
+    /// Too many requests in flight for the request's priority
+    Concurrency,
+    /// Smoothed response time past `latency_threshold_ms`
+    Latency,
+}
+
+/// Requests shed on one route for one reason
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ShedCount {
+    pub route: String,
+    pub priority: Priority,
+    pub reason: ShedReason,
+    pub count: u64,
+    pub last_at: DateTime<Utc>,
+}
+
+/// Response of `GET /internal/load-shedding`
+#[derive(Debug, Serialize)]
+pub struct LoadSheddingReport {
+    pub enabled: bool,
+    pub in_flight: usize,
+    pub max_in_flight: usize,
+    pub low_max_in_flight: usize,
+    /// `None` before the first request or once stale
+    pub latency_ms: Option<f64>,
+    pub latency_threshold_ms: u64,
+    /// Requests shed since startup
+    pub shed_total: u64,
+    /// Most shed first
+    pub shed: Vec<ShedCount>,
+    pub at: DateTime<Utc>,
+}
+
+/// Smoothed response time and when it was last updated
+#[derive(Debug, Clone, Copy)]
+struct Latency {
+    smoothed: Duration,
+    at: Instant,
+}
+
+/// Requests in flight, response times and shed counts of the service
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    in_flight: AtomicUsize,
+    latency: Mutex<Option<Latency>>,
+    shed: Mutex<HashMap<(String, ShedReason), ShedCount>>,
+}
+
+/// Slot of one admitted request, released on drop
+pub struct Admitted<'a> {
+    shedder: &'a LoadShedder,
+}
+
+impl Drop for Admitted<'_> {
+    fn drop(&mut self) {
+        self.shedder.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            config,
+            in_flight: AtomicUsize::new(0),
+            latency: Mutex::new(None),
+            shed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Priority of a `method` request to the route template `route`
+    pub fn priority_of(&self, method: &Method, route: &str) -> Priority {
+        if method != Method::GET && method != Method::HEAD {
+            return Priority::High;
+        }
+        self.config.read_priority(route)
+    }
+
+    /// Smoothed response time, `None` before the first request or once stale
+    pub fn latency(&self, now: Instant) -> Option<Duration> {
+        let latency = (*self.latency.lock().unwrap())?;
+        (now.duration_since(latency.at) < LATENCY_STALE).then_some(latency.smoothed)
+    }
+
+    /// Take a slot for a request of `priority`, or say why it is shed
+    pub fn admit(&self, priority: Priority, now: Instant) -> Result<Admitted<'_>, ShedReason> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed);
+        let admitted = Admitted { shedder: self };
+        let limit = match priority {
+            Priority::High => return Ok(admitted),
+            Priority::Normal => self.config.max_in_flight,
+            Priority::Low => self.config.low_max_in_flight,
+        };
+        if in_flight >= limit {
+            return Err(ShedReason::Concurrency);
+        }
+        if priority == Priority::Low {
+            let threshold = self.config.latency_threshold();
+            if threshold.is_some_and(|threshold| self.latency(now).is_some_and(|l| l > threshold)) {
+                return Err(ShedReason::Latency);
+            }
+        }
+        Ok(admitted)
+    }
+
+    /// Fold the response time of a finished request into the smoothed one
+    pub fn observe(&self, elapsed: Duration, now: Instant) {
+        let mut latency = self.latency.lock().unwrap();
+        let smoothed = match *latency {
+            Some(previous) => {
+                previous.smoothed.mul_f64(1.0 - LATENCY_WEIGHT) + elapsed.mul_f64(LATENCY_WEIGHT)
+            }
+            None => elapsed,
+        };
+        *latency = Some(Latency { smoothed, at: now });
+    }
+
+    fn record_shed(&self, route: &str, priority: Priority, reason: ShedReason) {
+        let now = Utc::now();
+        let mut shed = self.shed.lock().unwrap();
+        let count = shed.entry((route.to_string(), reason)).or_insert_with(|| ShedCount {
+            route: route.to_string(),
+            priority,
+            reason,
+            count: 0,
+            last_at: now,
+        });
+        count.count += 1;
+        count.last_at = now;
+    }
+
+    pub fn report(&self, now: DateTime<Utc>) -> LoadSheddingReport {
+        let mut shed: Vec<ShedCount> = self.shed.lock().unwrap().values().cloned().collect();
+        shed.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.route.cmp(&b.route)));
+
+        LoadSheddingReport {
+            enabled: self.config.enabled,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            max_in_flight: self.config.max_in_flight,
+            low_max_in_flight: self.config.low_max_in_flight,
+            latency_ms: self.latency(Instant::now()).map(|l| l.as_secs_f64() * 1000.0),
+            latency_threshold_ms: self.config.latency_threshold_ms,
+            shed_total: shed.iter().map(|s| s.count).sum(),
+            shed,
+            at: now,
+        }
+    }
+}
+
+/// Route middleware shedding low-priority requests under load; outermost
+/// route layer, so shed requests cost no authorization or cache lookup
+pub async fn shed_load(
+    State(shedder): State<Arc<LoadShedder>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = match req.extensions().get::<MatchedPath>() {
+        Some(route) if shedder.config.enabled => {
+            versioning::unversioned(route.as_str()).to_string()
+        }
+        _ => return next.run(req).await,
+    };
+    let priority = shedder.priority_of(req.method(), &route);
+
+    let _admitted = match shedder.admit(priority, Instant::now()) {
+        Ok(admitted) => admitted,
+        Err(reason) => {
+            shedder.record_shed(&route, priority, reason);
+            tracing::debug!(route = %route, ?priority, ?reason, "request shed");
+            return ApiError::Overloaded { retry_after: shedder.config.retry_after_secs }
+                .into_response();
+        }
+    };
+    let started = Instant::now();
+    let response = next.run(req).await;
+    shedder.observe(started.elapsed(), Instant::now());
+    response
+}
+
+/// GET /internal/load-shedding - Requests in flight and shed since startup
+pub async fn get_load_shedding(State(state): State<Arc<AppState>>) -> Json<LoadSheddingReport> {
+    Json(state.load_shedder.report(Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shedder() -> LoadShedder {
+        LoadShedder::new(LoadSheddingConfig {
+            max_in_flight: 3,
+            low_max_in_flight: 1,
+            latency_threshold_ms: 100,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_requests_are_shed_by_priority_as_concurrency_rises() {
+        let shedder = shedder();
+        let now = Instant::now();
+
+        let first = shedder.admit(Priority::Low, now).unwrap();
+        assert_eq!(shedder.admit(Priority::Low, now).err(), Some(ShedReason::Concurrency));
+        let _second = shedder.admit(Priority::Normal, now).unwrap();
+        let _third = shedder.admit(Priority::Normal, now).unwrap();
+        assert_eq!(shedder.admit(Priority::Normal, now).err(), Some(ShedReason::Concurrency));
+        let _write = shedder.admit(Priority::High, now).unwrap();
+        assert_eq!(shedder.in_flight.load(Ordering::Relaxed), 4);
+
+        drop(first);
+        assert_eq!(shedder.in_flight.load(Ordering::Relaxed), 3);
+        assert_eq!(shedder.priority_of(&Method::POST, "/campaigns"), Priority::High);
+        assert_eq!(shedder.priority_of(&Method::GET, "/campaigns"), Priority::Low);
+        assert_eq!(shedder.priority_of(&Method::GET, "/alerts/history"), Priority::High);
+        assert_eq!(shedder.priority_of(&Method::GET, "/campaigns/{id}/detail"), Priority::Normal);
+    }
+
+    #[test]
+    fn test_slow_responses_shed_low_priority_until_stale() {
+        let shedder = shedder();
+        let now = Instant::now();
+
+        shedder.observe(Duration::from_millis(500), now);
+        assert_eq!(shedder.admit(Priority::Low, now).err(), Some(ShedReason::Latency));
+        assert!(shedder.admit(Priority::Normal, now).is_ok());
+
+        // Fast responses bring the smoothed time back under the threshold
+        for _ in 0..10 {
+            shedder.observe(Duration::from_millis(10), now);
+        }
+        assert!(shedder.admit(Priority::Low, now).is_ok());
+
+        shedder.observe(Duration::from_secs(1), now);
+        assert!(shedder.admit(Priority::Low, now).is_err());
+        assert!(shedder.admit(Priority::Low, now + LATENCY_STALE).is_ok());
+
+        shedder.record_shed("/campaigns", Priority::Low, ShedReason::Latency);
+        shedder.record_shed("/campaigns", Priority::Low, ShedReason::Latency);
+        let report = shedder.report(Utc::now());
+        assert_eq!(report.shed_total, 2);
+        assert_eq!(report.shed[0].reason, ShedReason::Latency);
+    }
+}
@@ -0,0 +1,274 @@
+//! # Response Locale Example
+//!
+//! Brazilian agencies read `1.234,50` where the API writes `1,234.50`, and
+//! spreadsheets set to pt-BR split CSV fields on `;`. The locale of a
+//! request is negotiated from `Accept-Language` (`pt-BR` or `en-US`, the
+//! default) and affects what is meant for people, never the JSON numbers:
+//!
+//! - reports created by the request keep its locale, and their CSV and
+//!   PDF exports use its separators and labels;
+//! - `GET /campaigns/summary` labels its metrics in the locale;
+//! - when `Accept-Language` names a supported locale, responses carry
+//!   `Content-Language`, and JSON object bodies a `formatting` block with
+//!   the separators and currency symbol position, so clients format the
+//!   numbers they receive the same way.
+//!
+//! Like quota warnings, bodies carrying an ETag are not rewritten.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "pt-BR")]
+    PtBr,
+}
+
+impl Locale {
+    /// BCP 47 tag, as sent in `Content-Language`
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::PtBr => "pt-BR",
+        }
+    }
+
+    /// Most preferred supported locale of an `Accept-Language` value
+    ///
+    /// Any regional variant matches by language (`pt-PT` gets `pt-BR`);
+    /// `*` gets the default. `None` when nothing listed is supported.
+    pub fn negotiate(accept_language: &str) -> Option<Locale> {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let tag = parts.next().filter(|t| !t.is_empty())?;
+                let q = parts
+                    .find_map(|p| p.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse().ok())?;
+                Some((tag, q))
+            })
+            .filter(|(_, q)| *q > 0.0)
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges.into_iter().find_map(|(tag, _)| {
+            let language = tag.split('-').next().unwrap_or(tag);
+            match language.to_ascii_lowercase().as_str() {
+                "*" | "en" => Some(Locale::EnUs),
+                "pt" => Some(Locale::PtBr),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn formatting(self) -> Formatting {
+        match self {
+            Locale::EnUs => Formatting {
+                locale: self,
+                decimal_separator: '.',
+                thousands_separator: ',',
+                currency_symbol_position: SymbolPosition::After,
+                csv_delimiter: ',',
+            },
+            Locale::PtBr => Formatting {
+                locale: self,
+                decimal_separator: ',',
+                thousands_separator: '.',
+                currency_symbol_position: SymbolPosition::Before,
+                csv_delimiter: ';',
+            },
+        }
+    }
+
+    /// Display label of a metric of the `units` schema; unknown names as given
+    pub fn metric_label(self, name: &str) -> String {
+        let label = match (self, name) {
+            (Locale::EnUs, "impressions") => "Impressions",
+            (Locale::EnUs, "clicks") => "Clicks",
+            (Locale::EnUs, "conversions") => "Conversions",
+            (Locale::EnUs, "cost") => "Cost",
+            (Locale::EnUs, "conversion_value") => "Conversion value",
+            (Locale::PtBr, "impressions") => "Impressões",
+            (Locale::PtBr, "clicks") => "Cliques",
+            (Locale::PtBr, "conversions") => "Conversões",
+            (Locale::PtBr, "cost") => "Custo",
+            (Locale::PtBr, "conversion_value") => "Valor de conversão",
+            (_, "ctr") => "CTR",
+            (_, "cpa") => "CPA",
+            (_, "roas") => "ROAS",
+            _ => name,
+        };
+        label.to_string()
+    }
+
+    /// Currency as shown next to an amount: the ISO code in en-US, the
+    /// symbol in pt-BR where one is common
+    pub fn currency_symbol(self, code: &str) -> &str {
+        match (self, code) {
+            (Locale::PtBr, "BRL") => "R$",
+            (Locale::PtBr, "USD") => "US$",
+            (Locale::PtBr, "EUR") => "€",
+            _ => code,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolPosition {
+    Before,
+    After,
+}
+
+/// Number conventions of a locale, sent as the `formatting` block
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct Formatting {
+    pub locale: Locale,
+    pub decimal_separator: char,
+    pub thousands_separator: char,
+    pub currency_symbol_position: SymbolPosition,
+    /// Field separator of CSV exports
+    pub csv_delimiter: char,
+}
+
+impl Formatting {
+    /// `value` with `places` decimals and grouped thousands, e.g. `1.234,50`
+    pub fn number(&self, value: f64, places: usize) -> String {
+        let fixed = format!("{:.places$}", value.abs());
+        let (whole, fraction) = match fixed.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (fixed.as_str(), None),
+        };
+
+        let mut formatted = String::new();
+        if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            formatted.push('-');
+        }
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                formatted.push(self.thousands_separator);
+            }
+            formatted.push(c);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal_separator);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// `value` as short as it round-trips, without grouping, for CSV fields
+    pub fn plain(&self, value: f64) -> String {
+        value.to_string().replace('.', &self.decimal_separator.to_string())
+    }
+
+    /// Amount with two decimals and its currency, e.g. `R$ 1.234,50`
+    pub fn money(&self, value: f64, currency: &str) -> String {
+        let amount = self.number(value, 2);
+        let symbol = self.locale.currency_symbol(currency);
+        match self.currency_symbol_position {
+            _ if symbol.is_empty() => amount,
+            SymbolPosition::Before => format!("{} {}", symbol, amount),
+            SymbolPosition::After => format!("{} {}", amount, symbol),
+        }
+    }
+}
+
+/// Locale of the current request; the default outside a request scope
+pub fn current() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+/// Add `formatting` to a JSON object body; other bodies are left as is
+fn with_formatting(body: &[u8], formatting: Formatting) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    value
+        .as_object_mut()?
+        .insert("formatting".to_string(), serde_json::to_value(formatting).ok()?);
+    serde_json::to_vec(&value).ok()
+}
+
+/// Middleware scoping the request's locale, see the module docs
+pub async fn negotiate_locale(req: Request, next: Next) -> Response {
+    let requested = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Locale::negotiate);
+    let response = LOCALE.scope(requested.unwrap_or_default(), next.run(req)).await;
+    let Some(locale) = requested else {
+        return response;
+    };
+
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(locale.tag()));
+    parts.headers.append(header::VARY, HeaderValue::from_static("accept-language"));
+
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes() == b"application/json");
+    if !is_json || parts.status != StatusCode::OK || parts.headers.contains_key(header::ETAG) {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not buffer response for formatting");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    match with_formatting(&bytes, locale.formatting()) {
+        Some(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::units::{format_value_in, metric};
+
+    #[test]
+    fn test_negotiates_by_quality_and_language() {
+        assert_eq!(Locale::negotiate("pt-BR,pt;q=0.9,en;q=0.8"), Some(Locale::PtBr));
+        assert_eq!(Locale::negotiate("fr-FR, en-GB;q=0.5, pt;q=0.7"), Some(Locale::PtBr));
+        assert_eq!(Locale::negotiate("pt;q=0, en"), Some(Locale::EnUs));
+        assert_eq!(Locale::negotiate("*"), Some(Locale::EnUs));
+        assert_eq!(Locale::negotiate("fr, de;q=0.5"), None);
+    }
+
+    #[test]
+    fn test_pt_br_formats_numbers_and_money() {
+        let pt = Locale::PtBr.formatting();
+        let cost = metric("cost").unwrap();
+        let ctr = metric("ctr").unwrap();
+
+        assert_eq!(format_value_in(cost, 1234.5, "BRL", &pt), "R$ 1.234,50");
+        assert_eq!(format_value_in(cost, -12.0, "EUR", &pt), "-€ 12,00");
+        assert_eq!(format_value_in(ctr, 3.2, "", &pt), "3,20%");
+        assert_eq!(pt.plain(1234.5), "1234,5");
+
+        let en = Locale::EnUs.formatting();
+        assert_eq!(format_value_in(cost, 1234567.0, "BRL", &en), "1,234,567.00 BRL");
+        assert_eq!(Locale::PtBr.metric_label("conversions"), "Conversões");
+    }
+}
@@ -0,0 +1,295 @@
+//! # Microsoft Advertising Connector Example
+//!
+//! Fetches Microsoft Advertising (Bing Ads) campaigns through the gateway
+//! (which owns the Microsoft identity platform OAuth flow and the
+//! `DeveloperToken` / `CustomerAccountId` headers) and normalizes them into
+//! the unified `Campaign` model.
+//!
+//! Campaign lists are paged by `page_index` without a total count: a full
+//! page means another may follow, up to `gateway.pagination.max_pages`.
+//! Pages are requested several at a time, see `pagination`.
+//!
+//! Compiled only with the `microsoft` cargo feature.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::campaign_aggregator::{
+    roas, Campaign, CampaignMetrics, CampaignStatus, CampaignType, Platform,
+};
+use super::config::PaginationConfig;
+use super::connectors::{ConnectorError, PlatformConnector};
+use super::credentials;
+use super::finite::safe_div;
+use super::ids::CampaignId;
+use super::pagination::{self, Page};
+
+/// Campaign performance attached by the gateway
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct MicrosoftPerformance {
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: f64,
+    pub spend: f64,
+    /// Conversion value reported as `Revenue`
+    #[serde(default)]
+    pub revenue: f64,
+}
+
+/// Campaign as returned by the gateway's Microsoft route
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct MicrosoftCampaign {
+    pub id: i64,
+    pub name: String,
+    pub status: String,
+    /// e.g. `Search` or `Shopping`, mapped by the normalization rules
+    #[serde(default)]
+    pub campaign_type: Option<String>,
+    /// `DailyBudgetStandard` or `DailyBudgetAccelerated`
+    pub budget_type: Option<String>,
+    pub daily_budget: Option<f64>,
+    /// Shared budgets override the campaign's own `DailyBudget`
+    pub shared_budget_amount: Option<f64>,
+    pub currency_code: Option<String>,
+    #[serde(default)]
+    pub performance: MicrosoftPerformance,
+}
+
+/// Map a Microsoft Advertising campaign status into `CampaignStatus`
+///
+/// Budget-paused and suspended campaigns are not delivering, so they count
+/// as paused. Other values are left to the normalization rules.
+pub fn map_status(status: &str) -> CampaignStatus {
+    match status {
+        "Active" => CampaignStatus::Enabled,
+        "Paused" | "BudgetPaused" | "BudgetAndManualPaused" | "Suspended" => {
+            CampaignStatus::Paused
+        }
+        "Deleted" => CampaignStatus::Removed,
+        other => CampaignStatus::Other(other.to_string()),
+    }
+}
+
+/// Daily budget, preferring the shared budget amount when present
+pub fn normalize_daily_budget(campaign: &MicrosoftCampaign) -> f64 {
+    campaign
+        .shared_budget_amount
+        .or(campaign.daily_budget)
+        .unwrap_or(0.0)
+}
+
+/// Normalize a Microsoft Advertising campaign into the unified model
+pub fn normalize(raw: MicrosoftCampaign) -> Campaign {
+    let perf = &raw.performance;
+    // Microsoft reports fractional conversions for modelled attribution
+    let conversions = perf.conversions.round() as u32;
+
+    let metrics = CampaignMetrics {
+        impressions: perf.impressions,
+        clicks: perf.clicks,
+        conversions,
+        cost: perf.spend,
+        ctr: if perf.impressions > 0 {
+            perf.clicks as f64 / perf.impressions as f64 * 100.0
+        } else {
+            0.0
+        },
+        // Fractions of a conversion would blow the CPA up past any amount
+        cpa: safe_div(perf.spend, perf.conversions).filter(|_| conversions > 0).unwrap_or(0.0),
+        conversion_value: perf.revenue,
+        roas: roas(perf.revenue, perf.spend),
+        conversion_actions: Vec::new(),
+        reach: None,
+        frequency: None,
+        video_views: None,
+        video_view_rate: None,
+        average_watch_time_seconds: None,
+    };
+
+    Campaign {
+        id: raw.id.to_string().into(),
+        platform: Platform::Microsoft,
+        daily_budget: normalize_daily_budget(&raw),
+        name: raw.name,
+        status: map_status(&raw.status),
+        objective: None,
+        campaign_type: raw.campaign_type.map(CampaignType::Other),
+        currency: raw.currency_code.unwrap_or_else(|| "USD".to_string()),
+        metrics,
+        pacing: None,
+        targeting: None,
+        bidding: None,
+        quality: None,
+        auction: None,
+        tracking_template: None,
+        account_id: None,
+        labels: Vec::new(),
+        tags: HashMap::new(),
+    }
+}
+
+/// Page `index` of a list paged by index; full pages point to the next one
+pub fn index_page<T>(items: Vec<T>, index: u32, page_size: u32) -> Page<T> {
+    let full = items.len() == page_size as usize;
+    Page {
+        next: full.then(|| (index + 1).to_string()),
+        items,
+    }
+}
+
+/// Platform status value for a unified status change; `None` for
+/// unmapped statuses, which cannot be set
+pub fn platform_status(status: &CampaignStatus) -> Option<&'static str> {
+    match status {
+        CampaignStatus::Enabled => Some("Active"),
+        CampaignStatus::Paused => Some("Paused"),
+        CampaignStatus::Removed => Some("Deleted"),
+        CampaignStatus::Other(_) => None,
+    }
+}
+
+/// Connector for the gateway's Microsoft Advertising routes
+pub struct MicrosoftConnector {
+    pub http_client: reqwest::Client,
+    pub gateway_url: String,
+    pub pagination: PaginationConfig,
+}
+
+impl MicrosoftConnector {
+    fn url(&self, path: &str) -> String {
+        format!("{}/v1/microsoft/{}", self.gateway_url, path)
+    }
+
+    /// Request to `path`, carrying the tenant's access token when the call
+    /// is made with one, see `credentials`
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        credentials::authorize(self.http_client.request(method, self.url(path)))
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for MicrosoftConnector {
+    fn platform(&self) -> Platform {
+        Platform::Microsoft
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        let page_size = self.pagination.page_size;
+        let fetch_page = |index: u32| async move {
+            let response = self
+                .request(reqwest::Method::GET, "campaigns")
+                .query(&[("page_index", index), ("page_size", page_size)])
+                .send()
+                .await?;
+
+            let items = response.error_for_status()?.json::<Vec<MicrosoftCampaign>>().await?;
+            Ok::<_, ConnectorError>(index_page(items, index, page_size))
+        };
+        let raw = pagination::collect_pages_concurrently(
+            &Platform::Microsoft,
+            &self.pagination,
+            fetch_page,
+        )
+        .await?;
+
+        Ok(raw.into_iter().map(normalize).collect())
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("campaigns/{}", campaign_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        let raw = response.error_for_status()?.json::<MicrosoftCampaign>().await?;
+        Ok(normalize(raw).metrics)
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        let status = platform_status(&status)
+            .ok_or(ConnectorError::NotSupported("setting an unmapped status"))?;
+        self.request(reqwest::Method::POST, &format!("campaigns/{}/status", campaign_id))
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        self.request(reqwest::Method::GET, "health")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("campaigns/{}", campaign_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ConnectorError::NotFound(campaign_id.to_string()));
+        }
+
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_status() {
+        assert!(matches!(map_status("Active"), CampaignStatus::Enabled));
+        assert!(matches!(map_status("BudgetPaused"), CampaignStatus::Paused));
+        assert!(matches!(map_status("Deleted"), CampaignStatus::Removed));
+        assert_eq!(map_status("InReview"), CampaignStatus::Other("InReview".to_string()));
+    }
+
+    #[test]
+    fn test_full_pages_point_to_next_index() {
+        assert_eq!(index_page(vec![1, 2], 0, 2).next.as_deref(), Some("1"));
+        assert_eq!(index_page(vec![3], 1, 2).next, None);
+        assert_eq!(index_page(Vec::<u8>::new(), 2, 2).next, None);
+    }
+
+    #[test]
+    fn test_normalize_prefers_shared_budget() {
+        let raw: MicrosoftCampaign = serde_json::from_value(serde_json::json!({
+            "Id": 7,
+            "Name": "Bing Brand",
+            "Status": "Active",
+            "BudgetType": "DailyBudgetStandard",
+            "DailyBudget": 20.0,
+            "SharedBudgetAmount": 35.0,
+            "CurrencyCode": "BRL",
+            "Performance": { "Impressions": 200, "Clicks": 10, "Conversions": 2.0, "Spend": 30.0 }
+        }))
+        .unwrap();
+
+        let campaign = normalize(raw);
+        assert_eq!(campaign.daily_budget, 35.0);
+        assert_eq!(campaign.metrics.ctr, 5.0);
+        assert_eq!(campaign.metrics.cpa, 15.0);
+    }
+}
@@ -0,0 +1,131 @@
+//! # Rust Core API Routes
+//!
+//! Module tree for the examples in this directory. Copy the directory to
+//! `src/routes/` and declare `mod routes;` in the crate root.
+
+pub mod accounts;
+pub mod ads;
+pub mod alerts;
+pub mod annotations;
+pub mod anomalies;
+pub mod api_usage;
+pub mod apply;
+pub mod archive;
+pub mod assets;
+pub mod attribution;
+pub mod auction;
+pub mod audiences;
+pub mod audit;
+pub mod automation;
+pub mod backfill;
+pub mod backup;
+pub mod benchmarks;
+pub mod bidding;
+pub mod breakdowns;
+pub mod budget_advice;
+pub mod bulk;
+pub mod cache_policy;
+pub mod call_budget;
+pub mod campaign_aggregator;
+pub mod campaign_groups;
+pub mod changelog;
+pub mod coalesce;
+pub mod columnar;
+pub mod compression;
+pub mod concurrency;
+pub mod config;
+pub mod connectors;
+pub mod conversions;
+pub mod cost_guard;
+pub mod creative_fatigue;
+pub mod credentials;
+pub mod delta_sync;
+pub mod demo;
+pub mod dictionary;
+pub mod digest;
+pub mod entity_cache;
+pub mod error;
+pub mod etag;
+pub mod experiments;
+pub mod fields;
+pub mod filter;
+pub mod finite;
+pub mod freeze;
+pub mod frequency;
+pub mod freshness;
+pub mod graph;
+pub mod health;
+pub mod history;
+pub mod hydration;
+pub mod idempotency;
+pub mod ids;
+pub mod insertion_orders;
+pub mod keywords;
+pub mod labels;
+pub mod latency_budget;
+pub mod launch;
+pub mod lenient;
+pub mod load_shedding;
+pub mod locale;
+pub mod money;
+pub mod naming;
+pub mod ndjson;
+pub mod negatives;
+pub mod normalization;
+pub mod pacing;
+pub mod pagination;
+pub mod platform_errors;
+pub mod products;
+pub mod profiling;
+pub mod quality;
+pub mod quota;
+pub mod raw;
+pub mod rbac;
+pub mod reauth;
+pub mod recommendations;
+pub mod refresh;
+pub mod region;
+pub mod reload;
+pub mod schedules;
+pub mod search;
+pub mod segments;
+pub mod server;
+pub mod sort;
+pub mod spec;
+pub mod spend_caps;
+pub mod store;
+pub mod summary_cache;
+pub mod sync;
+pub mod targeting;
+pub mod timezones;
+pub mod timing;
+pub mod units;
+pub mod updates;
+pub mod utm;
+pub mod versioning;
+pub mod warmup;
+pub mod ws;
+
+// Optional subsystems
+#[cfg(feature = "reports")]
+pub mod reports;
+#[cfg(feature = "redis")]
+pub mod shared;
+#[cfg(feature = "warehouse")]
+pub mod warehouse;
+
+// Optional platform connectors
+#[cfg(feature = "linkedin")]
+pub mod linkedin;
+#[cfg(feature = "microsoft")]
+pub mod microsoft;
+
+// Fake gateway for tests, reusable downstream behind the `testing` feature
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+// Golden-file and property tests of platform normalization
+#[cfg(test)]
+mod arbitrary;
+#[cfg(test)]
+mod golden;
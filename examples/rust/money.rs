@@ -0,0 +1,90 @@
+//! # Currency-Aware Totals Example
+//!
+//! Campaigns report budgets and spend in the currency of their ad account,
+//! and a BRL budget plus a USD budget is not a meaningful number. Totals
+//! across campaigns are therefore kept per currency:
+//!
+//! - `Currency`: ISO 4217 code, normalized to upper case;
+//! - `Money`: an amount in one currency;
+//! - `MoneyByCurrency`: totals keyed by currency, serialized as
+//!   `{"BRL": {"amount": 350.0, "currency": "BRL"}}`.
+//!
+//! The service holds no exchange rates, so amounts are never converted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// ISO 4217 currency code, e.g. `BRL`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct Currency(String);
+
+impl Currency {
+    pub fn new(code: &str) -> Self {
+        Self(code.trim().to_ascii_uppercase())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Amount in one currency
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Money {
+    pub amount: f64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn zero(currency: Currency) -> Self {
+        Self {
+            amount: 0.0,
+            currency,
+        }
+    }
+}
+
+/// Totals keyed by currency
+pub type MoneyByCurrency = BTreeMap<Currency, Money>;
+
+/// Sum `(currency, amount)` pairs per currency
+pub fn sum_by_currency(amounts: impl IntoIterator<Item = (Currency, f64)>) -> MoneyByCurrency {
+    let mut totals = MoneyByCurrency::new();
+    for (currency, amount) in amounts {
+        totals
+            .entry(currency.clone())
+            .or_insert_with(|| Money::zero(currency))
+            .amount += amount;
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amounts_are_summed_per_normalized_currency() {
+        let totals = sum_by_currency([
+            (Currency::new("BRL"), 100.0),
+            (Currency::new("usd"), 20.0),
+            (Currency::new(" brl "), 50.0),
+        ]);
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[&Currency::new("BRL")].amount, 150.0);
+        assert_eq!(totals[&Currency::new("USD")].amount, 20.0);
+        assert_eq!(
+            serde_json::to_value(&totals).unwrap()["USD"],
+            serde_json::json!({ "amount": 20.0, "currency": "USD" })
+        );
+    }
+}
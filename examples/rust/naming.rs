@@ -0,0 +1,335 @@
+//! # Naming Convention Example
+//!
+//! Agencies encode market, product and objective in campaign names, e.g.
+//! `BR_Imoveis_Leads`. Naming conventions parse those names into tags:
+//! every campaign returned by the aggregator carries the fields extracted
+//! from its name in `tags` (`{"market": "BR", "product": "Imoveis", ...}`).
+//!
+//! A convention is either a template (`{market}_{product}_{objective}`)
+//! or a regex with named capture groups, and may restrict the values a
+//! field accepts. Conventions are tried in order; the first one matching
+//! a name tags the campaign. `GET /campaigns/naming-violations` lists the
+//! campaigns no convention matches, or whose fields hold values outside
+//! the allowed lists.
+
+use axum::{extract::State, response::Json};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, Platform};
+use super::error::ApiError;
+use super::ids::CampaignId;
+
+/// How a convention recognizes names
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NamingPattern {
+    /// Literal text with `{field}` placeholders; each placeholder matches
+    /// up to the next literal part
+    Template { template: String },
+    /// Regex whose named capture groups become tags
+    Regex { pattern: String },
+}
+
+/// One accepted naming convention
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NamingConvention {
+    pub name: String,
+    #[serde(flatten)]
+    pub pattern: NamingPattern,
+    /// Accepted values per field, compared case-insensitively; fields not
+    /// listed accept anything
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub allowed_values: BTreeMap<String, Vec<String>>,
+}
+
+/// Configured naming conventions, tried in order
+#[derive(Default)]
+pub struct NamingConventions {
+    conventions: RwLock<Vec<NamingConvention>>,
+}
+
+impl NamingConventions {
+    pub fn get(&self) -> Vec<NamingConvention> {
+        self.conventions.read().unwrap().clone()
+    }
+
+    pub fn set(&self, conventions: Vec<NamingConvention>) {
+        *self.conventions.write().unwrap() = conventions;
+    }
+}
+
+/// Why a campaign name breaks the convention
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ViolationReason {
+    /// No convention matches the name
+    NoMatch,
+    DisallowedValue {
+        convention: String,
+        field: String,
+        value: String,
+        allowed: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NamingViolation {
+    pub id: CampaignId,
+    pub platform: Platform,
+    pub name: String,
+    pub reasons: Vec<ViolationReason>,
+}
+
+/// Anchored regex equivalent to a template
+fn template_regex(template: &str) -> Result<String, String> {
+    let mut regex = String::from("^");
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| format!("unclosed placeholder in {:?}", template))?;
+        let field = &rest[open + 1..close];
+        if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid field name {:?} in {:?}", field, template));
+        }
+
+        regex.push_str(&regex::escape(&rest[..open]));
+        regex.push_str(&format!("(?P<{}>.+?)", field));
+        rest = &rest[close + 1..];
+    }
+
+    regex.push_str(&regex::escape(rest));
+    regex.push('$');
+    Ok(regex)
+}
+
+struct CompiledConvention<'a> {
+    convention: &'a NamingConvention,
+    regex: Regex,
+}
+
+fn compile(conventions: &[NamingConvention]) -> Result<Vec<CompiledConvention<'_>>, ApiError> {
+    conventions
+        .iter()
+        .map(|convention| {
+            let pattern = match &convention.pattern {
+                NamingPattern::Template { template } => {
+                    template_regex(template).map_err(ApiError::Validation)?
+                }
+                NamingPattern::Regex { pattern } => pattern.clone(),
+            };
+            let regex = Regex::new(&pattern).map_err(|e| {
+                ApiError::Validation(format!("convention {:?}: {}", convention.name, e))
+            })?;
+            Ok(CompiledConvention { convention, regex })
+        })
+        .collect()
+}
+
+/// First convention matching `name`, with the fields it extracts
+fn parse_name<'a>(
+    compiled: &'a [CompiledConvention],
+    name: &str,
+) -> Option<(&'a NamingConvention, HashMap<String, String>)> {
+    compiled.iter().find_map(|c| {
+        let captures = c.regex.captures(name.trim())?;
+        let tags = c
+            .regex
+            .capture_names()
+            .flatten()
+            .filter_map(|field| {
+                let value = captures.name(field)?.as_str().trim();
+                Some((field.to_string(), value.to_string()))
+            })
+            .collect();
+        Some((c.convention, tags))
+    })
+}
+
+/// Fields whose value is outside the convention's allowed list
+fn disallowed(
+    convention: &NamingConvention,
+    tags: &HashMap<String, String>,
+) -> Vec<ViolationReason> {
+    convention
+        .allowed_values
+        .iter()
+        .filter_map(|(field, allowed)| {
+            let value = tags.get(field)?;
+            let ok = allowed.iter().any(|a| a.eq_ignore_ascii_case(value));
+            (!ok).then(|| ViolationReason::DisallowedValue {
+                convention: convention.name.clone(),
+                field: field.clone(),
+                value: value.clone(),
+                allowed: allowed.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Fill `tags` of every campaign from its name
+///
+/// Conventions were validated when saved; should one fail to compile
+/// anyway, campaigns are left untagged.
+pub fn tag_campaigns(conventions: &[NamingConvention], campaigns: &mut [Campaign]) {
+    let Ok(compiled) = compile(conventions) else {
+        return;
+    };
+
+    for campaign in campaigns {
+        if let Some((_, tags)) = parse_name(&compiled, &campaign.name) {
+            campaign.tags = tags;
+        }
+    }
+}
+
+/// Campaigns whose names break the naming conventions
+pub fn find_violations(
+    conventions: &[NamingConvention],
+    campaigns: &[Campaign],
+) -> Result<Vec<NamingViolation>, ApiError> {
+    let compiled = compile(conventions)?;
+
+    Ok(campaigns
+        .iter()
+        .filter_map(|campaign| {
+            let reasons = match parse_name(&compiled, &campaign.name) {
+                Some((convention, tags)) => disallowed(convention, &tags),
+                None => vec![ViolationReason::NoMatch],
+            };
+            (!reasons.is_empty()).then(|| NamingViolation {
+                id: campaign.id.clone(),
+                platform: campaign.platform.clone(),
+                name: campaign.name.clone(),
+                reasons,
+            })
+        })
+        .collect())
+}
+
+/// GET /campaigns/naming-violations - Campaigns breaking the naming convention
+///
+/// Empty while no convention is configured.
+pub async fn get_naming_violations(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<NamingViolation>>, ApiError> {
+    let conventions = state.naming.get();
+    if conventions.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let campaigns = aggregate_campaigns(&state).await;
+    find_violations(&conventions, &campaigns).map(Json)
+}
+
+/// GET /naming/conventions - Current naming conventions
+pub async fn get_naming_conventions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<NamingConvention>>, ApiError> {
+    Ok(Json(state.naming.get()))
+}
+
+/// PUT /naming/conventions - Replace the naming conventions
+///
+/// Rejects the whole list if any template or regex is invalid.
+pub async fn put_naming_conventions(
+    State(state): State<Arc<AppState>>,
+    Json(conventions): Json<Vec<NamingConvention>>,
+) -> Result<Json<Vec<NamingConvention>>, ApiError> {
+    compile(&conventions)?;
+    state.naming.set(conventions.clone());
+    Ok(Json(conventions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conventions() -> Vec<NamingConvention> {
+        vec![
+            NamingConvention {
+                name: "agency".to_string(),
+                pattern: NamingPattern::Template {
+                    template: "{market}_{product}_{objective}".to_string(),
+                },
+                allowed_values: BTreeMap::from([(
+                    "objective".to_string(),
+                    vec!["leads".to_string(), "traffic".to_string()],
+                )]),
+            },
+            NamingConvention {
+                name: "legacy".to_string(),
+                pattern: NamingPattern::Regex {
+                    pattern: r"^\[(?P<platform>G|M)\]\s*(?P<product>.+)$".to_string(),
+                },
+                allowed_values: BTreeMap::new(),
+            },
+        ]
+    }
+
+    fn campaign(id: &str, name: &str) -> Campaign {
+        let mut campaign = Campaign::sample(id, Platform::Google);
+        campaign.name = name.to_string();
+        campaign
+    }
+
+    #[test]
+    fn test_tags_extracted_by_first_matching_convention() {
+        let mut campaigns = vec![
+            campaign("1", "BR_Imoveis Alto Padrao_Leads"),
+            campaign("2", "[G] Black Friday"),
+            campaign("3", "Brand Search"),
+        ];
+
+        tag_campaigns(&conventions(), &mut campaigns);
+
+        assert_eq!(campaigns[0].tags["market"], "BR");
+        assert_eq!(campaigns[0].tags["product"], "Imoveis Alto Padrao");
+        assert_eq!(campaigns[0].tags["objective"], "Leads");
+        assert_eq!(campaigns[1].tags["product"], "Black Friday");
+        assert!(campaigns[2].tags.is_empty());
+    }
+
+    #[test]
+    fn test_violations_list_unmatched_names_and_disallowed_values() {
+        let campaigns = vec![
+            campaign("1", "BR_Imoveis_Leads"),
+            campaign("2", "BR_Imoveis_Awareness"),
+            campaign("3", "Brand Search"),
+        ];
+
+        let violations = find_violations(&conventions(), &campaigns).unwrap();
+
+        assert_eq!(violations.len(), 2);
+        assert_eq!(
+            violations[0].reasons,
+            vec![ViolationReason::DisallowedValue {
+                convention: "agency".to_string(),
+                field: "objective".to_string(),
+                value: "Awareness".to_string(),
+                allowed: vec!["leads".to_string(), "traffic".to_string()],
+            }]
+        );
+        assert_eq!(violations[1].reasons, vec![ViolationReason::NoMatch]);
+    }
+
+    #[test]
+    fn test_invalid_templates_rejected() {
+        let invalid = |template: &str| NamingConvention {
+            name: "bad".to_string(),
+            pattern: NamingPattern::Template {
+                template: template.to_string(),
+            },
+            allowed_values: BTreeMap::new(),
+        };
+
+        assert!(compile(&[invalid("{market_{product}")]).is_err());
+        assert!(compile(&[invalid("{market")]).is_err());
+        assert!(compile(&[invalid("{}_x")]).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! # NDJSON Streaming Example
+//!
+//! `GET /campaigns` with `Accept: application/x-ndjson` answers with one
+//! campaign per line, written as soon as its platform's list arrives,
+//! instead of merging every platform's list and serializing it whole. For
+//! accounts with tens of thousands of campaigns the merged list and its
+//! JSON are never held in memory at once:
+//!
+//! - platforms are fetched concurrently and the lines of the platforms
+//!   that answered are interleaved, so a large platform does not hold back
+//!   the others;
+//! - lines come in arrival order, so `sort` does not apply; `bid_strategy`,
+//!   `include=targeting`, `include=quality` and `include=auction` need
+//!   every campaign before the first line and are rejected with 400, as
+//!   is `account_id`, which needs the account hierarchy;
+//! - `fields`, `label`, `objective`, `campaign_type`, `include_removed`,
+//!   `include=pacing` and naming tags apply to each line;
+//! - a failing platform is logged and skipped, as in the JSON response.
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+
+use super::call_budget;
+use super::campaign_aggregator::{
+    matches_kind, AppState, Campaign, CampaignObjective, CampaignQuery, CampaignStatus,
+    CampaignType, Platform,
+};
+use super::connectors::PlatformConnector;
+use super::error::ApiError;
+use super::fields::{FieldSelection, Sparse};
+use super::filter::Filter;
+use super::timezones;
+use super::{labels, naming, pacing};
+
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Whether `Accept` lists NDJSON
+pub fn accepts_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|media| media.split(';').next())
+        .any(|media| media.trim().eq_ignore_ascii_case(NDJSON_CONTENT_TYPE))
+}
+
+/// Campaigns of every connector, each platform's as soon as it answers,
+/// interleaved with the platforms already answering
+pub fn campaign_stream(
+    connectors: Vec<Arc<dyn PlatformConnector>>,
+) -> impl Stream<Item = Campaign> + Send {
+    let platforms = connectors.into_iter().map(|connector| {
+        stream::once(async move {
+            match connector.fetch_campaigns().await {
+                Ok(campaigns) => campaigns,
+                Err(e) => {
+                    tracing::warn!(
+                        platform = connector.platform().as_str(),
+                        error = %e,
+                        "platform fetch failed"
+                    );
+                    Vec::new()
+                }
+            }
+        })
+        .flat_map(stream::iter)
+        .boxed()
+    });
+
+    stream::select_all(platforms)
+}
+
+/// NDJSON response for `GET /campaigns`
+pub fn stream_campaigns(
+    state: Arc<AppState>,
+    query: &CampaignQuery,
+    selection: Option<FieldSelection>,
+) -> Result<Response, ApiError> {
+    let batched = ["targeting", "quality", "auction"];
+    if query.bid_strategy.is_some() || batched.iter().any(|block| query.includes(block)) {
+        return Err(ApiError::Validation(format!(
+            "bid_strategy and include=targeting, quality or auction are not available with {}",
+            NDJSON_CONTENT_TYPE
+        )));
+    }
+    // The account hierarchy is fetched through the connectors, not streamed
+    if query.account_id.is_some() {
+        return Err(ApiError::Validation(format!(
+            "account_id is not available with {}",
+            NDJSON_CONTENT_TYPE
+        )));
+    }
+    // Totals over a range need the whole list; a stream has no pages
+    if query.date_range()?.is_some() || query.limit.is_some() || query.cursor.is_some() {
+        return Err(ApiError::Validation(format!(
+            "start_date, end_date, limit and cursor are not available with {}",
+            NDJSON_CONTENT_TYPE
+        )));
+    }
+
+    // One gateway call per connector, charged before the response starts
+    call_budget::charge(state.connectors.all().len())?;
+
+    let conventions = state.naming.get();
+    let normalization = state.normalization.clone();
+    let tables = normalization.get();
+    let with_pacing = query.includes("pacing");
+    let today = timezones::today();
+    let label_keys = query.label.as_deref().map(labels::parse_label_list);
+    let objectives = query.objective.as_deref().map(CampaignObjective::parse_list).transpose()?;
+    let types = query.campaign_type.as_deref().map(CampaignType::parse_list).transpose()?;
+    let platforms = query.platform.as_deref().map(Platform::parse_list).transpose()?;
+    let statuses = query.status.as_deref().map(CampaignStatus::parse_list).transpose()?;
+    let filter = query.filter.as_deref().map(Filter::parse).transpose()?;
+    let include_removed = query.include_removed
+        || statuses.iter().flatten().any(|s| *s == CampaignStatus::Removed);
+
+    let campaigns = campaign_stream(state.connectors.all().to_vec()).map(move |mut campaign| {
+        // Before filtering, which compares statuses and tags
+        let one = std::slice::from_mut(&mut campaign);
+        normalization.apply(&tables, one);
+        naming::tag_campaigns(&conventions, one);
+        campaign
+    });
+    let campaigns = campaigns.filter(move |campaign| {
+        let labelled = match &label_keys {
+            Some(keys) => labels::carries_label(campaign, keys),
+            None => true,
+        };
+        let keep = labelled
+            && matches_kind(campaign, objectives.as_deref(), types.as_deref())
+            && platforms.iter().all(|list| list.contains(&campaign.platform))
+            && statuses.iter().all(|list| list.contains(&campaign.status))
+            && filter.iter().all(|filter| filter.matches(campaign));
+        std::future::ready(keep && (include_removed || campaign.status != CampaignStatus::Removed))
+    });
+    let lines = campaigns.map(move |mut campaign| {
+        if with_pacing {
+            pacing::attach_pacing(std::slice::from_mut(&mut campaign), today);
+        }
+
+        let mut line = serde_json::to_vec(&Sparse(campaign, selection.clone()))?;
+        line.push(b'\n');
+        Ok::<_, serde_json::Error>(Bytes::from(line))
+    });
+    let body = Body::from_stream(call_budget::scope_stream(call_budget::current(), lines));
+
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static(NDJSON_CONTENT_TYPE))],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+    use super::super::connectors::{ConnectorRegistry, MockConnector};
+
+    fn registry() -> ConnectorRegistry {
+        let mut registry = ConnectorRegistry::default();
+        for (platform, fail) in [(Platform::Google, false), (Platform::Meta, true)] {
+            registry.register(Arc::new(MockConnector {
+                platform: platform.clone(),
+                campaigns: (0..3)
+                    .map(|i| Campaign::sample(&format!("{:?}-{}", platform, i), platform.clone()))
+                    .collect(),
+                fail,
+            }));
+        }
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_failing_platform_is_skipped() {
+        let campaigns: Vec<Campaign> = campaign_stream(registry().all().to_vec()).collect().await;
+
+        assert_eq!(campaigns.len(), 3);
+        assert!(campaigns.iter().all(|c| c.platform == Platform::Google));
+    }
+
+    #[test]
+    fn test_accept_header_negotiation() {
+        let accept = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+            accepts_ndjson(&headers)
+        };
+
+        assert!(accept("application/x-ndjson"));
+        assert!(accept("application/json;q=0.5, application/x-ndjson"));
+        assert!(!accept("application/json"));
+        assert!(!accepts_ndjson(&HeaderMap::new()));
+    }
+}
@@ -0,0 +1,464 @@
+//! # Negative Keyword and Exclusion Example
+//!
+//! Campaign-level exclusions, one model for both platforms:
+//!
+//! - Google: negative keywords, set on the campaign or inherited from a
+//!   shared negative keyword list (`shared_list`);
+//! - Meta: excluded custom audiences (by audience ID) and excluded
+//!   placements (`audience_network`, `instagram_reels`, ...).
+//!
+//! `GET /campaigns/{platform}/{id}/exclusions` lists them and
+//! `PATCH /campaigns/{platform}/{id}/exclusions` adds and removes entries
+//! set on the campaign, returning the exclusions read back from the
+//! platform. Shared list entries apply to every campaign using the list,
+//! so they are edited on the list in the platform, never through one
+//! campaign. Changes go through freezes and the audit log like status and
+//! budget changes.
+//!
+//! `GET /exclusions/conflicts` checks every Google campaign's negatives
+//! against the search terms that converted over the date range and flags
+//! negatives that block them now, following Google's matching rules for
+//! negatives (no close variants):
+//!
+//! - broad: every word of the negative is in the term, in any order;
+//! - phrase: the words of the negative are in the term, in order;
+//! - exact: the term is the negative.
+//!
+//! A negative blocking a term that converted in its own campaign is
+//! likely a mistake (`blocks_own_traffic`); one blocking a term that
+//! converted in another campaign is often deliberate routing, and is
+//! reported for review.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::audit::{AuditChange, AuditEntry};
+use super::call_budget;
+use super::campaign_aggregator::{aggregate_campaigns, AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::CampaignId;
+use super::keywords::{self, KeywordQuery, MatchType, SearchTerm};
+use super::labels::find_on_platform;
+
+/// Longest negative keyword Google accepts, in characters
+pub const MAX_KEYWORD_CHARS: usize = 80;
+
+/// Most words in a negative keyword Google accepts
+pub const MAX_KEYWORD_WORDS: usize = 10;
+
+/// Most entries one `PATCH` may add and remove
+pub const MAX_EXCLUSION_CHANGES: usize = 1_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionKind {
+    /// Google
+    NegativeKeyword,
+    /// Meta custom audience
+    Audience,
+    /// Meta placement
+    Placement,
+}
+
+impl ExclusionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExclusionKind::NegativeKeyword => "negative_keyword",
+            ExclusionKind::Audience => "audience",
+            ExclusionKind::Placement => "placement",
+        }
+    }
+
+    pub fn supported_on(&self, platform: &Platform) -> bool {
+        match self {
+            ExclusionKind::NegativeKeyword => *platform == Platform::Google,
+            ExclusionKind::Audience | ExclusionKind::Placement => *platform == Platform::Meta,
+        }
+    }
+}
+
+/// One exclusion of a campaign, as returned by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Exclusion {
+    pub kind: ExclusionKind,
+    /// Keyword text, audience ID or placement
+    pub value: String,
+    /// Negative keywords only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_type: Option<MatchType>,
+    /// Audience name, when the platform reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Shared negative keyword list the keyword comes from; `None` when
+    /// set on the campaign itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_list: Option<String>,
+}
+
+impl Exclusion {
+    /// Same entry, ignoring the display name; keywords compare case-insensitively
+    fn same_entry(&self, other: &Exclusion) -> bool {
+        self.kind == other.kind
+            && self.match_type == other.match_type
+            && self.shared_list == other.shared_list
+            && match self.kind {
+                ExclusionKind::NegativeKeyword => {
+                    normalize_words(&self.value) == normalize_words(&other.value)
+                }
+                _ => self.value == other.value,
+            }
+    }
+}
+
+/// Response of the `/campaigns/{platform}/{id}/exclusions` routes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignExclusions {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub exclusions: Vec<Exclusion>,
+}
+
+/// Body of `PATCH /campaigns/{platform}/{id}/exclusions`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExclusionUpdate {
+    #[serde(default)]
+    pub add: Vec<Exclusion>,
+    #[serde(default)]
+    pub remove: Vec<Exclusion>,
+}
+
+impl ExclusionUpdate {
+    /// Check every entry is valid on `platform` and set on the campaign
+    pub fn validate(&self, platform: &Platform) -> Result<(), ApiError> {
+        let changes = self.add.len() + self.remove.len();
+        if changes == 0 {
+            return Err(ApiError::Validation("add or remove at least one exclusion".to_string()));
+        }
+        if changes > MAX_EXCLUSION_CHANGES {
+            return Err(ApiError::Validation(format!(
+                "{} exclusion changes; at most {} per request",
+                changes, MAX_EXCLUSION_CHANGES
+            )));
+        }
+
+        for exclusion in self.add.iter().chain(&self.remove) {
+            validate_exclusion(exclusion, platform)?;
+        }
+        if let Some(both) = self.add.iter().find(|a| self.remove.iter().any(|r| a.same_entry(r))) {
+            return Err(ApiError::Validation(format!(
+                "{} {:?} is both added and removed",
+                both.kind.as_str(),
+                both.value
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn validate_exclusion(exclusion: &Exclusion, platform: &Platform) -> Result<(), ApiError> {
+    let kind = exclusion.kind.as_str();
+    if !exclusion.kind.supported_on(platform) {
+        return Err(ApiError::Validation(format!(
+            "{} exclusions are not supported on {}",
+            kind,
+            platform.as_str()
+        )));
+    }
+    if exclusion.value.trim().is_empty() {
+        return Err(ApiError::Validation(format!("{} value must not be empty", kind)));
+    }
+    if exclusion.shared_list.is_some() {
+        return Err(ApiError::Validation(format!(
+            "{} {:?} belongs to a shared list; edit the list instead",
+            kind, exclusion.value
+        )));
+    }
+
+    match exclusion.kind {
+        ExclusionKind::NegativeKeyword => {
+            if exclusion.match_type.is_none() {
+                return Err(ApiError::Validation(format!(
+                    "negative keyword {:?} needs a match_type",
+                    exclusion.value
+                )));
+            }
+            let words = normalize_words(&exclusion.value).len();
+            if exclusion.value.chars().count() > MAX_KEYWORD_CHARS || words > MAX_KEYWORD_WORDS {
+                return Err(ApiError::Validation(format!(
+                    "negative keyword {:?} exceeds {} characters or {} words",
+                    exclusion.value, MAX_KEYWORD_CHARS, MAX_KEYWORD_WORDS
+                )));
+            }
+        }
+        _ if exclusion.match_type.is_some() => {
+            return Err(ApiError::Validation(format!("{} exclusions have no match_type", kind)));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Lowercased words of a keyword or search term
+fn normalize_words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// Whether the negative keyword `negative` keeps ads from showing on `term`
+pub fn blocks(negative: &str, match_type: MatchType, term: &str) -> bool {
+    let negative = normalize_words(negative);
+    let term = normalize_words(term);
+    if negative.is_empty() {
+        return false;
+    }
+
+    match match_type {
+        MatchType::Exact => negative == term,
+        MatchType::Phrase => term.windows(negative.len()).any(|window| window == negative),
+        MatchType::Broad => negative.iter().all(|word| term.contains(word)),
+    }
+}
+
+/// Negative keyword blocking a search term that converted
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExclusionConflict {
+    /// Campaign the negative applies to
+    pub campaign_id: CampaignId,
+    pub negative: String,
+    pub match_type: MatchType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared_list: Option<String>,
+    pub search_term: String,
+    /// Campaign the term converted in
+    pub term_campaign_id: CampaignId,
+    pub conversions: f64,
+    pub cost: f64,
+    /// The term converted in the campaign the negative applies to
+    pub blocks_own_traffic: bool,
+}
+
+/// Response of `GET /exclusions/conflicts`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConflictReport {
+    pub campaigns_checked: usize,
+    /// Own-traffic conflicts first, then most conversions first
+    pub conflicts: Vec<ExclusionConflict>,
+}
+
+/// Conflicts between the negatives of `campaigns` and converting `terms`
+pub fn find_conflicts(
+    campaigns: &[(CampaignId, Vec<Exclusion>)],
+    terms: &[SearchTerm],
+) -> Vec<ExclusionConflict> {
+    let converting: Vec<&SearchTerm> = terms.iter().filter(|t| t.conversions > 0.0).collect();
+
+    let mut conflicts = Vec::new();
+    for (campaign_id, exclusions) in campaigns {
+        let negatives = exclusions.iter().filter(|e| e.kind == ExclusionKind::NegativeKeyword);
+        for negative in negatives {
+            let Some(match_type) = negative.match_type else { continue };
+            for term in &converting {
+                if !blocks(&negative.value, match_type, &term.search_term) {
+                    continue;
+                }
+                conflicts.push(ExclusionConflict {
+                    campaign_id: campaign_id.clone(),
+                    negative: negative.value.clone(),
+                    match_type,
+                    shared_list: negative.shared_list.clone(),
+                    search_term: term.search_term.clone(),
+                    term_campaign_id: term.campaign_id.clone(),
+                    conversions: term.conversions,
+                    cost: term.cost,
+                    blocks_own_traffic: term.campaign_id == *campaign_id,
+                });
+            }
+        }
+    }
+
+    conflicts.sort_by(|a, b| {
+        b.blocks_own_traffic
+            .cmp(&a.blocks_own_traffic)
+            .then(b.conversions.total_cmp(&a.conversions))
+    });
+    conflicts
+}
+
+/// GET /campaigns/{platform}/{id}/exclusions - Exclusions of a campaign
+pub async fn get_campaign_exclusions(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<CampaignExclusions>, ApiError> {
+    let connector = state
+        .connectors
+        .get(&platform)
+        .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+    let exclusions = connector.fetch_exclusions(&id).await?;
+
+    Ok(Json(CampaignExclusions { platform, campaign_id: id, exclusions }))
+}
+
+/// PATCH /campaigns/{platform}/{id}/exclusions - Add and remove exclusions
+///
+/// Returns the campaign's exclusions read back from the platform.
+pub async fn update_campaign_exclusions(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+    Json(update): Json<ExclusionUpdate>,
+) -> Result<Json<CampaignExclusions>, ApiError> {
+    update.validate(&platform)?;
+    let (connector, campaign) = find_on_platform(&state, &platform, &id).await?;
+    let target = format!("update_exclusions {}/{}", platform.as_str(), id);
+    state.freezes.check(&actor, &campaign.labels, &target, Utc::now())?;
+
+    let result = connector.update_exclusions(&id, &update).await.map_err(ApiError::from);
+    let change = AuditChange::Exclusions { added: update.add, removed: update.remove };
+    state.audit.record(AuditEntry::new(&actor, &platform, &id, change, &result));
+    result?;
+
+    Ok(Json(CampaignExclusions {
+        exclusions: connector.fetch_exclusions(&id).await?,
+        platform,
+        campaign_id: id,
+    }))
+}
+
+/// GET /exclusions/conflicts - Negatives blocking converting search terms
+pub async fn get_exclusion_conflicts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<KeywordQuery>,
+) -> Result<Json<ConflictReport>, ApiError> {
+    let range = keywords::date_range(query.start_date, query.end_date)?;
+    let connector = state
+        .connectors
+        .get(&Platform::Google)
+        .ok_or_else(|| ApiError::NotSupported(Platform::Google.as_str().to_string()))?;
+    let campaign_ids: Vec<CampaignId> = aggregate_campaigns(&state)
+        .await
+        .into_iter()
+        .filter(|c| c.platform == Platform::Google && c.status != CampaignStatus::Removed)
+        .map(|c| c.id)
+        .collect();
+    call_budget::charge(campaign_ids.len() + 1)?;
+
+    let terms = keywords::fetch_search_terms(&state, Platform::Google.as_str(), range).await?;
+    if !terms.iter().any(|t| t.conversions > 0.0) {
+        return Ok(Json(ConflictReport {
+            campaigns_checked: campaign_ids.len(),
+            conflicts: Vec::new(),
+        }));
+    }
+
+    let fetches = campaign_ids.iter().map(|id| {
+        let connector = connector.clone();
+        async move { (id.clone(), connector.fetch_exclusions(id).await) }
+    });
+    let mut campaigns = Vec::new();
+    for (id, result) in futures::future::join_all(fetches).await {
+        match result {
+            Ok(exclusions) => campaigns.push((id, exclusions)),
+            Err(e) => tracing::warn!(campaign_id = %id, error = %e, "exclusion fetch failed"),
+        }
+    }
+
+    Ok(Json(ConflictReport {
+        campaigns_checked: campaigns.len(),
+        conflicts: find_conflicts(&campaigns, &terms),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::keywords::SearchTermStatus;
+    use super::*;
+
+    fn negative(value: &str, match_type: MatchType) -> Exclusion {
+        Exclusion {
+            kind: ExclusionKind::NegativeKeyword,
+            value: value.to_string(),
+            match_type: Some(match_type),
+            name: None,
+            shared_list: None,
+        }
+    }
+
+    fn term(campaign_id: &str, search_term: &str, conversions: f64) -> SearchTerm {
+        SearchTerm {
+            platform: Platform::Google,
+            campaign_id: campaign_id.into(),
+            ad_group_id: "ag1".into(),
+            search_term: search_term.to_string(),
+            matched_keyword: None,
+            match_type: None,
+            status: SearchTermStatus::None,
+            impressions: 100,
+            clicks: 10,
+            conversions,
+            cost: 20.0,
+        }
+    }
+
+    #[test]
+    fn test_negatives_blocking_converting_terms_are_flagged() {
+        assert!(blocks("aluguel", MatchType::Broad, "apartamento para aluguel"));
+        assert!(blocks("para aluguel", MatchType::Phrase, "apartamento para aluguel"));
+        assert!(!blocks("aluguel para", MatchType::Phrase, "apartamento para aluguel"));
+        assert!(!blocks("apartamento", MatchType::Exact, "apartamento barato"));
+
+        let campaigns = vec![
+            ("g1".into(), vec![negative("barato", MatchType::Broad)]),
+            ("g2".into(), vec![negative("Apartamento Barato", MatchType::Exact)]),
+        ];
+        let terms = vec![
+            term("g2", "apartamento barato", 1.0),
+            term("g1", "apartamento barato centro", 3.0),
+            term("g1", "casa barato", 0.0),
+        ];
+
+        let conflicts = find_conflicts(&campaigns, &terms);
+        let pairs: Vec<(&str, &str, bool)> = conflicts
+            .iter()
+            .map(|c| (c.campaign_id.as_str(), c.search_term.as_str(), c.blocks_own_traffic))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("g1", "apartamento barato centro", true),
+                ("g2", "apartamento barato", true),
+                ("g1", "apartamento barato", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_rejects_entries_the_platform_cannot_take() {
+        let update = |add: Vec<Exclusion>| ExclusionUpdate { add, remove: Vec::new() };
+        assert!(update(vec![negative("grátis", MatchType::Broad)])
+            .validate(&Platform::Google)
+            .is_ok());
+        assert!(update(vec![negative("grátis", MatchType::Broad)])
+            .validate(&Platform::Meta)
+            .is_err());
+        assert!(update(Vec::new()).validate(&Platform::Google).is_err());
+
+        let mut shared = negative("grátis", MatchType::Broad);
+        shared.shared_list = Some("Marca".to_string());
+        assert!(update(vec![shared]).validate(&Platform::Google).is_err());
+
+        let audience = Exclusion {
+            kind: ExclusionKind::Audience,
+            value: "23851234".to_string(),
+            match_type: None,
+            name: None,
+            shared_list: None,
+        };
+        let both = ExclusionUpdate { add: vec![audience.clone()], remove: vec![audience] };
+        assert!(both.validate(&Platform::Meta).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! # Normalization Rules Example
+//!
+//! Platforms add status, objective and campaign type values faster than we
+//! release: Google reports `PENDING` for campaigns waiting on their start
+//! date, Meta `IN_PROCESS` while it applies an edit. Values the connectors
+//! do not know deserialize as `CampaignStatus::Other`, `CampaignObjective::Other`
+//! and `CampaignType::Other` instead of failing the whole campaign list, and
+//! mapping tables per platform turn them into unified values:
+//!
+//! ```json
+//! {
+//!   "google": {"status": {"PENDING": "PAUSED"},
+//!              "campaign_type": {"DEMAND_GEN": "DISPLAY"}},
+//!   "meta": {"status": {"IN_PROCESS": "ENABLED"},
+//!            "objective": {"OUTCOME_STORE_TRAFFIC": "TRAFFIC"}}
+//! }
+//! ```
+//!
+//! Built-in tables cover Meta's and LinkedIn's objectives and Meta's and
+//! Microsoft's campaign types; Google's channel types `SEARCH`, `DISPLAY`,
+//! `VIDEO` and `SHOPPING` are unified values already. The file named
+//! by `normalization.rules_path` is loaded over them at startup and again
+//! by `POST /admin/normalization/reload` or `POST /admin/reload`, so a new
+//! value is mapped without a release. Tables apply when campaigns are read, like naming
+//! conventions, so a reload also changes campaigns already in the entity
+//! cache. Values no table maps stay `Other` and are listed by
+//! `GET /admin/normalization` until a table covers them.
+
+use axum::{extract::State, response::Json};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{
+    AppState, Campaign, CampaignObjective, CampaignStatus, CampaignType, Platform,
+};
+use super::error::ApiError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NormalizationError {
+    #[error("cannot read normalization rules: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("invalid normalization rules: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// Mapping tables of one platform, keyed by the platform's own value
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MappingTable {
+    pub status: BTreeMap<String, CampaignStatus>,
+    pub objective: BTreeMap<String, CampaignObjective>,
+    pub campaign_type: BTreeMap<String, CampaignType>,
+}
+
+impl MappingTable {
+    /// Add the entries of `other`, replacing those for the same values
+    fn extend(&mut self, other: MappingTable) {
+        self.status.extend(other.status);
+        self.objective.extend(other.objective);
+        self.campaign_type.extend(other.campaign_type);
+    }
+}
+
+pub type MappingTables = HashMap<Platform, MappingTable>;
+
+/// Tables shipped with the service
+///
+/// Meta objectives cover both the outcome-based ones and those of campaigns
+/// created before them (`LINK_CLICKS`, ...); `CONVERSIONS` and
+/// `APP_INSTALLS` are accepted by `CampaignObjective` itself.
+pub fn builtin_tables() -> MappingTables {
+    let mut tables = MappingTables::new();
+    tables.insert(
+        Platform::Meta,
+        MappingTable {
+            objective: entries([
+                ("OUTCOME_AWARENESS", CampaignObjective::Awareness),
+                ("OUTCOME_TRAFFIC", CampaignObjective::Traffic),
+                ("OUTCOME_ENGAGEMENT", CampaignObjective::Engagement),
+                ("OUTCOME_LEADS", CampaignObjective::Leads),
+                ("OUTCOME_APP_PROMOTION", CampaignObjective::AppPromotion),
+                ("OUTCOME_SALES", CampaignObjective::Sales),
+                ("BRAND_AWARENESS", CampaignObjective::Awareness),
+                ("REACH", CampaignObjective::Awareness),
+                ("LINK_CLICKS", CampaignObjective::Traffic),
+                ("POST_ENGAGEMENT", CampaignObjective::Engagement),
+                ("LEAD_GENERATION", CampaignObjective::Leads),
+                ("PRODUCT_CATALOG_SALES", CampaignObjective::Sales),
+            ]),
+            // Advantage+ shopping campaigns, by their `smart_promotion_type`
+            campaign_type: entries([("AUTOMATED_SHOPPING_ADS", CampaignType::Shopping)]),
+            ..MappingTable::default()
+        },
+    );
+    #[cfg(feature = "linkedin")]
+    tables.insert(
+        Platform::LinkedIn,
+        MappingTable {
+            objective: entries([
+                ("BRAND_AWARENESS", CampaignObjective::Awareness),
+                ("WEBSITE_VISIT", CampaignObjective::Traffic),
+                ("ENGAGEMENT", CampaignObjective::Engagement),
+                ("LEAD_GENERATION", CampaignObjective::Leads),
+                ("WEBSITE_CONVERSION", CampaignObjective::Sales),
+            ]),
+            ..MappingTable::default()
+        },
+    );
+    #[cfg(feature = "microsoft")]
+    tables.insert(
+        Platform::Microsoft,
+        MappingTable {
+            campaign_type: entries([
+                ("Search", CampaignType::Search),
+                ("DynamicSearchAds", CampaignType::Search),
+                ("Audience", CampaignType::Display),
+                ("Shopping", CampaignType::Shopping),
+            ]),
+            ..MappingTable::default()
+        },
+    );
+    tables
+}
+
+fn entries<T, const N: usize>(entries: [(&str, T); N]) -> BTreeMap<String, T> {
+    entries.into_iter().map(|(value, mapped)| (value.to_string(), mapped)).collect()
+}
+
+/// Value seen on a campaign that no table maps
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct UnmappedValue {
+    pub platform: Platform,
+    /// `status`, `objective` or `campaign_type`
+    pub field: &'static str,
+    pub value: String,
+}
+
+/// Response of `GET /admin/normalization`
+#[derive(Debug, Serialize)]
+pub struct NormalizationReport {
+    /// Built-in tables with the loaded file applied over them
+    pub rules: MappingTables,
+    pub unmapped: Vec<UnmappedValue>,
+}
+
+/// Mapping tables in effect, replaced as a whole on reload
+pub struct NormalizationRules {
+    tables: RwLock<Arc<MappingTables>>,
+    unmapped: RwLock<HashSet<UnmappedValue>>,
+}
+
+impl Default for NormalizationRules {
+    fn default() -> Self {
+        Self {
+            tables: RwLock::new(Arc::new(builtin_tables())),
+            unmapped: RwLock::default(),
+        }
+    }
+}
+
+impl NormalizationRules {
+    pub fn get(&self) -> Arc<MappingTables> {
+        self.tables.read().unwrap().clone()
+    }
+
+    /// Replace the tables with the built-in ones plus those of `path`
+    ///
+    /// On error the tables in effect are kept.
+    pub fn load(&self, path: &Path) -> Result<Arc<MappingTables>, NormalizationError> {
+        let bytes = std::fs::read(path)?;
+        let loaded: MappingTables = serde_json::from_slice(&bytes)?;
+
+        let mut tables = builtin_tables();
+        for (platform, table) in loaded {
+            tables.entry(platform).or_default().extend(table);
+        }
+        let tables = Arc::new(tables);
+        *self.tables.write().unwrap() = tables.clone();
+        // Values the new tables map are no longer unmapped
+        self.unmapped.write().unwrap().clear();
+        Ok(tables)
+    }
+
+    /// Go back to the built-in tables, once `normalization.rules_path` is unset
+    pub fn reset(&self) {
+        *self.tables.write().unwrap() = Arc::new(builtin_tables());
+        self.unmapped.write().unwrap().clear();
+    }
+
+    /// Map the `Other` statuses, objectives and types of `campaigns` through
+    /// `tables`
+    pub fn apply(&self, tables: &MappingTables, campaigns: &mut [Campaign]) {
+        let mut unmapped = Vec::new();
+        for campaign in campaigns {
+            let table = tables.get(&campaign.platform);
+            if let CampaignStatus::Other(value) = &campaign.status {
+                match table.and_then(|t| t.status.get(value)) {
+                    Some(status) => campaign.status = status.clone(),
+                    None => unmapped.push(UnmappedValue {
+                        platform: campaign.platform.clone(),
+                        field: "status",
+                        value: value.clone(),
+                    }),
+                }
+            }
+            if let Some(CampaignObjective::Other(value)) = &campaign.objective {
+                match table.and_then(|t| t.objective.get(value)) {
+                    Some(objective) => campaign.objective = Some(objective.clone()),
+                    None => unmapped.push(UnmappedValue {
+                        platform: campaign.platform.clone(),
+                        field: "objective",
+                        value: value.clone(),
+                    }),
+                }
+            }
+            if let Some(CampaignType::Other(value)) = &campaign.campaign_type {
+                match table.and_then(|t| t.campaign_type.get(value)) {
+                    Some(campaign_type) => campaign.campaign_type = Some(campaign_type.clone()),
+                    None => unmapped.push(UnmappedValue {
+                        platform: campaign.platform.clone(),
+                        field: "campaign_type",
+                        value: value.clone(),
+                    }),
+                }
+            }
+        }
+        self.record(unmapped);
+    }
+
+    /// Remember unmapped values, logging each the first time it is seen
+    fn record(&self, mut values: Vec<UnmappedValue>) {
+        {
+            let known = self.unmapped.read().unwrap();
+            values.retain(|v| !known.contains(v));
+        }
+        if values.is_empty() {
+            return;
+        }
+
+        let mut known = self.unmapped.write().unwrap();
+        for value in values {
+            if !known.contains(&value) {
+                tracing::warn!(
+                    platform = value.platform.as_str(),
+                    field = value.field,
+                    value = %value.value,
+                    "no normalization rule maps this value"
+                );
+                known.insert(value);
+            }
+        }
+    }
+
+    /// Unmapped values, by platform, field and value
+    pub fn unmapped(&self) -> Vec<UnmappedValue> {
+        let mut values: Vec<UnmappedValue> =
+            self.unmapped.read().unwrap().iter().cloned().collect();
+        values.sort_by(|a, b| {
+            (a.platform.as_str(), a.field, &a.value).cmp(&(b.platform.as_str(), b.field, &b.value))
+        });
+        values
+    }
+}
+
+/// Map the unmapped values of `campaigns` with the tables in effect
+pub fn normalize_campaigns(state: &AppState, campaigns: &mut [Campaign]) {
+    state.normalization.apply(&state.normalization.get(), campaigns);
+}
+
+/// GET /admin/normalization - Tables in effect and values they miss
+pub async fn get_normalization(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NormalizationReport>, ApiError> {
+    Ok(Json(NormalizationReport {
+        rules: state.normalization.get().as_ref().clone(),
+        unmapped: state.normalization.unmapped(),
+    }))
+}
+
+/// POST /admin/normalization/reload - Load `normalization.rules_path` again
+pub async fn reload_normalization(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NormalizationReport>, ApiError> {
+    let config = state.live_config.get();
+    let path = config
+        .normalization
+        .rules_path
+        .as_deref()
+        .ok_or_else(|| ApiError::Validation("normalization.rules_path is not set".to_string()))?;
+    let rules = state
+        .normalization
+        .load(path)
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    tracing::info!(path = %path.display(), "normalization rules reloaded");
+
+    Ok(Json(NormalizationReport {
+        rules: rules.as_ref().clone(),
+        unmapped: state.normalization.unmapped(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(id: &str, platform: Platform, status: &str, objective: &str) -> Campaign {
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.status = CampaignStatus::Other(status.to_string());
+        campaign.objective = Some(CampaignObjective::Other(objective.to_string()));
+        campaign
+    }
+
+    #[test]
+    fn test_unknown_values_deserialize_as_other() {
+        let status: CampaignStatus = serde_json::from_str(r#""IN_PROCESS""#).unwrap();
+        assert_eq!(status, CampaignStatus::Other("IN_PROCESS".to_string()));
+        let status: CampaignStatus = serde_json::from_str(r#""PAUSED""#).unwrap();
+        assert_eq!(status, CampaignStatus::Paused);
+        assert_eq!(serde_json::to_value(CampaignStatus::Other("X".into())).unwrap(), "X");
+    }
+
+    #[test]
+    fn test_loaded_tables_extend_builtin_ones() {
+        let path = std::env::temp_dir().join(format!("rules-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{"meta": {"status": {"IN_PROCESS": "ENABLED"}}}"#).unwrap();
+        let rules = NormalizationRules::default();
+        let mut campaigns = vec![
+            campaign("m1", Platform::Meta, "IN_PROCESS", "OUTCOME_LEADS"),
+            campaign("g1", Platform::Google, "PENDING", "SEARCH"),
+        ];
+        rules.apply(&rules.get(), &mut campaigns[..1]);
+        assert_eq!(rules.unmapped()[0].value, "IN_PROCESS");
+
+        let tables = rules.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        rules.apply(&tables, &mut campaigns);
+
+        assert_eq!(campaigns[0].status, CampaignStatus::Enabled);
+        assert_eq!(campaigns[0].objective, Some(CampaignObjective::Leads));
+        assert_eq!(campaigns[1].status, CampaignStatus::Other("PENDING".to_string()));
+        let unmapped = rules.unmapped();
+        let unmapped: Vec<(&str, &str)> =
+            unmapped.iter().map(|v| (v.field, v.value.as_str())).collect();
+        assert_eq!(unmapped, [("objective", "SEARCH"), ("status", "PENDING")]);
+    }
+}
@@ -0,0 +1,191 @@
+//! # Budget Pacing Example
+//!
+//! Projects end-of-month spend from month-to-date spend and flags
+//! campaigns that are delivering faster or slower than their daily
+//! budget allows.
+//!
+//! Month-to-date spend is read from `metrics.cost`: the gateway reports
+//! campaign metrics for the current calendar month (`THIS_MONTH` on
+//! Google, `this_month` date preset on Meta).
+
+use axum::{extract::State, response::Json};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, Platform};
+use super::error::ApiError;
+use super::ids::CampaignId;
+use super::timezones;
+
+/// How far (in percentage points) a campaign may drift from 100% pace
+/// before it is flagged as over- or under-pacing
+pub const PACE_TOLERANCE: f64 = 10.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PaceStatus {
+    OnPace,
+    Overpacing,
+    Underpacing,
+}
+
+/// Pacing figures for a single campaign
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Pacing {
+    pub month_to_date_spend: f64,
+    /// Spend expected by today if the daily budget were delivered exactly
+    pub expected_spend: f64,
+    /// Month-to-date spend extrapolated to the last day of the month
+    pub projected_spend: f64,
+    /// Daily budget multiplied by the number of days in the month
+    pub monthly_budget: f64,
+    /// Month-to-date spend as a percentage of expected spend
+    pub pace_percentage: f64,
+    pub status: PaceStatus,
+}
+
+/// Pacing entry returned by `GET /campaigns/pacing`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignPacing {
+    pub id: CampaignId,
+    pub platform: Platform,
+    pub name: String,
+    pub daily_budget: f64,
+    pub pacing: Pacing,
+}
+
+/// Compute pacing for a daily budget and month-to-date spend as of `today`
+///
+/// `today` counts as an elapsed day, so spend on the first of the month
+/// is compared against one full day of budget.
+pub fn compute_pacing(daily_budget: f64, month_to_date_spend: f64, today: NaiveDate) -> Pacing {
+    let days_elapsed = today.day() as f64;
+    let days_in_month = days_in_month(today) as f64;
+
+    let expected_spend = daily_budget * days_elapsed;
+    let projected_spend = month_to_date_spend / days_elapsed * days_in_month;
+    let monthly_budget = daily_budget * days_in_month;
+
+    let pace_percentage = if expected_spend > 0.0 {
+        month_to_date_spend / expected_spend * 100.0
+    } else {
+        0.0
+    };
+
+    let status = if expected_spend <= 0.0 {
+        if month_to_date_spend > 0.0 {
+            PaceStatus::Overpacing
+        } else {
+            PaceStatus::OnPace
+        }
+    } else if pace_percentage > 100.0 + PACE_TOLERANCE {
+        PaceStatus::Overpacing
+    } else if pace_percentage < 100.0 - PACE_TOLERANCE {
+        PaceStatus::Underpacing
+    } else {
+        PaceStatus::OnPace
+    };
+
+    Pacing {
+        month_to_date_spend,
+        expected_spend,
+        projected_spend,
+        monthly_budget,
+        pace_percentage,
+        status,
+    }
+}
+
+/// Fill in the `pacing` block of every campaign
+pub fn attach_pacing(campaigns: &mut [Campaign], today: NaiveDate) {
+    for campaign in campaigns.iter_mut() {
+        campaign.pacing = Some(compute_pacing(
+            campaign.daily_budget,
+            campaign.metrics.cost,
+            today,
+        ));
+    }
+}
+
+/// GET /campaigns/pacing - Pacing report for all campaigns
+///
+/// Returns campaigns ordered by pace percentage (most overpaced first).
+pub async fn get_campaign_pacing(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CampaignPacing>>, ApiError> {
+    let today = timezones::today();
+    let campaigns = aggregate_campaigns(&state).await;
+
+    let mut report: Vec<CampaignPacing> = campaigns
+        .into_iter()
+        .map(|c| CampaignPacing {
+            pacing: compute_pacing(c.daily_budget, c.metrics.cost, today),
+            id: c.id,
+            platform: c.platform,
+            name: c.name,
+            daily_budget: c.daily_budget,
+        })
+        .collect();
+
+    report.sort_by(|a, b| {
+        b.pacing
+            .pace_percentage
+            .partial_cmp(&a.pacing.pace_percentage)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(Json(report))
+}
+
+/// Number of days in the month containing `date`
+pub fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first_of_next| first_of_next.pred_opt())
+        .map(|last_day| last_day.day())
+        .unwrap_or(30)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_compute_pacing_on_pace() {
+        // 10 days into a 30-day month at exactly R$ 100/day
+        let pacing = compute_pacing(100.0, 1000.0, date(2025, 11, 10));
+
+        assert_eq!(pacing.expected_spend, 1000.0);
+        assert_eq!(pacing.projected_spend, 3000.0);
+        assert_eq!(pacing.monthly_budget, 3000.0);
+        assert_eq!(pacing.pace_percentage, 100.0);
+        assert_eq!(pacing.status, PaceStatus::OnPace);
+    }
+
+    #[test]
+    fn test_compute_pacing_flags_over_and_under() {
+        let over = compute_pacing(100.0, 1500.0, date(2025, 11, 10));
+        assert_eq!(over.status, PaceStatus::Overpacing);
+        assert_eq!(over.projected_spend, 4500.0);
+
+        let under = compute_pacing(100.0, 500.0, date(2025, 11, 10));
+        assert_eq!(under.status, PaceStatus::Underpacing);
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(date(2024, 2, 15)), 29);
+        assert_eq!(days_in_month(date(2025, 12, 31)), 31);
+        assert_eq!(days_in_month(date(2025, 11, 1)), 30);
+    }
+}
@@ -0,0 +1,323 @@
+//! # Upstream Pagination Example
+//!
+//! Platform list endpoints are paginated and the gateway relays their
+//! envelopes as is: Google Ads returns a `nextPageToken`, Meta a `paging`
+//! block with an `after` cursor, LinkedIn `start`/`count` offsets and
+//! Microsoft Advertising page indexes. Each connector follows its
+//! platform's pagination to the last page, so the aggregator always sees
+//! complete lists:
+//!
+//! - `gateway.pagination.page_size` items are requested per page;
+//! - at most `gateway.pagination.max_pages` pages are read per list; a
+//!   longer list is cut short with a warning rather than failed;
+//! - every page after the first is charged to the request's upstream
+//!   call budget.
+//!
+//! Token and cursor pages (Google Ads, Meta) can only be read one after
+//! the other. Pages addressed by offset (LinkedIn, Microsoft Advertising)
+//! are read `gateway.pagination.concurrency` at a time once the first page
+//! shows the list goes on. Either way items keep their page order and the
+//! list ends at the first page without a successor, so totals match a
+//! page-by-page walk. Each list is read inside a `paginate` span whose
+//! `pages` and `items` fields follow the progress.
+//!
+//! Gateway routes that still answer with a bare JSON array are read as a
+//! single page.
+
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::future::Future;
+use tracing::Instrument;
+
+use super::call_budget;
+use super::campaign_aggregator::Platform;
+use super::config::PaginationConfig;
+use super::connectors::ConnectorError;
+
+/// One page of a list, with the cursor of the next page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Opaque position of the next page (token, cursor or offset);
+    /// `None` on the last page
+    pub next: Option<String>,
+}
+
+/// Fetch pages from the first one until a page has no successor, or
+/// `config.max_pages` were read
+///
+/// `fetch_page` receives `None` for the first page, then the `next`
+/// cursor of the previous page. A cursor repeating the previous one ends
+/// the list instead of looping on the same page.
+pub async fn collect_pages<T, F, Fut>(
+    platform: &Platform,
+    config: &PaginationConfig,
+    mut fetch_page: F,
+) -> Result<Vec<T>, ConnectorError>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page<T>, ConnectorError>>,
+{
+    async {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        for page in 0..config.max_pages {
+            if page > 0 {
+                call_budget::charge(1)?;
+            }
+
+            let Page { items: page_items, next } = fetch_page(cursor.clone()).await?;
+            items.extend(page_items);
+            record_progress(page + 1, items.len());
+
+            match next {
+                Some(next) if !next.is_empty() && cursor.as_deref() != Some(next.as_str()) => {
+                    cursor = Some(next);
+                }
+                _ => return Ok(items),
+            }
+        }
+
+        warn_truncated(platform, config, items.len());
+        Ok(items)
+    }
+    .instrument(progress_span(platform))
+    .await
+}
+
+/// Fetch the pages of a list addressed by offset, with up to
+/// `config.concurrency` pages in flight
+///
+/// `fetch_page` receives the page number, from 0. The first page is read
+/// alone; when it has a successor, the next pages are requested ahead of
+/// the one being read. Pages requested past the last one are dropped, at
+/// most `concurrency - 1` wasted calls per list.
+pub async fn collect_pages_concurrently<T, F, Fut>(
+    platform: &Platform,
+    config: &PaginationConfig,
+    fetch_page: F,
+) -> Result<Vec<T>, ConnectorError>
+where
+    F: Fn(u32) -> Fut,
+    Fut: Future<Output = Result<Page<T>, ConnectorError>>,
+{
+    async {
+        let Page { mut items, next } = fetch_page(0).await?;
+        record_progress(1, items.len());
+        if next.is_none() {
+            return Ok(items);
+        }
+
+        let mut pages = stream::iter(1..config.max_pages)
+            .map(|page| {
+                // Charged when the page is requested, not when it is read
+                let charged = call_budget::charge(1);
+                let fetch = fetch_page(page);
+                async move {
+                    charged?;
+                    fetch.await
+                }
+            })
+            .buffered(config.concurrency.max(1));
+
+        let mut read = 1;
+        while let Some(page) = pages.next().await {
+            let Page { items: page_items, next } = page?;
+            items.extend(page_items);
+            read += 1;
+            record_progress(read, items.len());
+            if next.is_none() {
+                return Ok(items);
+            }
+        }
+
+        warn_truncated(platform, config, items.len());
+        Ok(items)
+    }
+    .instrument(progress_span(platform))
+    .await
+}
+
+fn progress_span(platform: &Platform) -> tracing::Span {
+    tracing::info_span!(
+        "paginate",
+        platform = platform.as_str(),
+        pages = tracing::field::Empty,
+        items = tracing::field::Empty,
+    )
+}
+
+fn record_progress(pages: u32, items: usize) {
+    tracing::Span::current()
+        .record("pages", pages)
+        .record("items", items);
+    tracing::debug!(pages, items, "page fetched");
+}
+
+fn warn_truncated(platform: &Platform, config: &PaginationConfig, items: usize) {
+    tracing::warn!(
+        platform = platform.as_str(),
+        max_pages = config.max_pages,
+        items,
+        "list truncated after max_pages"
+    );
+}
+
+/// `paging` block of Meta Graph API lists
+#[derive(Debug, Deserialize, Default)]
+pub struct MetaPaging {
+    #[serde(default)]
+    pub cursors: Option<MetaCursors>,
+    /// URL of the next page; absent on the last page
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct MetaCursors {
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+/// List response of the gateway's Google Ads and Meta routes
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GatewayPage<T> {
+    /// Google Ads: `{"results": [...], "nextPageToken": "..."}`
+    Google {
+        results: Vec<T>,
+        #[serde(default, alias = "nextPageToken")]
+        next_page_token: Option<String>,
+    },
+    /// Meta: `{"data": [...], "paging": {"cursors": {"after": "..."}, "next": "..."}}`
+    Meta {
+        data: Vec<T>,
+        #[serde(default)]
+        paging: Option<MetaPaging>,
+    },
+    Unpaged(Vec<T>),
+}
+
+impl<T> From<GatewayPage<T>> for Page<T> {
+    fn from(page: GatewayPage<T>) -> Self {
+        match page {
+            GatewayPage::Google {
+                results,
+                next_page_token,
+            } => Page {
+                items: results,
+                next: next_page_token,
+            },
+            // Meta keeps an `after` cursor on the last page; only `next`
+            // says whether another page exists
+            GatewayPage::Meta { data, paging } => Page {
+                items: data,
+                next: paging
+                    .filter(|p| p.next.is_some())
+                    .and_then(|p| p.cursors?.after),
+            },
+            GatewayPage::Unpaged(items) => Page { items, next: None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Page of a list with two items per page
+    fn index_page(items: Vec<u32>, n: u32) -> Page<u32> {
+        Page {
+            next: (items.len() == 2).then(|| (n + 1).to_string()),
+            items,
+        }
+    }
+
+    fn parse(json: serde_json::Value) -> Page<u32> {
+        serde_json::from_value::<GatewayPage<u32>>(json).unwrap().into()
+    }
+
+    #[test]
+    fn test_gateway_envelopes_expose_next_cursor() {
+        let google = parse(serde_json::json!({ "results": [1, 2], "nextPageToken": "t2" }));
+        assert_eq!(google, Page { items: vec![1, 2], next: Some("t2".to_string()) });
+
+        let meta = parse(serde_json::json!({
+            "data": [3],
+            "paging": { "cursors": { "after": "c2" }, "next": "https://graph.facebook.com/..." }
+        }));
+        assert_eq!(meta.next.as_deref(), Some("c2"));
+
+        let meta_last = parse(serde_json::json!({
+            "data": [4],
+            "paging": { "cursors": { "after": "c3" } }
+        }));
+        assert_eq!(meta_last.next, None);
+
+        assert_eq!(parse(serde_json::json!([5, 6])), Page { items: vec![5, 6], next: None });
+    }
+
+    #[tokio::test]
+    async fn test_pages_followed_until_last_or_max_pages() {
+        let pages = |cursor: Option<String>| async move {
+            let n: u32 = cursor.as_deref().map_or(0, |c| c.parse().unwrap());
+            Ok::<_, ConnectorError>(Page {
+                items: vec![n],
+                next: (n < 4).then(|| (n + 1).to_string()),
+            })
+        };
+
+        let config = PaginationConfig::default();
+        let all = collect_pages(&Platform::Google, &config, pages).await.unwrap();
+        assert_eq!(all, vec![0, 1, 2, 3, 4]);
+
+        let capped = PaginationConfig {
+            max_pages: 2,
+            ..Default::default()
+        };
+        let first = collect_pages(&Platform::Google, &capped, pages).await.unwrap();
+        assert_eq!(first, vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_pages_match_a_sequential_walk() {
+        let fetched = std::sync::atomic::AtomicU32::new(0);
+        // Pages 0 to 4 hold two items each, page 5 is empty
+        let pages = |n: u32| {
+            fetched.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                let items = if n < 5 { vec![n * 2, n * 2 + 1] } else { Vec::new() };
+                Ok::<_, ConnectorError>(index_page(items, n))
+            }
+        };
+
+        let config = PaginationConfig {
+            page_size: 2,
+            concurrency: 4,
+            ..Default::default()
+        };
+        let all = collect_pages_concurrently(&Platform::Google, &config, pages)
+            .await
+            .unwrap();
+
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+        // The empty page ends the list; at most three more were in flight
+        assert!(fetched.load(std::sync::atomic::Ordering::SeqCst) <= 6 + 3);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_cursor_ends_the_list() {
+        let stuck = |_cursor: Option<String>| async {
+            Ok::<_, ConnectorError>(Page {
+                items: vec![1],
+                next: Some("same".to_string()),
+            })
+        };
+
+        let all = collect_pages(&Platform::Meta, &PaginationConfig::default(), stuck)
+            .await
+            .unwrap();
+        assert_eq!(all, vec![1, 1]);
+    }
+}
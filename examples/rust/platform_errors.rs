@@ -0,0 +1,300 @@
+//! # Platform Error Taxonomy Example
+//!
+//! Google Ads and Meta report failures in their own shapes (a Google Ads
+//! `errorCode` such as `{"quotaError": "RESOURCE_EXHAUSTED"}`, a Meta
+//! `code`/`error_subcode` pair). The gateway relays those bodies
+//! untouched; this module maps them onto a small, stable set of codes with
+//! a remediation hint, so clients branch on `code` instead of matching
+//! upstream message strings. The original code stays available as
+//! `upstream_code` for support tickets.
+//!
+//! | code                     | status | meaning                                   |
+//! |--------------------------|--------|-------------------------------------------|
+//! | `auth_expired`           | 401    | OAuth token expired or revoked            |
+//! | `permission_denied`      | 403    | Connected user lacks access               |
+//! | `account_suspended`      | 403    | Ad account disabled on the platform       |
+//! | `resource_not_found`     | 404    | Campaign, ad group or asset does not exist |
+//! | `duplicate_name`         | 409    | Name already used in the account          |
+//! | `invalid_field`          | 422    | A field value was rejected                |
+//! | `budget_too_low`         | 422    | Budget under the platform minimum         |
+//! | `policy_violation`       | 422    | Blocked by advertising policy             |
+//! | `rate_limited`           | 429    | Short-term throttling                     |
+//! | `quota_exhausted`        | 429    | Daily operation quota used up             |
+//! | `platform_unavailable`   | 502    | Transient platform failure                |
+//! | `unknown_platform_error` | 502    | Anything not mapped yet                   |
+
+use serde::{Deserialize, Serialize};
+
+use super::campaign_aggregator::Platform;
+
+/// Stable error codes for ad platform failures
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlatformErrorCode {
+    AuthExpired,
+    PermissionDenied,
+    AccountSuspended,
+    ResourceNotFound,
+    DuplicateName,
+    InvalidField,
+    BudgetTooLow,
+    PolicyViolation,
+    RateLimited,
+    QuotaExhausted,
+    PlatformUnavailable,
+    UnknownPlatformError,
+}
+
+impl PlatformErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlatformErrorCode::AuthExpired => "auth_expired",
+            PlatformErrorCode::PermissionDenied => "permission_denied",
+            PlatformErrorCode::AccountSuspended => "account_suspended",
+            PlatformErrorCode::ResourceNotFound => "resource_not_found",
+            PlatformErrorCode::DuplicateName => "duplicate_name",
+            PlatformErrorCode::InvalidField => "invalid_field",
+            PlatformErrorCode::BudgetTooLow => "budget_too_low",
+            PlatformErrorCode::PolicyViolation => "policy_violation",
+            PlatformErrorCode::RateLimited => "rate_limited",
+            PlatformErrorCode::QuotaExhausted => "quota_exhausted",
+            PlatformErrorCode::PlatformUnavailable => "platform_unavailable",
+            PlatformErrorCode::UnknownPlatformError => "unknown_platform_error",
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        match self {
+            PlatformErrorCode::AuthExpired => 401,
+            PlatformErrorCode::PermissionDenied | PlatformErrorCode::AccountSuspended => 403,
+            PlatformErrorCode::ResourceNotFound => 404,
+            PlatformErrorCode::DuplicateName => 409,
+            PlatformErrorCode::InvalidField
+            | PlatformErrorCode::BudgetTooLow
+            | PlatformErrorCode::PolicyViolation => 422,
+            PlatformErrorCode::RateLimited | PlatformErrorCode::QuotaExhausted => 429,
+            PlatformErrorCode::PlatformUnavailable | PlatformErrorCode::UnknownPlatformError => 502,
+        }
+    }
+
+    /// What the client or the account owner can do about it
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            PlatformErrorCode::AuthExpired => {
+                "Reconnect the ad account to refresh its authorization."
+            }
+            PlatformErrorCode::PermissionDenied => {
+                "Grant the connected user access to this account or asset on the platform."
+            }
+            PlatformErrorCode::AccountSuspended => {
+                "The ad account is disabled; resolve it in the platform's account settings."
+            }
+            PlatformErrorCode::ResourceNotFound => {
+                "The resource was removed on the platform; refresh the campaign list."
+            }
+            PlatformErrorCode::DuplicateName => "Choose a name not used elsewhere in the account.",
+            PlatformErrorCode::InvalidField => "Correct the rejected field and retry.",
+            PlatformErrorCode::BudgetTooLow => "Raise the budget above the platform minimum.",
+            PlatformErrorCode::PolicyViolation => {
+                "Edit the ad to comply with the platform's advertising policies or request a review."
+            }
+            PlatformErrorCode::RateLimited => "Retry after the indicated delay.",
+            PlatformErrorCode::QuotaExhausted => {
+                "The daily operation quota is used up; retry tomorrow or batch changes."
+            }
+            PlatformErrorCode::PlatformUnavailable => "Temporary platform failure; retry later.",
+            PlatformErrorCode::UnknownPlatformError => {
+                "Contact support with the upstream code and request ID."
+            }
+        }
+    }
+}
+
+/// Failure reported by an ad platform, translated into our taxonomy
+#[derive(Debug, Serialize, Deserialize, Clone, thiserror::Error)]
+#[error("{platform} rejected the request ({upstream_code}): {message}", platform = .platform.as_str())]
+pub struct PlatformError {
+    pub platform: Platform,
+    pub code: PlatformErrorCode,
+    /// Platform's own code, e.g. `quotaError.RESOURCE_EXHAUSTED` or `190/463`
+    pub upstream_code: String,
+    pub message: String,
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope<T> {
+    error: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleStatus {
+    message: Option<String>,
+    #[serde(default)]
+    details: Vec<GoogleFailure>,
+}
+
+#[derive(Deserialize)]
+struct GoogleFailure {
+    #[serde(default)]
+    errors: Vec<GoogleAdsError>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleAdsError {
+    /// Single-entry map, e.g. `{"quotaError": "RESOURCE_EXHAUSTED"}`
+    error_code: serde_json::Map<String, serde_json::Value>,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MetaError {
+    message: Option<String>,
+    code: i64,
+    error_subcode: Option<i64>,
+}
+
+/// Map a Google Ads `errorCode` entry onto our codes
+pub fn map_google(kind: &str, value: &str) -> PlatformErrorCode {
+    match (kind, value) {
+        ("authenticationError", _) => PlatformErrorCode::AuthExpired,
+        ("authorizationError", "CUSTOMER_NOT_ENABLED") => PlatformErrorCode::AccountSuspended,
+        ("authorizationError", _) => PlatformErrorCode::PermissionDenied,
+        ("quotaError", "RESOURCE_EXHAUSTED") => PlatformErrorCode::QuotaExhausted,
+        ("quotaError", _) => PlatformErrorCode::RateLimited,
+        ("campaignBudgetError", "MONEY_AMOUNT_TOO_SMALL") => PlatformErrorCode::BudgetTooLow,
+        ("campaignError", "DUPLICATE_CAMPAIGN_NAME")
+        | ("adGroupError", "DUPLICATE_ADGROUP_NAME") => PlatformErrorCode::DuplicateName,
+        ("policyFindingError", _) | ("policyViolationError", _) => PlatformErrorCode::PolicyViolation,
+        ("mutateError", "RESOURCE_NOT_FOUND") => PlatformErrorCode::ResourceNotFound,
+        ("fieldError", _)
+        | ("rangeError", _)
+        | ("requestError", _)
+        | ("stringLengthError", _)
+        | ("campaignBudgetError", _) => PlatformErrorCode::InvalidField,
+        ("internalError", _) => PlatformErrorCode::PlatformUnavailable,
+        _ => PlatformErrorCode::UnknownPlatformError,
+    }
+}
+
+/// Map a Meta Graph API `code` / `error_subcode` pair onto our codes
+pub fn map_meta(code: i64, subcode: Option<i64>) -> PlatformErrorCode {
+    match (code, subcode) {
+        (190, _) | (102, _) => PlatformErrorCode::AuthExpired,
+        (10, _) | (200..=299, _) => PlatformErrorCode::PermissionDenied,
+        (4, _) | (17, _) | (32, _) | (613, _) | (80000..=80014, _) => PlatformErrorCode::RateLimited,
+        (368, _) => PlatformErrorCode::PolicyViolation,
+        (100, Some(33)) => PlatformErrorCode::ResourceNotFound,
+        (100, _) => PlatformErrorCode::InvalidField,
+        (1, _) | (2, _) => PlatformErrorCode::PlatformUnavailable,
+        _ => PlatformErrorCode::UnknownPlatformError,
+    }
+}
+
+/// Codes implied by the HTTP status alone, for bodies we cannot parse
+fn map_status(status: u16) -> PlatformErrorCode {
+    match status {
+        401 => PlatformErrorCode::AuthExpired,
+        403 => PlatformErrorCode::PermissionDenied,
+        404 => PlatformErrorCode::ResourceNotFound,
+        429 => PlatformErrorCode::RateLimited,
+        500..=599 => PlatformErrorCode::PlatformUnavailable,
+        _ => PlatformErrorCode::UnknownPlatformError,
+    }
+}
+
+fn parse_google(body: &str) -> Option<(PlatformErrorCode, String, String)> {
+    let status = serde_json::from_str::<ErrorEnvelope<GoogleStatus>>(body).ok()?.error;
+    let error = status.details.iter().flat_map(|d| &d.errors).next()?;
+    let (kind, value) = error.error_code.iter().next()?;
+    let value = value.as_str().unwrap_or_default();
+
+    Some((
+        map_google(kind, value),
+        format!("{}.{}", kind, value),
+        error.message.clone().or(status.message.clone()).unwrap_or_default(),
+    ))
+}
+
+fn parse_meta(body: &str) -> Option<(PlatformErrorCode, String, String)> {
+    let error = serde_json::from_str::<ErrorEnvelope<MetaError>>(body).ok()?.error;
+    let upstream_code = match error.error_subcode {
+        Some(subcode) => format!("{}/{}", error.code, subcode),
+        None => error.code.to_string(),
+    };
+
+    Some((
+        map_meta(error.code, error.error_subcode),
+        upstream_code,
+        error.message.unwrap_or_default(),
+    ))
+}
+
+/// Translate an error response relayed by the gateway
+pub fn translate(
+    platform: &Platform,
+    status: u16,
+    retry_after: Option<u64>,
+    body: &str,
+) -> PlatformError {
+    let parsed = match platform {
+        Platform::Google => parse_google(body),
+        Platform::Meta => parse_meta(body),
+        #[cfg(any(feature = "linkedin", feature = "microsoft"))]
+        _ => None,
+    };
+
+    let (code, upstream_code, message) = parsed.unwrap_or_else(|| {
+        (
+            map_status(status),
+            format!("http_{}", status),
+            body.chars().take(200).collect(),
+        )
+    });
+
+    PlatformError {
+        platform: platform.clone(),
+        code,
+        upstream_code,
+        message,
+        retry_after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_google_ads_failure_is_mapped() {
+        let body = r#"{"error":{"code":400,"message":"Request contains an invalid argument.","status":"INVALID_ARGUMENT","details":[{"errors":[{"errorCode":{"campaignBudgetError":"MONEY_AMOUNT_TOO_SMALL"},"message":"Too low."}]}]}}"#;
+
+        let error = translate(&Platform::Google, 400, None, body);
+
+        assert_eq!(error.code, PlatformErrorCode::BudgetTooLow);
+        assert_eq!(error.upstream_code, "campaignBudgetError.MONEY_AMOUNT_TOO_SMALL");
+        assert_eq!(error.message, "Too low.");
+    }
+
+    #[test]
+    fn test_meta_error_is_mapped_by_code_and_subcode() {
+        let body = r#"{"error":{"message":"Error validating access token","type":"OAuthException","code":190,"error_subcode":463,"fbtrace_id":"A1"}}"#;
+
+        let error = translate(&Platform::Meta, 400, None, body);
+
+        assert_eq!(error.code, PlatformErrorCode::AuthExpired);
+        assert_eq!(error.upstream_code, "190/463");
+        assert_eq!(map_meta(17, None), PlatformErrorCode::RateLimited);
+    }
+
+    #[test]
+    fn test_unparseable_body_falls_back_to_status() {
+        let error = translate(&Platform::Google, 503, Some(10), "<html>Service Unavailable</html>");
+
+        assert_eq!(error.code, PlatformErrorCode::PlatformUnavailable);
+        assert_eq!(error.upstream_code, "http_503");
+        assert_eq!(error.retry_after, Some(10));
+    }
+}
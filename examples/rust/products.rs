@@ -0,0 +1,214 @@
+//! # Product Metrics Example
+//!
+//! E-commerce clients manage shopping budgets product by product, so
+//! `GET /campaigns/{platform}/{id}/products` returns the metrics of each
+//! item a Google Shopping or Meta catalog sales campaign advertised:
+//! impressions, clicks, cost and ROAS, in one shape for both platforms.
+//!
+//! Products are keyed by the ID of their feed entry, so the same SKU lines
+//! up across platforms: Google reports it lowercased as `product_item_id`,
+//! sometimes prefixed with its channel, language and country
+//! (`online:pt:BR:SKU-123`); Meta reports the catalog's `retailer_id`.
+//! Both become the bare, lowercased ID (`sku-123`). Rows landing on the
+//! same product are combined, with CTR, CPA and ROAS recomputed.
+//!
+//! Only campaigns of type `SHOPPING` or with the `SALES` objective have
+//! products; others are rejected rather than answered with an empty list.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{
+    combine_metrics, AppState, Campaign, CampaignMetrics, CampaignObjective, CampaignType,
+    Platform,
+};
+use super::error::ApiError;
+use super::ids::CampaignId;
+use super::money::Currency;
+use super::normalization;
+
+/// Product report row as returned by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProductRow {
+    /// Google `product_item_id`, Meta `retailer_id`
+    #[serde(alias = "product_item_id", alias = "retailer_id")]
+    pub item_id: String,
+    /// Google `product_title`, Meta `product_name`
+    #[serde(default, alias = "product_title", alias = "product_name")]
+    pub title: Option<String>,
+    pub impressions: u64,
+    pub clicks: u64,
+    #[serde(default)]
+    pub conversions: u32,
+    /// Google `cost`, Meta `spend`
+    #[serde(alias = "spend")]
+    pub cost: f64,
+    #[serde(default)]
+    pub conversion_value: f64,
+}
+
+/// Metrics of one product
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProductMetrics {
+    /// Feed ID of the product, normalized
+    pub product_id: String,
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub metrics: CampaignMetrics,
+    /// Share of the campaign's cost spent on this product, from 0 to 1
+    pub share_of_cost: f64,
+}
+
+/// Response of `GET /campaigns/{platform}/{id}/products`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProductReport {
+    pub campaign_id: CampaignId,
+    pub platform: Platform,
+    pub currency: Currency,
+    /// Highest cost first
+    pub products: Vec<ProductMetrics>,
+}
+
+/// Feed ID of a product as reported by either platform, e.g. `sku-123`
+pub fn normalize_product_id(value: &str) -> String {
+    let value = value.trim();
+    // Google `channel:language:country:offer_id`; the offer ID may hold colons
+    let offer_id = match value.splitn(4, ':').collect::<Vec<_>>()[..] {
+        ["online" | "local", _, _, offer_id] => offer_id,
+        _ => value,
+    };
+    offer_id.to_lowercase()
+}
+
+/// Whether a campaign advertises products from a feed or catalog
+pub fn sells_products(campaign: &Campaign) -> bool {
+    campaign.campaign_type == Some(CampaignType::Shopping)
+        || campaign.objective == Some(CampaignObjective::Sales)
+}
+
+/// Metrics per product of normalized rows, highest cost first
+pub fn build_products(rows: &[ProductRow]) -> Vec<ProductMetrics> {
+    let mut by_product: BTreeMap<&str, (Option<&str>, Vec<CampaignMetrics>)> = BTreeMap::new();
+    for row in rows {
+        let (title, metrics) = by_product.entry(&row.item_id).or_default();
+        if title.is_none() {
+            *title = row.title.as_deref().filter(|t| !t.is_empty());
+        }
+        metrics.push(CampaignMetrics {
+            impressions: row.impressions,
+            clicks: row.clicks,
+            conversions: row.conversions,
+            cost: row.cost,
+            conversion_value: row.conversion_value,
+            ..Default::default()
+        });
+    }
+    let total_cost: f64 = rows.iter().map(|r| r.cost).sum();
+
+    let mut products: Vec<ProductMetrics> = by_product
+        .into_iter()
+        .map(|(product_id, (title, metrics))| {
+            let metrics = combine_metrics(&metrics);
+            ProductMetrics {
+                product_id: product_id.to_string(),
+                title: title.map(str::to_string),
+                share_of_cost: if total_cost > 0.0 { metrics.cost / total_cost } else { 0.0 },
+                metrics,
+            }
+        })
+        .collect();
+
+    // Stable sort keeps products with equal cost in ID order
+    products.sort_by(|a, b| b.metrics.cost.total_cmp(&a.metrics.cost));
+    products
+}
+
+/// GET /campaigns/{platform}/{id}/products - Metrics per advertised product
+pub async fn get_campaign_products(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<ProductReport>, ApiError> {
+    let connector = state
+        .connectors
+        .get(&platform)
+        .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+
+    let mut campaign = connector
+        .fetch_campaigns()
+        .await?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| ApiError::NotFound(format!("campaign {}", id)))?;
+    normalization::normalize_campaigns(&state, std::slice::from_mut(&mut campaign));
+    if !sells_products(&campaign) {
+        return Err(ApiError::Validation(format!(
+            "campaign {} is not a shopping or catalog sales campaign",
+            id
+        )));
+    }
+
+    let rows = connector.fetch_products(&id).await?;
+
+    Ok(Json(ProductReport {
+        currency: campaign.currency(),
+        products: build_products(&rows),
+        campaign_id: id,
+        platform,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_ids_match_across_feeds() {
+        let cases = [
+            ("online:pt:BR:SKU-123", "sku-123"),
+            ("local:pt:BR:sku-123", "sku-123"),
+            ("sku-123", "sku-123"),
+            (" SKU-123 ", "sku-123"),
+            ("online:pt:BR:kit:2", "kit:2"),
+            ("ref:2024:azul", "ref:2024:azul"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(normalize_product_id(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_rows_of_one_product_are_combined() {
+        let row = |item_id: &str, title: Option<&str>, clicks, cost, conversion_value| {
+            ProductRow {
+                item_id: item_id.to_string(),
+                title: title.map(str::to_string),
+                impressions: 1_000,
+                clicks,
+                cost,
+                conversion_value,
+                ..Default::default()
+            }
+        };
+        let rows = [
+            row("sku-1", None, 10, 20.0, 100.0),
+            row("sku-2", Some("Sofá"), 30, 10.0, 0.0),
+            row("sku-1", Some("Mesa"), 30, 30.0, 150.0),
+        ];
+
+        let products = build_products(&rows);
+
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].product_id, "sku-1");
+        assert_eq!(products[0].title.as_deref(), Some("Mesa"));
+        assert_eq!(products[0].metrics.clicks, 40);
+        assert_eq!(products[0].metrics.roas, 5.0);
+        assert!((products[0].share_of_cost - 50.0 / 60.0).abs() < 1e-9);
+        assert_eq!(products[1].metrics.roas, 0.0);
+    }
+}
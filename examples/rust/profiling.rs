@@ -0,0 +1,258 @@
+//! # Runtime Profiling Example
+//!
+//! Admin endpoints for diagnosing latency spikes under sync load:
+//!
+//! - `GET /admin/runtime` reports tokio runtime health: workers, alive
+//!   tasks, queue depths and the scheduling delay of a probe task. Built
+//!   with `RUSTFLAGS="--cfg tokio_unstable"` it adds per-worker poll
+//!   counts, mean poll times and busy ratios, and flags workers stuck in a
+//!   single poll during the sampling window (a blocking call on an async
+//!   worker).
+//! - `GET /admin/profile?seconds=10` samples CPU stacks for the given
+//!   duration and returns a flamegraph SVG. Needs the `profiling` feature
+//!   (`pprof` with its `flamegraph` feature); one profile runs at a time.
+//!
+//! Both are restricted to `X-Actor-Role: admin`. The manifest declares
+//! `tokio_unstable` under `[lints.rust]` `check-cfg`, so builds without it
+//! pass `-D warnings`.
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::error::ApiError;
+use super::freeze::{Actor, ActorRole};
+
+/// Window between the two runtime samples
+pub const SAMPLE_WINDOW: Duration = Duration::from_millis(250);
+
+/// Profile length without `?seconds=`
+pub const DEFAULT_PROFILE_SECS: u64 = 10;
+
+/// Longest profile accepted
+pub const MAX_PROFILE_SECS: u64 = 60;
+
+/// Stack samples per second
+pub const PROFILE_FREQUENCY: i32 = 99;
+
+fn require_admin(actor: &Actor) -> Result<(), ApiError> {
+    if actor.role != ActorRole::Admin {
+        return Err(ApiError::Forbidden("runtime diagnostics are restricted to admins".to_string()));
+    }
+    Ok(())
+}
+
+/// Activity of one worker thread during the sampling window
+#[cfg(tokio_unstable)]
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkerStats {
+    pub worker: usize,
+    pub polls: u64,
+    pub mean_poll_time_us: f64,
+    /// Share of the window spent polling tasks, 0-1
+    pub busy_ratio: f64,
+    pub local_queue_depth: usize,
+    /// Busy for the whole window without completing a poll
+    pub blocked: bool,
+}
+
+/// `GET /admin/runtime` response
+#[derive(Debug, Serialize, Clone)]
+pub struct RuntimeStats {
+    pub workers: usize,
+    pub alive_tasks: usize,
+    pub global_queue_depth: usize,
+    /// Time a freshly spawned task waited before its first poll; high
+    /// values mean every worker is busy or blocked
+    pub scheduling_delay_us: u64,
+    #[cfg(tokio_unstable)]
+    pub blocking_threads: usize,
+    #[cfg(tokio_unstable)]
+    pub idle_blocking_threads: usize,
+    #[cfg(tokio_unstable)]
+    pub blocking_queue_depth: usize,
+    #[cfg(tokio_unstable)]
+    pub worker_stats: Vec<WorkerStats>,
+    /// Workers flagged as `blocked`
+    #[cfg(tokio_unstable)]
+    pub blocked_workers: usize,
+}
+
+#[cfg(tokio_unstable)]
+fn worker_snapshot(metrics: &tokio::runtime::RuntimeMetrics) -> Vec<(u64, Duration)> {
+    (0..metrics.num_workers())
+        .map(|w| (metrics.worker_poll_count(w), metrics.worker_total_busy_duration(w)))
+        .collect()
+}
+
+/// Busy for at least this share of the window with no finished poll
+#[cfg(tokio_unstable)]
+const BLOCKED_BUSY_RATIO: f64 = 0.9;
+
+/// Sample the current runtime over `SAMPLE_WINDOW`
+pub async fn sample_runtime() -> RuntimeStats {
+    let handle = tokio::runtime::Handle::current();
+    let metrics = handle.metrics();
+
+    #[cfg(tokio_unstable)]
+    let before = worker_snapshot(&metrics);
+    let window_start = Instant::now();
+
+    let spawned = Instant::now();
+    let scheduling_delay = tokio::spawn(async move { spawned.elapsed() })
+        .await
+        .unwrap_or_default();
+    tokio::time::sleep(SAMPLE_WINDOW.saturating_sub(window_start.elapsed())).await;
+
+    #[cfg(tokio_unstable)]
+    let worker_stats: Vec<WorkerStats> = {
+        let window = window_start.elapsed();
+        worker_snapshot(&metrics)
+            .into_iter()
+            .zip(before)
+            .enumerate()
+            .map(|(worker, ((polls, busy), (polls_before, busy_before)))| {
+                let polls = polls.saturating_sub(polls_before);
+                let busy = busy.saturating_sub(busy_before);
+                let busy_ratio = busy.as_secs_f64() / window.as_secs_f64();
+                WorkerStats {
+                    worker,
+                    polls,
+                    mean_poll_time_us: metrics.worker_mean_poll_time(worker).as_secs_f64() * 1e6,
+                    busy_ratio: busy_ratio.min(1.0),
+                    local_queue_depth: metrics.worker_local_queue_depth(worker),
+                    blocked: polls == 0 && busy_ratio >= BLOCKED_BUSY_RATIO,
+                }
+            })
+            .collect()
+    };
+
+    RuntimeStats {
+        workers: metrics.num_workers(),
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+        scheduling_delay_us: scheduling_delay.as_micros() as u64,
+        #[cfg(tokio_unstable)]
+        blocking_threads: metrics.num_blocking_threads(),
+        #[cfg(tokio_unstable)]
+        idle_blocking_threads: metrics.num_idle_blocking_threads(),
+        #[cfg(tokio_unstable)]
+        blocking_queue_depth: metrics.blocking_queue_depth(),
+        #[cfg(tokio_unstable)]
+        blocked_workers: worker_stats.iter().filter(|w| w.blocked).count(),
+        #[cfg(tokio_unstable)]
+        worker_stats,
+    }
+}
+
+/// GET /admin/runtime - Tokio runtime health (admins only)
+pub async fn get_runtime_stats(actor: Actor) -> Result<Json<RuntimeStats>, ApiError> {
+    require_admin(&actor)?;
+    Ok(Json(sample_runtime().await))
+}
+
+/// Query parameters for `GET /admin/profile`
+#[derive(Debug, Deserialize, Default)]
+pub struct ProfileQuery {
+    pub seconds: Option<u64>,
+}
+
+impl ProfileQuery {
+    pub fn duration(&self) -> Result<Duration, ApiError> {
+        match self.seconds.unwrap_or(DEFAULT_PROFILE_SECS) {
+            seconds @ 1..=MAX_PROFILE_SECS => Ok(Duration::from_secs(seconds)),
+            _ => Err(ApiError::Validation(format!(
+                "seconds must be between 1 and {}",
+                MAX_PROFILE_SECS
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "profiling")]
+static PROFILING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Sample CPU stacks for `duration` and render a flamegraph SVG
+///
+/// Runs on a blocking thread: the sampler is driven by signals and the
+/// guard must stay alive for the whole window.
+#[cfg(feature = "profiling")]
+async fn cpu_flamegraph(duration: Duration) -> Result<Vec<u8>, ApiError> {
+    use std::sync::atomic::Ordering;
+
+    if PROFILING.swap(true, Ordering::SeqCst) {
+        return Err(ApiError::Conflict("a profile is already running".to_string()));
+    }
+
+    let result = tokio::task::spawn_blocking(move || {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(PROFILE_FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(|e| e.to_string())?;
+        std::thread::sleep(duration);
+
+        let report = guard.report().build().map_err(|e| e.to_string())?;
+        let mut svg = Vec::new();
+        report.flamegraph(&mut svg).map_err(|e| e.to_string())?;
+        Ok::<_, String>(svg)
+    })
+    .await;
+    PROFILING.store(false, Ordering::SeqCst);
+
+    match result {
+        Ok(Ok(svg)) => Ok(svg),
+        Ok(Err(e)) => Err(ApiError::Internal(format!("profiling failed: {}", e))),
+        Err(e) => Err(ApiError::Internal(format!("profiling task failed: {}", e))),
+    }
+}
+
+#[cfg(not(feature = "profiling"))]
+async fn cpu_flamegraph(_duration: Duration) -> Result<Vec<u8>, ApiError> {
+    Err(ApiError::NotSupported("CPU profiling without the `profiling` feature".to_string()))
+}
+
+/// GET /admin/profile - CPU flamegraph over `?seconds=` (admins only)
+pub async fn get_cpu_profile(
+    actor: Actor,
+    Query(query): Query<ProfileQuery>,
+) -> Result<Response, ApiError> {
+    require_admin(&actor)?;
+    let duration = query.duration()?;
+
+    tracing::info!(actor = %actor.id, seconds = duration.as_secs(), "cpu profile started");
+    let svg = cpu_flamegraph(duration).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static("image/svg+xml"))],
+        svg,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_runtime_sample_reports_workers() {
+        let stats = sample_runtime().await;
+
+        // `#[tokio::test]` runs a single-threaded runtime
+        assert_eq!(stats.workers, 1);
+        assert!(stats.alive_tasks <= 1);
+    }
+
+    #[test]
+    fn test_profile_duration_is_bounded() {
+        let query = |seconds| ProfileQuery { seconds };
+
+        assert_eq!(query(None).duration().unwrap(), Duration::from_secs(DEFAULT_PROFILE_SECS));
+        assert!(query(Some(0)).duration().is_err());
+        assert!(query(Some(MAX_PROFILE_SECS + 1)).duration().is_err());
+    }
+}
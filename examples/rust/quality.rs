@@ -0,0 +1,352 @@
+//! # Quality Diagnostics Example
+//!
+//! Google Ads rates each keyword with a 1-10 quality score and three
+//! components; Meta ranks each ad's quality, engagement rate and conversion
+//! rate against the ads competing for the same audience. Both are
+//! normalized into one `QualityDiagnostics` block per campaign, with an
+//! entry per ad group, requested with `GET /campaigns?include=quality`:
+//!
+//! - components map onto three shared signals: engagement (Google expected
+//!   CTR, Meta engagement rate ranking), relevance (ad relevance, quality
+//!   ranking) and conversion (landing page experience, conversion rate
+//!   ranking);
+//! - Meta's below-average percentiles (`BELOW_AVERAGE_10`, `_20`, `_35`)
+//!   all read as below average;
+//! - the rating of a campaign or ad group is the one carrying the most
+//!   spend among its keywords or ads;
+//! - a keyword or ad is low quality with a quality score of at most
+//!   `LOW_QUALITY_SCORE` or any below-average signal, and
+//!   `low_quality_spend` totals what those spent, comparable across
+//!   platforms.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::call_budget;
+use super::campaign_aggregator::Campaign;
+use super::connectors::{ConnectorError, ConnectorRegistry};
+use super::ids::{AdGroupId, CampaignId, GlobalCampaignId};
+use super::keywords::LOW_QUALITY_SCORE;
+
+/// Rating of a quality signal against competing ads
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityRating {
+    BelowAverage,
+    Average,
+    AboveAverage,
+}
+
+impl QualityRating {
+    /// Google Ads quality score bucket; `UNSPECIFIED` and `UNKNOWN` have no rating
+    pub fn from_google(bucket: &str) -> Option<Self> {
+        match bucket {
+            "BELOW_AVERAGE" => Some(QualityRating::BelowAverage),
+            "AVERAGE" => Some(QualityRating::Average),
+            "ABOVE_AVERAGE" => Some(QualityRating::AboveAverage),
+            _ => None,
+        }
+    }
+
+    /// Meta ranking, e.g. `BELOW_AVERAGE_20`; `UNKNOWN` has no rating
+    pub fn from_meta(ranking: &str) -> Option<Self> {
+        match ranking {
+            "AVERAGE" => Some(QualityRating::Average),
+            "ABOVE_AVERAGE" => Some(QualityRating::AboveAverage),
+            r if r.starts_with("BELOW_AVERAGE") => Some(QualityRating::BelowAverage),
+            _ => None,
+        }
+    }
+}
+
+/// Keyword quality as returned by the Google gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleKeywordQuality {
+    pub campaign_id: CampaignId,
+    pub ad_group_id: AdGroupId,
+    pub criterion_id: String,
+    pub quality_score: Option<u8>,
+    /// Expected CTR
+    pub search_predicted_ctr: Option<String>,
+    /// Ad relevance
+    pub creative_quality_score: Option<String>,
+    /// Landing page experience
+    pub post_click_quality_score: Option<String>,
+    #[serde(default)]
+    pub cost: f64,
+}
+
+/// Ad relevance diagnostics as returned by the Meta gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaAdQuality {
+    pub campaign_id: CampaignId,
+    pub adset_id: AdGroupId,
+    pub ad_id: String,
+    pub quality_ranking: Option<String>,
+    pub engagement_rate_ranking: Option<String>,
+    pub conversion_rate_ranking: Option<String>,
+    #[serde(default)]
+    pub spend: f64,
+}
+
+/// Quality signals of one keyword or ad
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QualitySignal {
+    pub campaign_id: CampaignId,
+    pub ad_group_id: AdGroupId,
+    /// Google only
+    pub quality_score: Option<u8>,
+    pub engagement: Option<QualityRating>,
+    pub relevance: Option<QualityRating>,
+    pub conversion: Option<QualityRating>,
+    pub spend: f64,
+}
+
+impl QualitySignal {
+    pub fn is_low_quality(&self) -> bool {
+        self.quality_score.is_some_and(|q| q <= LOW_QUALITY_SCORE)
+            || [self.engagement, self.relevance, self.conversion]
+                .contains(&Some(QualityRating::BelowAverage))
+    }
+}
+
+pub fn normalize_google(raw: &GoogleKeywordQuality) -> QualitySignal {
+    let rating = |bucket: &Option<String>| bucket.as_deref().and_then(QualityRating::from_google);
+    QualitySignal {
+        campaign_id: raw.campaign_id.clone(),
+        ad_group_id: raw.ad_group_id.clone(),
+        quality_score: raw.quality_score,
+        engagement: rating(&raw.search_predicted_ctr),
+        relevance: rating(&raw.creative_quality_score),
+        conversion: rating(&raw.post_click_quality_score),
+        spend: raw.cost,
+    }
+}
+
+pub fn normalize_meta(raw: &MetaAdQuality) -> QualitySignal {
+    let rating = |ranking: &Option<String>| ranking.as_deref().and_then(QualityRating::from_meta);
+    QualitySignal {
+        campaign_id: raw.campaign_id.clone(),
+        ad_group_id: raw.adset_id.clone(),
+        quality_score: None,
+        engagement: rating(&raw.engagement_rate_ranking),
+        relevance: rating(&raw.quality_ranking),
+        conversion: rating(&raw.conversion_rate_ranking),
+        spend: raw.spend,
+    }
+}
+
+/// Quality of a set of keywords or ads
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct QualityScores {
+    /// Spend-weighted quality score, Google only
+    pub quality_score: Option<f64>,
+    pub engagement: Option<QualityRating>,
+    pub relevance: Option<QualityRating>,
+    pub conversion: Option<QualityRating>,
+    pub spend: f64,
+    /// Spend of low-quality keywords or ads
+    pub low_quality_spend: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdGroupQuality {
+    pub ad_group_id: AdGroupId,
+    #[serde(flatten)]
+    pub scores: QualityScores,
+}
+
+/// Quality diagnostics of a campaign
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct QualityDiagnostics {
+    #[serde(flatten)]
+    pub scores: QualityScores,
+    /// Most low-quality spend first
+    pub ad_groups: Vec<AdGroupQuality>,
+}
+
+/// Rating carrying the most spend; by count when nothing spent, the worse
+/// one on ties
+fn dominant(
+    signals: &[&QualitySignal],
+    rating: impl Fn(&QualitySignal) -> Option<QualityRating>,
+) -> Option<QualityRating> {
+    let mut weights: BTreeMap<QualityRating, (f64, usize)> = BTreeMap::new();
+    for signal in signals {
+        if let Some(r) = rating(signal) {
+            let weight = weights.entry(r).or_default();
+            weight.0 += signal.spend;
+            weight.1 += 1;
+        }
+    }
+
+    weights
+        .into_iter()
+        .rev()
+        .max_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+        .map(|(r, _)| r)
+}
+
+fn scores(signals: &[&QualitySignal]) -> QualityScores {
+    let scored: Vec<(f64, f64)> = signals
+        .iter()
+        .filter_map(|s| s.quality_score.map(|q| (q as f64, s.spend)))
+        .collect();
+    let scored_spend: f64 = scored.iter().map(|(_, spend)| spend).sum();
+    let quality_score = if scored.is_empty() {
+        None
+    } else if scored_spend > 0.0 {
+        Some(scored.iter().map(|(q, spend)| q * spend).sum::<f64>() / scored_spend)
+    } else {
+        Some(scored.iter().map(|(q, _)| q).sum::<f64>() / scored.len() as f64)
+    };
+
+    QualityScores {
+        quality_score,
+        engagement: dominant(signals, |s| s.engagement),
+        relevance: dominant(signals, |s| s.relevance),
+        conversion: dominant(signals, |s| s.conversion),
+        spend: signals.iter().map(|s| s.spend).sum(),
+        low_quality_spend: signals.iter().filter(|s| s.is_low_quality()).map(|s| s.spend).sum(),
+    }
+}
+
+/// Aggregate keyword or ad signals into diagnostics per campaign
+pub fn diagnostics(signals: &[QualitySignal]) -> HashMap<CampaignId, QualityDiagnostics> {
+    let mut by_campaign: HashMap<&CampaignId, BTreeMap<&AdGroupId, Vec<&QualitySignal>>> =
+        HashMap::new();
+    for signal in signals {
+        by_campaign
+            .entry(&signal.campaign_id)
+            .or_default()
+            .entry(&signal.ad_group_id)
+            .or_default()
+            .push(signal);
+    }
+
+    by_campaign
+        .into_iter()
+        .map(|(campaign_id, ad_groups)| {
+            let all: Vec<&QualitySignal> = ad_groups.values().flatten().copied().collect();
+            let mut ad_groups: Vec<AdGroupQuality> = ad_groups
+                .into_iter()
+                .map(|(ad_group_id, signals)| AdGroupQuality {
+                    ad_group_id: ad_group_id.clone(),
+                    scores: scores(&signals),
+                })
+                .collect();
+            ad_groups.sort_by(|a, b| {
+                b.scores.low_quality_spend.total_cmp(&a.scores.low_quality_spend)
+            });
+
+            let diagnostics = QualityDiagnostics {
+                scores: scores(&all),
+                ad_groups,
+            };
+            (campaign_id.clone(), diagnostics)
+        })
+        .collect()
+}
+
+/// Fill in the `quality` block of every campaign, one gateway call per
+/// platform
+///
+/// Platforms without quality diagnostics are skipped; other failures are
+/// logged and leave the block empty.
+pub async fn attach_quality(registry: &ConnectorRegistry, campaigns: &mut [Campaign]) {
+    let mut ids_by_platform: HashMap<_, Vec<CampaignId>> = HashMap::new();
+    for campaign in campaigns.iter() {
+        ids_by_platform
+            .entry(campaign.platform.clone())
+            .or_default()
+            .push(campaign.id.clone());
+    }
+
+    if call_budget::charge(ids_by_platform.len()).is_err() {
+        return;
+    }
+
+    let fetches = ids_by_platform.iter().filter_map(|(platform, ids)| {
+        let connector = registry.get(platform)?;
+        Some(async move { (platform.clone(), connector.fetch_quality(ids).await) })
+    });
+
+    let mut quality = HashMap::new();
+    for (platform, result) in futures::future::join_all(fetches).await {
+        match result {
+            Ok(by_campaign) => {
+                for (campaign_id, q) in by_campaign {
+                    quality.insert(GlobalCampaignId::new(platform.clone(), campaign_id), q);
+                }
+            }
+            Err(ConnectorError::NotSupported(_)) => {}
+            Err(e) => tracing::warn!(platform = platform.as_str(), error = %e, "quality fetch failed"),
+        }
+    }
+
+    for campaign in campaigns.iter_mut() {
+        campaign.quality = quality.remove(&campaign.global_id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn google(ad_group: &str, score: u8, ctr: &str, cost: f64) -> QualitySignal {
+        normalize_google(&GoogleKeywordQuality {
+            campaign_id: "g1".into(),
+            ad_group_id: ad_group.into(),
+            criterion_id: format!("{}-{}", ad_group, score),
+            quality_score: Some(score),
+            search_predicted_ctr: Some(ctr.to_string()),
+            creative_quality_score: Some("AVERAGE".to_string()),
+            post_click_quality_score: Some("UNSPECIFIED".to_string()),
+            cost,
+        })
+    }
+
+    #[test]
+    fn test_meta_rankings_map_onto_shared_signals() {
+        let signal = normalize_meta(&MetaAdQuality {
+            campaign_id: "m1".into(),
+            adset_id: "as1".into(),
+            ad_id: "ad1".to_string(),
+            quality_ranking: Some("ABOVE_AVERAGE".to_string()),
+            engagement_rate_ranking: Some("AVERAGE".to_string()),
+            conversion_rate_ranking: Some("BELOW_AVERAGE_35".to_string()),
+            spend: 40.0,
+        });
+
+        assert_eq!(signal.relevance, Some(QualityRating::AboveAverage));
+        assert_eq!(signal.engagement, Some(QualityRating::Average));
+        assert_eq!(signal.conversion, Some(QualityRating::BelowAverage));
+        assert!(signal.is_low_quality());
+        assert_eq!(QualityRating::from_meta("UNKNOWN"), None);
+    }
+
+    #[test]
+    fn test_diagnostics_weight_by_spend_and_rank_ad_groups() {
+        let signals = vec![
+            google("ag1", 8, "ABOVE_AVERAGE", 30.0),
+            google("ag1", 7, "AVERAGE", 10.0),
+            google("ag2", 3, "BELOW_AVERAGE", 20.0),
+            google("ag2", 6, "AVERAGE", 0.0),
+        ];
+
+        let diagnostics = &diagnostics(&signals)[&CampaignId::from("g1")];
+
+        let scores = &diagnostics.scores;
+        assert_eq!(scores.quality_score, Some((8.0 * 30.0 + 7.0 * 10.0 + 3.0 * 20.0) / 60.0));
+        assert_eq!(scores.engagement, Some(QualityRating::AboveAverage));
+        assert_eq!(scores.relevance, Some(QualityRating::Average));
+        assert_eq!(scores.conversion, None);
+        assert_eq!(scores.spend, 60.0);
+        assert_eq!(scores.low_quality_spend, 20.0);
+
+        let ad_groups: Vec<&AdGroupId> =
+            diagnostics.ad_groups.iter().map(|a| &a.ad_group_id).collect();
+        assert_eq!(ad_groups, ["ag2", "ag1"]);
+        assert_eq!(diagnostics.ad_groups[0].scores.engagement, Some(QualityRating::BelowAverage));
+    }
+}
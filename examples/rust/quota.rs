@@ -0,0 +1,849 @@
+//! # Quota Warnings Example
+//!
+//! Platform API quotas belong to our developer tokens and are shared by
+//! every tenant: once Google Ads' daily operations run out, every
+//! dashboard breaks until midnight. Clients are warned before that
+//! happens instead of discovering it through 429s:
+//!
+//! - every connector operation is counted per platform and UTC day, and
+//!   compared with `quota.daily_calls` (e.g. `google = 15000`);
+//! - a platform past `quota.warn_ratio` of its quota (80% by default), or
+//!   a request past that share of its upstream call budget, adds a warning
+//!   to the response: JSON object bodies get a `warnings` member, and each
+//!   warning is also sent as an `X-Quota-Warning` header so list responses
+//!   carry them too;
+//! - usage the platforms report in response headers (Meta's
+//!   `X-App-Usage`, `X-Business-Use-Case-Usage` and `X-Ad-Account-Usage`,
+//!   or `X-RateLimit-Limit`/`-Remaining` relayed by the gateway) is kept
+//!   for `REPORTED_USAGE_TTL_SECS`;
+//! - past `quota.throttle_ratio` of the daily calls or of the reported
+//!   usage, upstream calls are delayed, up to `quota.max_throttle_ms` once
+//!   the quota is used up;
+//! - after a rate limit, calls wait until its `Retry-After` (or
+//!   `DEFAULT_BACKOFF_SECS`) has passed, and fail right away as
+//!   `rate_limited` when that is longer than `quota.max_throttle_ms`;
+//! - retries are calls too: the reauth connector's retry is metered and
+//!   throttled again;
+//! - `GET /quota` shows the calls used and remaining per platform today,
+//!   and `GET /internal/quotas` (admins) the same report for operators.
+
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{Json, Response},
+};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::accounts::AccountNode;
+use super::auction::AuctionInsights;
+use super::audiences::AudienceInfo;
+use super::bidding::{Bidding, BiddingChange};
+use super::breakdowns::{Dimension, SegmentRow};
+use super::call_budget;
+use super::campaign_aggregator::{AppState, Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::config::QuotaConfig;
+use super::connectors::{ConnectorError, PlatformConnector};
+use super::conversions::ConversionActionRow;
+use super::hydration::{Ad, AdGroup};
+use super::ids::{AdGroupId, CampaignId};
+use super::launch::LaunchPayload;
+use super::negatives::{Exclusion, ExclusionUpdate};
+use super::platform_errors::{PlatformError, PlatformErrorCode};
+use super::products::ProductRow;
+use super::quality::QualityDiagnostics;
+use super::spec::{AdGroupSpec, CampaignSpec};
+use super::targeting::Targeting;
+
+pub const QUOTA_WARNING_HEADER: &str = "x-quota-warning";
+
+/// How long usage reported in response headers counts; Meta reports
+/// usage over a rolling hour
+pub const REPORTED_USAGE_TTL_SECS: i64 = 300;
+
+/// Backoff after a rate limit that came without `Retry-After`
+pub const DEFAULT_BACKOFF_SECS: u64 = 30;
+
+/// Usage fields of Meta's usage headers, in percent
+const META_USAGE_FIELDS: &[&str] =
+    &["call_count", "total_cputime", "total_time", "acc_id_util_pct"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaWarningCode {
+    /// A platform's daily API quota is running out
+    PlatformQuotaLow,
+    /// The request used most of its upstream call budget
+    CallBudgetLow,
+}
+
+impl QuotaWarningCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaWarningCode::PlatformQuotaLow => "platform_quota_low",
+            QuotaWarningCode::CallBudgetLow => "call_budget_low",
+        }
+    }
+}
+
+/// Warning added to responses
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuotaWarning {
+    pub code: QuotaWarningCode,
+    pub platform: Option<Platform>,
+    pub used: u64,
+    pub limit: u64,
+    pub message: String,
+}
+
+/// Quota usage a platform reported in response headers
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReportedUsage {
+    /// Highest usage reported, in percent of the platform's limit
+    pub percent: f64,
+    /// Header it was reported in
+    pub header: String,
+    pub reported_at: DateTime<Utc>,
+}
+
+/// Today's usage of one platform's quota
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlatformQuota {
+    pub platform: Platform,
+    pub calls_used: u64,
+    /// `None` when no quota is configured for the platform
+    pub daily_limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Past `quota.warn_ratio` of the daily limit
+    pub warning: bool,
+    /// Usage reported by the platform in the last `REPORTED_USAGE_TTL_SECS`
+    pub reported_usage: Option<ReportedUsage>,
+    /// Until when calls back off after a rate limit
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Delay added to each call; `quota.max_throttle_ms` while calls fail
+    /// right away
+    pub throttle_ms: u64,
+}
+
+/// `GET /quota` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuotaReport {
+    pub date: NaiveDate,
+    /// When the daily counts start over (next UTC midnight)
+    pub resets_at: DateTime<Utc>,
+    pub warn_ratio: f64,
+    pub throttle_ratio: f64,
+    pub platforms: Vec<PlatformQuota>,
+}
+
+#[derive(Default)]
+struct DailyCalls {
+    date: Option<NaiveDate>,
+    calls: HashMap<Platform, u64>,
+}
+
+/// What a platform's responses said about its limits
+#[derive(Default)]
+struct UpstreamSignals {
+    reported: Option<ReportedUsage>,
+    retry_at: Option<DateTime<Utc>>,
+}
+
+/// Highest usage in Meta's usage header JSON
+fn max_usage(value: &serde_json::Value) -> Option<f64> {
+    META_USAGE_FIELDS
+        .iter()
+        .filter_map(|field| value.get(field)?.as_f64())
+        .reduce(f64::max)
+}
+
+/// Highest usage percentage in upstream response headers, with the
+/// header it was found in
+pub fn parse_usage_headers(headers: &HeaderMap) -> Option<(f64, &'static str)> {
+    let json = |name: &str| -> Option<serde_json::Value> {
+        serde_json::from_slice(headers.get(name)?.as_bytes()).ok()
+    };
+    let number = |name: &str| -> Option<f64> {
+        headers.get(name)?.to_str().ok()?.trim().parse().ok()
+    };
+
+    let mut reported = Vec::new();
+    for header in ["x-app-usage", "x-ad-account-usage"] {
+        if let Some(usage) = json(header).as_ref().and_then(max_usage) {
+            reported.push((usage, header));
+        }
+    }
+    // Business ID -> usage per use case
+    if let Some(by_business) = json("x-business-use-case-usage") {
+        let usage = by_business
+            .as_object()
+            .into_iter()
+            .flat_map(|businesses| businesses.values())
+            .filter_map(serde_json::Value::as_array)
+            .flatten()
+            .filter_map(max_usage)
+            .reduce(f64::max);
+        if let Some(usage) = usage {
+            reported.push((usage, "x-business-use-case-usage"));
+        }
+    }
+    if let (Some(limit), Some(remaining)) =
+        (number("x-ratelimit-limit"), number("x-ratelimit-remaining"))
+    {
+        if limit > 0.0 {
+            reported.push(((limit - remaining) / limit * 100.0, "x-ratelimit-remaining"));
+        }
+    }
+
+    reported
+        .into_iter()
+        .filter(|(usage, _)| usage.is_finite())
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+/// Upstream calls per platform for the current UTC day, and the limits
+/// the platforms reported
+#[derive(Default)]
+pub struct QuotaTracker {
+    config: QuotaConfig,
+    usage: Mutex<DailyCalls>,
+    signals: Mutex<HashMap<Platform, UpstreamSignals>>,
+}
+
+impl QuotaTracker {
+    pub fn new(config: QuotaConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Count one call to `platform`
+    pub fn record(&self, platform: &Platform, now: DateTime<Utc>) {
+        let mut usage = self.usage.lock().unwrap();
+        let today = now.date_naive();
+        if usage.date != Some(today) {
+            *usage = DailyCalls {
+                date: Some(today),
+                calls: HashMap::new(),
+            };
+        }
+        *usage.calls.entry(platform.clone()).or_default() += 1;
+    }
+
+    /// Calls made to `platform` today
+    pub fn calls(&self, platform: &Platform, now: DateTime<Utc>) -> u64 {
+        let usage = self.usage.lock().unwrap();
+        if usage.date != Some(now.date_naive()) {
+            return 0;
+        }
+        usage.calls.get(platform).copied().unwrap_or_default()
+    }
+
+    /// Note the usage reported in an upstream response's headers
+    pub fn observe(&self, platform: &Platform, headers: &HeaderMap, now: DateTime<Utc>) {
+        let Some((percent, header)) = parse_usage_headers(headers) else {
+            return;
+        };
+        let mut signals = self.signals.lock().unwrap();
+        signals.entry(platform.clone()).or_default().reported = Some(ReportedUsage {
+            percent,
+            header: header.to_string(),
+            reported_at: now,
+        });
+    }
+
+    /// Back off from `platform` for `retry_after` seconds after a rate limit
+    pub fn record_rate_limit(
+        &self,
+        platform: &Platform,
+        retry_after: Option<u64>,
+        now: DateTime<Utc>,
+    ) {
+        let secs = retry_after.unwrap_or(DEFAULT_BACKOFF_SECS);
+        let retry_at = now + chrono::Duration::seconds(secs.try_into().unwrap_or(i64::MAX));
+        let mut signals = self.signals.lock().unwrap();
+        let signal = signals.entry(platform.clone()).or_default();
+        // A shorter Retry-After on a parallel call does not cut the backoff
+        signal.retry_at = signal.retry_at.max(Some(retry_at));
+    }
+
+    fn reported_usage(&self, platform: &Platform, now: DateTime<Utc>) -> Option<ReportedUsage> {
+        let signals = self.signals.lock().unwrap();
+        let reported = signals.get(platform)?.reported.as_ref()?;
+        let fresh = now - reported.reported_at < chrono::Duration::seconds(REPORTED_USAGE_TTL_SECS);
+        fresh.then(|| reported.clone())
+    }
+
+    fn retry_at(&self, platform: &Platform, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let signals = self.signals.lock().unwrap();
+        signals.get(platform)?.retry_at.filter(|retry_at| *retry_at > now)
+    }
+
+    /// Highest share of `platform`'s quota known to be used: today's calls
+    /// against the daily limit, or the usage the platform reported
+    pub fn usage_ratio(&self, platform: &Platform, now: DateTime<Utc>) -> Option<f64> {
+        let counted = self
+            .config
+            .daily_calls_for(platform)
+            .filter(|limit| *limit > 0)
+            .map(|limit| self.calls(platform, now) as f64 / limit as f64);
+        let reported = self.reported_usage(platform, now).map(|u| u.percent / 100.0);
+
+        counted.into_iter().chain(reported).reduce(f64::max)
+    }
+
+    /// Delay before the next call to `platform`
+    ///
+    /// Fails when the platform asked to back off for longer than
+    /// `quota.max_throttle_ms`.
+    pub fn throttle(
+        &self,
+        platform: &Platform,
+        now: DateTime<Utc>,
+    ) -> Result<Duration, PlatformError> {
+        let max_delay = self.config.max_throttle();
+
+        if let Some(retry_at) = self.retry_at(platform, now) {
+            let wait = (retry_at - now).to_std().unwrap_or_default();
+            if wait > max_delay {
+                return Err(PlatformError {
+                    platform: platform.clone(),
+                    code: PlatformErrorCode::RateLimited,
+                    upstream_code: "backoff".to_string(),
+                    message: format!("backing off until {}", retry_at.to_rfc3339()),
+                    retry_after: Some(wait.as_secs_f64().ceil() as u64),
+                });
+            }
+            return Ok(wait);
+        }
+
+        let Some(ratio) = self.usage_ratio(platform, now) else {
+            return Ok(Duration::ZERO);
+        };
+        let throttle_ratio = self.config.throttle_ratio;
+        if ratio < throttle_ratio {
+            return Ok(Duration::ZERO);
+        }
+        let share = if throttle_ratio >= 1.0 {
+            1.0
+        } else {
+            ((ratio - throttle_ratio) / (1.0 - throttle_ratio)).min(1.0)
+        };
+        Ok(max_delay.mul_f64(share))
+    }
+
+    pub fn platform_quota(&self, platform: &Platform, now: DateTime<Utc>) -> PlatformQuota {
+        let calls_used = self.calls(platform, now);
+        let daily_limit = self.config.daily_calls_for(platform);
+        let throttle = self.throttle(platform, now).unwrap_or(self.config.max_throttle());
+
+        PlatformQuota {
+            platform: platform.clone(),
+            calls_used,
+            daily_limit,
+            remaining: daily_limit.map(|limit| limit.saturating_sub(calls_used)),
+            warning: daily_limit
+                .is_some_and(|limit| calls_used as f64 >= limit as f64 * self.config.warn_ratio),
+            reported_usage: self.reported_usage(platform, now),
+            retry_at: self.retry_at(platform, now),
+            throttle_ms: throttle.as_millis().try_into().unwrap_or(u64::MAX),
+        }
+    }
+
+    pub fn report(&self, platforms: &[Platform], now: DateTime<Utc>) -> QuotaReport {
+        let date = now.date_naive();
+        let midnight = date
+            .checked_add_days(Days::new(1))
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc())
+            .unwrap_or(now);
+
+        QuotaReport {
+            date,
+            resets_at: midnight,
+            warn_ratio: self.config.warn_ratio,
+            throttle_ratio: self.config.throttle_ratio,
+            platforms: platforms.iter().map(|p| self.platform_quota(p, now)).collect(),
+        }
+    }
+
+    /// Warnings for platforms past the warning ratio, and for `budget`
+    /// when the request used that share of it
+    pub fn warnings(
+        &self,
+        now: DateTime<Utc>,
+        budget: Option<&call_budget::CallBudget>,
+    ) -> Vec<QuotaWarning> {
+        let platforms: Vec<Platform> = {
+            let usage = self.usage.lock().unwrap();
+            usage.calls.keys().cloned().collect()
+        };
+
+        let mut warnings: Vec<QuotaWarning> = platforms
+            .iter()
+            .map(|p| self.platform_quota(p, now))
+            .filter(|q| q.warning)
+            .map(|q| {
+                let limit = q.daily_limit.unwrap_or_default();
+                QuotaWarning {
+                    code: QuotaWarningCode::PlatformQuotaLow,
+                    message: format!(
+                        "{} API quota: {} of {} daily calls used",
+                        q.platform.as_str(),
+                        q.calls_used,
+                        limit
+                    ),
+                    platform: Some(q.platform),
+                    used: q.calls_used,
+                    limit,
+                }
+            })
+            .collect();
+        warnings.sort_by_key(|w| w.platform.as_ref().map(Platform::as_str));
+
+        if let Some(budget) = budget {
+            let (used, limit) = (budget.used(), budget.limit());
+            if !budget.is_exceeded() && used as f64 >= limit as f64 * self.config.warn_ratio {
+                warnings.push(QuotaWarning {
+                    code: QuotaWarningCode::CallBudgetLow,
+                    platform: None,
+                    used: used as u64,
+                    limit: limit as u64,
+                    message: format!(
+                        "request used {} of its {} upstream calls; narrow the query",
+                        used, limit
+                    ),
+                });
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Connector decorator counting every operation against the quota and
+/// throttling it as the quota runs out
+pub struct MeteredConnector {
+    pub inner: Arc<dyn PlatformConnector>,
+    pub quota: Arc<QuotaTracker>,
+}
+
+impl MeteredConnector {
+    /// Wait out the platform's throttle, then count the call
+    async fn admit(&self) -> Result<(), ConnectorError> {
+        let platform = self.inner.platform();
+        let delay = self.quota.throttle(&platform, Utc::now())?;
+        if !delay.is_zero() {
+            let delay_ms = delay.as_millis() as u64;
+            tracing::debug!(platform = platform.as_str(), delay_ms, "throttling upstream call");
+            tokio::time::sleep(delay).await;
+        }
+
+        self.quota.record(&platform, Utc::now());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for MeteredConnector {
+    fn platform(&self) -> Platform {
+        self.inner.platform()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_campaigns().await
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_metrics(campaign_id).await
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.mutate_status(campaign_id, status).await
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.health_check().await
+    }
+
+    async fn fetch_ad_groups(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<Vec<AdGroup>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_ad_groups(campaign_ids).await
+    }
+
+    async fn fetch_audiences_by_id(
+        &self,
+        audience_ids: &[String],
+    ) -> Result<Vec<AudienceInfo>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_audiences_by_id(audience_ids).await
+    }
+
+    async fn mutate_budget(
+        &self,
+        campaign_id: &CampaignId,
+        daily_budget: f64,
+    ) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.mutate_budget(campaign_id, daily_budget).await
+    }
+
+    async fn fetch_ads(&self, ad_group_ids: &[AdGroupId]) -> Result<Vec<Ad>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_ads(ad_group_ids).await
+    }
+
+    async fn rename_campaign(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.rename_campaign(campaign_id, name).await
+    }
+
+    async fn create_campaign(&self, campaign: &CampaignSpec) -> Result<CampaignId, ConnectorError> {
+        self.admit().await?;
+        self.inner.create_campaign(campaign).await
+    }
+
+    async fn launch_campaign(&self, payload: &LaunchPayload) -> Result<CampaignId, ConnectorError> {
+        self.admit().await?;
+        self.inner.launch_campaign(payload).await
+    }
+
+    async fn create_ad_group(
+        &self,
+        campaign_id: &CampaignId,
+        ad_group: &AdGroupSpec,
+    ) -> Result<AdGroupId, ConnectorError> {
+        self.admit().await?;
+        self.inner.create_ad_group(campaign_id, ad_group).await
+    }
+
+    async fn update_ad_group(&self, ad_group: &AdGroupSpec) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.update_ad_group(ad_group).await
+    }
+
+    async fn fetch_targeting(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Targeting>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_targeting(campaign_ids).await
+    }
+
+    async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+        self.inner.refresh_credentials().await
+    }
+
+    async fn fetch_bidding(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Bidding>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_bidding(campaign_ids).await
+    }
+
+    async fn mutate_bidding(
+        &self,
+        campaign_id: &CampaignId,
+        change: &BiddingChange,
+    ) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.mutate_bidding(campaign_id, change).await
+    }
+
+    async fn fetch_quality(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, QualityDiagnostics>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_quality(campaign_ids).await
+    }
+
+    async fn fetch_auction_insights(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, AuctionInsights>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_auction_insights(campaign_ids).await
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        campaign_id: &CampaignId,
+        dimension: Dimension,
+    ) -> Result<Vec<SegmentRow>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_breakdown(campaign_id, dimension).await
+    }
+
+    async fn fetch_products(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ProductRow>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_products(campaign_id).await
+    }
+
+    async fn add_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.add_label(campaign_id, name).await
+    }
+
+    async fn remove_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.remove_label(campaign_id, name).await
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_raw_campaign(campaign_id).await
+    }
+
+    async fn fetch_changed_campaigns(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Campaign>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_changed_campaigns(since).await
+    }
+
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_accounts().await
+    }
+
+    async fn fetch_conversion_actions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ConversionActionRow>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_conversion_actions(campaign_id).await
+    }
+
+    async fn fetch_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<Exclusion>, ConnectorError> {
+        self.admit().await?;
+        self.inner.fetch_exclusions(campaign_id).await
+    }
+
+    async fn update_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+        update: &ExclusionUpdate,
+    ) -> Result<(), ConnectorError> {
+        self.admit().await?;
+        self.inner.update_exclusions(campaign_id, update).await
+    }
+}
+
+/// Add `warnings` to a JSON object body; other bodies are left as is
+fn with_warnings(body: &[u8], warnings: &[QuotaWarning]) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    value
+        .as_object_mut()?
+        .insert("warnings".to_string(), serde_json::to_value(warnings).ok()?);
+    serde_json::to_vec(&value).ok()
+}
+
+/// Middleware adding quota warnings to responses
+///
+/// Runs inside the call budget middleware so the request's budget is
+/// still in scope. Bodies carrying an ETag are not rewritten, as the tag
+/// would no longer match.
+pub async fn attach_quota_warnings(
+    State(quota): State<Arc<QuotaTracker>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(req).await;
+    let warnings = quota.warnings(Utc::now(), call_budget::current().as_ref());
+    if warnings.is_empty() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    for warning in &warnings {
+        let value = format!("{}: {}", warning.code.as_str(), warning.message);
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            parts.headers.append(QUOTA_WARNING_HEADER, value);
+        }
+    }
+
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes() == b"application/json");
+    if !is_json || parts.status != StatusCode::OK || parts.headers.contains_key(header::ETAG) {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not buffer response for quota warnings");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    match with_warnings(&bytes, &warnings) {
+        Some(body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// GET /quota, GET /internal/quotas - Today's calls, remaining quota and
+/// throttling per platform
+pub async fn get_quota(State(state): State<Arc<AppState>>) -> Json<QuotaReport> {
+    let platforms: Vec<Platform> = state.connectors.all().iter().map(|c| c.platform()).collect();
+
+    Json(state.quota.report(&platforms, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn tracker(google_limit: u64) -> QuotaTracker {
+        let mut config = QuotaConfig::default();
+        config.daily_calls.insert("google".to_string(), google_limit);
+        QuotaTracker::new(config)
+    }
+
+    #[test]
+    fn test_warns_past_ratio_and_resets_daily() {
+        let quota = tracker(10);
+        let now = Utc::now();
+        for _ in 0..7 {
+            quota.record(&Platform::Google, now);
+        }
+        quota.record(&Platform::Meta, now);
+        assert!(quota.warnings(now, None).is_empty());
+
+        quota.record(&Platform::Google, now);
+        let warnings = quota.warnings(now, None);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, QuotaWarningCode::PlatformQuotaLow);
+        assert_eq!(warnings[0].used, 8);
+
+        let report = quota.report(&[Platform::Google, Platform::Meta], now);
+        assert_eq!(report.platforms[0].remaining, Some(2));
+        // No quota configured for Meta
+        assert_eq!(report.platforms[1].remaining, None);
+        assert_eq!(report.platforms[1].calls_used, 1);
+
+        let tomorrow = now + chrono::Duration::days(1);
+        assert_eq!(quota.calls(&Platform::Google, tomorrow), 0);
+        assert!(quota.warnings(tomorrow, None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_object_bodies_get_warnings_member() {
+        let quota = Arc::new(tracker(1));
+        quota.record(&Platform::Google, Utc::now());
+
+        let router = Router::new()
+            .route("/summary", get(|| async { Json(serde_json::json!({ "cost": 1.0 })) }))
+            .route("/list", get(|| async { Json(serde_json::json!([1, 2])) }))
+            .layer(middleware::from_fn_with_state(quota, attach_quota_warnings));
+
+        let get_json = |path: &'static str| {
+            let router = router.clone();
+            async move {
+                let request = Request::get(path).body(Body::empty()).unwrap();
+                let response = router.oneshot(request).await.unwrap();
+                assert!(response.headers().contains_key(QUOTA_WARNING_HEADER));
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let summary = get_json("/summary").await;
+        assert_eq!(summary["cost"], 1.0);
+        assert_eq!(summary["warnings"][0]["code"], "platform_quota_low");
+
+        assert_eq!(get_json("/list").await, serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_reads_highest_usage_from_platform_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-app-usage",
+            HeaderValue::from_static(r#"{"call_count":28,"total_cputime":25,"total_time":25}"#),
+        );
+        headers.insert(
+            "x-business-use-case-usage",
+            HeaderValue::from_static(
+                r#"{"1234":[{"type":"ads_management","call_count":95,"total_time":12}]}"#,
+            ),
+        );
+        assert_eq!(parse_usage_headers(&headers), Some((95.0, "x-business-use-case-usage")));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("1000"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("250"));
+        assert_eq!(parse_usage_headers(&headers), Some((75.0, "x-ratelimit-remaining")));
+
+        assert_eq!(parse_usage_headers(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_throttles_near_the_limit_and_backs_off_after_rate_limits() {
+        let quota = tracker(100);
+        let now = Utc::now();
+        let max = quota.config.max_throttle();
+        for _ in 0..80 {
+            quota.record(&Platform::Google, now);
+        }
+        assert_eq!(quota.throttle(&Platform::Google, now).unwrap(), Duration::ZERO);
+
+        // Meta reports 95% used: halfway from the default 0.9 to the limit
+        let mut headers = HeaderMap::new();
+        headers.insert("x-app-usage", HeaderValue::from_static(r#"{"call_count":95}"#));
+        quota.observe(&Platform::Meta, &headers, now);
+        let delay = quota.throttle(&Platform::Meta, now).unwrap();
+        assert!(delay.abs_diff(max / 2) < Duration::from_millis(1));
+        let stale = now + chrono::Duration::seconds(REPORTED_USAGE_TTL_SECS);
+        assert_eq!(quota.throttle(&Platform::Meta, stale).unwrap(), Duration::ZERO);
+
+        // A short Retry-After is waited out, a long one fails right away
+        quota.record_rate_limit(&Platform::Google, Some(1), now);
+        assert_eq!(quota.throttle(&Platform::Google, now).unwrap(), Duration::from_secs(1));
+        quota.record_rate_limit(&Platform::Google, Some(60), now);
+        let error = quota.throttle(&Platform::Google, now).unwrap_err();
+        assert_eq!(error.code, PlatformErrorCode::RateLimited);
+        assert_eq!(error.retry_after, Some(60));
+
+        let report = quota.platform_quota(&Platform::Google, now);
+        assert_eq!(report.throttle_ms, 2000);
+        let later = now + chrono::Duration::seconds(61);
+        assert_eq!(quota.platform_quota(&Platform::Google, later).retry_at, None);
+    }
+}
@@ -0,0 +1,234 @@
+//! # Raw Platform Payloads Example
+//!
+//! When our numbers disagree with a platform's UI, the first question is
+//! whether the platform sent something else or the mapping lost it.
+//! `GET /campaigns/{platform}/{id}/raw` answers it by returning the
+//! campaign as the platform returned it next to the normalized `Campaign`
+//! the API serves.
+//!
+//! With `raw_capture.enabled`, every payload read this way is also kept for
+//! `raw_capture.retention_days`, so a mapping bug can be investigated after
+//! the platform's data changed. Captures live in memory and are listed by
+//! `GET /internal/raw-captures`.
+//!
+//! Payloads are redacted before they are returned or kept: values of keys
+//! naming credentials or contact details, and of `raw_capture.redact_keys`,
+//! are replaced with `"[redacted]"` at any depth.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{AppState, Campaign, Platform};
+use super::error::ApiError;
+use super::ids::CampaignId;
+use super::normalization;
+
+/// Replaces every redacted value
+pub const REDACTED: &str = "[redacted]";
+
+/// Keys containing any of these, in any case, are always redacted
+const SENSITIVE_KEY_PARTS: &[&str] =
+    &["token", "secret", "password", "authorization", "email", "phone"];
+
+/// Whether values under `key` are redacted; `extra_keys` are matched
+/// whole, ignoring case
+pub fn is_sensitive_key(key: &str, extra_keys: &[String]) -> bool {
+    let key = key.to_lowercase();
+    SENSITIVE_KEY_PARTS.iter().any(|part| key.contains(part))
+        || extra_keys.iter().any(|k| k.to_lowercase() == key)
+}
+
+/// Redact sensitive values of `value` in place, at any depth
+///
+/// `extra_keys` are matched whole, ignoring case.
+pub fn redact(value: &mut serde_json::Value, extra_keys: &[String]) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if is_sensitive_key(key, extra_keys) {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact(field, extra_keys);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact(item, extra_keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Response of `GET /campaigns/{platform}/{id}/raw`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RawCampaign {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub fetched_at: DateTime<Utc>,
+    /// The platform's response, redacted
+    pub raw: serde_json::Value,
+    /// `None` when the campaign is not in the platform's campaign list
+    pub campaign: Option<Campaign>,
+    /// Whether the payload was kept, see `raw_capture.enabled`
+    pub captured: bool,
+}
+
+/// Payload kept by the capture mode
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RawCapture {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub captured_at: DateTime<Utc>,
+    pub raw: serde_json::Value,
+}
+
+/// Captured payloads, oldest first
+#[derive(Default)]
+pub struct RawCaptureStore {
+    captures: RwLock<Vec<RawCapture>>,
+}
+
+impl RawCaptureStore {
+    /// Keep `capture`, dropping captures older than `retention_days`
+    pub fn record(&self, capture: RawCapture, retention_days: u32) {
+        let cutoff = capture.captured_at - Duration::days(retention_days.into());
+        let mut captures = self.captures.write().unwrap();
+        captures.retain(|c| c.captured_at > cutoff);
+        captures.push(capture);
+    }
+
+    /// Captures younger than `retention_days`, newest first
+    pub fn list(
+        &self,
+        query: &RawCaptureQuery,
+        retention_days: u32,
+        now: DateTime<Utc>,
+    ) -> Vec<RawCapture> {
+        let cutoff = now - Duration::days(retention_days.into());
+        self.captures
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|c| c.captured_at > cutoff)
+            .filter(|c| query.platform.iter().all(|p| *p == c.platform))
+            .filter(|c| query.campaign_id.iter().all(|id| *id == c.campaign_id))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RawCaptureQuery {
+    pub platform: Option<Platform>,
+    pub campaign_id: Option<CampaignId>,
+}
+
+/// GET /campaigns/{platform}/{id}/raw - The platform's payload next to
+/// the normalized campaign
+pub async fn get_raw_campaign(
+    State(state): State<Arc<AppState>>,
+    Path((platform, id)): Path<(Platform, CampaignId)>,
+) -> Result<Json<RawCampaign>, ApiError> {
+    let connector = state
+        .connectors
+        .get(&platform)
+        .ok_or_else(|| ApiError::NotSupported(platform.as_str().to_string()))?;
+    let settings = &state.config.raw_capture;
+
+    let mut raw = connector.fetch_raw_campaign(&id).await?;
+    let fetched_at = Utc::now();
+    redact(&mut raw, &settings.redact_keys);
+    // The campaign as served, so a stale cache shows up as a discrepancy too
+    let mut campaign = connector.fetch_campaigns().await?.into_iter().find(|c| c.id == id);
+    normalization::normalize_campaigns(&state, campaign.as_mut_slice());
+
+    if settings.enabled {
+        let capture = RawCapture {
+            platform: platform.clone(),
+            campaign_id: id.clone(),
+            captured_at: fetched_at,
+            raw: raw.clone(),
+        };
+        state.raw_captures.record(capture, settings.retention_days);
+    }
+
+    Ok(Json(RawCampaign {
+        platform,
+        campaign_id: id,
+        fetched_at,
+        raw,
+        campaign,
+        captured: settings.enabled,
+    }))
+}
+
+/// GET /internal/raw-captures - Kept payloads, newest first
+pub async fn list_raw_captures(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RawCaptureQuery>,
+) -> Result<Json<Vec<RawCapture>>, ApiError> {
+    let retention_days = state.config.raw_capture.retention_days;
+
+    Ok(Json(state.raw_captures.list(&query, retention_days, Utc::now())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sensitive_values_are_redacted_at_any_depth() {
+        let mut raw = json!({
+            "id": "120200",
+            "access_token": "EAAB...",
+            "creative": {"contact_email": "a@b.com", "title": "Sale"},
+            "promoted_objects": [{"Customer_Id": 42, "pixel_id": "99"}],
+        });
+
+        redact(&mut raw, &["customer_id".to_string()]);
+
+        assert_eq!(
+            raw,
+            json!({
+                "id": "120200",
+                "access_token": REDACTED,
+                "creative": {"contact_email": REDACTED, "title": "Sale"},
+                "promoted_objects": [{"Customer_Id": REDACTED, "pixel_id": "99"}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_captures_expire_after_retention() {
+        let store = RawCaptureStore::default();
+        let now = Utc::now();
+        let capture = |id: &str, days_ago: i64| RawCapture {
+            platform: Platform::Meta,
+            campaign_id: CampaignId::from(id),
+            captured_at: now - Duration::days(days_ago),
+            raw: json!({}),
+        };
+        store.record(capture("old", 10), 7);
+        store.record(capture("m1", 3), 7);
+        store.record(capture("m2", 0), 7);
+
+        let all = store.list(&RawCaptureQuery::default(), 7, now);
+        let ids: Vec<&str> = all.iter().map(|c| c.campaign_id.as_str()).collect();
+        assert_eq!(ids, ["m2", "m1"]);
+
+        let query = RawCaptureQuery {
+            platform: Some(Platform::Google),
+            campaign_id: None,
+        };
+        assert!(store.list(&query, 7, now).is_empty());
+    }
+}
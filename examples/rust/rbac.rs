@@ -0,0 +1,428 @@
+//! # Role-Based Access Control Example
+//!
+//! The authenticating proxy in front of the API identifies the caller
+//! (`X-Actor`, `X-Actor-Role`, `X-Tenant-Id`); this layer decides what the
+//! caller may do. Each actor holds a role, globally or per tenant:
+//!
+//! - `viewer` reads: every `GET` endpoint;
+//! - `analyst` also runs analyses and configures reporting: spec diffs and
+//!   plans, experiments, reports, segments, alert rules, automation rule
+//!   previews and backfills;
+//! - `manager` also changes what runs on the ad platforms: bulk changes
+//!   and rollbacks, bidding, spec applies, automation rules, segment
+//!   syncs, naming and grouping rules, insertion orders and CRM uploads.
+//!
+//! `/admin/*`, `/internal/*`, `/rbac/*` and freeze window changes need
+//! `X-Actor-Role: admin`, which also passes every other check. A
+//! tenant-specific assignment wins over a global one; actors without any
+//! get `rbac.default_role` (`viewer`), or are rejected when
+//! `rbac.require_assignment` is set.
+//! `/healthz` and `/readyz` stay open for the orchestrator.
+//!
+//! The check is the `Authorized` extractor, run for every route by
+//! `middleware::from_extractor_with_state`. Admins manage assignments
+//! through `GET`/`PUT /rbac/assignments` and
+//! `DELETE /rbac/assignments/{actor}?tenant=`.
+
+use axum::{
+    extract::{FromRequestParts, MatchedPath, Path, Query, State},
+    http::{request::Parts, Method},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+use super::freeze::{Actor, ActorRole};
+use super::versioning;
+
+/// Routes served to anyone
+pub const PUBLIC_ROUTES: &[&str] = &["/healthz", "/readyz"];
+
+/// Write routes open to analysts: they compute, or configure reporting,
+/// without changing anything on the ad platforms
+pub const ANALYST_ROUTES: &[&str] = &[
+    "/accounts/spec/diff",
+    "/accounts/spec/plan",
+    "/experiments",
+    "/reports",
+    "/segments",
+    "/alerts/rules",
+    "/automation/rules/preview",
+    "/backfills",
+    "/backfills/{id}/resume",
+    "/refresh",
+    "/campaigns/{id}/{campaign_id}/annotations",
+    "/campaigns/{id}/{campaign_id}/annotations/{annotation_id}",
+];
+
+/// Routes whose every method needs an admin
+pub const ADMIN_PREFIXES: &[&str] = &["/admin/", "/internal/", "/rbac/"];
+
+/// Roles in increasing order of access
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Analyst,
+    Manager,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Analyst => "analyst",
+            Role::Manager => "manager",
+        }
+    }
+
+    pub fn grants(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Read => true,
+            Permission::Analyze => *self >= Role::Analyst,
+            Permission::Mutate => *self >= Role::Manager,
+            Permission::Admin => false,
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "viewer" => Ok(Role::Viewer),
+            "analyst" => Ok(Role::Analyst),
+            "manager" => Ok(Role::Manager),
+            other => Err(format!("unknown role {:?}, expected viewer, analyst or manager", other)),
+        }
+    }
+}
+
+/// What a route needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Read,
+    Analyze,
+    Mutate,
+    Admin,
+}
+
+impl Permission {
+    fn describe(&self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::Analyze => "run analyses",
+            Permission::Mutate => "change campaigns",
+            Permission::Admin => "administer",
+        }
+    }
+}
+
+/// Permission needed for `method` on the route template `route`; `None`
+/// for public routes
+pub fn route_permission(method: &Method, route: &str) -> Option<Permission> {
+    // `/v1/campaigns` needs what `/campaigns` needs
+    let route = versioning::unversioned(route);
+    if PUBLIC_ROUTES.contains(&route) {
+        return None;
+    }
+    if ADMIN_PREFIXES.iter().any(|prefix| route.starts_with(prefix)) {
+        return Some(Permission::Admin);
+    }
+
+    let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    Some(if read {
+        Permission::Read
+    } else if route.starts_with("/freeze-windows") {
+        Permission::Admin
+    } else if ANALYST_ROUTES.contains(&route) {
+        Permission::Analyze
+    } else {
+        Permission::Mutate
+    })
+}
+
+/// Role of an actor, for one tenant or for all of them
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoleAssignment {
+    pub actor: String,
+    /// `None` applies to every tenant without an assignment of its own
+    pub tenant: Option<String>,
+    pub role: Role,
+    pub assigned_by: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// Request body for `PUT /rbac/assignments`
+#[derive(Debug, Deserialize)]
+pub struct AssignRole {
+    pub actor: String,
+    pub tenant: Option<String>,
+    pub role: Role,
+}
+
+/// Query parameters for `DELETE /rbac/assignments/{actor}`
+#[derive(Debug, Deserialize, Default)]
+pub struct RevokeQuery {
+    pub tenant: Option<String>,
+}
+
+/// Role assignments by actor and tenant
+#[derive(Default)]
+pub struct RoleStore {
+    assignments: RwLock<HashMap<(String, Option<String>), RoleAssignment>>,
+}
+
+impl RoleStore {
+    /// Add or replace the assignment of `assignment.actor` for its tenant
+    pub fn assign(&self, assignment: RoleAssignment) {
+        let key = (assignment.actor.clone(), assignment.tenant.clone());
+        self.assignments.write().unwrap().insert(key, assignment);
+    }
+
+    pub fn revoke(&self, actor: &str, tenant: Option<&str>) -> Option<RoleAssignment> {
+        let key = (actor.to_string(), tenant.map(str::to_string));
+        self.assignments.write().unwrap().remove(&key)
+    }
+
+    /// Assignments sorted by actor, global one first
+    pub fn list(&self) -> Vec<RoleAssignment> {
+        let mut assignments: Vec<_> = self.assignments.read().unwrap().values().cloned().collect();
+        assignments.sort_by(|a, b| (&a.actor, &a.tenant).cmp(&(&b.actor, &b.tenant)));
+        assignments
+    }
+
+    /// Role of `actor` in `tenant`, falling back to its global assignment
+    pub fn role_for(&self, actor: &str, tenant: Option<&str>) -> Option<Role> {
+        let assignments = self.assignments.read().unwrap();
+        let lookup = |tenant: Option<&str>| {
+            assignments
+                .get(&(actor.to_string(), tenant.map(str::to_string)))
+                .map(|a| a.role)
+        };
+
+        tenant.and_then(|t| lookup(Some(t))).or_else(|| lookup(None))
+    }
+
+    pub fn persist(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.list())?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Add assignments previously written by `persist`
+    pub fn load(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let loaded: Vec<RoleAssignment> = serde_json::from_slice(&bytes)?;
+
+        for assignment in loaded {
+            self.assign(assignment);
+        }
+        Ok(())
+    }
+}
+
+/// Caller allowed to use the matched route
+#[derive(Debug, Clone)]
+pub struct Authorized {
+    pub actor: Actor,
+    /// `None` for admins and public routes
+    pub role: Option<Role>,
+}
+
+impl FromRequestParts<Arc<AppState>> for Authorized {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let actor = Actor::from_headers(&parts.headers);
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| parts.uri.path().to_string());
+
+        let Some(permission) = route_permission(&parts.method, &route) else {
+            return Ok(Self { actor, role: None });
+        };
+        if actor.role == ActorRole::Admin {
+            return Ok(Self { actor, role: None });
+        }
+        if permission == Permission::Admin {
+            return Err(ApiError::Forbidden(format!("{} is restricted to admins", route)));
+        }
+
+        let rbac = &state.config.rbac;
+        let role = state
+            .roles
+            .role_for(&actor.id, actor.tenant.as_deref())
+            .or((!rbac.require_assignment).then_some(rbac.default_role));
+        match role {
+            Some(role) if role.grants(permission) => Ok(Self {
+                actor,
+                role: Some(role),
+            }),
+            Some(role) => Err(ApiError::Forbidden(format!(
+                "role {} cannot {} ({} {})",
+                role.as_str(),
+                permission.describe(),
+                parts.method,
+                route
+            ))),
+            None if actor.id == "anonymous" => Err(ApiError::Unauthorized),
+            None => Err(ApiError::Forbidden(format!("no role assigned to {}", actor.id))),
+        }
+    }
+}
+
+fn persist(state: &AppState) {
+    if let Some(path) = &state.config.rbac.assignments_path {
+        if let Err(e) = state.roles.persist(path) {
+            tracing::error!(path = %path.display(), error = %e, "role assignments not persisted");
+        }
+    }
+}
+
+/// GET /rbac/assignments - Every role assignment (admins only)
+pub async fn list_role_assignments(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<RoleAssignment>>, ApiError> {
+    Ok(Json(state.roles.list()))
+}
+
+/// PUT /rbac/assignments - Assign a role to an actor (admins only)
+pub async fn assign_role(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Json(body): Json<AssignRole>,
+) -> Result<Json<RoleAssignment>, ApiError> {
+    if body.actor.trim().is_empty() {
+        return Err(ApiError::Validation("actor must not be empty".to_string()));
+    }
+
+    let assignment = RoleAssignment {
+        actor: body.actor,
+        tenant: body.tenant,
+        role: body.role,
+        assigned_by: actor.id,
+        assigned_at: Utc::now(),
+    };
+    state.roles.assign(assignment.clone());
+    persist(&state);
+
+    Ok(Json(assignment))
+}
+
+/// DELETE /rbac/assignments/{actor}?tenant= - Revoke a role (admins only)
+pub async fn revoke_role(
+    State(state): State<Arc<AppState>>,
+    Path(actor): Path<String>,
+    Query(query): Query<RevokeQuery>,
+) -> Result<Json<RoleAssignment>, ApiError> {
+    let revoked = state
+        .roles
+        .revoke(&actor, query.tenant.as_deref())
+        .ok_or_else(|| ApiError::NotFound(format!("role assignment of {}", actor)))?;
+    persist(&state);
+
+    Ok(Json(revoked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::config::AppConfig;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn assignment(actor: &str, tenant: Option<&str>, role: Role) -> RoleAssignment {
+        RoleAssignment {
+            actor: actor.to_string(),
+            tenant: tenant.map(str::to_string),
+            role,
+            assigned_by: "admin".to_string(),
+            assigned_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_route_permissions() {
+        assert_eq!(route_permission(&Method::GET, "/healthz"), None);
+        assert_eq!(route_permission(&Method::GET, "/campaigns"), Some(Permission::Read));
+        assert_eq!(
+            route_permission(&Method::POST, "/accounts/spec/plan"),
+            Some(Permission::Analyze)
+        );
+        assert_eq!(route_permission(&Method::POST, "/campaigns/bulk"), Some(Permission::Mutate));
+        assert_eq!(route_permission(&Method::POST, "/v1/campaigns/bulk"), Some(Permission::Mutate));
+        assert_eq!(route_permission(&Method::GET, "/admin/runtime"), Some(Permission::Admin));
+        assert_eq!(route_permission(&Method::GET, "/internal/quotas"), Some(Permission::Admin));
+        assert_eq!(
+            route_permission(&Method::DELETE, "/freeze-windows/{id}"),
+            Some(Permission::Admin)
+        );
+    }
+
+    #[test]
+    fn test_tenant_assignment_wins_over_global() {
+        let roles = RoleStore::default();
+        roles.assign(assignment("alice", None, Role::Viewer));
+        roles.assign(assignment("alice", Some("acme"), Role::Manager));
+
+        assert_eq!(roles.role_for("alice", Some("acme")), Some(Role::Manager));
+        assert_eq!(roles.role_for("alice", Some("globex")), Some(Role::Viewer));
+        assert_eq!(roles.role_for("alice", None), Some(Role::Viewer));
+        assert_eq!(roles.role_for("bob", Some("acme")), None);
+    }
+
+    async fn status(router: &Router, request: axum::http::request::Builder) -> StatusCode {
+        let request = request.body(Body::empty()).unwrap();
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_viewers_read_but_cannot_mutate() {
+        let mut config = AppConfig::default();
+        config.gateway.service_token = "test".to_string();
+        let state = Arc::new(AppState::from_config(config).unwrap());
+        state.roles.assign(assignment("carol", Some("acme"), Role::Manager));
+
+        let router = Router::new()
+            .route("/campaigns", get(|| async { "ok" }))
+            .route("/campaigns/bulk", post(|| async { "ok" }))
+            .route_layer(middleware::from_extractor_with_state::<Authorized, _>(state.clone()))
+            .with_state(state);
+
+        let viewer = |request: axum::http::request::Builder| request.header("x-actor", "dave");
+        assert_eq!(status(&router, viewer(Request::get("/campaigns"))).await, StatusCode::OK);
+        assert_eq!(
+            status(&router, viewer(Request::post("/campaigns/bulk"))).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let manager = Request::post("/campaigns/bulk")
+            .header("x-actor", "carol")
+            .header("x-tenant-id", "acme");
+        assert_eq!(status(&router, manager).await, StatusCode::OK);
+
+        let admin = Request::post("/campaigns/bulk")
+            .header("x-actor", "root")
+            .header("x-actor-role", "admin");
+        assert_eq!(status(&router, admin).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,469 @@
+//! # Re-authentication Example
+//!
+//! Platform OAuth tokens expire or get revoked between syncs. Every
+//! registered connector is wrapped in a `ReauthConnector`: when a call
+//! fails with `auth_expired`, it refreshes the platform token and retries
+//! the call once. Only the retry's outcome is surfaced.
+//!
+//! Calls of a tenant connected to the platform (see `credentials`) are
+//! made with the tenant's access token, refreshed by the credential
+//! manager; other calls rely on the gateway, asked to refresh its own.
+//!
+//! Every refresh attempt produces a credential health event. A failed
+//! refresh means the account owner has to reconnect the platform, so it
+//! is logged at error level and listed by `GET /credentials/health`.
+
+use async_trait::async_trait;
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use super::accounts::AccountNode;
+use super::auction::AuctionInsights;
+use super::audiences::AudienceInfo;
+use super::bidding::{Bidding, BiddingChange};
+use super::breakdowns::{Dimension, SegmentRow};
+use super::campaign_aggregator::{AppState, Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::connectors::{ConnectorError, PlatformConnector};
+use super::conversions::ConversionActionRow;
+use super::credentials::{self, CredentialManager};
+use super::error::ApiError;
+use super::hydration::{Ad, AdGroup};
+use super::ids::{AdGroupId, CampaignId};
+use super::launch::LaunchPayload;
+use super::negatives::{Exclusion, ExclusionUpdate};
+use super::platform_errors::PlatformErrorCode;
+use super::products::ProductRow;
+use super::quality::QualityDiagnostics;
+use super::spec::{AdGroupSpec, CampaignSpec};
+use super::targeting::Targeting;
+use super::timing;
+
+/// Events kept in memory; older ones are dropped
+pub const MAX_CREDENTIAL_EVENTS: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStatus {
+    /// Token refreshed, the call was retried
+    Refreshed,
+    /// Refresh failed; the platform must be reconnected
+    RefreshFailed,
+}
+
+/// Outcome of a token refresh attempt
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialHealthEvent {
+    pub platform: Platform,
+    /// Tenant whose connection was refreshed; `None` for the gateway's
+    /// own credentials
+    pub tenant: Option<String>,
+    pub status: CredentialStatus,
+    /// Refresh error, for `refresh_failed`
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Recent credential health events
+#[derive(Default)]
+pub struct CredentialHealth {
+    events: RwLock<Vec<CredentialHealthEvent>>,
+}
+
+impl CredentialHealth {
+    pub fn record(&self, event: CredentialHealthEvent) {
+        match event.status {
+            CredentialStatus::Refreshed => tracing::info!(
+                platform = event.platform.as_str(),
+                tenant = event.tenant.as_deref(),
+                "platform token refreshed"
+            ),
+            CredentialStatus::RefreshFailed => tracing::error!(
+                platform = event.platform.as_str(),
+                tenant = event.tenant.as_deref(),
+                error = event.error.as_deref().unwrap_or_default(),
+                "platform token refresh failed, reconnect required"
+            ),
+        }
+
+        let mut events = self.events.write().unwrap();
+        events.push(event);
+        if events.len() > MAX_CREDENTIAL_EVENTS {
+            let excess = events.len() - MAX_CREDENTIAL_EVENTS;
+            events.drain(..excess);
+        }
+    }
+
+    /// Events, most recent first
+    pub fn events(&self) -> Vec<CredentialHealthEvent> {
+        let mut events = self.events.read().unwrap().clone();
+        events.reverse();
+        events
+    }
+}
+
+fn is_auth_expired(error: &ConnectorError) -> bool {
+    matches!(error, ConnectorError::Platform(e) if e.code == PlatformErrorCode::AuthExpired)
+}
+
+/// Connector decorator refreshing expired tokens and retrying once
+pub struct ReauthConnector {
+    pub inner: Arc<dyn PlatformConnector>,
+    pub health: Arc<CredentialHealth>,
+    /// Tenants' own connections to the platform
+    pub credentials: Arc<CredentialManager>,
+    /// Concurrent calls failing together trigger one refresh at a time
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl ReauthConnector {
+    pub fn new(
+        inner: Arc<dyn PlatformConnector>,
+        health: Arc<CredentialHealth>,
+        credentials: Arc<CredentialManager>,
+    ) -> Self {
+        Self {
+            inner,
+            health,
+            credentials,
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Run `call`; on `auth_expired`, refresh the token and run it again
+    async fn with_reauth<T, F, Fut>(&self, call: F) -> Result<T, ConnectorError>
+    where
+        F: Fn() -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, ConnectorError>> + Send,
+        T: Send,
+    {
+        let platform = self.inner.platform();
+        let tenant = credentials::tenant();
+        let token = match &tenant {
+            Some(tenant) => self
+                .credentials
+                .access_token(tenant, &platform)
+                .await
+                .map_err(|e| e.into_connector_error(&platform))?,
+            None => None,
+        };
+
+        match credentials::with_access_token(token.clone(), call()).await {
+            Err(e) if is_auth_expired(&e) => {
+                let _guard = self.refresh_lock.lock().await;
+                // The tenant's token if it was used, the gateway's otherwise
+                let (tenant, refreshed) = match (tenant, token) {
+                    (Some(tenant), Some(_)) => {
+                        let refresh = self.credentials.refresh(&tenant, &platform);
+                        let refreshed = timing::timed("auth", refresh).await;
+                        (Some(tenant), refreshed.map(Some).map_err(|e| e.to_string()))
+                    }
+                    _ => {
+                        let refresh = self.inner.refresh_credentials();
+                        let refreshed = timing::timed("auth", refresh).await;
+                        (None, refreshed.map(|()| None).map_err(|e| e.to_string()))
+                    }
+                };
+
+                self.health.record(CredentialHealthEvent {
+                    platform,
+                    tenant,
+                    status: match refreshed {
+                        Ok(_) => CredentialStatus::Refreshed,
+                        Err(_) => CredentialStatus::RefreshFailed,
+                    },
+                    error: refreshed.as_ref().err().cloned(),
+                    at: Utc::now(),
+                });
+
+                match refreshed {
+                    Ok(token) => credentials::with_access_token(token, call()).await,
+                    // The original auth error tells the client what to do
+                    Err(_) => Err(e),
+                }
+            }
+            result => result,
+        }
+    }
+}
+
+#[async_trait]
+impl PlatformConnector for ReauthConnector {
+    fn platform(&self) -> Platform {
+        self.inner.platform()
+    }
+
+    async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_campaigns()).await
+    }
+
+    async fn fetch_metrics(&self, campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_metrics(campaign_id)).await
+    }
+
+    async fn mutate_status(
+        &self,
+        campaign_id: &CampaignId,
+        status: CampaignStatus,
+    ) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.mutate_status(campaign_id, status.clone())).await
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.health_check()).await
+    }
+
+    async fn fetch_ad_groups(&self, campaign_ids: &[CampaignId]) -> Result<Vec<AdGroup>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_ad_groups(campaign_ids)).await
+    }
+
+    async fn fetch_audiences_by_id(
+        &self,
+        audience_ids: &[String],
+    ) -> Result<Vec<AudienceInfo>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_audiences_by_id(audience_ids)).await
+    }
+
+    async fn mutate_budget(&self, campaign_id: &CampaignId, daily_budget: f64) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.mutate_budget(campaign_id, daily_budget)).await
+    }
+
+    async fn fetch_ads(&self, ad_group_ids: &[AdGroupId]) -> Result<Vec<Ad>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_ads(ad_group_ids)).await
+    }
+
+    async fn rename_campaign(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.rename_campaign(campaign_id, name)).await
+    }
+
+    async fn create_campaign(&self, campaign: &CampaignSpec) -> Result<CampaignId, ConnectorError> {
+        self.with_reauth(|| self.inner.create_campaign(campaign)).await
+    }
+
+    async fn launch_campaign(&self, payload: &LaunchPayload) -> Result<CampaignId, ConnectorError> {
+        self.with_reauth(|| self.inner.launch_campaign(payload)).await
+    }
+
+    async fn create_ad_group(
+        &self,
+        campaign_id: &CampaignId,
+        ad_group: &AdGroupSpec,
+    ) -> Result<AdGroupId, ConnectorError> {
+        self.with_reauth(|| self.inner.create_ad_group(campaign_id, ad_group)).await
+    }
+
+    async fn update_ad_group(&self, ad_group: &AdGroupSpec) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.update_ad_group(ad_group)).await
+    }
+
+    async fn fetch_targeting(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Targeting>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_targeting(campaign_ids)).await
+    }
+
+    async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+        self.inner.refresh_credentials().await
+    }
+
+    async fn fetch_bidding(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, Bidding>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_bidding(campaign_ids)).await
+    }
+
+    async fn mutate_bidding(
+        &self,
+        campaign_id: &CampaignId,
+        change: &BiddingChange,
+    ) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.mutate_bidding(campaign_id, change)).await
+    }
+
+    async fn fetch_quality(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, QualityDiagnostics>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_quality(campaign_ids)).await
+    }
+
+    async fn fetch_auction_insights(
+        &self,
+        campaign_ids: &[CampaignId],
+    ) -> Result<HashMap<CampaignId, AuctionInsights>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_auction_insights(campaign_ids)).await
+    }
+
+    async fn fetch_breakdown(
+        &self,
+        campaign_id: &CampaignId,
+        dimension: Dimension,
+    ) -> Result<Vec<SegmentRow>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_breakdown(campaign_id, dimension)).await
+    }
+
+    async fn fetch_products(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ProductRow>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_products(campaign_id)).await
+    }
+
+    async fn add_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.add_label(campaign_id, name)).await
+    }
+
+    async fn remove_label(&self, campaign_id: &CampaignId, name: &str) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.remove_label(campaign_id, name)).await
+    }
+
+    async fn fetch_raw_campaign(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<serde_json::Value, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_raw_campaign(campaign_id)).await
+    }
+
+    async fn fetch_changed_campaigns(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Campaign>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_changed_campaigns(since)).await
+    }
+
+    async fn fetch_accounts(&self) -> Result<Vec<AccountNode>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_accounts()).await
+    }
+
+    async fn fetch_conversion_actions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<ConversionActionRow>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_conversion_actions(campaign_id)).await
+    }
+
+    async fn fetch_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+    ) -> Result<Vec<Exclusion>, ConnectorError> {
+        self.with_reauth(|| self.inner.fetch_exclusions(campaign_id)).await
+    }
+
+    async fn update_exclusions(
+        &self,
+        campaign_id: &CampaignId,
+        update: &ExclusionUpdate,
+    ) -> Result<(), ConnectorError> {
+        self.with_reauth(|| self.inner.update_exclusions(campaign_id, update)).await
+    }
+}
+
+/// GET /credentials/health - Token refresh events, most recent first
+pub async fn get_credential_health(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<CredentialHealthEvent>>, ApiError> {
+    Ok(Json(state.credential_health.events()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::platform_errors::PlatformError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Connector whose token is expired until refreshed
+    struct ExpiringConnector {
+        calls: AtomicUsize,
+        expired: std::sync::Mutex<bool>,
+        refresh_works: bool,
+    }
+
+    impl ExpiringConnector {
+        fn new(refresh_works: bool) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                expired: std::sync::Mutex::new(true),
+                refresh_works,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl PlatformConnector for ExpiringConnector {
+        fn platform(&self) -> Platform {
+            Platform::Google
+        }
+
+        async fn fetch_campaigns(&self) -> Result<Vec<Campaign>, ConnectorError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if *self.expired.lock().unwrap() {
+                return Err(ConnectorError::Platform(PlatformError {
+                    platform: Platform::Google,
+                    code: PlatformErrorCode::AuthExpired,
+                    upstream_code: "UNAUTHENTICATED".to_string(),
+                    message: "token expired".to_string(),
+                    retry_after: None,
+                }));
+            }
+            Ok(vec![Campaign::sample("1", Platform::Google)])
+        }
+
+        async fn fetch_metrics(&self, _campaign_id: &CampaignId) -> Result<CampaignMetrics, ConnectorError> {
+            Ok(CampaignMetrics::default())
+        }
+
+        async fn mutate_status(
+            &self,
+            _campaign_id: &CampaignId,
+            _status: CampaignStatus,
+        ) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+
+        async fn refresh_credentials(&self) -> Result<(), ConnectorError> {
+            if !self.refresh_works {
+                return Err(ConnectorError::NotSupported("token refresh"));
+            }
+            *self.expired.lock().unwrap() = false;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_refreshed_and_call_retried() {
+        let inner = Arc::new(ExpiringConnector::new(true));
+        let health = Arc::new(CredentialHealth::default());
+        let connector = ReauthConnector::new(inner.clone(), health.clone(), Arc::default());
+
+        let campaigns = connector.fetch_campaigns().await.unwrap();
+
+        assert_eq!(campaigns.len(), 1);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(health.events()[0].status, CredentialStatus::Refreshed);
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_surfaces_auth_error_and_records_event() {
+        let inner = Arc::new(ExpiringConnector::new(false));
+        let health = Arc::new(CredentialHealth::default());
+        let connector = ReauthConnector::new(inner.clone(), health.clone(), Arc::default());
+
+        let err = connector.fetch_campaigns().await.unwrap_err();
+
+        assert!(is_auth_expired(&err));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+        let events = health.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status, CredentialStatus::RefreshFailed);
+        assert!(events[0].error.is_some());
+    }
+}
@@ -0,0 +1,283 @@
+//! # Budget Recommendations Example
+//!
+//! `GET /recommendations/budget` suggests moving daily budget from
+//! high-CPA to low-CPA enabled campaigns, across platforms, under a total
+//! budget constraint:
+//!
+//! - `total_budget` is the daily budget to distribute; without it the
+//!   current total is kept and budget is only moved;
+//! - no campaign changes by more than `max_change_pct` (default 20%) in
+//!   one step, so learning phases are not reset;
+//! - campaigns with fewer than `MIN_CONVERSIONS` conversions have no
+//!   reliable CPA and are left out, as are paused campaigns.
+//!
+//! Conversions are projected linearly at each campaign's current CPA:
+//! with that model filling the cheapest conversions first is optimal.
+//! Real campaigns get more expensive as they scale, so projected gains
+//! are an upper bound. Budgets in different currencies are never mixed;
+//! each currency gets its own plan.
+
+use axum::extract::{Query, State};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::finite::{safe_div, Finite};
+use super::ids::CampaignId;
+
+/// Conversions needed before a campaign's CPA is trusted
+pub const MIN_CONVERSIONS: u32 = 10;
+
+/// Largest change of a single campaign's budget without `?max_change_pct=`
+pub const DEFAULT_MAX_CHANGE_PCT: f64 = 20.0;
+
+/// Changes smaller than this (in currency units) are not suggested
+pub const MIN_CHANGE: f64 = 1.0;
+
+/// Query parameters for `GET /recommendations/budget`
+#[derive(Debug, Deserialize, Default)]
+pub struct BudgetQuery {
+    /// Daily budget to distribute; requires `currency` when campaigns use
+    /// several currencies
+    pub total_budget: Option<f64>,
+    pub currency: Option<String>,
+    pub max_change_pct: Option<f64>,
+}
+
+/// Suggested budget of one campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BudgetChange {
+    pub campaign_id: CampaignId,
+    pub platform: Platform,
+    pub name: String,
+    pub cpa: f64,
+    pub current_budget: f64,
+    pub recommended_budget: f64,
+    pub change: f64,
+    /// Daily conversions gained (lost when negative) at the current CPA
+    pub projected_conversions: f64,
+}
+
+/// Recommendations for the campaigns of one currency
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BudgetPlan {
+    pub currency: String,
+    pub current_total: f64,
+    pub recommended_total: f64,
+    /// Budget that could not be placed without exceeding `max_change_pct`
+    pub unallocated: f64,
+    pub projected_daily_conversions: f64,
+    pub projected_conversions_change: f64,
+    /// Largest projected impact first
+    pub changes: Vec<BudgetChange>,
+}
+
+struct Candidate<'a> {
+    campaign: &'a Campaign,
+    cpa: f64,
+    min: f64,
+    max: f64,
+}
+
+fn candidate(campaign: &Campaign, max_change: f64) -> Option<Candidate<'_>> {
+    if campaign.status != CampaignStatus::Enabled
+        || campaign.daily_budget <= 0.0
+        || campaign.metrics.conversions < MIN_CONVERSIONS
+    {
+        return None;
+    }
+    let cpa = safe_div(campaign.metrics.cost, campaign.metrics.conversions as f64)?;
+    if cpa <= 0.0 {
+        return None;
+    }
+
+    Some(Candidate {
+        campaign,
+        cpa,
+        min: campaign.daily_budget * (1.0 - max_change),
+        max: campaign.daily_budget * (1.0 + max_change),
+    })
+}
+
+/// Distribute `total_budget` (default: the current total) over the
+/// eligible campaigns, cheapest conversions first
+///
+/// All campaigns must share one currency.
+pub fn plan_budget(
+    currency: &str,
+    campaigns: &[Campaign],
+    total_budget: Option<f64>,
+    max_change_pct: f64,
+) -> Result<BudgetPlan, ApiError> {
+    let max_change = max_change_pct / 100.0;
+    let mut candidates: Vec<Candidate> =
+        campaigns.iter().filter_map(|c| candidate(c, max_change)).collect();
+    candidates.sort_by(|a, b| a.cpa.total_cmp(&b.cpa));
+
+    let current_total: f64 = candidates.iter().map(|c| c.campaign.daily_budget).sum();
+    let target = total_budget.unwrap_or(current_total);
+    let floor: f64 = candidates.iter().map(|c| c.min).sum();
+    if target < floor {
+        return Err(ApiError::Validation(format!(
+            "total_budget {:.2} {} is below {:.2}, the lowest reachable with max_change_pct {}",
+            target, currency, floor, max_change_pct
+        )));
+    }
+
+    // Every campaign starts at its floor; the rest goes to the lowest CPA first
+    let mut remaining = target - floor;
+    let mut changes = Vec::new();
+    let mut projected_daily_conversions = 0.0;
+    for c in &candidates {
+        let extra = remaining.min(c.max - c.min);
+        remaining -= extra;
+
+        let recommended = c.min + extra;
+        let change = recommended - c.campaign.daily_budget;
+        projected_daily_conversions += recommended / c.cpa;
+        if change.abs() >= MIN_CHANGE {
+            changes.push(BudgetChange {
+                campaign_id: c.campaign.id.clone(),
+                platform: c.campaign.platform.clone(),
+                name: c.campaign.name.clone(),
+                cpa: c.cpa,
+                current_budget: c.campaign.daily_budget,
+                recommended_budget: recommended,
+                change,
+                projected_conversions: change / c.cpa,
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| b.projected_conversions.abs().total_cmp(&a.projected_conversions.abs()));
+    let current_conversions: f64 = candidates.iter().map(|c| c.campaign.daily_budget / c.cpa).sum();
+
+    Ok(BudgetPlan {
+        currency: currency.to_string(),
+        current_total,
+        recommended_total: target - remaining,
+        unallocated: remaining,
+        projected_daily_conversions,
+        projected_conversions_change: projected_daily_conversions - current_conversions,
+        changes,
+    })
+}
+
+/// One plan per currency, or only for `query.currency`
+pub fn recommend(campaigns: &[Campaign], query: &BudgetQuery) -> Result<Vec<BudgetPlan>, ApiError> {
+    let max_change_pct = query.max_change_pct.unwrap_or(DEFAULT_MAX_CHANGE_PCT);
+    if !(max_change_pct > 0.0 && max_change_pct <= 100.0) {
+        return Err(ApiError::Validation("max_change_pct must be within (0, 100]".to_string()));
+    }
+    if query.total_budget.is_some_and(|t| !t.is_finite() || t <= 0.0) {
+        return Err(ApiError::Validation("total_budget must be positive".to_string()));
+    }
+
+    let mut by_currency: BTreeMap<&str, Vec<Campaign>> = BTreeMap::new();
+    for campaign in campaigns {
+        if query.currency.as_deref().is_none_or(|c| c.eq_ignore_ascii_case(&campaign.currency)) {
+            by_currency.entry(&campaign.currency).or_default().push(campaign.clone());
+        }
+    }
+    if query.total_budget.is_some() && by_currency.len() > 1 {
+        return Err(ApiError::Validation(
+            "campaigns use several currencies; set currency with total_budget".to_string(),
+        ));
+    }
+
+    by_currency
+        .into_iter()
+        .map(|(currency, campaigns)| {
+            plan_budget(currency, &campaigns, query.total_budget, max_change_pct)
+        })
+        .collect()
+}
+
+/// GET /recommendations/budget - Ranked budget shifts toward lower CPA
+pub async fn get_budget_recommendations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BudgetQuery>,
+) -> Result<Finite<Vec<BudgetPlan>>, ApiError> {
+    let campaigns = aggregate_campaigns(&state).await;
+    recommend(&campaigns, &query).map(Finite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(
+        id: &str,
+        platform: Platform,
+        budget: f64,
+        cost: f64,
+        conversions: u32,
+    ) -> Campaign {
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.daily_budget = budget;
+        campaign.metrics.cost = cost;
+        campaign.metrics.conversions = conversions;
+        campaign
+    }
+
+    fn campaigns() -> Vec<Campaign> {
+        vec![
+            // CPA 50, 20 and 10
+            campaign("g1", Platform::Google, 100.0, 1_000.0, 20),
+            campaign("m1", Platform::Meta, 100.0, 400.0, 20),
+            campaign("g2", Platform::Google, 100.0, 200.0, 20),
+        ]
+    }
+
+    #[test]
+    fn test_budget_moves_from_high_to_low_cpa() {
+        let plan = plan_budget("BRL", &campaigns(), None, 20.0).unwrap();
+
+        assert!((plan.recommended_total - 300.0).abs() < 1e-9);
+        // Cheapest campaign ranked first: +20 at CPA 10
+        assert_eq!(plan.changes[0].campaign_id, "g2");
+        assert!((plan.changes[0].change - 20.0).abs() < 1e-9);
+        let g1 = plan.changes.iter().find(|c| c.campaign_id == "g1").unwrap();
+        assert!((g1.change + 20.0).abs() < 1e-9);
+        // +2 conversions on g2, -0.4 on g1
+        assert!((plan.projected_conversions_change - 1.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_total_budget_constraint_respects_max_change() {
+        let plan = plan_budget("BRL", &campaigns(), Some(400.0), 20.0).unwrap();
+        assert!((plan.recommended_total - 360.0).abs() < 1e-9);
+        assert!((plan.unallocated - 40.0).abs() < 1e-9);
+
+        assert!(plan_budget("BRL", &campaigns(), Some(200.0), 20.0).is_err());
+    }
+
+    #[test]
+    fn test_unreliable_and_paused_campaigns_untouched() {
+        let mut all = campaigns();
+        all.push(campaign("m2", Platform::Meta, 100.0, 10.0, 2));
+        all[0].status = CampaignStatus::Paused;
+
+        let plan = plan_budget("BRL", &all, None, 20.0).unwrap();
+
+        assert!((plan.current_total - 200.0).abs() < 1e-9);
+        assert!(plan.changes.iter().all(|c| c.campaign_id != "m2" && c.campaign_id != "g1"));
+    }
+
+    #[test]
+    fn test_currencies_planned_separately() {
+        let mut all = campaigns();
+        all[1].currency = "USD".to_string();
+
+        let plans = recommend(&all, &BudgetQuery::default()).unwrap();
+        assert_eq!(plans.len(), 2);
+
+        let query = BudgetQuery {
+            total_budget: Some(300.0),
+            ..Default::default()
+        };
+        assert!(recommend(&all, &query).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! # Metrics Refresh Jobs Example
+//!
+//! Refreshing hundreds of campaigns takes longer than a client should hold
+//! a request open. `POST /refresh` takes the campaigns as `platform:id`
+//! references, answers `202 Accepted` with a job right away and refreshes
+//! in the background; `GET /jobs/{id}` reports progress until the job is
+//! `completed`.
+//!
+//! Each campaign's cached entity and metrics are dropped and its metrics
+//! fetched again, `REFRESH_CONCURRENCY` campaigns at a time, so the next
+//! reads see fresh values. A campaign that fails is listed with its error
+//! and does not stop the job. Finished jobs are kept for
+//! `JOB_RETENTION_SECS`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+use super::ids::GlobalCampaignId;
+
+/// Campaigns refreshed at the same time by one job
+pub const REFRESH_CONCURRENCY: usize = 8;
+
+/// Largest number of campaigns in one job
+pub const MAX_REFRESH_CAMPAIGNS: usize = 1_000;
+
+/// How long finished jobs can still be polled
+pub const JOB_RETENTION_SECS: i64 = 3_600;
+
+/// Request body for `POST /refresh`
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    /// `platform:id` references, e.g. `google:123`
+    pub campaigns: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    /// Every campaign was attempted; see `failures`
+    Completed,
+}
+
+/// Campaign a job could not refresh
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RefreshFailure {
+    pub campaign: String,
+    pub error: String,
+}
+
+/// Metrics refresh job, as returned by `GET /jobs/{id}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshJob {
+    pub id: String,
+    pub status: JobStatus,
+    /// Campaigns to refresh, without duplicates
+    pub total: usize,
+    pub refreshed: usize,
+    pub failures: Vec<RefreshFailure>,
+    /// Share of the campaigns attempted, from 0 to 1
+    pub progress: f64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl RefreshJob {
+    fn new(total: usize, now: DateTime<Utc>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            status: if total == 0 { JobStatus::Completed } else { JobStatus::Running },
+            total,
+            refreshed: 0,
+            failures: Vec::new(),
+            progress: if total == 0 { 1.0 } else { 0.0 },
+            created_at: now,
+            updated_at: now,
+            finished_at: (total == 0).then_some(now),
+        }
+    }
+
+    /// Record the outcome of one campaign
+    fn record(
+        &mut self,
+        campaign: &GlobalCampaignId,
+        result: Result<(), String>,
+        now: DateTime<Utc>,
+    ) {
+        match result {
+            Ok(()) => self.refreshed += 1,
+            Err(error) => self.failures.push(RefreshFailure {
+                campaign: campaign.to_string(),
+                error,
+            }),
+        }
+
+        let attempted = self.refreshed + self.failures.len();
+        self.progress = attempted as f64 / self.total as f64;
+        self.updated_at = now;
+        if attempted >= self.total {
+            self.status = JobStatus::Completed;
+            self.finished_at = Some(now);
+        }
+    }
+}
+
+/// Parse and deduplicate `platform:id` references
+pub fn parse_campaigns(references: &[String]) -> Result<Vec<GlobalCampaignId>, ApiError> {
+    if references.len() > MAX_REFRESH_CAMPAIGNS {
+        return Err(ApiError::Validation(format!(
+            "at most {} campaigns per refresh",
+            MAX_REFRESH_CAMPAIGNS
+        )));
+    }
+
+    let mut campaigns: Vec<GlobalCampaignId> = Vec::with_capacity(references.len());
+    for reference in references {
+        let campaign: GlobalCampaignId = reference
+            .trim()
+            .parse()
+            .map_err(|e: super::ids::InvalidGlobalId| ApiError::Validation(e.to_string()))?;
+        if !campaigns.contains(&campaign) {
+            campaigns.push(campaign);
+        }
+    }
+    Ok(campaigns)
+}
+
+/// Refresh jobs by ID
+#[derive(Default)]
+pub struct RefreshJobStore {
+    jobs: RwLock<HashMap<String, RefreshJob>>,
+}
+
+impl RefreshJobStore {
+    /// Add a job, dropping jobs finished more than `JOB_RETENTION_SECS` ago
+    pub fn insert(&self, job: RefreshJob) {
+        let cutoff = job.created_at - chrono::Duration::seconds(JOB_RETENTION_SECS);
+        let mut jobs = self.jobs.write().unwrap();
+        jobs.retain(|_, j| j.finished_at.iter().all(|finished| *finished > cutoff));
+        jobs.insert(job.id.clone(), job);
+    }
+
+    pub fn get(&self, id: &str) -> Option<RefreshJob> {
+        self.jobs.read().unwrap().get(id).cloned()
+    }
+
+    fn record(&self, id: &str, campaign: &GlobalCampaignId, result: Result<(), String>) {
+        if let Some(job) = self.jobs.write().unwrap().get_mut(id) {
+            job.record(campaign, result, Utc::now());
+        }
+    }
+}
+
+/// Drop the cached values of `campaign` and fetch its metrics again
+async fn refresh_campaign(state: &AppState, campaign: &GlobalCampaignId) -> Result<(), String> {
+    let connector = state
+        .connectors
+        .get(&campaign.platform)
+        .ok_or_else(|| format!("platform {} is not enabled", campaign.platform.as_str()))?;
+
+    state.entity_cache.invalidate_campaign(&campaign.platform, &campaign.id);
+    connector
+        .fetch_metrics(&campaign.id)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Refresh every campaign of job `id`, recording each outcome as it lands
+pub async fn run_refresh(state: Arc<AppState>, id: String, campaigns: Vec<GlobalCampaignId>) {
+    stream::iter(campaigns)
+        .map(|campaign| {
+            let state = state.clone();
+            async move {
+                let result = refresh_campaign(&state, &campaign).await;
+                (campaign, result)
+            }
+        })
+        .buffer_unordered(REFRESH_CONCURRENCY)
+        .for_each(|(campaign, result)| {
+            if let Err(error) = &result {
+                tracing::warn!(job_id = %id, campaign = %campaign, error, "refresh failed");
+            }
+            state.refresh_jobs.record(&id, &campaign, result);
+            async {}
+        })
+        .await;
+}
+
+/// POST /refresh - Refresh the metrics of campaigns in the background
+pub async fn create_refresh(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<RefreshRequest>,
+) -> Result<(StatusCode, Json<RefreshJob>), ApiError> {
+    let campaigns = parse_campaigns(&body.campaigns)?;
+
+    let job = RefreshJob::new(campaigns.len(), Utc::now());
+    state.refresh_jobs.insert(job.clone());
+    if !campaigns.is_empty() {
+        tokio::spawn(run_refresh(state.clone(), job.id.clone(), campaigns));
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// GET /jobs/{id} - Progress of a refresh job
+pub async fn get_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<RefreshJob>, ApiError> {
+    state
+        .refresh_jobs
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("job {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+
+    #[test]
+    fn test_references_are_parsed_and_deduplicated() {
+        let references = ["google:1", " meta:2", "google:1"].map(String::from);
+
+        let campaigns = parse_campaigns(&references).unwrap();
+
+        let google = GlobalCampaignId::new(Platform::Google, "1");
+        assert_eq!(campaigns, [google, GlobalCampaignId::new(Platform::Meta, "2")]);
+        assert!(parse_campaigns(&["google".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_job_completes_once_every_campaign_is_attempted() {
+        let now = Utc::now();
+        let mut job = RefreshJob::new(2, now);
+        let google = GlobalCampaignId::new(Platform::Google, "1");
+
+        job.record(&google, Ok(()), now);
+        assert_eq!((job.status, job.progress), (JobStatus::Running, 0.5));
+
+        let meta = GlobalCampaignId::new(Platform::Meta, "2");
+        job.record(&meta, Err("campaign 2 not found".to_string()), now);
+        assert_eq!((job.status, job.progress), (JobStatus::Completed, 1.0));
+        assert_eq!(job.failures[0].campaign, "meta:2");
+        assert_eq!(RefreshJob::new(0, now).status, JobStatus::Completed);
+    }
+}
@@ -0,0 +1,482 @@
+//! # Multi-Region Example
+//!
+//! The service runs one instance per region (`region.current`), and the
+//! data of EU tenants must be processed and stored in the EU. Each tenant
+//! has a home region (`region.tenants`, by default the region of the
+//! instance it calls), while the API surface stays the same everywhere:
+//!
+//! - a request whose `X-Tenant-Id` is homed in another region is forwarded
+//!   whole to that region's instance (`region.peers`) and its response
+//!   relayed, so nothing about the tenant is processed or stored here;
+//! - every response names the region that served it in `X-Served-Region`,
+//!   and stored records (the audit log) are tagged with their region;
+//! - `GET /admin/regions` and `GET /admin/regions/audit` are cross-region
+//!   admin views. Residency is enforced by the instance holding the data:
+//!   a restricted region (`region.restricted`, EU by default) answers the
+//!   views of other regions with entry counts only, never the entries.
+//!
+//! Forwarded requests carry `X-Forwarded-Region` and are never forwarded
+//! again, so two misconfigured instances cannot bounce a request forever.
+
+use axum::{
+    body::Body,
+    extract::{Query, RawQuery, Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::audit::{AuditEntry, AuditLog, AuditQuery};
+use super::campaign_aggregator::AppState;
+use super::config::RegionConfig;
+use super::error::ApiError;
+
+/// Region of the instance that served a response
+pub const SERVED_REGION_HEADER: &str = "x-served-region";
+
+/// Region of the instance a request was forwarded from
+pub const FORWARDED_REGION_HEADER: &str = "x-forwarded-region";
+
+/// Largest request body forwarded to another region; axum's default body limit
+const MAX_REQUEST_BYTES: usize = 2 * 1024 * 1024;
+
+/// Request headers not copied to the home region's instance; the
+/// compression layer re-encodes the relayed response
+const SKIPPED_REQUEST_HEADERS: &[&str] =
+    &["host", "connection", "content-length", "accept-encoding"];
+
+/// Response headers not relayed from the home region's instance
+const SKIPPED_RESPONSE_HEADERS: &[&str] = &["connection", "content-length", "transfer-encoding"];
+
+/// Caller headers sent along with cross-region admin queries
+const ACTOR_HEADERS: &[&str] = &["x-actor", "x-actor-role"];
+
+/// Deployment region
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    #[default]
+    Us,
+    Eu,
+}
+
+impl Region {
+    pub const ALL: [Region; 2] = [Region::Us, Region::Eu];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Region::Us => "us",
+            Region::Eu => "eu",
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Region::ALL
+            .into_iter()
+            .find(|region| region.as_str() == s.trim().to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown region {:?}, expected us or eu", s))
+    }
+}
+
+/// Sends requests to the home region of their tenant
+pub struct RegionRouter {
+    config: RegionConfig,
+    /// Client for the other regions' instances; carries no gateway token
+    client: reqwest::Client,
+}
+
+impl RegionRouter {
+    pub fn new(config: RegionConfig, client: reqwest::Client) -> Self {
+        Self { config, client }
+    }
+
+    pub fn current(&self) -> Region {
+        self.config.current
+    }
+
+    /// Home region of the request's tenant when it is not this one
+    fn remote_home(&self, headers: &HeaderMap) -> Option<Region> {
+        if headers.contains_key(FORWARDED_REGION_HEADER) {
+            return None;
+        }
+        let home = self.config.home_of(header(headers, "x-tenant-id"));
+        (home != self.config.current).then_some(home)
+    }
+
+    fn peer_url(&self, region: Region, path_and_query: &str) -> Result<String, ApiError> {
+        let base = self.config.peer_url(region).ok_or_else(|| {
+            ApiError::Internal(format!("no instance configured for region {}", region))
+        })?;
+        Ok(format!("{}{}", base.trim_end_matches('/'), path_and_query))
+    }
+
+    /// Run `req` on the instance of `home` and relay its response
+    async fn forward(&self, home: Region, req: Request) -> Result<Response, ApiError> {
+        let (parts, body) = req.into_parts();
+        let body = axum::body::to_bytes(body, MAX_REQUEST_BYTES)
+            .await
+            .map_err(|e| ApiError::Validation(e.to_string()))?;
+        let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        let url = self.peer_url(home, path)?;
+
+        let mut headers = parts.headers;
+        for name in SKIPPED_REQUEST_HEADERS {
+            headers.remove(*name);
+        }
+        headers.insert(
+            FORWARDED_REGION_HEADER,
+            HeaderValue::from_static(self.current().as_str()),
+        );
+
+        tracing::debug!(region = home.as_str(), path, "forwarding request to home region");
+        let upstream = self
+            .client
+            .request(parts.method, url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| peer_error(home, e))?;
+
+        let status = upstream.status();
+        let mut headers = upstream.headers().clone();
+        for name in SKIPPED_RESPONSE_HEADERS {
+            headers.remove(*name);
+        }
+        let body = upstream.bytes().await.map_err(|e| peer_error(home, e))?;
+
+        let mut response = Response::new(Body::from(body));
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        Ok(response)
+    }
+
+    /// GET a JSON document from the instance of `region` on behalf of the caller
+    async fn fetch<T: DeserializeOwned>(
+        &self,
+        region: Region,
+        path_and_query: &str,
+        caller: &HeaderMap,
+    ) -> Result<T, ApiError> {
+        let mut request = self
+            .client
+            .get(self.peer_url(region, path_and_query)?)
+            .header(FORWARDED_REGION_HEADER, self.current().as_str());
+        for name in ACTOR_HEADERS {
+            if let Some(value) = caller.get(*name) {
+                request = request.header(*name, value.clone());
+            }
+        }
+
+        let response = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| peer_error(region, e))?;
+        response.json().await.map_err(|e| peer_error(region, e))
+    }
+}
+
+fn peer_error(region: Region, e: reqwest::Error) -> ApiError {
+    if e.is_timeout() {
+        ApiError::GatewayTimeout
+    } else {
+        ApiError::Gateway(format!("{} instance: {}", region, e))
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Middleware serving each request in its tenant's home region
+pub async fn route_to_home_region(
+    State(router): State<Arc<RegionRouter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut response = match router.remote_home(req.headers()) {
+        // The home instance sets its own X-Served-Region
+        Some(home) => match router.forward(home, req).await {
+            Ok(response) => return response,
+            Err(e) => e.into_response(),
+        },
+        None => next.run(req).await,
+    };
+
+    response.headers_mut().insert(
+        SERVED_REGION_HEADER,
+        HeaderValue::from_static(router.current().as_str()),
+    );
+    response
+}
+
+/// One region in `GET /admin/regions`
+#[derive(Debug, Serialize, Clone)]
+pub struct RegionStatus {
+    pub region: Region,
+    /// Region of the instance answering
+    pub current: bool,
+    /// Base URL of the region's instance; none for the current region
+    pub url: Option<String>,
+    pub reachable: bool,
+    /// Data of this region is only shared as counts
+    pub restricted: bool,
+    /// Tenants homed in the region
+    pub tenants: Vec<String>,
+}
+
+/// GET /admin/regions - Regions, their instances and the tenants homed there
+pub async fn get_regions(State(state): State<Arc<AppState>>) -> Json<Vec<RegionStatus>> {
+    let config = &state.config.region;
+    let tenants_of = |region: Region| {
+        let mut tenants: Vec<String> = config
+            .tenants
+            .iter()
+            .filter(|(_, home)| **home == region)
+            .map(|(tenant, _)| tenant.clone())
+            .collect();
+        tenants.sort();
+        tenants
+    };
+
+    let probes = config.peers().into_iter().map(|(region, url)| {
+        let client = state.regions.client.clone();
+        let url = format!("{}/healthz", url.trim_end_matches('/'));
+        async move {
+            let reachable = client
+                .get(&url)
+                .send()
+                .await
+                .is_ok_and(|r| r.status().is_success());
+            (region, reachable)
+        }
+    });
+
+    let mut regions = vec![RegionStatus {
+        region: config.current,
+        current: true,
+        url: None,
+        reachable: true,
+        restricted: config.is_restricted(config.current),
+        tenants: tenants_of(config.current),
+    }];
+    for (region, reachable) in futures::future::join_all(probes).await {
+        regions.push(RegionStatus {
+            region,
+            current: false,
+            url: config.peer_url(region).map(str::to_string),
+            reachable,
+            restricted: config.is_restricted(region),
+            tenants: tenants_of(region),
+        });
+    }
+
+    Json(regions)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewStatus {
+    Ok,
+    /// Entries withheld by the region's residency rules
+    Restricted,
+    Unreachable,
+}
+
+/// Audit entries of one region in `GET /admin/regions/audit`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegionAuditView {
+    pub region: Region,
+    pub status: ViewStatus,
+    /// Matching entries, also counted when they are withheld
+    pub entry_count: usize,
+    pub entries: Vec<AuditEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Entries of this instance's log as shown to an admin in `requester`
+fn local_audit_view(
+    log: &AuditLog,
+    config: &RegionConfig,
+    query: &AuditQuery,
+    requester: Region,
+) -> RegionAuditView {
+    let entries = log.query(query);
+    let withheld = requester != config.current && config.is_restricted(config.current);
+
+    RegionAuditView {
+        region: config.current,
+        status: if withheld { ViewStatus::Restricted } else { ViewStatus::Ok },
+        entry_count: entries.len(),
+        entries: if withheld { Vec::new() } else { entries },
+        error: None,
+    }
+}
+
+/// GET /admin/regions/audit - Audit entries of every region, within residency rules
+///
+/// Takes the filters of `GET /audit`. Asked by another region's instance
+/// (`X-Forwarded-Region`), answers with this region's view only.
+pub async fn get_cross_region_audit(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    RawQuery(raw_query): RawQuery,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<RegionAuditView>>, ApiError> {
+    let config = &state.config.region;
+    if let Some(requester) = header(&headers, FORWARDED_REGION_HEADER) {
+        let requester: Region = requester.parse().map_err(ApiError::Validation)?;
+        return Ok(Json(vec![local_audit_view(&state.audit, config, &query, requester)]));
+    }
+
+    let path = match raw_query {
+        Some(q) => format!("/admin/regions/audit?{}", q),
+        None => "/admin/regions/audit".to_string(),
+    };
+    let fetches = config.peers().into_iter().map(|(region, _)| {
+        let (state, headers, path) = (state.clone(), headers.clone(), path.clone());
+        async move {
+            let fetched = state
+                .regions
+                .fetch::<Vec<RegionAuditView>>(region, &path, &headers)
+                .await;
+            match fetched.map(|views| views.into_iter().find(|v| v.region == region)) {
+                Ok(Some(view)) => view,
+                Ok(None) => unreachable_view(region, "no view for its own region".to_string()),
+                Err(e) => unreachable_view(region, e.to_string()),
+            }
+        }
+    });
+
+    let mut views = vec![local_audit_view(&state.audit, config, &query, config.current)];
+    views.extend(futures::future::join_all(fetches).await);
+    Ok(Json(views))
+}
+
+fn unreachable_view(region: Region, error: String) -> RegionAuditView {
+    tracing::warn!(region = region.as_str(), error = %error, "region audit view unavailable");
+    RegionAuditView {
+        region,
+        status: ViewStatus::Unreachable,
+        entry_count: 0,
+        entries: Vec::new(),
+        error: Some(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::audit::AuditChange;
+    use super::super::campaign_aggregator::Platform;
+    use super::super::freeze::Actor;
+    use axum::{http::StatusCode, middleware, routing::get, Router};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    fn config(current: Region, eu_url: &str) -> RegionConfig {
+        RegionConfig {
+            current,
+            peers: HashMap::from([("eu".to_string(), eu_url.to_string())]),
+            tenants: HashMap::from([("acme-eu".to_string(), Region::Eu)]),
+            ..Default::default()
+        }
+    }
+
+    /// Router answering with the region it runs in
+    fn instance(router: Arc<RegionRouter>) -> Router {
+        let region = router.current();
+        Router::new()
+            .route("/campaigns", get(move || async move { region.as_str() }))
+            .layer(middleware::from_fn_with_state(router, route_to_home_region))
+    }
+
+    fn get_campaigns(tenant: &str) -> Request {
+        Request::get("/campaigns")
+            .header("x-tenant-id", tenant)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_served_in_the_tenant_home_region() {
+        let eu = Arc::new(RegionRouter::new(config(Region::Eu, ""), reqwest::Client::new()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let eu_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, instance(eu)).await.unwrap() });
+
+        let us = instance(Arc::new(RegionRouter::new(
+            config(Region::Us, &eu_url),
+            reqwest::Client::new(),
+        )));
+
+        let local = us.clone().oneshot(get_campaigns("acme")).await.unwrap();
+        assert_eq!(local.headers()[SERVED_REGION_HEADER], "us");
+
+        let forwarded = us.oneshot(get_campaigns("acme-eu")).await.unwrap();
+        assert_eq!(forwarded.status(), StatusCode::OK);
+        assert_eq!(forwarded.headers()[SERVED_REGION_HEADER], "eu");
+        let body = axum::body::to_bytes(forwarded.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"eu");
+    }
+
+    #[test]
+    fn test_forwarded_requests_are_never_forwarded_again() {
+        let router = RegionRouter::new(config(Region::Us, "http://eu"), reqwest::Client::new());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-tenant-id", HeaderValue::from_static("acme-eu"));
+        assert_eq!(router.remote_home(&headers), Some(Region::Eu));
+
+        headers.insert(FORWARDED_REGION_HEADER, HeaderValue::from_static("eu"));
+        assert_eq!(router.remote_home(&headers), None);
+    }
+
+    #[test]
+    fn test_restricted_region_only_shares_counts_with_other_regions() {
+        let log = AuditLog::new(None, Region::Eu);
+        log.record(AuditEntry::new(
+            &Actor::automation("rules", Some("acme-eu".to_string())),
+            &Platform::Google,
+            &"g1".into(),
+            AuditChange::DailyBudget {
+                before: Some(50.0),
+                after: 80.0,
+            },
+            &Ok::<_, ApiError>(()),
+        ));
+        let eu = config(Region::Eu, "");
+        let query = AuditQuery::default();
+
+        let own = local_audit_view(&log, &eu, &query, Region::Eu);
+        assert_eq!(own.status, ViewStatus::Ok);
+        assert_eq!(own.entries[0].region, Some(Region::Eu));
+
+        let from_us = local_audit_view(&log, &eu, &query, Region::Us);
+        assert_eq!(from_us.status, ViewStatus::Restricted);
+        assert_eq!(from_us.entry_count, 1);
+        assert!(from_us.entries.is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+//! # Configuration Reload Example
+//!
+//! During peak campaigns a restart drops in-flight syncs and empties the
+//! caches, so `POST /admin/reload` (admins only, like every `/admin/*`
+//! route) applies what can change without one:
+//!
+//! - the configuration file named by `IBVI_CONFIG` is read again, with the
+//!   `IBVI_*` overrides, and validated; an invalid file changes nothing;
+//! - the normalization mapping tables are loaded again from
+//!   `normalization.rules_path`, which may itself have changed;
+//! - benchmark baselines (`benchmarks`) take effect for the next request.
+//!
+//! The response lists every setting that changed, with its value before
+//! and after; values of credentials are redacted. Changes to any other
+//! section are reported under `restart_required` and only take effect on
+//! the next start, since connectors, clients and workers are built from
+//! the configuration once. Command-line demo settings are kept.
+//!
+//! The service holds no exchange rates (see `money`), so there are none to
+//! reload.
+//!
+//! ```bash
+//! curl -X POST -H 'X-Actor-Role: admin' localhost:3000/admin/reload
+//! ```
+
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::AppState;
+use super::config::AppConfig;
+use super::error::ApiError;
+use super::normalization::MappingTables;
+use super::raw::{self, REDACTED};
+
+/// Configuration sections applied by a reload; other sections need a restart
+pub const RELOADABLE_SECTIONS: &[&str] = &["benchmarks", "normalization"];
+
+/// Configuration as last loaded, replaced as a whole on reload
+///
+/// Read the reloadable sections from here rather than from
+/// `AppState::config`, which keeps the configuration of the start.
+pub struct LiveConfig {
+    config: RwLock<Arc<AppConfig>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: AppConfig) -> Self {
+        Self { config: RwLock::new(Arc::new(config)) }
+    }
+
+    pub fn get(&self) -> Arc<AppConfig> {
+        self.config.read().unwrap().clone()
+    }
+
+    fn set(&self, config: AppConfig) {
+        *self.config.write().unwrap() = Arc::new(config);
+    }
+}
+
+/// Setting or mapping that differs between two versions
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Change {
+    /// Dotted path, e.g. `benchmarks.tolerance` or `meta.status.IN_PROCESS`
+    pub path: String,
+    /// `None` when added
+    pub before: Option<Value>,
+    /// `None` when removed
+    pub after: Option<Value>,
+}
+
+/// Response of `POST /admin/reload`
+#[derive(Debug, Serialize)]
+pub struct ReloadReport {
+    pub reloaded_at: DateTime<Utc>,
+    /// Setting changes in effect now
+    pub applied: Vec<Change>,
+    /// Setting changes that take effect on the next start
+    pub restart_required: Vec<Change>,
+    /// Mapping table changes, by platform, field and platform value
+    pub normalization: Vec<Change>,
+}
+
+/// Differences between `before` and `after`, by path
+///
+/// Objects are compared key by key; any other value, arrays included, is
+/// compared whole.
+pub fn diff(before: &Value, after: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at("", Some(before), Some(after), &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, before: Option<&Value>, after: Option<&Value>, changes: &mut Vec<Change>) {
+    if let (Some(Value::Object(before)), Some(Value::Object(after))) = (before, after) {
+        let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            diff_at(&path, before.get(key), after.get(key), changes);
+        }
+    } else if before != after {
+        changes.push(Change {
+            path: path.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        });
+    }
+}
+
+/// Changes of two configurations, credentials redacted
+pub fn config_changes(before: &AppConfig, after: &AppConfig) -> Vec<Change> {
+    let to_value = |config: &AppConfig| serde_json::to_value(config).unwrap_or(Value::Null);
+    let mut changes = diff(&to_value(before), &to_value(after));
+    // Encryption keys (`credentials.key`, `backup.key`) match no sensitive key part
+    let extra_keys = ["key".to_string()];
+    for change in &mut changes {
+        if change.path.split('.').any(|part| raw::is_sensitive_key(part, &extra_keys)) {
+            for value in [&mut change.before, &mut change.after].into_iter().flatten() {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+    changes
+}
+
+/// Whether a change under `path` is applied by a reload
+pub fn is_reloadable(path: &str) -> bool {
+    let section = path.split('.').next().unwrap_or_default();
+    RELOADABLE_SECTIONS.contains(&section)
+}
+
+fn tables_value(tables: &MappingTables) -> Value {
+    serde_json::to_value(tables).unwrap_or(Value::Null)
+}
+
+/// POST /admin/reload - Apply the configuration file and mapping tables
+/// without a restart
+pub async fn reload(State(state): State<Arc<AppState>>) -> Result<Json<ReloadReport>, ApiError> {
+    let current = state.live_config.get();
+    let mut config = AppConfig::load().map_err(|e| ApiError::Validation(e.to_string()))?;
+    config.demo = current.demo.clone();
+
+    let tables_before = tables_value(&state.normalization.get());
+    match config.normalization.rules_path.as_deref() {
+        Some(path) => {
+            state.normalization.load(path).map_err(|e| ApiError::Validation(e.to_string()))?;
+        }
+        None => state.normalization.reset(),
+    }
+    let normalization = diff(&tables_before, &tables_value(&state.normalization.get()));
+
+    let (applied, restart_required): (Vec<Change>, Vec<Change>) =
+        config_changes(&current, &config).into_iter().partition(|c| is_reloadable(&c.path));
+    state.live_config.set(config);
+    tracing::info!(
+        applied = applied.len(),
+        restart_required = restart_required.len(),
+        mappings = normalization.len(),
+        "configuration reloaded"
+    );
+
+    Ok(Json(ReloadReport { reloaded_at: Utc::now(), applied, restart_required, normalization }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::VerticalBaseline;
+    use super::*;
+
+    #[test]
+    fn test_config_changes_are_redacted_and_classified() {
+        let before = AppConfig::default();
+        let mut after = before.clone();
+        after.benchmarks.tolerance = 0.3;
+        after.benchmarks.verticals.insert(
+            "real_estate".to_string(),
+            VerticalBaseline { ctr: 1.5, cpc: 2.0, cpa: 40.0, currency: "BRL".to_string() },
+        );
+        after.gateway.service_token = "s3cret".to_string();
+        after.credentials.key = "00".repeat(32);
+
+        let changes = config_changes(&before, &after);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "benchmarks.tolerance",
+                "benchmarks.verticals.real_estate",
+                "credentials.key",
+                "gateway.service_token",
+            ]
+        );
+        assert_eq!(changes[1].before, None);
+        assert_eq!(changes[3].after, Some(Value::String(REDACTED.to_string())));
+        assert_eq!(changes[2].after, Some(Value::String(REDACTED.to_string())));
+
+        let reloadable: Vec<bool> = changes.iter().map(|c| is_reloadable(&c.path)).collect();
+        assert_eq!(reloadable, [true, true, false, false]);
+    }
+}
@@ -0,0 +1,919 @@
+//! # Scheduled Reports Example
+//!
+//! `POST /reports` saves a report spec: campaign filters, the metrics to
+//! include, how rows are grouped, the output format and a cron schedule.
+//! A background scheduler checks for due reports, renders them to CSV,
+//! JSON or PDF and keeps the artifacts; `GET /reports/{id}/runs` lists
+//! past runs with download links.
+//!
+//! Exports are rendered in the locale of the request that created the
+//! report, see `locale`: a pt-BR CSV is `;`-separated with decimal commas,
+//! a pt-BR PDF has Portuguese labels and `1.234,50` amounts.
+//!
+//! Metrics are aggregated with the `units` schema, so a grouped CTR is
+//! recomputed from clicks and impressions rather than averaged. Schedules
+//! use the five cron fields (`minute hour day-of-month month day-of-week`)
+//! and are evaluated in UTC.
+//!
+//! Compiled only with the `reports` cargo feature (on by default).
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use tokio_util::sync::CancellationToken;
+
+use super::campaign_aggregator::{
+    aggregate_campaigns, AppState, Campaign, CampaignStatus, Platform,
+};
+use super::error::ApiError;
+use super::finite::to_checked_value;
+use super::locale::{self, Locale};
+use super::units::{self, Unit};
+
+/// Interval between two checks for due reports
+pub const SCHEDULER_TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Runs (and artifacts) kept per report; older ones are dropped
+pub const MAX_RUNS_PER_REPORT: usize = 50;
+
+/// How far ahead a schedule is searched for its next run; covers leap days
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 4 + 1;
+
+const PDF_LINES_PER_PAGE: usize = 60;
+
+/// Parsed five-field cron expression
+///
+/// Each field is a bit set of the values it matches. Supports `*`, single
+/// values, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `0-30/10`),
+/// plus the `@hourly`, `@daily`, `@weekly` and `@monthly` shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    /// Sunday is 0 (7 is accepted as well)
+    days_of_week: u64,
+    /// Day-of-month and day-of-week are both restricted; as in cron, a day
+    /// then matches when either field does
+    either_day: bool,
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        let step = match step {
+            Some(step) => step
+                .parse::<u32>()
+                .ok()
+                .filter(|s| *s > 0)
+                .ok_or_else(|| format!("invalid step {:?}", step))?,
+            None => 1,
+        };
+
+        let value = |v: &str| {
+            v.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("{:?} is outside {}-{}", v, min, max))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` means every 15 starting at 5
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("range {:?} is reversed", range));
+        }
+
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+
+    Ok(bits)
+}
+
+impl FromStr for CronSchedule {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let invalid =
+            |reason: String| ApiError::Validation(format!("schedule {:?}: {}", s, reason));
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(invalid("expected 5 fields".to_string()));
+        };
+
+        let mut days_of_week = parse_cron_field(day_of_week, 0, 7).map_err(invalid)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+
+        Ok(CronSchedule {
+            minutes: parse_cron_field(minute, 0, 59).map_err(invalid)?,
+            hours: parse_cron_field(hour, 0, 23).map_err(invalid)?,
+            days_of_month: parse_cron_field(day_of_month, 1, 31).map_err(invalid)?,
+            months: parse_cron_field(month, 1, 12).map_err(invalid)?,
+            days_of_week,
+            either_day: day_of_month != "*" && day_of_week != "*",
+        })
+    }
+}
+
+impl CronSchedule {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let bit = |set: u64, v: u32| set & (1 << v) != 0;
+        let day_of_month = bit(self.days_of_month, date.day());
+        let day_of_week = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+
+        bit(self.months, date.month())
+            && if self.either_day {
+                day_of_month || day_of_week
+            } else {
+                day_of_month && day_of_week
+            }
+    }
+
+    /// First matching minute strictly after `after`, `None` when the
+    /// expression never matches (e.g. `0 0 30 2 *`)
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.naive_utc().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        for offset in 0..MAX_LOOKAHEAD_DAYS {
+            let date = start.date() + Duration::days(offset);
+            if !self.matches_day(date) {
+                continue;
+            }
+            for hour in (0..24).filter(|h| self.hours & (1 << h) != 0) {
+                for minute in (0..60).filter(|m| self.minutes & (1 << m) != 0) {
+                    let candidate = date.and_hms_opt(hour, minute, 0)?;
+                    if candidate >= start {
+                        return Some(candidate.and_utc());
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Campaigns a report covers; empty lists match everything
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ReportFilters {
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
+    #[serde(default)]
+    pub statuses: Vec<CampaignStatus>,
+    /// Case-insensitive substring of the campaign name
+    pub name_contains: Option<String>,
+}
+
+impl ReportFilters {
+    pub fn matches(&self, campaign: &Campaign) -> bool {
+        (self.platforms.is_empty() || self.platforms.contains(&campaign.platform))
+            && (self.statuses.is_empty() || self.statuses.contains(&campaign.status))
+            && self
+                .name_contains
+                .as_deref()
+                .map(|needle| campaign.name.to_lowercase().contains(&needle.to_lowercase()))
+                .unwrap_or(true)
+    }
+}
+
+/// One report row per campaign, platform or status
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportGrouping {
+    Campaign,
+    #[default]
+    Platform,
+    Status,
+}
+
+impl ReportGrouping {
+    /// Grouping key and row label of a campaign
+    fn group_of(&self, campaign: &Campaign) -> (String, String) {
+        match self {
+            ReportGrouping::Campaign => (campaign.global_id().to_string(), campaign.name.clone()),
+            ReportGrouping::Platform => {
+                let platform = campaign.platform.as_str().to_string();
+                (platform.clone(), platform)
+            }
+            ReportGrouping::Status => {
+                let status = campaign.status.as_str();
+                (status.to_string(), status.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Csv,
+    Json,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "text/csv; charset=utf-8",
+            ReportFormat::Json => "application/json",
+            ReportFormat::Pdf => "application/pdf",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// Saved report definition
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportSpec {
+    pub id: String,
+    pub name: String,
+    pub filters: ReportFilters,
+    /// Metric names from `GET /metrics/schema`, in column order
+    pub metrics: Vec<String>,
+    pub group_by: ReportGrouping,
+    pub format: ReportFormat,
+    /// Cron expression, evaluated in UTC
+    pub schedule: String,
+    /// Locale of the exports, from `Accept-Language` unless given
+    #[serde(default)]
+    pub locale: Locale,
+    /// `None` when the schedule has no future occurrence
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /reports`
+#[derive(Debug, Deserialize)]
+pub struct CreateReport {
+    pub name: String,
+    #[serde(default)]
+    pub filters: ReportFilters,
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub group_by: ReportGrouping,
+    #[serde(default)]
+    pub format: ReportFormat,
+    pub schedule: String,
+    pub locale: Option<Locale>,
+}
+
+/// Aggregated metrics of one group
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReportRow {
+    pub group: String,
+    pub campaigns: usize,
+    pub values: BTreeMap<String, f64>,
+}
+
+/// Data of a report run, before rendering
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportTable {
+    pub report: String,
+    pub generated_at: DateTime<Utc>,
+    pub group_by: ReportGrouping,
+    pub metrics: Vec<String>,
+    /// Sorted by group label
+    pub rows: Vec<ReportRow>,
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+/// One execution of a report
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportRun {
+    pub id: String,
+    pub report_id: String,
+    pub format: ReportFormat,
+    pub generated_at: DateTime<Utc>,
+    pub rows: usize,
+    pub size_bytes: usize,
+    pub download_url: String,
+}
+
+/// Rendered output of a run
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub format: ReportFormat,
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Filter, group and aggregate campaigns as the spec describes
+pub fn build_table(spec: &ReportSpec, campaigns: &[Campaign], now: DateTime<Utc>) -> ReportTable {
+    let mut groups: BTreeMap<String, (String, Vec<&Campaign>)> = BTreeMap::new();
+    for campaign in campaigns.iter().filter(|c| spec.filters.matches(c)) {
+        let (key, label) = spec.group_by.group_of(campaign);
+        groups.entry(key).or_insert_with(|| (label, Vec::new())).1.push(campaign);
+    }
+
+    let mut rows: Vec<ReportRow> = groups
+        .into_values()
+        .map(|(group, members)| {
+            let metrics: Vec<_> = members.iter().map(|c| &c.metrics).collect();
+            let values = spec
+                .metrics
+                .iter()
+                .filter_map(|name| {
+                    let def = units::metric(name)?;
+                    Some((name.clone(), units::aggregate(def, &metrics)))
+                })
+                .collect();
+            ReportRow {
+                group,
+                campaigns: members.len(),
+                values,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.group.cmp(&b.group));
+
+    ReportTable {
+        report: spec.name.clone(),
+        generated_at: now,
+        group_by: spec.group_by,
+        metrics: spec.metrics.clone(),
+        rows,
+        locale: spec.locale,
+    }
+}
+
+fn csv_field(value: &str, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Header keeps the metric names in every locale, so imports do not break
+fn render_csv(table: &ReportTable) -> Vec<u8> {
+    let formatting = table.locale.formatting();
+    let delimiter = formatting.csv_delimiter;
+    let mut csv = format!("group{}campaigns", delimiter);
+    for metric in &table.metrics {
+        csv.push(delimiter);
+        csv.push_str(&csv_field(metric, delimiter));
+    }
+    csv.push('\n');
+
+    for row in &table.rows {
+        csv.push_str(&csv_field(&row.group, delimiter));
+        csv.push_str(&format!("{}{}", delimiter, row.campaigns));
+        for metric in &table.metrics {
+            let value = row.values.get(metric).copied().unwrap_or(0.0);
+            csv.push(delimiter);
+            csv.push_str(&formatting.plain(value));
+        }
+        csv.push('\n');
+    }
+
+    csv.into_bytes()
+}
+
+/// Text lines of the PDF: a header and one aligned line per row
+fn pdf_lines(table: &ReportTable) -> Vec<String> {
+    const VALUE_WIDTH: usize = 16;
+    let label_width = table
+        .rows
+        .iter()
+        .map(|r| r.group.chars().count())
+        .chain([5])
+        .max()
+        .unwrap_or(5)
+        .min(40);
+
+    let locale = table.locale;
+    let formatting = locale.formatting();
+    let (group, campaigns, generated, date_format) = match locale {
+        Locale::EnUs => ("Group", "Campaigns", "Generated", "%Y-%m-%d %H:%M"),
+        Locale::PtBr => ("Grupo", "Campanhas", "Gerado em", "%d/%m/%Y %H:%M"),
+    };
+    let mut header = format!("{:<label_width$} {:>9}", group, campaigns);
+    for metric in &table.metrics {
+        header.push_str(&format!(" {:>VALUE_WIDTH$}", locale.metric_label(metric)));
+    }
+
+    let mut lines = vec![
+        table.report.clone(),
+        format!("{} {} UTC", generated, table.generated_at.format(date_format)),
+        String::new(),
+        header,
+    ];
+    for row in &table.rows {
+        let label: String = row.group.chars().take(label_width).collect();
+        let mut line = format!("{:<label_width$} {:>9}", label, row.campaigns);
+        for metric in &table.metrics {
+            let value = row.values.get(metric).copied().unwrap_or(0.0);
+            let formatted = match units::metric(metric) {
+                // Groups can mix currencies, so amounts go without a code
+                Some(def) if def.unit == Unit::Currency => formatting.number(value, 2),
+                Some(def) => units::format_value_in(def, value, "", &formatting),
+                None => formatting.plain(value),
+            };
+            line.push_str(&format!(" {:>VALUE_WIDTH$}", formatted));
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// PDF string literal; the standard fonts cover Latin-1, other characters
+/// print as `?`
+fn pdf_text(line: &str) -> Vec<u8> {
+    let mut text = vec![b'('];
+    for c in line.chars() {
+        match c {
+            '\\' | '(' | ')' => text.extend([b'\\', c as u8]),
+            c if (c as u32) < 0x100 => text.push(c as u32 as u8),
+            _ => text.push(b'?'),
+        }
+    }
+    text.push(b')');
+    text
+}
+
+/// Minimal multi-page PDF of monospaced text lines
+fn render_pdf(lines: &[String]) -> Vec<u8> {
+    let pages: Vec<&[String]> = lines.chunks(PDF_LINES_PER_PAGE).collect();
+    let page_refs: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 4 + 2 * i)).collect();
+
+    // 1: catalog, 2: page tree, 3: font, then a page and its content per page
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_refs.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        let mut content = b"BT /F1 8 Tf 12 TL 30 810 Td\n".to_vec();
+        for line in page.iter() {
+            content.extend(pdf_text(line));
+            content.extend(b" Tj T*\n");
+        }
+        content.extend(b"ET");
+
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 842 595] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + 2 * i
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .into_bytes(),
+    );
+
+    pdf
+}
+
+/// Render a table in the given format
+pub fn render(table: &ReportTable, format: ReportFormat) -> Result<Vec<u8>, ApiError> {
+    Ok(match format {
+        ReportFormat::Csv => render_csv(table),
+        ReportFormat::Json => {
+            let value = to_checked_value(table).map_err(|e| ApiError::Internal(e.to_string()))?;
+            serde_json::to_vec_pretty(&value).map_err(|e| ApiError::Internal(e.to_string()))?
+        }
+        ReportFormat::Pdf => render_pdf(&pdf_lines(table)),
+    })
+}
+
+/// `monthly-spend` from `Monthly Spend!`
+fn slug(name: &str) -> String {
+    let slug: Vec<String> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if slug.is_empty() {
+        "report".to_string()
+    } else {
+        slug.join("-")
+    }
+}
+
+/// Build and render one run of a report
+pub fn generate_run(
+    spec: &ReportSpec,
+    campaigns: &[Campaign],
+    now: DateTime<Utc>,
+) -> Result<(ReportRun, Artifact), ApiError> {
+    let table = build_table(spec, campaigns, now);
+    let bytes = render(&table, spec.format)?;
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let run = ReportRun {
+        download_url: format!("/reports/{}/runs/{}/download", spec.id, id),
+        id,
+        report_id: spec.id.clone(),
+        format: spec.format,
+        generated_at: now,
+        rows: table.rows.len(),
+        size_bytes: bytes.len(),
+    };
+    let artifact = Artifact {
+        format: spec.format,
+        file_name: format!(
+            "{}-{}.{}",
+            slug(&spec.name),
+            now.format("%Y%m%d-%H%M"),
+            spec.format.extension()
+        ),
+        bytes,
+    };
+
+    Ok((run, artifact))
+}
+
+/// Report specs, their runs and the rendered artifacts
+#[derive(Default)]
+pub struct ReportStore {
+    specs: RwLock<HashMap<String, ReportSpec>>,
+    /// Runs per report, oldest first
+    runs: RwLock<HashMap<String, Vec<ReportRun>>>,
+    /// Artifacts by run ID
+    artifacts: RwLock<HashMap<String, Artifact>>,
+}
+
+impl ReportStore {
+    pub fn insert(&self, spec: ReportSpec) {
+        self.specs.write().unwrap().insert(spec.id.clone(), spec);
+    }
+
+    pub fn get(&self, id: &str) -> Option<ReportSpec> {
+        self.specs.read().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ReportSpec> {
+        self.specs.read().unwrap().values().cloned().collect()
+    }
+
+    /// Reports whose next run is at or before `now`
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<ReportSpec> {
+        self.specs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|s| s.next_run_at.is_some_and(|at| at <= now))
+            .cloned()
+            .collect()
+    }
+
+    pub fn reschedule(&self, id: &str, next_run_at: Option<DateTime<Utc>>) {
+        if let Some(spec) = self.specs.write().unwrap().get_mut(id) {
+            spec.next_run_at = next_run_at;
+        }
+    }
+
+    /// Keep a run and its artifact, dropping the oldest beyond
+    /// `MAX_RUNS_PER_REPORT`
+    pub fn record_run(&self, run: ReportRun, artifact: Artifact) {
+        let mut runs = self.runs.write().unwrap();
+        let mut artifacts = self.artifacts.write().unwrap();
+
+        artifacts.insert(run.id.clone(), artifact);
+        let report_runs = runs.entry(run.report_id.clone()).or_default();
+        report_runs.push(run);
+
+        let excess = report_runs.len().saturating_sub(MAX_RUNS_PER_REPORT);
+        for dropped in report_runs.drain(..excess) {
+            artifacts.remove(&dropped.id);
+        }
+    }
+
+    /// Runs of a report, newest first
+    pub fn runs(&self, report_id: &str) -> Vec<ReportRun> {
+        let runs = self.runs.read().unwrap();
+        runs.get(report_id)
+            .map(|runs| runs.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn artifact(&self, report_id: &str, run_id: &str) -> Option<Artifact> {
+        let runs = self.runs.read().unwrap();
+        runs.get(report_id)?.iter().find(|r| r.id == run_id)?;
+        self.artifacts.read().unwrap().get(run_id).cloned()
+    }
+}
+
+/// Render every due report; campaigns are fetched once per tick
+pub async fn run_due_reports(state: &AppState, now: DateTime<Utc>) {
+    let due = state.reports.due(now);
+    if due.is_empty() {
+        return;
+    }
+
+    let campaigns = aggregate_campaigns(state).await;
+
+    for spec in due {
+        // Rescheduled first, so a report that fails to render does not
+        // retry on every tick
+        let next_run_at = spec
+            .schedule
+            .parse::<CronSchedule>()
+            .ok()
+            .and_then(|schedule| schedule.next_after(now));
+        state.reports.reschedule(&spec.id, next_run_at);
+
+        match generate_run(&spec, &campaigns, now) {
+            Ok((run, artifact)) => {
+                tracing::info!(report_id = %spec.id, run_id = %run.id, "report generated");
+                state.reports.record_run(run, artifact);
+            }
+            Err(e) => tracing::error!(report_id = %spec.id, error = %e, "report generation failed"),
+        }
+    }
+}
+
+/// Check for due reports every `SCHEDULER_TICK` until `shutdown` is cancelled
+pub async fn run_report_scheduler(state: Arc<AppState>, shutdown: CancellationToken) {
+    let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => run_due_reports(&state, Utc::now()).await,
+        }
+    }
+
+    tracing::info!("report scheduler stopped");
+}
+
+/// POST /reports - Save a scheduled report
+pub async fn create_report(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateReport>,
+) -> Result<Json<ReportSpec>, ApiError> {
+    if body.name.trim().is_empty() {
+        return Err(ApiError::Validation("name must not be empty".to_string()));
+    }
+    if body.metrics.is_empty() {
+        return Err(ApiError::Validation("metrics must not be empty".to_string()));
+    }
+    if let Some(unknown) = body.metrics.iter().find(|m| units::metric(m).is_none()) {
+        return Err(ApiError::Validation(format!("unknown metric {:?}", unknown)));
+    }
+
+    let now = Utc::now();
+    let next_run_at = body.schedule.parse::<CronSchedule>()?.next_after(now);
+    if next_run_at.is_none() {
+        return Err(ApiError::Validation(format!("schedule {:?} never runs", body.schedule)));
+    }
+
+    let spec = ReportSpec {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: body.name,
+        filters: body.filters,
+        metrics: body.metrics,
+        group_by: body.group_by,
+        format: body.format,
+        schedule: body.schedule,
+        locale: body.locale.unwrap_or_else(locale::current),
+        next_run_at,
+        created_at: now,
+    };
+    state.reports.insert(spec.clone());
+
+    Ok(Json(spec))
+}
+
+/// GET /reports - List report specs
+pub async fn list_reports(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ReportSpec>>, ApiError> {
+    Ok(Json(state.reports.list()))
+}
+
+/// GET /reports/{id}/runs - Past runs with download links, newest first
+pub async fn get_report_runs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<ReportRun>>, ApiError> {
+    if state.reports.get(&id).is_none() {
+        return Err(ApiError::NotFound(format!("report {}", id)));
+    }
+    Ok(Json(state.reports.runs(&id)))
+}
+
+/// GET /reports/{id}/runs/{run_id}/download - Rendered artifact of a run
+pub async fn download_report_run(
+    State(state): State<Arc<AppState>>,
+    Path((id, run_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let artifact = state
+        .reports
+        .artifact(&id, &run_id)
+        .ok_or_else(|| ApiError::NotFound(format!("report run {}", run_id)))?;
+
+    let disposition =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", artifact.file_name))
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(artifact.format.content_type())),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        artifact.bytes,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn spec(group_by: ReportGrouping, format: ReportFormat) -> ReportSpec {
+        ReportSpec {
+            id: "r1".to_string(),
+            name: "Weekly Spend".to_string(),
+            filters: ReportFilters::default(),
+            metrics: vec!["cost".to_string(), "ctr".to_string()],
+            group_by,
+            format,
+            schedule: "0 8 * * 1".to_string(),
+            locale: Locale::EnUs,
+            next_run_at: None,
+            created_at: at(2025, 11, 1, 0, 0),
+        }
+    }
+
+    fn campaign(
+        id: &str,
+        platform: Platform,
+        impressions: u64,
+        clicks: u64,
+        cost: f64,
+    ) -> Campaign {
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.metrics.impressions = impressions;
+        campaign.metrics.clicks = clicks;
+        campaign.metrics.cost = cost;
+        campaign
+    }
+
+    #[test]
+    fn test_cron_next_run() {
+        let weekdays: CronSchedule = "30 8 * * 1-5".parse().unwrap();
+        // Friday 2025-11-07 09:00 -> Monday 08:30
+        assert_eq!(weekdays.next_after(at(2025, 11, 7, 9, 0)), Some(at(2025, 11, 10, 8, 30)));
+
+        let quarter_hour: CronSchedule = "*/15 * * * *".parse().unwrap();
+        assert_eq!(quarter_hour.next_after(at(2025, 11, 7, 9, 0)), Some(at(2025, 11, 7, 9, 15)));
+
+        let monthly: CronSchedule = "@monthly".parse().unwrap();
+        assert_eq!(monthly.next_after(at(2025, 11, 7, 9, 0)), Some(at(2025, 12, 1, 0, 0)));
+
+        let never: CronSchedule = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at(2025, 11, 7, 9, 0)), None);
+
+        assert!("61 * * * *".parse::<CronSchedule>().is_err());
+        assert!("0 8 * *".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_rows_grouped_with_ratios_recomputed() {
+        let campaigns = vec![
+            campaign("g1", Platform::Google, 100, 10, 5.0),
+            campaign("g2", Platform::Google, 10_000, 100, 500.0),
+            campaign("m1", Platform::Meta, 1_000, 20, 40.0),
+        ];
+
+        let spec = spec(ReportGrouping::Platform, ReportFormat::Csv);
+        let table = build_table(&spec, &campaigns, at(2025, 11, 10, 8, 0));
+
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0].group, "google");
+        assert_eq!(table.rows[0].campaigns, 2);
+        assert_eq!(table.rows[0].values["cost"], 505.0);
+        assert!((table.rows[0].values["ctr"] - 110.0 / 10_100.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_csv_escapes_group_labels() {
+        let mut named = campaign("g1", Platform::Google, 100, 10, 5.0);
+        named.name = "Lançamento, \"Fase 2\"".to_string();
+        let spec = spec(ReportGrouping::Campaign, ReportFormat::Csv);
+        let table = build_table(&spec, &[named], at(2025, 11, 10, 8, 0));
+
+        let csv = String::from_utf8(render(&table, ReportFormat::Csv).unwrap()).unwrap();
+
+        assert_eq!(csv, "group,campaigns,cost,ctr\n\"Lançamento, \"\"Fase 2\"\"\",1,5,10\n");
+    }
+
+    #[test]
+    fn test_pt_br_exports_use_semicolons_and_decimal_commas() {
+        let mut named = campaign("g1", Platform::Google, 1_000, 15, 1234.5);
+        named.name = "Lançamento; Fase 2".to_string();
+        let mut spec = spec(ReportGrouping::Campaign, ReportFormat::Csv);
+        spec.locale = Locale::PtBr;
+        let table = build_table(&spec, &[named], at(2025, 11, 10, 8, 0));
+
+        let csv = String::from_utf8(render(&table, ReportFormat::Csv).unwrap()).unwrap();
+        assert_eq!(csv, "group;campaigns;cost;ctr\n\"Lançamento; Fase 2\";1;1234,5;1,5\n");
+
+        let lines = pdf_lines(&table);
+        assert!(lines[1].starts_with("Gerado em 10/11/2025 08:00"));
+        assert!(lines[3].contains("Custo"));
+        assert!(lines[4].ends_with("1.234,50            1,50%"));
+    }
+
+    #[test]
+    fn test_pdf_has_valid_cross_reference_table() {
+        let campaigns: Vec<Campaign> = (0..150)
+            .map(|i| campaign(&format!("g{}", i), Platform::Google, 100, 1, 1.0))
+            .collect();
+        let spec = spec(ReportGrouping::Campaign, ReportFormat::Pdf);
+
+        let (run, artifact) = generate_run(&spec, &campaigns, at(2025, 11, 10, 8, 0)).unwrap();
+        let pdf = artifact.bytes;
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(text.contains("/Count 3"));
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref"));
+        assert_eq!(artifact.file_name, "weekly-spend-20251110-0800.pdf");
+        assert_eq!(run.download_url, format!("/reports/r1/runs/{}/download", run.id));
+    }
+}
@@ -0,0 +1,453 @@
+//! # Scheduled Campaign Changes Example
+//!
+//! Black Friday campaigns go live at midnight local time and stop when the
+//! sale ends, and nobody should have to be awake to flip them.
+//! `POST /schedules` queues future status changes of a campaign:
+//!
+//! ```json
+//! {"platform": "google", "campaign_id": "123", "timezone": "America/Sao_Paulo",
+//!  "changes": [{"status": "ENABLED", "at": "2025-11-24T00:00:00"},
+//!              {"status": "PAUSED", "at": "2025-11-30T23:59:00"}]}
+//! ```
+//!
+//! - `at` is a local time in `timezone`, an IANA name defaulting to
+//!   `schedules.timezone`, and is converted to UTC when the change is
+//!   scheduled. A local time skipped by a daylight saving change is
+//!   rejected; one repeated by it means its first occurrence.
+//! - A scheduler checks for due changes every `SCHEDULER_TICK` and runs
+//!   them like a bulk request of the actor `schedule:<id>` in the tenant of
+//!   the author, so they are checked against change freezes and spend caps
+//!   and recorded in the audit log.
+//! - Changes that fell due while the service was down run once it is back.
+//!   When several changes of a campaign are due at once only the latest
+//!   runs; the others are marked `superseded`.
+//!
+//! `GET /schedules` lists changes soonest first (`?state=pending` for those
+//! yet to run) and `DELETE /schedules/{id}` cancels a pending one. Changes
+//! are written next to the metrics snapshot (`server.snapshot_path`, with a
+//! `.schedules.json` extension) whenever they change and loaded on startup.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio_util::sync::CancellationToken;
+
+use super::bulk::{execute_bulk, BulkOperation};
+use super::campaign_aggregator::{AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::freeze::Actor;
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Interval between two checks for due changes
+pub const SCHEDULER_TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Changes accepted in one request
+pub const MAX_CHANGES_PER_REQUEST: usize = 20;
+
+/// Finished (executed, failed, superseded or cancelled) changes kept
+pub const MAX_FINISHED_CHANGES: usize = 1_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleState {
+    Pending,
+    Executed,
+    /// Rejected by a freeze or spend cap, or by the platform
+    Failed,
+    /// A later change of the campaign was due at the same time
+    Superseded,
+    Cancelled,
+}
+
+/// Status change of a campaign at a given time
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScheduledChange {
+    pub id: String,
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    pub status: CampaignStatus,
+    pub run_at: DateTime<Utc>,
+    /// `run_at` as requested, in `timezone`
+    pub local_time: NaiveDateTime,
+    pub timezone: Tz,
+    pub state: ScheduleState,
+    /// Tenant of the author, whose spend cap and freezes apply
+    pub tenant: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// `ApiError` code when the change failed
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+}
+
+impl ScheduledChange {
+    /// Actor the change is made and audited as
+    pub fn actor(&self) -> Actor {
+        Actor::automation(&format!("schedule:{}", self.id), self.tenant.clone())
+    }
+
+    pub fn operation(&self) -> BulkOperation {
+        BulkOperation::SetStatus {
+            platform: self.platform.clone(),
+            campaign_id: self.campaign_id.clone(),
+            status: self.status.clone(),
+        }
+    }
+
+    fn global_id(&self) -> GlobalCampaignId {
+        GlobalCampaignId::new(self.platform.clone(), self.campaign_id.clone())
+    }
+}
+
+/// One change of a `POST /schedules` request
+#[derive(Debug, Deserialize)]
+pub struct StatusAt {
+    pub status: CampaignStatus,
+    /// Local time, e.g. `2025-11-24T00:00:00`
+    pub at: NaiveDateTime,
+}
+
+/// Request body for `POST /schedules`
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRequest {
+    pub platform: Platform,
+    pub campaign_id: CampaignId,
+    /// `schedules.timezone` when absent
+    pub timezone: Option<Tz>,
+    pub changes: Vec<StatusAt>,
+}
+
+/// UTC time of local time `at` in `timezone`
+///
+/// Times skipped by a daylight saving change are rejected; repeated ones
+/// mean their first occurrence.
+pub fn to_utc(timezone: Tz, at: NaiveDateTime) -> Result<DateTime<Utc>, ApiError> {
+    timezone
+        .from_local_datetime(&at)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .ok_or_else(|| ApiError::Validation(format!("{} does not exist in {}", at, timezone)))
+}
+
+impl ScheduleRequest {
+    fn into_changes(
+        self,
+        default_timezone: Tz,
+        actor: &Actor,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ScheduledChange>, ApiError> {
+        if self.changes.is_empty() || self.changes.len() > MAX_CHANGES_PER_REQUEST {
+            return Err(ApiError::Validation(format!(
+                "changes must hold 1 to {} changes",
+                MAX_CHANGES_PER_REQUEST
+            )));
+        }
+        let timezone = self.timezone.unwrap_or(default_timezone);
+
+        self.changes
+            .into_iter()
+            .map(|change| {
+                if !matches!(change.status, CampaignStatus::Enabled | CampaignStatus::Paused) {
+                    return Err(ApiError::Validation(format!(
+                        "status {} cannot be scheduled, only ENABLED or PAUSED",
+                        change.status.as_str()
+                    )));
+                }
+                let run_at = to_utc(timezone, change.at)?;
+                if run_at <= now {
+                    return Err(ApiError::Validation(format!(
+                        "{} {} is in the past",
+                        change.at, timezone
+                    )));
+                }
+                Ok(ScheduledChange {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    platform: self.platform.clone(),
+                    campaign_id: self.campaign_id.clone(),
+                    status: change.status,
+                    run_at,
+                    local_time: change.at,
+                    timezone,
+                    state: ScheduleState::Pending,
+                    tenant: actor.tenant.clone(),
+                    created_by: actor.id.clone(),
+                    created_at: now,
+                    finished_at: None,
+                    error_code: None,
+                    error: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Scheduled changes, pending and finished
+#[derive(Default)]
+pub struct ScheduleStore {
+    changes: RwLock<Vec<ScheduledChange>>,
+}
+
+impl ScheduleStore {
+    pub fn add(&self, changes: Vec<ScheduledChange>) {
+        let mut all = self.changes.write().unwrap();
+        all.extend(changes);
+        all.sort_by_key(|c| c.run_at);
+
+        let finished = all.iter().filter(|c| c.state != ScheduleState::Pending).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_CHANGES);
+        all.retain(|c| {
+            let drop = excess > 0 && c.state != ScheduleState::Pending;
+            excess -= drop as usize;
+            !drop
+        });
+    }
+
+    /// Changes in `state`, or all of them, soonest first
+    pub fn list(&self, state: Option<ScheduleState>) -> Vec<ScheduledChange> {
+        let changes = self.changes.read().unwrap();
+        changes.iter().filter(|c| state.iter().all(|s| *s == c.state)).cloned().collect()
+    }
+
+    /// Cancel a pending change
+    pub fn cancel(&self, id: &str, now: DateTime<Utc>) -> Result<ScheduledChange, ApiError> {
+        let mut changes = self.changes.write().unwrap();
+        let change = changes
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or_else(|| ApiError::NotFound(format!("scheduled change {}", id)))?;
+        if change.state != ScheduleState::Pending {
+            return Err(ApiError::Conflict(format!(
+                "scheduled change {} is no longer pending",
+                id
+            )));
+        }
+        change.state = ScheduleState::Cancelled;
+        change.finished_at = Some(now);
+        Ok(change.clone())
+    }
+
+    /// Pending changes due at `now`, the latest per campaign
+    ///
+    /// Earlier due changes of the same campaigns are marked superseded.
+    pub fn take_due(&self, now: DateTime<Utc>) -> Vec<ScheduledChange> {
+        let mut changes = self.changes.write().unwrap();
+        let mut latest: HashMap<GlobalCampaignId, usize> = HashMap::new();
+        for (index, change) in changes.iter().enumerate() {
+            if change.state == ScheduleState::Pending && change.run_at <= now {
+                // Sorted by `run_at`, so later indices are later changes
+                latest.insert(change.global_id(), index);
+            }
+        }
+
+        let mut due = Vec::new();
+        for (index, change) in changes.iter_mut().enumerate() {
+            if change.state != ScheduleState::Pending || change.run_at > now {
+                continue;
+            }
+            if latest.get(&change.global_id()) == Some(&index) {
+                due.push(change.clone());
+            } else {
+                change.state = ScheduleState::Superseded;
+                change.finished_at = Some(now);
+            }
+        }
+        due
+    }
+
+    /// Record the outcome of a change returned by `take_due`
+    pub fn finish(&self, id: &str, error: Option<(String, String)>, now: DateTime<Utc>) {
+        let mut changes = self.changes.write().unwrap();
+        if let Some(change) = changes.iter_mut().find(|c| c.id == id) {
+            change.state = if error.is_some() {
+                ScheduleState::Failed
+            } else {
+                ScheduleState::Executed
+            };
+            change.finished_at = Some(now);
+            (change.error_code, change.error) = error.unzip();
+        }
+    }
+
+    /// Write every change to `path` atomically (write to temp file, then rename)
+    pub fn persist(&self, path: &FsPath) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(&*self.changes.read().unwrap())?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Add changes previously written by `persist`
+    pub fn load(&self, path: &FsPath) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let loaded: Vec<ScheduledChange> = serde_json::from_slice(&bytes)?;
+
+        self.add(loaded);
+        Ok(())
+    }
+}
+
+/// File the scheduled changes are written to, next to the metrics snapshot
+pub fn schedules_path(snapshot_path: &FsPath) -> PathBuf {
+    snapshot_path.with_extension("schedules.json")
+}
+
+fn persist(state: &AppState) {
+    let Some(path) = state.config.server.snapshot_path.as_deref().map(schedules_path) else {
+        return;
+    };
+    if let Err(e) = state.schedules.persist(&path) {
+        tracing::error!(path = %path.display(), error = %e, "scheduled changes not persisted");
+    }
+}
+
+/// Run the changes due at `now`
+pub async fn run_due_changes(state: &AppState, now: DateTime<Utc>) {
+    let due = state.schedules.take_due(now);
+    if due.is_empty() {
+        return;
+    }
+
+    for change in due {
+        let report = execute_bulk(
+            &state.connectors,
+            vec![change.operation()],
+            &state.freezes,
+            &state.spend_caps,
+            &change.actor(),
+            &state.audit,
+        )
+        .await;
+        let error = report
+            .results
+            .into_iter()
+            .find(|r| !r.success)
+            .map(|r| (r.error_code.unwrap_or_default(), r.error.unwrap_or_default()));
+        tracing::info!(
+            schedule_id = %change.id,
+            campaign_id = %change.campaign_id,
+            status = change.status.as_str(),
+            failed = error.is_some(),
+            "scheduled change ran"
+        );
+        state.schedules.finish(&change.id, error, Utc::now());
+    }
+    persist(state);
+}
+
+/// Check for due changes every `SCHEDULER_TICK` until `shutdown` is cancelled
+pub async fn run_change_scheduler(state: Arc<AppState>, shutdown: CancellationToken) {
+    let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => run_due_changes(&state, Utc::now()).await,
+        }
+    }
+
+    tracing::info!("change scheduler stopped");
+}
+
+/// POST /schedules - Schedule status changes of a campaign
+pub async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    actor: Actor,
+    Json(body): Json<ScheduleRequest>,
+) -> Result<Json<Vec<ScheduledChange>>, ApiError> {
+    let connector = state
+        .connectors
+        .get(&body.platform)
+        .ok_or_else(|| ApiError::NotSupported(body.platform.as_str().to_string()))?;
+    let changes = body.into_changes(state.config.schedules.timezone, &actor, Utc::now())?;
+
+    let campaign_id = &changes[0].campaign_id;
+    if !connector.fetch_campaigns().await?.iter().any(|c| &c.id == campaign_id) {
+        return Err(ApiError::NotFound(format!("campaign {}", campaign_id)));
+    }
+
+    state.schedules.add(changes.clone());
+    persist(&state);
+
+    Ok(Json(changes))
+}
+
+/// Query parameters for `GET /schedules`
+#[derive(Debug, Deserialize)]
+pub struct ScheduleQuery {
+    pub state: Option<ScheduleState>,
+}
+
+/// GET /schedules - Scheduled changes, soonest first
+pub async fn list_schedules(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScheduleQuery>,
+) -> Result<Json<Vec<ScheduledChange>>, ApiError> {
+    Ok(Json(state.schedules.list(query.state)))
+}
+
+/// DELETE /schedules/{id} - Cancel a pending change
+pub async fn cancel_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ScheduledChange>, ApiError> {
+    let cancelled = state.schedules.cancel(&id, Utc::now())?;
+    persist(&state);
+
+    Ok(Json(cancelled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn local(month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_local_times_become_utc_and_latest_due_change_wins() {
+        // No daylight saving in Brazil: midnight BRT is 03:00 UTC
+        let enable_at = to_utc(Tz::America__Sao_Paulo, local(11, 24, 0)).unwrap();
+        assert_eq!(enable_at, Utc.with_ymd_and_hms(2025, 11, 24, 3, 0, 0).unwrap());
+        // 02:00 on 9 March 2025 does not exist in New York
+        assert!(to_utc(Tz::America__New_York, local(3, 9, 2)).is_err());
+
+        let request = ScheduleRequest {
+            platform: Platform::Google,
+            campaign_id: "123".into(),
+            timezone: None,
+            changes: vec![
+                StatusAt { status: CampaignStatus::Paused, at: local(11, 30, 23) },
+                StatusAt { status: CampaignStatus::Enabled, at: local(11, 24, 0) },
+            ],
+        };
+        let actor = Actor::automation("ana", Some("acme".to_string()));
+        let created = Utc.with_ymd_and_hms(2025, 11, 1, 0, 0, 0).unwrap();
+        let changes = request.into_changes(Tz::America__Sao_Paulo, &actor, created).unwrap();
+
+        let store = ScheduleStore::default();
+        store.add(changes);
+        let pending = store.list(Some(ScheduleState::Pending));
+        assert_eq!(pending[0].status, CampaignStatus::Enabled, "soonest first");
+
+        // Down over the whole sale: only the pause runs
+        let due = store.take_due(Utc.with_ymd_and_hms(2025, 12, 2, 0, 0, 0).unwrap());
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].status, CampaignStatus::Paused);
+        assert_eq!(store.list(Some(ScheduleState::Superseded)).len(), 1);
+
+        store.finish(&due[0].id, None, Utc::now());
+        assert!(store.cancel(&due[0].id, Utc::now()).is_err());
+        assert!(store.list(Some(ScheduleState::Pending)).is_empty());
+    }
+}
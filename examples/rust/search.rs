@@ -0,0 +1,221 @@
+//! # Campaign Search Example
+//!
+//! `GET /campaigns/search?q=black+friday` finds campaigns by name or label
+//! across every platform, tolerating typos and word order. Matching is
+//! trigram based, like PostgreSQL's `pg_trgm`:
+//!
+//! - text is lower-cased, common Latin accents are folded (`promoção`
+//!   matches `promocao`) and punctuation separates words;
+//! - each word is padded with two leading spaces and one trailing space
+//!   and cut into three-character windows;
+//! - a campaign scores the share of the query's trigrams found in its name
+//!   or in one of its labels, whichever is highest.
+//!
+//! Campaigns scoring at least `MIN_SCORE` are returned by score, then by
+//! month-to-date spend (highest first).
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{
+    aggregate_campaigns, AppState, Campaign, CampaignStatus, Platform,
+};
+use super::error::ApiError;
+use super::ids::CampaignId;
+
+/// Share of the query's trigrams a campaign must contain to match
+pub const MIN_SCORE: f64 = 0.5;
+
+/// Results returned when `limit` is not given
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Largest `limit` accepted
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Query parameters for `GET /campaigns/search`
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// Campaign field the best match was found in
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "field", content = "value", rename_all = "snake_case")]
+pub enum MatchedField {
+    Name,
+    /// The label that matched
+    Label(String),
+}
+
+/// One search result
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchHit {
+    pub id: CampaignId,
+    pub platform: Platform,
+    pub name: String,
+    pub status: CampaignStatus,
+    pub labels: Vec<String>,
+    /// Month-to-date spend, the tie-breaker between equal scores
+    pub spend: f64,
+    pub currency: String,
+    /// Share of the query's trigrams found, from `MIN_SCORE` to 1
+    pub score: f64,
+    pub matched: MatchedField,
+}
+
+/// Fold the accents of Portuguese and Spanish text into plain ASCII letters
+fn fold(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        other => other,
+    }
+}
+
+/// Trigrams of `text`, `pg_trgm` style
+pub fn trigrams(text: &str) -> HashSet<String> {
+    let normalized: String = text.to_lowercase().chars().map(fold).collect();
+    let mut trigrams = HashSet::new();
+
+    for word in normalized.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let padded: Vec<char> = format!("  {} ", word).chars().collect();
+        for window in padded.windows(3) {
+            trigrams.insert(window.iter().collect());
+        }
+    }
+
+    trigrams
+}
+
+/// Share of `query`'s trigrams present in `text`
+fn score(query: &HashSet<String>, text: &str) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let text = trigrams(text);
+    query.intersection(&text).count() as f64 / query.len() as f64
+}
+
+/// Best match of `query` in the name or labels of `campaign`
+fn best_match(query: &HashSet<String>, campaign: &Campaign) -> (f64, MatchedField) {
+    campaign
+        .labels
+        .iter()
+        .map(|label| (score(query, label), MatchedField::Label(label.clone())))
+        .fold((score(query, &campaign.name), MatchedField::Name), |best, candidate| {
+            if candidate.0 > best.0 {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+/// Campaigns matching `q`, best first, at most `limit`
+pub fn search_campaigns(campaigns: &[Campaign], q: &str, limit: usize) -> Vec<SearchHit> {
+    let query = trigrams(q);
+
+    let mut hits: Vec<SearchHit> = campaigns
+        .iter()
+        .filter_map(|campaign| {
+            let (score, matched) = best_match(&query, campaign);
+            (score >= MIN_SCORE).then(|| SearchHit {
+                id: campaign.id.clone(),
+                platform: campaign.platform.clone(),
+                name: campaign.name.clone(),
+                status: campaign.status.clone(),
+                labels: campaign.labels.clone(),
+                spend: campaign.metrics.cost,
+                currency: campaign.currency.clone(),
+                score,
+                matched,
+            })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.spend.total_cmp(&a.spend))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+/// GET /campaigns/search - Campaigns whose name or labels match `q`
+pub async fn get_campaign_search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchHit>>, ApiError> {
+    if trigrams(&query.q).is_empty() {
+        return Err(ApiError::Validation("q must contain a letter or digit".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if limit == 0 || limit > MAX_SEARCH_LIMIT {
+        return Err(ApiError::Validation(format!(
+            "limit must be between 1 and {}",
+            MAX_SEARCH_LIMIT
+        )));
+    }
+
+    let campaigns = aggregate_campaigns(&state).await;
+
+    Ok(Json(search_campaigns(&campaigns, &query.q, limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(id: &str, name: &str, cost: f64) -> Campaign {
+        let mut campaign = Campaign::sample(id, Platform::Google);
+        campaign.name = name.to_string();
+        campaign.metrics.cost = cost;
+        campaign
+    }
+
+    fn ids(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|h| h.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ranks_by_relevance_then_spend() {
+        let campaigns = vec![
+            campaign("g1", "Search | Brand | BR", 900.0),
+            campaign("g2", "[G] BLACK FRIDAY | Retargeting", 100.0),
+            campaign("g3", "Black Friday Prospecting", 300.0),
+            campaign("g4", "Black Week Teaser", 500.0),
+        ];
+
+        let hits = search_campaigns(&campaigns, "black friday", 10);
+
+        // Both full matches, higher spend first; "Black Week" shares too few trigrams
+        assert_eq!(ids(&hits), ["g3", "g2"]);
+        assert_eq!(hits[0].score, 1.0);
+    }
+
+    #[test]
+    fn test_tolerates_typos_and_accents_and_matches_labels() {
+        let mut labeled = campaign("g1", "Always on", 10.0);
+        labeled.labels = vec!["Promoção de Natal".to_string()];
+        let campaigns = vec![labeled, campaign("g2", "Imoveis Leads SP", 20.0)];
+
+        let hits = search_campaigns(&campaigns, "promocao natal", 10);
+        assert_eq!(ids(&hits), ["g1"]);
+        assert_eq!(hits[0].matched, MatchedField::Label("Promoção de Natal".to_string()));
+
+        let hits = search_campaigns(&campaigns, "imovies", 10);
+        assert_eq!(ids(&hits), ["g2"]);
+    }
+}
@@ -0,0 +1,358 @@
+//! # Audience Segments Example
+//!
+//! Tenants ingest first-party CRM / conversion records and define
+//! segments over them with composable rules (e.g. "purchased in the last
+//! 90 days AND lifetime value > 1000"). Segment members are hashed
+//! (SHA-256, same normalization as the gateway's enhanced conversions)
+//! and uploaded to Google Customer Match and Meta Custom Audiences through
+//! the gateway's upload pipeline.
+
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::call_budget;
+use super::campaign_aggregator::AppState;
+use super::error::ApiError;
+
+/// First-party customer record ingested from CRM or conversion uploads
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomerRecord {
+    pub tenant_id: String,
+    pub customer_id: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub last_purchase_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub purchase_count: u32,
+    #[serde(default)]
+    pub lifetime_value: f64,
+}
+
+/// Segment membership rule
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SegmentRule {
+    PurchasedWithinDays { days: i64 },
+    LifetimeValueAbove { amount: f64 },
+    PurchaseCountAtLeast { count: u32 },
+    And { rules: Vec<SegmentRule> },
+    Or { rules: Vec<SegmentRule> },
+    Not { rule: Box<SegmentRule> },
+}
+
+impl SegmentRule {
+    /// Whether `record` belongs to the segment at time `now`
+    pub fn matches(&self, record: &CustomerRecord, now: DateTime<Utc>) -> bool {
+        match self {
+            SegmentRule::PurchasedWithinDays { days } => record
+                .last_purchase_at
+                .map(|at| at >= now - Duration::days(*days))
+                .unwrap_or(false),
+            SegmentRule::LifetimeValueAbove { amount } => record.lifetime_value > *amount,
+            SegmentRule::PurchaseCountAtLeast { count } => record.purchase_count >= *count,
+            SegmentRule::And { rules } => rules.iter().all(|r| r.matches(record, now)),
+            SegmentRule::Or { rules } => rules.iter().any(|r| r.matches(record, now)),
+            SegmentRule::Not { rule } => !rule.matches(record, now),
+        }
+    }
+}
+
+/// Tenant-defined audience segment
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Segment {
+    pub id: String,
+    pub tenant_id: String,
+    pub name: String,
+    pub rule: SegmentRule,
+    pub created_at: DateTime<Utc>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+}
+
+/// Request body for `POST /segments`
+#[derive(Debug, Deserialize)]
+pub struct CreateSegment {
+    pub tenant_id: String,
+    pub name: String,
+    pub rule: SegmentRule,
+}
+
+/// Hashed identifiers uploaded to the platforms
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AudienceUpload {
+    pub audience_name: String,
+    pub hashed_emails: Vec<String>,
+    pub hashed_phones: Vec<String>,
+}
+
+/// Result of syncing a segment to one platform
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlatformSyncResult {
+    pub platform: String,
+    pub uploaded: usize,
+    pub error: Option<String>,
+}
+
+/// Segment membership summary returned by the preview endpoint
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SegmentPreview {
+    pub segment_id: String,
+    pub members: usize,
+    pub with_email: usize,
+    pub with_phone: usize,
+}
+
+/// CRM records and segment definitions
+#[derive(Default)]
+pub struct SegmentStore {
+    records: RwLock<HashMap<(String, String), CustomerRecord>>,
+    segments: RwLock<HashMap<String, Segment>>,
+}
+
+impl SegmentStore {
+    /// Insert or replace records, keyed by tenant and customer ID
+    pub fn ingest(&self, records: Vec<CustomerRecord>) -> usize {
+        let mut store = self.records.write().unwrap();
+        let count = records.len();
+        for record in records {
+            store.insert((record.tenant_id.clone(), record.customer_id.clone()), record);
+        }
+        count
+    }
+
+    pub fn add_segment(&self, segment: Segment) {
+        self.segments
+            .write()
+            .unwrap()
+            .insert(segment.id.clone(), segment);
+    }
+
+    pub fn segment(&self, id: &str) -> Option<Segment> {
+        self.segments.read().unwrap().get(id).cloned()
+    }
+
+    pub fn segments(&self) -> Vec<Segment> {
+        self.segments.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn mark_synced(&self, id: &str, at: DateTime<Utc>) {
+        if let Some(segment) = self.segments.write().unwrap().get_mut(id) {
+            segment.last_synced_at = Some(at);
+        }
+    }
+
+    /// Records of the segment's tenant matching its rule
+    pub fn members(&self, segment: &Segment, now: DateTime<Utc>) -> Vec<CustomerRecord> {
+        self.records
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.tenant_id == segment.tenant_id && segment.rule.matches(r, now))
+            .cloned()
+            .collect()
+    }
+}
+
+/// SHA-256 of a trimmed, lowercased email
+pub fn hash_email(email: &str) -> String {
+    hex::encode(Sha256::digest(email.trim().to_lowercase().as_bytes()))
+}
+
+/// SHA-256 of the digits of a phone number
+pub fn hash_phone(phone: &str) -> String {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    hex::encode(Sha256::digest(digits.as_bytes()))
+}
+
+/// Build the hashed upload payload for a set of members
+pub fn build_upload(audience_name: &str, members: &[CustomerRecord]) -> AudienceUpload {
+    AudienceUpload {
+        audience_name: audience_name.to_string(),
+        hashed_emails: members
+            .iter()
+            .filter_map(|m| m.email.as_deref())
+            .filter(|e| !e.trim().is_empty())
+            .map(hash_email)
+            .collect(),
+        hashed_phones: members
+            .iter()
+            .filter_map(|m| m.phone.as_deref())
+            .filter(|p| p.chars().any(|c| c.is_ascii_digit()))
+            .map(hash_phone)
+            .collect(),
+    }
+}
+
+/// Upload a segment to one platform through the gateway
+async fn upload_audience(
+    state: &AppState,
+    platform: &str,
+    upload: &AudienceUpload,
+) -> Result<(), reqwest::Error> {
+    let url = format!("{}/v1/{}/audiences/upload", state.gateway_url, platform);
+
+    state.http_client
+        .post(&url)
+        .json(upload)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// POST /crm/records - Ingest CRM / conversion records
+pub async fn ingest_records(
+    State(state): State<Arc<AppState>>,
+    Json(records): Json<Vec<CustomerRecord>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let ingested = state.segments.ingest(records);
+    Ok(Json(serde_json::json!({ "ingested": ingested })))
+}
+
+/// POST /segments - Define a segment
+pub async fn create_segment(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateSegment>,
+) -> Result<Json<Segment>, ApiError> {
+    let segment = Segment {
+        id: uuid::Uuid::new_v4().to_string(),
+        tenant_id: body.tenant_id,
+        name: body.name,
+        rule: body.rule,
+        created_at: Utc::now(),
+        last_synced_at: None,
+    };
+
+    state.segments.add_segment(segment.clone());
+
+    Ok(Json(segment))
+}
+
+/// GET /segments - List segments
+pub async fn list_segments(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Segment>>, ApiError> {
+    Ok(Json(state.segments.segments()))
+}
+
+/// GET /segments/{id}/preview - Current membership counts
+pub async fn preview_segment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<SegmentPreview>, ApiError> {
+    let segment = state
+        .segments
+        .segment(&id)
+        .ok_or_else(|| ApiError::NotFound(format!("segment {}", id)))?;
+    let members = state.segments.members(&segment, Utc::now());
+
+    Ok(Json(SegmentPreview {
+        segment_id: id,
+        members: members.len(),
+        with_email: members.iter().filter(|m| m.email.is_some()).count(),
+        with_phone: members.iter().filter(|m| m.phone.is_some()).count(),
+    }))
+}
+
+/// POST /segments/{id}/sync - Upload the segment to Google and Meta
+pub async fn sync_segment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<PlatformSyncResult>>, ApiError> {
+    let segment = state
+        .segments
+        .segment(&id)
+        .ok_or_else(|| ApiError::NotFound(format!("segment {}", id)))?;
+    call_budget::charge(2)?;
+
+    let now = Utc::now();
+    let members = state.segments.members(&segment, now);
+    let upload = build_upload(&format!("ibvi_{}", segment.name), &members);
+    let uploaded = upload.hashed_emails.len() + upload.hashed_phones.len();
+
+    let (google, meta) = tokio::join!(
+        upload_audience(&state, "google", &upload),
+        upload_audience(&state, "meta", &upload)
+    );
+
+    let results: Vec<PlatformSyncResult> = [("google", google), ("meta", meta)]
+        .into_iter()
+        .map(|(platform, result)| PlatformSyncResult {
+            platform: platform.to_string(),
+            uploaded: if result.is_ok() { uploaded } else { 0 },
+            error: result.err().map(|e| e.to_string()),
+        })
+        .collect();
+
+    if results.iter().any(|r| r.error.is_none()) {
+        state.segments.mark_synced(&id, now);
+    }
+
+    Ok(Json(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, days_ago: Option<i64>, value: f64) -> CustomerRecord {
+        CustomerRecord {
+            tenant_id: "t1".to_string(),
+            customer_id: id.to_string(),
+            email: Some(format!(" {}@Example.com ", id)),
+            phone: Some("+55 (11) 99999-0000".to_string()),
+            last_purchase_at: days_ago.map(|d| Utc::now() - Duration::days(d)),
+            purchase_count: 1,
+            lifetime_value: value,
+        }
+    }
+
+    #[test]
+    fn test_rule_combination() {
+        let rule = SegmentRule::And {
+            rules: vec![
+                SegmentRule::PurchasedWithinDays { days: 90 },
+                SegmentRule::LifetimeValueAbove { amount: 1000.0 },
+            ],
+        };
+        let now = Utc::now();
+
+        assert!(rule.matches(&record("a", Some(10), 1500.0), now));
+        assert!(!rule.matches(&record("b", Some(120), 1500.0), now));
+        assert!(!rule.matches(&record("c", Some(10), 500.0), now));
+        assert!(!rule.matches(&record("d", None, 5000.0), now));
+    }
+
+    #[test]
+    fn test_hashing_normalizes_identifiers() {
+        assert_eq!(hash_email(" Ana@Example.com "), hash_email("ana@example.com"));
+        assert_eq!(hash_phone("+55 (11) 99999-0000"), hash_phone("5511999990000"));
+        assert_eq!(hash_email("a").len(), 64);
+    }
+
+    #[test]
+    fn test_members_are_scoped_to_tenant() {
+        let store = SegmentStore::default();
+        let mut other = record("x", Some(1), 10.0);
+        other.tenant_id = "t2".to_string();
+        store.ingest(vec![record("a", Some(1), 10.0), other]);
+
+        let segment = Segment {
+            id: "s1".to_string(),
+            tenant_id: "t1".to_string(),
+            name: "recent".to_string(),
+            rule: SegmentRule::PurchasedWithinDays { days: 30 },
+            created_at: Utc::now(),
+            last_synced_at: None,
+        };
+
+        assert_eq!(store.members(&segment, Utc::now()).len(), 1);
+    }
+}
@@ -0,0 +1,584 @@
+//! # Server Example
+//!
+//! Builds the router, runs the background sync worker, change scheduler and
+//! report scheduler and shuts them down cleanly for Kubernetes rollouts: on
+//! SIGTERM or SIGINT the server stops accepting connections, in-flight
+//! requests get a drain window to finish, the background tasks complete
+//! their current pass, and the metrics store is flushed to disk before the
+//! process exits. With `backup.url` set, a scheduler also writes encrypted
+//! backups, see `backup`, and with `warehouse.url` set an exporter writes
+//! daily snapshots to the analytics bucket, see `warehouse`.
+
+use axum::{
+    middleware,
+    routing::{delete, get, patch, post, put},
+    Router,
+};
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::backup::{run_backup_scheduler, BackupTarget};
+use super::call_budget::{enforce_call_budget, DEFAULT_CALL_BUDGET};
+use super::campaign_aggregator::{get_campaign_summary, get_campaigns, AppState};
+use super::compression::compression_layer;
+use super::config::{AppConfig, CompressionConfig};
+use super::idempotency::enforce_idempotency;
+use super::sync::{run_sync_worker, SYNC_INTERVAL};
+use super::{
+    accounts, ads, alerts, annotations, anomalies, api_usage, apply, archive, assets,
+    attribution, audiences, audit, automation, backfill, benchmarks, bidding, breakdowns, bulk,
+    cache_policy, campaign_groups, changelog, columnar, concurrency, conversions, cost_guard,
+    creative_fatigue, credentials, dictionary, digest, experiments, freeze, frequency,
+    freshness, graph, health, history, hydration, insertion_orders, keywords, labels,
+    latency_budget, launch, lenient, load_shedding, locale, naming, negatives, normalization,
+    pacing, products, profiling, quota, raw, rbac, reauth, recommendations, refresh, region,
+    reload, schedules, search, segments, spec, spend_caps, timezones, timing, units, updates,
+    utm, versioning, warmup, ws,
+};
+#[cfg(feature = "reports")]
+use super::reports;
+#[cfg(feature = "warehouse")]
+use super::warehouse::{self, WarehouseExporter};
+
+/// Time in-flight requests get to finish after a shutdown signal
+///
+/// Kept below Kubernetes' default 30s `terminationGracePeriodSeconds` so the
+/// flush still runs before the pod is killed.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Time each background task gets to finish a pass that is already running
+pub const WORKER_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runtime options of the server
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    pub addr: SocketAddr,
+    /// Metrics store snapshot, loaded on startup and flushed on shutdown
+    pub snapshot_path: Option<PathBuf>,
+    pub drain_timeout: Duration,
+    /// Gateway calls a single request may trigger
+    pub upstream_call_budget: usize,
+    pub compression: CompressionConfig,
+    /// Expose the per-stage latency breakdown as `X-Timing`
+    pub timing_header: bool,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            snapshot_path: None,
+            drain_timeout: DRAIN_TIMEOUT,
+            upstream_call_budget: DEFAULT_CALL_BUDGET,
+            compression: CompressionConfig::default(),
+            timing_header: false,
+        }
+    }
+}
+
+impl ServerOptions {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            addr: config.server.addr,
+            snapshot_path: config.server.snapshot_path.clone(),
+            drain_timeout: Duration::from_secs(config.server.drain_timeout_secs),
+            upstream_call_budget: config.server.upstream_call_budget,
+            compression: config.server.compression.clone(),
+            timing_header: config.server.timing_header,
+        }
+    }
+}
+
+/// Router with every example route
+pub fn build_router(state: Arc<AppState>, options: &ServerOptions) -> Router {
+    let usage = state.usage.clone();
+    let tenant_usage = state.api_usage.clone();
+    let idempotency = state.idempotency.clone();
+    let quota_tracker = state.quota.clone();
+    let regions = state.regions.clone();
+    let config = state.config.clone();
+    let shedder = state.load_shedder.clone();
+
+    let router = Router::new()
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/campaigns", get(get_campaigns).post(launch::launch_campaigns))
+        .route("/campaigns/summary", get(get_campaign_summary))
+        .route("/campaigns/breakdown", get(columnar::get_campaign_breakdown))
+        .route("/campaigns/pacing", get(pacing::get_campaign_pacing))
+        .route("/campaigns/anomalies", get(anomalies::get_anomalies))
+        .route("/campaigns/benchmark", get(benchmarks::get_campaign_benchmarks))
+        .route("/campaigns/naming-violations", get(naming::get_naming_violations))
+        .route("/campaigns/utm-audit", get(utm::get_utm_audit))
+        .route("/campaigns/search", get(search::get_campaign_search))
+        .route("/campaigns/archived", get(archive::get_archived_campaigns))
+        .route("/campaigns/stream", get(updates::stream_updates))
+        .route("/ws", get(ws::ws_handler))
+        .route("/campaigns/bulk", post(bulk::bulk_mutate))
+        .route("/bulk/{batch_id}/rollback", post(bulk::rollback_bulk))
+        .route("/campaigns/{id}/detail", get(hydration::get_campaign_detail))
+        .route("/campaigns/{id}/graph", get(graph::get_campaign_graph))
+        .route("/campaigns/{id}/breakdowns", get(breakdowns::get_campaign_breakdowns))
+        .route("/campaigns/google/{id}/keywords", get(keywords::get_keywords))
+        // `/campaigns/{platform}/{id}/ads`; the router needs one parameter
+        // name per path position, so the platform segment is `{id}` here
+        .route("/campaigns/{id}/{campaign_id}/ads", get(ads::get_campaign_ads))
+        .route("/campaigns/{id}/{campaign_id}/attribution", get(attribution::get_attribution))
+        .route("/campaigns/{id}/{campaign_id}/history", get(history::get_campaign_history))
+        .route("/campaigns/{id}/{campaign_id}/changes", get(changelog::get_campaign_changes))
+        .route(
+            "/campaigns/{id}/{campaign_id}/conversions",
+            get(conversions::get_campaign_conversions),
+        )
+        .route("/campaigns/{id}/{campaign_id}/bidding", patch(bidding::patch_campaign_bidding))
+        .route(
+            "/campaigns/{id}/{campaign_id}/labels",
+            get(labels::get_campaign_labels).post(labels::add_campaign_label),
+        )
+        .route(
+            "/campaigns/{id}/{campaign_id}/labels/{label}",
+            delete(labels::remove_campaign_label),
+        )
+        .route(
+            "/campaigns/{id}/{campaign_id}/exclusions",
+            get(negatives::get_campaign_exclusions).patch(negatives::update_campaign_exclusions),
+        )
+        .route(
+            "/campaigns/{id}/{campaign_id}/products",
+            get(products::get_campaign_products),
+        )
+        .route("/campaigns/{id}/{campaign_id}/raw", get(raw::get_raw_campaign))
+        .route(
+            "/campaigns/{id}/{campaign_id}/annotations",
+            get(annotations::list_annotations).post(annotations::add_annotation),
+        )
+        .route(
+            "/campaigns/{id}/{campaign_id}/annotations/{annotation_id}",
+            delete(annotations::delete_annotation),
+        )
+        .route("/labels", get(labels::list_labels))
+        .route("/search-terms", get(keywords::get_search_terms))
+        .route("/exclusions/conflicts", get(negatives::get_exclusion_conflicts))
+        .route(
+            "/insertion-orders",
+            post(insertion_orders::create_insertion_order)
+                .get(insertion_orders::list_insertion_orders),
+        )
+        .route(
+            "/insertion-orders/{id}/burndown",
+            get(insertion_orders::get_burn_down),
+        )
+        .route(
+            "/alerts/rules",
+            post(alerts::create_alert_rule).get(alerts::list_alert_rules),
+        )
+        .route("/alerts/history", get(alerts::get_alert_history))
+        .route("/digest", get(digest::get_digest))
+        .route(
+            "/automation/rules",
+            post(automation::create_automation_rule).get(automation::list_automation_rules),
+        )
+        .route("/automation/rules/preview", post(automation::preview_automation_rule))
+        .route("/automation/rules/{id}", delete(automation::delete_automation_rule))
+        .route("/automation/log", get(automation::get_automation_log))
+        .route("/schedules", post(schedules::create_schedule).get(schedules::list_schedules))
+        .route("/schedules/{id}", delete(schedules::cancel_schedule))
+        .route(
+            "/experiments",
+            post(experiments::create_experiment).get(experiments::list_experiments),
+        )
+        .route("/experiments/{id}/results", get(experiments::get_experiment_results))
+        .route("/insights/frequency", get(frequency::get_frequency_insight))
+        .route("/creatives/fatigue", get(creative_fatigue::get_creative_fatigue))
+        .route("/assets/performance", get(assets::get_asset_performance))
+        .route(
+            "/naming/conventions",
+            get(naming::get_naming_conventions).put(naming::put_naming_conventions),
+        )
+        .route(
+            "/utm/convention",
+            get(utm::get_utm_convention).put(utm::put_utm_convention),
+        )
+        .route("/campaign-groups", get(campaign_groups::get_campaign_groups))
+        .route(
+            "/campaign-groups/rules",
+            get(campaign_groups::get_matching_rules).put(campaign_groups::put_matching_rules),
+        )
+        .route("/crm/records", post(segments::ingest_records))
+        .route(
+            "/segments",
+            post(segments::create_segment).get(segments::list_segments),
+        )
+        .route("/segments/{id}/preview", get(segments::preview_segment))
+        .route("/segments/{id}/sync", post(segments::sync_segment))
+        .route("/audiences", get(audiences::list_audiences))
+        .route("/audiences/overlap", get(audiences::get_self_competition))
+        .route("/audiences/{id}/sizes", get(audiences::get_audience_sizes))
+        .route(
+            "/backfills",
+            post(backfill::create_backfill).get(backfill::list_backfills),
+        )
+        .route("/backfills/{id}", get(backfill::get_backfill))
+        .route("/backfills/{id}/resume", post(backfill::resume_backfill))
+        .route("/refresh", post(refresh::create_refresh))
+        .route("/jobs/{id}", get(refresh::get_job))
+        .route("/accounts/spec", get(spec::export_spec))
+        .route("/accounts/spec/diff", post(spec::diff_spec))
+        .route("/accounts/spec/plan", post(apply::plan_spec))
+        .route("/accounts/spec/apply", post(apply::apply_spec))
+        .route(
+            "/freeze-windows",
+            get(freeze::list_freeze_windows).post(freeze::create_freeze_window),
+        )
+        .route("/freeze-windows/overrides", get(freeze::list_freeze_overrides))
+        .route("/freeze-windows/{id}", delete(freeze::delete_freeze_window))
+        .route("/spend-caps", get(spend_caps::get_spend_caps))
+        .route("/spend-caps/overrides", get(spend_caps::list_spend_cap_overrides))
+        .route("/cost-guard/trips", get(cost_guard::list_cost_guard_trips))
+        .route("/cost-guard/trips/{id}/re-enable", post(cost_guard::re_enable_campaign))
+        .route("/audit", get(audit::get_audit_log))
+        .route("/accounts", get(accounts::get_accounts))
+        .route("/accounts/summary", get(accounts::get_account_summary))
+        .route("/quota", get(quota::get_quota))
+        .route("/internal/quotas", get(quota::get_quota))
+        .route("/internal/concurrency", get(concurrency::get_concurrency))
+        .route("/internal/load-shedding", get(load_shedding::get_load_shedding))
+        .route("/internal/raw-captures", get(raw::list_raw_captures))
+        .route("/internal/decode-errors", get(lenient::get_decode_errors))
+        .route(
+            "/rbac/assignments",
+            get(rbac::list_role_assignments).put(rbac::assign_role),
+        )
+        .route("/rbac/assignments/{actor}", delete(rbac::revoke_role))
+        .route("/credentials/health", get(reauth::get_credential_health))
+        .route("/connections", get(credentials::list_connections))
+        .route(
+            "/connections/{platform}",
+            put(credentials::put_connection).delete(credentials::delete_connection),
+        )
+        .route("/metrics/schema", get(units::get_metric_schema))
+        .route("/meta/schema", get(dictionary::get_data_dictionary))
+        .route(
+            "/recommendations/budget",
+            get(recommendations::get_budget_recommendations),
+        )
+        .route("/admin/regions", get(region::get_regions))
+        .route("/admin/regions/audit", get(region::get_cross_region_audit))
+        .route("/admin/runtime", get(profiling::get_runtime_stats))
+        .route("/admin/profile", get(profiling::get_cpu_profile))
+        .route("/admin/normalization", get(normalization::get_normalization))
+        .route("/admin/normalization/reload", post(normalization::reload_normalization))
+        .route("/admin/reload", post(reload::reload))
+        .route("/admin/usage", get(api_usage::get_api_usage));
+
+    #[cfg(feature = "reports")]
+    let router = router
+        .route(
+            "/reports",
+            post(reports::create_report).get(reports::list_reports),
+        )
+        .route("/reports/{id}/runs", get(reports::get_report_runs))
+        .route(
+            "/reports/{id}/runs/{run_id}/download",
+            get(reports::download_report_run),
+        );
+
+    #[cfg(feature = "warehouse")]
+    let router = router.route("/admin/warehouse/exports", post(warehouse::export_snapshots));
+
+    // Unversioned paths are the `/v1` routes, kept for existing clients
+    let router = Router::new()
+        .nest("/v1", router.clone())
+        .nest("/v2", versioning::v2_routes())
+        .merge(router);
+
+    // Route layers only wrap the routes added above
+    router
+        .route_layer(middleware::from_extractor_with_state::<rbac::Authorized, _>(
+            state.clone(),
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            config.clone(),
+            cache_policy::apply_cache_policy,
+        ))
+        // Outside the cache policy, so degraded responses are never cached
+        .route_layer(middleware::from_fn_with_state(
+            config.clone(),
+            latency_budget::enforce_latency_budget,
+        ))
+        // Outermost route layer, so shed requests cost no authorization
+        .route_layer(middleware::from_fn_with_state(shedder, load_shedding::shed_load))
+        .with_state(state)
+        .layer(middleware::from_fn_with_state(idempotency, enforce_idempotency))
+        .layer(middleware::from_fn_with_state(usage, warmup::record_usage))
+        .layer(middleware::from_fn(lenient::report_skipped_records))
+        .layer(middleware::from_fn(freshness::attach_freshness))
+        .layer(middleware::from_fn(locale::negotiate_locale))
+        .layer(middleware::from_fn_with_state(config.clone(), timezones::scope_timezone))
+        .layer(middleware::from_fn(credentials::scope_tenant))
+        // Inside the call budget middleware, which scopes the request's budget
+        .layer(middleware::from_fn_with_state(
+            quota_tracker,
+            quota::attach_quota_warnings,
+        ))
+        // Outside the layers rewriting bodies, so the bytes sent are counted
+        .layer(middleware::from_fn_with_state(tenant_usage, api_usage::record_api_usage))
+        .layer(middleware::from_fn_with_state(
+            options.upstream_call_budget,
+            enforce_call_budget,
+        ))
+        // Outside the call budget and idempotency layers: a forwarded request
+        // is metered and deduplicated by its home region's instance
+        .layer(middleware::from_fn_with_state(regions, region::route_to_home_region))
+        // Outside every layer answering with errors, so `v2` ones get their envelope
+        .layer(middleware::from_fn_with_state(config, versioning::apply_version_policy))
+        .layer(compression_layer(&options.compression))
+        .layer(middleware::from_fn_with_state(
+            options.timing_header,
+            timing::record_timings,
+        ))
+}
+
+/// Resolve once SIGINT (Ctrl+C) or SIGTERM is received
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("received SIGINT"),
+        _ = terminate => tracing::info!("received SIGTERM"),
+    }
+}
+
+/// Flush in-memory state before exit
+pub fn on_shutdown(state: &AppState, options: &ServerOptions) {
+    if let Some(path) = &options.snapshot_path {
+        match state.metrics_store.persist(path) {
+            Ok(()) => tracing::info!(path = %path.display(), "metrics store flushed"),
+            Err(e) => tracing::error!(path = %path.display(), error = %e, "metrics store flush failed"),
+        }
+        let path = api_usage::usage_path(path);
+        if let Err(e) = state.api_usage.persist(&path) {
+            tracing::error!(path = %path.display(), error = %e, "API usage flush failed");
+        }
+    }
+    if let Some(path) = &state.config.warmup.usage_path {
+        if let Err(e) = state.usage.persist(path) {
+            tracing::error!(path = %path.display(), error = %e, "usage stats flush failed");
+        }
+    }
+}
+
+/// Serve the API until a shutdown signal, then drain and flush
+pub async fn run(state: Arc<AppState>, options: ServerOptions) -> std::io::Result<()> {
+    let shutdown = CancellationToken::new();
+    let token = shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        token.cancel();
+    });
+    run_until(state, options, shutdown).await
+}
+
+/// Serve the API until `shutdown` is cancelled, then drain and flush
+pub async fn run_until(
+    state: Arc<AppState>,
+    options: ServerOptions,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    if let Some(path) = options.snapshot_path.as_deref().filter(|p| p.exists()) {
+        if let Err(e) = state.metrics_store.load(path) {
+            tracing::warn!(path = %path.display(), error = %e, "could not load metrics snapshot");
+        }
+    }
+    let api_usage_path = options.snapshot_path.as_deref().map(api_usage::usage_path);
+    if let Some(path) = api_usage_path.filter(|p| p.exists()) {
+        if let Err(e) = state.api_usage.load(&path) {
+            tracing::warn!(path = %path.display(), error = %e, "could not load API usage");
+        }
+    }
+    let annotations_path = options.snapshot_path.as_deref().map(annotations::annotations_path);
+    if let Some(path) = annotations_path.filter(|p| p.exists()) {
+        if let Err(e) = state.annotations.load(&path) {
+            tracing::error!(path = %path.display(), error = %e, "could not load annotations");
+        }
+    }
+    let schedules_path = options.snapshot_path.as_deref().map(schedules::schedules_path);
+    if let Some(path) = schedules_path.filter(|p| p.exists()) {
+        if let Err(e) = state.schedules.load(&path) {
+            tracing::error!(path = %path.display(), error = %e, "could not load scheduled changes");
+        }
+    }
+    if let Some(path) = state.config.warmup.usage_path.as_deref().filter(|p| p.exists()) {
+        if let Err(e) = state.usage.load(path) {
+            tracing::warn!(path = %path.display(), error = %e, "could not load usage stats");
+        }
+    }
+    if let Some(path) = state.config.rbac.assignments_path.as_deref().filter(|p| p.exists()) {
+        if let Err(e) = state.roles.load(path) {
+            tracing::error!(path = %path.display(), error = %e, "could not load role assignments");
+        }
+    }
+    if let Some(path) = state.config.normalization.rules_path.as_deref() {
+        if let Err(e) = state.normalization.load(path) {
+            tracing::error!(path = %path.display(), error = %e, "normalization rules not loaded");
+        }
+    }
+    if let Some(path) = state.config.credentials.path.as_deref().filter(|p| p.exists()) {
+        if let Err(e) = state.credentials.load(path) {
+            tracing::error!(path = %path.display(), error = %e, "could not load connections");
+        }
+    }
+    if let Some(path) = state.config.audit.path.as_deref().filter(|p| p.exists()) {
+        if let Err(e) = state.audit.load(path) {
+            tracing::error!(path = %path.display(), error = %e, "could not load audit log");
+        }
+    }
+
+    let worker = tokio::spawn(run_sync_worker(state.clone(), SYNC_INTERVAL, shutdown.clone()));
+    let changes = tokio::spawn(schedules::run_change_scheduler(state.clone(), shutdown.clone()));
+    let backups = match BackupTarget::from_config(&state.config.backup) {
+        Ok(target) => target.map(|target| {
+            let interval = state.config.backup_interval();
+            tokio::spawn(run_backup_scheduler(state.clone(), target, interval, shutdown.clone()))
+        }),
+        Err(e) => {
+            tracing::error!(error = %e, "backups disabled");
+            None
+        }
+    };
+    let refresher = state.credentials.is_enabled().then(|| {
+        tokio::spawn(credentials::run_credential_refresher(state.clone(), shutdown.clone()))
+    });
+    #[cfg(feature = "reports")]
+    let scheduler = tokio::spawn(reports::run_report_scheduler(state.clone(), shutdown.clone()));
+    #[cfg(feature = "warehouse")]
+    let exporter = WarehouseExporter::from_config(&state.config.warehouse).map(|exporter| {
+        tokio::spawn(warehouse::run_warehouse_exporter(state.clone(), exporter, shutdown.clone()))
+    });
+
+    let listener = tokio::net::TcpListener::bind(options.addr).await?;
+    tracing::info!(addr = %options.addr, "listening");
+
+    // Liveness is served while warming up; readiness waits for it
+    let router = build_router(state.clone(), &options);
+    let warm_up = tokio::spawn(warmup::run_warmup(state.clone(), router.clone()));
+
+    let token = shutdown.clone();
+    let server = axum::serve(listener, router)
+        .with_graceful_shutdown(async move { token.cancelled().await })
+        .into_future();
+
+    // Open SSE streams never finish on their own, so the drain window is
+    // bounded instead of waiting for every connection to close
+    let drain_deadline = async {
+        shutdown.cancelled().await;
+        tokio::time::sleep(options.drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = server => result?,
+        _ = drain_deadline => tracing::warn!("drain timeout elapsed, dropping remaining connections"),
+    }
+
+    shutdown.cancel();
+    warm_up.abort();
+    if tokio::time::timeout(WORKER_STOP_TIMEOUT, worker).await.is_err() {
+        tracing::warn!("sync worker did not stop in time");
+    }
+    if tokio::time::timeout(WORKER_STOP_TIMEOUT, changes).await.is_err() {
+        tracing::warn!("change scheduler did not stop in time");
+    }
+    if let Some(backups) = backups {
+        if tokio::time::timeout(WORKER_STOP_TIMEOUT, backups).await.is_err() {
+            tracing::warn!("backup scheduler did not stop in time");
+        }
+    }
+    if let Some(refresher) = refresher {
+        if tokio::time::timeout(WORKER_STOP_TIMEOUT, refresher).await.is_err() {
+            tracing::warn!("credential refresher did not stop in time");
+        }
+    }
+    #[cfg(feature = "reports")]
+    if tokio::time::timeout(WORKER_STOP_TIMEOUT, scheduler).await.is_err() {
+        tracing::warn!("report scheduler did not stop in time");
+    }
+    #[cfg(feature = "warehouse")]
+    if let Some(exporter) = exporter {
+        if tokio::time::timeout(WORKER_STOP_TIMEOUT, exporter).await.is_err() {
+            tracing::warn!("warehouse exporter did not stop in time");
+        }
+    }
+
+    on_shutdown(&state, &options);
+    tracing::info!("shutdown complete");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{Campaign, Platform};
+    use super::super::connectors::ConnectorRegistry;
+
+    /// State without platforms, so the sync worker records nothing itself
+    fn state() -> Arc<AppState> {
+        let mut config = AppConfig::default();
+        config.gateway.service_token = "test".to_string();
+        let mut state = AppState::from_config(config).unwrap();
+        state.connectors = ConnectorRegistry::default();
+        Arc::new(state)
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_the_workers_and_the_store_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("metrics-{}.json", uuid::Uuid::new_v4()));
+        let options = ServerOptions {
+            addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            snapshot_path: Some(path.clone()),
+            drain_timeout: Duration::from_millis(100),
+            ..ServerOptions::default()
+        };
+
+        let first = state();
+        first
+            .metrics_store
+            .record(timezones::today(), &[Campaign::sample("1", Platform::Google)]);
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(run_until(first.clone(), options.clone(), shutdown.clone()));
+        shutdown.cancel();
+
+        // A worker ignoring the token would hold the exit for its stop timeout
+        let stopped = tokio::time::timeout(WORKER_STOP_TIMEOUT, server).await;
+        stopped.expect("workers did not stop").unwrap().unwrap();
+        assert!(path.exists());
+
+        let second = state();
+        let restart = CancellationToken::new();
+        restart.cancel();
+        run_until(second.clone(), options, restart).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let _ = std::fs::remove_file(api_usage::usage_path(&path));
+
+        assert_eq!(second.metrics_store.snapshots(), first.metrics_store.snapshots());
+    }
+}
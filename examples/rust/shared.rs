@@ -0,0 +1,173 @@
+//! # Shared State Example
+//!
+//! Every replica keeps its entity cache and idempotency keys in memory and
+//! runs its own sync worker. Behind a load balancer that means replicas
+//! serve different cached lists, a retried write can land on a replica
+//! that never saw the first attempt, and each replica syncs every interval.
+//! Built with the `redis` feature and given a `[redis]` section, replicas
+//! share one Redis instance instead:
+//!
+//! - campaign lists cached by the entity cache are also kept in Redis for
+//!   `cache.campaigns_ttl_secs`, and mutations drop them for all replicas;
+//! - idempotency keys are claimed with `SET NX`, so a keyed write runs on
+//!   one replica and is replayed by all of them;
+//! - the sync worker takes the `sync` lease before syncing, so one replica
+//!   syncs per interval.
+//!
+//! Keys start with `redis.key_prefix`. The connection is opened on first
+//! use and re-established by itself; while Redis is unreachable the cache
+//! and idempotency keys fall back to the replica's memory and syncs are
+//! skipped rather than run by every replica.
+
+use redis::aio::ConnectionManager;
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use super::config::{ConfigError, RedisConfig};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SharedError {
+    #[error("redis request failed: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("invalid shared value: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Compare-and-delete, so a replica only drops a value it wrote
+const DELETE_IF_EQUAL: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// JSON values and leases in the deployment's Redis instance
+pub struct SharedStore {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    prefix: String,
+    /// Recorded as the holder of the leases this replica takes
+    replica_id: String,
+}
+
+impl SharedStore {
+    pub fn from_config(config: &RedisConfig) -> Result<Self, ConfigError> {
+        let client = redis::Client::open(config.url.as_str())
+            .map_err(|e| ConfigError::invalid("redis.url", e.to_string()))?;
+
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+            prefix: config.key_prefix.clone(),
+            replica_id: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.prefix, key)
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, SharedError> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?;
+        Ok(connection.clone())
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, SharedError> {
+        let value: Option<String> = redis::cmd("GET")
+            .arg(self.key(key))
+            .query_async(&mut self.connection().await?)
+            .await?;
+
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    /// Store `value` under `key` for `ttl`
+    pub async fn set<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<(), SharedError> {
+        let () = redis::cmd("SET")
+            .arg(self.key(key))
+            .arg(serde_json::to_string(value)?)
+            .arg("PX")
+            .arg(millis(ttl))
+            .query_async(&mut self.connection().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Store `value` under `key` for `ttl` unless the key exists; whether
+    /// it was stored
+    pub async fn set_new<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<bool, SharedError> {
+        let stored: Option<String> = redis::cmd("SET")
+            .arg(self.key(key))
+            .arg(serde_json::to_string(value)?)
+            .arg("NX")
+            .arg("PX")
+            .arg(millis(ttl))
+            .query_async(&mut self.connection().await?)
+            .await?;
+        Ok(stored.is_some())
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<(), SharedError> {
+        let _deleted: u64 = redis::cmd("DEL")
+            .arg(self.key(key))
+            .query_async(&mut self.connection().await?)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete `key` if it still holds `value`; whether it was deleted
+    pub async fn delete_if<T: Serialize>(&self, key: &str, value: &T) -> Result<bool, SharedError> {
+        let deleted: u64 = redis::Script::new(DELETE_IF_EQUAL)
+            .key(self.key(key))
+            .arg(serde_json::to_string(value)?)
+            .invoke_async(&mut self.connection().await?)
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Take the lease `name` for `ttl` unless a replica holds it; whether
+    /// this replica now holds it
+    ///
+    /// Leases are never released, they expire.
+    pub async fn try_lease(&self, name: &str, ttl: Duration) -> Result<bool, SharedError> {
+        self.set_new(&format!("lease:{}", name), &self.replica_id, ttl).await
+    }
+}
+
+/// `PX` takes whole milliseconds greater than zero
+fn millis(ttl: Duration) -> u64 {
+    ttl.as_millis().clamp(1, u64::MAX.into()) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_prefixed_per_deployment() {
+        let config = RedisConfig {
+            url: "redis://127.0.0.1:6379/0".to_string(),
+            key_prefix: "ibvi-staging".to_string(),
+        };
+
+        // Opening the client does not connect
+        let store = SharedStore::from_config(&config).unwrap();
+
+        assert_eq!(store.key("campaigns:google"), "ibvi-staging:campaigns:google");
+        assert_eq!(millis(Duration::ZERO), 1);
+    }
+}
@@ -0,0 +1,255 @@
+//! # Campaign Sort Example
+//!
+//! `GET /campaigns?sort=platform,-cost,name` sorts by each listed key in
+//! turn, the next key breaking ties of the previous one. A `-` prefix
+//! sorts a key in descending order; keys without one are ascending.
+//!
+//! - numeric keys: `budget` (daily budget), `impressions`, `clicks`,
+//!   `conversions`, `cost`, `conversion_value`, `ctr`, `cpa`, `roas`;
+//! - text keys: `name` (ignoring case), `platform`, `status`, `id`.
+//!
+//! Numbers are compared with a total order: a NaN metric sorts after
+//! every number whatever the direction, instead of leaving the list in an
+//! arbitrary order. Campaigns equal on every key are ordered by platform
+//! and ID, so repeated requests (and the pages of one list) always see
+//! the same order.
+//!
+//! Without `sort` the list is sorted by daily budget, highest first. The
+//! single-metric values accepted before multi-key sorting (`budget`,
+//! `cost`, `conversions`, `conversion_value`, `roas`) still sort highest
+//! first when given alone, so existing dashboards keep their order.
+
+use std::cmp::Ordering;
+
+use super::campaign_aggregator::Campaign;
+use super::error::ApiError;
+
+/// Sort keys of legacy `?sort=` values, which meant highest first
+const LEGACY_KEYS: &[&str] = &["budget", "cost", "conversions", "conversion_value", "roas"];
+
+/// Field a campaign list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Budget,
+    Impressions,
+    Clicks,
+    Conversions,
+    Cost,
+    ConversionValue,
+    Ctr,
+    Cpa,
+    Roas,
+    Name,
+    Platform,
+    Status,
+    Id,
+}
+
+impl SortField {
+    pub fn parse(name: &str) -> Option<SortField> {
+        Some(match name {
+            "budget" | "daily_budget" => SortField::Budget,
+            "impressions" => SortField::Impressions,
+            "clicks" => SortField::Clicks,
+            "conversions" => SortField::Conversions,
+            "cost" => SortField::Cost,
+            "conversion_value" => SortField::ConversionValue,
+            "ctr" => SortField::Ctr,
+            "cpa" => SortField::Cpa,
+            "roas" => SortField::Roas,
+            "name" => SortField::Name,
+            "platform" => SortField::Platform,
+            "status" => SortField::Status,
+            "id" => SortField::Id,
+            _ => return None,
+        })
+    }
+
+    /// Value of a numeric field, `None` for text fields
+    fn number(self, campaign: &Campaign) -> Option<f64> {
+        let metrics = &campaign.metrics;
+        Some(match self {
+            SortField::Budget => campaign.daily_budget,
+            SortField::Impressions => metrics.impressions as f64,
+            SortField::Clicks => metrics.clicks as f64,
+            SortField::Conversions => metrics.conversions as f64,
+            SortField::Cost => metrics.cost,
+            SortField::ConversionValue => metrics.conversion_value,
+            SortField::Ctr => metrics.ctr,
+            SortField::Cpa => metrics.cpa,
+            SortField::Roas => metrics.roas,
+            SortField::Name | SortField::Platform | SortField::Status | SortField::Id => {
+                return None
+            }
+        })
+    }
+
+    /// Ascending order of `a` and `b` on this field; NaN after any number
+    fn compare(self, a: &Campaign, b: &Campaign) -> Ordering {
+        match self {
+            SortField::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortField::Platform => a.platform.as_str().cmp(b.platform.as_str()),
+            SortField::Status => a.status.as_str().cmp(b.status.as_str()),
+            SortField::Id => a.id.cmp(&b.id),
+            _ => {
+                let (a, b) = (self.number(a).unwrap_or(0.0), self.number(b).unwrap_or(0.0));
+                a.total_cmp(&b)
+            }
+        }
+    }
+
+    fn is_nan(self, campaign: &Campaign) -> bool {
+        self.number(campaign).is_some_and(f64::is_nan)
+    }
+}
+
+/// One key of a sort specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl SortKey {
+    fn compare(&self, a: &Campaign, b: &Campaign) -> Ordering {
+        // NaN last in either direction
+        match (self.field.is_nan(a), self.field.is_nan(b)) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) => {}
+        }
+        let ordering = self.field.compare(a, b);
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// Keys a campaign list is sorted by, most significant first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortSpec {
+    pub keys: Vec<SortKey>,
+}
+
+impl Default for SortSpec {
+    /// Daily budget, highest first
+    fn default() -> Self {
+        Self {
+            keys: vec![SortKey {
+                field: SortField::Budget,
+                descending: true,
+            }],
+        }
+    }
+}
+
+impl SortSpec {
+    /// Parse `?sort=`, e.g. `platform,-cost,name`; see the module docs
+    pub fn parse(value: &str) -> Result<SortSpec, ApiError> {
+        let value = value.trim();
+        if LEGACY_KEYS.contains(&value) {
+            let field = SortField::parse(value).expect("legacy keys are sort fields");
+            return Ok(SortSpec {
+                keys: vec![SortKey {
+                    field,
+                    descending: true,
+                }],
+            });
+        }
+
+        let keys = value
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let (descending, name) = match key.strip_prefix('-') {
+                    Some(name) => (true, name),
+                    None => (false, key.strip_prefix('+').unwrap_or(key)),
+                };
+                let field = SortField::parse(&name.to_ascii_lowercase())
+                    .ok_or_else(|| ApiError::Validation(format!("unknown sort key {:?}", name)))?;
+                Ok(SortKey { field, descending })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+
+        if keys.is_empty() {
+            return Ok(SortSpec::default());
+        }
+        Ok(SortSpec { keys })
+    }
+
+    /// Sort `campaigns` by the keys, then by platform and ID
+    pub fn sort(&self, campaigns: &mut [Campaign]) {
+        campaigns.sort_by(|a, b| self.compare(a, b));
+    }
+
+    fn compare(&self, a: &Campaign, b: &Campaign) -> Ordering {
+        self.keys
+            .iter()
+            .map(|key| key.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| {
+                SortField::Platform.compare(a, b).then_with(|| SortField::Id.compare(a, b))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+
+    fn ids(campaigns: &[Campaign]) -> Vec<&str> {
+        campaigns.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_multiple_keys_with_directions() {
+        let campaign = |id: &str, platform, name: &str, cost| {
+            let mut campaign = Campaign::sample(id, platform);
+            campaign.name = name.to_string();
+            campaign.metrics.cost = cost;
+            campaign
+        };
+        let mut campaigns = vec![
+            campaign("m1", Platform::Meta, "b", 10.0),
+            campaign("g2", Platform::Google, "a", 10.0),
+            campaign("g1", Platform::Google, "B", 10.0),
+            campaign("g3", Platform::Google, "c", 30.0),
+        ];
+
+        SortSpec::parse("platform,-cost,name").unwrap().sort(&mut campaigns);
+        assert_eq!(ids(&campaigns), ["g3", "g2", "g1", "m1"]);
+
+        SortSpec::parse("cost").unwrap().sort(&mut campaigns);
+        assert_eq!(ids(&campaigns), ["g3", "g1", "g2", "m1"], "legacy: highest first");
+
+        SortSpec::parse("cost,id").unwrap().sort(&mut campaigns);
+        assert_eq!(ids(&campaigns), ["g1", "g2", "m1", "g3"]);
+
+        assert!(SortSpec::parse("platform,-spend").is_err());
+        assert_eq!(SortSpec::parse(" , ").unwrap(), SortSpec::default());
+    }
+
+    #[test]
+    fn test_nan_sorts_last_and_ties_break_by_id() {
+        let mut campaigns: Vec<Campaign> =
+            ["g3", "g1", "g2"].iter().map(|id| Campaign::sample(id, Platform::Google)).collect();
+        campaigns[0].metrics.roas = 2.0;
+        campaigns[1].metrics.roas = f64::NAN;
+
+        for spec in ["roas", "-roas"] {
+            SortSpec::parse(spec).unwrap().sort(&mut campaigns);
+            assert_eq!(campaigns.last().unwrap().id, "g1", "{}", spec);
+        }
+        SortSpec::parse("-roas").unwrap().sort(&mut campaigns);
+        assert_eq!(ids(&campaigns), ["g3", "g2", "g1"]);
+
+        // Equal budgets: platform, then ID
+        SortSpec::default().sort(&mut campaigns);
+        assert_eq!(ids(&campaigns), ["g1", "g2", "g3"]);
+    }
+}
@@ -0,0 +1,429 @@
+//! # Declarative Account Spec Example
+//!
+//! Exports the structure of an account (campaigns, budgets, ad groups and
+//! their audience targeting) as a declarative YAML or JSON document, and
+//! diffs a desired document against live state, Terraform style. The spec
+//! is meant to live in git: export once, edit, review the diff in a PR.
+//!
+//! ```yaml
+//! api_version: ibvi.ads/v1
+//! campaigns:
+//!   - platform: google
+//!     id: "123"
+//!     name: "[G] Search - Brand"
+//!     status: ENABLED
+//!     daily_budget: 150.0
+//!     currency: BRL
+//!     ad_groups:
+//!       - id: "456"
+//!         name: Brand terms
+//!         status: ENABLED
+//!         audiences: ["aud-1"]
+//! ```
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, CampaignStatus, Platform};
+use super::error::ApiError;
+use super::hydration::{CampaignDetail, Hydrator};
+use super::ids::{AccountId, AdGroupId, CampaignId};
+
+/// Version marker written into every exported spec
+pub const SPEC_API_VERSION: &str = "ibvi.ads/v1";
+
+/// Budgets closer than this are considered equal
+const BUDGET_EPSILON: f64 = 0.005;
+
+/// Declarative description of an account's structure
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AccountSpec {
+    pub api_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<AccountId>,
+    #[serde(default)]
+    pub campaigns: Vec<CampaignSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CampaignSpec {
+    pub platform: Platform,
+    /// Platform ID; absent for campaigns that do not exist yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<CampaignId>,
+    pub name: String,
+    pub status: CampaignStatus,
+    pub daily_budget: f64,
+    pub currency: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ad_groups: Vec<AdGroupSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdGroupSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<AdGroupId>,
+    pub name: String,
+    pub status: CampaignStatus,
+    /// Targeted audience IDs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audiences: Vec<String>,
+}
+
+/// One changed field, `from` live `to` desired
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+/// Difference between the desired spec and live state
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SpecChange {
+    CreateCampaign {
+        campaign: CampaignSpec,
+    },
+    UpdateCampaign {
+        platform: Platform,
+        id: CampaignId,
+        name: String,
+        changes: Vec<FieldChange>,
+    },
+    /// Live campaign missing from the spec
+    RemoveCampaign {
+        platform: Platform,
+        id: CampaignId,
+        name: String,
+    },
+    CreateAdGroup {
+        platform: Platform,
+        campaign_id: CampaignId,
+        ad_group: AdGroupSpec,
+    },
+    UpdateAdGroup {
+        platform: Platform,
+        campaign_id: CampaignId,
+        id: AdGroupId,
+        changes: Vec<FieldChange>,
+    },
+}
+
+/// Query parameters of the spec endpoints
+#[derive(Debug, Deserialize, Default)]
+pub struct SpecQuery {
+    pub platform: Option<Platform>,
+    pub account_id: Option<AccountId>,
+    /// `yaml` or `json` (default)
+    pub format: Option<String>,
+}
+
+impl SpecQuery {
+    fn matches(&self, detail: &CampaignDetail) -> bool {
+        self.platform.as_ref().is_none_or(|p| *p == detail.campaign.platform)
+            && self
+                .account_id
+                .as_ref()
+                .is_none_or(|a| detail.campaign.account_id.as_ref() == Some(a))
+    }
+}
+
+/// Spec describing hydrated live campaigns; removed campaigns are left out
+pub fn build_spec(account_id: Option<AccountId>, details: Vec<CampaignDetail>) -> AccountSpec {
+    let campaigns = details
+        .into_iter()
+        .filter(|d| d.campaign.status != CampaignStatus::Removed)
+        .map(|d| CampaignSpec {
+            platform: d.campaign.platform,
+            id: Some(d.campaign.id),
+            name: d.campaign.name,
+            status: d.campaign.status,
+            daily_budget: d.campaign.daily_budget,
+            currency: d.campaign.currency,
+            ad_groups: d
+                .ad_groups
+                .into_iter()
+                .map(|g| AdGroupSpec {
+                    id: Some(g.ad_group.id),
+                    name: g.ad_group.name,
+                    status: g.ad_group.status,
+                    audiences: g.ad_group.audience_ids,
+                })
+                .collect(),
+        })
+        .collect();
+
+    AccountSpec {
+        api_version: SPEC_API_VERSION.to_string(),
+        account_id,
+        campaigns,
+    }
+}
+
+fn change<T: Serialize + PartialEq>(field: &str, from: &T, to: &T) -> Option<FieldChange> {
+    (from != to).then(|| FieldChange {
+        field: field.to_string(),
+        from: serde_json::json!(from),
+        to: serde_json::json!(to),
+    })
+}
+
+fn find_ad_group<'a>(
+    specs: &'a [AdGroupSpec],
+    wanted: &AdGroupSpec,
+) -> Option<&'a AdGroupSpec> {
+    specs.iter().find(|g| match &wanted.id {
+        Some(id) => g.id.as_ref() == Some(id),
+        None => g.name == wanted.name,
+    })
+}
+
+fn diff_campaign(live: &CampaignSpec, desired: &CampaignSpec, changes: &mut Vec<SpecChange>) {
+    let id = live.id.clone().unwrap_or_default();
+
+    let mut fields: Vec<FieldChange> = [
+        change("name", &live.name, &desired.name),
+        change("status", &live.status, &desired.status),
+        change("currency", &live.currency, &desired.currency),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if (live.daily_budget - desired.daily_budget).abs() > BUDGET_EPSILON {
+        fields.extend(change("daily_budget", &live.daily_budget, &desired.daily_budget));
+    }
+
+    if !fields.is_empty() {
+        changes.push(SpecChange::UpdateCampaign {
+            platform: live.platform.clone(),
+            id: id.clone(),
+            name: live.name.clone(),
+            changes: fields,
+        });
+    }
+
+    for wanted in &desired.ad_groups {
+        let Some(current) = find_ad_group(&live.ad_groups, wanted) else {
+            changes.push(SpecChange::CreateAdGroup {
+                platform: live.platform.clone(),
+                campaign_id: id.clone(),
+                ad_group: wanted.clone(),
+            });
+            continue;
+        };
+
+        let mut live_audiences = current.audiences.clone();
+        let mut wanted_audiences = wanted.audiences.clone();
+        live_audiences.sort();
+        wanted_audiences.sort();
+
+        let fields: Vec<FieldChange> = [
+            change("name", &current.name, &wanted.name),
+            change("status", &current.status, &wanted.status),
+            change("audiences", &live_audiences, &wanted_audiences),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if !fields.is_empty() {
+            changes.push(SpecChange::UpdateAdGroup {
+                platform: live.platform.clone(),
+                campaign_id: id.clone(),
+                id: current.id.clone().unwrap_or_default(),
+                changes: fields,
+            });
+        }
+    }
+}
+
+/// Changes needed to turn `live` into `desired`
+///
+/// Campaigns are matched by platform and ID, or by platform and name when
+/// the desired campaign has no ID yet.
+pub fn diff_specs(live: &AccountSpec, desired: &AccountSpec) -> Vec<SpecChange> {
+    let mut changes = Vec::new();
+    let mut matched = Vec::new();
+
+    for wanted in &desired.campaigns {
+        let current = live.campaigns.iter().position(|c| {
+            c.platform == wanted.platform
+                && match &wanted.id {
+                    Some(id) => c.id.as_ref() == Some(id),
+                    None => c.name == wanted.name,
+                }
+        });
+
+        match current {
+            Some(index) => {
+                matched.push(index);
+                diff_campaign(&live.campaigns[index], wanted, &mut changes);
+            }
+            None => changes.push(SpecChange::CreateCampaign {
+                campaign: wanted.clone(),
+            }),
+        }
+    }
+
+    for (index, campaign) in live.campaigns.iter().enumerate() {
+        if !matched.contains(&index) {
+            changes.push(SpecChange::RemoveCampaign {
+                platform: campaign.platform.clone(),
+                id: campaign.id.clone().unwrap_or_default(),
+                name: campaign.name.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Live spec for the campaigns selected by `query`
+pub async fn live_spec(state: &AppState, query: &SpecQuery) -> Result<AccountSpec, ApiError> {
+    let campaigns = aggregate_campaigns(state).await;
+    let details = Hydrator::new(&state.connectors).hydrate(campaigns).await?;
+
+    Ok(build_spec(
+        query.account_id.clone(),
+        details.into_iter().filter(|d| query.matches(d)).collect(),
+    ))
+}
+
+/// Parse a spec sent as YAML or JSON, chosen by `Content-Type`
+pub fn parse_spec(headers: &HeaderMap, body: &[u8]) -> Result<AccountSpec, ApiError> {
+    let is_yaml = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("yaml"));
+
+    let spec: AccountSpec = if is_yaml {
+        serde_yaml::from_slice(body).map_err(|e| ApiError::Validation(e.to_string()))?
+    } else {
+        serde_json::from_slice(body).map_err(|e| ApiError::Validation(e.to_string()))?
+    };
+
+    if spec.api_version != SPEC_API_VERSION {
+        return Err(ApiError::Validation(format!(
+            "unsupported api_version {:?}, expected {:?}",
+            spec.api_version, SPEC_API_VERSION
+        )));
+    }
+
+    Ok(spec)
+}
+
+/// GET /accounts/spec - Export the live structure as a declarative spec
+pub async fn export_spec(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpecQuery>,
+) -> Result<Response, ApiError> {
+    let spec = live_spec(&state, &query).await?;
+
+    match query.format.as_deref() {
+        Some("yaml") => {
+            let yaml = serde_yaml::to_string(&spec).map_err(|e| ApiError::Internal(e.to_string()))?;
+            Ok((
+                [(header::CONTENT_TYPE, HeaderValue::from_static("application/yaml"))],
+                yaml,
+            )
+                .into_response())
+        }
+        None | Some("json") => Ok(Json(spec).into_response()),
+        Some(other) => Err(ApiError::Validation(format!("unknown format {:?}", other))),
+    }
+}
+
+/// POST /accounts/spec/diff - Changes needed to reach the posted spec
+pub async fn diff_spec(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpecQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Vec<SpecChange>>, ApiError> {
+    let desired = parse_spec(&headers, &body)?;
+    let live = live_spec(&state, &query).await?;
+
+    Ok(Json(diff_specs(&live, &desired)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(id: Option<&str>, name: &str, budget: f64) -> CampaignSpec {
+        CampaignSpec {
+            platform: Platform::Google,
+            id: id.map(CampaignId::from),
+            name: name.to_string(),
+            status: CampaignStatus::Enabled,
+            daily_budget: budget,
+            currency: "BRL".to_string(),
+            ad_groups: vec![AdGroupSpec {
+                id: Some("ag1".into()),
+                name: "Brand terms".to_string(),
+                status: CampaignStatus::Enabled,
+                audiences: vec!["a".to_string(), "b".to_string()],
+            }],
+        }
+    }
+
+    fn spec(campaigns: Vec<CampaignSpec>) -> AccountSpec {
+        AccountSpec {
+            api_version: SPEC_API_VERSION.to_string(),
+            account_id: None,
+            campaigns,
+        }
+    }
+
+    #[test]
+    fn test_identical_specs_have_no_changes() {
+        let live = spec(vec![campaign(Some("1"), "Brand", 100.0)]);
+        assert!(diff_specs(&live, &live.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_updates_creates_and_removals() {
+        let live = spec(vec![
+            campaign(Some("1"), "Brand", 100.0),
+            campaign(Some("2"), "Old", 50.0),
+        ]);
+
+        let mut brand = campaign(Some("1"), "Brand", 150.0);
+        brand.ad_groups[0].audiences = vec!["b".to_string(), "a".to_string()];
+        brand.ad_groups[0].status = CampaignStatus::Paused;
+        let desired = spec(vec![brand, campaign(None, "New", 20.0)]);
+
+        let changes = diff_specs(&live, &desired);
+
+        assert_eq!(changes.len(), 4);
+        assert!(matches!(
+            &changes[0],
+            SpecChange::UpdateCampaign { id, changes, .. } if id == "1" && changes[0].field == "daily_budget"
+        ));
+        // Audience order does not matter, only the status changed
+        assert!(matches!(
+            &changes[1],
+            SpecChange::UpdateAdGroup { changes, .. } if changes.len() == 1 && changes[0].field == "status"
+        ));
+        assert!(matches!(&changes[2], SpecChange::CreateCampaign { campaign } if campaign.name == "New"));
+        assert!(matches!(&changes[3], SpecChange::RemoveCampaign { id, .. } if id == "2"));
+    }
+
+    #[test]
+    fn test_yaml_spec_round_trips() {
+        let original = spec(vec![campaign(Some("1"), "Brand", 100.0)]);
+        let yaml = serde_yaml::to_string(&original).unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/yaml"));
+
+        assert_eq!(parse_spec(&headers, yaml.as_bytes()).unwrap(), original);
+    }
+}
@@ -0,0 +1,428 @@
+//! # Spend Caps Example
+//!
+//! Hard monthly spend caps per tenant, configured under `[spend_caps]`.
+//! A tenant's cap covers the campaigns of its `account_ids`, or every
+//! campaign when it lists none. Caps are compared with spend as reported,
+//! without currency conversion.
+//!
+//! Projected monthly spend is month-to-date spend plus the daily budget of
+//! every enabled campaign for each day left in the month, today included,
+//! so the projection errs on the high side.
+//!
+//! Budget and status changes of bulk requests, bulk rollbacks and spec
+//! applies are checked against the cap of the caller's tenant
+//! (`X-Tenant-Id`), in request order:
+//! - a change raising the projection above the cap fails with
+//!   `422 spend_cap_exceeded`; changes lowering it always pass;
+//! - admins may push past the cap by sending
+//!   `X-Spend-Cap-Override: <reason>`, and every override is recorded.
+//!
+//! After every sync, tenants projected to reach `alert_ratio` of their cap
+//! raise a critical alert through the tenant's notifier.
+
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::alerts::{AlertEvent, AlertSeverity, NotifierConfig};
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, CampaignStatus};
+use super::config::{SpendCapConfig, TenantSpendCap};
+use super::error::ApiError;
+use super::freeze::{Actor, ActorRole};
+use super::ids::{AccountId, GlobalCampaignId};
+use super::pacing::days_in_month;
+use super::timezones;
+
+/// Spend figures of one tenant, returned by `GET /spend-caps`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpendCapStatus {
+    pub tenant: String,
+    pub monthly_cap: f64,
+    pub month_to_date_spend: f64,
+    pub projected_spend: f64,
+    /// Projected spend as a share of the cap
+    pub utilization: f64,
+    /// Whether the projection reached `spend_caps.alert_ratio` of the cap
+    pub near_cap: bool,
+}
+
+/// Audit record of a change let through a spend cap
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpendCapOverride {
+    pub tenant: String,
+    pub actor: String,
+    pub reason: String,
+    /// What was changed, e.g. `set_budget google/123`
+    pub target: String,
+    /// Projected monthly spend with the change applied
+    pub projected_spend: f64,
+    pub monthly_cap: f64,
+    pub at: DateTime<Utc>,
+}
+
+/// Change to a campaign that moves its projected spend
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpendChange {
+    Status(CampaignStatus),
+    DailyBudget(f64),
+}
+
+/// Days left in the month of `today`, today included
+pub fn remaining_days(today: NaiveDate) -> u32 {
+    days_in_month(today) - today.day() + 1
+}
+
+/// Daily spend of a campaign with `status`; paused and removed campaigns
+/// do not spend, an unmapped status may be delivering
+fn daily_spend(status: &CampaignStatus, daily_budget: f64) -> f64 {
+    match status {
+        CampaignStatus::Enabled | CampaignStatus::Other(_) => daily_budget,
+        CampaignStatus::Paused | CampaignStatus::Removed => 0.0,
+    }
+}
+
+/// Month-to-date and projected monthly spend of the campaigns `cap` covers
+fn project(cap: &TenantSpendCap, campaigns: &[Campaign], today: NaiveDate) -> (f64, f64) {
+    let remaining = remaining_days(today) as f64;
+    campaigns
+        .iter()
+        .filter(|c| cap.covers(c.account_id.as_ref()))
+        .fold((0.0, 0.0), |(spent, projected), c| {
+            let future = daily_spend(&c.status, c.daily_budget) * remaining;
+            (spent + c.metrics.cost, projected + c.metrics.cost + future)
+        })
+}
+
+/// Configured caps and the override audit trail
+pub struct SpendCaps {
+    config: SpendCapConfig,
+    overrides: RwLock<Vec<SpendCapOverride>>,
+}
+
+impl SpendCaps {
+    pub fn new(config: SpendCapConfig) -> Self {
+        Self {
+            config,
+            overrides: RwLock::default(),
+        }
+    }
+
+    /// Spend of every capped tenant, by tenant
+    pub fn statuses(&self, campaigns: &[Campaign], today: NaiveDate) -> Vec<SpendCapStatus> {
+        let mut statuses: Vec<SpendCapStatus> = self
+            .config
+            .tenants
+            .iter()
+            .map(|(tenant, cap)| {
+                let (spent, projected) = project(cap, campaigns, today);
+                let utilization = projected / cap.monthly_cap;
+                SpendCapStatus {
+                    tenant: tenant.clone(),
+                    monthly_cap: cap.monthly_cap,
+                    month_to_date_spend: spent,
+                    projected_spend: projected,
+                    utilization,
+                    near_cap: utilization >= self.config.alert_ratio,
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.tenant.cmp(&b.tenant));
+        statuses
+    }
+
+    /// Critical alerts of tenants near their cap, with where to deliver them
+    pub fn alerts(
+        &self,
+        campaigns: &[Campaign],
+        now: DateTime<Utc>,
+    ) -> Vec<(Option<NotifierConfig>, AlertEvent)> {
+        self.statuses(campaigns, now.date_naive())
+            .into_iter()
+            .filter(|status| status.near_cap)
+            .map(|status| {
+                let reached = if status.utilization > 1.0 { "above" } else { "at" };
+                let event = AlertEvent {
+                    rule_id: format!("spend-cap:{}", status.tenant),
+                    campaign_id: String::new(),
+                    campaign_name: format!("tenant {}", status.tenant),
+                    message: format!(
+                        "Tenant {} is projected to spend {:.2} this month, {} {:.0}% of its {:.2} spend cap",
+                        status.tenant,
+                        status.projected_spend,
+                        reached,
+                        status.utilization * 100.0,
+                        status.monthly_cap
+                    ),
+                    severity: AlertSeverity::Critical,
+                    triggered_at: now,
+                    delivered: false,
+                    occurrences: 1,
+                    last_triggered_at: None,
+                    summary_sent: false,
+                };
+                (self.config.tenants[&status.tenant].notifier.clone(), event)
+            })
+            .collect()
+    }
+
+    /// Start checking the changes of one request by `actor` against the
+    /// live `campaigns`
+    pub fn guard<'a>(
+        &'a self,
+        actor: &'a Actor,
+        campaigns: &[Campaign],
+        now: DateTime<Utc>,
+    ) -> SpendCapGuard<'a> {
+        let today = now.with_timezone(&timezones::current()).date_naive();
+        let cap = actor
+            .tenant
+            .as_deref()
+            .and_then(|tenant| self.config.tenants.get_key_value(tenant))
+            .map(|(tenant, cap)| (tenant.as_str(), cap));
+        let (projected, planned) = match cap {
+            Some((_, cap)) => (
+                project(cap, campaigns, today).1,
+                campaigns
+                    .iter()
+                    .filter(|c| cap.covers(c.account_id.as_ref()))
+                    .map(|c| (c.global_id(), (c.status.clone(), c.daily_budget)))
+                    .collect(),
+            ),
+            None => (0.0, HashMap::new()),
+        };
+
+        SpendCapGuard {
+            caps: self,
+            actor,
+            cap,
+            projected,
+            remaining_days: remaining_days(today) as f64,
+            planned,
+            now,
+        }
+    }
+
+    /// Override audit trail, most recent first
+    pub fn overrides(&self) -> Vec<SpendCapOverride> {
+        let mut overrides = self.overrides.read().unwrap().clone();
+        overrides.reverse();
+        overrides
+    }
+}
+
+/// Projected spend of the caller's tenant as the changes of one request
+/// are admitted
+pub struct SpendCapGuard<'a> {
+    caps: &'a SpendCaps,
+    actor: &'a Actor,
+    /// Cap of the actor's tenant; without one every change passes
+    cap: Option<(&'a str, &'a TenantSpendCap)>,
+    projected: f64,
+    remaining_days: f64,
+    /// Status and daily budget of the covered campaigns, admitted changes included
+    planned: HashMap<GlobalCampaignId, (CampaignStatus, f64)>,
+    now: DateTime<Utc>,
+}
+
+impl SpendCapGuard<'_> {
+    /// Allow or block `change` of a campaign, counting it when allowed
+    ///
+    /// Campaigns the cap does not cover, or that do not exist, always pass.
+    pub fn check(
+        &mut self,
+        id: &GlobalCampaignId,
+        change: SpendChange,
+        target: &str,
+    ) -> Result<(), ApiError> {
+        let Some((status, daily_budget)) = self.planned.get(id).cloned() else {
+            return Ok(());
+        };
+        let after = match change {
+            SpendChange::Status(status) => (status, daily_budget),
+            SpendChange::DailyBudget(daily_budget) => (status.clone(), daily_budget),
+        };
+
+        let delta = daily_spend(&after.0, after.1) - daily_spend(&status, daily_budget);
+        self.admit(delta * self.remaining_days, target)?;
+        self.planned.insert(id.clone(), after);
+        Ok(())
+    }
+
+    /// Allow or block creating a campaign in `account_id`
+    pub fn check_new(
+        &mut self,
+        account_id: Option<&AccountId>,
+        status: &CampaignStatus,
+        daily_budget: f64,
+        target: &str,
+    ) -> Result<(), ApiError> {
+        if !self.cap.is_some_and(|(_, cap)| cap.covers(account_id)) {
+            return Ok(());
+        }
+        self.admit(daily_spend(status, daily_budget) * self.remaining_days, target)
+    }
+
+    fn admit(&mut self, delta: f64, target: &str) -> Result<(), ApiError> {
+        let Some((tenant, cap)) = self.cap else {
+            return Ok(());
+        };
+        let projected = self.projected + delta;
+
+        if delta > 0.0 && projected > cap.monthly_cap {
+            match (self.actor.role, &self.actor.spend_cap_override) {
+                (ActorRole::Admin, Some(reason)) => {
+                    tracing::warn!(
+                        %tenant,
+                        actor = %self.actor.id,
+                        %target,
+                        "spend cap overridden"
+                    );
+                    self.caps.overrides.write().unwrap().push(SpendCapOverride {
+                        tenant: tenant.to_string(),
+                        actor: self.actor.id.clone(),
+                        reason: reason.clone(),
+                        target: target.to_string(),
+                        projected_spend: projected,
+                        monthly_cap: cap.monthly_cap,
+                        at: self.now,
+                    });
+                }
+                _ => {
+                    return Err(ApiError::SpendCapExceeded(format!(
+                        "{} would raise the projected monthly spend of tenant {:?} to {:.2}, above its {:.2} cap",
+                        target, tenant, projected, cap.monthly_cap
+                    )))
+                }
+            }
+        }
+
+        self.projected = projected;
+        Ok(())
+    }
+}
+
+/// GET /spend-caps - Projected spend of every capped tenant
+pub async fn get_spend_caps(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SpendCapStatus>>, ApiError> {
+    let campaigns = aggregate_campaigns(&state).await;
+    Ok(Json(state.spend_caps.statuses(&campaigns, timezones::today())))
+}
+
+/// GET /spend-caps/overrides - Override audit trail, most recent first
+pub async fn list_spend_cap_overrides(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SpendCapOverride>>, ApiError> {
+    Ok(Json(state.spend_caps.overrides()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+
+    fn now() -> DateTime<Utc> {
+        // 21 days left in June, today included
+        NaiveDate::from_ymd_opt(2026, 6, 10)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    /// Spent 900 so far at 100 a day: projected 900 + 2100 = 3000
+    fn campaigns() -> Vec<Campaign> {
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        campaign.status = CampaignStatus::Enabled;
+        campaign.daily_budget = 100.0;
+        campaign.metrics.cost = 900.0;
+        campaign.account_id = Some("123".into());
+
+        let mut other_account = Campaign::sample("g2", Platform::Google);
+        other_account.status = CampaignStatus::Enabled;
+        other_account.account_id = Some("999".into());
+
+        vec![campaign, other_account]
+    }
+
+    fn caps(monthly_cap: f64) -> SpendCaps {
+        SpendCaps::new(SpendCapConfig {
+            alert_ratio: 0.9,
+            tenants: HashMap::from([(
+                "acme".to_string(),
+                TenantSpendCap {
+                    monthly_cap,
+                    account_ids: vec!["123".into()],
+                    notifier: None,
+                },
+            )]),
+        })
+    }
+
+    fn actor(role: ActorRole, spend_cap_override: Option<&str>) -> Actor {
+        Actor {
+            role,
+            spend_cap_override: spend_cap_override.map(str::to_string),
+            ..Actor::automation("ana", Some("acme".to_string()))
+        }
+    }
+
+    fn g1() -> GlobalCampaignId {
+        GlobalCampaignId::new(Platform::Google, "g1")
+    }
+
+    #[test]
+    fn test_projection_covers_the_tenant_accounts_only() {
+        let statuses = caps(3200.0).statuses(&campaigns(), now().date_naive());
+
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].month_to_date_spend, 900.0);
+        assert_eq!(statuses[0].projected_spend, 3000.0);
+        assert!(statuses[0].near_cap);
+
+        let alerts = caps(3200.0).alerts(&campaigns(), now());
+        assert_eq!(alerts[0].1.severity, AlertSeverity::Critical);
+        assert!(caps(5000.0).alerts(&campaigns(), now()).is_empty());
+    }
+
+    #[test]
+    fn test_changes_over_the_cap_are_blocked_in_request_order() {
+        let caps = caps(3200.0);
+        let user = actor(ActorRole::User, None);
+        let mut guard = caps.guard(&user, &campaigns(), now());
+
+        // +5 a day for 21 days fits, another +10 a day does not
+        assert!(guard.check(&g1(), SpendChange::DailyBudget(105.0), "a").is_ok());
+        let err = guard.check(&g1(), SpendChange::DailyBudget(115.0), "b").unwrap_err();
+        assert_eq!(err.code(), "spend_cap_exceeded");
+
+        // Lowering spend always passes and frees room
+        assert!(guard.check(&g1(), SpendChange::Status(CampaignStatus::Paused), "c").is_ok());
+        assert!(guard.check_new(Some(&"123".into()), &CampaignStatus::Enabled, 50.0, "d").is_ok());
+
+        // Campaigns of other accounts are not capped
+        let g2 = GlobalCampaignId::new(Platform::Google, "g2");
+        assert!(guard.check(&g2, SpendChange::DailyBudget(10_000.0), "e").is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_override_and_overrides_are_audited() {
+        let caps = caps(3000.0);
+
+        let automation = actor(ActorRole::Automation, Some("launch"));
+        let mut guard = caps.guard(&automation, &campaigns(), now());
+        assert!(guard.check(&g1(), SpendChange::DailyBudget(101.0), "x").is_err());
+
+        let admin = actor(ActorRole::Admin, Some("approved by finance"));
+        let mut guard = caps.guard(&admin, &campaigns(), now());
+        let raise = guard.check(&g1(), SpendChange::DailyBudget(101.0), "set_budget google/g1");
+        assert!(raise.is_ok());
+
+        let overrides = caps.overrides();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].reason, "approved by finance");
+        assert_eq!(overrides[0].projected_spend, 3021.0);
+    }
+}
@@ -0,0 +1,521 @@
+//! # Metrics Store Example
+//!
+//! Keeps the per-campaign metric time series recorded by the sync worker.
+//! The gateway reports month-to-date totals, so the store keeps one
+//! snapshot per campaign per day and derives daily values from the
+//! difference between consecutive snapshots. Snapshots also carry the
+//! campaign's daily budget, status and bid strategy on that day, and its
+//! name, platform and currency, so campaigns removed from the platforms
+//! stay reportable from the store alone.
+//!
+//! Series are keyed by `GlobalCampaignId`: a Google and a Meta campaign
+//! sharing an ID are two series. Persisted files key them `google:123`;
+//! files written with bare campaign IDs still load, each series taking the
+//! platform recorded in its snapshots.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::RwLock;
+
+use super::bidding::BidStrategy;
+use super::campaign_aggregator::{roas, Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::ids::{CampaignId, GlobalCampaignId};
+use super::money::Currency;
+
+/// Metrics delivered by a campaign on a single day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DailyMetrics {
+    pub date: NaiveDate,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub spend: f64,
+    #[serde(default)]
+    pub conversion_value: f64,
+}
+
+impl DailyMetrics {
+    /// Click-through rate in percent
+    pub fn ctr(&self) -> f64 {
+        if self.impressions == 0 {
+            0.0
+        } else {
+            self.clicks as f64 / self.impressions as f64 * 100.0
+        }
+    }
+
+    /// Cost per acquisition, `None` when the day had no conversions
+    pub fn cpa(&self) -> Option<f64> {
+        if self.conversions == 0 {
+            None
+        } else {
+            Some(self.spend / self.conversions as f64)
+        }
+    }
+
+    /// Return on ad spend of the day, 0 when nothing was spent
+    pub fn roas(&self) -> f64 {
+        roas(self.conversion_value, self.spend)
+    }
+}
+
+/// State of a campaign recorded on one day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Snapshot {
+    /// Month-to-date totals
+    #[serde(flatten)]
+    pub metrics: CampaignMetrics,
+    /// `None` in snapshots written before budgets were recorded
+    #[serde(default)]
+    pub daily_budget: Option<f64>,
+    /// `None` in snapshots written before statuses were recorded
+    #[serde(default)]
+    pub status: Option<CampaignStatus>,
+    /// `None` when bidding was not fetched with the snapshot
+    #[serde(default)]
+    pub bid_strategy: Option<BidStrategy>,
+    /// `None` in snapshots written before names were recorded
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub platform: Option<Platform>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+/// Campaign whose latest snapshot shows it removed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RemovedCampaign {
+    pub id: CampaignId,
+    pub platform: Platform,
+    /// `None` when only snapshots older than these fields show the campaign
+    pub name: Option<String>,
+    pub currency: Option<Currency>,
+    /// Day of the first snapshot of the latest run showing it removed
+    pub removed_on: NaiveDate,
+    /// Day of the last snapshot showing it enabled or paused
+    pub last_active_on: Option<NaiveDate>,
+    /// Month-to-date totals on `removed_on`, after its last delivery
+    pub final_metrics: CampaignMetrics,
+}
+
+/// Snapshots of each campaign by day, keyed by campaign
+pub type SnapshotSeries = HashMap<GlobalCampaignId, BTreeMap<NaiveDate, Snapshot>>;
+
+/// Serde of a `SnapshotSeries` keyed `google:123`, JSON objects only having
+/// string keys; for fields holding one, with `#[serde(with = ...)]`
+pub mod series_keys {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        series: &SnapshotSeries,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(series.iter().map(|(id, days)| (id.to_string(), days)))
+    }
+
+    /// Bare campaign IDs, as written before series were keyed by platform,
+    /// take the latest platform recorded in their snapshots; series without
+    /// one cannot be told apart and are skipped
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SnapshotSeries, D::Error> {
+        let keyed = HashMap::<String, BTreeMap<NaiveDate, Snapshot>>::deserialize(deserializer)?;
+        let mut series = SnapshotSeries::with_capacity(keyed.len());
+        for (key, days) in keyed {
+            let id = match key.parse::<GlobalCampaignId>() {
+                Ok(id) => id,
+                Err(_) => match days.values().rev().find_map(|s| s.platform.clone()) {
+                    Some(platform) => GlobalCampaignId::new(platform, key),
+                    None => {
+                        tracing::warn!(campaign_id = %key, "skipping snapshots without a platform");
+                        continue;
+                    }
+                },
+            };
+            series.entry(id).or_default().extend(days);
+        }
+        Ok(series)
+    }
+}
+
+/// In-memory time series of month-to-date snapshots keyed by campaign
+#[derive(Default)]
+pub struct MetricsStore {
+    snapshots: RwLock<SnapshotSeries>,
+    /// When campaigns of each platform were last recorded by this process
+    recorded_at: RwLock<HashMap<Platform, DateTime<Utc>>>,
+}
+
+impl MetricsStore {
+    /// Record the current metrics of every campaign as the snapshot for `date`
+    ///
+    /// Recording twice on the same day overwrites the earlier snapshot.
+    pub fn record(&self, date: NaiveDate, campaigns: &[Campaign]) {
+        {
+            let now = Utc::now();
+            let mut recorded_at = self.recorded_at.write().unwrap();
+            for campaign in campaigns {
+                recorded_at.insert(campaign.platform.clone(), now);
+            }
+        }
+
+        let mut snapshots = self.snapshots.write().unwrap();
+
+        for campaign in campaigns {
+            snapshots
+                .entry(campaign.global_id())
+                .or_default()
+                .insert(
+                    date,
+                    Snapshot {
+                        metrics: campaign.metrics.clone(),
+                        daily_budget: Some(campaign.daily_budget),
+                        status: Some(campaign.status.clone()),
+                        bid_strategy: campaign.bidding.as_ref().map(|b| b.strategy),
+                        name: Some(campaign.name.clone()),
+                        platform: Some(campaign.platform.clone()),
+                        currency: Some(campaign.currency()),
+                    },
+                );
+        }
+    }
+
+    /// When campaigns of `platform` were last recorded; `None` before the
+    /// first sync of the process, even with snapshots loaded from disk
+    pub fn recorded_at(&self, platform: &Platform) -> Option<DateTime<Utc>> {
+        self.recorded_at.read().unwrap().get(platform).copied()
+    }
+
+    /// `platform`'s campaigns as of their latest snapshot, for requests that
+    /// cannot wait for the platform; campaigns recorded before names,
+    /// statuses or budgets were are left out
+    pub fn latest_campaigns(&self, platform: &Platform) -> Vec<Campaign> {
+        let snapshots = self.snapshots.read().unwrap();
+        snapshots
+            .iter()
+            .filter(|(id, _)| id.platform == *platform)
+            .filter_map(|(id, series)| {
+                let (_, latest) = series.last_key_value()?;
+                Some(Campaign {
+                    id: id.id.clone(),
+                    platform: platform.clone(),
+                    name: latest.name.clone()?,
+                    status: latest.status.clone()?,
+                    objective: None,
+                    campaign_type: None,
+                    daily_budget: latest.daily_budget?,
+                    currency: latest.currency.as_ref()?.as_str().to_string(),
+                    metrics: latest.metrics.clone(),
+                    pacing: None,
+                    targeting: None,
+                    bidding: None,
+                    quality: None,
+                    auction: None,
+                    tracking_template: None,
+                    account_id: None,
+                    labels: Vec::new(),
+                    tags: HashMap::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// Every campaign with at least one snapshot
+    pub fn campaign_ids(&self) -> Vec<GlobalCampaignId> {
+        self.snapshots.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Daily values for a campaign, oldest first
+    pub fn daily_series(&self, campaign: &GlobalCampaignId) -> Vec<DailyMetrics> {
+        let snapshots = self.snapshots.read().unwrap();
+
+        let Some(series) = snapshots.get(campaign) else {
+            return Vec::new();
+        };
+
+        let mut daily = Vec::with_capacity(series.len());
+        let mut previous: Option<(&NaiveDate, &CampaignMetrics)> = None;
+
+        for (date, snapshot) in series {
+            let metrics = &snapshot.metrics;
+            let day = match previous {
+                // Same month: the day's values are the growth of the totals
+                Some((prev_date, prev))
+                    if prev_date.year() == date.year() && prev_date.month() == date.month() =>
+                {
+                    DailyMetrics {
+                        date: *date,
+                        impressions: metrics.impressions.saturating_sub(prev.impressions),
+                        clicks: metrics.clicks.saturating_sub(prev.clicks),
+                        conversions: metrics.conversions.saturating_sub(prev.conversions),
+                        spend: (metrics.cost - prev.cost).max(0.0),
+                        conversion_value: (metrics.conversion_value - prev.conversion_value)
+                            .max(0.0),
+                    }
+                }
+                // First snapshot of a month: totals are the day's values
+                _ => DailyMetrics {
+                    date: *date,
+                    impressions: metrics.impressions,
+                    clicks: metrics.clicks,
+                    conversions: metrics.conversions,
+                    spend: metrics.cost,
+                    conversion_value: metrics.conversion_value,
+                },
+            };
+
+            daily.push(day);
+            previous = Some((date, metrics));
+        }
+
+        daily
+    }
+
+    /// Write all snapshots to `path` as JSON
+    ///
+    /// Writes to a temporary file first so a crash mid-write never leaves a
+    /// truncated snapshot behind.
+    pub fn persist(&self, path: &Path) -> std::io::Result<()> {
+        let mut json = Vec::new();
+        series_keys::serialize(&self.snapshots(), &mut serde_json::Serializer::new(&mut json))?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Load snapshots previously written by `persist`, merging them into the store
+    pub fn load(&self, path: &Path) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        self.merge(series_keys::deserialize(&mut serde_json::Deserializer::from_slice(&bytes))?);
+        Ok(())
+    }
+
+    /// Copy of every snapshot, keyed by campaign
+    pub fn snapshots(&self) -> SnapshotSeries {
+        self.snapshots.read().unwrap().clone()
+    }
+
+    /// Add `loaded` snapshots, replacing recorded ones on the same day
+    pub fn merge(&self, loaded: SnapshotSeries) {
+        let mut snapshots = self.snapshots.write().unwrap();
+        for (campaign_id, series) in loaded {
+            snapshots.entry(campaign_id).or_default().extend(series);
+        }
+    }
+
+    /// Every snapshot of a campaign, oldest first
+    pub fn series(&self, campaign: &GlobalCampaignId) -> BTreeMap<NaiveDate, Snapshot> {
+        self.snapshots.read().unwrap().get(campaign).cloned().unwrap_or_default()
+    }
+
+    /// Daily budget recorded with each snapshot of a campaign
+    pub fn budget_series(&self, campaign: &GlobalCampaignId) -> BTreeMap<NaiveDate, f64> {
+        self.snapshots
+            .read()
+            .unwrap()
+            .get(campaign)
+            .map(|series| {
+                series
+                    .iter()
+                    .filter_map(|(date, s)| Some((*date, s.daily_budget?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Campaigns whose latest snapshot shows them removed, by ID
+    ///
+    /// Their snapshots are kept, so daily values and period comparisons
+    /// still cover the days they ran.
+    pub fn removed(&self) -> Vec<RemovedCampaign> {
+        let snapshots = self.snapshots.read().unwrap();
+        let mut removed: Vec<RemovedCampaign> = snapshots
+            .iter()
+            .filter_map(|(id, series)| {
+                let is_removed = |s: &Snapshot| s.status == Some(CampaignStatus::Removed);
+                let (_, latest) = series.last_key_value()?;
+                if !is_removed(latest) {
+                    return None;
+                }
+                // Walk back over the latest run of removed snapshots
+                let (removed_on, first_removed) =
+                    series.iter().rev().take_while(|(_, s)| is_removed(s)).last()?;
+                let last_active_on = series.range(..*removed_on).next_back().map(|(d, _)| *d);
+
+                Some(RemovedCampaign {
+                    id: id.id.clone(),
+                    platform: id.platform.clone(),
+                    name: latest.name.clone(),
+                    currency: latest.currency.clone(),
+                    removed_on: *removed_on,
+                    last_active_on,
+                    final_metrics: first_removed.metrics.clone(),
+                })
+            })
+            .collect();
+
+        removed.sort_by(|a, b| (&a.id, &a.platform).cmp(&(&b.id, &b.platform)));
+        removed
+    }
+
+    /// The most recent `days` daily values for a campaign, oldest first
+    pub fn recent(&self, campaign: &GlobalCampaignId, days: usize) -> Vec<DailyMetrics> {
+        let series = self.daily_series(campaign);
+        let skip = series.len().saturating_sub(days);
+        series.into_iter().skip(skip).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Platform;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 11, d).unwrap()
+    }
+
+    fn google(id: &str) -> GlobalCampaignId {
+        GlobalCampaignId::new(Platform::Google, id)
+    }
+
+    #[test]
+    fn test_daily_series_derives_day_values_from_totals() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+
+        campaign.metrics.cost = 100.0;
+        campaign.metrics.conversions = 2;
+        store.record(day(1), &[campaign.clone()]);
+
+        campaign.metrics.cost = 250.0;
+        campaign.metrics.conversions = 5;
+        store.record(day(2), &[campaign]);
+
+        let series = store.daily_series(&google("g1"));
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].spend, 100.0);
+        assert_eq!(series[1].spend, 150.0);
+        assert_eq!(series[1].conversions, 3);
+        assert_eq!(series[1].cpa(), Some(50.0));
+    }
+
+    #[test]
+    fn test_recent_keeps_latest_days() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+
+        for d in 1..=5 {
+            campaign.metrics.cost = d as f64 * 10.0;
+            store.record(day(d), &[campaign.clone()]);
+        }
+
+        let recent = store.recent(&google("g1"), 2);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].date, day(4));
+        assert!(store.recent(&google("unknown"), 3).is_empty());
+    }
+
+    #[test]
+    fn test_persist_and_load_round_trip() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        campaign.metrics.cost = 42.0;
+        store.record(day(3), &[campaign]);
+
+        let path = std::env::temp_dir().join(format!("metrics-{}.json", uuid::Uuid::new_v4()));
+        store.persist(&path).unwrap();
+
+        let restored = MetricsStore::default();
+        restored.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.daily_series(&google("g1")), store.daily_series(&google("g1")));
+    }
+
+    #[test]
+    fn test_snapshots_without_budget_still_load() {
+        let path = std::env::temp_dir().join(format!("metrics-{}.json", uuid::Uuid::new_v4()));
+        let metrics = serde_json::to_value(CampaignMetrics {
+            cost: 42.0,
+            ..Default::default()
+        })
+        .unwrap();
+        let keyed = serde_json::json!({ "google:g1": { "2025-11-03": metrics } });
+        std::fs::write(&path, keyed.to_string()).unwrap();
+
+        let store = MetricsStore::default();
+        store.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(store.daily_series(&google("g1"))[0].spend, 42.0);
+        assert!(store.budget_series(&google("g1")).is_empty());
+    }
+
+    #[test]
+    fn test_removed_campaigns_keep_their_final_metrics() {
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        let other = Campaign::sample("g2", Platform::Google);
+
+        campaign.metrics.cost = 80.0;
+        store.record(day(1), &[campaign.clone(), other.clone()]);
+        campaign.metrics.cost = 120.0;
+        campaign.status = CampaignStatus::Removed;
+        store.record(day(2), &[campaign.clone(), other.clone()]);
+        // Next month: still listed as removed, month-to-date totals reset
+        campaign.metrics.cost = 0.0;
+        store.record(NaiveDate::from_ymd_opt(2025, 12, 1).unwrap(), &[campaign, other]);
+
+        let removed = store.removed();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, "g1");
+        assert_eq!(removed[0].name.as_deref(), Some("Campaign g1"));
+        assert_eq!((removed[0].removed_on, removed[0].last_active_on), (day(2), Some(day(1))));
+        assert_eq!(removed[0].final_metrics.cost, 120.0);
+        assert_eq!(store.daily_series(&google("g1"))[1].spend, 40.0);
+    }
+
+    #[test]
+    fn test_campaigns_sharing_an_id_across_platforms_keep_their_series() {
+        let store = MetricsStore::default();
+        let mut google_campaign = Campaign::sample("123", Platform::Google);
+        let mut meta_campaign = Campaign::sample("123", Platform::Meta);
+        google_campaign.metrics.cost = 10.0;
+        meta_campaign.metrics.cost = 70.0;
+        store.record(day(1), &[google_campaign, meta_campaign]);
+
+        let meta = GlobalCampaignId::new(Platform::Meta, "123");
+        assert_eq!(store.daily_series(&google("123"))[0].spend, 10.0);
+        assert_eq!(store.daily_series(&meta)[0].spend, 70.0);
+        assert_eq!(store.latest_campaigns(&Platform::Meta)[0].metrics.cost, 70.0);
+        assert_eq!(store.campaign_ids().len(), 2);
+    }
+
+    #[test]
+    fn test_files_keyed_by_bare_ids_take_the_platform_of_their_snapshots() {
+        let path = std::env::temp_dir().join(format!("metrics-{}.json", uuid::Uuid::new_v4()));
+        let store = MetricsStore::default();
+        let mut campaign = Campaign::sample("m1", Platform::Meta);
+        campaign.metrics.cost = 42.0;
+        store.record(day(3), &[campaign]);
+        let meta = GlobalCampaignId::new(Platform::Meta, "m1");
+        let snapshot = store.snapshots()[&meta][&day(3)].clone();
+        let unplaced = Snapshot { platform: None, ..snapshot.clone() };
+        let legacy = serde_json::json!({
+            "m1": { "2025-11-03": snapshot },
+            "unknown": { "2025-11-03": unplaced },
+        });
+        std::fs::write(&path, legacy.to_string()).unwrap();
+
+        let restored = MetricsStore::default();
+        restored.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.snapshots(), store.snapshots());
+    }
+}
@@ -0,0 +1,229 @@
+//! # Precomputed Summary Example
+//!
+//! `GET /campaigns/summary` summed the columns of the last sync on every
+//! request, although most syncs change nothing between two dashboard
+//! refreshes. The sync worker now keeps the summary ready:
+//!
+//! - each sync fingerprints what a summary reads of every campaign
+//!   (currency, status, daily budget and metrics) and diffs the
+//!   fingerprints with those of the previous sync;
+//! - when no campaign was added, removed or changed, the summary of the
+//!   previous sync is kept; otherwise it is summed again from the new
+//!   columns, once per sync, off the request path;
+//! - requests are answered from the kept summary with no work
+//!   proportional to the number of campaigns, reported as `store` data of
+//!   the last sync in the freshness block. `?compare=previous_period`
+//!   still reads the metrics store.
+//!
+//! A changed summary is summed again rather than adjusted by the changed
+//! campaigns' differences, so its totals stay equal to those of a summary
+//! computed from scratch; subtracting floating point amounts would drift.
+//! Like the columns, a summary older than `COLUMNS_MAX_AGE` is not served,
+//! and the request builds one from a fresh aggregation instead.
+
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use super::campaign_aggregator::{AppState, Campaign, CampaignSummary, Platform};
+use super::columnar::{self, CampaignColumns, COLUMNS_MAX_AGE};
+use super::freshness::{self, DataSource};
+use super::ids::GlobalCampaignId;
+use super::money::Currency;
+
+/// Summary of the campaigns of a sync, ready to serve
+#[derive(Debug, Clone)]
+pub struct PrecomputedSummary {
+    pub summary: Arc<CampaignSummary>,
+    /// Campaign IDs per currency, for period comparisons
+    pub ids_by_currency: Arc<BTreeMap<Currency, Vec<GlobalCampaignId>>>,
+    pub platforms: Vec<Platform>,
+    /// Last sync that found the summary current
+    pub synced_at: DateTime<Utc>,
+    /// Sync that last changed the summary
+    pub changed_at: DateTime<Utc>,
+}
+
+impl PrecomputedSummary {
+    pub fn build(columns: &CampaignColumns) -> Self {
+        Self {
+            summary: Arc::new(columns.summarize()),
+            ids_by_currency: Arc::new(columns.ids_by_currency()),
+            platforms: columns.platforms().to_vec(),
+            synced_at: columns.built_at,
+            changed_at: columns.built_at,
+        }
+    }
+}
+
+/// Campaigns of a sync that differ from those of the previous one
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CampaignDiff {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+impl CampaignDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Summary of the last sync and the fingerprints it was built from
+#[derive(Default)]
+pub struct SummaryCache {
+    current: RwLock<Option<Arc<PrecomputedSummary>>>,
+    fingerprints: Mutex<HashMap<GlobalCampaignId, u64>>,
+}
+
+/// Hash of the fields of `campaign` a summary reads
+fn fingerprint(campaign: &Campaign) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    campaign.currency().hash(&mut hasher);
+    campaign.status.hash(&mut hasher);
+    campaign.daily_budget.to_bits().hash(&mut hasher);
+
+    let metrics = &campaign.metrics;
+    (metrics.impressions, metrics.clicks, metrics.conversions).hash(&mut hasher);
+    metrics.cost.to_bits().hash(&mut hasher);
+    metrics.conversion_value.to_bits().hash(&mut hasher);
+    (metrics.reach, metrics.video_views).hash(&mut hasher);
+    metrics.average_watch_time_seconds.map(f64::to_bits).hash(&mut hasher);
+    for action in &metrics.conversion_actions {
+        action.action.hash(&mut hasher);
+        action.conversions.to_bits().hash(&mut hasher);
+        action.value.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+impl SummaryCache {
+    /// Bring the summary up to the campaigns of a sync, laid out in
+    /// `columns`; it is only summed again when the returned diff is not empty
+    pub fn update(
+        &self,
+        campaigns: &[Campaign],
+        columns: &CampaignColumns,
+    ) -> (Arc<PrecomputedSummary>, CampaignDiff) {
+        let fingerprints: HashMap<GlobalCampaignId, u64> = campaigns
+            .iter()
+            .map(|c| (GlobalCampaignId::new(c.platform.clone(), c.id.clone()), fingerprint(c)))
+            .collect();
+
+        let mut previous = self.fingerprints.lock().unwrap();
+        let mut diff = CampaignDiff::default();
+        for (id, fingerprint) in &fingerprints {
+            match previous.get(id) {
+                None => diff.added += 1,
+                Some(before) if before != fingerprint => diff.changed += 1,
+                Some(_) => {}
+            }
+        }
+        diff.removed = previous.len() - (fingerprints.len() - diff.added);
+        *previous = fingerprints;
+
+        let mut current = self.current.write().unwrap();
+        let summary = Arc::new(match current.as_deref() {
+            Some(kept) if diff.is_empty() => {
+                PrecomputedSummary { synced_at: columns.built_at, ..kept.clone() }
+            }
+            _ => PrecomputedSummary::build(columns),
+        });
+        *current = Some(summary.clone());
+        (summary, diff)
+    }
+
+    /// Summary of the last sync, unless synced more than `max_age` before `now`
+    pub fn fresh(&self, now: DateTime<Utc>, max_age: Duration) -> Option<Arc<PrecomputedSummary>> {
+        let summary = self.current.read().unwrap().clone()?;
+        let age = (now - summary.synced_at).to_std().unwrap_or_default();
+        (age <= max_age).then_some(summary)
+    }
+}
+
+/// Summary of the last sync when fresh, else of the current columns
+pub async fn current_summary(state: &AppState) -> Arc<PrecomputedSummary> {
+    if let Some(summary) = state.summaries.fresh(Utc::now(), COLUMNS_MAX_AGE) {
+        for platform in &summary.platforms {
+            freshness::record(platform, DataSource::Store, Some(summary.synced_at));
+        }
+        return summary;
+    }
+
+    Arc::new(PrecomputedSummary::build(&*columnar::current_columns(state).await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::campaign_aggregator::CampaignStatus;
+    use super::*;
+
+    fn campaigns() -> Vec<Campaign> {
+        (0..4)
+            .map(|i| {
+                let platform = if i % 2 == 0 { Platform::Meta } else { Platform::Google };
+                let mut campaign = Campaign::sample(&i.to_string(), platform);
+                campaign.status = CampaignStatus::Enabled;
+                campaign.daily_budget = 50.0;
+                campaign.metrics.impressions = 1_000;
+                campaign.metrics.cost = 10.1 * i as f64;
+                campaign
+            })
+            .collect()
+    }
+
+    fn sync(cache: &SummaryCache, campaigns: &[Campaign], minute: u32) -> CampaignDiff {
+        let at = "2026-10-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+            + chrono::Duration::minutes(minute.into());
+        cache.update(campaigns, &CampaignColumns::build(campaigns, at)).1
+    }
+
+    fn summary_json(cache: &SummaryCache) -> serde_json::Value {
+        let current = cache.current.read().unwrap().clone().unwrap();
+        serde_json::to_value(&*current.summary).unwrap()
+    }
+
+    #[test]
+    fn test_unchanged_syncs_keep_the_summary() {
+        let cache = SummaryCache::default();
+        let mut campaigns = campaigns();
+        let first = sync(&cache, &campaigns, 0);
+        assert_eq!(first, CampaignDiff { added: 4, removed: 0, changed: 0 });
+        let kept = cache.current.read().unwrap().clone().unwrap();
+
+        // Same values in another order
+        campaigns.reverse();
+        assert!(sync(&cache, &campaigns, 5).is_empty());
+        let current = cache.current.read().unwrap().clone().unwrap();
+        assert!(Arc::ptr_eq(&kept.summary, &current.summary));
+        assert_eq!(current.synced_at - kept.synced_at, chrono::Duration::minutes(5));
+        assert_eq!(current.changed_at, kept.changed_at);
+
+        // A renamed campaign sums the same
+        campaigns[0].name = "Renamed".to_string();
+        assert!(sync(&cache, &campaigns, 10).is_empty());
+    }
+
+    #[test]
+    fn test_changed_campaigns_sum_the_summary_again() {
+        let cache = SummaryCache::default();
+        let mut campaigns = campaigns();
+        sync(&cache, &campaigns, 0);
+
+        campaigns[1].metrics.cost += 5.0;
+        campaigns[2].status = CampaignStatus::Paused;
+        campaigns.remove(3);
+        campaigns.push(Campaign::sample("9", Platform::Google));
+        let diff = sync(&cache, &campaigns, 5);
+        assert_eq!(diff, CampaignDiff { added: 1, removed: 1, changed: 2 });
+
+        let expected = CampaignColumns::build(&campaigns, Utc::now()).summarize();
+        assert_eq!(summary_json(&cache), serde_json::to_value(&expected).unwrap());
+        let current = cache.current.read().unwrap().clone().unwrap();
+        assert_eq!(current.changed_at, current.synced_at);
+    }
+}
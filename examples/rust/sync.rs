@@ -0,0 +1,170 @@
+//! # Sync Worker Example
+//!
+//! Background task that periodically pulls campaigns from the gateway,
+//! records their metrics and audience sizes in the store, evaluates
+//! alert and automation rules, raises critical alerts for tenants
+//! nearing their spend cap and pauses runaway campaigns (`cost_guard`).
+//!
+//! With `sync.delta`, campaigns are fetched in full once a day and only
+//! changed ones in between, see `delta_sync`.
+//!
+//! With shared state (feature `redis`), replicas take turns: a sync only
+//! runs on the replica taking the `sync` lease, held for most of an
+//! interval so the next tick on any replica can take it again.
+
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::alerts::{self, AlertEvent, DeliveryMode};
+use super::anomalies;
+use super::audiences;
+use super::automation;
+use super::bidding;
+use super::campaign_aggregator::{AppState, Campaign, CampaignSummary};
+use super::columnar::CampaignColumns;
+use super::cost_guard;
+use super::updates::{CampaignUpdate, DashboardEvent};
+
+/// Default interval between two syncs
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Share of the interval the sync lease is held
+#[cfg(feature = "redis")]
+const SYNC_LEASE_RATIO: f64 = 0.9;
+
+/// Run the sync loop, syncing once per `interval` until `shutdown` is cancelled
+///
+/// Cancellation is only observed between syncs, so a sync that already
+/// started always finishes writing to the store.
+pub async fn run_sync_worker(state: Arc<AppState>, interval: Duration, shutdown: CancellationToken) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => {
+                if take_sync_lease(&state, interval).await {
+                    sync_once(&state).await;
+                }
+            }
+        }
+    }
+
+    tracing::info!("sync worker stopped");
+}
+
+/// Whether this replica syncs on this tick
+#[cfg(feature = "redis")]
+async fn take_sync_lease(state: &AppState, interval: Duration) -> bool {
+    let Some(shared) = &state.shared else {
+        return true;
+    };
+
+    match shared.try_lease("sync", interval.mul_f64(SYNC_LEASE_RATIO)).await {
+        Ok(taken) => {
+            if !taken {
+                tracing::debug!("another replica holds the sync lease");
+            }
+            taken
+        }
+        Err(e) => {
+            // Syncing anyway would have every replica sync
+            tracing::error!(error = %e, "sync lease unavailable, skipping sync");
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "redis"))]
+async fn take_sync_lease(_state: &AppState, _interval: Duration) -> bool {
+    true
+}
+
+/// Perform a single sync: fetch, record, evaluate alert and automation
+/// rules and publish updates to live subscribers
+pub async fn sync_once(state: &AppState) {
+    let started = Utc::now();
+    // Recorded days are reporting days of the agency, see `timezones`
+    let today = started.with_timezone(&state.config.timezones.default).date_naive();
+    let mut synced = state.delta_sync.fetch_campaigns(state, started).await;
+    let mut campaigns = std::mem::take(&mut synced.campaigns);
+    // Recorded with the snapshots, for the changelog
+    bidding::attach_bidding(&state.connectors, &mut campaigns).await;
+
+    // Campaigns carried over by a delta sync keep their recorded snapshot
+    let fetched: Vec<Campaign> =
+        campaigns.iter().filter(|c| !synced.is_carried(c)).cloned().collect();
+    state.metrics_store.record(today, &fetched);
+    state.labels.rebuild(&campaigns);
+    let columns = state.columns.replace(CampaignColumns::build(&campaigns, started));
+    let (summary, diff) = state.summaries.update(&campaigns, &columns);
+    if !diff.is_empty() {
+        tracing::debug!(?diff, "campaign summary updated");
+    }
+    audiences::sync_audiences(state, today).await;
+    automation::run_rules(state, &campaigns).await;
+
+    let rules = state.alerts.rules();
+    let fired = alerts::evaluate_rules(
+        &rules,
+        &campaigns,
+        &state.metrics_store,
+        today,
+    );
+
+    for (rule, event) in fired {
+        if rule.delivery != DeliveryMode::Immediate {
+            state.alerts.queue_digest(event);
+            continue;
+        }
+        if state.alerts.absorb(&event, rule.dedup_window()) {
+            continue;
+        }
+        let event = state.alert_dispatcher.dispatch(&rule, event).await;
+        record_alert(state, event);
+    }
+
+    let runaways = cost_guard::run_cost_guard(state, &campaigns).await;
+    let now = Utc::now();
+    // Folded within the default dedup window, like rule alerts
+    let cap_window = chrono::Duration::hours(alerts::DEFAULT_DEDUP_WINDOW_HOURS.into());
+    for (notifier, event) in state.spend_caps.alerts(&campaigns, now).into_iter().chain(runaways) {
+        if state.alerts.absorb(&event, cap_window) {
+            continue;
+        }
+        tracing::error!(rule_id = %event.rule_id, "{}", event.message);
+        let event = match &notifier {
+            Some(notifier) => state.alert_dispatcher.deliver(notifier, event).await,
+            None => event,
+        };
+        record_alert(state, event);
+    }
+    for (rule, summary) in state.alerts.take_due_summaries(&rules, now) {
+        state.alert_dispatcher.dispatch(&rule, summary).await;
+    }
+    for (rule, digest) in state.alerts.take_due_digests(&rules, now) {
+        let digest = state.alert_dispatcher.dispatch(&rule, digest).await;
+        record_alert(state, digest);
+    }
+
+    for campaign in &campaigns {
+        let series = state.metrics_store.daily_series(&campaign.global_id());
+        // Sending only fails when nobody is subscribed
+        let _ = state.updates.send(CampaignUpdate {
+            campaign: campaign.clone(),
+            anomalies: anomalies::detect_anomalies(&campaign.global_id(), &series, today),
+        });
+    }
+    let summary = CampaignSummary::clone(&summary.summary);
+    let _ = state.dashboard_events.send(DashboardEvent::Summary(Box::new(summary)));
+
+    tracing::info!(campaigns = campaigns.len(), "sync completed");
+}
+
+/// Record an alert and publish it to dashboard subscribers
+fn record_alert(state: &AppState, event: AlertEvent) {
+    let _ = state.dashboard_events.send(DashboardEvent::Alert(Box::new(event.clone())));
+    state.alerts.record(event);
+}
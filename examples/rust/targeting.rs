@@ -0,0 +1,282 @@
+//! # Targeting Example
+//!
+//! Adds who a campaign reaches to the unified model. Platforms describe
+//! targeting very differently: Google Ads returns criterion IDs (geo
+//! target constants, age range criteria) while Meta returns a targeting
+//! spec with ISO country codes and `age_min` / `age_max`. Both are
+//! normalized into one `Targeting` block with human-readable values,
+//! requested with `GET /campaigns?expand=targeting`.
+//!
+//! IDs missing from the lookup tables are kept in their raw form
+//! (`geoTargetConstants/1234`) rather than dropped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::call_budget;
+use super::campaign_aggregator::Campaign;
+use super::connectors::{ConnectorError, ConnectorRegistry};
+use super::ids::{CampaignId, GlobalCampaignId};
+
+/// Google Ads geo target constants used by our accounts
+const GOOGLE_GEO_TARGETS: &[(u64, &str)] = &[
+    (2076, "Brazil"),
+    (2620, "Portugal"),
+    (2840, "United States"),
+    (1001773, "São Paulo, Brazil"),
+];
+
+/// Google Ads age range criterion IDs
+const GOOGLE_AGE_RANGES: &[(u64, &str)] = &[
+    (503001, "18-24"),
+    (503002, "25-34"),
+    (503003, "35-44"),
+    (503004, "45-54"),
+    (503005, "55-64"),
+    (503006, "65+"),
+    (503999, "unknown"),
+];
+
+/// ISO 3166 country codes used in Meta targeting specs
+const COUNTRY_NAMES: &[(&str, &str)] = &[
+    ("BR", "Brazil"),
+    ("PT", "Portugal"),
+    ("US", "United States"),
+];
+
+/// Oldest age Meta accepts; `age_max` at this value means "and over"
+const META_MAX_AGE: u8 = 65;
+
+/// Normalized targeting of a campaign
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Targeting {
+    /// Countries, regions and cities, e.g. `São Paulo, Brazil`
+    pub locations: Vec<String>,
+    /// Age ranges, e.g. `25-34`, `65+`
+    pub age_ranges: Vec<String>,
+    pub interests: Vec<String>,
+    /// Names of custom / remarketing audiences
+    pub custom_audiences: Vec<String>,
+}
+
+/// Named entity in platform targeting payloads
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NamedId {
+    pub id: String,
+    pub name: String,
+}
+
+/// Campaign targeting as returned by the Google gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleTargeting {
+    pub campaign_id: CampaignId,
+    #[serde(default)]
+    pub location_ids: Vec<u64>,
+    #[serde(default)]
+    pub age_range_ids: Vec<u64>,
+    #[serde(default)]
+    pub user_interests: Vec<NamedId>,
+    #[serde(default)]
+    pub user_lists: Vec<NamedId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MetaGeoLocations {
+    #[serde(default)]
+    pub countries: Vec<String>,
+    #[serde(default)]
+    pub regions: Vec<NamedId>,
+    #[serde(default)]
+    pub cities: Vec<NamedId>,
+}
+
+/// Meta targeting spec, merged over the campaign's ad sets by the gateway
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MetaTargetingSpec {
+    #[serde(default)]
+    pub geo_locations: MetaGeoLocations,
+    pub age_min: Option<u8>,
+    pub age_max: Option<u8>,
+    #[serde(default)]
+    pub interests: Vec<NamedId>,
+    #[serde(default)]
+    pub custom_audiences: Vec<NamedId>,
+}
+
+/// Campaign targeting as returned by the Meta gateway route
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaTargeting {
+    pub campaign_id: CampaignId,
+    pub targeting: MetaTargetingSpec,
+}
+
+fn lookup<K: PartialEq + Copy>(table: &[(K, &str)], key: K) -> Option<String> {
+    table
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, name)| name.to_string())
+}
+
+fn names(items: &[NamedId]) -> Vec<String> {
+    items.iter().map(|i| i.name.clone()).collect()
+}
+
+pub fn normalize_google(raw: &GoogleTargeting) -> Targeting {
+    Targeting {
+        locations: raw
+            .location_ids
+            .iter()
+            .map(|id| lookup(GOOGLE_GEO_TARGETS, *id).unwrap_or_else(|| format!("geoTargetConstants/{}", id)))
+            .collect(),
+        age_ranges: raw
+            .age_range_ids
+            .iter()
+            .map(|id| lookup(GOOGLE_AGE_RANGES, *id).unwrap_or_else(|| format!("ageRange/{}", id)))
+            .collect(),
+        interests: names(&raw.user_interests),
+        custom_audiences: names(&raw.user_lists),
+    }
+}
+
+pub fn normalize_meta(raw: &MetaTargetingSpec) -> Targeting {
+    let geo = &raw.geo_locations;
+    let locations = geo
+        .countries
+        .iter()
+        .map(|code| lookup(COUNTRY_NAMES, code.as_str()).unwrap_or_else(|| code.clone()))
+        .chain(geo.regions.iter().map(|r| r.name.clone()))
+        .chain(geo.cities.iter().map(|c| c.name.clone()))
+        .collect();
+
+    let age_ranges = match (raw.age_min, raw.age_max) {
+        (None, None) => Vec::new(),
+        (min, Some(max)) if max < META_MAX_AGE => vec![format!("{}-{}", min.unwrap_or(18), max)],
+        (min, _) => vec![format!("{}+", min.unwrap_or(18))],
+    };
+
+    Targeting {
+        locations,
+        age_ranges,
+        interests: names(&raw.interests),
+        custom_audiences: names(&raw.custom_audiences),
+    }
+}
+
+/// Google geo target constant of the country `code` (ISO 3166), to create
+/// campaigns targeting it
+pub fn google_country_target(code: &str) -> Option<u64> {
+    let name = lookup(COUNTRY_NAMES, code.to_ascii_uppercase().as_str())?;
+    GOOGLE_GEO_TARGETS.iter().find(|(_, n)| *n == name).map(|(id, _)| *id)
+}
+
+/// Google age range criteria overlapping the ages from `min` to `max`
+pub fn google_age_ranges(min: u8, max: u8) -> Vec<u64> {
+    GOOGLE_AGE_RANGES
+        .iter()
+        .filter(|(_, range)| {
+            let (low, high) = match range.split_once('-') {
+                Some((low, high)) => (low.parse::<u8>().ok(), high.parse::<u8>().ok()),
+                None => {
+                    let low = range.strip_suffix('+').and_then(|low| low.parse::<u8>().ok());
+                    (low, Some(u8::MAX))
+                }
+            };
+            matches!((low, high), (Some(low), Some(high)) if low <= max && high >= min)
+        })
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/// Fill in the `targeting` block of every campaign, one gateway call per
+/// platform
+///
+/// Platforms without targeting support are skipped; other failures are
+/// logged and leave the block empty.
+pub async fn attach_targeting(registry: &ConnectorRegistry, campaigns: &mut [Campaign]) {
+    let mut ids_by_platform: HashMap<_, Vec<CampaignId>> = HashMap::new();
+    for campaign in campaigns.iter() {
+        ids_by_platform
+            .entry(campaign.platform.clone())
+            .or_default()
+            .push(campaign.id.clone());
+    }
+
+    if call_budget::charge(ids_by_platform.len()).is_err() {
+        return;
+    }
+
+    let fetches = ids_by_platform.iter().filter_map(|(platform, ids)| {
+        let connector = registry.get(platform)?;
+        Some(async move { (platform.clone(), connector.fetch_targeting(ids).await) })
+    });
+
+    let mut targeting = HashMap::new();
+    for (platform, result) in futures::future::join_all(fetches).await {
+        match result {
+            Ok(by_campaign) => {
+                for (campaign_id, t) in by_campaign {
+                    targeting.insert(GlobalCampaignId::new(platform.clone(), campaign_id), t);
+                }
+            }
+            Err(ConnectorError::NotSupported(_)) => {}
+            Err(e) => tracing::warn!(platform = platform.as_str(), error = %e, "targeting fetch failed"),
+        }
+    }
+
+    for campaign in campaigns.iter_mut() {
+        campaign.targeting = targeting.remove(&campaign.global_id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> NamedId {
+        NamedId {
+            id: name.to_lowercase(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_google_criteria_ids_become_readable() {
+        let raw = GoogleTargeting {
+            campaign_id: "g1".into(),
+            location_ids: vec![1001773, 9999999],
+            age_range_ids: vec![503002, 503006],
+            user_interests: vec![named("Real Estate")],
+            user_lists: vec![named("Site visitors 30d")],
+        };
+
+        let targeting = normalize_google(&raw);
+
+        assert_eq!(
+            targeting.locations,
+            vec!["São Paulo, Brazil".to_string(), "geoTargetConstants/9999999".to_string()]
+        );
+        assert_eq!(targeting.age_ranges, vec!["25-34".to_string(), "65+".to_string()]);
+        assert_eq!(targeting.custom_audiences, vec!["Site visitors 30d".to_string()]);
+    }
+
+    #[test]
+    fn test_meta_spec_is_normalized() {
+        let raw = MetaTargetingSpec {
+            geo_locations: MetaGeoLocations {
+                countries: vec!["BR".to_string()],
+                regions: Vec::new(),
+                cities: vec![named("Campinas")],
+            },
+            age_min: Some(25),
+            age_max: Some(65),
+            interests: vec![named("Real estate investing")],
+            custom_audiences: Vec::new(),
+        };
+
+        let targeting = normalize_meta(&raw);
+
+        assert_eq!(targeting.locations, vec!["Brazil".to_string(), "Campinas".to_string()]);
+        assert_eq!(targeting.age_ranges, vec!["25+".to_string()]);
+        assert_eq!(targeting.interests, vec!["Real estate investing".to_string()]);
+    }
+}
@@ -0,0 +1,325 @@
+//! # Fake Gateway Test Harness
+//!
+//! An axum server on an ephemeral port that answers the gateway's list
+//! routes with canned Google Ads and Meta responses, in the envelopes the
+//! real gateway relays (`results`/`nextPageToken`, `data`/`paging`). Tests
+//! point an `AppState` at it and drive the real HTTP client, connectors,
+//! pagination and handlers without platform credentials.
+//!
+//! Each platform answers according to its [`Scenario`], switchable while the
+//! server runs:
+//!
+//! - `Fixtures`: the canned campaigns, Google split over two pages
+//! - `Error`: a status with a platform error body, relayed untouched
+//! - `Slow`: the fixtures after a delay, to exercise request timeouts
+//!
+//! Compiled for the crate's own tests and behind the `testing` feature, so
+//! downstream crates can reuse it from their integration tests:
+//!
+//! ```rust,ignore
+//! let gateway = FakeGateway::start().await;
+//! gateway.set_scenario(Platform::Meta, Scenario::meta_token_expired());
+//! let router = build_router(gateway.state(), &ServerOptions::default());
+//! ```
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::campaign_aggregator::{AppState, Campaign, CampaignMetrics, CampaignStatus, Platform};
+use super::config::AppConfig;
+
+/// How a platform's gateway routes answer
+#[derive(Debug, Clone)]
+pub enum Scenario {
+    Fixtures,
+    /// `status` with `body`, as the gateway relays platform failures
+    Error { status: u16, body: String },
+    /// The fixtures, after `delay`
+    Slow(Duration),
+}
+
+impl Scenario {
+    /// Google Ads daily operation quota used up
+    pub fn google_quota_exhausted() -> Self {
+        Scenario::Error {
+            status: 429,
+            body: json!({"error": {
+                "code": 429,
+                "message": "Resource has been exhausted (e.g. check quota).",
+                "status": "RESOURCE_EXHAUSTED",
+                "details": [{"errors": [{
+                    "errorCode": {"quotaError": "RESOURCE_EXHAUSTED"},
+                    "message": "Too many requests."
+                }]}]
+            }})
+            .to_string(),
+        }
+    }
+
+    /// Meta access token expired
+    pub fn meta_token_expired() -> Self {
+        Scenario::Error {
+            status: 400,
+            body: json!({"error": {
+                "message": "Error validating access token: Session has expired.",
+                "type": "OAuthException",
+                "code": 190,
+                "error_subcode": 463,
+                "fbtrace_id": "AbCdEf"
+            }})
+            .to_string(),
+        }
+    }
+
+    /// Transient upstream failure with an HTML body
+    pub fn unavailable() -> Self {
+        Scenario::Error {
+            status: 503,
+            body: "<html>Service Unavailable</html>".to_string(),
+        }
+    }
+}
+
+/// Canned Google Ads campaigns
+pub fn google_campaigns() -> Vec<Campaign> {
+    vec![
+        campaign("g-101", Platform::Google, "Search | Brand | BR", 250.0, 12_000, 480, 310.5),
+        campaign("g-102", Platform::Google, "Search | Generic | BR", 120.0, 8_400, 210, 190.0),
+        campaign("g-103", Platform::Google, "PMax | Retail | BR", 80.0, 30_000, 150, 75.2),
+    ]
+}
+
+/// Canned Meta campaigns
+pub fn meta_campaigns() -> Vec<Campaign> {
+    vec![
+        campaign("m-201", Platform::Meta, "Prospecting | LAL 1% | BR", 200.0, 55_000, 900, 205.0),
+        campaign("m-202", Platform::Meta, "Retargeting | 30d | BR", 60.0, 9_000, 260, 58.9),
+    ]
+}
+
+fn campaign(
+    id: &str,
+    platform: Platform,
+    name: &str,
+    daily_budget: f64,
+    impressions: u64,
+    clicks: u64,
+    cost: f64,
+) -> Campaign {
+    Campaign {
+        id: id.into(),
+        platform,
+        name: name.to_string(),
+        status: CampaignStatus::Enabled,
+        objective: None,
+        campaign_type: None,
+        daily_budget,
+        currency: "BRL".to_string(),
+        metrics: CampaignMetrics {
+            impressions,
+            clicks,
+            cost,
+            ..Default::default()
+        },
+        pacing: None,
+        targeting: None,
+        bidding: None,
+        quality: None,
+        auction: None,
+        tracking_template: None,
+        account_id: None,
+        labels: Vec::new(),
+        tags: HashMap::new(),
+    }
+}
+
+/// Scenarios and request counts shared with the handlers
+#[derive(Default)]
+struct Shared {
+    scenarios: RwLock<HashMap<String, Scenario>>,
+    /// List requests received per platform, pages included
+    requests: RwLock<HashMap<String, usize>>,
+}
+
+/// Running fake gateway
+pub struct FakeGateway {
+    pub url: String,
+    shared: Arc<Shared>,
+}
+
+impl FakeGateway {
+    /// Serve the fixtures until the process exits
+    pub async fn start() -> FakeGateway {
+        let shared = Arc::new(Shared::default());
+        let router = Router::new()
+            .route("/v1/{platform}/health", get(|| async { StatusCode::OK }))
+            .route("/v1/{platform}/campaigns", get(list_campaigns))
+            .with_state(shared.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind fake gateway");
+        let url = format!("http://{}", listener.local_addr().expect("fake gateway address"));
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("fake gateway stopped");
+        });
+
+        FakeGateway { url, shared }
+    }
+
+    pub fn set_scenario(&self, platform: Platform, scenario: Scenario) {
+        self.shared
+            .scenarios
+            .write()
+            .unwrap()
+            .insert(platform.as_str().to_string(), scenario);
+    }
+
+    /// List requests `platform` received so far
+    pub fn requests(&self, platform: &Platform) -> usize {
+        self.shared.requests.read().unwrap().get(platform.as_str()).copied().unwrap_or(0)
+    }
+
+    /// Test configuration with every connector pointed at this gateway
+    pub fn config(&self) -> AppConfig {
+        let mut config = AppConfig::default();
+        config.gateway.url = self.url.clone();
+        config.gateway.service_token = "test".to_string();
+        config
+    }
+
+    /// Application state built from [`FakeGateway::config`]
+    pub fn state(&self) -> Arc<AppState> {
+        Arc::new(AppState::from_config(self.config()).expect("valid test config"))
+    }
+}
+
+/// Position parameters of both platforms' list routes
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    page_token: Option<String>,
+    after: Option<String>,
+}
+
+async fn list_campaigns(
+    State(shared): State<Arc<Shared>>,
+    Path(platform): Path<String>,
+    Query(page): Query<PageQuery>,
+) -> Response {
+    *shared.requests.write().unwrap().entry(platform.clone()).or_default() += 1;
+    let scenario = shared
+        .scenarios
+        .read()
+        .unwrap()
+        .get(&platform)
+        .cloned()
+        .unwrap_or(Scenario::Fixtures);
+
+    match scenario {
+        Scenario::Fixtures => {}
+        Scenario::Error { status, body } => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY);
+            return (status, [(header::CONTENT_TYPE, "application/json")], body).into_response();
+        }
+        Scenario::Slow(delay) => tokio::time::sleep(delay).await,
+    }
+
+    match platform.as_str() {
+        "google" => google_page(page.page_token.as_deref()).into_response(),
+        "meta" => meta_page(page.after.as_deref()).into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Google fixtures over two pages, linked by `nextPageToken`
+fn google_page(token: Option<&str>) -> Json<serde_json::Value> {
+    let campaigns = google_campaigns();
+    let (first, second) = campaigns.split_at(2);
+    Json(match token {
+        None => json!({ "results": first, "nextPageToken": "page-2" }),
+        Some(_) => json!({ "results": second }),
+    })
+}
+
+/// Meta fixtures on one page; Meta keeps the `after` cursor on the last page
+fn meta_page(_after: Option<&str>) -> Json<serde_json::Value> {
+    Json(json!({
+        "data": meta_campaigns(),
+        "paging": { "cursors": { "before": "MAZDZD", "after": "MQZDZD" } }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::server::{build_router, ServerOptions};
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn get_campaigns(state: Arc<AppState>) -> (StatusCode, Vec<Campaign>) {
+        let router = build_router(state, &ServerOptions::default());
+        let request = Request::get("/campaigns").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn ids(campaigns: &[Campaign]) -> Vec<&str> {
+        campaigns.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_campaigns_are_merged_across_platforms_and_pages() {
+        let gateway = FakeGateway::start().await;
+
+        let (status, campaigns) = get_campaigns(gateway.state()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        // Highest daily budget first
+        assert_eq!(ids(&campaigns), ["g-101", "m-201", "g-102", "g-103", "m-202"]);
+        assert_eq!(gateway.requests(&Platform::Google), 2);
+        assert_eq!(gateway.requests(&Platform::Meta), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_platform_is_skipped() {
+        let gateway = FakeGateway::start().await;
+        gateway.set_scenario(Platform::Meta, Scenario::meta_token_expired());
+
+        let (status, campaigns) = get_campaigns(gateway.state()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&campaigns), ["g-101", "g-102", "g-103"]);
+
+        gateway.set_scenario(Platform::Meta, Scenario::Fixtures);
+        gateway.set_scenario(Platform::Google, Scenario::google_quota_exhausted());
+        let (_, campaigns) = get_campaigns(gateway.state()).await;
+        assert_eq!(ids(&campaigns), ["m-201", "m-202"]);
+    }
+
+    #[tokio::test]
+    async fn test_slow_platform_times_out_without_failing_the_request() {
+        let gateway = FakeGateway::start().await;
+        gateway.set_scenario(Platform::Google, Scenario::Slow(Duration::from_millis(1500)));
+        let mut config = gateway.config();
+        config.timeouts.request_secs = 1;
+        let state = Arc::new(AppState::from_config(config).unwrap());
+
+        let (status, campaigns) = get_campaigns(state).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&campaigns), ["m-201", "m-202"]);
+    }
+}
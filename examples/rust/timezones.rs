@@ -0,0 +1,145 @@
+//! # Reporting Time Zones Example
+//!
+//! A São Paulo agency's "yesterday" ends at 03:00 UTC, and an ad account
+//! set to Los Angeles time closes its day four hours later still, so
+//! dailies compared across the three never match. Each request gets a
+//! reporting time zone: the tenant's (`timezones.tenants`, by
+//! `X-Tenant-Id`), else `timezones.default`. Then:
+//!
+//! - `start_date` and `end_date` are days of the reporting time zone, and
+//!   a missing `end_date` is today there, not in UTC;
+//! - date ranges sent to a platform are converted to the days of the
+//!   platform's account time zone (`timezones.platforms`) covering the
+//!   requested ones: Monday in São Paulo is Monday and Tuesday in UTC.
+//!   Platforms without an entry report in the reporting time zone;
+//! - the sync worker records metrics under the days of `timezones.default`,
+//!   so date-ranged campaign totals add up the agency's days;
+//! - every response carries the time zone applied, as
+//!   `X-Timezone: America/Sao_Paulo`.
+//!
+//! ```toml
+//! [timezones]
+//! default = "America/Sao_Paulo"
+//! tenants = { acme = "America/Manaus" }
+//! platforms = { meta = "America/Los_Angeles" }
+//! ```
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::sync::Arc;
+
+use super::config::{AppConfig, TimezoneConfig};
+use super::credentials;
+
+/// Response header naming the reporting time zone applied
+pub const TIMEZONE_HEADER: &str = "x-timezone";
+
+tokio::task_local! {
+    static TIMEZONE: Tz;
+}
+
+/// Reporting time zone of the current request; UTC outside a request scope
+pub fn current() -> Tz {
+    TIMEZONE.try_with(|timezone| *timezone).unwrap_or(Tz::UTC)
+}
+
+/// Today in the reporting time zone of the current request
+pub fn today() -> NaiveDate {
+    Utc::now().with_timezone(&current()).date_naive()
+}
+
+/// First instant of `date` in `timezone`
+///
+/// Where a DST change skips midnight (São Paulo until 2019) the day starts
+/// at 01:00.
+pub fn start_of_day(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    let local = |time: NaiveTime| timezone.from_local_datetime(&date.and_time(time)).earliest();
+    local(NaiveTime::MIN)
+        .or_else(|| local(NaiveTime::from_hms_opt(1, 0, 0).unwrap_or(NaiveTime::MIN)))
+        .map_or_else(|| date.and_time(NaiveTime::MIN).and_utc(), |start| start.with_timezone(&Utc))
+}
+
+/// Days of `to` covering the days `start..=end` of `from`
+pub fn convert_range(
+    (start, end): (NaiveDate, NaiveDate),
+    from: Tz,
+    to: Tz,
+) -> (NaiveDate, NaiveDate) {
+    let first = start_of_day(start, from);
+    let next = end.succ_opt().map_or(first, |next| start_of_day(next, from));
+    let last = (next - chrono::Duration::seconds(1)).max(first);
+    (first.with_timezone(&to).date_naive(), last.with_timezone(&to).date_naive())
+}
+
+/// Days to request from `platform` for the days `range` of the current
+/// request's reporting time zone
+pub fn platform_range(
+    config: &TimezoneConfig,
+    platform: &str,
+    range: (NaiveDate, NaiveDate),
+) -> (NaiveDate, NaiveDate) {
+    let local = current();
+    convert_range(range, local, config.for_platform(platform, local))
+}
+
+/// Middleware scoping the request's reporting time zone and echoing it in
+/// `X-Timezone`; runs inside `credentials::scope_tenant`
+pub async fn scope_timezone(
+    State(config): State<Arc<AppConfig>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let timezone = config.timezones.for_tenant(credentials::tenant().as_deref());
+    let mut response = TIMEZONE.scope(timezone, next.run(req)).await;
+    if let Ok(value) = HeaderValue::from_str(timezone.name()) {
+        response.headers_mut().insert(TIMEZONE_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_local_days_map_to_the_platform_days_covering_them() {
+        let sao_paulo = Tz::America__Sao_Paulo;
+
+        // 03:00 UTC on the 5th to 02:59:59 UTC on the 7th
+        assert_eq!(convert_range((day(5), day(6)), sao_paulo, Tz::UTC), (day(5), day(7)));
+        assert_eq!(
+            convert_range((day(5), day(5)), sao_paulo, Tz::America__Los_Angeles),
+            (day(4), day(5))
+        );
+        assert_eq!(convert_range((day(5), day(6)), sao_paulo, sao_paulo), (day(5), day(6)));
+
+        let config = TimezoneConfig {
+            default: sao_paulo,
+            tenants: HashMap::from([("acme".to_string(), Tz::America__Manaus)]),
+            platforms: HashMap::from([("meta".to_string(), Tz::UTC)]),
+        };
+        assert_eq!(config.for_tenant(Some("acme")), Tz::America__Manaus);
+        assert_eq!(config.for_tenant(Some("other")), sao_paulo);
+        assert_eq!(config.for_platform("google", sao_paulo), sao_paulo);
+        assert_eq!(config.for_platform("meta", sao_paulo), Tz::UTC);
+    }
+
+    #[test]
+    fn test_days_starting_after_a_skipped_midnight_start_at_one() {
+        // São Paulo skipped from 00:00 to 01:00 on 2018-11-04
+        let date = NaiveDate::from_ymd_opt(2018, 11, 4).unwrap();
+        let start = start_of_day(date, Tz::America__Sao_Paulo);
+        assert_eq!(start, date.and_hms_opt(3, 0, 0).unwrap().and_utc());
+    }
+}
@@ -0,0 +1,147 @@
+//! # Latency Breakdown Example
+//!
+//! Splits each request's latency into pipeline stages so a regression
+//! after a release can be pinned to one of them:
+//!
+//! - `auth`: credential refreshes done by the reauth connector
+//! - `adapter.{platform}`: each platform connector's gateway fetch
+//! - `merge`: combining and sorting the platform results
+//! - `serialize`: rendering the response body
+//!
+//! The middleware scopes a `Timings` collector to the request's task, the
+//! same way the call budget is scoped. Every stage runs inside a tracing
+//! span named `stage`, so span-based exporters get per-stage durations,
+//! and the breakdown is logged once per request. With
+//! `server.timing_header` enabled, responses also carry it as
+//! `X-Timing: adapter.google;dur=182.4, merge;dur=0.6, total;dur=190.1`
+//! (`Server-Timing` syntax, durations in milliseconds).
+//!
+//! Adapters run concurrently, so their durations overlap and can add up
+//! to more than `total`. A stage that runs several times in one request
+//! is summed.
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// Response header carrying the breakdown
+pub const TIMING_HEADER: &str = "x-timing";
+
+tokio::task_local! {
+    static TIMINGS: Timings;
+}
+
+/// Stage durations of one request, in first-seen order
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    stages: Arc<Mutex<Vec<(String, Duration)>>>,
+}
+
+impl Timings {
+    fn add(&self, stage: &str, elapsed: Duration) {
+        let mut stages = self.stages.lock().unwrap();
+        match stages.iter_mut().find(|(name, _)| name == stage) {
+            Some((_, total)) => *total += elapsed,
+            None => stages.push((stage.to_string(), elapsed)),
+        }
+    }
+
+    pub fn stages(&self) -> Vec<(String, Duration)> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    /// `stage;dur=ms` entries followed by the request total
+    pub fn header_value(&self, total: Duration) -> String {
+        self.stages()
+            .into_iter()
+            .chain([("total".to_string(), total)])
+            .map(|(stage, elapsed)| format!("{};dur={:.1}", stage, elapsed.as_secs_f64() * 1_000.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn record(stage: &str, elapsed: Duration) {
+    // Work outside a request (sync worker, reports) is not collected
+    let _ = TIMINGS.try_with(|timings| timings.add(stage, elapsed));
+}
+
+/// Run `fut` as `stage` of the current request
+pub async fn timed<F: Future>(stage: &str, fut: F) -> F::Output {
+    let started = Instant::now();
+    let output = fut.instrument(tracing::info_span!("stage", name = stage)).await;
+    record(stage, started.elapsed());
+    output
+}
+
+/// Run `f` as `stage` of the current request
+pub fn time<T>(stage: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let output = tracing::info_span!("stage", name = stage).in_scope(f);
+    record(stage, started.elapsed());
+    output
+}
+
+/// Middleware collecting the stage timings of each request
+///
+/// The state is whether to add the `X-Timing` header.
+pub async fn record_timings(State(expose): State<bool>, req: Request, next: Next) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let timings = Timings::default();
+    let started = Instant::now();
+
+    let mut response = TIMINGS.scope(timings.clone(), next.run(req)).await;
+
+    let total = started.elapsed();
+    let breakdown = timings.header_value(total);
+    tracing::info!(
+        %method,
+        %path,
+        total_ms = total.as_millis() as u64,
+        timing = %breakdown,
+        "request timing"
+    );
+
+    if expose {
+        if let Ok(value) = HeaderValue::from_str(&breakdown) {
+            response.headers_mut().insert(TIMING_HEADER, value);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stages_collected_within_request_scope() {
+        let timings = Timings::default();
+
+        TIMINGS
+            .scope(timings.clone(), async {
+                timed("adapter.google", async {}).await;
+                time("merge", || ());
+                time("merge", || ());
+            })
+            .await;
+        // Outside a request nothing is recorded, and nothing panics
+        time("merge", || ());
+
+        let stages: Vec<String> = timings.stages().into_iter().map(|(s, _)| s).collect();
+        assert_eq!(stages, vec!["adapter.google", "merge"]);
+
+        let header = timings.header_value(Duration::from_millis(12));
+        assert!(header.starts_with("adapter.google;dur="));
+        assert!(header.ends_with("total;dur=12.0"));
+    }
+}
@@ -0,0 +1,274 @@
+//! # Metric Units Example
+//!
+//! Every metric of `CampaignMetrics` is described once in `METRICS`: its
+//! unit (currency, ratio, count, milliseconds) and how it aggregates.
+//! Counts and amounts are summed; ratios such as CTR, CPA and ROAS are
+//! recomputed from the summed components, because averaging a ratio
+//! weights a campaign with 10 clicks like one with 10,000.
+//!
+//! `GET /metrics/schema` publishes the table so clients format and
+//! aggregate the same way.
+
+use axum::response::Json;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::campaign_aggregator::CampaignMetrics;
+use super::error::ApiError;
+use super::finite::safe_div;
+use super::locale::{Formatting, Locale};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    /// Amount in the campaign currency
+    Currency,
+    /// Quotient of two metrics
+    Ratio,
+    Count,
+    Milliseconds,
+}
+
+/// How values of a metric combine across campaigns or days
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Aggregation {
+    Sum,
+    /// `sum(numerator) / sum(denominator) * scale`
+    Ratio {
+        numerator: &'static str,
+        denominator: &'static str,
+        scale: f64,
+    },
+    /// Durations average, weighted by `weight`
+    WeightedMean { weight: &'static str },
+}
+
+/// Schema entry of one metric
+#[derive(Debug, Serialize, Clone)]
+pub struct MetricDef {
+    pub name: &'static str,
+    pub unit: Unit,
+    pub aggregation: Aggregation,
+    /// Ratio stored as a percentage (0-100) rather than a fraction
+    pub percent: bool,
+    #[serde(skip)]
+    pub value: fn(&CampaignMetrics) -> f64,
+}
+
+/// Schema of `CampaignMetrics`
+pub const METRICS: &[MetricDef] = &[
+    MetricDef {
+        name: "impressions",
+        unit: Unit::Count,
+        aggregation: Aggregation::Sum,
+        percent: false,
+        value: |m| m.impressions as f64,
+    },
+    MetricDef {
+        name: "clicks",
+        unit: Unit::Count,
+        aggregation: Aggregation::Sum,
+        percent: false,
+        value: |m| m.clicks as f64,
+    },
+    MetricDef {
+        name: "conversions",
+        unit: Unit::Count,
+        aggregation: Aggregation::Sum,
+        percent: false,
+        value: |m| m.conversions as f64,
+    },
+    MetricDef {
+        name: "cost",
+        unit: Unit::Currency,
+        aggregation: Aggregation::Sum,
+        percent: false,
+        value: |m| m.cost,
+    },
+    MetricDef {
+        name: "conversion_value",
+        unit: Unit::Currency,
+        aggregation: Aggregation::Sum,
+        percent: false,
+        value: |m| m.conversion_value,
+    },
+    MetricDef {
+        name: "ctr",
+        unit: Unit::Ratio,
+        aggregation: Aggregation::Ratio {
+            numerator: "clicks",
+            denominator: "impressions",
+            scale: 100.0,
+        },
+        percent: true,
+        value: |m| m.ctr,
+    },
+    MetricDef {
+        name: "cpa",
+        unit: Unit::Currency,
+        aggregation: Aggregation::Ratio {
+            numerator: "cost",
+            denominator: "conversions",
+            scale: 1.0,
+        },
+        percent: false,
+        value: |m| m.cpa,
+    },
+    MetricDef {
+        name: "roas",
+        unit: Unit::Ratio,
+        aggregation: Aggregation::Ratio {
+            numerator: "conversion_value",
+            denominator: "cost",
+            scale: 1.0,
+        },
+        percent: false,
+        value: |m| m.roas,
+    },
+];
+
+pub fn metric(name: &str) -> Option<&'static MetricDef> {
+    METRICS.iter().find(|m| m.name == name)
+}
+
+/// Aggregate one metric over `rows` according to its schema entry
+///
+/// Ratios with a zero denominator aggregate to 0, matching how the
+/// connectors fill them in.
+pub fn aggregate(def: &MetricDef, rows: &[&CampaignMetrics]) -> f64 {
+    let sum = |name: &str| -> f64 {
+        metric(name)
+            .map(|d| rows.iter().map(|m| (d.value)(m)).sum())
+            .unwrap_or(0.0)
+    };
+
+    match def.aggregation {
+        Aggregation::Sum => rows.iter().map(|m| (def.value)(m)).sum(),
+        Aggregation::Ratio {
+            numerator,
+            denominator,
+            scale,
+        } => {
+            safe_div(sum(numerator), sum(denominator)).map_or(0.0, |r| r * scale)
+        }
+        Aggregation::WeightedMean { weight } => {
+            let weight_of =
+                |m: &CampaignMetrics| metric(weight).map(|w| (w.value)(m)).unwrap_or(0.0);
+            let total_weight: f64 = rows.iter().map(|m| weight_of(m)).sum();
+            if total_weight == 0.0 {
+                0.0
+            } else {
+                rows.iter().map(|m| (def.value)(m) * weight_of(m)).sum::<f64>() / total_weight
+            }
+        }
+    }
+}
+
+/// Aggregate every metric of the schema
+pub fn aggregate_all(rows: &[&CampaignMetrics]) -> BTreeMap<&'static str, f64> {
+    METRICS.iter().map(|def| (def.name, aggregate(def, rows))).collect()
+}
+
+/// Render a value for display, e.g. `1,234.50 BRL`, `3.20%`, `4.50x`,
+/// `1,200`, `850 ms`
+pub fn format_value(def: &MetricDef, value: f64, currency: &str) -> String {
+    format_value_in(def, value, currency, &Locale::default().formatting())
+}
+
+/// Render a value for display in a locale, e.g. `R$ 1.234,50` or `3,20%`
+/// in pt-BR
+pub fn format_value_in(
+    def: &MetricDef,
+    value: f64,
+    currency: &str,
+    formatting: &Formatting,
+) -> String {
+    let sign = if value < 0.0 { "-" } else { "" };
+    let value = value.abs();
+    let number = |value: f64, places: usize| formatting.number(value, places);
+
+    match def.unit {
+        Unit::Currency => format!("{}{}", sign, formatting.money(value, currency)),
+        Unit::Ratio if def.percent => format!("{}{}%", sign, number(value, 2)),
+        Unit::Ratio => format!("{}{}x", sign, number(value, 2)),
+        Unit::Count => format!("{}{}", sign, number(value, 0)),
+        Unit::Milliseconds if value >= 1_000.0 => {
+            format!("{}{} s", sign, number(value / 1_000.0, 1))
+        }
+        Unit::Milliseconds => format!("{}{} ms", sign, number(value, 0)),
+    }
+}
+
+/// Every metric of `metrics` rendered for display
+pub fn format_metrics(metrics: &CampaignMetrics, currency: &str) -> BTreeMap<&'static str, String> {
+    METRICS
+        .iter()
+        .map(|def| (def.name, format_value(def, (def.value)(metrics), currency)))
+        .collect()
+}
+
+/// GET /metrics/schema - Unit and aggregation of every metric
+pub async fn get_metric_schema() -> Result<Json<&'static [MetricDef]>, ApiError> {
+    Ok(Json(METRICS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(impressions: u64, clicks: u64, cost: f64) -> CampaignMetrics {
+        CampaignMetrics {
+            impressions,
+            clicks,
+            cost,
+            ctr: clicks as f64 / impressions as f64 * 100.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ratios_recomputed_instead_of_averaged() {
+        let small = metrics(100, 10, 5.0);
+        let large = metrics(10_000, 100, 500.0);
+
+        let ctr = aggregate(metric("ctr").unwrap(), &[&small, &large]);
+
+        // (10 + 100) / (100 + 10_000), not the mean of 10% and 1%
+        assert!((ctr - 110.0 / 10_100.0 * 100.0).abs() < 1e-9);
+        assert_eq!(aggregate_all(&[&small, &large])["cost"], 505.0);
+    }
+
+    #[test]
+    fn test_values_formatted_by_unit() {
+        let m = CampaignMetrics {
+            impressions: 1_234_567,
+            cost: 1234.5,
+            ctr: 3.2,
+            roas: 4.5,
+            ..Default::default()
+        };
+
+        let formatted = format_metrics(&m, "BRL");
+
+        assert_eq!(formatted["impressions"], "1,234,567");
+        assert_eq!(formatted["cost"], "1,234.50 BRL");
+        assert_eq!(formatted["ctr"], "3.20%");
+        assert_eq!(formatted["roas"], "4.50x");
+    }
+
+    #[test]
+    fn test_every_ratio_component_is_in_the_schema() {
+        for def in METRICS {
+            if let Aggregation::Ratio {
+                numerator,
+                denominator,
+                ..
+            } = def.aggregation
+            {
+                assert!(metric(numerator).is_some(), "{}", def.name);
+                assert!(metric(denominator).is_some(), "{}", def.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! # Live Updates Example
+//!
+//! Server-Sent Events stream pushing campaign updates to dashboards. The
+//! sync worker publishes one `CampaignUpdate` per campaign after every
+//! sync on a broadcast channel; each SSE client gets its own receiver.
+//! A second channel carries the summary after every sync and each alert
+//! recorded, for WebSocket subscribers (see `ws`).
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::Stream;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+
+use super::alerts::AlertEvent;
+use super::anomalies::Anomaly;
+use super::campaign_aggregator::{AppState, Campaign, CampaignSummary};
+
+/// Capacity of the update channel; slow clients skip missed updates
+pub const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Update pushed to subscribers after each sync
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignUpdate {
+    pub campaign: Campaign,
+    /// Anomalies detected on the latest synced day
+    #[serde(default)]
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// Dashboard event other than a campaign update
+#[derive(Debug, Clone)]
+pub enum DashboardEvent {
+    /// Totals of every campaign after a sync
+    Summary(Box<CampaignSummary>),
+    /// Alert recorded by the sync worker
+    Alert(Box<AlertEvent>),
+}
+
+/// Create the broadcast sender stored in `AppState`
+pub fn update_channel() -> broadcast::Sender<CampaignUpdate> {
+    broadcast::channel(UPDATE_CHANNEL_CAPACITY).0
+}
+
+/// Create the dashboard event sender stored in `AppState`
+pub fn dashboard_channel() -> broadcast::Sender<DashboardEvent> {
+    broadcast::channel(UPDATE_CHANNEL_CAPACITY).0
+}
+
+/// GET /campaigns/stream - SSE stream of campaign updates
+pub async fn stream_updates(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.updates.subscribe()).filter_map(|update| {
+        // Lagged receivers drop the missed updates and keep streaming
+        let update = update.ok()?;
+        Event::default()
+            .event("campaign")
+            .json_data(&update)
+            .ok()
+            .map(Ok)
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
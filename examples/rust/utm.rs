@@ -0,0 +1,590 @@
+//! # UTM Audit Example
+//!
+//! Analytics attributes paid traffic by the UTM parameters each campaign
+//! appends to its landing pages: Google's final URL suffix or tracking
+//! template, Meta's URL tags. A campaign missing `utm_campaign`, or
+//! tagging itself `utm_source=Facebook` next to `utm_source=meta`, splits
+//! or loses its conversions in every report downstream.
+//!
+//! The UTM convention lists the parameters every tracking template must
+//! set and the value each one must hold, written with placeholders:
+//!
+//! - `{platform}`, `{campaign_id}`, `{campaign_name}`;
+//! - any tag the naming conventions extract from the name, e.g. `{market}`.
+//!
+//! Placeholder values are slugged (`Imoveis Alto Padrao` becomes
+//! `imoveis_alto_padrao`). Values holding a platform macro such as
+//! `{campaignid}` or `{{campaign.name}}` are filled in at click time and
+//! accepted as they are. `GET /campaigns/utm-audit` checks every campaign
+//! against the convention and, for each one that breaks it, suggests a
+//! corrected template keeping its landing page and non-UTM parameters.
+//! With `check_landing_pages=true` the landing page of each full-URL
+//! template is also requested, and pages answering with an error reported.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use super::campaign_aggregator::{aggregate_campaigns, AppState, Campaign, Platform};
+use super::error::ApiError;
+use super::ids::CampaignId;
+
+/// Landing pages requested at once by an audit
+pub const LANDING_PAGE_CONCURRENCY: usize = 8;
+
+/// A parameter the convention requires
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UtmParameter {
+    pub name: String,
+    /// Expected value with placeholders; any value is accepted when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// Parameters every tracking template must set
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UtmConvention {
+    pub parameters: Vec<UtmParameter>,
+    /// Require lowercase values in every `utm_` parameter
+    #[serde(default)]
+    pub lowercase: bool,
+}
+
+impl Default for UtmConvention {
+    fn default() -> Self {
+        let parameter = |name: &str, value: &str| UtmParameter {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+        };
+        Self {
+            parameters: vec![
+                parameter("utm_source", "{platform}"),
+                parameter("utm_medium", "cpc"),
+                parameter("utm_campaign", "{campaign_name}"),
+            ],
+            lowercase: true,
+        }
+    }
+}
+
+impl UtmConvention {
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut seen = HashSet::new();
+        for parameter in &self.parameters {
+            let name = parameter.name.as_str();
+            if name.is_empty() || name.contains(['=', '&', '?', '#']) {
+                return Err(ApiError::Validation(format!("invalid parameter name {:?}", name)));
+            }
+            if !seen.insert(name) {
+                return Err(ApiError::Validation(format!("parameter {:?} listed twice", name)));
+            }
+            if let Some(value) = &parameter.value {
+                placeholders(value).map_err(ApiError::Validation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Configured UTM convention
+#[derive(Default)]
+pub struct UtmConventions {
+    convention: RwLock<UtmConvention>,
+}
+
+impl UtmConventions {
+    pub fn get(&self) -> UtmConvention {
+        self.convention.read().unwrap().clone()
+    }
+
+    pub fn set(&self, convention: UtmConvention) {
+        *self.convention.write().unwrap() = convention;
+    }
+}
+
+/// How a campaign's tracking breaks the convention
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UtmIssue {
+    MissingTrackingTemplate,
+    MissingParameter { parameter: String },
+    DuplicateParameter { parameter: String },
+    UnexpectedValue {
+        parameter: String,
+        expected: String,
+        actual: String,
+    },
+    NotLowercase { parameter: String, value: String },
+    /// Landing page served over plain HTTP
+    InsecureLandingPage { url: String },
+    /// Landing page answered with an error or could not be reached
+    LandingPageUnreachable { url: String, reason: String },
+}
+
+impl UtmIssue {
+    /// Whether a corrected template fixes the issue
+    fn is_correctable(&self) -> bool {
+        !matches!(
+            self,
+            UtmIssue::InsecureLandingPage { .. } | UtmIssue::LandingPageUnreachable { .. }
+        )
+    }
+}
+
+/// Audit of one campaign
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CampaignUtmAudit {
+    pub id: CampaignId,
+    pub platform: Platform,
+    pub name: String,
+    pub tracking_template: Option<String>,
+    /// Landing page of a full-URL template
+    pub landing_page: Option<String>,
+    pub issues: Vec<UtmIssue>,
+    /// Template satisfying the convention, when the current one does not
+    pub suggested_template: Option<String>,
+}
+
+impl CampaignUtmAudit {
+    pub fn is_compliant(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// `GET /campaigns/utm-audit` response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UtmAuditReport {
+    pub compliant: usize,
+    pub non_compliant: usize,
+    pub campaigns: Vec<CampaignUtmAudit>,
+}
+
+/// Query parameters for `GET /campaigns/utm-audit`
+#[derive(Debug, Deserialize, Default)]
+pub struct UtmAuditQuery {
+    pub platform: Option<Platform>,
+    /// List only the campaigns with issues
+    #[serde(default)]
+    pub violations_only: bool,
+    /// Request each landing page and report the ones failing
+    #[serde(default)]
+    pub check_landing_pages: bool,
+}
+
+/// Tracking template split into landing page and query parameters
+struct ParsedTemplate<'a> {
+    /// Everything before `?`; `None` for a bare suffix such as Meta's URL tags
+    base: Option<&'a str>,
+    params: Vec<(&'a str, &'a str)>,
+}
+
+fn parse_template(template: &str) -> ParsedTemplate<'_> {
+    let template = template.trim();
+    let (base, query) = match template.split_once('?') {
+        Some((base, query)) => (Some(base), query),
+        None if template.contains("://") || template.starts_with('{') => (Some(template), ""),
+        None => (None, template),
+    };
+    let query = query.split_once('#').map_or(query, |(query, _)| query);
+
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .collect();
+
+    ParsedTemplate { base, params }
+}
+
+/// Names of the `{placeholder}`s in a convention value
+fn placeholders(value: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = value;
+
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| format!("unclosed placeholder in {:?}", value))?;
+        let name = &rest[open + 1..close];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid placeholder {:?} in {:?}", name, value));
+        }
+        names.push(name);
+        rest = &rest[close + 1..];
+    }
+
+    Ok(names)
+}
+
+/// Value safe in a query string: runs of other characters become `_`
+fn slug(value: &str, lowercase: bool) -> String {
+    let slug = value
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if lowercase {
+        slug.to_ascii_lowercase()
+    } else {
+        slug
+    }
+}
+
+/// Convention value for `campaign`, or `None` when a placeholder has no
+/// value for it (a tag its name does not carry)
+fn expected_value(template: &str, campaign: &Campaign, lowercase: bool) -> Option<String> {
+    let mut value = template.to_string();
+    for name in placeholders(template).ok()? {
+        let raw = match name {
+            "platform" => campaign.platform.as_str(),
+            "campaign_id" => campaign.id.as_str(),
+            "campaign_name" => campaign.name.as_str(),
+            tag => campaign.tags.get(tag)?.as_str(),
+        };
+        value = value.replace(&format!("{{{}}}", name), &slug(raw, lowercase));
+    }
+    Some(value)
+}
+
+/// Filled in by the platform at click time
+fn is_dynamic(value: &str) -> bool {
+    value.contains('{')
+}
+
+/// Check one campaign's tracking template against the convention
+pub fn audit_campaign(convention: &UtmConvention, campaign: &Campaign) -> CampaignUtmAudit {
+    let mut audit = CampaignUtmAudit {
+        id: campaign.id.clone(),
+        platform: campaign.platform.clone(),
+        name: campaign.name.clone(),
+        tracking_template: campaign.tracking_template.clone(),
+        landing_page: None,
+        issues: Vec::new(),
+        suggested_template: None,
+    };
+
+    let template = campaign.tracking_template.as_deref().filter(|t| !t.trim().is_empty());
+    let parsed = template.map(parse_template).unwrap_or(ParsedTemplate {
+        base: None,
+        params: Vec::new(),
+    });
+    if template.is_none() {
+        audit.issues.push(UtmIssue::MissingTrackingTemplate);
+    }
+
+    audit.landing_page = parsed
+        .base
+        .filter(|base| base.starts_with("http://") || base.starts_with("https://"))
+        .map(str::to_string);
+    if let Some(url) = audit.landing_page.as_ref().filter(|url| url.starts_with("http://")) {
+        audit.issues.push(UtmIssue::InsecureLandingPage { url: url.clone() });
+    }
+
+    let expected: HashMap<&str, Option<String>> = convention
+        .parameters
+        .iter()
+        .map(|p| {
+            let value = p.value.as_deref();
+            let value = value.and_then(|v| expected_value(v, campaign, convention.lowercase));
+            (p.name.as_str(), value)
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut corrected: Vec<(&str, String)> = Vec::new();
+    for &(name, value) in &parsed.params {
+        if !seen.insert(name) {
+            audit.issues.push(UtmIssue::DuplicateParameter {
+                parameter: name.to_string(),
+            });
+            continue;
+        }
+
+        let mut fixed = value.to_string();
+        match expected.get(name) {
+            Some(Some(want)) if !is_dynamic(value) && value != want => {
+                audit.issues.push(UtmIssue::UnexpectedValue {
+                    parameter: name.to_string(),
+                    expected: want.clone(),
+                    actual: value.to_string(),
+                });
+                fixed = want.clone();
+            }
+            _ if convention.lowercase
+                && name.starts_with("utm_")
+                && !is_dynamic(value)
+                && value.chars().any(|c| c.is_ascii_uppercase()) =>
+            {
+                audit.issues.push(UtmIssue::NotLowercase {
+                    parameter: name.to_string(),
+                    value: value.to_string(),
+                });
+                fixed = value.to_ascii_lowercase();
+            }
+            _ => {}
+        }
+        corrected.push((name, fixed));
+    }
+
+    for parameter in &convention.parameters {
+        let name = parameter.name.as_str();
+        if seen.contains(name) {
+            continue;
+        }
+        audit.issues.push(UtmIssue::MissingParameter {
+            parameter: name.to_string(),
+        });
+        if let Some(Some(want)) = expected.get(name) {
+            corrected.push((name, want.clone()));
+        }
+    }
+
+    if audit.issues.iter().any(UtmIssue::is_correctable) {
+        let query = corrected
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        audit.suggested_template = Some(match parsed.base {
+            Some(base) => format!("{}?{}", base, query),
+            None => query,
+        });
+    }
+
+    audit
+}
+
+/// Request `url`, falling back to GET for servers refusing HEAD
+async fn check_landing_page(client: &reqwest::Client, url: &str) -> Result<(), String> {
+    let mut response = client.head(url).send().await.map_err(|e| e.to_string())?;
+    if matches!(response.status().as_u16(), 405 | 501) {
+        response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    }
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        return Err(format!("HTTP {}", status.as_u16()));
+    }
+    Ok(())
+}
+
+/// Report unreachable landing pages, requesting each distinct URL once
+async fn check_landing_pages(state: &AppState, audits: &mut [CampaignUtmAudit]) {
+    // Landing pages are third-party sites and must not get the service token
+    let client = match reqwest::Client::builder()
+        .timeout(state.config.request_timeout())
+        .connect_timeout(state.config.connect_timeout())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!(error = %e, "landing page client unavailable");
+            return;
+        }
+    };
+
+    let urls: HashSet<String> = audits.iter().filter_map(|a| a.landing_page.clone()).collect();
+    let failures: HashMap<String, String> = stream::iter(urls)
+        .map(|url| {
+            let client = &client;
+            async move {
+                let result = check_landing_page(client, &url).await;
+                result.err().map(|reason| (url, reason))
+            }
+        })
+        .buffer_unordered(LANDING_PAGE_CONCURRENCY)
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await;
+
+    for audit in audits {
+        let Some(url) = &audit.landing_page else {
+            continue;
+        };
+        if let Some(reason) = failures.get(url) {
+            audit.issues.push(UtmIssue::LandingPageUnreachable {
+                url: url.clone(),
+                reason: reason.clone(),
+            });
+        }
+    }
+}
+
+/// GET /campaigns/utm-audit - UTM compliance of every campaign
+pub async fn get_utm_audit(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UtmAuditQuery>,
+) -> Result<Json<UtmAuditReport>, ApiError> {
+    let convention = state.utm.get();
+    let mut audits: Vec<CampaignUtmAudit> = aggregate_campaigns(&state)
+        .await
+        .iter()
+        .filter(|c| query.platform.as_ref().is_none_or(|p| *p == c.platform))
+        .map(|c| audit_campaign(&convention, c))
+        .collect();
+
+    if query.check_landing_pages {
+        check_landing_pages(&state, &mut audits).await;
+    }
+
+    let compliant = audits.iter().filter(|a| a.is_compliant()).count();
+    let non_compliant = audits.len() - compliant;
+    if query.violations_only {
+        audits.retain(|a| !a.is_compliant());
+    }
+
+    Ok(Json(UtmAuditReport {
+        compliant,
+        non_compliant,
+        campaigns: audits,
+    }))
+}
+
+/// GET /utm/convention - Current UTM convention
+pub async fn get_utm_convention(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<UtmConvention>, ApiError> {
+    Ok(Json(state.utm.get()))
+}
+
+/// PUT /utm/convention - Replace the UTM convention
+pub async fn put_utm_convention(
+    State(state): State<Arc<AppState>>,
+    Json(convention): Json<UtmConvention>,
+) -> Result<Json<UtmConvention>, ApiError> {
+    convention.validate()?;
+    state.utm.set(convention.clone());
+    Ok(Json(convention))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(platform: Platform, name: &str, template: Option<&str>) -> Campaign {
+        let mut campaign = Campaign::sample("1", platform);
+        campaign.name = name.to_string();
+        campaign.tracking_template = template.map(str::to_string);
+        campaign
+    }
+
+    #[test]
+    fn test_compliant_templates_pass() {
+        let convention = UtmConvention::default();
+        let suffix = campaign(
+            Platform::Meta,
+            "Casa Jardim",
+            Some("utm_source=meta&utm_medium=cpc&utm_campaign=casa_jardim"),
+        );
+        let dynamic = campaign(
+            Platform::Google,
+            "Brand",
+            Some("{lpurl}?utm_source=google&utm_medium=cpc&utm_campaign={campaignid}"),
+        );
+
+        assert!(audit_campaign(&convention, &suffix).is_compliant());
+        assert!(audit_campaign(&convention, &dynamic).is_compliant());
+    }
+
+    #[test]
+    fn test_issues_reported_and_template_corrected() {
+        let convention = UtmConvention::default();
+        let broken = campaign(
+            Platform::Google,
+            "BR Imoveis | Leads",
+            Some(
+                "http://example.com/imoveis\
+                 ?utm_source=Google&gclid=x&utm_source=bing&utm_medium=CPC",
+            ),
+        );
+
+        let audit = audit_campaign(&convention, &broken);
+
+        assert_eq!(
+            audit.issues,
+            vec![
+                UtmIssue::InsecureLandingPage {
+                    url: "http://example.com/imoveis".to_string()
+                },
+                UtmIssue::UnexpectedValue {
+                    parameter: "utm_source".to_string(),
+                    expected: "google".to_string(),
+                    actual: "Google".to_string(),
+                },
+                UtmIssue::DuplicateParameter {
+                    parameter: "utm_source".to_string()
+                },
+                UtmIssue::UnexpectedValue {
+                    parameter: "utm_medium".to_string(),
+                    expected: "cpc".to_string(),
+                    actual: "CPC".to_string(),
+                },
+                UtmIssue::MissingParameter {
+                    parameter: "utm_campaign".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            audit.suggested_template.as_deref(),
+            Some(
+                "http://example.com/imoveis?utm_source=google&gclid=x&utm_medium=cpc\
+                 &utm_campaign=br_imoveis_leads"
+            )
+        );
+    }
+
+    #[test]
+    fn test_tag_placeholders_and_missing_template() {
+        let convention = UtmConvention {
+            parameters: vec![UtmParameter {
+                name: "utm_content".to_string(),
+                value: Some("{market}-{campaign_id}".to_string()),
+            }],
+            lowercase: false,
+        };
+        let mut tagged = campaign(Platform::Meta, "BR_Imoveis_Leads", None);
+        tagged.tags.insert("market".to_string(), "BR".to_string());
+        let untagged = campaign(Platform::Meta, "Brand", Some("utm_content=anything"));
+
+        let audit = audit_campaign(&convention, &tagged);
+        assert_eq!(
+            audit.issues,
+            vec![
+                UtmIssue::MissingTrackingTemplate,
+                UtmIssue::MissingParameter {
+                    parameter: "utm_content".to_string()
+                },
+            ]
+        );
+        assert_eq!(audit.suggested_template.as_deref(), Some("utm_content=BR-1"));
+
+        // Without the tag, only presence is checked
+        assert!(audit_campaign(&convention, &untagged).is_compliant());
+    }
+
+    #[test]
+    fn test_invalid_conventions_rejected() {
+        let convention = |name: &str, value: &str| UtmConvention {
+            parameters: vec![UtmParameter {
+                name: name.to_string(),
+                value: Some(value.to_string()),
+            }],
+            lowercase: true,
+        };
+
+        assert!(convention("utm_source", "{platform}").validate().is_ok());
+        assert!(convention("utm_source", "{platform").validate().is_err());
+        assert!(convention("utm_source", "{plat form}").validate().is_err());
+        assert!(convention("utm=source", "x").validate().is_err());
+
+        let mut twice = UtmConvention::default();
+        twice.parameters.push(twice.parameters[0].clone());
+        assert!(twice.validate().is_err());
+    }
+}
@@ -0,0 +1,361 @@
+//! # API Versioning Example
+//!
+//! Changing the shape of money amounts or of error bodies breaks every
+//! client at once, so breaking changes ship as a new version of the API
+//! next to the old one:
+//!
+//! - `/v1/...` serves every route with its current shape. Unversioned
+//!   paths (`/campaigns`) are the `v1` routes, kept for existing clients;
+//! - `/v2/campaigns` and `/v2/campaigns/summary` wrap results in a
+//!   `{"data": ..., "meta": {...}}` envelope, return amounts as `Money`
+//!   (`{"amount": 120.0, "currency": "BRL"}`) instead of bare numbers
+//!   next to a `currency` field, and report the next page cursor in
+//!   `meta.next_cursor`. Field selection and NDJSON streaming are `v1`
+//!   only for now;
+//! - `v2` errors are `{"error": {"code": ..., "message": ..., "status": ...}}`
+//!   with the stable codes of `error`, rather than `application/problem+json`.
+//!
+//! Both versions call the same handler cores (`list_campaigns`,
+//! `campaign_summary`); only the rendering differs.
+//!
+//! A version listed in `api_versions.deprecated` is answered with a
+//! `Deprecation` header (RFC 9745) from its configured day, a `Sunset`
+//! header (RFC 8594) when a sunset day is set, and a `Link` to the
+//! successor route when the next version serves the same path. From the
+//! sunset day on its requests are refused with `410 Gone`. Health checks
+//! and `/internal/` routes are not versioned and never deprecated.
+
+use axum::{
+    body::Body,
+    extract::{Query, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::campaign_aggregator::{
+    campaign_summary, list_campaigns, AppState, Campaign, CampaignQuery, CampaignSummary,
+};
+use super::config::{AppConfig, VersionDeprecation};
+use super::error::{ApiError, Problem};
+use super::finite::Finite;
+use super::history::SummaryQuery;
+use super::money::Money;
+use super::timezones;
+
+/// Paths outside the version policy
+const UNVERSIONED_PREFIXES: &[&str] = &["/healthz", "/readyz", "/internal/"];
+
+/// Paths served by the `v2` routes, without the version prefix
+pub const V2_PATHS: &[&str] = &["/campaigns", "/campaigns/summary"];
+
+/// Version of the API
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    /// Version replacing this one
+    pub fn successor(&self) -> Option<ApiVersion> {
+        match self {
+            ApiVersion::V1 => Some(ApiVersion::V2),
+            ApiVersion::V2 => None,
+        }
+    }
+
+    /// Whether this version serves `path`, given without the version prefix
+    pub fn serves(&self, path: &str) -> bool {
+        match self {
+            ApiVersion::V1 => true,
+            ApiVersion::V2 => V2_PATHS.contains(&path),
+        }
+    }
+}
+
+/// Version of a request path and the path without its version prefix;
+/// unversioned paths are `v1`
+pub fn version_of(path: &str) -> (ApiVersion, &str) {
+    for version in [ApiVersion::V1, ApiVersion::V2] {
+        let Some(rest) = path.strip_prefix('/').and_then(|p| p.strip_prefix(version.as_str()))
+        else {
+            continue;
+        };
+        if rest.is_empty() {
+            return (version, "/");
+        }
+        if rest.starts_with('/') {
+            return (version, rest);
+        }
+    }
+    (ApiVersion::V1, path)
+}
+
+/// `path` without its version prefix, e.g. to look up route permissions
+pub fn unversioned(path: &str) -> &str {
+    version_of(path).1
+}
+
+/// Response body of the `v2` routes
+#[derive(Debug, Serialize)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: EnvelopeMeta,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct EnvelopeMeta {
+    /// Cursor of the next page, for paged lists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Error body of the `v2` routes
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ErrorBody {
+    /// Stable code, as in `application/problem+json` bodies
+    pub code: String,
+    pub message: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_code: Option<String>,
+}
+
+impl From<Problem> for ErrorEnvelope {
+    fn from(problem: Problem) -> Self {
+        Self {
+            error: ErrorBody {
+                code: problem.code,
+                message: problem.detail,
+                status: problem.status,
+                retry_after: problem.retry_after,
+                remediation: problem.remediation,
+                upstream_code: problem.upstream_code,
+            },
+        }
+    }
+}
+
+/// `v2` representation of a campaign: budget and money metrics as `Money`
+pub fn campaign_v2(campaign: &Campaign) -> Result<Value, ApiError> {
+    let currency = campaign.currency();
+    let money = |amount: f64| serde_json::to_value(Money { amount, currency: currency.clone() });
+    let to_internal = |e: serde_json::Error| ApiError::Internal(e.to_string());
+
+    let mut value = serde_json::to_value(campaign).map_err(to_internal)?;
+    let Some(fields) = value.as_object_mut() else {
+        return Err(ApiError::Internal("campaign is not an object".to_string()));
+    };
+    fields.remove("currency");
+    fields.insert("daily_budget".to_string(), money(campaign.daily_budget).map_err(to_internal)?);
+    if let Some(metrics) = fields.get_mut("metrics").and_then(Value::as_object_mut) {
+        let m = &campaign.metrics;
+        for (name, amount) in
+            [("cost", m.cost), ("cpa", m.cpa), ("conversion_value", m.conversion_value)]
+        {
+            metrics.insert(name.to_string(), money(amount).map_err(to_internal)?);
+        }
+    }
+    Ok(value)
+}
+
+/// GET /v2/campaigns - Unified campaigns with `Money` amounts, in an envelope
+pub async fn get_campaigns_v2(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CampaignQuery>,
+) -> Result<Finite<Envelope<Vec<Value>>>, ApiError> {
+    if query.fields.is_some() {
+        return Err(ApiError::Validation("fields is only supported by /v1".to_string()));
+    }
+    let page = list_campaigns(&state, &query).await?;
+    let campaigns = page.campaigns.iter().map(campaign_v2).collect::<Result<_, _>>()?;
+
+    Ok(Finite(Envelope {
+        data: campaigns,
+        meta: EnvelopeMeta { next_cursor: page.next_cursor.map(|next| next.to_string()) },
+    }))
+}
+
+/// GET /v2/campaigns/summary - Totals per currency, in an envelope
+pub async fn get_campaign_summary_v2(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SummaryQuery>,
+) -> Result<Finite<Envelope<CampaignSummary>>, ApiError> {
+    Ok(Finite(Envelope {
+        data: campaign_summary(&state, &query).await?,
+        meta: EnvelopeMeta::default(),
+    }))
+}
+
+/// Routes of the `v2` tree, nested under `/v2`; one per `V2_PATHS` entry
+pub fn v2_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/campaigns", get(get_campaigns_v2))
+        .route("/campaigns/summary", get(get_campaign_summary_v2))
+}
+
+/// `Deprecation`, `Sunset` and `Link` headers of a deprecated version
+pub fn deprecation_headers(
+    version: ApiVersion,
+    path: &str,
+    deprecation: &VersionDeprecation,
+) -> Vec<(HeaderName, String)> {
+    let midnight = |day: NaiveDate| day.and_time(chrono::NaiveTime::MIN).and_utc();
+    let mut headers = vec![(
+        HeaderName::from_static("deprecation"),
+        format!("@{}", midnight(deprecation.since).timestamp()),
+    )];
+    if let Some(sunset) = deprecation.sunset {
+        let date = midnight(sunset).format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        headers.push((HeaderName::from_static("sunset"), date));
+    }
+    if let Some(successor) = version.successor().filter(|s| s.serves(path)) {
+        let link = format!("</{}{}>; rel=\"successor-version\"", successor.as_str(), path);
+        headers.push((header::LINK, link));
+    }
+    headers
+}
+
+/// Rewrite an `application/problem+json` response as a `v2` error envelope
+async fn with_error_envelope(response: Response) -> Response {
+    let is_problem = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes() == b"application/problem+json");
+    if !is_problem {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not buffer error response");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(problem) = serde_json::from_slice::<Problem>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let body = Json(ErrorEnvelope::from(problem)).into_response().into_body();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(parts, body)
+}
+
+fn insert_headers(headers: &mut HeaderMap, added: Vec<(HeaderName, String)>) {
+    for (name, value) in added {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+/// Middleware applying the version policy: sunset versions are refused,
+/// deprecated ones get their headers and `v2` errors their envelope
+pub async fn apply_version_policy(
+    State(config): State<Arc<AppConfig>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    if UNVERSIONED_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return next.run(req).await;
+    }
+    let (version, route) = version_of(&path);
+    let deprecation = config.api_versions.deprecated.get(&version);
+
+    let today = timezones::today();
+    let response = match deprecation.and_then(|d| d.sunset).filter(|sunset| *sunset <= today) {
+        Some(sunset) => {
+            let successor =
+                version.successor().map_or(String::new(), |s| format!("; use /{}", s.as_str()));
+            let message = format!("API {} was sunset on {}{}", version.as_str(), sunset, successor);
+            ApiError::Gone(message).into_response()
+        }
+        None => next.run(req).await,
+    };
+
+    let mut response = match version {
+        ApiVersion::V1 => response,
+        ApiVersion::V2 => with_error_envelope(response).await,
+    };
+    if let Some(deprecation) = deprecation {
+        insert_headers(response.headers_mut(), deprecation_headers(version, route, deprecation));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::campaign_aggregator::Platform;
+    use super::*;
+
+    #[test]
+    fn test_deprecated_versions_point_to_their_successor() {
+        assert_eq!(version_of("/campaigns"), (ApiVersion::V1, "/campaigns"));
+        assert_eq!(version_of("/v2/campaigns/summary"), (ApiVersion::V2, "/campaigns/summary"));
+        assert_eq!(version_of("/v1"), (ApiVersion::V1, "/"));
+        assert_eq!(version_of("/v1beta/x"), (ApiVersion::V1, "/v1beta/x"));
+
+        let day = |d| NaiveDate::from_ymd_opt(2026, 11, d).unwrap();
+        let deprecation = VersionDeprecation { since: day(1), sunset: Some(day(30)) };
+        let headers = deprecation_headers(ApiVersion::V1, "/campaigns", &deprecation);
+        let values: Vec<(&str, &str)> =
+            headers.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+        assert_eq!(
+            values,
+            [
+                ("deprecation", "@1793491200"),
+                ("sunset", "Mon, 30 Nov 2026 00:00:00 GMT"),
+                ("link", "</v2/campaigns>; rel=\"successor-version\""),
+            ]
+        );
+        // No successor route for the pacing report
+        assert_eq!(deprecation_headers(ApiVersion::V1, "/campaigns/pacing", &deprecation).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_v2_renders_money_and_error_envelopes() {
+        let mut campaign = Campaign::sample("g1", Platform::Google);
+        campaign.metrics.cost = 42.5;
+        let value = campaign_v2(&campaign).unwrap();
+        assert!(value.get("currency").is_none());
+        assert_eq!(value["daily_budget"], serde_json::json!({"amount": 100.0, "currency": "BRL"}));
+        assert_eq!(value["metrics"]["cost"]["amount"], 42.5);
+        assert_eq!(value["metrics"]["clicks"], 0);
+
+        let response =
+            with_error_envelope(ApiError::NotFound("campaign g9".into()).into_response()).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let envelope: ErrorEnvelope = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!((envelope.error.code.as_str(), envelope.error.status), ("not_found", 404));
+        assert_eq!(envelope.error.message, "campaign g9 not found");
+    }
+}
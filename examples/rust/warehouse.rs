@@ -0,0 +1,467 @@
+//! # Warehouse Export Example
+//!
+//! The analytics team joins ads data with CRM data in the warehouse, and
+//! should not page through the API to do it. With `warehouse.url` set
+//! (feature `warehouse`), the previous day's campaign snapshots are
+//! exported once a day after `warehouse.export_at` (UTC):
+//!
+//! - as Parquet files in the bucket, one per platform, partitioned Hive
+//!   style: `campaign_snapshots/v1/date=2026-10-14/platform=google/snapshots.parquet`;
+//! - optionally streamed to a BigQuery table (`warehouse.bigquery`) with
+//!   `tabledata.insertAll`, one JSON row per campaign and day.
+//!
+//! Rows hold the day's delivery (derived like
+//! `GET /campaigns/{platform}/{id}/history` from month-to-date totals), and
+//! the campaign's name, status, currency and daily budget that day.
+//! Parquet files leave out the partition columns `date` and `platform`,
+//! which readers take from the path.
+//!
+//! ## Schema versions
+//!
+//! The schema version is part of the path and is written into every
+//! file's key-value metadata (`ibvi.schema_version`) and every BigQuery
+//! row. A change that is not purely additive bumps `SCHEMA_VERSION`, so
+//! new files land under `v2/` and never mix with files of the old schema
+//! in one table.
+//!
+//! A day is exported once: a marker object `_exported/<date>` is written
+//! after its files, and checked before exporting again after a restart.
+//! `POST /admin/warehouse/exports` exports a given day again, e.g. to
+//! backfill; its files replace the earlier ones, but BigQuery only drops
+//! repeated rows within its short de-duplication window.
+
+use arrow::array::{ArrayRef, Float64Array, StringArray, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::backup::{BackupError, DirObjectStore, HttpObjectStore, ObjectStore};
+use super::campaign_aggregator::AppState;
+use super::config::{BigQueryConfig, WarehouseConfig};
+use super::error::ApiError;
+use super::store::MetricsStore;
+
+/// Version of the row schema, see the module docs
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Parquet key-value metadata holding `SCHEMA_VERSION`
+pub const SCHEMA_VERSION_KEY: &str = "ibvi.schema_version";
+
+/// How often the exporter checks whether a day is due
+pub const EXPORT_TICK: Duration = Duration::from_secs(10 * 60);
+
+/// Rows per `insertAll` request, as BigQuery recommends
+const BIGQUERY_BATCH_SIZE: usize = 500;
+
+const BIGQUERY_API: &str = "https://bigquery.googleapis.com/bigquery/v2";
+
+#[derive(Debug, thiserror::Error)]
+pub enum WarehouseError {
+    #[error("warehouse exports are not configured (warehouse.url)")]
+    NotConfigured,
+    #[error("parquet encoding failed: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[error("arrow batch is invalid: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+    #[error(transparent)]
+    Storage(#[from] BackupError),
+    #[error("bigquery insert failed: {0}")]
+    BigQuery(String),
+}
+
+impl From<WarehouseError> for ApiError {
+    fn from(e: WarehouseError) -> Self {
+        match e {
+            WarehouseError::NotConfigured => ApiError::NotSupported(e.to_string()),
+            e => ApiError::Internal(e.to_string()),
+        }
+    }
+}
+
+/// One campaign on one day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SnapshotRow {
+    pub schema_version: u32,
+    pub date: NaiveDate,
+    pub platform: String,
+    pub campaign_id: String,
+    pub campaign_name: Option<String>,
+    pub status: Option<String>,
+    pub currency: Option<String>,
+    pub daily_budget: Option<f64>,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub conversions: u32,
+    pub spend: f64,
+    pub conversion_value: f64,
+}
+
+impl SnapshotRow {
+    /// Same for every export of the row, so BigQuery can drop repeats
+    fn insert_id(&self) -> String {
+        format!("v{}:{}:{}:{}", self.schema_version, self.date, self.platform, self.campaign_id)
+    }
+}
+
+/// Rows of every campaign with a snapshot on `date`, by platform
+pub fn snapshot_rows(store: &MetricsStore, date: NaiveDate) -> BTreeMap<String, Vec<SnapshotRow>> {
+    let mut partitions: BTreeMap<String, Vec<SnapshotRow>> = BTreeMap::new();
+
+    for campaign in store.campaign_ids() {
+        let Some(snapshot) = store.series(&campaign).remove(&date) else {
+            continue;
+        };
+        let Some(day) = store.daily_series(&campaign).into_iter().find(|d| d.date == date) else {
+            continue;
+        };
+        let platform = campaign.platform.as_str().to_string();
+
+        partitions.entry(platform.clone()).or_default().push(SnapshotRow {
+            schema_version: SCHEMA_VERSION,
+            date,
+            platform,
+            campaign_id: campaign.id.to_string(),
+            campaign_name: snapshot.name,
+            status: snapshot.status.map(|s| s.as_str().to_string()),
+            currency: snapshot.currency.map(|c| c.to_string()),
+            daily_budget: snapshot.daily_budget,
+            impressions: day.impressions,
+            clicks: day.clicks,
+            conversions: day.conversions,
+            spend: day.spend,
+            conversion_value: day.conversion_value,
+        });
+    }
+
+    partitions
+}
+
+/// Columns of the Parquet files; `date` and `platform` are in the path
+pub fn parquet_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("campaign_id", DataType::Utf8, false),
+        Field::new("campaign_name", DataType::Utf8, true),
+        Field::new("status", DataType::Utf8, true),
+        Field::new("currency", DataType::Utf8, true),
+        Field::new("daily_budget", DataType::Float64, true),
+        Field::new("impressions", DataType::UInt64, false),
+        Field::new("clicks", DataType::UInt64, false),
+        Field::new("conversions", DataType::UInt32, false),
+        Field::new("spend", DataType::Float64, false),
+        Field::new("conversion_value", DataType::Float64, false),
+    ])
+}
+
+/// Encode `rows` as one Snappy-compressed Parquet file
+pub fn to_parquet(rows: &[SnapshotRow]) -> Result<Vec<u8>, WarehouseError> {
+    let strings = |f: fn(&SnapshotRow) -> Option<&str>| -> ArrayRef {
+        Arc::new(rows.iter().map(f).collect::<StringArray>())
+    };
+    let columns: Vec<ArrayRef> = vec![
+        strings(|r| Some(r.campaign_id.as_str())),
+        strings(|r| r.campaign_name.as_deref()),
+        strings(|r| r.status.as_deref()),
+        strings(|r| r.currency.as_deref()),
+        Arc::new(rows.iter().map(|r| r.daily_budget).collect::<Float64Array>()),
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.impressions))),
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.clicks))),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.conversions))),
+        Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.spend))),
+        Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.conversion_value))),
+    ];
+    let batch = RecordBatch::try_new(Arc::new(parquet_schema()), columns)?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            SCHEMA_VERSION_KEY.to_string(),
+            SCHEMA_VERSION.to_string(),
+        )]))
+        .build();
+    let mut bytes = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut bytes, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(bytes)
+}
+
+/// Object key of a day's file for `platform`
+pub fn partition_key(date: NaiveDate, platform: &str) -> String {
+    format!(
+        "campaign_snapshots/v{}/date={}/platform={}/snapshots.parquet",
+        SCHEMA_VERSION, date, platform
+    )
+}
+
+fn marker_key(date: NaiveDate) -> String {
+    format!("campaign_snapshots/v{}/_exported/{}", SCHEMA_VERSION, date)
+}
+
+/// Table rows are streamed to
+pub struct BigQuerySink {
+    pub http_client: reqwest::Client,
+    pub config: BigQueryConfig,
+    pub token: Option<String>,
+}
+
+impl BigQuerySink {
+    /// Stream `rows` with `tabledata.insertAll`, in batches
+    pub async fn insert(&self, rows: &[SnapshotRow]) -> Result<(), WarehouseError> {
+        let url = format!(
+            "{}/projects/{}/datasets/{}/tables/{}/insertAll",
+            BIGQUERY_API, self.config.project, self.config.dataset, self.config.table
+        );
+
+        for batch in rows.chunks(BIGQUERY_BATCH_SIZE) {
+            let rows: Vec<serde_json::Value> = batch
+                .iter()
+                .map(|row| serde_json::json!({ "insertId": row.insert_id(), "json": row }))
+                .collect();
+            let body = serde_json::json!({ "rows": rows });
+            let mut request = self.http_client.post(&url).json(&body);
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            let response: serde_json::Value = request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| WarehouseError::BigQuery(e.to_string()))?
+                .json()
+                .await
+                .map_err(|e| WarehouseError::BigQuery(e.to_string()))?;
+            // Rejected rows come back in a successful response
+            if let Some(errors) = response["insertErrors"].as_array().filter(|e| !e.is_empty()) {
+                return Err(WarehouseError::BigQuery(format!(
+                    "{} of {} rows rejected, first: {}",
+                    errors.len(),
+                    batch.len(),
+                    errors[0]
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What one day's export wrote
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ExportSummary {
+    pub date: NaiveDate,
+    pub schema_version: u32,
+    /// Object keys of the Parquet files
+    pub files: Vec<String>,
+    pub rows: usize,
+    pub streamed_to_bigquery: bool,
+}
+
+/// Bucket and table snapshots are exported to
+pub struct WarehouseExporter {
+    store: Box<dyn ObjectStore>,
+    bigquery: Option<BigQuerySink>,
+    export_at: NaiveTime,
+}
+
+impl WarehouseExporter {
+    pub fn new(
+        store: Box<dyn ObjectStore>,
+        bigquery: Option<BigQuerySink>,
+        export_at: NaiveTime,
+    ) -> Self {
+        Self {
+            store,
+            bigquery,
+            export_at,
+        }
+    }
+
+    /// Exporter configured by `warehouse`, `None` when exports are disabled
+    pub fn from_config(config: &WarehouseConfig) -> Option<Self> {
+        let url = config.url.as_ref()?;
+        let store: Box<dyn ObjectStore> = match url.strip_prefix("file://") {
+            Some(root) => Box::new(DirObjectStore {
+                root: PathBuf::from(root),
+            }),
+            None => Box::new(HttpObjectStore {
+                http_client: reqwest::Client::new(),
+                url: url.clone(),
+                token: config.token.clone(),
+            }),
+        };
+        let bigquery = config.bigquery.as_ref().map(|bigquery| BigQuerySink {
+            http_client: reqwest::Client::new(),
+            token: bigquery.token.clone().or_else(|| config.token.clone()),
+            config: bigquery.clone(),
+        });
+        Some(Self::new(store, bigquery, config.export_at))
+    }
+
+    /// Write the files of `date`, stream its rows, then mark it exported
+    pub async fn export(
+        &self,
+        metrics: &MetricsStore,
+        date: NaiveDate,
+    ) -> Result<ExportSummary, WarehouseError> {
+        let partitions = snapshot_rows(metrics, date);
+        let mut files = Vec::with_capacity(partitions.len());
+        for (platform, rows) in &partitions {
+            let key = partition_key(date, platform);
+            self.store.put(&key, to_parquet(rows)?).await?;
+            files.push(key);
+        }
+
+        let rows: Vec<SnapshotRow> = partitions.into_values().flatten().collect();
+        if let Some(bigquery) = &self.bigquery {
+            bigquery.insert(&rows).await?;
+        }
+        self.store.put(&marker_key(date), Utc::now().to_rfc3339().into_bytes()).await?;
+
+        Ok(ExportSummary {
+            date,
+            schema_version: SCHEMA_VERSION,
+            files,
+            rows: rows.len(),
+            streamed_to_bigquery: self.bigquery.is_some(),
+        })
+    }
+
+    /// Whether `date` was exported, by this or an earlier process
+    pub async fn is_exported(&self, date: NaiveDate) -> Result<bool, WarehouseError> {
+        match self.store.get(&marker_key(date)).await {
+            Ok(_) => Ok(true),
+            Err(BackupError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Day due for export at `now`: yesterday, once `export_at` passed
+    pub fn due_date(&self, now: DateTime<Utc>) -> Option<NaiveDate> {
+        (now.time() >= self.export_at).then(|| now.date_naive().pred_opt()).flatten()
+    }
+}
+
+/// Export each day once it is due, until `shutdown` is cancelled
+pub async fn run_warehouse_exporter(
+    state: Arc<AppState>,
+    exporter: WarehouseExporter,
+    shutdown: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(EXPORT_TICK);
+    let mut exported: Option<NaiveDate> = None;
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => {
+                let Some(date) = exporter.due_date(Utc::now()) else {
+                    continue;
+                };
+                if exported == Some(date) {
+                    continue;
+                }
+
+                let result = match exporter.is_exported(date).await {
+                    Ok(true) => Ok(None),
+                    Ok(false) => exporter.export(&state.metrics_store, date).await.map(Some),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(summary) => {
+                        if let Some(summary) = summary {
+                            tracing::info!(%date, rows = summary.rows, "snapshots exported");
+                        }
+                        exported = Some(date);
+                    }
+                    // Retried on the next tick
+                    Err(e) => tracing::error!(%date, error = %e, "snapshot export failed"),
+                }
+            }
+        }
+    }
+
+    tracing::info!("warehouse exporter stopped");
+}
+
+/// Request body for `POST /admin/warehouse/exports`
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    pub date: NaiveDate,
+}
+
+/// POST /admin/warehouse/exports - Export one day's snapshots now
+pub async fn export_snapshots(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<ExportRequest>,
+) -> Result<Json<ExportSummary>, ApiError> {
+    if body.date >= Utc::now().date_naive() {
+        return Err(ApiError::Validation("only past days can be exported".to_string()));
+    }
+    let exporter = WarehouseExporter::from_config(&state.config.warehouse)
+        .ok_or(WarehouseError::NotConfigured)?;
+
+    Ok(Json(exporter.export(&state.metrics_store, body.date).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::{Campaign, Platform};
+    use axum::body::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_rows_hold_daily_values_by_platform() {
+        let store = MetricsStore::default();
+        let mut google = Campaign::sample("g1", Platform::Google);
+        let meta = Campaign::sample("m1", Platform::Meta);
+
+        google.metrics.cost = 100.0;
+        store.record(day(13), &[google.clone(), meta.clone()]);
+        google.metrics.cost = 250.0;
+        store.record(day(14), &[google]);
+
+        let partitions = snapshot_rows(&store, day(14));
+
+        assert_eq!(partitions.keys().collect::<Vec<_>>(), ["google"]);
+        let row = &partitions["google"][0];
+        assert_eq!(row.spend, 150.0);
+        assert_eq!(row.insert_id(), "v1:2026-10-14:google:g1");
+        assert_eq!(
+            partition_key(day(14), "google"),
+            "campaign_snapshots/v1/date=2026-10-14/platform=google/snapshots.parquet"
+        );
+    }
+
+    #[test]
+    fn test_parquet_files_carry_the_schema_version() {
+        let store = MetricsStore::default();
+        store.record(day(14), &[Campaign::sample("g1", Platform::Google)]);
+        let rows = &snapshot_rows(&store, day(14))["google"];
+
+        let bytes = Bytes::from(to_parquet(rows).unwrap());
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes).unwrap();
+
+        let metadata = reader.metadata().file_metadata().key_value_metadata().unwrap();
+        let version = metadata.iter().find(|kv| kv.key == SCHEMA_VERSION_KEY).unwrap();
+        assert_eq!(version.value.as_deref(), Some("1"));
+        let batches: Vec<RecordBatch> = reader.build().unwrap().map(Result::unwrap).collect();
+        assert_eq!(batches[0].num_rows(), 1);
+        assert_eq!(batches[0].schema().fields().len(), parquet_schema().fields().len());
+    }
+}
@@ -0,0 +1,316 @@
+//! # Startup Warm-up Example
+//!
+//! Right after a deploy every request pays for cold connection pools and
+//! cold caches along the request path (gateway, platform APIs), which
+//! shows up as a latency spike. With `warmup.enabled`, a new instance
+//! warms up before `GET /readyz` reports it ready:
+//!
+//! 1. opens `connections_per_platform` pooled connections to every
+//!    platform gateway;
+//! 2. replays, in process, the most requested `GET` paths of the busiest
+//!    tenants, with their `X-Tenant-Id`.
+//!
+//! Tenants come from `warmup.tenants`, then from recorded usage: every
+//! `GET` carrying `X-Tenant-Id` is counted per tenant and path, and the
+//! counts are kept across restarts in `warmup.usage_path`. Tenants
+//! without recorded usage get `DEFAULT_PATHS`.
+//!
+//! Warm-up is bounded by `warmup.timeout_secs`; when it runs out the
+//! instance becomes ready anyway rather than never joining the pool.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+
+use super::campaign_aggregator::AppState;
+use super::config::WarmupConfig;
+use super::health;
+
+/// Header marking replayed requests, which are not counted as usage
+pub const WARMUP_HEADER: &str = "x-warmup";
+
+/// Paths primed for tenants without recorded usage
+pub const DEFAULT_PATHS: &[&str] = &["/campaigns", "/campaigns/summary"];
+
+/// Paths never recorded nor replayed: probes, admin tools and streams
+const SKIPPED_PREFIXES: &[&str] = &["/healthz", "/readyz", "/admin/", "/campaigns/stream"];
+
+/// Requests per tenant and path (with query string)
+#[derive(Default)]
+pub struct UsageStats {
+    counts: RwLock<HashMap<String, HashMap<String, u64>>>,
+}
+
+impl UsageStats {
+    pub fn record(&self, tenant: &str, path: &str) {
+        *self
+            .counts
+            .write()
+            .unwrap()
+            .entry(tenant.to_string())
+            .or_default()
+            .entry(path.to_string())
+            .or_default() += 1;
+    }
+
+    /// Tenants with the most requests, busiest first
+    pub fn top_tenants(&self, n: usize) -> Vec<String> {
+        let counts = self.counts.read().unwrap();
+        let mut tenants: Vec<(&String, u64)> = counts
+            .iter()
+            .map(|(tenant, paths)| (tenant, paths.values().sum()))
+            .collect();
+        tenants.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        tenants.into_iter().take(n).map(|(t, _)| t.clone()).collect()
+    }
+
+    /// Most requested paths of `tenant`, most requested first
+    pub fn top_paths(&self, tenant: &str, n: usize) -> Vec<String> {
+        let counts = self.counts.read().unwrap();
+        let Some(paths) = counts.get(tenant) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<(&String, &u64)> = paths.iter().collect();
+        paths.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        paths.into_iter().take(n).map(|(p, _)| p.clone()).collect()
+    }
+
+    /// Write the counts to `path` atomically (write to temp file, then rename)
+    pub fn persist(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec(&*self.counts.read().unwrap())?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Add counts previously written by `persist`
+    pub fn load(&self, path: &Path) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let loaded: HashMap<String, HashMap<String, u64>> = serde_json::from_slice(&bytes)?;
+
+        let mut counts = self.counts.write().unwrap();
+        for (tenant, paths) in loaded {
+            let entry = counts.entry(tenant).or_default();
+            for (path, count) in paths {
+                *entry.entry(path).or_default() += count;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn skipped(path: &str) -> bool {
+    SKIPPED_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// Middleware counting `GET` requests per tenant
+pub async fn record_usage(
+    State(usage): State<Arc<UsageStats>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let tenant = req
+        .headers()
+        .get("x-tenant-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+
+    if req.method() == Method::GET && !req.headers().contains_key(WARMUP_HEADER) {
+        if let (Some(tenant), Some(path)) = (tenant, req.uri().path_and_query()) {
+            if !skipped(path.path()) {
+                usage.record(tenant, path.as_str());
+            }
+        }
+    }
+
+    next.run(req).await
+}
+
+/// Whether warm-up has finished
+#[derive(Default)]
+pub struct WarmupStatus {
+    done: AtomicBool,
+}
+
+impl WarmupStatus {
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+
+    pub fn mark_done(&self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Requests replayed for one tenant
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TenantPlan {
+    pub tenant: String,
+    pub paths: Vec<String>,
+}
+
+/// Configured tenants first, then the busiest recorded ones, up to
+/// `learned_tenants` more
+pub fn plan(config: &WarmupConfig, usage: &UsageStats) -> Vec<TenantPlan> {
+    let mut tenants = config.tenants.clone();
+    let learned = usage
+        .top_tenants(config.learned_tenants + tenants.len())
+        .into_iter()
+        .filter(|t| !config.tenants.contains(t))
+        .take(config.learned_tenants);
+    tenants.extend(learned);
+
+    tenants
+        .into_iter()
+        .map(|tenant| {
+            let mut paths = usage.top_paths(&tenant, config.paths_per_tenant);
+            paths.retain(|p| !skipped(p));
+            if paths.is_empty() {
+                paths = DEFAULT_PATHS.iter().map(|p| p.to_string()).collect();
+            }
+            TenantPlan { tenant, paths }
+        })
+        .collect()
+}
+
+/// Outcome of a warm-up, logged once it ends
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct WarmupReport {
+    pub connections_opened: usize,
+    pub tenants: usize,
+    pub requests: usize,
+    pub failed_requests: usize,
+    pub timed_out: bool,
+}
+
+/// Open `per_platform` pooled connections to every platform gateway
+///
+/// Concurrent probes each need their own connection, which the shared
+/// client keeps alive in its pool afterwards.
+pub async fn prime_connection_pools(state: &AppState, per_platform: usize) -> usize {
+    let probes = state
+        .connectors
+        .all()
+        .iter()
+        .flat_map(|connector| (0..per_platform).map(move |_| health::probe(connector.as_ref())));
+
+    futures::future::join_all(probes)
+        .await
+        .into_iter()
+        .filter(|h| *h != health::PlatformHealth::Down)
+        .count()
+}
+
+/// Replay the planned requests through `router`, one tenant at a time
+async fn replay(router: &Router, plans: &[TenantPlan], report: &mut WarmupReport) {
+    for plan in plans {
+        let requests = plan.paths.iter().map(|path| {
+            let request = Request::get(path.as_str())
+                .header("x-tenant-id", plan.tenant.as_str())
+                .header(WARMUP_HEADER, "1")
+                .body(Body::empty());
+            async move {
+                match request {
+                    Ok(request) => router.clone().oneshot(request).await.map(|r| r.status()).ok(),
+                    Err(_) => None,
+                }
+            }
+        });
+
+        for status in futures::future::join_all(requests).await {
+            report.requests += 1;
+            if !status.is_some_and(|s: StatusCode| s.is_success()) {
+                report.failed_requests += 1;
+            }
+        }
+        report.tenants += 1;
+    }
+}
+
+/// Warm connection pools and caches, then mark the instance ready
+///
+/// Marks it ready straight away when warm-up is disabled.
+pub async fn run_warmup(state: Arc<AppState>, router: Router) -> WarmupReport {
+    let config = state.config.warmup.clone();
+    let mut report = WarmupReport::default();
+    if !config.enabled {
+        state.warmup.mark_done();
+        return report;
+    }
+
+    let started = Instant::now();
+    let plans = plan(&config, &state.usage);
+    let warm = async {
+        report.connections_opened =
+            prime_connection_pools(&state, config.connections_per_platform).await;
+        replay(&router, &plans, &mut report).await;
+    };
+    let timed_out = tokio::time::timeout(Duration::from_secs(config.timeout_secs), warm)
+        .await
+        .is_err();
+    report.timed_out = timed_out;
+
+    state.warmup.mark_done();
+    if report.timed_out {
+        tracing::warn!(?report, "warm-up timed out, marking instance ready");
+    } else {
+        tracing::info!(?report, elapsed_ms = started.elapsed().as_millis() as u64, "warm-up done");
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage() -> UsageStats {
+        let usage = UsageStats::default();
+        for _ in 0..3 {
+            usage.record("acme", "/campaigns?sort=roas");
+        }
+        usage.record("acme", "/campaigns/summary");
+        usage.record("globex", "/campaigns");
+        usage
+    }
+
+    #[test]
+    fn test_busiest_tenants_and_paths_first() {
+        let usage = usage();
+
+        assert_eq!(usage.top_tenants(5), vec!["acme", "globex"]);
+        assert_eq!(usage.top_paths("acme", 1), vec!["/campaigns?sort=roas"]);
+        assert!(usage.top_paths("initech", 5).is_empty());
+    }
+
+    #[test]
+    fn test_plan_puts_configured_tenants_first() {
+        let config = WarmupConfig {
+            enabled: true,
+            tenants: vec!["initech".to_string(), "globex".to_string()],
+            learned_tenants: 1,
+            ..Default::default()
+        };
+
+        let plans = plan(&config, &usage());
+
+        let tenants: Vec<&str> = plans.iter().map(|p| p.tenant.as_str()).collect();
+        assert_eq!(tenants, vec!["initech", "globex", "acme"]);
+        // No recorded usage: default paths
+        assert_eq!(plans[0].paths, vec!["/campaigns", "/campaigns/summary"]);
+        assert_eq!(plans[1].paths, vec!["/campaigns"]);
+    }
+}
@@ -0,0 +1,333 @@
+//! # WebSocket Subscriptions Example
+//!
+//! `GET /ws` upgrades to a WebSocket carrying JSON text messages. A
+//! dashboard opens one connection and multiplexes its widgets over it,
+//! each widget subscribing under an ID of its choosing:
+//!
+//! ```json
+//! {"type": "subscribe", "id": "w1", "topic": "campaigns",
+//!  "filter": {"platform": "meta", "labels": ["black friday"]}}
+//! {"type": "subscribe", "id": "w2", "topic": "summary"}
+//! {"type": "subscribe", "id": "w3", "topic": "alerts", "severity": "critical"}
+//! {"type": "unsubscribe", "id": "w1"}
+//! {"type": "ping"}
+//! ```
+//!
+//! The server answers `subscribed`, `unsubscribed`, `pong` or `error`, and
+//! sends `{"type": "event", "subscription": "w2", "topic": "summary",
+//! "data": {...}}` for every update matching a subscription:
+//!
+//! - `campaigns`: each `CampaignUpdate` published after a sync, filtered by
+//!   platform, status, labels (any of, as in `?label=`) and campaign IDs;
+//! - `summary`: the totals of every campaign after each sync;
+//! - `alerts`: each alert recorded by the sync worker, optionally of one
+//!   severity or of some campaigns.
+//!
+//! Updates come from the broadcast channels the sync worker publishes to;
+//! there is no initial state, dashboards load it over the REST routes. A
+//! connection too slow for the channels gets `lagged` with the number of
+//! updates it missed. Needs axum's `ws` feature.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::alerts::{AlertEvent, AlertSeverity};
+use super::campaign_aggregator::{AppState, CampaignStatus, CampaignSummary, Platform};
+use super::ids::CampaignId;
+use super::labels;
+use super::updates::{CampaignUpdate, DashboardEvent};
+
+/// Subscriptions one connection may hold
+pub const MAX_SUBSCRIPTIONS: usize = 32;
+
+/// Campaigns a `campaigns` subscription receives; empty fields match all
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct CampaignFilter {
+    pub platform: Option<Platform>,
+    pub status: Option<CampaignStatus>,
+    /// Any of these labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub campaign_ids: Vec<CampaignId>,
+}
+
+impl CampaignFilter {
+    pub fn matches(&self, update: &CampaignUpdate) -> bool {
+        let campaign = &update.campaign;
+        let label_keys: Vec<String> = self.labels.iter().map(|l| labels::label_key(l)).collect();
+
+        self.platform.iter().all(|p| *p == campaign.platform)
+            && self.status.iter().all(|s| *s == campaign.status)
+            && (label_keys.is_empty() || labels::carries_label(campaign, &label_keys))
+            && (self.campaign_ids.is_empty() || self.campaign_ids.contains(&campaign.id))
+    }
+}
+
+/// What a subscription receives
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "topic", rename_all = "snake_case")]
+pub enum Topic {
+    Campaigns {
+        #[serde(default)]
+        filter: CampaignFilter,
+    },
+    Summary,
+    Alerts {
+        severity: Option<AlertSeverity>,
+        #[serde(default)]
+        campaign_ids: Vec<CampaignId>,
+    },
+}
+
+/// Message sent by the client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        id: String,
+        #[serde(flatten)]
+        topic: Topic,
+    },
+    Unsubscribe {
+        id: String,
+    },
+    Ping,
+}
+
+/// Update delivered to a subscription
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "topic", content = "data", rename_all = "snake_case")]
+pub enum Payload {
+    Campaigns(Box<CampaignUpdate>),
+    Summary(Box<CampaignSummary>),
+    Alerts(Box<AlertEvent>),
+}
+
+/// Message sent by the server
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed {
+        id: String,
+    },
+    Unsubscribed {
+        id: String,
+    },
+    Event {
+        subscription: String,
+        #[serde(flatten)]
+        payload: Payload,
+    },
+    /// Updates dropped because the connection fell behind
+    Lagged {
+        skipped: u64,
+    },
+    Error {
+        /// Subscription the error is about, if any
+        id: Option<String>,
+        message: String,
+    },
+    Pong,
+}
+
+/// Subscriptions of one connection, in subscription order
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    topics: Vec<(String, Topic)>,
+}
+
+impl Subscriptions {
+    /// Apply a client message, returning the reply
+    pub fn handle(&mut self, text: &str) -> ServerMessage {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                return ServerMessage::Error {
+                    id: None,
+                    message: format!("invalid message: {}", e),
+                }
+            }
+        };
+        let error = |id: String, message: &str| ServerMessage::Error {
+            id: Some(id),
+            message: message.to_string(),
+        };
+
+        match message {
+            ClientMessage::Subscribe { id, topic } => {
+                if self.topics.iter().any(|(existing, _)| *existing == id) {
+                    return error(id, "subscription ID already in use");
+                }
+                if self.topics.len() >= MAX_SUBSCRIPTIONS {
+                    return error(id, "too many subscriptions on this connection");
+                }
+                self.topics.push((id.clone(), topic));
+                ServerMessage::Subscribed { id }
+            }
+            ClientMessage::Unsubscribe { id } => {
+                let before = self.topics.len();
+                self.topics.retain(|(existing, _)| *existing != id);
+                if self.topics.len() == before {
+                    return error(id, "no such subscription");
+                }
+                ServerMessage::Unsubscribed { id }
+            }
+            ClientMessage::Ping => ServerMessage::Pong,
+        }
+    }
+
+    /// Events for the subscriptions matching a campaign update
+    pub fn campaign_events(&self, update: &CampaignUpdate) -> Vec<ServerMessage> {
+        self.topics
+            .iter()
+            .filter(|(_, topic)| {
+                matches!(topic, Topic::Campaigns { filter } if filter.matches(update))
+            })
+            .map(|(id, _)| ServerMessage::Event {
+                subscription: id.clone(),
+                payload: Payload::Campaigns(Box::new(update.clone())),
+            })
+            .collect()
+    }
+
+    /// Events for the subscriptions matching a summary or alert
+    pub fn dashboard_events(&self, event: &DashboardEvent) -> Vec<ServerMessage> {
+        self.topics
+            .iter()
+            .filter_map(|(id, topic)| {
+                let payload = match (topic, event) {
+                    (Topic::Summary, DashboardEvent::Summary(summary)) => {
+                        Payload::Summary(summary.clone())
+                    }
+                    (Topic::Alerts { severity, campaign_ids }, DashboardEvent::Alert(alert))
+                        if severity.iter().all(|s| *s == alert.severity)
+                            && (campaign_ids.is_empty()
+                                || campaign_ids.iter().any(|c| *c == alert.campaign_id)) =>
+                    {
+                        Payload::Alerts(alert.clone())
+                    }
+                    _ => return None,
+                };
+                Some(ServerMessage::Event {
+                    subscription: id.clone(),
+                    payload,
+                })
+            })
+            .collect()
+    }
+}
+
+/// GET /ws - Multiplexed dashboard subscriptions over a WebSocket
+pub async fn ws_handler(State(state): State<Arc<AppState>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| serve_connection(socket, state))
+}
+
+/// Relay client messages and matching updates until either side closes
+async fn serve_connection(socket: WebSocket, state: Arc<AppState>) {
+    // Subscribed before the first message, so no update slips between
+    let mut updates = state.updates.subscribe();
+    let mut events = state.dashboard_events.subscribe();
+    let (mut sink, mut stream) = socket.split();
+    let mut subscriptions = Subscriptions::default();
+
+    loop {
+        let outgoing = tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => vec![subscriptions.handle(text.as_str())],
+                // Pings are answered by axum; binary frames are not part of the protocol
+                Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Binary(_))) => continue,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+            },
+            update = updates.recv() => match update {
+                Ok(update) => subscriptions.campaign_events(&update),
+                Err(RecvError::Lagged(skipped)) => vec![ServerMessage::Lagged { skipped }],
+                Err(RecvError::Closed) => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) => subscriptions.dashboard_events(&event),
+                Err(RecvError::Lagged(skipped)) => vec![ServerMessage::Lagged { skipped }],
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        for message in outgoing {
+            let Ok(json) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if sink.send(Message::Text(json.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::campaign_aggregator::Campaign;
+
+    fn update(id: &str, platform: Platform, labels: &[&str]) -> CampaignUpdate {
+        let mut campaign = Campaign::sample(id, platform);
+        campaign.labels = labels.iter().map(|l| l.to_string()).collect();
+        CampaignUpdate {
+            campaign,
+            anomalies: Vec::new(),
+        }
+    }
+
+    fn subscriptions(events: &[ServerMessage]) -> Vec<String> {
+        events
+            .iter()
+            .map(|m| match m {
+                ServerMessage::Event { subscription, .. } => subscription.clone(),
+                other => panic!("not an event: {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_subscriptions_are_multiplexed_by_filter() {
+        let mut subs = Subscriptions::default();
+        let replies = [
+            r#"{"type":"subscribe","id":"meta","topic":"campaigns","filter":{"platform":"meta"}}"#,
+            r#"{"type":"subscribe","id":"bf","topic":"campaigns","filter":{"labels":["BF"]}}"#,
+            r#"{"type":"subscribe","id":"totals","topic":"summary"}"#,
+            r#"{"type":"subscribe","id":"totals","topic":"summary"}"#,
+            r#"{"type":"ping"}"#,
+        ]
+        .map(|text| serde_json::to_value(subs.handle(text)).unwrap()["type"].clone());
+        assert_eq!(replies, ["subscribed", "subscribed", "subscribed", "error", "pong"]);
+
+        let events = subs.campaign_events(&update("m1", Platform::Meta, &["bf"]));
+        assert_eq!(subscriptions(&events), ["meta", "bf"]);
+        assert!(subs.campaign_events(&update("g1", Platform::Google, &[])).is_empty());
+
+        subs.handle(r#"{"type":"unsubscribe","id":"meta"}"#);
+        let events = subs.campaign_events(&update("m1", Platform::Meta, &["bf"]));
+        assert_eq!(subscriptions(&events), ["bf"]);
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap()["topic"],
+            serde_json::json!("campaigns")
+        );
+    }
+
+    #[test]
+    fn test_invalid_messages_get_an_error_reply() {
+        let mut subs = Subscriptions::default();
+
+        let reply = serde_json::to_value(subs.handle(r#"{"type":"subscribe","id":"x"}"#)).unwrap();
+
+        assert_eq!(reply["type"], "error");
+        assert!(reply["id"].is_null());
+    }
+}